@@ -0,0 +1,100 @@
+#!/usr/bin/env python3
+"""
+Reassembly of configurations sent by the runner as content instead of a path.
+
+The runner may send the validated configuration inline in the ``load`` command,
+optionally gzip-compressed, or split into ``load_chunk`` commands followed by a
+``load`` command that references the transfer id.
+"""
+
+import base64
+import gzip
+import hashlib
+from typing import Any
+
+
+class ConfigTransferError(Exception):
+    """Raised when a transferred configuration cannot be reassembled."""
+
+
+class ConfigAssembler:
+    """Collects configuration chunks and decodes the final payload."""
+
+    def __init__(self):
+        self._transfers: dict[str, list[str | None]] = {}
+
+    def add_chunk(self, params: dict[str, Any]) -> dict[str, Any]:
+        """Store one chunk of a transfer."""
+        transfer_id = params.get("transfer_id")
+        index = params.get("index")
+        total = params.get("total")
+        data = params.get("data")
+
+        if not transfer_id or index is None or not total or data is None:
+            return {"success": False, "error": "Invalid load_chunk parameters"}
+
+        chunks = self._transfers.setdefault(transfer_id, [None] * total)
+        if len(chunks) != total or not 0 <= index < total:
+            return {"success": False, "error": f"Chunk {index} out of range for {transfer_id}"}
+
+        chunks[index] = data
+        return {"success": True, "received": sum(c is not None for c in chunks)}
+
+    def resolve(self, params: dict[str, Any]) -> str | None:
+        """Return the configuration JSON text described by a ``load`` command.
+
+        Returns None when the command carries neither content nor a transfer id,
+        so callers can fall back to ``config_path``.
+        """
+        transfer_id = params.get("transfer_id")
+        if transfer_id:
+            chunks = self._transfers.pop(transfer_id, None)
+            if chunks is None:
+                raise ConfigTransferError(f"Unknown transfer: {transfer_id}")
+            missing = [i for i, c in enumerate(chunks) if c is None]
+            if missing:
+                raise ConfigTransferError(f"Transfer {transfer_id} is missing chunks {missing}")
+            payload = "".join(chunks)
+        else:
+            payload = params.get("config_data")
+            if payload is None:
+                return None
+
+        encoding = params.get("encoding", "json")
+        if encoding == "gzip+base64":
+            config_data = gzip.decompress(base64.b64decode(payload)).decode("utf-8")
+        elif encoding == "json":
+            config_data = payload
+        else:
+            raise ConfigTransferError(f"Unsupported config encoding: {encoding}")
+
+        expected = params.get("sha256")
+        if expected:
+            actual = hashlib.sha256(config_data.encode("utf-8")).hexdigest()
+            if actual != expected:
+                raise ConfigTransferError("Configuration checksum mismatch")
+
+        return config_data
+
+
+def apply_delta(config: dict[str, Any], delta: dict[str, Any]) -> dict[str, Any]:
+    """Apply a ``load_delta`` change set to a previously loaded configuration.
+
+    Each section in the delta lists ``added`` and ``modified`` entries (matched
+    by ``id``) and the ids of ``removed`` entries.
+    """
+    updated = dict(config)
+    for section, changes in delta.items():
+        entries = list(updated.get(section, []))
+        removed = set(changes.get("removed", []))
+        replacements = {e.get("id"): e for e in changes.get("modified", [])}
+
+        entries = [
+            replacements.get(entry.get("id"), entry)
+            for entry in entries
+            if entry.get("id") not in removed
+        ]
+        entries.extend(changes.get("added", []))
+        updated[section] = entries
+
+    return updated
@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+"""
+Sidecar channel for image data.
+
+Instead of base64 screenshots inside JSON events, the bridge writes raw image
+frames to a socket of their own, offered by the runner in
+``QONTINUI_FRAMES_ADDRESS`` and ``QONTINUI_FRAMES_TOKEN``, and events refer to
+them by ``frame_id``. Each frame is a binary transport frame whose message is
+``{"id", "format", "width", "height"}``, with ``format`` one of ``png``,
+``jpeg`` or ``rgb`` (raw pixels).
+"""
+
+import json
+import os
+import socket
+import struct
+import sys
+import threading
+import uuid
+
+from transport import FRAME_BINARY, FRAME_JSON, connect_socket
+
+ADDRESS_ENV = "QONTINUI_FRAMES_ADDRESS"
+TOKEN_ENV = "QONTINUI_FRAMES_TOKEN"
+
+_HEADER = struct.Struct(">IB")
+_LENGTH = struct.Struct(">I")
+
+
+class FrameChannel:
+    """Sends image frames to the runner, when it offered a frame channel."""
+
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._socket: socket.socket | None = None
+        address = os.environ.get(ADDRESS_ENV)
+        if address:
+            try:
+                self._socket = connect_socket(address)
+                token = json.dumps({"type": "connect", "token": os.environ.get(TOKEN_ENV, "")})
+                payload = token.encode()
+                self._socket.sendall(_HEADER.pack(len(payload), FRAME_JSON) + payload)
+            except (OSError, ValueError) as e:
+                self._socket = None
+                sys.stderr.write(f"No frame channel, can't connect to {address}: {e}\n")
+
+    @property
+    def available(self) -> bool:
+        return self._socket is not None
+
+    def send(self, data: bytes, format: str, width: int = 0, height: int = 0) -> str | None:
+        """Send one frame and return the id events refer to it by, or None
+        when there's no channel."""
+        if self._socket is None:
+            return None
+        frame_id = str(uuid.uuid4())
+        header = json.dumps(
+            {"id": frame_id, "format": format, "width": width, "height": height}
+        ).encode()
+        body = _LENGTH.pack(len(header)) + header + data
+        try:
+            with self._lock:
+                self._socket.sendall(_HEADER.pack(len(body), FRAME_BINARY) + body)
+        except OSError as e:
+            sys.stderr.write(f"Frame channel closed: {e}\n")
+            self._socket = None
+            return None
+        return frame_id
+
+    def send_image(self, image) -> str | None:
+        """Send a PIL image as raw RGB pixels."""
+        rgb = image.convert("RGB")
+        return self.send(rgb.tobytes(), "rgb", rgb.width, rgb.height)
+
+
+_channel: FrameChannel | None = None
+
+
+def get_frame_channel() -> FrameChannel:
+    """The bridge's frame channel, connected on first use."""
+    global _channel
+    if _channel is None:
+        _channel = FrameChannel()
+    return _channel
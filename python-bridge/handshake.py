@@ -0,0 +1,38 @@
+#!/usr/bin/env python3
+"""
+Protocol version handshake with the runner.
+
+Every bridge sends a ``hello`` event before ``ready``, telling the runner which
+protocol version it speaks, which qontinui library it loaded and which commands
+it handles; the runner doesn't send the others. Bump ``PROTOCOL_VERSION``
+together with the runner's whenever messages change shape.
+"""
+
+import platform
+from importlib import metadata
+from typing import Any
+
+PROTOCOL_VERSION = 1
+
+
+def qontinui_version() -> str | None:
+    """Installed qontinui library version, or None when it isn't installed."""
+    try:
+        return metadata.version("qontinui")
+    except metadata.PackageNotFoundError:
+        return None
+
+
+def hello(bridge: str, commands: list[str], features: tuple[str, ...] = ()) -> dict[str, Any]:
+    """Data of the ``hello`` event sent by ``bridge``, which handles ``commands``.
+
+    ``features`` lists what the bridge enforces beyond its commands, e.g. ``safe_mode``.
+    """
+    return {
+        "protocol_version": PROTOCOL_VERSION,
+        "qontinui_version": qontinui_version(),
+        "bridge": bridge,
+        "python_version": platform.python_version(),
+        "commands": commands,
+        "features": list(features),
+    }
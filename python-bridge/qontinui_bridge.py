@@ -15,14 +15,36 @@ from enum import Enum
 from typing import Any
 
 # Import Qontinui library - REQUIRED (no fallback)
+from config_transfer import ConfigAssembler, ConfigTransferError, apply_delta
+from handshake import hello
+from transport import get_transport
 from qontinui.json_executor.json_runner import JSONRunner
 from qontinui.mock import MockModeManager
 from qontinui.runner import DSLParser, ExecutionError, StatementExecutor
 
 
+# Commands handle_command understands, announced in the hello event
+COMMANDS = [
+    "load",
+    "load_chunk",
+    "load_delta",
+    "start",
+    "stop",
+    "ping",
+    "status",
+    "get_monitors",
+    "scheduler_start",
+    "scheduler_stop",
+    "scheduler_status",
+    "scheduler_get_statistics",
+    "execute_dsl",
+]
+
+
 class EventType(Enum):
     """Event types for Tauri communication."""
 
+    HELLO = "hello"
     READY = "ready"
     CONFIG_LOADED = "config_loaded"
     EXECUTION_STARTED = "execution_started"
@@ -61,12 +83,15 @@ class QontinuiBridge:
         self._execution_thread = None
         self._is_running = False
         self._temp_config_file = None
+        self._config_assembler = ConfigAssembler()
+        self._loaded_config: dict[str, Any] | None = None
         self._scheduler_running = False
         self._dsl_parser = DSLParser()
         self._dsl_executor = None
         self._setup_callbacks()
 
         mode_str = "mock/simulation" if mock_mode else "real"
+        self._emit_event(EventType.HELLO, hello("qontinui_bridge", COMMANDS))
         self._emit_event(
             EventType.READY, {"message": f"Qontinui bridge initialized in {mode_str} mode"}
         )
@@ -96,7 +121,7 @@ class QontinuiBridge:
             )
 
     def _emit_event(self, event_type: EventType, data: dict[str, Any]):
-        """Emit event to Tauri through stdout."""
+        """Emit event to Tauri through the transport."""
         event = {
             "type": "event",
             "event": event_type.value,
@@ -105,8 +130,7 @@ class QontinuiBridge:
             "data": data,
         }
         self._sequence += 1
-        sys.stdout.write(json.dumps(event) + "\n")
-        sys.stdout.flush()
+        get_transport().send(event)
 
     def _emit_log(self, level: str, message: str):
         """Emit log message."""
@@ -120,10 +144,17 @@ class QontinuiBridge:
         try:
             if cmd_type == "load":
                 return self._handle_load(params)
+            elif cmd_type == "load_chunk":
+                return self._config_assembler.add_chunk(params)
+            elif cmd_type == "load_delta":
+                return self._handle_load_delta(params)
             elif cmd_type == "start":
                 return self._handle_start(params)
             elif cmd_type == "stop":
                 return self._handle_stop()
+            elif cmd_type == "ping":
+                # Heartbeat from the runner's watchdog
+                return {"success": True, "time": time.time()}
             elif cmd_type == "status":
                 return self._handle_status()
             elif cmd_type == "get_monitors":
@@ -155,8 +186,11 @@ class QontinuiBridge:
     def _handle_load(self, params: dict[str, Any]) -> dict[str, Any]:
         """Handle configuration loading."""
         try:
-            # Get configuration data
-            config_data = params.get("config_data")
+            # Get configuration data (inline, compressed or chunked)
+            try:
+                config_data = self._config_assembler.resolve(params)
+            except ConfigTransferError as e:
+                return {"success": False, "error": str(e)}
             if not config_data:
                 # Try loading from file path (backward compatibility)
                 config_path = params.get("config_path")
@@ -189,6 +223,7 @@ class QontinuiBridge:
                 # Parse config to get metadata for event
                 try:
                     config = json.loads(config_data)
+                    self._loaded_config = config
                     config_info = {
                         "version": config.get("version", "unknown"),
                         "name": config.get("metadata", {}).get("name", "Unnamed"),
@@ -205,6 +240,14 @@ class QontinuiBridge:
         except Exception:
             raise
 
+    def _handle_load_delta(self, params: dict[str, Any]) -> dict[str, Any]:
+        """Apply changed entries to the loaded configuration and reload it."""
+        if self._loaded_config is None:
+            return {"success": False, "error": "No configuration loaded to apply changes to"}
+
+        merged = apply_delta(self._loaded_config, params)
+        return self._handle_load({"config_data": json.dumps(merged)})
+
     def _handle_get_monitors(self) -> dict[str, Any]:
         """Handle monitor detection request."""
         try:
@@ -531,7 +574,7 @@ def main():
     bridge = QontinuiBridge(mock_mode=mock_mode)
 
     # Simple stdin reader - all logic in Qontinui
-    for line in sys.stdin:
+    for line in get_transport().lines():
         try:
             command = json.loads(line.strip())
 
@@ -539,8 +582,7 @@ def main():
                 response = bridge.handle_command(command)
                 response["id"] = command.get("id")
                 response["type"] = "response"
-                sys.stdout.write(json.dumps(response) + "\n")
-                sys.stdout.flush()
+                get_transport().send(response)
 
         except json.JSONDecodeError as e:
             bridge._emit_event(
@@ -6,15 +6,22 @@ Qontinui executor that integrates with the actual Qontinui library.
 import base64
 import json
 import os
+import re
 import sys
 import tempfile
 import threading
 import time
 import traceback
+from collections import deque
 from enum import Enum
 from pathlib import Path
 from typing import Any
 
+from config_transfer import ConfigAssembler, ConfigTransferError, apply_delta
+from frames import get_frame_channel
+from handshake import hello
+from transport import get_transport
+
 # Add qontinui library src directory to path
 # This file is in: qontinui_parent/qontinui-runner/python-bridge/qontinui_executor.py
 # We need to add: qontinui_parent/qontinui/src
@@ -71,9 +78,40 @@ except ImportError as e:
     )
 
 
+# ${secret:NAME} in action settings, or $${secret:NAME} for the literal text
+SECRET_PLACEHOLDER = re.compile(r"(\$?)\$\{secret:([A-Za-z0-9_.-]+)\}")
+
+# Commands handle_command understands, announced in the hello event
+COMMANDS = [
+    "load",
+    "load_chunk",
+    "load_delta",
+    "start",
+    "secret_value",
+    "pause",
+    "resume",
+    "stop",
+    "ping",
+    "status",
+    "set_variables",
+    "get_variables",
+    "active_states",
+    "state_graph",
+    "transition_history",
+    "activate_state",
+    "deactivate_state",
+    "rebuild_states",
+    "match_image",
+    "start_recording",
+    "stop_recording",
+    "recording_status",
+]
+
+
 class EventType(Enum):
     """Event types for communication with Tauri."""
 
+    HELLO = "hello"
     READY = "ready"
     CONFIG_LOADED = "config_loaded"
     EXECUTION_STARTED = "execution_started"
@@ -83,6 +121,8 @@ class EventType(Enum):
     WORKFLOW_STARTED = "workflow_started"
     WORKFLOW_COMPLETED = "workflow_completed"
     EXECUTION_COMPLETED = "execution_completed"
+    EXECUTION_PAUSED = "execution_paused"
+    EXECUTION_RESUMED = "execution_resumed"
     ERROR = "error"
     LOG = "log"
     MATCH_FOUND = "match_found"
@@ -91,6 +131,17 @@ class EventType(Enum):
     ACTION_EXECUTION = "action_execution"
     RECORDING_STARTED = "recording_started"
     RECORDING_STOPPED = "recording_stopped"
+    VARIABLE_SET = "variable_set"
+    SECRET_REQUEST = "secret_request"
+    INPUT_TARGET = "input_target"
+
+
+# Transitions kept for get_transition_history, newest last
+TRANSITION_HISTORY_SIZE = 200
+
+# Action types counted against the runner's input limits
+INPUT_KINDS = {"CLICK": "click", "DRAG": "click", "TYPE": "keystroke", "KEY": "keystroke"}
+INPUT_LIMIT_KEYS = {"click": "max_clicks_per_second", "keystroke": "max_keystrokes_per_second"}
 
 
 class QontinuiExecutor:
@@ -103,24 +154,44 @@ class QontinuiExecutor:
         self.is_running = False
         self._sequence = 0
         self.temp_dir = None
+        self._config_assembler = ConfigAssembler()
         self.use_graph_execution = False
         self.qontinui_config = None
         self.mock_mode = "real"  # Track mock mode: "real", "mock", "screenshot"
         self.screenshot_dir = None  # Screenshot directory for screenshot mode
         self.settings = None  # FrameworkSettings instance
         self._last_find_location = None  # Store location of most recent FIND result for "Last Find Result" clicks
+        self.variables = {"global": {}, "config": {}}  # Persistent variables sent by the runner on start
+        self._resume = threading.Event()  # Cleared while the runner has paused execution
+        self._resume.set()
+        self.input_limits = None  # Synthetic input caps sent by the runner on start
+        self._input_next = {"click": 0.0, "keystroke": 0.0}  # Earliest time the next input may start
+        self._last_destructive = 0.0
+        self._secret_replies = {}  # request_id -> secret_value params, filled by the stdin loop
+        self._secret_lock = threading.Condition()
+        self._resume_after = None  # Action id a restored run continues after, sent on start
+        self.safe_bounds = None  # Safe mode rectangle pointer input must stay in, sent on start
+        self.run_options = {}  # Per-run speed, retries, failure strategy and tags, sent on start
+        self.variable_bindings = {}  # Variables for the current run only, sent on start
+        self.active_states: set[str] = set()  # Ids of the states the automation is in
+        self.transition_history = deque(maxlen=TRANSITION_HISTORY_SIZE)
 
         if QONTINUI_AVAILABLE:
             self.actions = FluentActions()
             self.settings = get_settings()
 
+        self.announce()
+
+    def announce(self):
+        """Tell the runner which protocol this executor speaks and that it's ready."""
+        self._emit_event(EventType.HELLO, hello("qontinui_executor", COMMANDS, ("safe_mode",)))
         self._emit_event(
             EventType.READY,
             {"message": "Qontinui executor initialized", "library_available": QONTINUI_AVAILABLE},
         )
 
     def _emit_event(self, event_type: EventType, data: dict[str, Any]):
-        """Emit event to Tauri through stdout."""
+        """Emit event to Tauri through the transport."""
         event = {
             "type": "event",
             "event": event_type.value,
@@ -129,12 +200,141 @@ class QontinuiExecutor:
             "data": data,
         }
         self._sequence += 1
-        print(json.dumps(event), flush=True)
+        get_transport().send(event)
 
     def _emit_log(self, level: str, message: str):
         """Emit log message."""
         self._emit_event(EventType.LOG, {"level": level, "message": message})
 
+    def get_variable(self, key: str, scope: str = "config") -> Any:
+        """Read a variable bound for this run or stored by the runner (None when unset)."""
+        if key in self.variable_bindings:
+            return self.variable_bindings[key]
+        return self.variables.get(scope, {}).get(key)
+
+    def set_variable(self, key: str, value: Any, scope: str = "config"):
+        """Store a variable; the runner persists it for later runs. None deletes it."""
+        scoped = self.variables.setdefault(scope, {})
+        if value is None:
+            scoped.pop(key, None)
+        else:
+            scoped[key] = value
+        self._emit_event(EventType.VARIABLE_SET, {"key": key, "value": value, "scope": scope})
+
+    def _execution_setting(self, key: str, default: Any) -> Any:
+        """A configuration execution setting, for what the run doesn't override."""
+        value = (self.config or {}).get("settings", {}).get("execution", {}).get(key)
+        return default if value is None else value
+
+    def _wait_while_paused(self):
+        """Block between actions while paused; returns early if execution stops."""
+        while self.is_running and not self._resume.wait(0.5):
+            pass
+
+    def _pace_input(self, action_type: str, destructive: bool):
+        """Wait until starting this action keeps within the runner's input limits.
+
+        The runner checks action_started timestamps and stops runs that exceed them.
+        """
+        limits = self.input_limits
+        if not limits or not limits.get("enabled", True):
+            return
+        kind = INPUT_KINDS.get(action_type)
+        rate = limits.get(INPUT_LIMIT_KEYS[kind], 0) if kind else 0
+        min_gap = limits.get("min_destructive_delay_ms", 0) / 1000.0
+
+        now = time.time()
+        ready = now
+        if rate:
+            ready = max(ready, self._input_next[kind])
+        if destructive:
+            ready = max(ready, self._last_destructive + min_gap)
+        if ready > now:
+            self._emit_log("debug", f"Input limit: delaying {action_type} by {ready - now:.2f}s")
+            time.sleep(ready - now)
+
+        now = time.time()
+        if rate:
+            self._input_next[kind] = now + 1.0 / rate
+        if destructive:
+            self._last_destructive = now
+
+    def _charge_keystrokes(self, count: int):
+        """Count typed characters beyond the first against the keystroke limit."""
+        rate = (self.input_limits or {}).get("max_keystrokes_per_second", 0)
+        if rate and count > 1 and (self.input_limits or {}).get("enabled", True):
+            self._input_next["keystroke"] += (count - 1) / rate
+
+    def _guard_pointer(self, action_data: dict[str, Any], *locations):
+        """Report where pointer input is about to go and refuse it outside safe mode bounds.
+
+        The runner checks the reported points as well and stops the run on a violation.
+        """
+        bounds = self.safe_bounds
+        if not bounds:
+            return
+        points = [[getattr(loc, "x", None), getattr(loc, "y", None)] for loc in locations]
+        self._emit_event(
+            EventType.INPUT_TARGET,
+            {
+                "action_id": action_data.get("id"),
+                "action_type": action_data.get("type"),
+                "points": points,
+            },
+        )
+        for x, y in points:
+            inside = (
+                x is not None
+                and y is not None
+                and bounds["x"] <= x < bounds["x"] + bounds["width"]
+                and bounds["y"] <= y < bounds["y"] + bounds["height"]
+            )
+            if not inside:
+                self.is_running = False
+                raise RuntimeError(f"Safe mode: input at ({x}, {y}) is outside the allowed area")
+
+    def _resolve_secrets(self, value: Any) -> tuple[Any, list[str]]:
+        """Replace ${secret:NAME} placeholders in an action's settings with the secrets.
+
+        The runner leaves them in the configuration, so each value is asked for
+        only when the action runs; $${secret: stays a literal ${secret:. Returns
+        the resolved copy and the names of the secrets used.
+        """
+        used: list[str] = []
+
+        def replace(match: re.Match) -> str:
+            if match.group(1):
+                return match.group(0)[1:]
+            used.append(match.group(2))
+            return self.get_secret(match.group(2))
+
+        def resolve(item: Any) -> Any:
+            if isinstance(item, str):
+                return SECRET_PLACEHOLDER.sub(replace, item)
+            if isinstance(item, dict):
+                return {key: resolve(field) for key, field in item.items()}
+            if isinstance(item, list):
+                return [resolve(entry) for entry in item]
+            return item
+
+        return resolve(value), used
+
+    def get_secret(self, name: str, timeout: float = 60.0) -> str:
+        """Fetch a secret from the runner's keychain at the moment it is needed.
+
+        The value is never logged or kept after the call; raises RuntimeError
+        when the runner can't provide it.
+        """
+        request_id = f"secret-{time.time_ns()}"
+        self._emit_event(EventType.SECRET_REQUEST, {"request_id": request_id, "name": name})
+        with self._secret_lock:
+            if not self._secret_lock.wait_for(lambda: request_id in self._secret_replies, timeout):
+                raise RuntimeError(f"Timed out waiting for secret '{name}'")
+            reply = self._secret_replies.pop(request_id)
+        if "value" not in reply:
+            raise RuntimeError(reply.get("error") or f"Secret '{name}' is not available")
+        return reply["value"]
+
     def _get_best_match_regardless_of_threshold(self, image_id: str) -> dict:
         """Get best match info even if it doesn't meet threshold.
 
@@ -213,13 +413,15 @@ class QontinuiExecutor:
         except Exception:
             template_size = "unknown"
 
-        # Try to get screenshot size
+        # Try to get screenshot size, and send the screenshot over the frame channel
         screenshot_size = "unknown"
+        frame_id = None
         try:
             from PIL import ImageGrab
 
             screenshot = ImageGrab.grab()
             screenshot_size = f"{screenshot.width}x{screenshot.height}"
+            frame_id = get_frame_channel().send_image(screenshot)
         except Exception:
             pass
 
@@ -242,6 +444,7 @@ class QontinuiExecutor:
                 "percent_off": (
                     ((threshold - confidence) / threshold * 100) if confidence < threshold else 0
                 ),
+                "frame_id": frame_id,
             }
             self._emit_log(
                 "debug",
@@ -257,6 +460,7 @@ class QontinuiExecutor:
                 "threshold": threshold,
                 "confidence": 0.0,
                 "found": False,
+                "frame_id": frame_id,
             }
 
             # Add best match information if available
@@ -352,13 +556,17 @@ class QontinuiExecutor:
 
         return result
 
-    def load_configuration(self, config_path: str) -> bool:
-        """Load configuration from file and set up Qontinui states."""
+    def load_configuration(self, config_path: str | None, config_data: str | None = None) -> bool:
+        """Load configuration from file or inline content and set up Qontinui states."""
         try:
-            self._emit_log("info", f"Loading configuration from: {config_path}")
+            if config_data is not None:
+                self._emit_log("info", "Loading configuration sent by the runner")
+                self.config = json.loads(config_data)
+            else:
+                self._emit_log("info", f"Loading configuration from: {config_path}")
 
-            with open(config_path) as f:
-                self.config = json.load(f)
+                with open(config_path) as f:
+                    self.config = json.load(f)
 
             # Note: We allow config loading even without Qontinui library for testing
             # Actual execution will still require the library
@@ -506,6 +714,11 @@ class QontinuiExecutor:
                     import traceback
                     self._emit_log("debug", f"Traceback: {traceback.format_exc()}")
 
+            self.active_states = {
+                state.get("id") for state in self.config.get("states", []) if state.get("isInitial")
+            }
+            self.transition_history.clear()
+
             config_info = {
                 "path": config_path,
                 "version": self.config.get("version", "unknown"),
@@ -546,10 +759,21 @@ class QontinuiExecutor:
             return True
 
         try:
+            destructive = bool(action_data.get("destructive", False))
+            self._pace_input(action_type, destructive)
             self._emit_event(
                 EventType.ACTION_STARTED,
-                {"action_id": action_data.get("id"), "action_type": action_type},
+                {
+                    "action_id": action_data.get("id"),
+                    "action_type": action_type,
+                    "destructive": destructive,
+                },
             )
+            try:
+                config, secrets_used = self._resolve_secrets(config)
+            except RuntimeError as e:
+                self._emit_log("error", str(e))
+                return False
 
             if action_type == "CLICK":
                 target = config.get("target", {})
@@ -558,6 +782,7 @@ class QontinuiExecutor:
                 if isinstance(target, str):
                     if target == "Last Find Result":
                         if self._last_find_location:
+                            self._guard_pointer(action_data, self._last_find_location)
                             self.actions.click(self._last_find_location)
                             self._emit_log("info", f"Clicked at last find location: {self._last_find_location}")
                         else:
@@ -593,6 +818,7 @@ class QontinuiExecutor:
                             if matches:
                                 # Click on first match
                                 location = matches[0].location
+                                self._guard_pointer(action_data, location)
                                 self.actions.click(location)
                                 self._emit_log("info", f"Clicked at {location}")
                             else:
@@ -602,6 +828,7 @@ class QontinuiExecutor:
                         x = target.get("x", 0)
                         y = target.get("y", 0)
                         location = Location(x, y)
+                        self._guard_pointer(action_data, location)
                         self.actions.click(location)
                         self._emit_log("info", f"Clicked at ({x}, {y})")
                 # Fallback: check for x,y directly in config (legacy format)
@@ -609,6 +836,7 @@ class QontinuiExecutor:
                     x = config.get("x", 0)
                     y = config.get("y", 0)
                     location = Location(x, y)
+                    self._guard_pointer(action_data, location)
                     self.actions.click(location)
                     self._emit_log("info", f"Clicked at ({x}, {y}) [legacy format]")
 
@@ -639,6 +867,14 @@ class QontinuiExecutor:
 
                     if not text:
                         self._emit_log("warning", f"Could not load text from stateString (state={selected_state}, strings={selected_strings})")
+                elif text_source == "secret":
+                    # Resolved from the runner's keychain only now, never stored or logged
+                    secret_name = config.get("secretName", "")
+                    try:
+                        text = self.get_secret(secret_name)
+                    except RuntimeError as e:
+                        self._emit_log("error", str(e))
+                        return False
                 else:
                     text = config.get("text", "")
 
@@ -679,7 +915,13 @@ class QontinuiExecutor:
                     self.actions.clear()  # Clear the chain for next use
                 elif hasattr(self.actions, "type"):
                     self.actions.type(processed_text)
-                self._emit_log("info", f"Typed: {text}")
+                self._charge_keystrokes(len(processed_text))
+                if text_source == "secret":
+                    self._emit_log("info", f"Typed secret '{config.get('secretName', '')}'")
+                elif secrets_used:
+                    self._emit_log("info", f"Typed text with secrets {', '.join(secrets_used)}")
+                else:
+                    self._emit_log("info", f"Typed: {text}")
 
                 # Note: The {ENTER} placeholder in the text is already handled by _process_special_keys
                 # The press_enter flag is kept for backward compatibility but may be redundant
@@ -826,6 +1068,7 @@ class QontinuiExecutor:
                 success = navigation_api.open_states(state_names)
 
                 if success:
+                    self._enter_states(self._state_ids(state_names))
                     self._emit_log("info", f"Successfully navigated to states: {state_names}")
                 else:
                     self._emit_log("warning", f"Failed to navigate to states: {state_names}")
@@ -835,7 +1078,7 @@ class QontinuiExecutor:
             elif action_type in ("RUN_WORKFLOW", "RUN_PROCESS"):
                 workflow_id = config.get("workflow") or config.get("workflowId")
                 if not workflow_id:
-                    self._emit_log("error", f"No workflow ID in config: {config}")
+                    self._emit_log("error", f"No workflow ID in config: {action_data.get('config', {})}")
                     return False
 
                 self._emit_log("info", f"RUN_WORKFLOW - Running nested workflow: {workflow_id}")
@@ -933,6 +1176,7 @@ class QontinuiExecutor:
                     to_loc = Location(to_target.get("x", 0), to_target.get("y", 0))
 
                 if from_loc and to_loc:
+                    self._guard_pointer(action_data, from_loc, to_loc)
                     self.actions.drag(from_loc, to_loc)
                     self._emit_log("info", f"Dragged from {from_loc} to {to_loc}")
                 else:
@@ -976,6 +1220,7 @@ class QontinuiExecutor:
                 if isinstance(target, str):
                     if target == "Last Find Result":
                         if self._last_find_location:
+                            self._guard_pointer(action_data, self._last_find_location)
                             self.actions.move(self._last_find_location)
                             self._emit_log("info", f"Moved mouse to last find location: {self._last_find_location}")
                         else:
@@ -995,6 +1240,7 @@ class QontinuiExecutor:
 
                             if matches:
                                 location = matches[0].location
+                                self._guard_pointer(action_data, location)
                                 self.actions.move(location)
                                 self._emit_log("info", f"Moved mouse to {location}")
                             else:
@@ -1004,6 +1250,7 @@ class QontinuiExecutor:
                         x = target.get("x", 0)
                         y = target.get("y", 0)
                         location = Location(x, y)
+                        self._guard_pointer(action_data, location)
                         self.actions.move(location)
                         self._emit_log("info", f"Moved mouse to ({x}, {y})")
 
@@ -1043,22 +1290,47 @@ class QontinuiExecutor:
             self._emit_log("error", f"Workflow {workflow_id} not found")
             return False
 
+        # Only the run's own workflow resumes; nested workflows run in full
+        resume_after = self._resume_after
+        self._resume_after = None
+        action_ids = [action.get("id") if isinstance(action, dict) else None for action in actions]
+        if resume_after and resume_after in action_ids:
+            actions = actions[action_ids.index(resume_after) + 1 :]
+            self._emit_log("info", f"Resuming workflow {workflow_id} after action {resume_after}")
+
         self._emit_event(
             EventType.WORKFLOW_STARTED, {"workflow_id": workflow_id, "workflow_name": workflow_id}
         )
 
         success = True
+        speed = self.run_options.get("speed") or 1.0
+        max_retries = self.run_options.get("max_retries")
+        if max_retries is None:
+            max_retries = self._execution_setting("defaultRetryCount", 0)
+        strategy = self.run_options.get("failure_strategy") or self._execution_setting(
+            "failureStrategy", "stop"
+        )
 
         for action in actions:
+            self._wait_while_paused()
             if not self.is_running:
                 break
 
-            if not self._execute_action(action):
+            succeeded = self._execute_action(action)
+            attempt = 0
+            while not succeeded and attempt < max_retries and self.is_running:
+                attempt += 1
+                self._emit_log("info", f"Retrying action (attempt {attempt + 1} of {max_retries + 1})")
+                time.sleep(0.5 / speed)
+                succeeded = self._execute_action(action)
+
+            if not succeeded:
                 success = False
-                break
+                if strategy != "continue":
+                    break
 
             # Small delay between actions
-            time.sleep(0.5)
+            time.sleep(0.5 / speed)
 
         self._emit_event(
             EventType.WORKFLOW_COMPLETED, {"workflow_id": workflow_id, "success": success}
@@ -1080,11 +1352,170 @@ class QontinuiExecutor:
         """
         try:
             success = self._execute_workflow(workflow_id)
+            self._record_transition(workflow_id, success)
             return {'success': success}
         except Exception as e:
             self._emit_log("error", f"Workflow execution failed: {e}")
             return {'success': False, 'error': str(e)}
 
+    def _state_ids(self, names_or_ids: list[str]) -> set[str]:
+        """Ids of the configuration's states, given by id or name."""
+        ids = set()
+        for state in self.config.get("states", []) if self.config else []:
+            if state.get("id") in names_or_ids or state.get("name") in names_or_ids:
+                ids.add(state.get("id"))
+        return ids
+
+    def _enter_states(self, entered: set[str], left: set[str] = frozenset(), transition_id=None):
+        """Update the active states and tell the runner if they changed."""
+        active = (self.active_states - left) | entered
+        if active == self.active_states:
+            return
+        self.active_states = active
+        self._emit_event(
+            EventType.STATE_CHANGED,
+            {"active_states": sorted(active), "transition_id": transition_id},
+        )
+
+    def _record_transition(self, workflow_id: str, success: bool):
+        """Note a transition the navigation system ran through one of its workflows."""
+        transitions = [
+            t
+            for t in (self.config or {}).get("transitions", [])
+            if workflow_id in t.get("workflows", [])
+        ]
+        if not transitions:
+            return
+        # The one leaving a state the automation is in, when several run the workflow
+        transition = next(
+            (t for t in transitions if t.get("fromState") in self.active_states), transitions[0]
+        )
+        from_state = transition.get("fromState")
+        to_states = [s for s in [transition.get("toState")] if s] + transition.get("activateStates", [])
+        self.transition_history.append(
+            {
+                "transition_id": transition.get("id"),
+                "workflow_id": workflow_id,
+                "from_state": from_state,
+                "to_states": to_states,
+                "success": success,
+                "timestamp": time.time(),
+            }
+        )
+        if success:
+            left = set(transition.get("deactivateStates", []))
+            if from_state and transition.get("toState"):
+                left.add(from_state)
+            self._enter_states(set(to_states), left, transition.get("id"))
+
+    def _rebuild_states(self) -> dict[str, Any]:
+        """Make the states whose images are on screen the active ones.
+
+        States without loaded images can't be checked and keep their activation.
+        """
+        if self.config is None:
+            return {"success": False, "error": "No configuration loaded"}
+        if not QONTINUI_AVAILABLE:
+            return {"success": False, "error": "Finding states on screen needs the Qontinui library"}
+
+        found, unverified = set(), []
+        for state in self.config.get("states", []):
+            image_ids = [
+                image.get("id") for image in state.get("stateImages", []) if image.get("id") in self.images
+            ] + [image_id for image_id in state.get("identifyingImages", []) if image_id in self.images]
+            if not image_ids:
+                unverified.append(state.get("id"))
+                continue
+            for image_id in image_ids:
+                try:
+                    if Find(self.images[image_id]).similarity(0.9).execute().matches:
+                        found.add(state.get("id"))
+                        break
+                except Exception as e:
+                    self._emit_log("debug", f"Could not look for image {image_id}: {e}")
+
+        kept = self.active_states & set(unverified)
+        self._enter_states(found | kept, self.active_states - found - kept)
+        self._emit_log("info", f"Rebuilt states from the screen: {sorted(found)}")
+        return {
+            "success": True,
+            "data": {"states": self._active_state_refs(), "unverified": unverified},
+        }
+
+    def _match_image(self, image_id: str, threshold: float) -> dict[str, Any]:
+        """Look for an image once, the way actions do, reporting scores below the threshold too."""
+        if image_id not in self.images:
+            return {"success": False, "error": f"Image not loaded: {image_id}"}
+        if not QONTINUI_AVAILABLE:
+            return {"success": False, "error": "Matching images needs the Qontinui library"}
+
+        def rect(match) -> dict[str, Any]:
+            region = getattr(match, "region", match)
+            return {
+                "x": int(getattr(region, "x", 0)),
+                "y": int(getattr(region, "y", 0)),
+                "width": int(getattr(region, "w", getattr(region, "width", 0))),
+                "height": int(getattr(region, "h", getattr(region, "height", 0))),
+                "score": float(getattr(match, "score", threshold)),
+            }
+
+        try:
+            matches = [rect(m) for m in Find(self.images[image_id]).similarity(threshold).execute().matches or []]
+        except Exception as e:
+            return {"success": False, "error": f"Could not look for image {image_id}: {e}"}
+        matches.sort(key=lambda m: m["score"], reverse=True)
+        best = self._get_best_match_regardless_of_threshold(image_id)
+        best_score = best["confidence"] if best else (matches[0]["score"] if matches else None)
+        near_misses = []
+        if best and best["confidence"] < threshold:
+            template = self.images[image_id]
+            width = int(getattr(template, "width", 0))
+            height = int(getattr(template, "height", 0))
+            near_misses.append(
+                {
+                    "x": best["x"] - width // 2,
+                    "y": best["y"] - height // 2,
+                    "width": width,
+                    "height": height,
+                    "score": best["confidence"],
+                }
+            )
+        return {
+            "success": True,
+            "data": {"matches": matches, "best_score": best_score, "near_misses": near_misses},
+        }
+
+    def _active_state_refs(self) -> list[dict[str, Any]]:
+        """Id and name of each active state."""
+        names = {s.get("id"): s.get("name") for s in (self.config or {}).get("states", [])}
+        return [{"id": i, "name": names.get(i)} for i in sorted(self.active_states)]
+
+    def _state_graph(self) -> dict[str, Any]:
+        """The configuration's states and transitions, with the active states marked."""
+        config = self.config or {}
+        return {
+            "states": [
+                {
+                    "id": state.get("id"),
+                    "name": state.get("name"),
+                    "initial": bool(state.get("isInitial")),
+                    "active": state.get("id") in self.active_states,
+                }
+                for state in config.get("states", [])
+            ],
+            "transitions": [
+                {
+                    "id": transition.get("id"),
+                    "from_state": transition.get("fromState"),
+                    "to_state": transition.get("toState"),
+                    "activate_states": transition.get("activateStates", []),
+                    "deactivate_states": transition.get("deactivateStates", []),
+                    "workflows": transition.get("workflows", []),
+                }
+                for transition in config.get("transitions", [])
+            ],
+        }
+
     def _run_workflow(self, workflow_id: str):
         """Run a specific workflow directly."""
         try:
@@ -1144,7 +1575,8 @@ class QontinuiExecutor:
             self.is_running = True
 
             self._emit_event(
-                EventType.EXECUTION_STARTED, {"workflow_id": workflow_id}
+                EventType.EXECUTION_STARTED,
+                {"workflow_id": workflow_id, "tags": self.run_options.get("tags", [])},
             )
 
             # Run workflow in separate thread
@@ -1181,20 +1613,67 @@ class QontinuiExecutor:
         params = command.get("params", {})
 
         if cmd_type == "load":
+            try:
+                config_data = self._config_assembler.resolve(params)
+            except ConfigTransferError as e:
+                return {"success": False, "error": str(e)}
             config_path = params.get("config_path")
-            success = self.load_configuration(config_path)
+            success = self.load_configuration(config_path, config_data)
+            return {"success": success}
+
+        elif cmd_type == "load_chunk":
+            return self._config_assembler.add_chunk(params)
+
+        elif cmd_type == "load_delta":
+            if self.config is None:
+                return {"success": False, "error": "No configuration loaded to apply changes to"}
+            merged = apply_delta(self.config, params)
+            success = self.load_configuration(None, json.dumps(merged))
             return {"success": success}
 
         elif cmd_type == "start":
+            self.variables = params.get("variables") or {"global": {}, "config": {}}
+            self.input_limits = params.get("input_limits")
+            self._resume_after = params.get("resume_after_action")
+            self.safe_bounds = params.get("safe_bounds")
+            self.run_options = {
+                key: params[key]
+                for key in ("speed", "max_retries", "failure_strategy", "tags")
+                if key in params
+            }
+            self.variable_bindings = params.get("variable_bindings") or {}
+            self._resume.set()
             # Get workflow_id from params
             workflow_id = params.get("workflow_id")
             success = self.start_execution(workflow_id)
             return {"success": success}
 
+        elif cmd_type == "secret_value":
+            with self._secret_lock:
+                self._secret_replies[params.get("request_id")] = params
+                self._secret_lock.notify_all()
+            return {"success": True}
+
+        elif cmd_type == "pause":
+            if not self.is_running:
+                return {"success": False, "error": "No execution in progress"}
+            self._resume.clear()
+            self._emit_event(EventType.EXECUTION_PAUSED, {"reason": params.get("reason")})
+            return {"success": True}
+
+        elif cmd_type == "resume":
+            self._resume.set()
+            self._emit_event(EventType.EXECUTION_RESUMED, {})
+            return {"success": True}
+
         elif cmd_type == "stop":
             self.stop_execution()
             return {"success": True}
 
+        elif cmd_type == "ping":
+            # Heartbeat from the runner's watchdog
+            return {"success": True, "time": time.time()}
+
         elif cmd_type == "status":
             return {
                 "is_running": self.is_running,
@@ -1202,6 +1681,57 @@ class QontinuiExecutor:
                 "library_available": QONTINUI_AVAILABLE,
             }
 
+        elif cmd_type == "set_variables":
+            # Runtime inputs for the current run; a None value removes one
+            bindings = dict(self.variable_bindings)
+            for key, value in (params.get("variables") or {}).items():
+                if value is None:
+                    bindings.pop(key, None)
+                else:
+                    bindings[key] = value
+            self.variable_bindings = bindings
+            return {"success": True, "data": {"run": bindings}}
+
+        elif cmd_type == "get_variables":
+            return {
+                "success": True,
+                "data": {
+                    "run": self.variable_bindings,
+                    "global": self.variables.get("global", {}),
+                    "config": self.variables.get("config", {}),
+                    "is_running": self.is_running,
+                    "paused": self.is_running and not self._resume.is_set(),
+                },
+            }
+
+        elif cmd_type == "active_states":
+            return {"success": True, "data": {"states": self._active_state_refs()}}
+
+        elif cmd_type in ("activate_state", "deactivate_state"):
+            state_ids = self._state_ids([params.get("state_id")])
+            if not state_ids:
+                return {"success": False, "error": f"Unknown state: {params.get('state_id')}"}
+            self._emit_log("info", f"Manual state correction: {cmd_type} {params.get('state_id')}")
+            if cmd_type == "activate_state":
+                self._enter_states(state_ids)
+            else:
+                self._enter_states(set(), state_ids)
+            return {"success": True, "data": {"states": self._active_state_refs()}}
+
+        elif cmd_type == "rebuild_states":
+            return self._rebuild_states()
+
+        elif cmd_type == "match_image":
+            return self._match_image(params.get("image_id"), float(params.get("threshold", 0.8)))
+
+        elif cmd_type == "state_graph":
+            if self.config is None:
+                return {"success": False, "error": "No configuration loaded"}
+            return {"success": True, "data": self._state_graph()}
+
+        elif cmd_type == "transition_history":
+            return {"success": True, "data": {"transitions": list(self.transition_history)}}
+
         elif cmd_type == "start_recording":
             return self._handle_start_recording(params)
 
@@ -1258,8 +1788,13 @@ def main():
     """Main entry point for the Qontinui executor."""
     executor = QontinuiExecutor()
 
-    # Read commands from stdin
-    for line in sys.stdin:
+    # Read commands from the runner (stdin, or the socket it offered)
+    serve(executor, get_transport().lines())
+
+
+def serve(executor: QontinuiExecutor, lines):
+    """Handle the runner's commands in ``lines`` until they end."""
+    for line in lines:
         try:
             command = json.loads(line.strip())
 
@@ -1267,8 +1802,7 @@ def main():
                 response = executor.handle_command(command)
                 response["id"] = command.get("id")
                 response["type"] = "response"
-                sys.stdout.write(json.dumps(response) + "\n")
-                sys.stdout.flush()
+                get_transport().send(response)
 
         except json.JSONDecodeError as e:
             executor._emit_event(
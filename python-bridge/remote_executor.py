@@ -0,0 +1,117 @@
+#!/usr/bin/env python3
+"""
+Serves the Qontinui executor to a runner on another machine.
+
+    QONTINUI_REMOTE_TOKEN=... python remote_executor.py [--host 0.0.0.0] [--port 9876]
+        [--cert cert.pem --key key.pem]
+
+Without ``--host`` only runners on this machine can connect; listening on any
+other address needs a certificate, so the token and the session never cross
+the network in clear text. Runners connect over TCP, behind TLS when a
+certificate is given, and send
+``{"type": "connect", "token": ...}`` as their first frame; the token must
+match ``QONTINUI_REMOTE_TOKEN``. An accepted runner gets the executor's
+``hello`` and then exchanges the same frames as over the local socket
+transport. A refused one is disconnected without a word.
+
+One runner is served at a time. The executor, with its configuration and any
+run, outlives the connection, so a runner that reconnects carries on where it
+left off.
+"""
+
+import argparse
+import hmac
+import ipaddress
+import json
+import os
+import socket
+import ssl
+import sys
+
+from qontinui_executor import QontinuiExecutor, serve
+from transport import get_transport, read_frame
+
+TOKEN_ENV = "QONTINUI_REMOTE_TOKEN"
+DEFAULT_PORT = 9876
+
+# How long a runner gets to send the token
+AUTH_TIMEOUT = 5.0
+
+
+def authenticate(conn: socket.socket, token: str) -> bool:
+    """Whether the runner on ``conn`` sent the token first."""
+    conn.settimeout(AUTH_TIMEOUT)
+    try:
+        line = read_frame(conn.makefile("rb"))
+        sent = json.loads(line).get("token", "") if line else ""
+    except (OSError, ValueError, AttributeError):
+        return False
+    finally:
+        conn.settimeout(None)
+    return isinstance(sent, str) and hmac.compare_digest(sent.encode(), token.encode())
+
+
+def is_loopback(host: str) -> bool:
+    """Whether ``host`` only accepts connections from this machine."""
+    if host == "localhost":
+        return True
+    try:
+        return ipaddress.ip_address(host).is_loopback
+    except ValueError:
+        return False
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[1])
+    parser.add_argument("--host", default="127.0.0.1")
+    parser.add_argument("--port", type=int, default=DEFAULT_PORT)
+    parser.add_argument("--cert", help="PEM certificate to serve TLS with")
+    parser.add_argument("--key", help="PEM private key of the certificate")
+    args = parser.parse_args()
+
+    token = os.environ.get(TOKEN_ENV)
+    if not token:
+        sys.exit(f"Set {TOKEN_ENV} to the token runners must present")
+
+    if not args.cert and not is_loopback(args.host):
+        sys.exit(f"Listening on {args.host} needs --cert and --key")
+
+    tls = None
+    if args.cert:
+        tls = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        tls.load_cert_chain(args.cert, args.key)
+
+    listener = socket.create_server((args.host, args.port))
+    sys.stderr.write(
+        f"Serving the executor on {args.host}:{args.port}{' with TLS' if tls else ''}\n"
+    )
+    executor = None
+    transport = get_transport()
+    while True:
+        conn, peer = listener.accept()
+        conn.setsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY, 1)
+        try:
+            if tls is not None:
+                conn = tls.wrap_socket(conn, server_side=True)
+        except (OSError, ssl.SSLError) as e:
+            sys.stderr.write(f"TLS handshake with {peer[0]} failed: {e}\n")
+            conn.close()
+            continue
+        if not authenticate(conn, token):
+            sys.stderr.write(f"Refused {peer[0]}: wrong token\n")
+            conn.close()
+            continue
+
+        sys.stderr.write(f"Runner connected from {peer[0]}\n")
+        transport.attach(conn)
+        if executor is None:
+            executor = QontinuiExecutor()
+        else:
+            executor.announce()
+        serve(executor, transport.socket_lines())
+        transport.detach()
+        sys.stderr.write(f"Runner at {peer[0]} disconnected\n")
+
+
+if __name__ == "__main__":
+    main()
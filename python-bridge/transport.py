@@ -0,0 +1,173 @@
+#!/usr/bin/env python3
+"""
+Transport between the bridge and the runner.
+
+Messages are JSON lines on stdin and stdout unless the runner offers a socket
+in ``QONTINUI_TRANSPORT_ADDRESS`` (``tcp://127.0.0.1:PORT`` or
+``unix:///path``). The bridge then connects, sends the token from
+``QONTINUI_TRANSPORT_TOKEN`` and exchanges length-prefixed frames there: the
+payload's length as a 4-byte big-endian integer, a kind byte (0 for a JSON
+message, 1 for binary) and the payload. A binary frame holds the message's
+length as 4 bytes, the message and raw bytes, which reach the runner as the
+base64 ``binary`` field of the message's data.
+
+stdin is still read after connecting, so commands the runner sent before the
+bridge connected aren't lost. If the socket can't be reached the bridge stays
+on stdio.
+
+``remote_executor.py`` instead attaches the sockets of runners connecting
+from other machines, one at a time; while none is attached messages go to
+stdout.
+"""
+
+import base64
+import json
+import os
+import queue
+import socket
+import struct
+import sys
+import threading
+from collections.abc import Iterator
+from typing import Any
+
+ADDRESS_ENV = "QONTINUI_TRANSPORT_ADDRESS"
+TOKEN_ENV = "QONTINUI_TRANSPORT_TOKEN"
+
+FRAME_JSON = 0
+FRAME_BINARY = 1
+
+CONNECT_TIMEOUT = 5.0
+
+_HEADER = struct.Struct(">IB")
+_LENGTH = struct.Struct(">I")
+
+
+class Transport:
+    """Sends messages to the runner and receives its commands."""
+
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._socket: socket.socket | None = None
+        address = os.environ.get(ADDRESS_ENV)
+        if address:
+            try:
+                self._socket = connect_socket(address)
+                self._send_frame(
+                    FRAME_JSON,
+                    json.dumps({"type": "connect", "token": os.environ.get(TOKEN_ENV, "")}).encode(),
+                )
+            except (OSError, ValueError) as e:
+                self._socket = None
+                sys.stderr.write(f"Falling back to stdio, can't connect to {address}: {e}\n")
+
+    @property
+    def kind(self) -> str:
+        """``socket`` once connected, ``stdio`` otherwise."""
+        return "stdio" if self._socket is None else "socket"
+
+    def send(self, message: dict[str, Any], binary: bytes | None = None):
+        """Send one message, with ``binary`` bytes attached to its data."""
+        with self._lock:
+            if self._socket is None:
+                if binary is not None:
+                    message.setdefault("data", {})["binary"] = base64.b64encode(binary).decode()
+                sys.stdout.write(json.dumps(message) + "\n")
+                sys.stdout.flush()
+                return
+            payload = json.dumps(message).encode()
+            try:
+                if binary is None:
+                    self._send_frame(FRAME_JSON, payload)
+                else:
+                    self._send_frame(FRAME_BINARY, _LENGTH.pack(len(payload)) + payload + binary)
+            except OSError as e:
+                # The runner went away mid-run; the command loop notices too
+                sys.stderr.write(f"Dropped a message, the runner's connection failed: {e}\n")
+
+    def _send_frame(self, kind: int, payload: bytes):
+        self._socket.sendall(_HEADER.pack(len(payload), kind) + payload)
+
+    def attach(self, sock: socket.socket):
+        """Send messages to ``sock``, a runner that connected from elsewhere."""
+        with self._lock:
+            self._socket = sock
+
+    def detach(self):
+        """Close the attached socket; messages go to stdout again."""
+        with self._lock:
+            sock, self._socket = self._socket, None
+        if sock is not None:
+            sock.close()
+
+    def socket_lines(self) -> Iterator[str]:
+        """JSON text of each command on the socket, until it closes."""
+        stream = self._socket.makefile("rb")
+        while (line := read_frame(stream)) is not None:
+            yield line
+
+    def lines(self) -> Iterator[str]:
+        """JSON text of each command, until the runner goes away."""
+        if self._socket is None:
+            yield from sys.stdin
+            return
+
+        received: queue.Queue[str | None] = queue.Queue()
+        threading.Thread(target=self._read_stdin, args=(received,), daemon=True).start()
+        threading.Thread(target=self._read_socket, args=(received,), daemon=True).start()
+        while (line := received.get()) is not None:
+            yield line
+
+    @staticmethod
+    def _read_stdin(received: "queue.Queue[str | None]"):
+        for line in sys.stdin:
+            received.put(line)
+        received.put(None)
+
+    def _read_socket(self, received: "queue.Queue[str | None]"):
+        stream = self._socket.makefile("rb")
+        while (line := read_frame(stream)) is not None:
+            received.put(line)
+        received.put(None)
+
+
+def read_frame(stream) -> str | None:
+    """Text of the next JSON frame on ``stream``, skipping binary ones, or None
+    once it ends."""
+    while True:
+        header = stream.read(_HEADER.size)
+        if len(header) < _HEADER.size:
+            return None
+        length, kind = _HEADER.unpack(header)
+        payload = stream.read(length)
+        if len(payload) < length:
+            return None
+        if kind == FRAME_JSON:
+            return payload.decode()
+
+
+def connect_socket(address: str) -> socket.socket:
+    """Connect to a runner socket at ``tcp://host:port`` or ``unix://path``."""
+    if address.startswith("tcp://"):
+        host, port = address[len("tcp://") :].rsplit(":", 1)
+        sock = socket.create_connection((host, int(port)), timeout=CONNECT_TIMEOUT)
+        sock.setsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY, 1)
+    elif address.startswith("unix://"):
+        sock = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        sock.settimeout(CONNECT_TIMEOUT)
+        sock.connect(address[len("unix://") :])
+    else:
+        raise ValueError(f"unknown transport address {address}")
+    sock.settimeout(None)
+    return sock
+
+
+_transport: Transport | None = None
+
+
+def get_transport() -> Transport:
+    """The bridge's transport, connected on first use."""
+    global _transport
+    if _transport is None:
+        _transport = Transport()
+    return _transport
@@ -0,0 +1,158 @@
+//! AT-SPI backend, talking to the accessibility bus over D-Bus. The bus is
+//! found through the session bus, so this works wherever a desktop session
+//! has accessibility enabled.
+
+use super::{ElementBounds, Node};
+use ::atspi::proxy::accessible::AccessibleProxyBlocking;
+use ::atspi::proxy::action::ActionProxyBlocking;
+use ::atspi::proxy::bus::BusProxyBlocking;
+use ::atspi::proxy::component::ComponentProxyBlocking;
+use ::atspi::proxy::editable_text::EditableTextProxyBlocking;
+use ::atspi::proxy::text::TextProxyBlocking;
+use ::atspi::{CoordType, Interface, ObjectRefOwned, State};
+use zbus::blocking::Connection;
+use zbus::proxy::{CacheProperties, Defaults};
+
+const REGISTRY: &str = "org.a11y.atspi.Registry";
+
+pub(super) struct Element {
+    connection: Connection,
+    accessible: AccessibleProxyBlocking<'static>,
+}
+
+/// Every application's top-level windows.
+pub(super) fn windows() -> Result<Vec<Element>, String> {
+    let connection = connect().map_err(|e| format!("AT-SPI is not available: {}", e))?;
+    let root = AccessibleProxyBlocking::builder(&connection)
+        .destination(REGISTRY)
+        .and_then(|builder| builder.cache_properties(CacheProperties::No).build())
+        .map_err(|e| format!("AT-SPI is not available: {}", e))?;
+    let root = Element {
+        connection,
+        accessible: root,
+    };
+    Ok(root.children().iter().flat_map(Node::children).collect())
+}
+
+fn connect() -> zbus::Result<Connection> {
+    let session = Connection::session()?;
+    let address = BusProxyBlocking::new(&session)?.get_address()?;
+    zbus::blocking::connection::Builder::address(address.as_str())?.build()
+}
+
+impl Element {
+    fn child(&self, object: ObjectRefOwned) -> Option<Element> {
+        let name = object.name()?.clone();
+        let accessible = AccessibleProxyBlocking::builder(&self.connection)
+            .destination(name)
+            .and_then(|builder| builder.path(object.path().clone()))
+            .and_then(|builder| builder.cache_properties(CacheProperties::No).build())
+            .ok()?;
+        Some(Element {
+            connection: self.connection.clone(),
+            accessible,
+        })
+    }
+
+    fn implements(&self, interface: Interface) -> bool {
+        self.accessible
+            .get_interfaces()
+            .is_ok_and(|interfaces| interfaces.contains(interface))
+    }
+
+    /// A proxy for another of the element's interfaces.
+    fn interface<T>(&self) -> zbus::Result<T>
+    where
+        T: From<zbus::Proxy<'static>> + Defaults,
+    {
+        let proxy = self.accessible.inner();
+        zbus::blocking::proxy::Builder::<T>::new(&self.connection)
+            .destination(proxy.destination().clone())?
+            .path(proxy.path().clone())?
+            .cache_properties(CacheProperties::No)
+            .build()
+    }
+}
+
+impl Node for Element {
+    fn name(&self) -> String {
+        self.accessible.name().unwrap_or_default()
+    }
+
+    fn role(&self) -> String {
+        self.accessible
+            .get_role_name()
+            .unwrap_or_default()
+            .to_lowercase()
+    }
+
+    fn children(&self) -> Vec<Self> {
+        self.accessible
+            .get_children()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|object| !object.is_null())
+            .filter_map(|object| self.child(object))
+            .collect()
+    }
+
+    fn value(&self) -> Option<String> {
+        if !self.implements(Interface::Text) {
+            return None;
+        }
+        self.interface::<TextProxyBlocking>()
+            .and_then(|text| text.get_text(0, -1))
+            .ok()
+    }
+
+    fn enabled(&self) -> bool {
+        self.accessible
+            .get_state()
+            .is_ok_and(|state| state.contains(State::Enabled))
+    }
+
+    fn bounds(&self) -> Option<ElementBounds> {
+        let (x, y, width, height) = self
+            .interface::<ComponentProxyBlocking>()
+            .and_then(|component| component.get_extents(CoordType::Screen))
+            .ok()?;
+        Some(ElementBounds {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    fn activate(&self) -> Result<(), String> {
+        if !self.implements(Interface::Action) {
+            return Err("Element has no actions".to_string());
+        }
+        let action = self
+            .interface::<ActionProxyBlocking>()
+            .map_err(|e| format!("Element has no actions: {}", e))?;
+        if action.n_actions().unwrap_or(0) == 0 {
+            return Err("Element has no actions".to_string());
+        }
+        action
+            .do_action(0)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to activate the element: {}", e))
+    }
+
+    fn set_value(&self, text: &str) -> Result<bool, String> {
+        if !self.implements(Interface::EditableText) {
+            return Ok(false);
+        }
+        self.interface::<EditableTextProxyBlocking>()
+            .and_then(|editable| editable.set_text_contents(text))
+            .map_err(|e| format!("Failed to set the element's text: {}", e))
+    }
+
+    fn focus(&self) -> Result<(), String> {
+        self.interface::<ComponentProxyBlocking>()
+            .and_then(|component| component.grab_focus())
+            .map(|_| ())
+            .map_err(|e| format!("Failed to focus the element: {}", e))
+    }
+}
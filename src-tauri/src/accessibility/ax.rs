@@ -0,0 +1,148 @@
+//! AX API backend. The windows of every regular (Dock) application are
+//! searched; the runner needs the Accessibility permission for any of them
+//! to answer.
+
+use super::{ElementBounds, Node};
+use accessibility::{AXAttribute, AXUIElement, AXUIElementAttributes};
+use accessibility_sys::{
+    kAXValueTypeCGPoint, kAXValueTypeCGSize, AXIsProcessTrusted, AXValueGetValue, AXValueRef,
+};
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
+use objc2_app_kit::{NSApplicationActivationPolicy, NSWorkspace};
+use std::ffi::c_void;
+
+/// Seconds an unresponsive application may hold up each AX call.
+const MESSAGING_TIMEOUT: f32 = 2.0;
+
+pub(super) struct Element(AXUIElement);
+
+/// The windows of every regular application.
+pub(super) fn windows() -> Result<Vec<Element>, String> {
+    if !unsafe { AXIsProcessTrusted() } {
+        return Err(
+            "The runner isn't allowed to use accessibility; enable it under System Settings > \
+             Privacy & Security > Accessibility"
+                .to_string(),
+        );
+    }
+
+    let mut windows = Vec::new();
+    for app in NSWorkspace::sharedWorkspace().runningApplications().iter() {
+        if app.activationPolicy() != NSApplicationActivationPolicy::Regular {
+            continue;
+        }
+        let element = AXUIElement::application(app.processIdentifier());
+        let _ = element.set_messaging_timeout(MESSAGING_TIMEOUT);
+        // Applications without windows report an error rather than none
+        if let Ok(app_windows) = element.windows() {
+            windows.extend(app_windows.iter().map(|window| Element(window.clone())));
+        }
+    }
+    Ok(windows)
+}
+
+fn attribute(name: &'static str) -> AXAttribute<CFType> {
+    AXAttribute::new(&CFString::from_static_string(name))
+}
+
+/// Unpacks an AXValue holding a CGPoint or CGSize, both two doubles.
+fn pair(value: &CFType, kind: u32) -> Option<(f64, f64)> {
+    let mut pair = [0f64; 2];
+    let unpacked = unsafe {
+        AXValueGetValue(
+            value.as_CFTypeRef() as AXValueRef,
+            kind,
+            pair.as_mut_ptr() as *mut c_void,
+        )
+    };
+    unpacked.then_some((pair[0], pair[1]))
+}
+
+impl Node for Element {
+    fn name(&self) -> String {
+        [self.0.title(), self.0.description()]
+            .into_iter()
+            .flatten()
+            .map(|name| name.to_string())
+            .find(|name| !name.is_empty())
+            .unwrap_or_default()
+    }
+
+    fn role(&self) -> String {
+        let role = self
+            .0
+            .role()
+            .map(|role| role.to_string())
+            .unwrap_or_default();
+        role.strip_prefix("AX").unwrap_or(&role).to_lowercase()
+    }
+
+    fn children(&self) -> Vec<Self> {
+        self.0
+            .children()
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|child| Element(child.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn value(&self) -> Option<String> {
+        let value = self.0.value().ok()?;
+        if let Some(text) = value.downcast::<CFString>() {
+            Some(text.to_string())
+        } else if let Some(flag) = value.downcast::<CFBoolean>() {
+            Some(bool::from(flag).to_string())
+        } else {
+            value
+                .downcast::<CFNumber>()
+                .and_then(|number| number.to_f64())
+                .map(|number| number.to_string())
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.0.enabled().map(bool::from).unwrap_or(true)
+    }
+
+    fn bounds(&self) -> Option<ElementBounds> {
+        let position = self.0.attribute(&attribute("AXPosition")).ok()?;
+        let size = self.0.attribute(&attribute("AXSize")).ok()?;
+        let (x, y) = pair(&position, kAXValueTypeCGPoint)?;
+        let (width, height) = pair(&size, kAXValueTypeCGSize)?;
+        Some(ElementBounds {
+            x: x.round() as i32,
+            y: y.round() as i32,
+            width: width.round() as i32,
+            height: height.round() as i32,
+        })
+    }
+
+    fn activate(&self) -> Result<(), String> {
+        self.0
+            .perform_action(&CFString::from_static_string("AXPress"))
+            .map_err(|_| "Element does not support AXPress".to_string())
+    }
+
+    fn set_value(&self, text: &str) -> Result<bool, String> {
+        let settable = self.0.is_settable(&AXAttribute::value()).unwrap_or(false);
+        if !settable {
+            return Ok(false);
+        }
+        self.0
+            .set_value(CFString::new(text).as_CFType())
+            .map(|_| true)
+            .map_err(|e| format!("Failed to set the element's text: {}", e))
+    }
+
+    fn focus(&self) -> Result<(), String> {
+        self.0
+            .set_attribute(&attribute("AXFocused"), CFBoolean::true_value().as_CFType())
+            .map_err(|e| format!("Failed to focus the element: {}", e))
+    }
+}
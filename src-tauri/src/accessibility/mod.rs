@@ -0,0 +1,239 @@
+//! Semantic UI element access through the OS accessibility APIs: UI
+//! Automation on Windows, the AX API on macOS and AT-SPI on Linux.
+//!
+//! Each backend exposes the top-level windows as `Node`s; the search, the
+//! choice of target and the typing fallback are shared. Element handles
+//! don't survive between calls; actions re-resolve their target from an
+//! `ElementQuery`.
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod at_spi;
+#[cfg(target_os = "macos")]
+mod ax;
+#[cfg(target_os = "windows")]
+mod uia;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+use at_spi as platform;
+#[cfg(target_os = "macos")]
+use ax as platform;
+#[cfg(target_os = "windows")]
+use uia as platform;
+
+use enigo::{Enigo, Keyboard};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Longest a search may spend walking the accessibility tree.
+const WALK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Filters for `query_elements`. All string filters are case-insensitive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElementQuery {
+    /// Platform role name without its `ControlType.`/`AX` prefix, e.g.
+    /// "button" or "edit" (UIA), "textfield" (AX), "push button" (AT-SPI).
+    pub role: Option<String>,
+    pub name: Option<String>,
+    pub name_contains: Option<String>,
+    /// Only search top-level windows whose title contains this.
+    pub window: Option<String>,
+    pub max_results: Option<usize>,
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiElement {
+    pub name: String,
+    pub role: String,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub window: Option<String>,
+    #[serde(default)]
+    pub enabled: bool,
+    /// Screen coordinates. Physical pixels on Windows and Linux, points on
+    /// macOS.
+    #[serde(default)]
+    pub bounds: Option<ElementBounds>,
+}
+
+/// One element of the platform's accessibility tree. Reads that fail give
+/// an empty or missing value rather than an error, since windows can close
+/// mid-walk.
+pub(crate) trait Node: Sized {
+    fn name(&self) -> String;
+    /// Role without its platform prefix, lowercased.
+    fn role(&self) -> String;
+    fn children(&self) -> Vec<Self>;
+    fn value(&self) -> Option<String>;
+    fn enabled(&self) -> bool;
+    fn bounds(&self) -> Option<ElementBounds>;
+    /// Invokes, presses or runs the default action.
+    fn activate(&self) -> Result<(), String>;
+    /// Sets the editable text; `Ok(false)` when the element has none.
+    fn set_value(&self, text: &str) -> Result<bool, String>;
+    fn focus(&self) -> Result<(), String>;
+}
+
+pub fn query_elements(query: &ElementQuery) -> Result<Vec<UiElement>, String> {
+    on_own_thread(|| {
+        let matches = search(platform::windows()?, query)?;
+        Ok(matches
+            .iter()
+            .map(|(node, window)| describe(node, window))
+            .collect())
+    })
+}
+
+/// Activates the matching element (invoke, press or default action). With
+/// several matches `index` picks one; without it the query must be unique.
+pub fn click_element(query: &ElementQuery, index: Option<usize>) -> Result<UiElement, String> {
+    on_own_thread(|| {
+        let (node, window) = pick(search(platform::windows()?, query)?, index)?;
+        node.activate()?;
+        Ok(describe(&node, &window))
+    })
+}
+
+/// Sets the text of the matching element, falling back to focusing it and
+/// typing the text when it exposes no editable value.
+pub fn type_into_element(
+    query: &ElementQuery,
+    index: Option<usize>,
+    text: &str,
+) -> Result<UiElement, String> {
+    on_own_thread(|| {
+        let (node, window) = pick(search(platform::windows()?, query)?, index)?;
+        if !node.set_value(text)? {
+            node.focus()?;
+            let mut enigo = Enigo::new(&enigo::Settings::default())
+                .map_err(|e| format!("Failed to type into the element: {}", e))?;
+            enigo
+                .text(text)
+                .map_err(|e| format!("Failed to type into the element: {}", e))?;
+        }
+        Ok(describe(&node, &window))
+    })
+}
+
+/// Runs `f` on a thread of its own, so the backends can set up COM or a
+/// D-Bus connection the way they need whatever thread the caller is on.
+fn on_own_thread<T: Send>(f: impl FnOnce() -> Result<T, String> + Send) -> Result<T, String> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(f)
+            .join()
+            .map_err(|_| "Accessibility backend panicked".to_string())?
+    })
+}
+
+/// Walks each window whose title passes the query's window filter, depth
+/// first, and returns the matches with the title of their window.
+pub(crate) fn search<N: Node>(
+    windows: Vec<N>,
+    query: &ElementQuery,
+) -> Result<Vec<(N, String)>, String> {
+    let limit = query.max_results.unwrap_or(50);
+    let max_depth = query.max_depth.unwrap_or(12);
+    let deadline = Instant::now() + WALK_TIMEOUT;
+    let mut found = Vec::new();
+
+    for window in windows {
+        let title = window.name();
+        if query
+            .window
+            .as_deref()
+            .is_some_and(|part| !contains(&title, part))
+        {
+            continue;
+        }
+        let mut stack = vec![(window, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            if found.len() >= limit {
+                return Ok(found);
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Accessibility search timed out after {} s",
+                    WALK_TIMEOUT.as_secs()
+                ));
+            }
+            if depth < max_depth {
+                // Reversed so the first child is visited next
+                let children = node.children();
+                stack.extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+            }
+            if is_match(&node, query) {
+                found.push((node, title.clone()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// The element an action applies to: the one at `index`, or the only match.
+pub(crate) fn pick<N>(
+    mut matches: Vec<(N, String)>,
+    index: Option<usize>,
+) -> Result<(N, String), String> {
+    match index {
+        Some(index) if index < matches.len() => Ok(matches.swap_remove(index)),
+        Some(index) => Err(format!(
+            "No element at index {} ({} matched)",
+            index,
+            matches.len()
+        )),
+        None if matches.is_empty() => Err("No element matches the query".to_string()),
+        None if matches.len() > 1 => Err(format!(
+            "{} elements match the query; pass an index",
+            matches.len()
+        )),
+        None => Ok(matches.remove(0)),
+    }
+}
+
+pub(crate) fn describe<N: Node>(node: &N, window: &str) -> UiElement {
+    UiElement {
+        name: node.name(),
+        role: node.role(),
+        value: node.value(),
+        window: Some(window.to_string()),
+        enabled: node.enabled(),
+        bounds: node.bounds(),
+    }
+}
+
+fn is_match<N: Node>(node: &N, query: &ElementQuery) -> bool {
+    if query
+        .role
+        .as_deref()
+        .is_some_and(|role| !node.role().eq_ignore_ascii_case(role))
+    {
+        return false;
+    }
+    if query.name.is_none() && query.name_contains.is_none() {
+        return true;
+    }
+    let name = node.name();
+    query
+        .name
+        .as_deref()
+        .is_none_or(|wanted| name.to_lowercase() == wanted.to_lowercase())
+        && query
+            .name_contains
+            .as_deref()
+            .is_none_or(|part| contains(&name, part))
+}
+
+fn contains(text: &str, part: &str) -> bool {
+    text.to_lowercase().contains(&part.to_lowercase())
+}
@@ -0,0 +1,110 @@
+//! UI Automation backend. The control view is walked, so layout-only panes
+//! that UIA hides from screen readers are skipped here too.
+
+use super::{ElementBounds, Node};
+use uiautomation::patterns::{
+    UIInvokePattern, UISelectionItemPattern, UITogglePattern, UIValuePattern,
+};
+use uiautomation::{UIAutomation, UIElement, UITreeWalker};
+
+pub(super) struct Element {
+    element: UIElement,
+    walker: UITreeWalker,
+}
+
+/// The desktop's top-level windows.
+pub(super) fn windows() -> Result<Vec<Element>, String> {
+    let automation =
+        UIAutomation::new().map_err(|e| format!("UI Automation is not available: {}", e))?;
+    let walker = automation
+        .get_control_view_walker()
+        .map_err(|e| format!("UI Automation is not available: {}", e))?;
+    let root = automation
+        .get_root_element()
+        .map_err(|e| format!("UI Automation is not available: {}", e))?;
+    Ok(Element {
+        element: root,
+        walker,
+    }
+    .children())
+}
+
+impl Node for Element {
+    fn name(&self) -> String {
+        self.element.get_name().unwrap_or_default()
+    }
+
+    fn role(&self) -> String {
+        // The Debug name is ProgrammaticName without `ControlType.`
+        self.element
+            .get_control_type()
+            .map(|control_type| format!("{:?}", control_type).to_lowercase())
+            .unwrap_or_default()
+    }
+
+    fn children(&self) -> Vec<Self> {
+        let mut children = Vec::new();
+        let mut next = self.walker.get_first_child(&self.element);
+        while let Ok(element) = next {
+            next = self.walker.get_next_sibling(&element);
+            children.push(Element {
+                element,
+                walker: self.walker.clone(),
+            });
+        }
+        children
+    }
+
+    fn value(&self) -> Option<String> {
+        self.element
+            .get_pattern::<UIValuePattern>()
+            .and_then(|pattern| pattern.get_value())
+            .ok()
+    }
+
+    fn enabled(&self) -> bool {
+        self.element.is_enabled().unwrap_or(false)
+    }
+
+    fn bounds(&self) -> Option<ElementBounds> {
+        let rect = self.element.get_bounding_rectangle().ok()?;
+        if rect.get_width() <= 0 && rect.get_height() <= 0 {
+            return None;
+        }
+        Some(ElementBounds {
+            x: rect.get_left(),
+            y: rect.get_top(),
+            width: rect.get_width(),
+            height: rect.get_height(),
+        })
+    }
+
+    fn activate(&self) -> Result<(), String> {
+        let result = if let Ok(pattern) = self.element.get_pattern::<UIInvokePattern>() {
+            pattern.invoke()
+        } else if let Ok(pattern) = self.element.get_pattern::<UITogglePattern>() {
+            pattern.toggle()
+        } else if let Ok(pattern) = self.element.get_pattern::<UISelectionItemPattern>() {
+            pattern.select()
+        } else {
+            return Err("Element supports no invoke, toggle or select pattern".to_string());
+        };
+        result.map_err(|e| format!("Failed to activate the element: {}", e))
+    }
+
+    fn set_value(&self, text: &str) -> Result<bool, String> {
+        match self.element.get_pattern::<UIValuePattern>() {
+            Ok(pattern) if !pattern.is_readonly().unwrap_or(true) => pattern
+                .set_value(text)
+                .map(|_| true)
+                .map_err(|e| format!("Failed to set the element's text: {}", e)),
+            _ => Ok(false),
+        }
+    }
+
+    fn focus(&self) -> Result<(), String> {
+        self.element
+            .set_focus()
+            .map_err(|e| format!("Failed to focus the element: {}", e))
+    }
+}
@@ -0,0 +1,508 @@
+//! Agent mode: the runner as a headless background service on a machine
+//! that runs automation for others. Started with `--agent` it shows no
+//! window or tray and serves the HTTP API, enabling it with a token the
+//! first time. A runner UI
+//! elsewhere or a CI job submits jobs to `POST /jobs`: a configuration and
+//! the workflows to run from it, which go through the execution queue one
+//! after another. Their progress is at `GET /jobs`, the events at `/events`
+//! and `/ws`, and the runner's log at `GET /logs`.
+//!
+//! Clients on other machines are only accepted over HTTPS, so the token
+//! never crosses the network in clear text: without a certificate the
+//! agent listens on 127.0.0.1 only.
+//!
+//! `--install-agent` registers the agent to start when the user logs in,
+//! as a systemd user unit on Linux or a scheduled task on Windows, saving
+//! the certificate given with `--tls-cert` and `--tls-key`, and
+//! `--uninstall-agent` removes it. Neither is a system service: automation
+//! needs the user's desktop, which system services run outside of.
+
+use crate::commands::{self, AppState};
+use crate::http_api;
+use crate::logging::{self, LOG_FILE_PREFIX};
+use crate::profiles;
+use crate::queue::{self, QueueParams, QueueProgress};
+use crate::settings::{HttpApiSettings, SettingsStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+/// Jobs remembered for `GET /jobs`; older ones drop off.
+pub const MAX_JOBS: usize = 100;
+
+/// Log lines `GET /logs` returns unless asked for more or fewer.
+pub const DEFAULT_LOG_LINES: usize = 200;
+
+/// Name of the systemd unit and the Windows scheduled task.
+const SERVICE_NAME: &str = "qontinui-runner-agent";
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// What the runner was started to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchMode {
+    Window,
+    Agent,
+    InstallAgent,
+    UninstallAgent,
+}
+
+impl LaunchMode {
+    /// Reads the mode from the command line arguments, without the program
+    /// name. Unknown arguments are left to Tauri.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut mode = Self::Window;
+        for arg in args {
+            match arg.as_str() {
+                "--agent" => mode = Self::Agent,
+                "--install-agent" => return Self::InstallAgent,
+                "--uninstall-agent" => return Self::UninstallAgent,
+                _ => {}
+            }
+        }
+        mode
+    }
+}
+
+/// Switches the runner to agent mode for the rest of the process.
+pub fn activate() {
+    ACTIVE.store(true, Ordering::Relaxed);
+    info!("Running as an agent");
+}
+
+/// Whether the runner was started with `--agent`.
+pub fn active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// The API settings the agent serves with: always enabled, with a token
+/// generated and saved the first time.
+pub fn api_settings(settings: &SettingsStore) -> Result<HttpApiSettings, String> {
+    let current = settings.http_api();
+    if current.enabled && !current.token.is_empty() {
+        return Ok(current);
+    }
+    settings.set_http_api(true, None)
+}
+
+/// The value of `--name <value>` or `--name=<value>` in `args`.
+pub fn option(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix(name)? {
+            "" => args.get(i + 1).cloned(),
+            value => value.strip_prefix('=').map(str::to_string),
+        })
+}
+
+/// Registers the agent to start at login and starts it, serving HTTPS
+/// with the `--tls-cert` and `--tls-key` in `args` if given. Returns what
+/// to tell the user, including the API address and token.
+pub fn install(args: &[String]) -> Result<String, String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the runner executable: {}", e))?;
+    let settings = SettingsStore::open(profiles::data_dir().join("app-settings.json"));
+    let cert = option(args, "--tls-cert");
+    let key = option(args, "--tls-key");
+    if cert.is_some() || key.is_some() {
+        settings.set_http_api_tls(cert.map(PathBuf::from), key.map(PathBuf::from))?;
+    }
+    let api = api_settings(&settings)?;
+    if api.tls() {
+        // Fails now rather than in the background service
+        http_api::tls_config(&api)?;
+    }
+    register(&exe)?;
+    let reach = if api.tls() {
+        format!("on port {} over HTTPS", api.port)
+    } else {
+        format!(
+            "on 127.0.0.1:{} only; install with --tls-cert and --tls-key to accept clients from other machines",
+            api.port
+        )
+    };
+    Ok(format!(
+        "Installed the Qontinui Runner agent. Its API is {} with the token {}",
+        reach, api.token
+    ))
+}
+
+/// Stops the agent and removes its registration.
+pub fn uninstall() -> Result<String, String> {
+    unregister()?;
+    Ok("Uninstalled the Qontinui Runner agent".to_string())
+}
+
+/// The systemd user unit starting `exe` as an agent with the desktop
+/// session.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn systemd_unit(exe: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Qontinui Runner agent\n\
+         PartOf=graphical-session.target\n\
+         After=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         ExecStart=\"{}\" --agent\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n",
+        exe.display()
+    )
+}
+
+/// `schtasks` arguments creating a task that starts `exe` as an agent when
+/// the user logs in.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn scheduled_task_args(exe: &Path) -> Vec<String> {
+    vec![
+        "/Create".to_string(),
+        "/F".to_string(),
+        "/SC".to_string(),
+        "ONLOGON".to_string(),
+        "/RL".to_string(),
+        "LIMITED".to_string(),
+        "/TN".to_string(),
+        SERVICE_NAME.to_string(),
+        "/TR".to_string(),
+        format!("\"{}\" --agent", exe.display()),
+    ]
+}
+
+#[cfg(target_os = "linux")]
+fn unit_path() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|dir| {
+            dir.join("systemd")
+                .join("user")
+                .join(format!("{}.service", SERVICE_NAME))
+        })
+        .ok_or_else(|| "Failed to find the user's config directory".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn register(exe: &Path) -> Result<(), String> {
+    let path = unit_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, systemd_unit(exe))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    run("systemctl", &["--user", "daemon-reload"])?;
+    run(
+        "systemctl",
+        &[
+            "--user",
+            "enable",
+            "--now",
+            &format!("{}.service", SERVICE_NAME),
+        ],
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn unregister() -> Result<(), String> {
+    let path = unit_path()?;
+    if !path.exists() {
+        return Err("The agent isn't installed".to_string());
+    }
+    run(
+        "systemctl",
+        &[
+            "--user",
+            "disable",
+            "--now",
+            &format!("{}.service", SERVICE_NAME),
+        ],
+    )?;
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    run("systemctl", &["--user", "daemon-reload"])
+}
+
+#[cfg(target_os = "windows")]
+fn register(exe: &Path) -> Result<(), String> {
+    let args = scheduled_task_args(exe);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run("schtasks", &args)?;
+    run("schtasks", &["/Run", "/TN", SERVICE_NAME])
+}
+
+#[cfg(target_os = "windows")]
+fn unregister() -> Result<(), String> {
+    // Not running is fine
+    let _ = run("schtasks", &["/End", "/TN", SERVICE_NAME]);
+    run("schtasks", &["/Delete", "/F", "/TN", SERVICE_NAME])
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn register(_exe: &Path) -> Result<(), String> {
+    Err("Installing the agent isn't supported on this platform; start the runner with --agent from a login item instead".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn unregister() -> Result<(), String> {
+    Err("Installing the agent isn't supported on this platform".to_string())
+}
+
+#[cfg_attr(not(any(target_os = "linux", target_os = "windows")), allow(dead_code))]
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} {} failed: {}",
+        program,
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
+}
+
+/// A job as submitted to `POST /jobs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobRequest {
+    /// Configuration file on the agent's machine.
+    pub config_path: Option<String>,
+    /// The configuration itself, for callers that don't share a file
+    /// system with the agent.
+    pub config: Option<Value>,
+    /// Workflows to run, in order.
+    pub workflows: Vec<String>,
+    /// Executor to start if none is running.
+    pub executor_type: Option<String>,
+    pub monitor_index: Option<i32>,
+    /// Shown in the queue instead of the workflow ids.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRun {
+    /// The run's execution queue entry.
+    pub entry_id: String,
+    pub workflow_id: String,
+    /// `queued`, `running`, `completed`, `failed` or `cancelled`.
+    pub status: &'static str,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub submitted_at: String,
+    /// Configuration the workflows run from; `None` for the one that was
+    /// already loaded.
+    pub config_path: Option<String>,
+    /// `queued`, `running`, `completed`, `failed` or `cancelled`, from its
+    /// runs.
+    pub status: &'static str,
+    pub runs: Vec<JobRun>,
+}
+
+impl Job {
+    fn refresh(&mut self) {
+        let count = |status| self.runs.iter().filter(|run| run.status == status).count();
+        let queued = count("queued");
+        self.status = if count("running") > 0 || (queued > 0 && queued < self.runs.len()) {
+            "running"
+        } else if queued > 0 {
+            "queued"
+        } else if count("failed") > 0 {
+            "failed"
+        } else if count("cancelled") > 0 {
+            "cancelled"
+        } else {
+            "completed"
+        };
+    }
+}
+
+/// The jobs submitted to this runner, newest last.
+#[derive(Default)]
+pub struct Jobs(Mutex<VecDeque<Job>>);
+
+impl Jobs {
+    /// Starts tracking a job whose runs have been queued.
+    pub fn add(&self, id: String, config_path: Option<String>, runs: Vec<JobRun>) -> Job {
+        let mut job = Job {
+            id,
+            submitted_at: chrono::Local::now().to_rfc3339(),
+            config_path,
+            status: "queued",
+            runs,
+        };
+        job.refresh();
+        let mut jobs = self.0.lock().unwrap();
+        if jobs.len() == MAX_JOBS {
+            jobs.pop_front();
+        }
+        jobs.push_back(job.clone());
+        job
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|job| job.id == id)
+            .cloned()
+    }
+
+    /// Updates the run an execution queue report is about.
+    pub fn record(&self, progress: &QueueProgress) {
+        let Some(entry) = &progress.entry else {
+            return;
+        };
+        let status = match progress.status {
+            "started" => "running",
+            "completed" => "completed",
+            "failed" => "failed",
+            "cancelled" => "cancelled",
+            _ => return,
+        };
+        let mut jobs = self.0.lock().unwrap();
+        for job in jobs.iter_mut() {
+            if let Some(run) = job.runs.iter_mut().find(|run| run.entry_id == entry.id) {
+                run.status = status;
+                run.error = progress.error.clone();
+                job.refresh();
+                return;
+            }
+        }
+    }
+}
+
+/// Loads the job's configuration, starting the executor first if needed,
+/// and queues its workflows.
+pub fn submit(app_handle: &AppHandle, request: JobRequest) -> Result<Job, String> {
+    if request.workflows.is_empty() {
+        return Err("A job needs at least one workflow".to_string());
+    }
+    if request.config.is_some() && request.config_path.is_some() {
+        return Err("Give either the configuration or its path, not both".to_string());
+    }
+    let state = app_handle.state::<AppState>();
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let config_path = match request.config {
+        Some(config) => {
+            let dir = jobs_dir();
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let path = dir.join(format!("{}.json", id));
+            std::fs::write(&path, config.to_string())
+                .map_err(|e| format!("Failed to save the job's configuration: {}", e))?;
+            Some(path.to_string_lossy().into_owned())
+        }
+        None => request.config_path,
+    };
+    // Swapping the configuration would change what queued runs execute
+    if config_path.is_some() && !state.execution_queue.entries().is_empty() {
+        return Err(
+            "Other runs are queued; submit the job when they're done, or without a configuration to run it against the loaded one"
+                .to_string(),
+        );
+    }
+
+    let running = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|bridge| bridge.is_running());
+    if !running {
+        let executor_type = request
+            .executor_type
+            .unwrap_or_else(|| "simple".to_string());
+        succeeded(commands::start_python_executor_with_type(
+            app_handle.clone(),
+            app_handle.state(),
+            executor_type,
+            None,
+        )?)?;
+    }
+    if let Some(path) = &config_path {
+        succeeded(commands::load_configuration(
+            path.clone(),
+            None,
+            Some(true),
+            app_handle.clone(),
+            app_handle.state(),
+        )?)?;
+    }
+
+    let mut runs = Vec::new();
+    for workflow_id in request.workflows {
+        let entry = queue::enqueue(
+            app_handle,
+            &workflow_id,
+            QueueParams {
+                monitor_index: request.monitor_index,
+                label: request.label.clone(),
+            },
+        )?;
+        runs.push(JobRun {
+            entry_id: entry.id,
+            workflow_id,
+            status: "queued",
+            error: None,
+        });
+    }
+    info!("Queued job {} with {} runs", id, runs.len());
+    Ok(state.agent_jobs.add(id, config_path, runs))
+}
+
+fn succeeded(response: commands::CommandResponse) -> Result<(), String> {
+    if response.success {
+        return Ok(());
+    }
+    Err(response
+        .message
+        .unwrap_or_else(|| "The command failed".to_string()))
+}
+
+/// Where configurations submitted inline are saved.
+fn jobs_dir() -> PathBuf {
+    profiles::data_dir().join("agent-jobs")
+}
+
+/// The last `lines` lines of the newest log file.
+pub fn tail_log(lines: usize) -> Result<Vec<String>, String> {
+    let dir = logging::log_dir();
+    let newest = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(LOG_FILE_PREFIX)
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .ok_or_else(|| "There's no log file yet".to_string())?;
+    let content = std::fs::read_to_string(newest.path())
+        .map_err(|e| format!("Failed to read the log: {}", e))?;
+    let all: Vec<&str> = content.lines().collect();
+    Ok(all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
@@ -0,0 +1,292 @@
+//! The images of the loaded configuration. Each configuration is checked
+//! once it's loaded — every image's data is found and its hash, taken while
+//! the configuration was validated, compared with the `sha256` the entry
+//! may carry — so missing, unreadable and duplicate images are known, and
+//! reported with an `asset-warnings` event, before a run trips over them. Decoded images and thumbnails are cached, so
+//! previews and screen searches don't decode the same image again; entries
+//! are keyed by the image's data, so an edited image is decoded afresh.
+
+use crate::capture::RgbImage;
+use crate::commands::AppState;
+use crate::config::images::{sniff_mime_type, ImageSource};
+use crate::config::QontinuiConfig;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Decoded pixels kept in memory before the least recently used images
+/// are dropped, in bytes.
+const MAX_DECODED_BYTES: usize = 128 * 1024 * 1024;
+const MAX_THUMBNAILS: usize = 512;
+pub const MIN_THUMBNAIL_SIZE: u32 = 16;
+pub const MAX_THUMBNAIL_SIZE: u32 = 1024;
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetProblem {
+    pub image_id: String,
+    pub name: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AssetReport {
+    pub images: usize,
+    /// Images without data, whose data can't be read, or whose data doesn't
+    /// match their `sha256`.
+    pub missing: Vec<AssetProblem>,
+    /// Groups of images with the same content.
+    pub duplicates: Vec<Vec<String>>,
+}
+
+impl AssetReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+/// Checks every image of `config`.
+pub fn check(config: &QontinuiConfig) -> AssetReport {
+    let mut missing = Vec::new();
+    let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+    for image in &config.images {
+        let error = if config.image_store.source(&image.id).is_none() {
+            Some("The image has no data".to_string())
+        } else {
+            match config.image_store.validated_digest(&image.id) {
+                Err(e) => Some(e),
+                Ok(digest) => {
+                    let expected = image.extra.get("sha256").and_then(|sha256| sha256.as_str());
+                    let mismatch = expected
+                        .filter(|expected| !expected.eq_ignore_ascii_case(&digest.sha256))
+                        .map(|expected| {
+                            format!("Content hash {} doesn't match {}", digest.sha256, expected)
+                        });
+                    by_hash
+                        .entry(digest.sha256)
+                        .or_default()
+                        .push(image.id.clone());
+                    mismatch
+                }
+            }
+        };
+        if let Some(error) = error {
+            missing.push(AssetProblem {
+                image_id: image.id.clone(),
+                name: image.name.clone(),
+                error,
+            });
+        }
+    }
+    let mut duplicates: Vec<Vec<String>> = by_hash
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    duplicates.sort();
+    AssetReport {
+        images: config.images.len(),
+        missing,
+        duplicates,
+    }
+}
+
+/// Identifies an image's data without reading it: the payload of an
+/// embedded image, the path and modification time of a file.
+fn fingerprint(config: &QontinuiConfig, id: &str) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    match config.image_store.source(id)? {
+        ImageSource::Embedded(data) => data.hash(&mut hasher),
+        ImageSource::File(path) => {
+            let path = config.image_store.resolve_path(path);
+            path.hash(&mut hasher);
+            std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .hash(&mut hasher);
+        }
+    }
+    Some(hasher.finish())
+}
+
+struct Decoded {
+    fingerprint: u64,
+    image: Arc<RgbImage>,
+}
+
+#[derive(Default)]
+struct Cache {
+    /// The configuration `report` is of.
+    checked: Option<(Weak<QontinuiConfig>, AssetReport)>,
+    decoded: HashMap<String, Decoded>,
+    decoded_bytes: usize,
+    /// Least recently used first.
+    recent: VecDeque<String>,
+    /// PNG thumbnails by image, data and size.
+    thumbnails: HashMap<(String, u64, u32), Arc<Vec<u8>>>,
+}
+
+impl Cache {
+    fn touch(&mut self, id: &str) {
+        self.recent.retain(|recent| recent != id);
+        self.recent.push_back(id.to_string());
+    }
+
+    fn insert(&mut self, id: &str, decoded: Decoded) {
+        self.remove(id);
+        self.decoded_bytes += pixel_bytes(&decoded.image);
+        self.decoded.insert(id.to_string(), decoded);
+        self.touch(id);
+        while self.decoded_bytes > MAX_DECODED_BYTES && self.recent.len() > 1 {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(old) = self.decoded.remove(id) {
+            self.decoded_bytes -= pixel_bytes(&old.image);
+        }
+        self.recent.retain(|recent| recent != id);
+    }
+}
+
+fn pixel_bytes(image: &RgbImage) -> usize {
+    image.pixels.len() * 3
+}
+
+/// Decoded images and thumbnails of the loaded configuration, and what its
+/// check found.
+#[derive(Default)]
+pub struct AssetCache {
+    cache: Mutex<Cache>,
+}
+
+impl AssetCache {
+    /// The check of `config`, done now unless it already was.
+    pub fn report(&self, config: &Arc<QontinuiConfig>) -> AssetReport {
+        if let Some((checked, report)) = &self.cache.lock().unwrap().checked {
+            if checked.as_ptr() == Arc::as_ptr(config) {
+                return report.clone();
+            }
+        }
+        let report = check(config);
+        self.cache.lock().unwrap().checked = Some((Arc::downgrade(config), report.clone()));
+        report
+    }
+
+    /// Image `id` of `config`, decoded.
+    pub fn decoded(&self, config: &QontinuiConfig, id: &str) -> Result<Arc<RgbImage>, String> {
+        let fingerprint =
+            fingerprint(config, id).ok_or_else(|| format!("Image not found: {}", id))?;
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(image) = cache
+                .decoded
+                .get(id)
+                .filter(|decoded| decoded.fingerprint == fingerprint)
+                .map(|decoded| decoded.image.clone())
+            {
+                cache.touch(id);
+                return Ok(image);
+            }
+        }
+
+        // Decoded without the lock, so other images can be served meanwhile
+        let bytes = config.image_store.load(id)?;
+        let image = match sniff_mime_type(&bytes) {
+            "image/png" => RgbImage::decode_png(&bytes),
+            other => Err(format!("Can't decode {} images, only PNG", other)),
+        }
+        .map(Arc::new)
+        .map_err(|e| format!("Image {}: {}", id, e))?;
+        self.cache.lock().unwrap().insert(
+            id,
+            Decoded {
+                fingerprint,
+                image: image.clone(),
+            },
+        );
+        Ok(image)
+    }
+
+    /// Image `id` scaled down so neither side is longer than `size`, as a
+    /// PNG. Images already that small are kept as they are.
+    pub fn thumbnail(
+        &self,
+        config: &QontinuiConfig,
+        id: &str,
+        size: u32,
+    ) -> Result<Arc<Vec<u8>>, String> {
+        if !(MIN_THUMBNAIL_SIZE..=MAX_THUMBNAIL_SIZE).contains(&size) {
+            return Err(format!(
+                "Thumbnail size {} is outside {} to {}",
+                size, MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE
+            ));
+        }
+        let fingerprint =
+            fingerprint(config, id).ok_or_else(|| format!("Image not found: {}", id))?;
+        let key = (id.to_string(), fingerprint, size);
+        if let Some(png) = self.cache.lock().unwrap().thumbnails.get(&key) {
+            return Ok(png.clone());
+        }
+
+        let image = self.decoded(config, id)?;
+        let factor = image.width.max(image.height).div_ceil(size);
+        let png = Arc::new(image.downscale(factor).encode_png()?);
+        let mut cache = self.cache.lock().unwrap();
+        if cache.thumbnails.len() >= MAX_THUMBNAILS {
+            cache.thumbnails.clear();
+        }
+        cache.thumbnails.insert(key, png.clone());
+        Ok(png)
+    }
+
+    /// Drops every decoded image and thumbnail.
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        let checked = cache.checked.take();
+        *cache = Cache {
+            checked,
+            ..Default::default()
+        };
+    }
+}
+
+/// A PNG as a `data:` URL.
+pub fn png_data_url(png: &[u8]) -> String {
+    format!("data:image/png;base64,{}", STANDARD.encode(png))
+}
+
+/// Checks a newly loaded configuration in the background, dropping what
+/// was cached for the previous one, and raises `asset-warnings` when images
+/// are missing, unreadable or duplicated.
+pub fn check_loaded(app_handle: &AppHandle, config: Arc<QontinuiConfig>) {
+    let app_handle = app_handle.clone();
+    thread::spawn(move || {
+        let state = app_handle.state::<AppState>();
+        state.assets.clear();
+        let report = state.assets.report(&config);
+        if report.is_clean() {
+            info!(
+                "All {} images of the configuration are readable",
+                report.images
+            );
+            return;
+        }
+        for problem in &report.missing {
+            warn!("Image {}: {}", problem.image_id, problem.error);
+        }
+        for group in &report.duplicates {
+            warn!("Images with the same content: {}", group.join(", "));
+        }
+        let _ = app_handle.emit("asset-warnings", &report);
+    });
+}
@@ -0,0 +1,212 @@
+//! In-process screen region capture through `xcap`, which talks to GDI on
+//! Windows, CoreGraphics on macOS and X11 on Linux, so previews and colour
+//! polling don't start a helper process for every frame.
+
+use crate::monitors::MonitorInfo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A rectangle in physical pixels, relative to whatever it is attached to
+/// (a monitor for capture requests, the captured region for OCR boxes).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 8-bit RGB pixels, row-major.
+#[derive(Debug, Clone)]
+pub struct RgbImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl RgbImage {
+    /// Decodes a PNG of any colour type, dropping alpha.
+    pub fn decode_png(bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| format!("Failed to decode PNG: {}", e))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .map_err(|e| format!("Failed to decode PNG: {}", e))?;
+
+        let channels = info.color_type.samples();
+        let pixels = buf[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|px| match px.len() {
+                1 | 2 => [px[0]; 3],
+                _ => [px[0], px[1], px[2]],
+            })
+            .collect();
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+}
+
+impl RgbImage {
+    pub fn encode_png(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let data: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        Ok(bytes)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        Self::decode_png(&bytes).map_err(|e| format!("{:?}: {}", path, e))
+    }
+
+    /// The part of the image inside `region`, clipped to the image. `None`
+    /// when nothing is left.
+    pub fn crop(&self, region: Region) -> Option<Self> {
+        let left = region.x.clamp(0, self.width as i32) as u32;
+        let top = region.y.clamp(0, self.height as i32) as u32;
+        let right = (region.x + region.width as i32).clamp(0, self.width as i32) as u32;
+        let bottom = (region.y + region.height as i32).clamp(0, self.height as i32) as u32;
+        if right <= left || bottom <= top {
+            return None;
+        }
+        let pixels = (top..bottom)
+            .flat_map(|y| {
+                let row = (y * self.width) as usize;
+                self.pixels[row + left as usize..row + right as usize]
+                    .iter()
+                    .copied()
+            })
+            .collect();
+        Some(Self {
+            width: right - left,
+            height: bottom - top,
+            pixels,
+        })
+    }
+
+    /// Box-filter downscale by an integer factor.
+    pub fn downscale(&self, factor: u32) -> Self {
+        if factor <= 1 {
+            return self.clone();
+        }
+        let width = (self.width / factor).max(1);
+        let height = (self.height / factor).max(1);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u32; 3];
+                let mut count = 0;
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        let (sx, sy) = (x * factor + dx, y * factor + dy);
+                        if sx < self.width && sy < self.height {
+                            let pixel = self.pixels[(sy * self.width + sx) as usize];
+                            for (total, channel) in sum.iter_mut().zip(pixel) {
+                                *total += channel as u32;
+                            }
+                            count += 1;
+                        }
+                    }
+                }
+                pixels.push(sum.map(|total| (total / count) as u8));
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// Captures `region` of `monitor` as PNG bytes.
+pub fn capture_region(monitor: &MonitorInfo, region: Region) -> Result<Vec<u8>, String> {
+    capture_image(monitor, region)?.encode_png()
+}
+
+/// Captures `region` of `monitor` without going through PNG.
+pub fn capture_image(monitor: &MonitorInfo, region: Region) -> Result<RgbImage, String> {
+    if region.width == 0 || region.height == 0 {
+        return Err("Capture region is empty".to_string());
+    }
+    if region.x < 0
+        || region.y < 0
+        || region.x as u64 + region.width as u64 > monitor.width as u64
+        || region.y as u64 + region.height as u64 > monitor.height as u64
+    {
+        return Err(format!(
+            "Capture region {:?} is outside monitor {} ({}x{})",
+            region, monitor.index, monitor.width, monitor.height
+        ));
+    }
+
+    let screen = native_monitor(monitor)?
+        .capture_image()
+        .map_err(|e| format!("Screen capture failed: {}", e))?;
+    let (width, height) = (screen.width(), screen.height());
+    let rgba = screen.as_raw();
+    let pixels = (region.y as u32..(region.y as u32 + region.height).min(height))
+        .flat_map(|y| {
+            let row = (y * width) as usize;
+            let left = row + region.x as usize;
+            let right = row + (region.x as u32 + region.width).min(width) as usize;
+            rgba[left * 4..right * 4]
+                .chunks_exact(4)
+                .map(|px| [px[0], px[1], px[2]])
+        })
+        .collect::<Vec<_>>();
+    if pixels.len() != (region.width * region.height) as usize {
+        return Err(format!(
+            "Monitor {} was captured at {}x{}, expected {}x{}",
+            monitor.index, width, height, monitor.width, monitor.height
+        ));
+    }
+    Ok(RgbImage {
+        width: region.width,
+        height: region.height,
+        pixels,
+    })
+}
+
+/// Captures the whole of `monitor` as PNG bytes.
+pub fn capture_monitor(monitor: &MonitorInfo) -> Result<Vec<u8>, String> {
+    capture_region(
+        monitor,
+        Region {
+            x: 0,
+            y: 0,
+            width: monitor.width,
+            height: monitor.height,
+        },
+    )
+}
+
+/// The `xcap` display at the same position as `monitor`. macOS reports
+/// positions in points, so the scaled position is accepted too.
+fn native_monitor(monitor: &MonitorInfo) -> Result<xcap::Monitor, String> {
+    let scaled = (
+        (monitor.x as f64 / monitor.scale_factor).round() as i32,
+        (monitor.y as f64 / monitor.scale_factor).round() as i32,
+    );
+    xcap::Monitor::all()
+        .map_err(|e| format!("Failed to list displays: {}", e))?
+        .into_iter()
+        .find(|candidate| match (candidate.x(), candidate.y()) {
+            (Ok(x), Ok(y)) => (x, y) == (monitor.x, monitor.y) || (x, y) == scaled,
+            _ => false,
+        })
+        .ok_or_else(|| format!("Monitor {} is no longer connected", monitor.index))
+}
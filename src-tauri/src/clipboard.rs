@@ -0,0 +1,255 @@
+//! Text clipboard access through the OS clipboard tools, with a short
+//! history and optional capture of clipboard changes while an automation
+//! runs.
+
+use crate::executor::protocol::BridgeMessage;
+use crate::executor::python_bridge::ExecutorEvent;
+use crate::executor::sessions::{self, DEFAULT_SESSION};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, EventId, Listener};
+use tracing::{info, warn};
+
+const MAX_HISTORY: usize = 50;
+const CAPTURE_INTERVAL: Duration = Duration::from_millis(750);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardSource {
+    Get,
+    Set,
+    /// Changed by the target application during an execution.
+    Execution,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardEntry {
+    pub text: String,
+    pub source: ClipboardSource,
+    pub timestamp: String,
+}
+
+/// Clipboard history and execution capture state, shared through
+/// `AppState`.
+#[derive(Clone, Default)]
+pub struct Clipboard {
+    history: Arc<Mutex<VecDeque<ClipboardEntry>>>,
+    listener: Arc<Mutex<Option<EventId>>>,
+    /// Set while an execution is being watched; cleared to stop the poller.
+    watching: Arc<AtomicBool>,
+}
+
+impl Clipboard {
+    pub fn get(&self) -> Result<String, String> {
+        let text = read()?;
+        self.record(&text, ClipboardSource::Get);
+        Ok(text)
+    }
+
+    pub fn set(&self, text: &str) -> Result<(), String> {
+        write(text)?;
+        self.record(text, ClipboardSource::Set);
+        Ok(())
+    }
+
+    /// Newest first.
+    pub fn history(&self) -> Vec<ClipboardEntry> {
+        self.history.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.listener.lock().unwrap().is_some()
+    }
+
+    /// Starts or stops watching executions. While enabled, clipboard
+    /// changes between `execution_started` and `execution_completed` are
+    /// recorded and emitted as `clipboard_changed` executor events.
+    pub fn set_capture(&self, app_handle: &AppHandle, enabled: bool) {
+        let mut listener = self.listener.lock().unwrap();
+        if let Some(id) = listener.take() {
+            app_handle.unlisten(id);
+            self.watching.store(false, Ordering::SeqCst);
+        }
+        if !enabled {
+            info!("Clipboard capture disabled");
+            return;
+        }
+
+        let clipboard = self.clone();
+        let handle = app_handle.clone();
+        *listener = Some(app_handle.listen("executor-event", move |event| {
+            let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else {
+                return;
+            };
+            if !sessions::is_default(&payload) {
+                return;
+            }
+            match payload.get("event").and_then(Value::as_str) {
+                Some("execution_started") => clipboard.watch(handle.clone()),
+                Some("execution_completed") => clipboard.watching.store(false, Ordering::SeqCst),
+                _ => {}
+            }
+        }));
+        info!("Clipboard capture enabled");
+    }
+
+    fn watch(&self, app_handle: AppHandle) {
+        if self.watching.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let clipboard = self.clone();
+        std::thread::spawn(move || {
+            // Whatever was on the clipboard before the run isn't captured
+            let mut last = read().ok();
+            while clipboard.watching.load(Ordering::SeqCst) {
+                std::thread::sleep(CAPTURE_INTERVAL);
+                let text = match read() {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Clipboard capture stopped: {}", e);
+                        break;
+                    }
+                };
+                if last.as_deref() == Some(text.as_str()) {
+                    continue;
+                }
+
+                clipboard.record(&text, ClipboardSource::Execution);
+                let event = BridgeMessage::Event(ExecutorEvent::from_runner(
+                    "clipboard_changed",
+                    json!({ "text": text, "source": "runner" }),
+                ));
+                let _ = sessions::emit(&app_handle, "executor-event", DEFAULT_SESSION, &event);
+                last = Some(text);
+            }
+            clipboard.watching.store(false, Ordering::SeqCst);
+        });
+    }
+
+    fn record(&self, text: &str, source: ClipboardSource) {
+        let mut history = self.history.lock().unwrap();
+        if history.len() >= MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(ClipboardEntry {
+            text: text.to_string(),
+            source,
+            timestamp: chrono::Local::now().to_rfc3339(),
+        });
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_command() -> Command {
+    let mut cmd = Command::new("powershell");
+    cmd.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "[Console]::OutputEncoding = [Text.Encoding]::UTF8; Get-Clipboard -Raw",
+    ]);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn write_command() -> Command {
+    let mut cmd = Command::new("powershell");
+    cmd.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "[Console]::InputEncoding = [Text.Encoding]::UTF8; Set-Clipboard -Value ([Console]::In.ReadToEnd())",
+    ]);
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn read_command() -> Command {
+    Command::new("pbpaste")
+}
+
+#[cfg(target_os = "macos")]
+fn write_command() -> Command {
+    Command::new("pbcopy")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn read_command() -> Command {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut cmd = Command::new("wl-paste");
+        cmd.arg("--no-newline");
+        cmd
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard", "-o"]);
+        cmd
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn write_command() -> Command {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-copy")
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard", "-i"]);
+        cmd
+    }
+}
+
+/// The clipboard's text. An empty or non-text clipboard reads as "".
+pub fn read() -> Result<String, String> {
+    let output = read_command()
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to read clipboard: {}", e))?;
+    if !output.status.success() {
+        // The tools exit non-zero when the clipboard holds no text
+        return Ok(String::new());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    // Get-Clipboard terminates its output with a newline of its own
+    #[cfg(target_os = "windows")]
+    let text = text
+        .strip_suffix("\r\n")
+        .map(str::to_string)
+        .unwrap_or(text);
+    Ok(text)
+}
+
+pub fn write(text: &str) -> Result<(), String> {
+    // xclip and wl-copy fork a process that keeps serving the selection, so
+    // only the exit status is awaited, never the output pipes
+    let mut child = write_command()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to write clipboard: {}", e))?;
+    child
+        .stdin
+        .take()
+        .ok_or("Failed to open clipboard tool stdin")?
+        .write_all(text.as_bytes())
+        .map_err(|e| format!("Failed to write clipboard: {}", e))?;
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to write clipboard: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to write clipboard: clipboard tool {}",
+            status
+        ))
+    }
+}
@@ -0,0 +1,78 @@
+//! Colour checks on captured pixels, for verifying UI states (status lights,
+//! highlighted rows) without image matching.
+
+use crate::capture::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    /// Parses `#rrggbb` or `rrggbb`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let hex = text.trim().trim_start_matches('#');
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        };
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Self { r, g, b }),
+            _ => Err(format!("Invalid colour '{}', expected #rrggbb", text)),
+        }
+    }
+
+    /// True when no channel differs by more than `tolerance`.
+    pub fn matches(&self, other: &Color, tolerance: u8) -> bool {
+        self.r.abs_diff(other.r) <= tolerance
+            && self.g.abs_diff(other.g) <= tolerance
+            && self.b.abs_diff(other.b) <= tolerance
+    }
+}
+
+impl From<[u8; 3]> for Color {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColorSample {
+    /// Share of pixels within tolerance of the target, 0 to 1.
+    pub fraction: f32,
+    pub average: String,
+}
+
+/// How much of `image` is within `tolerance` of `target`, plus its mean
+/// colour for diagnostics.
+pub fn sample(image: &RgbImage, target: &Color, tolerance: u8) -> ColorSample {
+    let total = image.pixels.len().max(1);
+    let matching = image
+        .pixels
+        .iter()
+        .filter(|px| Color::from(**px).matches(target, tolerance))
+        .count();
+
+    let mut sums = [0u64; 3];
+    for px in &image.pixels {
+        for (sum, value) in sums.iter_mut().zip(px) {
+            *sum += *value as u64;
+        }
+    }
+    let average = Color::from(sums.map(|sum| (sum / total as u64) as u8));
+
+    ColorSample {
+        fraction: matching as f32 / total as f32,
+        average: average.to_string(),
+    }
+}
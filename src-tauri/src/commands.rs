@@ -1,15 +1,128 @@
+pub mod logs;
+
+use crate::accessibility::{self, ElementQuery};
+use crate::assets::{self, AssetCache};
+use crate::capture::{self, Region};
+use crate::clipboard::Clipboard;
+use crate::color::{self, Color};
+use crate::config::editor::{self, EditState, EntryChange, Section};
+use crate::config::fixtures::{self, FixtureSpec};
+use crate::config::loader::ConfigFormat;
+use crate::config::validation::Severity;
+use crate::config::{diff, draft, dry_run, migration};
 use crate::config::{ConfigLoader, QontinuiConfig};
 use crate::error::{AppError, UserFacingError};
-use crate::executor::PythonBridge;
+use crate::executor::backend::{ExecutorBackend, Subscriber};
+use crate::executor::backpressure::OverflowPolicy;
+use crate::executor::environment::ExecutorLaunchOptions;
+use crate::executor::execution_request::{self, ExecutionRequest};
+use crate::executor::frames::FrameStore;
+use crate::executor::input_limits::InputLimits;
+use crate::executor::pipeline::EventPipeline;
+use crate::executor::python_bridge::{PendingCommand, DEFAULT_RESPONSE_TIMEOUT};
+use crate::executor::remote_bridge::{RemoteExecutor, RemoteTarget};
+use crate::executor::restart::{RestartSettings, Restarts};
+use crate::executor::run_report::{ReportFormat, RunReport};
+use crate::executor::run_stream::{ExportFormat, RunStreams};
+use crate::executor::safe_mode::{RunBounds, SafeMode};
+use crate::executor::sessions::{
+    self, ExecutorSession, ExecutorSessions, SessionInfo, DEFAULT_SESSION,
+};
+use crate::executor::shutdown::{ShutdownOutcome, ShutdownSettings};
+use crate::executor::state_machine::{
+    self, ActiveStates, RebuiltStates, StateGraph, TransitionHistory,
+};
+use crate::executor::throttle::ThrottleConfig;
+use crate::executor::timeline::{self, EventTimeline};
+use crate::executor::transport::TransportKind;
+use crate::executor::{
+    environment, interpreters, provision, replay, selftest, watchdog, ConfigTransferMode,
+    PythonBridge,
+};
+use crate::harness;
+use crate::hotkeys;
+use crate::http_api::HttpApi;
+use crate::i18n::Translations;
+use crate::idle::{self, IdleMonitor, IdlePolicy};
+use crate::matching::{self, GrayImage, Match, MatchTest};
+use crate::monitors;
+use crate::notifications::RunNotifier;
+use crate::ocr;
+use crate::preview::{Preview, PreviewSettings};
+use crate::profiles::{ProfileSettings, Profiles};
+use crate::progress::{ExecutionProgress, ProgressTracker};
+use crate::queue::{self, ExecutionQueue, QueueParams};
+use crate::recordings::{RecordingEntry, RecordingKind, Recordings};
+use crate::region_select;
+use crate::replay::Replay;
+use crate::resources::{self, ResourceLimits};
+use crate::run_plan::{QueuedRun, RunPlan, RunTracker};
+use crate::scheduler::Scheduler;
+use crate::secrets;
+use crate::session::{SessionRun, SessionStore};
+use crate::settings::{HotkeySettings, SettingsStore, WebhookEvent};
+use crate::startup;
+use crate::tasks::TaskRunner;
+use crate::variables::{VariableScope, VariableStore};
+use crate::video::{VideoRecorder, VideoSettings};
+use crate::visual_diff;
+use crate::watcher::ConfigWatcher;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::process::Command;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tracing::{error, info, warn};
 
 pub struct AppState {
-    pub python_bridge: Mutex<Option<PythonBridge>>,
-    pub current_config: Mutex<Option<QontinuiConfig>>,
+    /// The default executor session's backend.
+    pub executor: Mutex<Option<Box<dyn ExecutorBackend>>>,
+    pub executor_sessions: ExecutorSessions,
+    pub current_config: Mutex<Option<Arc<QontinuiConfig>>>,
+    pub config_source_bytes: Mutex<u64>,
+    pub config_edits: Mutex<EditState>,
+    pub resource_limits: Mutex<ResourceLimits>,
+    pub input_limits: Mutex<InputLimits>,
+    pub safe_mode: Mutex<SafeMode>,
+    /// Bounds of each session's run when safe mode is on, resolved at start.
+    pub safe_bounds: RunBounds,
+    pub restarts: Restarts,
+    /// How long a stopped executor gets before it's terminated and killed.
+    pub shutdown: Mutex<ShutdownSettings>,
+    /// Transport executors are offered when they start.
+    pub executor_transport: Mutex<TransportKind>,
+    /// How executor processes are launched.
+    pub executor_launch: Mutex<ExecutorLaunchOptions>,
+    /// The executor on another machine the `"remote"` executor type
+    /// connects to.
+    pub remote_executor: RemoteExecutor,
+    /// Images executors sent over their frame channel.
+    pub frames: FrameStore,
+    pub idle_policy: Mutex<IdlePolicy>,
+    pub idle_monitor: IdleMonitor,
+    pub run_tracker: RunTracker,
+    pub progress: ProgressTracker,
+    pub execution_queue: ExecutionQueue,
+    /// Jobs submitted to the HTTP API, mostly by agents' clients.
+    pub agent_jobs: crate::agent::Jobs,
+    pub scheduler: Scheduler,
+    pub http_api: HttpApi,
+    pub run_notifier: RunNotifier,
+    pub profiles: Profiles,
+    pub tasks: TaskRunner,
+    pub clipboard: Clipboard,
+    pub preview: Preview,
+    pub replay: Replay,
+    pub video_recorder: VideoRecorder,
+    pub recordings: Recordings,
+    pub variables: VariableStore,
+    pub run_streams: RunStreams,
+    pub event_timeline: EventTimeline,
+    pub translations: Translations,
+    pub session: SessionStore,
+    pub config_watcher: ConfigWatcher,
+    pub assets: AssetCache,
+    pub settings: SettingsStore,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,8 +133,42 @@ pub struct CommandResponse {
 }
 
 #[tauri::command]
-pub fn load_configuration(path: String, state: State<AppState>) -> Result<CommandResponse, String> {
+pub fn load_configuration(
+    path: String,
+    transfer_mode: Option<String>,
+    confirm_large: Option<bool>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
     info!("Loading configuration from: {}", path);
+    let transfer_mode = transfer_mode
+        .as_deref()
+        .map(ConfigTransferMode::parse)
+        .transpose()?
+        .unwrap_or_default();
+
+    // Refuse oversized configurations unless the user has confirmed
+    let source_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    let max_bytes = state.resource_limits.lock().unwrap().max_config_bytes();
+    if source_bytes > max_bytes && !confirm_large.unwrap_or(false) {
+        warn!(
+            "Configuration {} is {} bytes, above the {} byte limit",
+            path, source_bytes, max_bytes
+        );
+        return Ok(CommandResponse {
+            success: false,
+            message: Some(format!(
+                "Configuration is {} MB, above the {} MB limit. Confirm to load it anyway.",
+                source_bytes / (1024 * 1024),
+                max_bytes / (1024 * 1024)
+            )),
+            data: Some(serde_json::json!({
+                "requires_confirmation": true,
+                "size_bytes": source_bytes,
+                "limit_bytes": max_bytes,
+            })),
+        });
+    }
 
     // Load the configuration file
     let config = ConfigLoader::load_from_file(&path)
@@ -31,24 +178,68 @@ pub fn load_configuration(path: String, state: State<AppState>) -> Result<Comman
         })
         .map_err(|e| e.to_string())?;
 
+    // Python reads the file itself in path mode, which only works when the
+    // file holds exactly what was loaded
+    let transfer_mode = match transfer_mode {
+        ConfigTransferMode::Path if !config.readable_as_is() => {
+            info!("Sending configuration by content, as the file can't be read as is");
+            ConfigTransferMode::Content
+        }
+        mode => mode,
+    };
+
     let summary = config.summary();
 
-    // Create data object with configuration info
+    // Only send counts and workflow headers back; full sections are fetched
+    // on demand through get_config_section so large image payloads aren't
+    // duplicated in the response.
     let config_data = serde_json::json!({
-        "workflows": config.workflows.clone(),
-        "states": config.states.clone(),
-        "transitions": config.transitions.clone(),
-        "images": config.images.clone()
+        "name": config.metadata.name,
+        "version": config.version,
+        "migrated_from": config.migrated_from,
+        "counts": {
+            "workflows": config.workflows.len(),
+            "states": config.states.len(),
+            "transitions": config.transitions.len(),
+            "images": config.images.len(),
+            "categories": config.categories.len(),
+        },
+        "workflows": config.workflow_summaries(),
+        "sections": QontinuiConfig::SECTIONS,
     });
 
-    // Store the configuration
-    *state.current_config.lock().unwrap() = Some(config);
+    let config = install_configuration(&state, &path, config, source_bytes);
     info!("Configuration loaded successfully: {}", summary);
+    assets::check_loaded(&app_handle, config.clone());
+    let name = Some(config.metadata.name.clone());
+    if let Err(e) = state
+        .settings
+        .add_recent_configuration(&path, name, None, None)
+    {
+        warn!("Failed to remember recent configuration: {}", e);
+    }
 
     // If Python bridge is running, send the configuration
-    if let Some(ref mut bridge) = *state.python_bridge.lock().unwrap() {
+    if let Some(ref mut bridge) = *state.executor.lock().unwrap() {
         if bridge.is_running() {
-            bridge.load_configuration(&path).map_err(|e| {
+            let transfer_mode = match transfer_mode {
+                ConfigTransferMode::Path if bridge.is_remote() => {
+                    info!("Sending configuration by content to the remote executor");
+                    ConfigTransferMode::Content
+                }
+                mode => mode,
+            };
+            let sent = match transfer_mode {
+                ConfigTransferMode::Path => bridge.load_configuration(&path),
+                ConfigTransferMode::Content | ConfigTransferMode::Compressed => bridge
+                    .sync_configuration(&config, transfer_mode == ConfigTransferMode::Compressed)
+                    .map(|delta| {
+                        if delta {
+                            info!("Sent configuration changes as a delta");
+                        }
+                    }),
+            };
+            sent.map_err(|e| {
                 error!("Failed to send configuration to Python: {}", e);
                 format!("Failed to send configuration to Python: {}", e)
             })?;
@@ -63,22 +254,103 @@ pub fn load_configuration(path: String, state: State<AppState>) -> Result<Comman
     })
 }
 
+/// Reloads the configuration at `path` whenever the file changes, or stops
+/// watching it when `enabled` is false. Each reload, or the reason a change
+/// wasn't picked up, is reported with a `config-reloaded` event.
+#[tauri::command]
+pub fn watch_configuration(
+    path: String,
+    enabled: bool,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let message = if enabled {
+        state
+            .config_watcher
+            .watch(app_handle, path.clone().into())?;
+        format!("Watching {} for changes", path)
+    } else if state.config_watcher.unwatch(std::path::Path::new(&path)) {
+        format!("Stopped watching {}", path)
+    } else {
+        format!("{} wasn't being watched", path)
+    };
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(message),
+        data: None,
+    })
+}
+
+/// Upgrades the configuration at `path` to the current schema and writes it
+/// to `output_path`, listing every change made. Current configurations are
+/// written back unchanged.
+#[tauri::command]
+pub fn migrate_configuration(path: String, output_path: String) -> Result<CommandResponse, String> {
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read configuration file: {}", e))?;
+    let mut value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse JSON configuration: {}", e))?;
+
+    let report = migration::migrate(&mut value)?;
+    let migrated = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+    std::fs::write(&output_path, migrated)
+        .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+    info!(
+        "Migrated {} from v{} to v{} ({} changes) into {}",
+        path,
+        report.from_version,
+        report.to_version,
+        report.changes.len(),
+        output_path
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(if report.migrated() {
+            format!(
+                "Migrated from version {} to {}",
+                report.from_version, report.to_version
+            )
+        } else {
+            format!("Already at version {}", report.to_version)
+        }),
+        data: Some(serde_json::json!(report)),
+    })
+}
+
 #[tauri::command]
 pub fn start_python_executor(
     app_handle: tauri::AppHandle,
     state: State<AppState>,
 ) -> Result<CommandResponse, String> {
-    start_python_executor_with_type(app_handle, state, "simple".to_string())
+    start_python_executor_with_type(app_handle, state, "simple".to_string(), None)
 }
 
+/// Starts the executor of `executor_type`, launched with `launch_options`
+/// instead of the profile's when given.
 #[tauri::command]
 pub fn start_python_executor_with_type(
     app_handle: tauri::AppHandle,
     state: State<AppState>,
     executor_type: String,
+    launch_options: Option<ExecutorLaunchOptions>,
+) -> Result<CommandResponse, String> {
+    start_python_executor_subscribed(app_handle, state, executor_type, launch_options, None)
+}
+
+/// Starts the executor like `start_python_executor_with_type`, handing
+/// `subscriber` everything it writes from the first message on.
+pub fn start_python_executor_subscribed(
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+    executor_type: String,
+    launch_options: Option<ExecutorLaunchOptions>,
+    subscriber: Option<Subscriber>,
 ) -> Result<CommandResponse, String> {
     info!("Starting Python executor with type: {}", executor_type);
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+    let mut bridge_lock = state.executor.lock().unwrap();
 
     // Check if already running
     if let Some(ref bridge) = *bridge_lock {
@@ -94,12 +366,22 @@ pub fn start_python_executor_with_type(
 
     // Create and start new bridge with specified executor type
     let mut bridge = PythonBridge::new(app_handle);
-    bridge.start_with_executor(&executor_type).map_err(|e| {
+    if let Some(options) = launch_options {
+        options.validate()?;
+        bridge = bridge.with_launch_options(options);
+    }
+    if let Some(subscriber) = subscriber {
+        bridge.subscribe(subscriber);
+    }
+    bridge.start(&executor_type).map_err(|e| {
         error!("Failed to start Python executor: {}", e);
         format!("Failed to start Python executor: {}", e)
     })?;
 
-    *bridge_lock = Some(bridge);
+    *bridge_lock = Some(Box::new(bridge));
+    state
+        .session
+        .update(|session| session.executor_type = Some(executor_type.clone()));
     info!(
         "Python executor started successfully in {} mode",
         executor_type
@@ -107,179 +389,3719 @@ pub fn start_python_executor_with_type(
 
     Ok(CommandResponse {
         success: true,
-        message: Some(format!(
-            "Python executor started with {} mode",
-            executor_type
-        )),
-        data: None,
+        message: Some(format!(
+            "Python executor started with {} mode",
+            executor_type
+        )),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn refresh_python_environment() -> Result<CommandResponse, String> {
+    info!("Refreshing cached Python environment");
+    environment::invalidate_cache();
+    environment::prewarm();
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Python environment will be re-detected".to_string()),
+        data: None,
+    })
+}
+
+/// Sets up the runner's own Python environment in the background, for
+/// machines without Poetry or a venv. Progress is reported through the
+/// background task events.
+#[tauri::command]
+pub fn provision_python_environment(
+    force: Option<bool>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let force = force.unwrap_or(false);
+    info!("Provisioning Python environment (force: {})", force);
+    let task_id = state.tasks.spawn(
+        app_handle,
+        "python_provision",
+        "Set up Python environment",
+        move |task| {
+            let provisioned = provision::provision(task, force)?;
+            serde_json::to_value(provisioned).map_err(|e| e.to_string())
+        },
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Python environment setup started".to_string()),
+        data: Some(serde_json::json!({ "task_id": task_id })),
+    })
+}
+
+/// Python interpreters found on PATH, in pyenv, Poetry and conda
+/// environments and common install locations, with their versions and
+/// whether they can import qontinui.
+#[tauri::command]
+pub async fn list_python_interpreters() -> Result<CommandResponse, String> {
+    let interpreters = tauri::async_runtime::spawn_blocking(interpreters::discover)
+        .await
+        .map_err(|e| format!("Failed to list Python interpreters: {}", e))?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "interpreters": interpreters,
+            "selected": environment::interpreter(),
+        })),
+    })
+}
+
+/// Runs bridge scripts with the interpreter at `path` from the next
+/// executor start, saved with the active profile. `None` goes back to
+/// detecting one.
+#[tauri::command]
+pub async fn set_python_interpreter(
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    let Some(path) = path else {
+        info!("Detecting the Python interpreter again");
+        environment::set_interpreter(None);
+        save_profile_settings(&state)?;
+        return Ok(CommandResponse {
+            success: true,
+            message: Some("Python interpreter will be detected".to_string()),
+            data: None,
+        });
+    };
+
+    let python = std::path::PathBuf::from(&path);
+    let probed = tauri::async_runtime::spawn_blocking(move || {
+        interpreters::probe(python, interpreters::InterpreterSource::Path)
+    })
+    .await
+    .map_err(|e| format!("Failed to check {}: {}", path, e))?;
+    let Some(version) = probed.version.clone() else {
+        return Err(format!("{} is not a working Python interpreter", path));
+    };
+    if !probed.qontinui_importable {
+        warn!("Selected interpreter {} can't import qontinui", path);
+    }
+
+    info!("Using Python {} at {}", version, path);
+    environment::set_interpreter(Some(probed.path.clone()));
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Using Python {} at {}", version, path)),
+        data: Some(serde_json::json!(probed)),
+    })
+}
+
+#[tauri::command]
+pub fn get_executor_launch_options(
+    state: State<AppState>,
+) -> Result<ExecutorLaunchOptions, String> {
+    Ok(state.executor_launch.lock().unwrap().clone())
+}
+
+/// Sets the environment variables, working directory, interpreter
+/// arguments and `PYTHONPATH` entries executors are launched with from the
+/// next start, saved with the active profile.
+#[tauri::command]
+pub fn set_executor_launch_options(
+    options: ExecutorLaunchOptions,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    options.validate()?;
+    info!("Updating executor launch options: {:?}", options);
+    *state.executor_launch.lock().unwrap() = options;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Takes effect when the executor next starts".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn stop_python_executor(state: State<AppState>) -> Result<CommandResponse, String> {
+    info!("Stopping Python executor");
+    let mut bridge_lock = state.executor.lock().unwrap();
+
+    let mut outcome = ShutdownOutcome::NotRunning;
+    if let Some(ref mut bridge) = *bridge_lock {
+        outcome = bridge.stop().map_err(|e| {
+            error!("Failed to stop Python executor: {}", e);
+            format!("Failed to stop Python executor: {}", e)
+        })?;
+        info!("Python executor stopped ({:?})", outcome);
+    }
+
+    *bridge_lock = None;
+    state.session.update(|session| {
+        session.executor_type = None;
+        session.active = None;
+    });
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Python executor stopped".to_string()),
+        data: Some(serde_json::json!({
+            "graceful": outcome.is_graceful(),
+            "outcome": outcome,
+        })),
+    })
+}
+
+/// The panic button: kills the executor and everything it started at
+/// once, without the graceful stop, and stops the execution queue. The
+/// run ends as failed and the executor has to be started again.
+#[tauri::command]
+pub fn abort_execution(
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    warn!("Aborting execution");
+    // Taken out first so nothing waits on the lock while it's killed
+    let bridge = state.executor.lock().unwrap().take();
+    if let Some(mut bridge) = bridge {
+        bridge.force_kill().map_err(|e| {
+            error!("Failed to kill Python executor: {}", e);
+            format!("Failed to kill Python executor: {}", e)
+        })?;
+    }
+    state.session.update(|session| {
+        session.executor_type = None;
+        session.active = None;
+    });
+    for task in state.tasks.list() {
+        if task.kind == "execution_queue" && !task.status.is_finished() {
+            let _ = state.tasks.cancel(&task.id);
+        }
+    }
+    watchdog::end_run(&app_handle, DEFAULT_SESSION, "Execution aborted");
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Execution aborted".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn start_execution(
+    process_id: Option<String>,
+    monitor_index: Option<i32>,
+    request: Option<ExecutionRequest>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let request = request.unwrap_or_default();
+    request.validate()?;
+    begin_execution(&state, process_id, monitor_index, &request, None)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Execution started".to_string()),
+        data: None,
+    })
+}
+
+/// Sends `start` with the stored variables, input limits, safe mode bounds
+/// and the run's `request`. With `resume_after_action` the workflow skips
+/// ahead to the action after it.
+pub(crate) fn begin_execution(
+    state: &AppState,
+    process_id: Option<String>,
+    monitor_index: Option<i32>,
+    request: &ExecutionRequest,
+    resume_after_action: Option<String>,
+) -> Result<(), String> {
+    let safe_bounds = resolve_safe_bounds(state)?;
+
+    let mut bridge_lock = state.executor.lock().unwrap();
+
+    if let Some(ref mut bridge) = *bridge_lock {
+        if !bridge.is_running() {
+            return Err("Python executor not running".to_string());
+        }
+        if safe_bounds.is_some() {
+            bridge.handshake().allows_safe_mode()?;
+        }
+
+        // Add workflow_id (required)
+        let Some(pid) = process_id else {
+            return Err("Workflow ID is required".to_string());
+        };
+        let config_name = current_config_name(state);
+        let mut params = execution_params(
+            state,
+            DEFAULT_SESSION,
+            config_name.as_deref(),
+            &pid,
+            monitor_index,
+            safe_bounds,
+        );
+        request.insert_into(&mut params);
+        if let Some(config_path) = state.session.current().config_path {
+            let recorded = state.settings.add_recent_configuration(
+                &config_path,
+                None,
+                Some(pid.clone()),
+                monitor_index,
+            );
+            if let Err(e) = recorded {
+                warn!("Failed to remember the workflow run: {}", e);
+            }
+        }
+        state.session.update(|session| {
+            session.workflow_id = Some(pid);
+            session.monitor_index = monitor_index;
+            session.execution_request = Some(request.clone());
+        });
+
+        if let Some(action_id) = resume_after_action {
+            params.insert(
+                "resume_after_action".to_string(),
+                serde_json::json!(action_id),
+            );
+        }
+
+        bridge
+            .start_execution_with_params(Some(serde_json::Value::Object(params)))
+            .map_err(|e| format!("Failed to start execution: {}", e))
+    } else {
+        Err("Python executor not initialized".to_string())
+    }
+}
+
+/// Resolved on every start so a window that moved since the last run is
+/// followed. Looking a window up can take a while, so call this before
+/// taking the bridge lock.
+fn resolve_safe_bounds(state: &AppState) -> Result<Option<Region>, String> {
+    let safe_mode = state.safe_mode.lock().unwrap().clone();
+    safe_mode.resolve().map_err(|e| format!("Safe mode: {}", e))
+}
+
+/// The `start` params every session sends: monitor, workflow, the stored
+/// variables for `config_name`, input limits and safe mode bounds.
+fn execution_params(
+    state: &AppState,
+    session_id: &str,
+    config_name: Option<&str>,
+    workflow_id: &str,
+    monitor_index: Option<i32>,
+    safe_bounds: Option<Region>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut params = serde_json::Map::new();
+
+    // Add monitor index (default to 0 if not provided)
+    params.insert(
+        "monitor_index".to_string(),
+        serde_json::json!(monitor_index.unwrap_or(0)),
+    );
+    params.insert("workflow_id".to_string(), serde_json::json!(workflow_id));
+
+    // Stored variables, so this run can pick up where the last one left off
+    params.insert(
+        "variables".to_string(),
+        state.variables.for_execution(config_name),
+    );
+
+    // The executor paces its input to these; the runner stops runs that don't
+    params.insert(
+        "input_limits".to_string(),
+        serde_json::json!(*state.input_limits.lock().unwrap()),
+    );
+
+    // The executor refuses pointer input outside these; the runner
+    // stops runs that report any
+    params.insert("safe_bounds".to_string(), serde_json::json!(safe_bounds));
+    state.safe_bounds.set(session_id, safe_bounds);
+
+    params
+}
+
+/// Queues a run that starts once the machine has been idle for the idle
+/// policy's minimum and pauses while the user is active. Returns the
+/// background task id; cancelling the task stops the run.
+#[tauri::command]
+pub fn start_execution_when_idle(
+    process_id: Option<String>,
+    monitor_index: Option<i32>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let workflow_id = process_id.ok_or("Workflow ID is required")?;
+    let policy = state.idle_policy.lock().unwrap().clone();
+    // Fail now rather than after the wait if idle time can't be read
+    idle::idle_time()?;
+
+    let handle = app_handle.clone();
+    let label = format!("Run {} when idle", workflow_id);
+    let queued_label = label.clone();
+    let task_id = state
+        .tasks
+        .spawn(app_handle, "idle_run", &label, move |task| {
+            let tracker = handle.state::<AppState>().run_tracker.clone();
+            let session = handle.state::<AppState>().session.clone();
+            session.update(|session| {
+                session.queued.push(SessionRun {
+                    task_id: task.id().to_string(),
+                    workflow_id: workflow_id.clone(),
+                    monitor_index,
+                })
+            });
+            tracker.enqueue(QueuedRun {
+                task_id: task.id().to_string(),
+                workflow_id: workflow_id.clone(),
+                label: queued_label,
+                status: None,
+                ready_at: None,
+            });
+
+            let result = idle::run_when_idle(
+                &handle,
+                task,
+                &policy,
+                |reason, remaining| {
+                    let ready_at = chrono::Duration::from_std(remaining)
+                        .ok()
+                        .map(|d| chrono::Local::now() + d);
+                    tracker.update_queued(task.id(), reason.to_string(), ready_at);
+                },
+                || {
+                    tracker.dequeue(task.id());
+                    session.update(|session| session.queued.retain(|run| run.task_id != task.id()));
+                    begin_execution(
+                        &handle.state::<AppState>(),
+                        Some(workflow_id),
+                        monitor_index,
+                        &ExecutionRequest::default(),
+                        None,
+                    )
+                },
+            );
+            tracker.dequeue(task.id());
+            session.update(|session| session.queued.retain(|run| run.task_id != task.id()));
+            result
+        });
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Run queued until the machine is idle".to_string()),
+        data: Some(serde_json::json!({ "task_id": task_id })),
+    })
+}
+
+/// The last session, if the runner didn't shut down cleanly, so the UI can
+/// offer to restore it.
+#[tauri::command]
+pub fn get_previous_session(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state.session.previous())),
+    })
+}
+
+#[tauri::command]
+pub fn discard_previous_session(state: State<AppState>) -> Result<CommandResponse, String> {
+    state.session.take_previous();
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Previous session discarded".to_string()),
+        data: None,
+    })
+}
+
+/// Restores the session that ended uncleanly: restarts its executor,
+/// reloads its configuration and requeues its queued runs. With
+/// `resume_execution` the interrupted execution is started again after its
+/// last completed action. A failed step doesn't stop the others; each is
+/// reported.
+#[tauri::command]
+pub fn restore_previous_session(
+    resume_execution: Option<bool>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let previous = state
+        .session
+        .take_previous()
+        .ok_or("No previous session to restore")?;
+    info!("Restoring session from {}", previous.started_at);
+
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    let mut note = |step: &str, result: Result<CommandResponse, String>| match result {
+        Ok(response) if response.success => restored.push(step.to_string()),
+        Ok(response) => failed.push(serde_json::json!({ "step": step, "error": response.message })),
+        Err(e) => failed.push(serde_json::json!({ "step": step, "error": e })),
+    };
+
+    // The executor first, so the configuration is sent to it on load
+    if let Some(executor_type) = previous.executor_type.clone() {
+        note(
+            "executor",
+            start_python_executor_with_type(app_handle.clone(), state.clone(), executor_type, None),
+        );
+    }
+    if let Some(path) = previous.config_path.clone() {
+        note(
+            "configuration",
+            load_configuration(path, None, Some(true), app_handle.clone(), state.clone()),
+        );
+    }
+    state.session.update(|session| {
+        session.workflow_id = previous.workflow_id.clone();
+        session.monitor_index = previous.monitor_index;
+        session.execution_request = previous.execution_request.clone();
+    });
+
+    if let Some(checkpoint) = previous
+        .active
+        .clone()
+        .filter(|_| resume_execution.unwrap_or(false))
+    {
+        let resumed = begin_execution(
+            &state,
+            Some(checkpoint.workflow_id.clone()),
+            checkpoint.monitor_index,
+            &previous.execution_request.clone().unwrap_or_default(),
+            checkpoint.last_completed_action.clone(),
+        )
+        .map(|_| CommandResponse {
+            success: true,
+            message: None,
+            data: None,
+        });
+        note(&format!("execution {}", checkpoint.workflow_id), resumed);
+    }
+    for run in &previous.queued {
+        note(
+            &format!("queued run {}", run.workflow_id),
+            start_execution_when_idle(
+                Some(run.workflow_id.clone()),
+                run.monitor_index,
+                app_handle.clone(),
+                state.clone(),
+            ),
+        );
+    }
+
+    if !failed.is_empty() {
+        warn!("Session restored with {} failed steps", failed.len());
+    }
+    Ok(CommandResponse {
+        success: failed.is_empty(),
+        message: Some(format!(
+            "Restored {} of {} session steps",
+            restored.len(),
+            restored.len() + failed.len()
+        )),
+        data: Some(serde_json::json!({
+            "restored": restored,
+            "failed": failed,
+            "session": previous,
+        })),
+    })
+}
+
+/// The active execution, queued runs and scheduled runs with estimated
+/// start and finish times, for a "what happens next" timeline.
+#[tauri::command]
+pub fn get_run_plan(state: State<AppState>) -> Result<RunPlan, String> {
+    let now = chrono::Local::now();
+    let mut plan = state.run_tracker.plan(now);
+    plan.scheduled = state.scheduler.planned(&state.run_tracker, now);
+    Ok(plan)
+}
+
+/// Actions and transitions done and left in the latest execution, with
+/// percent complete and an estimate of the time left. `None` before the
+/// first execution; `execution-progress` events carry the same while one
+/// runs.
+#[tauri::command]
+pub fn get_execution_progress(state: State<AppState>) -> Result<Option<ExecutionProgress>, String> {
+    Ok(state.progress.progress(std::time::Instant::now()))
+}
+
+/// Registers a recurring run of `process_id` from the configuration at
+/// `config_path`. `cron_expr` has the usual five fields in local time.
+#[tauri::command]
+pub fn add_schedule(
+    cron_expr: String,
+    process_id: String,
+    config_path: String,
+    executor_type: Option<String>,
+    monitor_index: Option<i32>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let schedule = state.scheduler.add(
+        &cron_expr,
+        &process_id,
+        &config_path,
+        executor_type,
+        monitor_index,
+    )?;
+    info!("Scheduled {} at '{}'", process_id, cron_expr);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("{} scheduled", process_id)),
+        data: Some(serde_json::json!(schedule)),
+    })
+}
+
+/// Every schedule with its next run time.
+#[tauri::command]
+pub fn list_schedules(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state
+            .scheduler
+            .list(chrono::Local::now()))),
+    })
+}
+
+/// Removes a schedule. A run it already started carries on.
+#[tauri::command]
+pub fn remove_schedule(
+    schedule_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let schedule = state.scheduler.remove(&schedule_id)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Schedule for {} removed", schedule.process_id)),
+        data: Some(serde_json::json!(schedule)),
+    })
+}
+
+/// The latest scheduled runs and their outcomes, newest first (50 by
+/// default).
+#[tauri::command]
+pub fn get_execution_history(
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state
+            .scheduler
+            .history(limit.unwrap_or(50)))),
+    })
+}
+
+/// Adds a workflow to the execution queue, which runs its entries one after
+/// another on the default executor. `params` takes `monitor_index` and a
+/// `label`.
+#[tauri::command]
+pub fn enqueue_execution(
+    process_id: String,
+    params: Option<QueueParams>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    let entry = queue::enqueue(&app_handle, &process_id, params.unwrap_or_default())?;
+    info!("Queued run of {} as {}", process_id, entry.id);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("{} queued", entry.label)),
+        data: Some(serde_json::json!(entry)),
+    })
+}
+
+#[tauri::command]
+pub fn get_queue(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state.execution_queue.entries())),
+    })
+}
+
+/// Reorders the waiting entries; `entry_ids` lists all of them in the new
+/// order.
+#[tauri::command]
+pub fn reorder_queue(
+    entry_ids: Vec<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let entries = state.execution_queue.reorder(&entry_ids)?;
+    state.run_tracker.reorder_queued(&entry_ids);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Queue reordered".to_string()),
+        data: Some(serde_json::json!(entries)),
+    })
+}
+
+/// Removes a waiting entry from the queue, or stops the running one.
+#[tauri::command]
+pub fn cancel_queued(entry_id: String, app_handle: AppHandle) -> Result<CommandResponse, String> {
+    let entry = queue::cancel(&app_handle, &entry_id)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("{} cancelled", entry.label)),
+        data: Some(serde_json::json!(entry)),
+    })
+}
+
+/// The local HTTP API's settings, including its token, and the port it's
+/// listening on if it's running.
+#[tauri::command]
+pub fn get_http_api_settings(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "settings": state.settings.http_api(),
+            "listening_port": state.http_api.port(),
+        })),
+    })
+}
+
+/// Turns the local HTTP API on or off, on `port` if given.
+#[tauri::command]
+pub fn set_http_api_settings(
+    enabled: bool,
+    port: Option<u16>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let settings = state.settings.set_http_api(enabled, port)?;
+    state.http_api.apply(&app_handle, &settings)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(if enabled {
+            format!("HTTP API listening on 127.0.0.1:{}", settings.port)
+        } else {
+            "HTTP API stopped".to_string()
+        }),
+        data: Some(serde_json::json!({
+            "settings": settings,
+            "listening_port": state.http_api.port(),
+        })),
+    })
+}
+
+/// Serves the HTTP API over HTTPS with the PEM certificate chain and key
+/// at `cert_path` and `key_path`, or plain HTTP again without them. An
+/// agent only accepts clients from other machines over HTTPS.
+#[tauri::command]
+pub fn set_http_api_tls(
+    cert_path: Option<String>,
+    key_path: Option<String>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let settings = state.settings.set_http_api_tls(
+        cert_path.map(std::path::PathBuf::from),
+        key_path.map(std::path::PathBuf::from),
+    )?;
+    if settings.enabled {
+        state.http_api.apply(&app_handle, &settings)?;
+    }
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(if settings.tls() {
+            "HTTP API served over HTTPS".to_string()
+        } else {
+            "HTTP API served over plain HTTP".to_string()
+        }),
+        data: Some(serde_json::json!({
+            "settings": settings,
+            "listening_port": state.http_api.port(),
+        })),
+    })
+}
+
+#[tauri::command]
+pub fn list_webhooks(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({ "webhooks": state.settings.webhooks() })),
+    })
+}
+
+/// Registers a webhook to be POSTed `events` (`run_started`,
+/// `run_completed`, `run_failed`) of runs in any session.
+#[tauri::command]
+pub fn add_webhook(
+    url: String,
+    events: Vec<WebhookEvent>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let webhook = state.settings.add_webhook(&url, events)?;
+    info!("Added webhook {} for {:?}", webhook.id, webhook.events);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Webhook added".to_string()),
+        data: Some(serde_json::json!(webhook)),
+    })
+}
+
+#[tauri::command]
+pub fn remove_webhook(
+    webhook_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let webhook = state.settings.remove_webhook(&webhook_id)?;
+    info!("Removed webhook {}", webhook.id);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Webhook removed".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_hotkeys(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state.settings.hotkeys())),
+    })
+}
+
+/// Registers new global hotkeys and saves them. The old ones stay if any
+/// of the new ones is invalid.
+#[tauri::command]
+pub fn set_hotkeys(
+    hotkeys: HotkeySettings,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    hotkeys::bindings(&hotkeys)?;
+    hotkeys::apply(&app_handle, &hotkeys)?;
+    state.settings.set_hotkeys(hotkeys.clone())?;
+    info!("Hotkeys set: {:?}", hotkeys);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Hotkeys updated".to_string()),
+        data: Some(serde_json::json!(hotkeys)),
+    })
+}
+
+#[tauri::command]
+pub fn get_idle_status(state: State<AppState>) -> Result<CommandResponse, String> {
+    let idle = idle::idle_time()?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "idle_seconds": idle.as_secs(),
+            "policy": *state.idle_policy.lock().unwrap(),
+        })),
+    })
+}
+
+#[tauri::command]
+pub fn set_idle_policy(
+    policy: IdlePolicy,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    info!("Updating idle policy: {:?}", policy);
+    *state.idle_policy.lock().unwrap() = policy;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Idle policy updated".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn stop_execution(state: State<AppState>) -> Result<CommandResponse, String> {
+    let mut bridge_lock = state.executor.lock().unwrap();
+
+    if let Some(ref mut bridge) = *bridge_lock {
+        bridge
+            .stop_execution()
+            .map_err(|e| format!("Failed to stop execution: {}", e))?;
+
+        Ok(CommandResponse {
+            success: true,
+            message: Some("Execution stopped".to_string()),
+            data: None,
+        })
+    } else {
+        Err("Python executor not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub fn get_executor_status(state: State<AppState>) -> Result<CommandResponse, String> {
+    let mut bridge_lock = state.executor.lock().unwrap();
+
+    if let Some(ref mut bridge) = *bridge_lock {
+        let is_running = bridge.is_running();
+
+        if is_running {
+            bridge
+                .get_status()
+                .map_err(|e| format!("Failed to get status: {}", e))?;
+        }
+
+        Ok(CommandResponse {
+            success: true,
+            message: None,
+            data: Some(serde_json::json!({
+                "python_running": is_running,
+                "config_loaded": state.current_config.lock().unwrap().is_some()
+            })),
+        })
+    } else {
+        Ok(CommandResponse {
+            success: true,
+            message: None,
+            data: Some(serde_json::json!({
+                "python_running": false,
+                "config_loaded": state.current_config.lock().unwrap().is_some()
+            })),
+        })
+    }
+}
+
+/// The runner's and the executor's versions, and whether they speak a
+/// protocol version both understand.
+#[tauri::command]
+pub fn get_executor_info(state: State<AppState>) -> Result<CommandResponse, String> {
+    let handshake = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|bridge| bridge.is_running())
+        .map(|bridge| (bridge.handshake(), bridge.transport()));
+    let (handshake, bridge_transport) = handshake.unzip();
+    let bridge_transport = bridge_transport.flatten();
+    let hello = handshake.as_ref().and_then(|handshake| handshake.hello());
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "runner_version": env!("CARGO_PKG_VERSION"),
+            "protocol_version": crate::executor::handshake::PROTOCOL_VERSION,
+            "executor_protocol_version": hello.map(|hello| hello.protocol_version),
+            "qontinui_version": hello.and_then(|hello| hello.qontinui_version.clone()),
+            "commands": hello.and_then(|hello| hello.commands.clone()),
+            "transport": bridge_transport,
+            "handshake": handshake,
+        })),
+    })
+}
+
+/// Starts another executor session next to the default one, with the
+/// configuration at `config_path` or the loaded one. Its events carry the
+/// returned `session_id`.
+#[tauri::command]
+pub fn create_session(
+    executor_type: Option<String>,
+    config_path: Option<String>,
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let executor_type = executor_type.unwrap_or_else(|| "simple".to_string());
+    let session_id = open_session(app_handle, &state, executor_type, config_path)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Executor session {} started", session_id)),
+        data: Some(serde_json::json!({ "session_id": session_id })),
+    })
+}
+
+/// Starts an executor session and sends it the configuration at
+/// `config_path`, or the loaded one. Returns the session id.
+pub(crate) fn open_session(
+    app_handle: AppHandle,
+    state: &AppState,
+    executor_type: String,
+    config_path: Option<String>,
+) -> Result<String, String> {
+    let config = match config_path.as_deref() {
+        Some(path) => Some(Arc::new(
+            ConfigLoader::load_from_file(path)
+                .map_err(|e| format!("Failed to load configuration: {}", e))?,
+        )),
+        None => state.current_config.lock().unwrap().clone(),
+    };
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    info!(
+        "Starting executor session {} with type: {}",
+        session_id, executor_type
+    );
+    let mut bridge = PythonBridge::new(app_handle).for_session(&session_id);
+    bridge
+        .start(&executor_type)
+        .map_err(|e| format!("Failed to start Python executor: {}", e))?;
+    // Bridge before config, like everywhere else
+    if let Some(ref config) = config {
+        bridge
+            .sync_configuration(config, false)
+            .map_err(|e| format!("Failed to load configuration: {}", e))?;
+    }
+
+    state.executor_sessions.insert(
+        &session_id,
+        ExecutorSession {
+            bridge: Box::new(bridge),
+            executor_type,
+            config,
+            config_path,
+            created_at: chrono::Local::now().to_rfc3339(),
+        },
+    );
+    Ok(session_id)
+}
+
+/// Stops a session's executor and forgets the session. The default session
+/// is stopped with `stop_python_executor` instead.
+#[tauri::command]
+pub fn destroy_session(
+    session_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    close_session(&state, &session_id)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Executor session {} stopped", session_id)),
+        data: None,
+    })
+}
+
+/// Stops a created session's executor and forgets the session.
+pub(crate) fn close_session(state: &AppState, session_id: &str) -> Result<(), String> {
+    if session_id == DEFAULT_SESSION {
+        return Err("The default session can't be destroyed".to_string());
+    }
+    let session = state
+        .executor_sessions
+        .remove(session_id)
+        .ok_or_else(|| format!("No executor session '{}'", session_id))?;
+    session
+        .lock()
+        .unwrap()
+        .bridge
+        .stop()
+        .map_err(|e| format!("Failed to stop Python executor: {}", e))?;
+    info!("Executor session {} destroyed", session_id);
+    Ok(())
+}
+
+/// The default session followed by the created ones.
+#[tauri::command]
+pub fn list_sessions(state: State<AppState>) -> Result<CommandResponse, String> {
+    let session = state.session.current();
+    let default = SessionInfo {
+        session_id: DEFAULT_SESSION.to_string(),
+        executor_type: session.executor_type,
+        running: state
+            .executor
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|bridge| bridge.is_running()),
+        config_path: session.config_path,
+        config_name: current_config_name(&state),
+        created_at: None,
+    };
+    let mut sessions = vec![default];
+    sessions.extend(state.executor_sessions.list());
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(sessions)),
+    })
+}
+
+/// `start_execution` for any session.
+#[tauri::command]
+pub fn start_session_execution(
+    session_id: String,
+    process_id: String,
+    monitor_index: Option<i32>,
+    request: Option<ExecutionRequest>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    if session_id == DEFAULT_SESSION {
+        return start_execution(Some(process_id), monitor_index, request, state);
+    }
+    let request = request.unwrap_or_default();
+    request.validate()?;
+    begin_session_execution(&state, &session_id, &process_id, monitor_index, &request)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Execution started".to_string()),
+        data: Some(serde_json::json!({ "session_id": session_id })),
+    })
+}
+
+/// `begin_execution` for a created session, with its own configuration's
+/// variables.
+pub(crate) fn begin_session_execution(
+    state: &AppState,
+    session_id: &str,
+    process_id: &str,
+    monitor_index: Option<i32>,
+    request: &ExecutionRequest,
+) -> Result<(), String> {
+    let safe_bounds = resolve_safe_bounds(state)?;
+    let session = state.executor_sessions.get(session_id)?;
+    let mut session = session.lock().unwrap();
+    if !session.bridge.is_running() {
+        return Err("Python executor not running".to_string());
+    }
+    if safe_bounds.is_some() {
+        session.bridge.handshake().allows_safe_mode()?;
+    }
+    let config_name = session.config.as_ref().map(|c| c.metadata.name.clone());
+    let mut params = execution_params(
+        state,
+        session_id,
+        config_name.as_deref(),
+        process_id,
+        monitor_index,
+        safe_bounds,
+    );
+    request.insert_into(&mut params);
+    session
+        .bridge
+        .start_execution_with_params(Some(serde_json::Value::Object(params)))
+        .map_err(|e| format!("Failed to start execution: {}", e))
+}
+
+/// `stop_execution` for any session.
+#[tauri::command]
+pub fn stop_session_execution(
+    session_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    sessions::with_bridge(&state, &session_id, |bridge| bridge.stop_execution())
+        .map_err(|e| format!("Failed to stop execution: {}", e))?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Execution stopped".to_string()),
+        data: Some(serde_json::json!({ "session_id": session_id })),
+    })
+}
+
+/// `get_executor_status` for any session.
+#[tauri::command]
+pub fn get_session_status(
+    session_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    if session_id == DEFAULT_SESSION {
+        return get_executor_status(state);
+    }
+    let session = state.executor_sessions.get(&session_id)?;
+    let mut session = session.lock().unwrap();
+    let is_running = session.bridge.is_running();
+    if is_running {
+        session
+            .bridge
+            .get_status()
+            .map_err(|e| format!("Failed to get status: {}", e))?;
+    }
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "session_id": session_id,
+            "python_running": is_running,
+            "config_loaded": session.config.is_some(),
+            "executor_type": session.executor_type,
+            "config_path": session.config_path,
+        })),
+    })
+}
+
+/// Sends `command` to the executor and waits for its response, up to
+/// `timeout_ms` (30 s by default). The response's success, error and data
+/// become the result, so callers see what the executor actually did.
+#[tauri::command]
+pub async fn send_executor_command(
+    command: String,
+    params: Option<serde_json::Value>,
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    let pending = send_awaiting(&state, &command, params)?;
+    await_response(pending, timeout_ms).await
+}
+
+/// Asks the executor for its status and returns its reply.
+#[tauri::command]
+pub async fn query_executor_status(
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    let pending = send_awaiting(&state, "status", None)?;
+    await_response(pending, timeout_ms).await
+}
+
+/// Binds variables in the running execution, for this run only: workflows
+/// read them before the stored ones, and they're never stored. A `null`
+/// value removes a binding. Returns the run's bindings.
+#[tauri::command]
+pub async fn set_execution_variables(
+    variables: serde_json::Map<String, serde_json::Value>,
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    execution_request::validate_variables(&variables)?;
+    let params = serde_json::json!({ "variables": variables });
+    let pending = send_awaiting(&state, "set_variables", Some(params))?;
+    await_response(pending, timeout_ms).await
+}
+
+/// The variables the executor holds right now: the run's bindings and its
+/// global and configuration scopes, including values set since the start.
+/// Meant for inspecting a paused execution.
+#[tauri::command]
+pub async fn get_execution_variables(
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    let pending = send_awaiting(&state, "get_variables", None)?;
+    await_response(pending, timeout_ms).await
+}
+
+/// The states the automation is in.
+#[tauri::command]
+pub async fn get_active_states(
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    ask_state_machine::<ActiveStates>(&state, "active_states", None, timeout_ms).await
+}
+
+/// The loaded configuration's states and transitions, with the active
+/// states marked.
+#[tauri::command]
+pub async fn get_state_graph(
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    ask_state_machine::<StateGraph>(&state, "state_graph", None, timeout_ms).await
+}
+
+/// The transitions the executor has run since the configuration was
+/// loaded, oldest first.
+#[tauri::command]
+pub async fn get_transition_history(
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    ask_state_machine::<TransitionHistory>(&state, "transition_history", None, timeout_ms).await
+}
+
+/// Tells the executor the automation is in `state_id` (an id or name), for
+/// when it got lost. Returns the active states.
+#[tauri::command]
+pub async fn activate_state(
+    state_id: String,
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    correct_state(&state, "activate_state", state_id, timeout_ms).await
+}
+
+/// Tells the executor the automation is no longer in `state_id`.
+#[tauri::command]
+pub async fn deactivate_state(
+    state_id: String,
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    correct_state(&state, "deactivate_state", state_id, timeout_ms).await
+}
+
+/// Has the executor look for every state's images on screen and make the
+/// states it finds the active ones. Takes a screen search per image, so
+/// allow a longer timeout for large configurations.
+#[tauri::command]
+pub async fn rebuild_state_from_screen(
+    timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    info!("Rebuilding the active states from the screen");
+    ask_state_machine::<RebuiltStates>(&state, "rebuild_states", None, timeout_ms).await
+}
+
+async fn correct_state(
+    state: &AppState,
+    command: &str,
+    state_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<CommandResponse, String> {
+    if state_id.trim().is_empty() {
+        return Err("State id is required".to_string());
+    }
+    info!("Correcting the state machine: {} {}", command, state_id);
+    let params = serde_json::json!({ "state_id": state_id });
+    ask_state_machine::<ActiveStates>(state, command, Some(params), timeout_ms).await
+}
+
+/// Sends a state machine command and checks the executor's answer is a `T`.
+async fn ask_state_machine<T: serde::de::DeserializeOwned + serde::Serialize>(
+    state: &AppState,
+    command: &str,
+    params: Option<serde_json::Value>,
+    timeout_ms: Option<u64>,
+) -> Result<CommandResponse, String> {
+    let pending = send_awaiting(state, command, params)?;
+    let response = await_response(pending, timeout_ms).await?;
+    if !response.success {
+        return Ok(response);
+    }
+    let answer: T = state_machine::parse(command, response.data)?;
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(answer)),
+    })
+}
+
+fn send_awaiting(
+    state: &AppState,
+    command: &str,
+    params: Option<serde_json::Value>,
+) -> Result<PendingCommand, String> {
+    let mut bridge = state.executor.lock().unwrap();
+    match bridge.as_mut() {
+        Some(bridge) if bridge.is_running() => bridge.send_command_awaiting(command, params),
+        _ => Err("Python executor not running".to_string()),
+    }
+}
+
+/// Waits off the async runtime, with the bridge lock already released.
+async fn await_response(
+    pending: PendingCommand,
+    timeout_ms: Option<u64>,
+) -> Result<CommandResponse, String> {
+    let timeout = timeout_ms
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(DEFAULT_RESPONSE_TIMEOUT);
+    let response = tauri::async_runtime::spawn_blocking(move || pending.wait(timeout))
+        .await
+        .map_err(|e| format!("Failed to wait for the executor: {}", e))??;
+
+    Ok(CommandResponse {
+        success: response.success,
+        message: response.error,
+        data: response.data,
+    })
+}
+
+#[tauri::command]
+pub fn set_event_throttle(
+    normal_per_second: u32,
+    bulk_per_second: u32,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let config = ThrottleConfig {
+        normal_per_second,
+        bulk_per_second,
+    };
+    info!("Setting executor event throttle: {:?}", config);
+
+    if let Some(ref bridge) = *state.executor.lock().unwrap() {
+        bridge.set_throttle_config(config);
+        Ok(CommandResponse {
+            success: true,
+            message: Some("Event throttle updated".to_string()),
+            data: None,
+        })
+    } else {
+        Err("Python executor not initialized".to_string())
+    }
+}
+
+/// Bounds the queue of executor messages waiting to reach the frontend.
+/// `policies` maps event types to what happens when it's full (`block`,
+/// `coalesce`, `drop_newest` or `drop_oldest`) and replaces the current
+/// ones when given.
+#[tauri::command]
+pub fn set_event_backpressure(
+    capacity: usize,
+    policies: Option<HashMap<String, OverflowPolicy>>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    if capacity == 0 {
+        return Err("The event queue needs room for at least one message".to_string());
+    }
+    if let Some(ref bridge) = *state.executor.lock().unwrap() {
+        let mut config = bridge.backpressure_config();
+        config.capacity = capacity;
+        if let Some(policies) = policies {
+            config.policies = policies;
+        }
+        info!("Setting executor event backpressure: {:?}", config);
+        bridge.set_backpressure_config(config);
+        Ok(CommandResponse {
+            success: true,
+            message: Some("Event backpressure updated".to_string()),
+            data: None,
+        })
+    } else {
+        Err("Python executor not initialized".to_string())
+    }
+}
+
+/// How the executor performs: commands sent and their round-trip latency,
+/// events per second, events that didn't reach the frontend, and the Python
+/// process's CPU and memory, with the event queue's counters.
+#[tauri::command]
+pub fn get_bridge_metrics(state: State<AppState>) -> Result<CommandResponse, String> {
+    if let Some(ref bridge) = *state.executor.lock().unwrap() {
+        Ok(CommandResponse {
+            success: true,
+            message: None,
+            data: Some(bridge.metrics()),
+        })
+    } else {
+        Err("Python executor not initialized".to_string())
+    }
+}
+
+/// Picks the transport the executor is offered when it next starts:
+/// `stdio`, `tcp` or `unix`. Stdio remains the fallback.
+#[tauri::command]
+pub fn set_executor_transport(
+    transport: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let kind = TransportKind::parse(&transport)?;
+    *state.executor_transport.lock().unwrap() = kind;
+    info!("Executor transport set to {:?}", kind);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Takes effect when the executor next starts".to_string()),
+        data: None,
+    })
+}
+
+/// Connects the default session to an executor on another machine, served
+/// there by `remote_executor.py`. A lost connection is retried, and how it
+/// goes arrives as `remote_connection` executor events.
+#[tauri::command]
+pub fn connect_remote_executor(
+    address: String,
+    token: String,
+    tls: Option<bool>,
+    ca_cert: Option<String>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let target = RemoteTarget {
+        address,
+        token,
+        tls: tls.unwrap_or(false),
+        ca_cert: ca_cert.map(std::path::PathBuf::from),
+    };
+    target.validate()?;
+    info!(
+        "Connecting to the remote executor at {} ({})",
+        target.address,
+        if target.tls { "TLS" } else { "plain TCP" }
+    );
+    state.remote_executor.set_target(target);
+    start_python_executor_with_type(app_handle, state, "remote".to_string(), None)
+}
+
+/// Disconnects from the remote executor, stopping any run on it first, and
+/// forgets its token.
+#[tauri::command]
+pub fn disconnect_remote_executor(state: State<AppState>) -> Result<CommandResponse, String> {
+    let outcome = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_mut()
+        .filter(|bridge| bridge.name() == "remote")
+        .map(|bridge| bridge.stop())
+        .transpose()?;
+    state.remote_executor.clear_target();
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(match outcome {
+            Some(_) => "Disconnected from the remote executor".to_string(),
+            None => "Not connected to a remote executor".to_string(),
+        }),
+        data: None,
+    })
+}
+
+/// Where the remote executor is and how the connection to it is doing,
+/// including reconnection attempts and the last error.
+#[tauri::command]
+pub fn get_remote_executor_status(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "target": state.remote_executor.target(),
+            "status": state.remote_executor.status(),
+        })),
+    })
+}
+
+#[tauri::command]
+pub fn set_protocol_strict_mode(
+    enabled: bool,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    match *state.executor.lock().unwrap() {
+        Some(ref bridge) => bridge.set_strict_protocol(enabled),
+        None => return Err("Python executor not initialized".to_string()),
+    }
+    info!(
+        "Strict protocol checking {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: None,
+    })
+}
+
+/// Replays a captured event log through the event pipeline as a background
+/// task. `speed` scales the original timing (2.0 is twice as fast); 0 sends
+/// everything without delays.
+#[tauri::command]
+pub fn replay_event_log(
+    path: String,
+    speed: Option<f64>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() || speed < 0.0 {
+        return Err(format!("Invalid replay speed: {}", speed));
+    }
+
+    let frames = replay::read_log(std::path::Path::new(&path))?;
+    let message_count = frames.len();
+    info!(
+        "Replaying {} messages from {} at {}x speed",
+        message_count, path, speed
+    );
+
+    let throttle_config = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|bridge| bridge.throttle_config())
+        .unwrap_or_default();
+    let mut pipeline =
+        EventPipeline::new(app_handle.clone(), Arc::new(Mutex::new(throttle_config)));
+
+    let task_id = state.tasks.spawn(
+        app_handle,
+        "replay_event_log",
+        &format!("Replay {}", path),
+        move |task| {
+            let summary = replay::replay(frames, speed, &mut pipeline, task);
+            serde_json::to_value(summary).map_err(|e| e.to_string())
+        },
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Replaying {} messages", message_count)),
+        data: Some(serde_json::json!({ "task_id": task_id })),
+    })
+}
+
+/// Runs whose message streams were recorded, newest first.
+#[tauri::command]
+pub fn list_event_streams(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "streams": state.run_streams.list(),
+            "current_run": state.run_streams.current_run(DEFAULT_SESSION),
+        })),
+    })
+}
+
+/// The events of `run_id`, by default the latest run, after
+/// `since_sequence`, so a frontend that reconnects or opens mid-run can
+/// backfill what it missed before following `executor-event`. Without a
+/// sequence the whole run is returned.
+#[tauri::command]
+pub fn get_event_timeline(
+    run_id: Option<String>,
+    since_sequence: Option<u32>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let Some(run_id) = run_id.or_else(|| state.run_streams.latest_run(DEFAULT_SESSION)) else {
+        return Ok(CommandResponse {
+            success: true,
+            message: Some("No run has started yet".to_string()),
+            data: None,
+        });
+    };
+    let since_sequence = since_sequence.unwrap_or(0);
+    let timeline = match state.event_timeline.since(&run_id, since_sequence) {
+        Some(timeline) => timeline,
+        None => timeline::from_stream(&run_id, &state.run_streams.read(&run_id)?, since_sequence),
+    };
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("{} events", timeline.events.len())),
+        data: Some(serde_json::json!(timeline)),
+    })
+}
+
+/// Writes a run's commands, responses and events as `jsonl` or `csv`, to
+/// `dest_path` or next to the recorded streams under `exports/`.
+#[tauri::command]
+pub fn export_event_stream(
+    run_id: String,
+    format: String,
+    dest_path: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let format = ExportFormat::parse(&format)?;
+    let dest = dest_path.map(std::path::PathBuf::from).unwrap_or_else(|| {
+        state
+            .run_streams
+            .dir()
+            .join("exports")
+            .join(format!("{}.{}", run_id, format.extension()))
+    });
+    let entries = state.run_streams.export(&run_id, format, &dest)?;
+    info!(
+        "Exported {} entries of run {} to {:?}",
+        entries, run_id, dest
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!(
+            "Exported {} entries to {}",
+            entries,
+            dest.display()
+        )),
+        data: Some(serde_json::json!({ "path": dest, "entries": entries })),
+    })
+}
+
+/// Writes a report of a recorded run as `json`, `html` or `junit` (XML) to
+/// `dest_path`, by default the `reports` folder next to the streams. Text
+/// is formatted for `locale`, by default the app's.
+#[tauri::command]
+pub fn generate_run_report(
+    run_id: String,
+    format: String,
+    dest_path: Option<String>,
+    locale: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let format = ReportFormat::parse(&format)?;
+    let locale = locale.unwrap_or_else(|| state.translations.locale());
+    let formatter = state.translations.formatter(&locale);
+    let entries = state.run_streams.read(&run_id)?;
+    let report = RunReport::build(&run_id, &entries, &formatter, &locale);
+    let dest = dest_path.map(std::path::PathBuf::from).unwrap_or_else(|| {
+        state
+            .run_streams
+            .dir()
+            .join("reports")
+            .join(format!("{}.{}", run_id, format.extension()))
+    });
+    report.write(format, &formatter, &dest)?;
+    info!("Wrote report of run {} to {:?}", run_id, dest);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Wrote report to {}", dest.display())),
+        data: Some(serde_json::json!({
+            "path": dest,
+            "summary": report.summary,
+        })),
+    })
+}
+
+/// Copies the event log of a run to `dest_path`, e.g. to attach it to a
+/// bug report.
+#[tauri::command]
+pub fn export_run_log(
+    run_id: String,
+    dest_path: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let dest = std::path::PathBuf::from(dest_path);
+    let size_bytes = state.run_streams.export_log(&run_id, &dest)?;
+    info!("Exported event log of run {} to {:?}", run_id, dest);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Exported event log to {}", dest.display())),
+        data: Some(serde_json::json!({ "path": dest, "size_bytes": size_bytes })),
+    })
+}
+
+/// Runs an end-to-end test script of runner operations as a background task.
+/// The task result is the script's `TestReport`.
+#[tauri::command]
+pub fn run_test_script(
+    path: String,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let script_path = std::path::PathBuf::from(&path);
+    let script = harness::load_script(&script_path)?;
+    let base_dir = script_path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+    info!(
+        "Running test script {} ({} steps)",
+        path,
+        script.steps.len()
+    );
+
+    let harness_handle = app_handle.clone();
+    let task_id = state.tasks.spawn(
+        app_handle,
+        "test_script",
+        &format!("Test script {}", path),
+        move |task| {
+            let report = harness::run(script, base_dir, harness_handle, task);
+            if report.passed {
+                info!("Test script '{}' passed", report.name);
+            } else {
+                warn!("Test script '{}' failed", report.name);
+            }
+            serde_json::to_value(report).map_err(|e| e.to_string())
+        },
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Test script started".to_string()),
+        data: Some(serde_json::json!({ "task_id": task_id })),
+    })
+}
+
+/// Checks a bridge script against the runner's protocol by running it
+/// through a fixed command sequence in a separate process. Defaults to the
+/// script the UI starts ("real"); the run itself always uses mock mode.
+#[tauri::command]
+pub fn run_bridge_selftest(
+    executor_type: Option<String>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let executor_type = executor_type.unwrap_or_else(|| "real".to_string());
+    info!("Running bridge self-test for {} executor", executor_type);
+
+    let selftest_handle = app_handle.clone();
+    let task_id = state.tasks.spawn(
+        app_handle,
+        "bridge_selftest",
+        &format!("Bridge self-test ({})", executor_type),
+        move |task| {
+            let report = selftest::run(&executor_type, selftest_handle, task);
+            if report.passed {
+                info!("Bridge self-test passed for {} executor", executor_type);
+            } else {
+                warn!("Bridge self-test failed for {} executor", executor_type);
+            }
+            serde_json::to_value(report).map_err(|e| e.to_string())
+        },
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Bridge self-test started".to_string()),
+        data: Some(serde_json::json!({ "task_id": task_id })),
+    })
+}
+
+fn current_config_name(state: &AppState) -> Option<String> {
+    state
+        .current_config
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|config| config.metadata.name.clone())
+}
+
+/// Reads a stored variable. `scope` is "global" (default) or "config" for
+/// the loaded configuration.
+#[tauri::command]
+pub fn get_variable(
+    key: String,
+    scope: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let scope = VariableScope::parse(scope.as_deref())?;
+    let value = state
+        .variables
+        .get(scope, current_config_name(&state).as_deref(), &key)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({ "key": key, "value": value })),
+    })
+}
+
+/// Stores a variable; a null `value` deletes it.
+#[tauri::command]
+pub fn set_variable(
+    key: String,
+    value: serde_json::Value,
+    scope: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let scope = VariableScope::parse(scope.as_deref())?;
+    let value = (!value.is_null()).then_some(value);
+    state
+        .variables
+        .set(scope, current_config_name(&state).as_deref(), &key, value)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Variable '{}' saved", key)),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn list_variables(
+    scope: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let scope = VariableScope::parse(scope.as_deref())?;
+    let variables = state
+        .variables
+        .list(scope, current_config_name(&state).as_deref())?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::Value::Object(variables)),
+    })
+}
+
+/// Recently loaded configurations, most recent first, with the workflow and
+/// monitor each was last run with and whether the file is still there.
+#[tauri::command]
+pub fn get_recent_configurations(state: State<AppState>) -> Result<CommandResponse, String> {
+    let recent: Vec<serde_json::Value> = state
+        .settings
+        .recent_configurations()
+        .into_iter()
+        .map(|entry| {
+            let exists = std::path::Path::new(&entry.path).exists();
+            let mut entry = serde_json::json!(entry);
+            entry["exists"] = serde_json::json!(exists);
+            entry
+        })
+        .collect();
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(recent)),
+    })
+}
+
+/// Puts `path` at the top of the recent configurations, optionally with
+/// the workflow and monitor to offer for the next run.
+#[tauri::command]
+pub fn add_recent_configuration(
+    path: String,
+    name: Option<String>,
+    workflow_id: Option<String>,
+    monitor_index: Option<i32>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let entry = state
+        .settings
+        .add_recent_configuration(&path, name, workflow_id, monitor_index)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(entry)),
+    })
+}
+
+#[tauri::command]
+pub fn clear_recent_configurations(state: State<AppState>) -> Result<CommandResponse, String> {
+    state.settings.clear_recent_configurations()?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Recent configurations cleared".to_string()),
+        data: None,
+    })
+}
+
+/// Saves a credential to the OS keychain. Configurations refer to it by
+/// `name`; the value is only handed to the executor when an action uses it.
+#[tauri::command]
+pub async fn store_secret(name: String, value: String) -> Result<CommandResponse, String> {
+    let stored = name.clone();
+    tauri::async_runtime::spawn_blocking(move || secrets::store(&stored, &value))
+        .await
+        .map_err(|e| format!("Failed to store secret: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Secret '{}' saved", name)),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub async fn delete_secret(name: String) -> Result<CommandResponse, String> {
+    let deleted = name.clone();
+    tauri::async_runtime::spawn_blocking(move || secrets::delete(&deleted))
+        .await
+        .map_err(|e| format!("Failed to delete secret: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Secret '{}' deleted", name)),
+        data: None,
+    })
+}
+
+/// Names only; secret values can't be read back through the runner.
+#[tauri::command]
+pub fn list_secret_names() -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({ "names": secrets::list_names() })),
+    })
+}
+
+#[tauri::command]
+pub fn get_current_configuration(state: State<AppState>) -> Result<Arc<QontinuiConfig>, String> {
+    state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())
+}
+
+#[tauri::command]
+pub fn get_config_section(name: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    let config = state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())?;
+
+    let section = config.section(&name)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(section),
+    })
+}
+
+/// Checks the configuration at `path` without loading it, returning each
+/// problem with its severity, a JSON Pointer to the element and, where
+/// there is one, a suggested fix.
+#[tauri::command]
+pub fn validate_configuration(path: String) -> Result<CommandResponse, String> {
+    let diagnostics = ConfigLoader::diagnose_file(&path)?;
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    info!(
+        "Validated {}: {} errors, {} warnings",
+        path, errors, warnings
+    );
+
+    Ok(CommandResponse {
+        success: errors == 0,
+        message: Some(format!("{} errors, {} warnings", errors, warnings)),
+        data: Some(serde_json::json!({
+            "errors": errors,
+            "warnings": warnings,
+            "diagnostics": diagnostics,
+        })),
+    })
+}
+
+/// Compares the configurations at `path_a` and `path_b` entry by entry:
+/// states, transitions, workflows and images added, removed or modified,
+/// with each changed field, and images whose pixels were swapped. Neither
+/// file is loaded or has to pass validation.
+#[tauri::command]
+pub async fn diff_configurations(
+    path_a: String,
+    path_b: String,
+) -> Result<CommandResponse, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let old = ConfigLoader::parse_file(&path_a)?;
+        let new = ConfigLoader::parse_file(&path_b)?;
+        let changes = diff::change_set(&old, &new)?;
+        let message = if changes.identical {
+            "The configurations are identical".to_string()
+        } else {
+            format!(
+                "{} entries changed, {} images swapped",
+                changes.entries.len(),
+                changes.images_swapped.len()
+            )
+        };
+        info!("Compared {} with {}: {}", path_a, path_b, message);
+
+        Ok(CommandResponse {
+            success: true,
+            message: Some(message),
+            data: Some(serde_json::json!(changes)),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to compare configurations: {}", e))?
+}
+
+/// Walks through workflow `process_id` without running it: resolves the
+/// states, transitions and images it uses, decodes each image, and reports
+/// unreachable states and missing assets. No input is performed.
+#[tauri::command]
+pub fn validate_execution(
+    process_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let config = state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())?;
+
+    let report = dry_run::walk_through(&config, &process_id)?;
+    info!(
+        "Dry run of {}: {} steps, {} missing assets, {} unreachable states",
+        process_id,
+        report.steps.len(),
+        report.missing_assets.len(),
+        report.unreachable_states.len()
+    );
+
+    Ok(CommandResponse {
+        success: report.ok,
+        message: Some(if report.ok {
+            format!("Workflow '{}' is ready to run", process_id)
+        } else {
+            format!("Workflow '{}' has problems", process_id)
+        }),
+        data: Some(serde_json::json!(report)),
+    })
+}
+
+/// Applies an edit to entry `id` of `section` in the loaded configuration
+/// and records it for undo.
+fn edit_configuration<F>(
+    state: &AppState,
+    section: Section,
+    id: &str,
+    description: String,
+    edit: F,
+) -> Result<CommandResponse, String>
+where
+    F: FnOnce(&mut QontinuiConfig) -> Result<(), String>,
+{
+    if state.idle_monitor.is_executing() {
+        return Err("Cannot edit the configuration while an execution is running".to_string());
+    }
+
+    // Held throughout so edits apply one at a time
+    let mut edits = state.config_edits.lock().unwrap();
+    let current = loaded_config(state)?;
+    let edited = editor::apply(&current, edit)?;
+    let change = EntryChange::between(&current, &edited, section, id, description.clone());
+    replace_configuration(state, &current, edited)?;
+    edits.record(change);
+    info!("Configuration edited: {}", description);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(description),
+        data: Some(serde_json::json!(*edits)),
+    })
+}
+
+/// Undoes the last edit, or redoes the last undone one.
+fn step_edit_history(state: &AppState, undo: bool) -> Result<CommandResponse, String> {
+    if state.idle_monitor.is_executing() {
+        return Err("Cannot edit the configuration while an execution is running".to_string());
+    }
+
+    let mut edits = state.config_edits.lock().unwrap();
+    let change = if undo {
+        edits.next_undo()
+    } else {
+        edits.next_redo()
+    }
+    .cloned()
+    .ok_or_else(|| format!("Nothing to {}", if undo { "undo" } else { "redo" }))?;
+
+    let current = loaded_config(state)?;
+    let edited = editor::apply(&current, |config| {
+        if undo {
+            change.undo(config);
+        } else {
+            change.redo(config);
+        }
+        Ok(())
+    })?;
+    replace_configuration(state, &current, edited)?;
+    if undo {
+        edits.undone();
+    } else {
+        edits.redone();
+    }
+    info!(
+        "Configuration edit {}: {}",
+        if undo { "undone" } else { "redone" },
+        change.description()
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: edits.last_edit.clone(),
+        data: Some(serde_json::json!(*edits)),
+    })
+}
+
+/// Makes `config`, loaded from `path`, the current configuration, with a
+/// fresh edit history. A watch on another file is stopped.
+pub(crate) fn install_configuration(
+    state: &AppState,
+    path: &str,
+    config: QontinuiConfig,
+    source_bytes: u64,
+) -> Arc<QontinuiConfig> {
+    let config = Arc::new(config);
+    *state.current_config.lock().unwrap() = Some(config.clone());
+    *state.config_source_bytes.lock().unwrap() = source_bytes;
+    let mut edits = EditState::loaded(path.into());
+    if config.bundle_dir.is_some() || config.source_format != ConfigFormat::Json {
+        // Saving writes JSON, which mustn't replace the bundle or a YAML or
+        // TOML file
+        edits.source_path = None;
+    }
+    *state.config_edits.lock().unwrap() = edits;
+    state
+        .session
+        .update(|session| session.config_path = Some(path.to_string()));
+
+    if let Some(watched) = state.config_watcher.path() {
+        if watched != std::path::Path::new(path) {
+            state.config_watcher.unwatch(&watched);
+        }
+    }
+    config
+}
+
+fn loaded_config(state: &AppState) -> Result<Arc<QontinuiConfig>, String> {
+    state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())
+}
+
+/// Swaps an edited copy of `current` in. If the executor is running it has
+/// to accept the edited config first.
+fn replace_configuration(
+    state: &AppState,
+    current: &Arc<QontinuiConfig>,
+    edited: QontinuiConfig,
+) -> Result<(), String> {
+    let edited = Arc::new(edited);
+    if let Some(ref mut bridge) = *state.executor.lock().unwrap() {
+        if bridge.is_running() {
+            bridge
+                .sync_configuration(&edited, false)
+                .map_err(|e| format!("Failed to send the edited configuration to Python: {}", e))?;
+        }
+    }
+
+    let mut config = state.current_config.lock().unwrap();
+    if !config
+        .as_ref()
+        .is_some_and(|loaded| Arc::ptr_eq(loaded, current))
+    {
+        return Err("The configuration was reloaded during the edit".to_string());
+    }
+    *config = Some(edited);
+    Ok(())
+}
+
+fn add_config_entry(
+    state: &AppState,
+    section: Section,
+    entry: serde_json::Value,
+) -> Result<CommandResponse, String> {
+    let id = entry
+        .get("id")
+        .and_then(|id| id.as_str())
+        .unwrap_or_default()
+        .to_string();
+    edit_configuration(
+        state,
+        section,
+        &id,
+        format!("Added {} '{}'", section.noun(), id),
+        |config| editor::add_entry(config, section, entry).map(|_| ()),
+    )
+}
+
+fn update_config_entry(
+    state: &AppState,
+    section: Section,
+    id: String,
+    changes: serde_json::Value,
+    replace: Option<bool>,
+) -> Result<CommandResponse, String> {
+    edit_configuration(
+        state,
+        section,
+        &id,
+        format!("Updated {} '{}'", section.noun(), id),
+        |config| editor::update_entry(config, section, &id, changes, replace.unwrap_or(false)),
+    )
+}
+
+fn remove_config_entry(
+    state: &AppState,
+    section: Section,
+    id: String,
+) -> Result<CommandResponse, String> {
+    edit_configuration(
+        state,
+        section,
+        &id,
+        format!("Removed {} '{}'", section.noun(), id),
+        |config| editor::remove_entry(config, section, &id).map(|_| ()),
+    )
+}
+
+#[tauri::command]
+pub fn add_state(
+    entry: serde_json::Value,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    add_config_entry(&state, Section::States, entry)
+}
+
+/// Sets the fields in `changes` on the state, or replaces it entirely when
+/// `replace` is true. Null fields are removed.
+#[tauri::command]
+pub fn update_state(
+    id: String,
+    changes: serde_json::Value,
+    replace: Option<bool>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    update_config_entry(&state, Section::States, id, changes, replace)
+}
+
+#[tauri::command]
+pub fn remove_state(id: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    remove_config_entry(&state, Section::States, id)
+}
+
+#[tauri::command]
+pub fn add_transition(
+    entry: serde_json::Value,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    add_config_entry(&state, Section::Transitions, entry)
+}
+
+#[tauri::command]
+pub fn update_transition(
+    id: String,
+    changes: serde_json::Value,
+    replace: Option<bool>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    update_config_entry(&state, Section::Transitions, id, changes, replace)
+}
+
+#[tauri::command]
+pub fn remove_transition(id: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    remove_config_entry(&state, Section::Transitions, id)
+}
+
+#[tauri::command]
+pub fn add_workflow(
+    entry: serde_json::Value,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    add_config_entry(&state, Section::Workflows, entry)
+}
+
+#[tauri::command]
+pub fn update_workflow(
+    id: String,
+    changes: serde_json::Value,
+    replace: Option<bool>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    update_config_entry(&state, Section::Workflows, id, changes, replace)
+}
+
+#[tauri::command]
+pub fn remove_workflow(id: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    remove_config_entry(&state, Section::Workflows, id)
+}
+
+/// `entry` may carry a base64 `data` payload or a `path` to an image file.
+#[tauri::command]
+pub fn add_image(
+    entry: serde_json::Value,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    add_config_entry(&state, Section::Images, entry)
+}
+
+#[tauri::command]
+pub fn update_image(
+    id: String,
+    changes: serde_json::Value,
+    replace: Option<bool>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    update_config_entry(&state, Section::Images, id, changes, replace)
+}
+
+#[tauri::command]
+pub fn remove_image(id: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    remove_config_entry(&state, Section::Images, id)
+}
+
+#[tauri::command]
+pub fn undo_config_edit(state: State<AppState>) -> Result<CommandResponse, String> {
+    step_edit_history(&state, true)
+}
+
+#[tauri::command]
+pub fn redo_config_edit(state: State<AppState>) -> Result<CommandResponse, String> {
+    step_edit_history(&state, false)
+}
+
+#[tauri::command]
+pub fn get_config_edit_state(state: State<AppState>) -> Result<EditState, String> {
+    Ok(state.config_edits.lock().unwrap().clone())
+}
+
+/// Writes the loaded configuration, edits included, back to the file it
+/// came from or to `path`. Relative image paths stay relative to the
+/// original file's directory.
+#[tauri::command]
+pub fn save_configuration(
+    path: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let mut edits = state.config_edits.lock().unwrap();
+    let config = loaded_config(&state)?;
+    let path = path
+        .map(std::path::PathBuf::from)
+        .or_else(|| edits.source_path.clone())
+        .ok_or_else(|| "No path to save the configuration to".to_string())?;
+
+    let content = config.to_file_json()?;
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to save configuration to {:?}: {}", path, e))?;
+    info!("Saved configuration to {:?}", path);
+    edits.saved(path.clone());
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Configuration saved to {}", path.display())),
+        data: Some(serde_json::json!(*edits)),
+    })
+}
+
+#[tauri::command]
+pub fn get_image_data(image_id: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    let config = state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())?;
+
+    let data_url = config.image_store.data_url(&image_id).map_err(|e| {
+        warn!("Failed to load image {}: {}", image_id, e);
+        e
+    })?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "id": image_id,
+            "data_url": data_url,
+        })),
+    })
+}
+
+/// Image `image_id` scaled down to fit `size` pixels (128 by default), as
+/// a PNG `data:` URL. Thumbnails are cached.
+#[tauri::command]
+pub fn get_image_thumbnail(
+    image_id: String,
+    size: Option<u32>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let config = loaded_config(&state)?;
+    let size = size.unwrap_or(assets::DEFAULT_THUMBNAIL_SIZE);
+    let png = state
+        .assets
+        .thumbnail(&config, &image_id, size)
+        .map_err(|e| {
+            warn!("Failed to make a thumbnail of image {}: {}", image_id, e);
+            e
+        })?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "id": image_id,
+            "size": size,
+            "data_url": assets::png_data_url(&png),
+        })),
+    })
+}
+
+/// Images of the loaded configuration that are missing or unreadable, and
+/// groups of images with the same content.
+#[tauri::command]
+pub fn get_missing_images(state: State<AppState>) -> Result<CommandResponse, String> {
+    let config = loaded_config(&state)?;
+    let report = state.assets.report(&config);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!(
+            "{} of {} images missing or unreadable, {} duplicate groups",
+            report.missing.len(),
+            report.images,
+            report.duplicates.len()
+        )),
+        data: Some(serde_json::to_value(report).map_err(|e| e.to_string())?),
+    })
+}
+
+#[tauri::command]
+pub fn verify_images(
+    background: Option<bool>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let config = state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())?;
+
+    if background.unwrap_or(false) {
+        let task_id =
+            state
+                .tasks
+                .spawn(app_handle, "verify_images", "Verify images", move |task| {
+                    let report = image_verification_report(&config, |done, total| {
+                        task.progress(
+                            done as f32 / total as f32,
+                            format!("Verified {} of {} images", done, total),
+                        );
+                        !task.is_cancelled()
+                    });
+                    Ok(report)
+                });
+        return Ok(CommandResponse {
+            success: true,
+            message: Some("Image verification started".to_string()),
+            data: Some(serde_json::json!({ "task_id": task_id })),
+        });
+    }
+
+    let report = image_verification_report(&config, |_, _| true);
+    let invalid = report["errors"].as_array().map_or(0, Vec::len);
+    Ok(CommandResponse {
+        success: invalid == 0,
+        message: Some(format!(
+            "Verified {} images, {} invalid, {} duplicate groups",
+            report["images"].as_array().map_or(0, Vec::len),
+            invalid,
+            report["duplicates"].as_array().map_or(0, Vec::len)
+        )),
+        data: Some(report),
+    })
+}
+
+fn image_verification_report<F>(config: &QontinuiConfig, on_progress: F) -> serde_json::Value
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let (results, duplicates) = config.image_store.verify_with(on_progress);
+    let mut images = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(digest) => images.push(digest),
+            Err(e) => {
+                warn!("Image verification failed: {}", e);
+                errors.push(e);
+            }
+        }
+    }
+
+    serde_json::json!({
+        "images": images,
+        "errors": errors,
+        "duplicates": duplicates,
+    })
+}
+
+#[tauri::command]
+pub fn list_background_tasks(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::to_value(state.tasks.list()).map_err(|e| e.to_string())?),
+    })
+}
+
+#[tauri::command]
+pub fn cancel_background_task(
+    task_id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    state.tasks.cancel(&task_id)?;
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Cancellation requested for task {}", task_id)),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_resource_usage(state: State<AppState>) -> Result<CommandResponse, String> {
+    let usage = resources::collect_usage(&state);
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::to_value(usage).map_err(|e| e.to_string())?),
+    })
+}
+
+#[tauri::command]
+pub fn get_health_report(state: State<AppState>) -> Result<CommandResponse, String> {
+    let python_running = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|bridge| bridge.is_running())
+        .unwrap_or(false);
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "python_running": python_running,
+            "config_loaded": state.current_config.lock().unwrap().is_some(),
+            "startup": startup::report(),
+            "resources": resources::collect_usage(&state),
+        })),
+    })
+}
+
+/// Whether commands for debugging the runner itself are available: in
+/// debug builds, or in release builds when `QONTINUI_DEBUG_COMMANDS` is set.
+fn debug_commands_enabled() -> bool {
+    cfg!(debug_assertions) || std::env::var_os("QONTINUI_DEBUG_COMMANDS").is_some()
+}
+
+/// Writes a synthetic configuration of the given size to `path`. Not exposed
+/// in the UI; used to produce performance and regression fixtures.
+/// Available where `dump_app_state` is.
+#[tauri::command]
+pub fn generate_fixture_config(
+    spec: Option<FixtureSpec>,
+    path: String,
+) -> Result<CommandResponse, String> {
+    if !debug_commands_enabled() {
+        return Err("generate_fixture_config is disabled in release builds".to_string());
+    }
+    let spec = spec.unwrap_or_default();
+    spec.validate()?;
+    let config = fixtures::generate(&spec);
+    let content = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, &content)
+        .map_err(|e| format!("Failed to write fixture to {}: {}", path, e))?;
+    info!(
+        "Generated fixture config {} ({} bytes)",
+        path,
+        content.len()
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Fixture written to {}", path)),
+        data: Some(serde_json::json!({
+            "path": path,
+            "size_bytes": content.len(),
+        })),
+    })
+}
+
+/// Dumps the runner's internal state for bug reports. Available in debug
+/// builds, or in release builds when `QONTINUI_DEBUG_COMMANDS` is set.
+#[tauri::command]
+pub fn dump_app_state(state: State<AppState>) -> Result<CommandResponse, String> {
+    if !debug_commands_enabled() {
+        return Err("dump_app_state is disabled in release builds".to_string());
+    }
+
+    let config = state.current_config.lock().unwrap().clone();
+    let bridge = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|bridge| bridge.snapshot());
+
+    let snapshot = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "captured_at": chrono::Local::now().to_rfc3339(),
+        "config": config.as_ref().map(|config| serde_json::json!({
+            "name": config.metadata.name,
+            "version": config.version,
+            "summary": config.summary(),
+            "image_count": config.image_store.len(),
+            "source_bytes": *state.config_source_bytes.lock().unwrap(),
+        })),
+        "bridge": bridge,
+        "python_environments": environment::cached(),
+        "background_tasks": state.tasks.list(),
+        "session": state.session.current(),
+        "resources": resources::collect_usage(&state),
+        "startup": startup::report(),
+    });
+
+    info!("Dumped app state for diagnostics");
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(snapshot),
+    })
+}
+
+#[tauri::command]
+pub fn set_resource_limits(
+    limits: ResourceLimits,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    info!("Updating resource limits: {:?}", limits);
+    *state.resource_limits.lock().unwrap() = limits;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Resource limits updated".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_input_limits(state: State<AppState>) -> Result<InputLimits, String> {
+    Ok(state.input_limits.lock().unwrap().clone())
+}
+
+/// Updates the synthetic input caps. They apply from the next execution.
+#[tauri::command]
+pub fn set_input_limits(
+    limits: InputLimits,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    info!("Updating input limits: {:?}", limits);
+    *state.input_limits.lock().unwrap() = limits;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Input limits updated".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_safe_mode(state: State<AppState>) -> Result<SafeMode, String> {
+    Ok(state.safe_mode.lock().unwrap().clone())
+}
+
+/// Updates the safe mode bounds. They apply from the next execution; an
+/// enabled window is checked now so a typo shows up before a run fails.
+#[tauri::command]
+pub fn set_safe_mode(
+    safe_mode: SafeMode,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    info!("Updating safe mode: {:?}", safe_mode);
+    let bounds = safe_mode
+        .resolve()
+        .map_err(|e| format!("Safe mode: {}", e))?;
+    *state.safe_mode.lock().unwrap() = safe_mode;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Safe mode updated".to_string()),
+        data: Some(serde_json::json!({ "bounds": bounds })),
+    })
+}
+
+#[tauri::command]
+pub fn get_restart_policy(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "settings": *state.restarts.settings.lock().unwrap(),
+            "attempts": state.restarts.attempts(),
+        })),
+    })
+}
+
+/// Sets whether a crashed executor is restarted: `never`, `on_crash`, or
+/// `always` (clean exits too), with the backoff between attempts.
+#[tauri::command]
+pub fn set_restart_policy(
+    settings: RestartSettings,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    if settings.initial_delay_ms > settings.max_delay_ms {
+        return Err("The initial restart delay can't exceed the maximum".to_string());
+    }
+    info!("Updating executor restart policy: {:?}", settings);
+    *state.restarts.settings.lock().unwrap() = settings;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Restart policy updated".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_shutdown_settings(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(*state.shutdown.lock().unwrap())),
+    })
+}
+
+/// Sets how long a stopped executor gets to exit on its own, and then on
+/// SIGTERM, before it's killed along with everything it started.
+#[tauri::command]
+pub fn set_shutdown_settings(
+    settings: ShutdownSettings,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    info!("Updating executor shutdown settings: {:?}", settings);
+    *state.shutdown.lock().unwrap() = settings;
+    save_profile_settings(&state)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Shutdown settings updated".to_string()),
+        data: None,
+    })
+}
+
+fn current_settings(state: &AppState) -> ProfileSettings {
+    ProfileSettings {
+        resource_limits: state.resource_limits.lock().unwrap().clone(),
+        input_limits: state.input_limits.lock().unwrap().clone(),
+        safe_mode: state.safe_mode.lock().unwrap().clone(),
+        restart: state.restarts.settings.lock().unwrap().clone(),
+        shutdown: state.shutdown.lock().unwrap().clone(),
+        idle_policy: state.idle_policy.lock().unwrap().clone(),
+        locale: state.translations.chosen(),
+        python_interpreter: environment::interpreter(),
+        executor_launch: state.executor_launch.lock().unwrap().clone(),
+    }
+}
+
+fn save_profile_settings(state: &AppState) -> Result<(), String> {
+    state
+        .profiles
+        .active()
+        .save_settings(&current_settings(state))
+}
+
+#[tauri::command]
+pub fn list_profiles(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "profiles": state.profiles.list(),
+            "active": state.profiles.active().name,
+        })),
+    })
+}
+
+/// Creates a profile with default settings, or a copy of the current ones
+/// with `copy_settings`. It isn't switched to.
+#[tauri::command]
+pub fn create_profile(
+    name: String,
+    copy_settings: Option<bool>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let settings = if copy_settings.unwrap_or(false) {
+        current_settings(&state)
+    } else {
+        ProfileSettings::default()
+    };
+    let profile = state.profiles.create(&name, &settings)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Profile '{}' created", name)),
+        data: Some(serde_json::json!(profile)),
+    })
+}
+
+/// Makes `name` the active profile, applying its settings and variables.
+/// Not allowed while an execution is running.
+#[tauri::command]
+pub fn switch_profile(name: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    if state.idle_monitor.is_executing() {
+        return Err("Cannot switch profiles while an execution is running".to_string());
+    }
+
+    let profile = state.profiles.set_active(&name)?;
+    let settings = profile.load_settings();
+    *state.resource_limits.lock().unwrap() = settings.resource_limits;
+    *state.input_limits.lock().unwrap() = settings.input_limits;
+    *state.safe_mode.lock().unwrap() = settings.safe_mode;
+    *state.restarts.settings.lock().unwrap() = settings.restart;
+    *state.shutdown.lock().unwrap() = settings.shutdown;
+    *state.idle_policy.lock().unwrap() = settings.idle_policy;
+    if let Err(e) = state.translations.choose(settings.locale.as_deref()) {
+        warn!("Ignoring profile locale: {}", e);
+    }
+    environment::set_interpreter(settings.python_interpreter);
+    *state.executor_launch.lock().unwrap() = settings.executor_launch;
+    state.variables.reopen(profile.variables_path());
+    state.run_streams.set_dir(profile.streams_dir());
+    state
+        .video_recorder
+        .set_dir(profile.recordings_dir.join("videos"));
+    state.recordings.set_dir(profile.recordings_dir.clone());
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Switched to profile '{}'", name)),
+        data: Some(serde_json::json!(profile)),
+    })
+}
+
+/// Strings for `locale`, or the current locale when omitted, with gaps
+/// filled from the language's and then the English catalog.
+#[tauri::command]
+pub fn get_translations(
+    locale: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let locale = match locale {
+        Some(locale) => {
+            crate::i18n::normalize(&locale).ok_or_else(|| format!("Invalid locale '{}'", locale))?
+        }
+        None => state.translations.locale(),
+    };
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "locale": locale,
+            "available": state.translations.available(),
+            "strings": state.translations.catalog(&locale),
+        })),
+    })
+}
+
+/// Sets the UI and report locale for the active profile; `None` goes back
+/// to the OS locale.
+#[tauri::command]
+pub fn set_locale(
+    locale: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    state.translations.choose(locale.as_deref())?;
+    save_profile_settings(&state)?;
+    let locale = state.translations.locale();
+    info!("Locale set to {}", locale);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Locale set to {}", locale)),
+        data: Some(serde_json::json!({ "locale": locale })),
+    })
+}
+
+#[tauri::command]
+pub fn handle_error(error: UserFacingError, app_handle: AppHandle) -> Result<(), String> {
+    error!("User-facing error: {:?}", error);
+
+    // Emit error event to frontend
+    app_handle
+        .emit("error", &error)
+        .map_err(|e| format!("Failed to emit error event: {}", e))?;
+
+    Ok(())
+}
+
+/// Returns the dialog for one error code, or every dialog when `code` is
+/// omitted, so error copy can be reviewed without reproducing each failure.
+#[tauri::command]
+pub fn preview_error(code: Option<String>) -> Result<CommandResponse, String> {
+    let previews = crate::error::preview_errors();
+
+    let data = match code {
+        Some(code) => {
+            let preview = previews
+                .into_iter()
+                .find(|p| p.error_code == code)
+                .ok_or_else(|| format!("Unknown error code: {}", code))?;
+            serde_json::to_value(preview)
+        }
+        None => serde_json::to_value(previews),
+    }
+    .map_err(|e| format!("Failed to serialize error preview: {}", e))?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(data),
+    })
+}
+
+#[tauri::command]
+pub fn get_monitors(app_handle: AppHandle) -> Result<CommandResponse, String> {
+    info!("Detecting system monitors");
+
+    // Real displays from the main window, or a simulated layout in tests
+    let monitors = monitors::detect(&app_handle)?;
+
+    let monitor_count = monitors.len();
+    let monitor_indices: Vec<i32> = (0..monitor_count as i32).collect();
+
+    info!("Detected {} monitors", monitor_count);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Detected {} monitors", monitor_count)),
+        data: Some(serde_json::json!({
+            "count": monitor_count,
+            "indices": monitor_indices,
+            "monitors": monitors,
+            "simulated": monitors::is_simulated(),
+        })),
+    })
+}
+
+/// Replaces the real displays with a layout fixture so multi-monitor
+/// behaviour can be tested on a single screen. Pass no path to go back to
+/// the real displays.
+#[tauri::command]
+pub fn set_simulated_monitor_layout(path: Option<String>) -> Result<CommandResponse, String> {
+    let layout = path
+        .as_deref()
+        .map(|path| monitors::load_layout(std::path::Path::new(path)))
+        .transpose()?;
+    let count = layout.as_ref().map(Vec::len);
+    monitors::set_simulated_layout(layout);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(match count {
+            Some(count) => format!("Simulating {} monitors", count),
+            None => "Using real monitors".to_string(),
+        }),
+        data: None,
+    })
+}
+
+/// Converts a monitor-relative point into virtual desktop coordinates.
+#[tauri::command]
+pub fn translate_coordinates(
+    monitor_index: usize,
+    x: f64,
+    y: f64,
+    logical: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    let monitors = monitors::detect(&app_handle)?;
+    let (global_x, global_y) =
+        monitors::monitor_to_global(&monitors, monitor_index, x, y, logical.unwrap_or(false))?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "x": global_x,
+            "y": global_y,
+            "monitor": monitors::global_to_monitor(&monitors, global_x, global_y),
+        })),
+    })
+}
+
+/// Lists UI elements matching `query` through the OS accessibility API.
+#[tauri::command]
+pub async fn query_elements(query: ElementQuery) -> Result<CommandResponse, String> {
+    info!("Querying accessibility elements: {:?}", query);
+
+    // Walking the accessibility tree can take seconds, so keep it off the
+    // main thread
+    let elements =
+        tauri::async_runtime::spawn_blocking(move || accessibility::query_elements(&query))
+            .await
+            .map_err(|e| format!("Accessibility query failed: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Found {} elements", elements.len())),
+        data: Some(serde_json::json!({ "elements": elements })),
+    })
+}
+
+#[tauri::command]
+pub async fn click_element(
+    query: ElementQuery,
+    index: Option<usize>,
+) -> Result<CommandResponse, String> {
+    info!("Clicking accessibility element: {:?}", query);
+
+    let element =
+        tauri::async_runtime::spawn_blocking(move || accessibility::click_element(&query, index))
+            .await
+            .map_err(|e| format!("Accessibility click failed: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Clicked {} '{}'", element.role, element.name)),
+        data: Some(serde_json::to_value(element).map_err(|e| e.to_string())?),
+    })
+}
+
+#[tauri::command]
+pub async fn type_into_element(
+    query: ElementQuery,
+    index: Option<usize>,
+    text: String,
+) -> Result<CommandResponse, String> {
+    info!("Typing into accessibility element: {:?}", query);
+
+    let element = tauri::async_runtime::spawn_blocking(move || {
+        accessibility::type_into_element(&query, index, &text)
+    })
+    .await
+    .map_err(|e| format!("Accessibility typing failed: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Typed into {} '{}'", element.role, element.name)),
+        data: Some(serde_json::to_value(element).map_err(|e| e.to_string())?),
+    })
+}
+
+/// Covers a monitor with an overlay on which the user drags a rectangle,
+/// and returns it in physical pixels, relative to the monitor and on the
+/// virtual desktop. Unsuccessful when cancelled with Escape.
+#[tauri::command]
+pub async fn select_screen_region(
+    monitor_index: Option<usize>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    let monitor = monitors::find(&app_handle, monitor_index.unwrap_or(0))?;
+
+    match region_select::select(&app_handle, monitor).await? {
+        Some(selection) => Ok(CommandResponse {
+            success: true,
+            message: Some(format!(
+                "Selected {}x{} at ({}, {})",
+                selection.region.width,
+                selection.region.height,
+                selection.region.x,
+                selection.region.y
+            )),
+            data: Some(serde_json::to_value(selection).map_err(|e| e.to_string())?),
+        }),
+        None => Ok(CommandResponse {
+            success: false,
+            message: Some("Region selection cancelled".to_string()),
+            data: None,
+        }),
+    }
+}
+
+/// Captures `rect` of a monitor (physical pixels relative to the monitor)
+/// and returns it as a base64 PNG.
+#[tauri::command]
+pub async fn capture_region(
+    rect: Region,
+    monitor_index: Option<usize>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    let monitor = monitors::find(&app_handle, monitor_index.unwrap_or(0))?;
+    let png = tauri::async_runtime::spawn_blocking(move || capture::capture_region(&monitor, rect))
+        .await
+        .map_err(|e| format!("Capture failed: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "png": STANDARD.encode(png),
+            "width": rect.width,
+            "height": rect.height,
+        })),
+    })
+}
+
+/// Takes a screenshot of a whole monitor without the Python executor.
+/// Written to `save_path` as a PNG when given, returned as base64 otherwise.
+#[tauri::command]
+pub async fn capture_screenshot(
+    monitor_index: Option<usize>,
+    save_path: Option<String>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    let monitor = monitors::find(&app_handle, monitor_index.unwrap_or(0))?;
+    info!("Capturing a screenshot of monitor {}", monitor.index);
+    let (width, height) = (monitor.width, monitor.height);
+    let png = tauri::async_runtime::spawn_blocking(move || capture::capture_monitor(&monitor))
+        .await
+        .map_err(|e| format!("Capture failed: {}", e))??;
+
+    let data = match save_path {
+        Some(path) => {
+            std::fs::write(&path, &png)
+                .map_err(|e| format!("Failed to save screenshot to {}: {}", path, e))?;
+            serde_json::json!({ "path": path, "width": width, "height": height })
+        }
+        None => serde_json::json!({
+            "png": STANDARD.encode(png),
+            "width": width,
+            "height": height,
+        }),
+    };
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Captured {}x{} screenshot", width, height)),
+        data: Some(data),
+    })
+}
+
+/// Starts emitting scaled-down frames of a monitor as `preview-frame`
+/// events, by default of the monitor executions run on.
+#[tauri::command]
+pub fn start_preview(
+    monitor_index: Option<usize>,
+    settings: Option<PreviewSettings>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let monitor_index = monitor_index.unwrap_or_else(|| {
+        state
+            .session
+            .current()
+            .monitor_index
+            .map_or(0, |index| index.max(0) as usize)
+    });
+    let monitor = monitors::find(&app_handle, monitor_index)?;
+    state
+        .preview
+        .start(&app_handle, monitor, settings.unwrap_or_default())?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Previewing monitor {}", monitor_index)),
+        data: Some(serde_json::json!(state.preview.status())),
+    })
+}
+
+#[tauri::command]
+pub fn stop_preview(state: State<AppState>) -> Result<CommandResponse, String> {
+    state.preview.stop();
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Preview stopped".to_string()),
+        data: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_preview_status(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state.preview.status())),
+    })
+}
+
+/// Replays a recorded trace of mouse and keyboard input (JSON Lines) at
+/// `speed` times its recorded pace. `replay-finished` is emitted when it
+/// ends.
+#[tauri::command]
+pub fn start_replay(
+    path: String,
+    speed: Option<f64>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let total = state
+        .replay
+        .start(&app_handle, &path, speed.unwrap_or(1.0))?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Replaying {} events", total)),
+        data: Some(serde_json::json!(state.replay.status())),
+    })
+}
+
+#[tauri::command]
+pub fn pause_replay(state: State<AppState>) -> Result<CommandResponse, String> {
+    let status = state.replay.pause()?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Replay paused".to_string()),
+        data: Some(serde_json::json!(status)),
+    })
+}
+
+#[tauri::command]
+pub fn resume_replay(state: State<AppState>) -> Result<CommandResponse, String> {
+    let status = state.replay.resume()?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Replay resumed".to_string()),
+        data: Some(serde_json::json!(status)),
+    })
+}
+
+#[tauri::command]
+pub fn stop_replay(state: State<AppState>) -> Result<CommandResponse, String> {
+    let status = state.replay.stop();
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Replay stopped".to_string()),
+        data: Some(serde_json::json!(status)),
+    })
+}
+
+#[tauri::command]
+pub fn get_replay_status(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(state.replay.status())),
+    })
+}
+
+/// Captures `rect` of a monitor (physical pixels relative to the monitor) and
+/// recognises its text. Word and line boxes use the same coordinates.
+#[tauri::command]
+pub async fn ocr_region(
+    monitor_index: usize,
+    rect: Region,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    info!("Running OCR on monitor {} region {:?}", monitor_index, rect);
+
+    let monitor = monitors::find(&app_handle, monitor_index)?;
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let image = capture::capture_image(&monitor, rect)?;
+        ocr::recognize(&image, rect)
+    })
+    .await
+    .map_err(|e| format!("OCR failed: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Recognised {} lines", result.lines.len())),
+        data: Some(serde_json::to_value(result).map_err(|e| e.to_string())?),
     })
 }
 
+/// Searches a monitor (or a region of it) for a configuration image without
+/// the Python executor. Matches are in monitor pixels, best first.
 #[tauri::command]
-pub fn stop_python_executor(state: State<AppState>) -> Result<CommandResponse, String> {
-    info!("Stopping Python executor");
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+pub async fn find_image_on_screen(
+    image_id: String,
+    monitor_index: Option<usize>,
+    region: Option<Region>,
+    threshold: Option<f32>,
+    max_results: Option<usize>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    let config = state
+        .current_config
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "No configuration loaded".to_string())?;
+    let template = state.assets.decoded(&config, &image_id)?;
 
-    if let Some(ref mut bridge) = *bridge_lock {
-        bridge.stop().map_err(|e| {
-            error!("Failed to stop Python executor: {}", e);
-            format!("Failed to stop Python executor: {}", e)
-        })?;
-        info!("Python executor stopped successfully");
-    }
+    let monitor_index = monitor_index.unwrap_or(0);
+    let monitor = monitors::find(&app_handle, monitor_index)?;
+    let region = region.unwrap_or(Region {
+        x: 0,
+        y: 0,
+        width: monitor.width,
+        height: monitor.height,
+    });
+    let threshold = threshold.unwrap_or(0.8);
+    info!(
+        "Searching monitor {} region {:?} for image {}",
+        monitor_index, region, image_id
+    );
 
-    *bridge_lock = None;
+    let started = std::time::Instant::now();
+    let matches = tauri::async_runtime::spawn_blocking(move || {
+        let needle = GrayImage::from_rgb(&template);
+        let haystack = GrayImage::decode_png(&capture::capture_region(&monitor, region)?)?;
+        let mut matches =
+            matching::find_template(&haystack, &needle, threshold, max_results.unwrap_or(10))?;
+        to_monitor_pixels(&mut matches, region, &haystack);
+        Ok::<_, String>(matches)
+    })
+    .await
+    .map_err(|e| format!("Image search failed: {}", e))??;
 
     Ok(CommandResponse {
         success: true,
-        message: Some("Python executor stopped".to_string()),
-        data: None,
+        message: Some(format!("Found {} matches", matches.len())),
+        data: Some(serde_json::json!({
+            "matches": matches,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        })),
     })
 }
 
+/// Looks for a configuration image on a monitor once, for tuning its
+/// threshold (0.8 by default) without running a workflow: the matches, the
+/// best score even when it's below the threshold, and the near misses. With
+/// `engine` `executor` the running executor searches the way its actions
+/// do; by default the runner searches itself.
 #[tauri::command]
-pub fn start_execution(
-    process_id: Option<String>,
-    monitor_index: Option<i32>,
-    state: State<AppState>,
+pub async fn test_image_match(
+    image_id: String,
+    monitor_index: Option<usize>,
+    threshold: Option<f32>,
+    engine: Option<String>,
+    timeout_ms: Option<u64>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
 ) -> Result<CommandResponse, String> {
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+    let threshold = threshold.unwrap_or(0.8);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("Threshold {} is outside 0 to 1", threshold));
+    }
+    let monitor_index = monitor_index.unwrap_or(0);
+    let engine = engine.unwrap_or_else(|| "native".to_string());
+    info!(
+        "Testing image {} on monitor {} at threshold {} ({})",
+        image_id, monitor_index, threshold, engine
+    );
 
-    if let Some(ref mut bridge) = *bridge_lock {
-        if !bridge.is_running() {
-            return Err("Python executor not running".to_string());
+    let started = std::time::Instant::now();
+    let test = match engine.as_str() {
+        "native" => {
+            let config = loaded_config(&state)?;
+            let template = state.assets.decoded(&config, &image_id)?;
+            let monitor = monitors::find(&app_handle, monitor_index)?;
+            tauri::async_runtime::spawn_blocking(move || {
+                let region = Region {
+                    x: 0,
+                    y: 0,
+                    width: monitor.width,
+                    height: monitor.height,
+                };
+                let needle = GrayImage::from_rgb(&template);
+                let haystack = GrayImage::decode_png(&capture::capture_monitor(&monitor)?)?;
+                let mut test = matching::test_template(&haystack, &needle, threshold, 10)?;
+                to_monitor_pixels(&mut test.matches, region, &haystack);
+                to_monitor_pixels(&mut test.near_misses, region, &haystack);
+                Ok::<_, String>(test)
+            })
+            .await
+            .map_err(|e| format!("Image search failed: {}", e))??
         }
-
-        // Build params
-        let mut params = serde_json::Map::new();
-
-        // Add monitor index (default to 0 if not provided)
-        params.insert(
-            "monitor_index".to_string(),
-            serde_json::json!(monitor_index.unwrap_or(0)),
-        );
-
-        // Add workflow_id (required)
-        if let Some(pid) = process_id {
-            params.insert("workflow_id".to_string(), serde_json::json!(pid));
-        } else {
-            return Err("Workflow ID is required".to_string());
+        "executor" => {
+            let params = serde_json::json!({
+                "image_id": image_id,
+                "monitor_index": monitor_index,
+                "threshold": threshold,
+            });
+            let pending = send_awaiting(&state, "match_image", Some(params))?;
+            let response = await_response(pending, timeout_ms).await?;
+            if !response.success {
+                return Ok(response);
+            }
+            state_machine::parse::<MatchTest>("match_image", response.data)?
         }
+        other => return Err(format!("Unknown matching engine: {}", other)),
+    };
 
-        bridge
-            .start_execution_with_params(Some(serde_json::Value::Object(params)))
-            .map_err(|e| format!("Failed to start execution: {}", e))?;
+    Ok(CommandResponse {
+        success: true,
+        message: Some(match test.best_score {
+            Some(best) => format!(
+                "{} matches at {}, best score {:.3}",
+                test.matches.len(),
+                threshold,
+                best
+            ),
+            None => "Nothing on screen resembles the image".to_string(),
+        }),
+        data: Some(serde_json::json!({
+            "engine": engine,
+            "threshold": threshold,
+            "matches": test.matches,
+            "best_score": test.best_score,
+            "near_misses": test.near_misses,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        })),
+    })
+}
 
-        Ok(CommandResponse {
-            success: true,
-            message: Some("Execution started".to_string()),
-            data: None,
-        })
-    } else {
-        Err("Python executor not initialized".to_string())
+/// Maps matches in a capture of `region` to monitor pixels. The capture
+/// can be larger than the region (Retina).
+fn to_monitor_pixels(matches: &mut [Match], region: Region, capture: &GrayImage) {
+    let (sx, sy) = (
+        region.width as f64 / capture.width as f64,
+        region.height as f64 / capture.height as f64,
+    );
+    for m in matches {
+        m.x = region.x as u32 + (m.x as f64 * sx).round() as u32;
+        m.y = region.y as u32 + (m.y as f64 * sy).round() as u32;
+        m.width = (m.width as f64 * sx).round() as u32;
+        m.height = (m.height as f64 * sy).round() as u32;
     }
 }
 
+/// Reads one pixel at monitor-relative physical coordinates.
 #[tauri::command]
-pub fn stop_execution(state: State<AppState>) -> Result<CommandResponse, String> {
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+pub async fn get_pixel_color(
+    x: i32,
+    y: i32,
+    monitor: Option<usize>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    let monitor = monitors::find(&app_handle, monitor.unwrap_or(0))?;
+    let region = Region {
+        x,
+        y,
+        width: 1,
+        height: 1,
+    };
 
-    if let Some(ref mut bridge) = *bridge_lock {
-        bridge
-            .stop_execution()
-            .map_err(|e| format!("Failed to stop execution: {}", e))?;
+    let pixel = tauri::async_runtime::spawn_blocking(move || {
+        let image = capture::capture_image(&monitor, region)?;
+        image
+            .pixels
+            .first()
+            .copied()
+            .ok_or_else(|| "Capture returned no pixels".to_string())
+    })
+    .await
+    .map_err(|e| format!("Pixel read failed: {}", e))??;
+    let color = Color::from(pixel);
 
-        Ok(CommandResponse {
-            success: true,
-            message: Some("Execution stopped".to_string()),
-            data: None,
-        })
-    } else {
-        Err("Python executor not initialized".to_string())
-    }
+    Ok(CommandResponse {
+        success: true,
+        message: Some(color.to_string()),
+        data: Some(serde_json::json!({
+            "color": color.to_string(),
+            "r": color.r,
+            "g": color.g,
+            "b": color.b,
+        })),
+    })
 }
 
+/// Polls `region` until at least `min_fraction` of its pixels are within
+/// `tolerance` (per channel) of `color`, or `timeout_ms` passes. A timeout
+/// is reported as `success: false` with the last sample.
 #[tauri::command]
-pub fn get_executor_status(state: State<AppState>) -> Result<CommandResponse, String> {
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
-
-    if let Some(ref mut bridge) = *bridge_lock {
-        let is_running = bridge.is_running();
+pub async fn wait_for_color(
+    region: Region,
+    color: String,
+    tolerance: Option<u8>,
+    timeout_ms: Option<u64>,
+    min_fraction: Option<f32>,
+    monitor: Option<usize>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    let target = Color::parse(&color)?;
+    let monitor = monitors::find(&app_handle, monitor.unwrap_or(0))?;
+    let tolerance = tolerance.unwrap_or(10);
+    let min_fraction = min_fraction.unwrap_or(0.9);
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(5000));
+    info!(
+        "Waiting up to {:?} for {} in {:?} on monitor {}",
+        timeout, target, region, monitor.index
+    );
 
-        if is_running {
-            bridge
-                .get_status()
-                .map_err(|e| format!("Failed to get status: {}", e))?;
+    let started = std::time::Instant::now();
+    let (matched, last) = tauri::async_runtime::spawn_blocking(move || loop {
+        let image = capture::capture_image(&monitor, region)?;
+        let sample = color::sample(&image, &target, tolerance);
+        if sample.fraction >= min_fraction {
+            return Ok::<_, String>((true, sample));
+        }
+        if started.elapsed() >= timeout {
+            return Ok((false, sample));
         }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    })
+    .await
+    .map_err(|e| format!("Colour wait failed: {}", e))??;
 
-        Ok(CommandResponse {
-            success: true,
-            message: None,
-            data: Some(serde_json::json!({
-                "python_running": is_running,
-                "config_loaded": state.current_config.lock().unwrap().is_some()
-            })),
-        })
-    } else {
-        Ok(CommandResponse {
-            success: true,
-            message: None,
-            data: Some(serde_json::json!({
-                "python_running": false,
-                "config_loaded": state.current_config.lock().unwrap().is_some()
-            })),
-        })
-    }
+    Ok(CommandResponse {
+        success: matched,
+        message: Some(if matched {
+            format!("{} appeared", target)
+        } else {
+            format!("{} not seen within {:?}", target, timeout)
+        }),
+        data: Some(serde_json::json!({
+            "matched": matched,
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+            "sample": last,
+        })),
+    })
 }
 
+/// Diffs two screenshots of the same size. The heatmap goes to
+/// `heatmap_path`, or a temporary file when omitted.
 #[tauri::command]
-pub fn get_current_configuration(state: State<AppState>) -> Result<QontinuiConfig, String> {
-    state
-        .current_config
-        .lock()
-        .unwrap()
-        .clone()
-        .ok_or_else(|| "No configuration loaded".to_string())
+pub async fn compare_screenshots(
+    path_a: String,
+    path_b: String,
+    threshold: Option<u8>,
+    heatmap_path: Option<String>,
+) -> Result<CommandResponse, String> {
+    let heatmap_path = heatmap_path
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("qontinui-diff-{}.png", uuid::Uuid::new_v4()))
+        });
+    info!("Comparing screenshots {} and {}", path_a, path_b);
+
+    let heatmap = heatmap_path.clone();
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        visual_diff::compare_files(
+            std::path::Path::new(&path_a),
+            std::path::Path::new(&path_b),
+            threshold.unwrap_or(16),
+            &heatmap,
+        )
+    })
+    .await
+    .map_err(|e| format!("Screenshot comparison failed: {}", e))??;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("{:.2}% of pixels changed", summary.score * 100.0)),
+        data: Some(serde_json::json!({
+            "summary": summary,
+            "heatmap": heatmap_path,
+        })),
+    })
 }
 
+/// Diffs every screenshot two runs have in common (matched by path relative
+/// to each run directory) in the background, most changed first.
 #[tauri::command]
-pub fn handle_error(error: UserFacingError, app_handle: AppHandle) -> Result<(), String> {
-    error!("User-facing error: {:?}", error);
+pub fn compare_run_screenshots(
+    run_a: String,
+    run_b: String,
+    threshold: Option<u8>,
+    output_dir: Option<String>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let output_dir = output_dir.map(std::path::PathBuf::from).unwrap_or_else(|| {
+        std::env::temp_dir().join(format!("qontinui-run-diff-{}", uuid::Uuid::new_v4()))
+    });
+    info!("Comparing run screenshots in {} and {}", run_a, run_b);
 
-    // Emit error event to frontend
-    app_handle
-        .emit("error", &error)
-        .map_err(|e| format!("Failed to emit error event: {}", e))?;
+    let task_id = state.tasks.spawn(
+        app_handle,
+        "compare_runs",
+        &format!("Compare runs {} and {}", run_a, run_b),
+        move |task| {
+            let comparison = visual_diff::compare_runs(
+                std::path::Path::new(&run_a),
+                std::path::Path::new(&run_b),
+                threshold.unwrap_or(16),
+                &output_dir,
+                |done, total| {
+                    task.progress(
+                        done as f32 / total as f32,
+                        format!("Compared {}/{} screenshots", done, total),
+                    );
+                    !task.is_cancelled()
+                },
+            )?;
+            serde_json::to_value(comparison).map_err(|e| e.to_string())
+        },
+    );
 
-    Ok(())
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Run comparison started".to_string()),
+        data: Some(serde_json::json!({ "task_id": task_id })),
+    })
 }
 
 #[tauri::command]
-pub fn get_monitors(app_handle: AppHandle) -> Result<CommandResponse, String> {
-    info!("Detecting system monitors");
+pub async fn get_clipboard(state: State<'_, AppState>) -> Result<CommandResponse, String> {
+    let clipboard = state.clipboard.clone();
+    let text = tauri::async_runtime::spawn_blocking(move || clipboard.get())
+        .await
+        .map_err(|e| format!("Failed to read clipboard: {}", e))??;
 
-    // Get available monitors from the main window
-    let monitors = app_handle
-        .get_webview_window("main")
-        .ok_or("Failed to get main window")?
-        .available_monitors()
-        .map_err(|e| format!("Failed to get monitors: {}", e))?;
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({ "text": text })),
+    })
+}
 
-    let monitor_count = monitors.len();
-    let monitor_indices: Vec<i32> = (0..monitor_count as i32).collect();
+#[tauri::command]
+pub async fn set_clipboard(
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse, String> {
+    let clipboard = state.clipboard.clone();
+    tauri::async_runtime::spawn_blocking(move || clipboard.set(&text))
+        .await
+        .map_err(|e| format!("Failed to write clipboard: {}", e))??;
 
-    info!("Detected {} monitors", monitor_count);
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Clipboard updated".to_string()),
+        data: None,
+    })
+}
 
+#[tauri::command]
+pub fn get_clipboard_history(state: State<AppState>) -> Result<CommandResponse, String> {
     Ok(CommandResponse {
         success: true,
-        message: Some(format!("Detected {} monitors", monitor_count)),
+        message: None,
         data: Some(serde_json::json!({
-            "count": monitor_count,
-            "indices": monitor_indices,
+            "entries": state.clipboard.history(),
+            "capturing": state.clipboard.is_capturing(),
         })),
     })
 }
 
+/// Turns recording of clipboard changes during executions on or off.
+#[tauri::command]
+pub fn set_clipboard_capture(
+    enabled: bool,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    state.clipboard.set_capture(&app_handle, enabled);
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!(
+            "Clipboard capture {}",
+            if enabled { "enabled" } else { "disabled" }
+        )),
+        data: None,
+    })
+}
+
 #[tauri::command]
 pub async fn check_for_updates(
     #[allow(unused_variables)] app_handle: AppHandle,
@@ -346,7 +4168,7 @@ pub fn start_recording(
     state: State<AppState>,
 ) -> Result<CommandResponse, String> {
     info!("Starting recording with base_dir: {}", base_dir);
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+    let mut bridge_lock = state.executor.lock().unwrap();
 
     if let Some(ref mut bridge) = *bridge_lock {
         if !bridge.is_running() {
@@ -356,12 +4178,19 @@ pub fn start_recording(
         bridge
             .start_recording(&base_dir)
             .map_err(|e| format!("Failed to start recording: {}", e))?;
+        drop(bridge_lock);
+
+        let mut entry = RecordingEntry::new(RecordingKind::Executor, base_dir.clone().into());
+        entry.run_id = state.run_streams.current_run(DEFAULT_SESSION);
+        describe_recording(&state, &mut entry);
+        let entry = state.recordings.begin(entry)?;
 
         Ok(CommandResponse {
             success: true,
             message: Some("Recording start command sent".to_string()),
             data: Some(serde_json::json!({
-                "base_dir": base_dir
+                "base_dir": base_dir,
+                "recording_id": entry.id,
             })),
         })
     } else {
@@ -372,7 +4201,7 @@ pub fn start_recording(
 #[tauri::command]
 pub fn stop_recording(state: State<AppState>) -> Result<CommandResponse, String> {
     info!("Stopping recording");
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+    let mut bridge_lock = state.executor.lock().unwrap();
 
     if let Some(ref mut bridge) = *bridge_lock {
         if !bridge.is_running() {
@@ -382,11 +4211,16 @@ pub fn stop_recording(state: State<AppState>) -> Result<CommandResponse, String>
         bridge
             .stop_recording()
             .map_err(|e| format!("Failed to stop recording: {}", e))?;
+        drop(bridge_lock);
+
+        let entry = finish_recording(&state.recordings, RecordingKind::Executor)?;
 
         Ok(CommandResponse {
             success: true,
             message: Some("Recording stop command sent".to_string()),
-            data: None,
+            data: entry
+                .map(|entry| serde_json::to_value(entry).map_err(|e| e.to_string()))
+                .transpose()?,
         })
     } else {
         Err("Python executor not initialized".to_string())
@@ -395,7 +4229,7 @@ pub fn stop_recording(state: State<AppState>) -> Result<CommandResponse, String>
 
 #[tauri::command]
 pub fn get_recording_status(state: State<AppState>) -> Result<CommandResponse, String> {
-    let mut bridge_lock = state.python_bridge.lock().unwrap();
+    let mut bridge_lock = state.executor.lock().unwrap();
 
     if let Some(ref mut bridge) = *bridge_lock {
         if !bridge.is_running() {
@@ -428,6 +4262,195 @@ pub fn get_recording_status(state: State<AppState>) -> Result<CommandResponse, S
     }
 }
 
+/// Starts recording a monitor to a video file with ffmpeg, independently
+/// of the executor. The file is named after the current run, if any.
+#[tauri::command]
+pub fn start_video_recording(
+    settings: Option<VideoSettings>,
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let settings = settings.unwrap_or_default();
+    let monitor_index = settings.monitor_index.unwrap_or_else(|| {
+        state
+            .session
+            .current()
+            .monitor_index
+            .map_or(0, |index| index.max(0) as usize)
+    });
+    let monitor = monitors::find(&app_handle, monitor_index)?;
+    let recording = state.video_recorder.start(
+        &monitor,
+        &settings,
+        state.run_streams.current_run(DEFAULT_SESSION),
+    )?;
+    let mut entry = RecordingEntry::new(RecordingKind::Video, recording.path.clone());
+    entry.run_id = recording.run_id.clone();
+    entry.monitor_index = Some(recording.monitor_index);
+    entry.fps = Some(recording.fps);
+    entry.started_at = recording.started_at.clone();
+    describe_recording(&state, &mut entry);
+    state.recordings.begin(entry)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Recording monitor {} to video", monitor_index)),
+        data: Some(serde_json::to_value(recording).map_err(|e| e.to_string())?),
+    })
+}
+
+#[tauri::command]
+pub fn stop_video_recording(state: State<AppState>) -> Result<CommandResponse, String> {
+    let recording = state.video_recorder.stop()?;
+    let entry = finish_recording(&state.recordings, RecordingKind::Video)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Video saved to {}", recording.path.display())),
+        data: Some(serde_json::to_value(entry).map_err(|e| e.to_string())?),
+    })
+}
+
+/// The recorded videos, newest first, and the one being recorded.
+#[tauri::command]
+pub fn list_video_recordings(state: State<AppState>) -> Result<CommandResponse, String> {
+    let videos: Vec<RecordingEntry> = state
+        .recordings
+        .list()
+        .into_iter()
+        .filter(|entry| entry.kind == RecordingKind::Video && !entry.is_recording())
+        .collect();
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "recording": state.video_recorder.current(),
+            "videos": videos,
+        })),
+    })
+}
+
+/// Notes the loaded configuration on a recording that's starting.
+fn describe_recording(state: &AppState, entry: &mut RecordingEntry) {
+    entry.config_name = current_config_name(state);
+    entry.config_path = state.session.current().config_path;
+}
+
+/// Marks the recording of `kind` in progress, if any, finished.
+pub fn finish_recording(
+    recordings: &Recordings,
+    kind: RecordingKind,
+) -> Result<Option<RecordingEntry>, String> {
+    recordings
+        .active(kind)
+        .map(|entry| recordings.finish(&entry.id))
+        .transpose()
+}
+
+/// The recordings library, newest first.
+#[tauri::command]
+pub fn list_recordings(state: State<AppState>) -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::to_value(state.recordings.list()).map_err(|e| e.to_string())?),
+    })
+}
+
+/// A recording with its size and frame count measured now.
+#[tauri::command]
+pub fn get_recording_metadata(
+    id: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let entry = state.recordings.metadata(&id)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::to_value(entry).map_err(|e| e.to_string())?),
+    })
+}
+
+/// Removes a recording from the library, and its files when they're in the
+/// profile's recordings directory.
+#[tauri::command]
+pub fn delete_recording(id: String, state: State<AppState>) -> Result<CommandResponse, String> {
+    let entry = state.recordings.delete(&id)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Recording '{}' deleted", entry.name)),
+        data: Some(serde_json::to_value(entry).map_err(|e| e.to_string())?),
+    })
+}
+
+/// Changes the name a recording is listed under; its files keep theirs.
+#[tauri::command]
+pub fn rename_recording(
+    id: String,
+    name: String,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let entry = state.recordings.rename(&id, &name)?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Recording renamed to '{}'", entry.name)),
+        data: Some(serde_json::to_value(entry).map_err(|e| e.to_string())?),
+    })
+}
+
+/// Drafts a configuration from a recorded session and saves it as a new
+/// file, next to the recording unless `output_path` is given. The draft is
+/// named after the recording's library entry when it has one.
+#[tauri::command]
+pub fn recording_to_config(
+    recording_path: String,
+    output_path: Option<String>,
+    state: State<AppState>,
+) -> Result<CommandResponse, String> {
+    let dir = std::path::PathBuf::from(&recording_path);
+    let name = state
+        .recordings
+        .list()
+        .into_iter()
+        .find(|entry| entry.path == dir)
+        .map(|entry| entry.name)
+        .or_else(|| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "Recording".to_string());
+    let config = draft::from_recording(&dir, &format!("{} (draft)", name))?;
+    let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    // Checked before saving, so a broken draft never reaches the editor
+    let summary = ConfigLoader::load_from_string(&content)?.summary();
+
+    let path = output_path
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| draft::draft_path(&dir));
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    std::fs::write(&path, &content)
+        .map_err(|e| format!("Failed to write draft to {}: {}", path.display(), e))?;
+    info!(
+        "Drafted {} from recording {}",
+        path.display(),
+        recording_path
+    );
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Draft configuration saved to {}", path.display())),
+        data: Some(serde_json::json!({
+            "path": path,
+            "summary": summary,
+        })),
+    })
+}
+
 #[tauri::command]
 pub fn open_folder(path: String) -> Result<CommandResponse, String> {
     info!("Opening folder: {}", path);
@@ -0,0 +1,252 @@
+//! Commands behind the log console: the rolling files written by
+//! `logging`, and the end of the newest one, optionally followed with a
+//! `log-line` event per new line.
+
+use super::CommandResponse;
+use crate::logging::{self, LogSettings, LOG_FILE_PREFIX};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tracing::info;
+
+const DEFAULT_TAIL_LINES: usize = 200;
+const MAX_TAIL_LINES: usize = 10_000;
+const FOLLOW_POLL: Duration = Duration::from_millis(500);
+
+/// Read size when searching backwards for the last lines.
+const TAIL_BLOCK: u64 = 64 * 1024;
+
+/// Bumped whenever following starts or stops, so only the newest follower
+/// keeps running.
+static FOLLOWER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFile {
+    pub name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LogLine {
+    file: String,
+    line: String,
+}
+
+/// Log files in `dir`, newest first.
+pub fn log_files(dir: &Path) -> Vec<LogFile> {
+    let mut files: Vec<LogFile> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(LOG_FILE_PREFIX) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some(LogFile {
+                name,
+                path: entry.path(),
+                size_bytes: metadata.len(),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339()),
+            })
+        })
+        .collect();
+    // The appender suffixes file names with the date
+    files.sort_by(|a, b| b.name.cmp(&a.name));
+    files
+}
+
+/// The last `count` lines of a file, and its length.
+pub fn read_tail(path: &Path, count: usize) -> Result<(Vec<String>, u64), String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?
+        .len();
+
+    // Read whole blocks from the end until enough lines are in
+    let mut start = len;
+    let mut bytes = Vec::new();
+    while start > 0 && bytes.iter().filter(|&&b| b == b'\n').count() <= count {
+        let block_start = start.saturating_sub(TAIL_BLOCK);
+        let mut block = vec![0; (start - block_start) as usize];
+        file.seek(SeekFrom::Start(block_start))
+            .and_then(|_| file.read_exact(&mut block))
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        block.extend_from_slice(&bytes);
+        bytes = block;
+        start = block_start;
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let lines = lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    Ok((lines, len))
+}
+
+/// Removes the complete lines from `buffer`, leaving a partly written last
+/// line for the next read.
+pub fn take_lines(buffer: &mut String) -> Vec<String> {
+    let Some(end) = buffer.rfind('\n') else {
+        return Vec::new();
+    };
+    let rest = buffer.split_off(end + 1);
+    let lines = buffer.lines().map(str::to_string).collect();
+    *buffer = rest;
+    lines
+}
+
+/// The runner's log files, newest first.
+#[tauri::command]
+pub fn get_log_files() -> Result<CommandResponse, String> {
+    let dir = logging::log_dir();
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "dir": dir,
+            "files": log_files(&dir),
+        })),
+    })
+}
+
+/// The last `lines` lines (200 by default) of the newest log file. With
+/// `follow`, lines written afterwards are sent as `log-line` events until
+/// `tail_logs` is called again.
+#[tauri::command]
+pub fn tail_logs(
+    lines: Option<usize>,
+    follow: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<CommandResponse, String> {
+    let generation = FOLLOWER.fetch_add(1, Ordering::Relaxed) + 1;
+    let dir = logging::log_dir();
+    let Some(newest) = log_files(&dir).into_iter().next() else {
+        return Err(format!("No log files in {}", dir.display()));
+    };
+
+    let count = lines.unwrap_or(DEFAULT_TAIL_LINES).min(MAX_TAIL_LINES);
+    let (tail, offset) = read_tail(&newest.path, count)?;
+    let follow = follow.unwrap_or(false);
+    if follow {
+        info!("Following log file {}", newest.name);
+        spawn_follower(app_handle, dir, newest.clone(), offset, generation);
+    }
+
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!({
+            "file": newest.name,
+            "lines": tail,
+            "following": follow,
+        })),
+    })
+}
+
+#[tauri::command]
+pub fn get_log_settings() -> Result<CommandResponse, String> {
+    Ok(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(serde_json::json!(LogSettings::load())),
+    })
+}
+
+/// Applies logging settings right away and saves them for the next start.
+#[tauri::command]
+pub fn set_log_settings(settings: LogSettings) -> Result<CommandResponse, String> {
+    logging::apply_settings(&settings)?;
+    settings.save()?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some("Logging settings updated".to_string()),
+        data: Some(serde_json::json!(settings)),
+    })
+}
+
+/// Changes only the level, e.g. to `debug` while diagnosing an issue.
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<CommandResponse, String> {
+    let settings = LogSettings {
+        level: logging::parse_level(&level)?.to_string().to_lowercase(),
+        ..LogSettings::load()
+    };
+    logging::apply_settings(&settings)?;
+    settings.save()?;
+
+    Ok(CommandResponse {
+        success: true,
+        message: Some(format!("Log level set to {}", settings.level)),
+        data: None,
+    })
+}
+
+fn spawn_follower(
+    app_handle: AppHandle,
+    dir: PathBuf,
+    file: LogFile,
+    offset: u64,
+    generation: u64,
+) {
+    thread::spawn(move || {
+        let mut file = file;
+        let mut offset = offset;
+        let mut buffer = String::new();
+        while FOLLOWER.load(Ordering::Relaxed) == generation {
+            thread::sleep(FOLLOW_POLL);
+
+            if let Ok(bytes) = read_from(&file.path, &mut offset) {
+                buffer.push_str(&String::from_utf8_lossy(&bytes));
+                for line in take_lines(&mut buffer) {
+                    let _ = app_handle.emit(
+                        "log-line",
+                        LogLine {
+                            file: file.name.clone(),
+                            line,
+                        },
+                    );
+                }
+            }
+
+            // The appender starts a new file every day
+            if let Some(newest) = log_files(&dir).into_iter().next() {
+                if newest.name != file.name {
+                    file = newest;
+                    offset = 0;
+                    buffer.clear();
+                }
+            }
+        }
+    });
+}
+
+/// Bytes appended since `offset`, moving it past them.
+fn read_from(path: &Path, offset: &mut u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < *offset {
+        // Truncated
+        *offset = 0;
+    }
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    *offset += bytes.len() as u64;
+    Ok(bytes)
+}
@@ -0,0 +1,185 @@
+//! Configuration bundles: a `.qontinui` or `.zip` archive holding the
+//! configuration JSON and the image files it uses, usually under
+//! `images/`. Bundles are unpacked into a cache directory keyed by their
+//! content, and image paths are pointed at the unpacked files so they
+//! resolve on any machine.
+
+use super::images::ImageSource;
+use super::types::QontinuiConfig;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// File extensions loaded as bundles.
+pub const BUNDLE_EXTENSIONS: [&str; 2] = ["qontinui", "zip"];
+
+/// Name of the configuration when a bundle holds several JSON files.
+const CONFIG_NAME: &str = "config.json";
+
+/// Most a bundle may unpack to, against archives that expand without end.
+const MAX_UNPACKED_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Left in an unpacked bundle once every file has been written.
+const COMPLETE_MARKER: &str = ".unpacked";
+
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            BUNDLE_EXTENSIONS
+                .iter()
+                .any(|bundle| ext.eq_ignore_ascii_case(bundle))
+        })
+}
+
+/// Where bundles are unpacked.
+pub fn cache_dir() -> PathBuf {
+    crate::profiles::data_dir().join("bundles")
+}
+
+/// Unpacks `bundle` under `cache`, unless the same content was unpacked
+/// before, and returns the path of its configuration JSON.
+pub fn unpack(bundle: &Path, cache: &Path) -> Result<PathBuf, String> {
+    let mut file =
+        File::open(bundle).map_err(|e| format!("Failed to open bundle {:?}: {}", bundle, e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read bundle {:?}: {}", bundle, e))?;
+    let hash: String = hasher.finalize()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let stem = bundle
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("bundle");
+    let dir = cache.join(format!("{}-{}", stem, hash));
+
+    if !dir.join(COMPLETE_MARKER).exists() {
+        // Unpack next to the final directory and move it into place, so an
+        // interrupted unpack is never mistaken for a complete one
+        let partial = cache.join(format!(".{}-{}", stem, uuid::Uuid::new_v4()));
+        let unpacked = extract(bundle, &partial).and_then(|()| {
+            let _ = fs::remove_dir_all(&dir);
+            fs::rename(&partial, &dir)
+                .map_err(|e| format!("Failed to move unpacked bundle into place: {}", e))
+        });
+        if let Err(e) = unpacked {
+            let _ = fs::remove_dir_all(&partial);
+            return Err(e);
+        }
+        tracing::info!("Unpacked bundle {:?} into {:?}", bundle, dir);
+    }
+
+    find_config(&dir)
+}
+
+fn extract(bundle: &Path, dest: &Path) -> Result<(), String> {
+    let file =
+        File::open(bundle).map_err(|e| format!("Failed to open bundle {:?}: {}", bundle, e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Not a valid bundle archive: {}", e))?;
+    fs::create_dir_all(dest).map_err(|e| format!("Failed to create {:?}: {}", dest, e))?;
+
+    let mut remaining = MAX_UNPACKED_BYTES;
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read bundle entry {}: {}", index, e))?;
+        let name = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("Bundle entry '{}' points outside the bundle", entry.name()))?;
+        let path = dest.join(name);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        let mut out =
+            File::create(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+        let written = io::copy(&mut (&mut entry).take(remaining + 1), &mut out)
+            .map_err(|e| format!("Failed to unpack '{}': {}", entry.name(), e))?;
+        if written > remaining {
+            return Err(format!(
+                "Bundle unpacks to more than {} MB",
+                MAX_UNPACKED_BYTES / (1024 * 1024)
+            ));
+        }
+        remaining -= written;
+    }
+
+    File::create(dest.join(COMPLETE_MARKER))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to finish unpacking bundle: {}", e))
+}
+
+/// The JSON file at the top of an unpacked bundle: the only one there, or
+/// `config.json` when there are several.
+fn find_config(dir: &Path) -> Result<PathBuf, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {:?}: {}", dir, e))?;
+    let mut candidates: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        })
+        .collect();
+
+    match candidates.len() {
+        0 => Err("The bundle has no configuration JSON at its top level".to_string()),
+        1 => Ok(candidates.remove(0)),
+        _ => {
+            let named = dir.join(CONFIG_NAME);
+            if candidates.contains(&named) {
+                Ok(named)
+            } else {
+                Err(format!(
+                    "The bundle has several JSON files at its top level; name the configuration {}",
+                    CONFIG_NAME
+                ))
+            }
+        }
+    }
+}
+
+/// Points the image files of a config loaded from a bundle unpacked in
+/// `dir` at the unpacked copies. Paths that don't resolve are looked up by
+/// file name under `images/`, which covers configs that referenced files
+/// elsewhere on the machine they were bundled on. Returns how many images
+/// were rewritten.
+pub fn rewrite_image_paths(config: &mut QontinuiConfig, dir: &Path) -> usize {
+    let mut rewritten = 0;
+    for image in &mut config.images {
+        let Some(ImageSource::File(path)) = config.image_store.source(&image.id) else {
+            continue;
+        };
+        let mut resolved = config.image_store.resolve_path(path);
+        if !resolved.exists() {
+            let raw = path.to_string_lossy();
+            let file_name = raw.rsplit(['/', '\\']).next().unwrap_or_default();
+            resolved = dir.join("images").join(file_name);
+            if file_name.is_empty() || !resolved.exists() {
+                tracing::warn!("Image {} isn't in the bundle: {}", image.id, raw);
+                continue;
+            }
+        }
+
+        image.extra.remove("filePath");
+        image.extra.insert(
+            "path".to_string(),
+            resolved.to_string_lossy().into_owned().into(),
+        );
+        config
+            .image_store
+            .set_source(&image.id, Some(ImageSource::File(resolved)));
+        rewritten += 1;
+    }
+    rewritten
+}
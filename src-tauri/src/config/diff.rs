@@ -0,0 +1,355 @@
+use super::types::{Entry, QontinuiConfig};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Entries added, changed or removed in one id-keyed config section.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SectionDelta {
+    pub added: Vec<Value>,
+    pub modified: Vec<Value>,
+    pub removed: Vec<String>,
+}
+
+impl SectionDelta {
+    pub fn len(&self) -> usize {
+        self.added.len() + self.modified.len() + self.removed.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Structural difference between two versions of a configuration.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigDelta {
+    pub states: SectionDelta,
+    pub transitions: SectionDelta,
+    pub workflows: SectionDelta,
+    pub images: SectionDelta,
+}
+
+impl ConfigDelta {
+    pub fn len(&self) -> usize {
+        self.states.len() + self.transitions.len() + self.workflows.len() + self.images.len()
+    }
+}
+
+/// Compares two sections entry by entry using each entry's `id`.
+pub fn diff_section<T: Entry + Serialize + PartialEq>(old: &[T], new: &[T]) -> SectionDelta {
+    let old_by_id: HashMap<&str, &T> = old.iter().map(|e| (e.id(), e)).collect();
+    let mut delta = SectionDelta::default();
+
+    for entry in new {
+        match old_by_id.get(entry.id()) {
+            Some(previous) if *previous == entry => {}
+            Some(_) => delta.modified.extend(to_value(entry)),
+            None => delta.added.extend(to_value(entry)),
+        }
+    }
+
+    let new_ids: HashSet<&str> = new.iter().map(Entry::id).collect();
+    delta.removed = old_by_id
+        .keys()
+        .filter(|id| !new_ids.contains(**id))
+        .map(|id| id.to_string())
+        .collect();
+
+    delta
+}
+
+/// Computes the changes needed to turn `old` into `new`. Returns `None` when
+/// top-level fields (version, metadata, settings, categories) differ, which
+/// requires sending the whole configuration.
+pub fn diff_configs(old: &QontinuiConfig, new: &QontinuiConfig) -> Option<ConfigDelta> {
+    let same_top_level = old.version == new.version
+        && old.categories == new.categories
+        && serde_json::to_value(&old.metadata).ok() == serde_json::to_value(&new.metadata).ok()
+        && serde_json::to_value(&old.settings).ok() == serde_json::to_value(&new.settings).ok();
+    if !same_top_level {
+        return None;
+    }
+
+    let mut images = diff_section(&old.images, &new.images);
+
+    // Image metadata can be unchanged while the payload differs
+    for entry in &new.images {
+        let unchanged_metadata = !images
+            .added
+            .iter()
+            .chain(&images.modified)
+            .any(|e| id(e) == Some(entry.id.as_str()));
+        if unchanged_metadata
+            && old.image_store.source(&entry.id) != new.image_store.source(&entry.id)
+        {
+            images.modified.extend(to_value(entry));
+        }
+    }
+
+    // The executor needs the payloads of changed images
+    new.image_store.embed_into(&mut images.added);
+    new.image_store.embed_into(&mut images.modified);
+
+    Some(ConfigDelta {
+        states: diff_section(&old.states, &new.states),
+        transitions: diff_section(&old.transitions, &new.transitions),
+        workflows: diff_section(&old.workflows, &new.workflows),
+        images,
+    })
+}
+
+fn id(entry: &Value) -> Option<&str> {
+    entry.get("id").and_then(Value::as_str)
+}
+
+fn to_value<T: Serialize>(entry: &T) -> Option<Value> {
+    serde_json::to_value(entry).ok()
+}
+
+/// How an entry differs between two configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A value that differs, at a JSON Pointer relative to its entry, or to the
+/// config for top-level fields. `before` is absent for added values and
+/// `after` for removed ones.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub pointer: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryDiff {
+    pub section: &'static str,
+    pub id: String,
+    pub kind: ChangeKind,
+    /// A single change at `""` holding the whole entry when it was added or
+    /// removed.
+    pub fields: Vec<FieldChange>,
+}
+
+/// An image whose pixels changed while its id stayed the same.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageSwap {
+    pub id: String,
+    /// Digests of the decoded images; absent when the image can't be read.
+    pub before_sha256: Option<String>,
+    pub after_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SectionCounts {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+}
+
+/// Everything that differs between two configurations, for reviewing a
+/// change rather than for syncing the executor.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeSet {
+    pub identical: bool,
+    /// Changes to version, metadata, categories and settings.
+    pub top_level: Vec<FieldChange>,
+    /// In section order, then in the order of the entries.
+    pub entries: Vec<EntryDiff>,
+    pub images_swapped: Vec<ImageSwap>,
+    pub counts: BTreeMap<&'static str, SectionCounts>,
+}
+
+const REVIEWED_SECTIONS: [&str; 4] = ["states", "transitions", "workflows", "images"];
+
+/// Compares two configurations field by field. Entries are matched by id,
+/// as are the entries of id-keyed lists inside them, such as a workflow's
+/// actions. Placeholders are compared as written.
+pub fn change_set(old: &QontinuiConfig, new: &QontinuiConfig) -> Result<ChangeSet, String> {
+    let mut top_level = Vec::new();
+    for section in ["version", "metadata", "categories", "settings"] {
+        let before = top_level_value(old, section)?;
+        let after = top_level_value(new, section)?;
+        diff_values(
+            Some(&before),
+            Some(&after),
+            &pointer_to(section),
+            &mut top_level,
+        );
+    }
+
+    let mut entries = Vec::new();
+    let mut counts = BTreeMap::new();
+    for section in REVIEWED_SECTIONS {
+        let before = reviewed_entries(old, section)?;
+        let after = reviewed_entries(new, section)?;
+        let count: &mut SectionCounts = counts.entry(section).or_default();
+        let mut change = |id: &str, kind, fields| {
+            match kind {
+                ChangeKind::Added => count.added += 1,
+                ChangeKind::Removed => count.removed += 1,
+                ChangeKind::Modified => count.modified += 1,
+            }
+            entries.push(EntryDiff {
+                section,
+                id: id.to_string(),
+                kind,
+                fields,
+            });
+        };
+
+        for entry in &before {
+            let id = id(entry).unwrap_or_default();
+            match after.iter().find(|other| self::id(other) == Some(id)) {
+                Some(other) => {
+                    let mut fields = Vec::new();
+                    diff_values(Some(entry), Some(other), "", &mut fields);
+                    if !fields.is_empty() {
+                        change(id, ChangeKind::Modified, fields);
+                    }
+                }
+                None => change(id, ChangeKind::Removed, whole(Some(entry), None)),
+            }
+        }
+        for entry in &after {
+            let id = id(entry).unwrap_or_default();
+            if !before.iter().any(|other| self::id(other) == Some(id)) {
+                change(id, ChangeKind::Added, whole(None, Some(entry)));
+            }
+        }
+    }
+
+    let mut images_swapped = Vec::new();
+    for image in &new.images {
+        let (Some(before), Some(after)) = (
+            old.image_store.source(&image.id),
+            new.image_store.source(&image.id),
+        ) else {
+            continue;
+        };
+        if before == after {
+            continue;
+        }
+        let before_sha256 = old.image_store.digest(&image.id).ok().map(|d| d.sha256);
+        let after_sha256 = new.image_store.digest(&image.id).ok().map(|d| d.sha256);
+        if before_sha256.is_none() || before_sha256 != after_sha256 {
+            images_swapped.push(ImageSwap {
+                id: image.id.clone(),
+                before_sha256,
+                after_sha256,
+            });
+        }
+    }
+
+    Ok(ChangeSet {
+        identical: top_level.is_empty() && entries.is_empty() && images_swapped.is_empty(),
+        top_level,
+        entries,
+        images_swapped,
+        counts,
+    })
+}
+
+fn top_level_value(config: &QontinuiConfig, name: &str) -> Result<Value, String> {
+    match name {
+        "version" => Ok(Value::from(config.version.as_str())),
+        _ => config.section(name),
+    }
+}
+
+/// A section's entries as written, without the `hasData`/`dataSize`
+/// markers of images, whose payloads are compared separately.
+fn reviewed_entries(config: &QontinuiConfig, section: &str) -> Result<Vec<Value>, String> {
+    let Value::Array(mut entries) = config.section(section)? else {
+        return Ok(Vec::new());
+    };
+    for entry in entries.iter_mut().filter_map(Value::as_object_mut) {
+        entry.remove("hasData");
+        entry.remove("dataSize");
+    }
+    Ok(entries)
+}
+
+fn whole(before: Option<&Value>, after: Option<&Value>) -> Vec<FieldChange> {
+    vec![FieldChange {
+        pointer: String::new(),
+        before: before.cloned(),
+        after: after.cloned(),
+    }]
+}
+
+fn pointer_to(key: &str) -> String {
+    format!("/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn diff_values(
+    before: Option<&Value>,
+    after: Option<&Value>,
+    pointer: &str,
+    changes: &mut Vec<FieldChange>,
+) {
+    match (before, after) {
+        (Some(a), Some(b)) if a == b => {}
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            for (key, value) in a {
+                diff_values(
+                    Some(value),
+                    b.get(key),
+                    &format!("{}{}", pointer, pointer_to(key)),
+                    changes,
+                );
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                diff_values(
+                    None,
+                    Some(value),
+                    &format!("{}{}", pointer, pointer_to(key)),
+                    changes,
+                );
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) if keyed(a) && keyed(b) => {
+            // Matched by id, so an inserted action doesn't show as every
+            // later action changing
+            for (i, item) in a.iter().enumerate() {
+                match b.iter().position(|other| id(other) == id(item)) {
+                    Some(j) => diff_values(
+                        Some(item),
+                        Some(&b[j]),
+                        &format!("{}/{}", pointer, j),
+                        changes,
+                    ),
+                    None => diff_values(Some(item), None, &format!("{}/{}", pointer, i), changes),
+                }
+            }
+            for (j, item) in b.iter().enumerate() {
+                if !a.iter().any(|other| id(other) == id(item)) {
+                    diff_values(None, Some(item), &format!("{}/{}", pointer, j), changes);
+                }
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                diff_values(Some(x), Some(y), &format!("{}/{}", pointer, i), changes);
+            }
+        }
+        (None, None) => {}
+        _ => changes.push(FieldChange {
+            pointer: pointer.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+    }
+}
+
+/// Whether every item of a non-empty list has a distinct string id.
+fn keyed(items: &[Value]) -> bool {
+    let ids: HashSet<&str> = items.iter().filter_map(id).collect();
+    !items.is_empty() && ids.len() == items.len()
+}
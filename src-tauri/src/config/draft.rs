@@ -0,0 +1,297 @@
+//! Drafts a configuration from a recorded session, as a starting point for
+//! refining it in qontinui-web. The recording is a folder holding the
+//! screenshots and an `actions.json` array (or `actions.jsonl`) of the
+//! recorded actions in the order done: `type` (`click`, `type`, `key`, ...), `timestamp` in
+//! milliseconds since the recording started, `screenshot` naming the
+//! screenshot taken just before the action, and the action's own fields
+//! (`x`, `y`, `text`, `key`, ...).
+//!
+//! Each distinct screen becomes a state identified by its screenshot, and
+//! the actions done on a screen become the workflow of a transition to the
+//! screen that followed. Clicks target a crop of the screenshot around the
+//! point clicked. Pauses between actions become waits.
+
+use super::migration::CURRENT_VERSION;
+use crate::capture::{Region, RgbImage};
+use crate::visual_diff;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Channel difference above which a pixel counts as changed between two
+/// screenshots.
+const PIXEL_THRESHOLD: u8 = 32;
+
+/// Screenshots differing in at most this share of pixels show the same
+/// state.
+const SAME_SCREEN: f64 = 0.02;
+
+/// Side of the square cropped around a click as its target.
+const TARGET_SIZE: u32 = 64;
+
+/// Shorter pauses between actions aren't turned into waits.
+const MIN_WAIT_MS: u64 = 500;
+
+/// Fields of a recorded action that aren't copied into the action's config.
+const RECORDING_FIELDS: [&str; 3] = ["type", "timestamp", "screenshot"];
+
+struct DraftState {
+    id: String,
+    /// The screenshot's image id.
+    image: String,
+    screen: RgbImage,
+}
+
+#[derive(Default)]
+struct Draft {
+    images: Vec<Value>,
+    states: Vec<DraftState>,
+    workflows: Vec<Value>,
+    transitions: Vec<Value>,
+}
+
+impl Draft {
+    fn add_image(&mut self, prefix: &str, image: &RgbImage) -> Result<String, String> {
+        let id = format!("{}-{}", prefix, self.images.len() + 1);
+        self.images.push(json!({
+            "id": id,
+            "name": id,
+            "format": "png",
+            "width": image.width,
+            "height": image.height,
+            "data": format!("data:image/png;base64,{}", STANDARD.encode(image.encode_png()?)),
+        }));
+        Ok(id)
+    }
+
+    /// The state showing `screen`, added when no earlier one does.
+    fn state_for(&mut self, screen: RgbImage) -> Result<String, String> {
+        let known = self.states.iter().find(|state| {
+            visual_diff::compare(&state.screen, &screen, PIXEL_THRESHOLD)
+                .is_ok_and(|(summary, _)| summary.score <= SAME_SCREEN)
+        });
+        if let Some(state) = known {
+            return Ok(state.id.clone());
+        }
+        let id = format!("state-{}", self.states.len() + 1);
+        let image = self.add_image("screen", &screen)?;
+        self.states.push(DraftState {
+            id: id.clone(),
+            image,
+            screen,
+        });
+        Ok(id)
+    }
+
+    /// Adds the workflow of the actions done in `from`, and the transition
+    /// running it when the screen changed to `to`.
+    fn add_workflow(&mut self, from: &str, to: Option<&str>, mut actions: Vec<Value>) {
+        if actions.is_empty() {
+            return;
+        }
+        let id = format!("workflow-{}", self.workflows.len() + 1);
+        for (i, action) in actions.iter_mut().enumerate() {
+            action["id"] = Value::from(format!("{}-action-{}", id, i + 1));
+        }
+        self.workflows.push(json!({
+            "id": id,
+            "name": format!("Recorded on {}", from),
+            "category": "Recorded",
+            "type": "sequence",
+            "actions": actions,
+        }));
+        if let Some(to) = to {
+            self.transitions.push(json!({
+                "id": format!("transition-{}", self.transitions.len() + 1),
+                "type": "FromTransition",
+                "workflows": [id],
+                "fromState": from,
+                "toState": to,
+                "staysVisible": false,
+                "activateStates": [to],
+                "deactivateStates": [from],
+            }));
+        }
+    }
+}
+
+/// Reads the recorded actions in `dir`.
+fn read_actions(dir: &Path) -> Result<Vec<Map<String, Value>>, String> {
+    let array = dir.join("actions.json");
+    let lines = dir.join("actions.jsonl");
+    let actions: Vec<Value> = if array.exists() {
+        let content = std::fs::read_to_string(&array)
+            .map_err(|e| format!("Failed to read {:?}: {}", array, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid {:?}: {}", array, e))?
+    } else if lines.exists() {
+        let content = std::fs::read_to_string(&lines)
+            .map_err(|e| format!("Failed to read {:?}: {}", lines, e))?;
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| format!("Invalid action on line {} of {:?}: {}", i + 1, lines, e))
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        return Err(format!("{:?} holds no actions.json or actions.jsonl", dir));
+    };
+
+    Ok(actions
+        .into_iter()
+        .filter_map(|action| match action {
+            Value::Object(fields) => Some(fields),
+            _ => None,
+        })
+        .collect())
+}
+
+/// `{"type": "click", "x": 10, "y": 20}` becomes
+/// `{"type": "CLICK", "config": {"x": 10, "y": 20}}`, with a target image
+/// when the click's screenshot is known.
+fn to_action(
+    draft: &mut Draft,
+    recorded: &Map<String, Value>,
+    screen: Option<&RgbImage>,
+) -> Result<Value, String> {
+    let kind = recorded
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+        .to_uppercase();
+    let mut config: Map<String, Value> = recorded
+        .iter()
+        .filter(|(field, _)| !RECORDING_FIELDS.contains(&field.as_str()))
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect();
+
+    let point = recorded
+        .get("x")
+        .and_then(Value::as_i64)
+        .zip(recorded.get("y").and_then(Value::as_i64));
+    if let (true, Some((x, y)), Some(screen)) = (kind.contains("CLICK"), point, screen) {
+        let half = (TARGET_SIZE / 2) as i32;
+        let region = Region {
+            x: x as i32 - half,
+            y: y as i32 - half,
+            width: TARGET_SIZE,
+            height: TARGET_SIZE,
+        };
+        if let Some(target) = screen.crop(region) {
+            let image = draft.add_image("target", &target)?;
+            config.insert(
+                "target".to_string(),
+                json!({ "type": "image", "imageId": image }),
+            );
+        }
+    }
+
+    Ok(json!({
+        "type": kind,
+        "config": config,
+    }))
+}
+
+/// Builds a draft configuration named `name` from the recording in `dir`.
+pub fn from_recording(dir: &Path, name: &str) -> Result<Value, String> {
+    let recorded = read_actions(dir)?;
+    if recorded.is_empty() {
+        return Err(format!("{:?} holds no recorded actions", dir));
+    }
+
+    let mut draft = Draft::default();
+    let mut current: Option<String> = None;
+    let mut pending: Vec<Value> = Vec::new();
+    let mut last_timestamp: Option<u64> = None;
+
+    for action in &recorded {
+        let screen = match action.get("screenshot").and_then(Value::as_str) {
+            Some(path) => Some(RgbImage::load(&dir.join(path))?),
+            None => None,
+        };
+        if let Some(screen) = &screen {
+            let state = draft.state_for(screen.clone())?;
+            if current.as_deref() != Some(state.as_str()) {
+                // Actions before the first screenshot belong to its state
+                if let Some(from) = current.take() {
+                    draft.add_workflow(&from, Some(&state), std::mem::take(&mut pending));
+                }
+                current = Some(state);
+            }
+        }
+
+        let timestamp = action.get("timestamp").and_then(Value::as_u64);
+        if let (Some(last), Some(now)) = (last_timestamp, timestamp) {
+            if now.saturating_sub(last) >= MIN_WAIT_MS && !pending.is_empty() {
+                pending.push(json!({
+                    "type": "WAIT",
+                    "config": { "duration": now - last },
+                }));
+            }
+        }
+        last_timestamp = timestamp.or(last_timestamp);
+
+        pending.push(to_action(&mut draft, action, screen.as_ref())?);
+    }
+    let last_state = current.unwrap_or_else(|| "state-1".to_string());
+    draft.add_workflow(&last_state, None, pending);
+
+    let states: Vec<Value> = if draft.states.is_empty() {
+        vec![json!({
+            "id": last_state,
+            "name": "Screen 1",
+            "identifyingImages": [],
+            "isInitial": true,
+            "isFinal": false,
+        })]
+    } else {
+        draft
+            .states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| {
+                json!({
+                    "id": state.id,
+                    "name": format!("Screen {}", i + 1),
+                    "identifyingImages": [state.image],
+                    "isInitial": i == 0,
+                    "isFinal": false,
+                })
+            })
+            .collect()
+    };
+    Ok(json!({
+        "version": CURRENT_VERSION,
+        "metadata": {
+            "name": name,
+            "description": format!("Drafted from the recording in {}", dir.display()),
+            "created": chrono::Local::now().format("%Y-%m-%d").to_string(),
+            "tags": ["draft", "recording"],
+        },
+        "images": draft.images,
+        "workflows": draft.workflows,
+        "states": states,
+        "transitions": draft.transitions,
+        "categories": ["Recorded"],
+        "settings": null,
+    }))
+}
+
+/// A path next to the recording that doesn't exist yet.
+pub fn draft_path(dir: &Path) -> PathBuf {
+    let stem = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "recording".to_string());
+    let parent = dir.parent().unwrap_or(dir);
+    (1..)
+        .map(|n| match n {
+            1 => parent.join(format!("{}-draft.json", stem)),
+            n => parent.join(format!("{}-draft-{}.json", stem, n)),
+        })
+        .find(|path| !path.exists())
+        .expect("some numbered path is free")
+}
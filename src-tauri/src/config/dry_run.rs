@@ -0,0 +1,230 @@
+//! Walks through a workflow without executing it: every action's images,
+//! nested workflows and the transitions that run the workflow are
+//! resolved, each referenced image is loaded and decoded, and the state
+//! graph is checked for states no transition can reach. Nothing is sent to
+//! the executor, so no input happens.
+
+use super::images::sniff_mime_type;
+use super::types::{find, QontinuiConfig, Workflow};
+use crate::capture::RgbImage;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunStep {
+    /// Workflows from the validated one down to the one holding the action.
+    pub workflow_path: Vec<String>,
+    pub action_id: String,
+    pub action_type: String,
+    pub images: Vec<String>,
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingAsset {
+    pub image_id: String,
+    /// Actions and states that use the image.
+    pub referenced_by: Vec<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunReport {
+    pub workflow_id: String,
+    pub workflow_name: Option<String>,
+    pub steps: Vec<DryRunStep>,
+    /// Transitions that run the workflow.
+    pub transitions: Vec<String>,
+    /// States those transitions leave, enter or activate.
+    pub states: Vec<String>,
+    pub images_checked: usize,
+    pub missing_assets: Vec<MissingAsset>,
+    pub unreachable_states: Vec<String>,
+    pub issues: Vec<String>,
+    pub ok: bool,
+}
+
+/// Walks through workflow `workflow_id` of `config`.
+pub fn walk_through(config: &QontinuiConfig, workflow_id: &str) -> Result<DryRunReport, String> {
+    let workflow = find(&config.workflows, workflow_id)
+        .ok_or_else(|| format!("Workflow not found: {}", workflow_id))?;
+
+    let mut walk = Walk {
+        config,
+        steps: Vec::new(),
+        issues: Vec::new(),
+        image_refs: BTreeMap::new(),
+    };
+    walk.workflow(workflow, &mut vec![workflow_id.to_string()]);
+
+    let mut transitions = Vec::new();
+    let mut states = Vec::new();
+    for transition in &config.transitions {
+        if !transition.workflows.iter().any(|w| w == workflow_id) {
+            continue;
+        }
+        transitions.push(transition.id.clone());
+        let touched = transition
+            .from_state
+            .as_deref()
+            .into_iter()
+            .chain(transition.targets());
+        for state_id in touched {
+            if states.iter().any(|s| s == state_id) {
+                continue;
+            }
+            states.push(state_id.to_string());
+            match find(&config.states, state_id) {
+                Some(state) => {
+                    for image in &state.identifying_images {
+                        walk.reference(image, format!("state '{}'", state_id));
+                    }
+                }
+                None => walk.issues.push(format!(
+                    "Transition '{}' references unknown state '{}'",
+                    transition.id, state_id
+                )),
+            }
+        }
+    }
+
+    let images_checked = walk.image_refs.len();
+    let missing_assets: Vec<MissingAsset> = walk
+        .image_refs
+        .into_iter()
+        .filter_map(|(image_id, referenced_by)| {
+            let error = check_image(config, &image_id).err()?;
+            Some(MissingAsset {
+                image_id,
+                referenced_by,
+                error,
+            })
+        })
+        .collect();
+
+    let mut issues = walk.issues;
+    let unreachable_states = unreachable_states(config, &mut issues);
+    let ok = issues.is_empty()
+        && missing_assets.is_empty()
+        && walk.steps.iter().all(|step| step.issues.is_empty());
+
+    Ok(DryRunReport {
+        workflow_id: workflow_id.to_string(),
+        workflow_name: workflow.name.clone(),
+        steps: walk.steps,
+        transitions,
+        states,
+        images_checked,
+        missing_assets,
+        unreachable_states,
+        issues,
+        ok,
+    })
+}
+
+struct Walk<'a> {
+    config: &'a QontinuiConfig,
+    steps: Vec<DryRunStep>,
+    issues: Vec<String>,
+    /// Image ids and what references them, sorted for a stable report.
+    image_refs: BTreeMap<String, Vec<String>>,
+}
+
+impl<'a> Walk<'a> {
+    fn workflow(&mut self, workflow: &'a Workflow, path: &mut Vec<String>) {
+        for action in &workflow.actions {
+            let mut step = DryRunStep {
+                workflow_path: path.clone(),
+                action_id: action.id.clone(),
+                action_type: action.action_type.clone(),
+                images: Vec::new(),
+                issues: Vec::new(),
+            };
+
+            if let Some((_, image)) = action.image() {
+                step.images.push(image.to_string());
+                self.reference(image, format!("action '{}'", action.id));
+            }
+
+            let mut descend = None;
+            if let Some(nested) = action.workflow_id() {
+                if path.iter().any(|p| p == nested) {
+                    step.issues.push(format!(
+                        "Workflow '{}' calls itself through this action",
+                        nested
+                    ));
+                } else {
+                    match find(&self.config.workflows, nested) {
+                        Some(workflow) => descend = Some(workflow),
+                        None => step.issues.push(format!("Unknown workflow '{}'", nested)),
+                    }
+                }
+            }
+
+            self.steps.push(step);
+            if let Some(nested) = descend {
+                path.push(nested.id.clone());
+                self.workflow(nested, path);
+                path.pop();
+            }
+        }
+    }
+
+    fn reference(&mut self, image_id: &str, by: String) {
+        let refs = self.image_refs.entry(image_id.to_string()).or_default();
+        if !refs.contains(&by) {
+            refs.push(by);
+        }
+    }
+}
+
+/// Loads an image and makes sure it decodes. PNGs are fully decoded; other
+/// formats are only recognized by their header.
+pub fn check_image(config: &QontinuiConfig, image_id: &str) -> Result<(), String> {
+    if find(&config.images, image_id).is_none() {
+        return Err("Not defined in the configuration".to_string());
+    }
+    let bytes = config.image_store.load(image_id)?;
+    match sniff_mime_type(&bytes) {
+        "image/png" => RgbImage::decode_png(&bytes).map(|_| ()),
+        "application/octet-stream" => Err("Not a recognized image format".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// States no chain of transitions leads to from an initial state.
+fn unreachable_states(config: &QontinuiConfig, issues: &mut Vec<String>) -> Vec<String> {
+    let initial: Vec<&str> = config
+        .states
+        .iter()
+        .filter(|s| s.is_initial)
+        .map(|s| s.id.as_str())
+        .collect();
+    if initial.is_empty() {
+        issues.push("No initial state, so reachability can't be checked".to_string());
+        return Vec::new();
+    }
+
+    let mut reached: HashSet<&str> = initial.iter().copied().collect();
+    let mut queue: VecDeque<&str> = initial.into_iter().collect();
+    while let Some(state) = queue.pop_front() {
+        for transition in &config.transitions {
+            if transition.from_state.as_deref() != Some(state) {
+                continue;
+            }
+            for target in transition.targets() {
+                if reached.insert(target) {
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+
+    config
+        .states
+        .iter()
+        .map(|s| s.id.as_str())
+        .filter(|id| !reached.contains(id))
+        .map(String::from)
+        .collect()
+}
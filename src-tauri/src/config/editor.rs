@@ -0,0 +1,394 @@
+//! Quick fixes to the loaded configuration without a round trip through the
+//! web builder. Edits are made on a copy that has to pass validation before
+//! it replaces the loaded config, and edits since the last load or save are
+//! tracked so the UI can offer to save them. Each edit keeps the affected
+//! entry as it was before and after, so it can be undone and redone.
+
+use super::images::ImageSource;
+use super::substitution;
+use super::types::{Entry, QontinuiConfig};
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+/// Edits kept for undo; older ones are dropped.
+const HISTORY_LIMIT: usize = 100;
+
+/// The id-keyed sections that can be edited entry by entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    States,
+    Transitions,
+    Workflows,
+    Images,
+}
+
+impl Section {
+    /// Singular name, for messages.
+    pub fn noun(self) -> &'static str {
+        match self {
+            Self::States => "state",
+            Self::Transitions => "transition",
+            Self::Workflows => "workflow",
+            Self::Images => "image",
+        }
+    }
+
+    fn position(self, config: &QontinuiConfig, id: &str) -> Option<usize> {
+        match self {
+            Self::States => index_of(&config.states, id),
+            Self::Transitions => index_of(&config.transitions, id),
+            Self::Workflows => index_of(&config.workflows, id),
+            Self::Images => index_of(&config.images, id),
+        }
+    }
+
+    fn get(self, config: &QontinuiConfig, index: usize) -> Value {
+        let entry = match self {
+            Self::States => serde_json::to_value(&config.states[index]),
+            Self::Transitions => serde_json::to_value(&config.transitions[index]),
+            Self::Workflows => serde_json::to_value(&config.workflows[index]),
+            Self::Images => serde_json::to_value(&config.images[index]),
+        };
+        entry.unwrap_or_default()
+    }
+
+    /// Inserts `entry` at `index`, or at the end past the last entry. With
+    /// `replace` it takes the place of the entry at `index` instead.
+    fn put(
+        self,
+        config: &mut QontinuiConfig,
+        index: usize,
+        entry: Value,
+        replace: bool,
+    ) -> Result<(), String> {
+        match self {
+            Self::States => put(&mut config.states, index, parse(self, entry)?, replace),
+            Self::Transitions => put(&mut config.transitions, index, parse(self, entry)?, replace),
+            Self::Workflows => put(&mut config.workflows, index, parse(self, entry)?, replace),
+            Self::Images => put(&mut config.images, index, parse(self, entry)?, replace),
+        }
+        Ok(())
+    }
+
+    fn remove(self, config: &mut QontinuiConfig, index: usize) -> Value {
+        let removed = match self {
+            Self::States => serde_json::to_value(config.states.remove(index)),
+            Self::Transitions => serde_json::to_value(config.transitions.remove(index)),
+            Self::Workflows => serde_json::to_value(config.workflows.remove(index)),
+            Self::Images => serde_json::to_value(config.images.remove(index)),
+        };
+        removed.unwrap_or_default()
+    }
+}
+
+fn index_of<T: Entry>(entries: &[T], id: &str) -> Option<usize> {
+    entries.iter().position(|entry| entry.id() == id)
+}
+
+fn parse<T: DeserializeOwned>(section: Section, entry: Value) -> Result<T, String> {
+    serde_json::from_value(entry).map_err(|e| format!("Invalid {}: {}", section.noun(), e))
+}
+
+fn put<T>(entries: &mut Vec<T>, index: usize, entry: T, replace: bool) {
+    if replace {
+        entries[index] = entry;
+    } else {
+        entries.insert(index.min(entries.len()), entry);
+    }
+}
+
+/// Applies `edit` to a copy of `config` and returns the copy if it still
+/// validates; `config` itself is never touched.
+pub fn apply<F>(config: &QontinuiConfig, edit: F) -> Result<QontinuiConfig, String>
+where
+    F: FnOnce(&mut QontinuiConfig) -> Result<(), String>,
+{
+    let mut edited = config.clone();
+    edit(&mut edited)?;
+    edited
+        .validate()
+        .map_err(|errors| format!("Edit rejected: {}", errors.join(", ")))?;
+    Ok(edited)
+}
+
+/// Appends `entry`, which needs an id not already used in the section.
+/// Image payloads in `data` go to the image store and placeholders are
+/// expanded like they are on load.
+pub fn add_entry(
+    config: &mut QontinuiConfig,
+    section: Section,
+    mut entry: Value,
+) -> Result<String, String> {
+    let id = entry
+        .get("id")
+        .and_then(Value::as_str)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| format!("A new {} needs an id", section.noun()))?
+        .to_string();
+    if section.position(config, &id).is_some() {
+        return Err(format!("{} '{}' already exists", capitalize(section), id));
+    }
+    let placeholders = substitution::expand(&mut entry).map_err(|e| e.to_string())?;
+    config.placeholders.extend(placeholders);
+
+    if let (Section::Images, Some(fields)) = (section, entry.as_object_mut()) {
+        config.image_store.absorb(&id, fields);
+    }
+    section.put(config, usize::MAX, entry, false)?;
+    Ok(id)
+}
+
+/// Sets the top-level fields in `changes` on the entry, removing those set
+/// to null. With `replace` the entry becomes `changes` instead. The id
+/// can't be changed.
+pub fn update_entry(
+    config: &mut QontinuiConfig,
+    section: Section,
+    id: &str,
+    mut changes: Value,
+    replace: bool,
+) -> Result<(), String> {
+    let placeholders = substitution::expand(&mut changes).map_err(|e| e.to_string())?;
+    config.placeholders.extend(placeholders);
+    let Value::Object(changes) = changes else {
+        return Err(format!(
+            "Changes to {} '{}' must be an object",
+            section.noun(),
+            id
+        ));
+    };
+    if changes
+        .get("id")
+        .is_some_and(|new_id| new_id.as_str() != Some(id))
+    {
+        return Err(format!(
+            "The id of {} '{}' can't be changed",
+            section.noun(),
+            id
+        ));
+    }
+    let index = section
+        .position(config, id)
+        .ok_or_else(|| not_found(section, id))?;
+
+    let touches_payload = ["data", "path", "filePath"]
+        .iter()
+        .any(|key| changes.contains_key(*key));
+    let mut updated = if replace {
+        Map::new()
+    } else {
+        match section.get(config, index) {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        }
+    };
+    for (key, value) in changes {
+        if value.is_null() {
+            updated.remove(&key);
+        } else {
+            updated.insert(key, value);
+        }
+    }
+    updated.insert("id".to_string(), Value::String(id.to_string()));
+
+    if section == Section::Images && (replace || touches_payload) {
+        config.image_store.absorb(id, &mut updated);
+    }
+    section.put(config, index, Value::Object(updated), true)
+}
+
+/// Removes the entry and returns it. Anything still referring to it makes
+/// the edit fail validation.
+pub fn remove_entry(
+    config: &mut QontinuiConfig,
+    section: Section,
+    id: &str,
+) -> Result<Value, String> {
+    let index = section
+        .position(config, id)
+        .ok_or_else(|| not_found(section, id))?;
+    if section == Section::Images {
+        config.image_store.remove(id);
+    }
+    Ok(section.remove(config, index))
+}
+
+fn not_found(section: Section, id: &str) -> String {
+    format!("{} '{}' not found", capitalize(section), id)
+}
+
+fn capitalize(section: Section) -> String {
+    let noun = section.noun();
+    noun[..1].to_uppercase() + &noun[1..]
+}
+
+/// An entry's position, content and image payload at one point in time.
+type EntrySnapshot = (usize, Value, Option<ImageSource>);
+
+/// What one edit did to one entry.
+#[derive(Debug, Clone)]
+pub struct EntryChange {
+    section: Section,
+    id: String,
+    description: String,
+    before: Option<EntrySnapshot>,
+    after: Option<EntrySnapshot>,
+}
+
+impl EntryChange {
+    /// Captures entry `id` of `section` before and after an edit.
+    pub fn between(
+        old: &QontinuiConfig,
+        new: &QontinuiConfig,
+        section: Section,
+        id: &str,
+        description: String,
+    ) -> Self {
+        Self {
+            section,
+            id: id.to_string(),
+            description,
+            before: snapshot(old, section, id),
+            after: snapshot(new, section, id),
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn undo(&self, config: &mut QontinuiConfig) {
+        restore(config, self.section, &self.id, self.before.as_ref());
+    }
+
+    pub fn redo(&self, config: &mut QontinuiConfig) {
+        restore(config, self.section, &self.id, self.after.as_ref());
+    }
+}
+
+fn snapshot(config: &QontinuiConfig, section: Section, id: &str) -> Option<EntrySnapshot> {
+    let index = section.position(config, id)?;
+    let source = match section {
+        Section::Images => config.image_store.source(id).cloned(),
+        _ => None,
+    };
+    Some((index, section.get(config, index), source))
+}
+
+fn restore(
+    config: &mut QontinuiConfig,
+    section: Section,
+    id: &str,
+    snapshot: Option<&EntrySnapshot>,
+) {
+    if let Some(index) = section.position(config, id) {
+        section.remove(config, index);
+    }
+    if section == Section::Images {
+        let source = snapshot.and_then(|(_, _, source)| source.clone());
+        config.image_store.set_source(id, source);
+    }
+    if let Some((index, entry, _)) = snapshot {
+        // Snapshots are serialized entries, so they always parse back
+        let _ = section.put(config, *index, entry.clone(), false);
+    }
+}
+
+/// Edits made to the loaded configuration, with the undo and redo history.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EditState {
+    /// File the configuration was loaded from, which `save_configuration`
+    /// writes to unless given another path.
+    pub source_path: Option<PathBuf>,
+    /// Whether the config differs from the file, taking undos into account.
+    pub dirty: bool,
+    /// Changes since the configuration was loaded or saved, undos and
+    /// redos included.
+    pub edit_count: u32,
+    pub last_edit: Option<String>,
+    #[serde(rename = "undo_depth", serialize_with = "serialize_len")]
+    undo: VecDeque<EntryChange>,
+    #[serde(rename = "redo_depth", serialize_with = "serialize_len")]
+    redo: VecDeque<EntryChange>,
+    /// Undo depth at which the config matches the file, while that point
+    /// is still in the history.
+    #[serde(skip)]
+    clean_depth: Option<usize>,
+}
+
+impl EditState {
+    pub fn loaded(path: PathBuf) -> Self {
+        Self {
+            source_path: Some(path),
+            clean_depth: Some(0),
+            ..Default::default()
+        }
+    }
+
+    /// Records a new edit, which discards anything that could be redone.
+    pub fn record(&mut self, change: EntryChange) {
+        if self
+            .clean_depth
+            .is_some_and(|depth| depth > self.undo.len())
+        {
+            self.clean_depth = None;
+        }
+        self.redo.clear();
+        self.last_edit = Some(change.description.clone());
+        self.undo.push_back(change);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+            self.clean_depth = self.clean_depth.and_then(|depth| depth.checked_sub(1));
+        }
+        self.changed();
+    }
+
+    pub fn next_undo(&self) -> Option<&EntryChange> {
+        self.undo.back()
+    }
+
+    pub fn next_redo(&self) -> Option<&EntryChange> {
+        self.redo.back()
+    }
+
+    /// Moves the last edit to the redo history once it has been undone.
+    pub fn undone(&mut self) {
+        if let Some(change) = self.undo.pop_back() {
+            self.last_edit = Some(format!("Undid: {}", change.description));
+            self.redo.push_back(change);
+            self.changed();
+        }
+    }
+
+    pub fn redone(&mut self) {
+        if let Some(change) = self.redo.pop_back() {
+            self.last_edit = Some(format!("Redid: {}", change.description));
+            self.undo.push_back(change);
+            self.changed();
+        }
+    }
+
+    /// Marks the current config as matching the file at `path`. The
+    /// history is kept, so edits can still be undone after saving.
+    pub fn saved(&mut self, path: PathBuf) {
+        self.source_path = Some(path);
+        self.clean_depth = Some(self.undo.len());
+        self.edit_count = 0;
+        self.dirty = false;
+    }
+
+    fn changed(&mut self) {
+        self.edit_count += 1;
+        self.dirty = self.clean_depth != Some(self.undo.len());
+    }
+}
+
+fn serialize_len<S: Serializer>(
+    changes: &VecDeque<EntryChange>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(changes.len() as u64)
+}
@@ -0,0 +1,263 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Write;
+
+/// Most states, transitions or workflows a fixture may have.
+pub const MAX_ENTRIES: usize = 100_000;
+pub const MAX_IMAGES: usize = 10_000;
+pub const MAX_IMAGE_SIDE: u32 = 4096;
+/// Most pixels across all of a fixture's images.
+pub const MAX_TOTAL_PIXELS: u64 = 256 * 1024 * 1024;
+
+/// Size and shape of a generated configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FixtureSpec {
+    pub states: usize,
+    pub transitions: usize,
+    pub workflows: usize,
+    pub images: usize,
+    pub image_width: u32,
+    pub image_height: u32,
+    /// Same seed, same output.
+    pub seed: u64,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self {
+            states: 10,
+            transitions: 20,
+            workflows: 5,
+            images: 10,
+            image_width: 64,
+            image_height: 64,
+            seed: 1,
+        }
+    }
+}
+
+impl FixtureSpec {
+    /// Refuses fixtures too large to generate in memory.
+    pub fn validate(&self) -> Result<(), String> {
+        let entries = [
+            ("states", self.states),
+            ("transitions", self.transitions),
+            ("workflows", self.workflows),
+        ];
+        for (section, count) in entries {
+            if count > MAX_ENTRIES {
+                return Err(format!(
+                    "A fixture can have at most {} {}",
+                    MAX_ENTRIES, section
+                ));
+            }
+        }
+        if self.images > MAX_IMAGES {
+            return Err(format!("A fixture can have at most {} images", MAX_IMAGES));
+        }
+        if self.image_width > MAX_IMAGE_SIDE || self.image_height > MAX_IMAGE_SIDE {
+            return Err(format!(
+                "Fixture images can be at most {0}x{0} pixels",
+                MAX_IMAGE_SIDE
+            ));
+        }
+        let pixels =
+            self.images as u64 * u64::from(self.image_width) * u64::from(self.image_height);
+        if pixels > MAX_TOTAL_PIXELS {
+            return Err(format!(
+                "A fixture's images can have at most {} pixels in all",
+                MAX_TOTAL_PIXELS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a valid configuration with the requested number of entries. Every
+/// reference (transition states, identifying images, action targets) points
+/// at a generated entry, and every image is a distinct decodable PNG.
+pub fn generate(spec: &FixtureSpec) -> Value {
+    let mut rng = Rng::new(spec.seed);
+    let state_count = spec.states.max(1);
+
+    let images: Vec<Value> = (0..spec.images)
+        .map(|i| {
+            let png = encode_png(spec.image_width, spec.image_height, &mut rng);
+            json!({
+                "id": format!("image-{}", i),
+                "name": format!("Image {}", i),
+                "format": "png",
+                "width": spec.image_width,
+                "height": spec.image_height,
+                "data": format!("data:image/png;base64,{}", STANDARD.encode(png)),
+            })
+        })
+        .collect();
+
+    let states: Vec<Value> = (0..state_count)
+        .map(|i| {
+            let identifying: Vec<String> = if spec.images > 0 {
+                vec![format!("image-{}", i % spec.images)]
+            } else {
+                Vec::new()
+            };
+            json!({
+                "id": format!("state-{}", i),
+                "name": format!("State {}", i),
+                "identifyingImages": identifying,
+                "position": { "x": (i % 10) * 150, "y": (i / 10) * 150 },
+                "isInitial": i == 0,
+                "isFinal": false,
+            })
+        })
+        .collect();
+
+    let workflows: Vec<Value> = (0..spec.workflows)
+        .map(|i| {
+            let mut actions = Vec::new();
+            if spec.images > 0 {
+                let image = format!("image-{}", rng.below(spec.images));
+                actions.push(json!({
+                    "id": format!("workflow-{}-find", i),
+                    "type": "FIND",
+                    "config": { "target": { "type": "image", "imageId": image } },
+                }));
+                actions.push(json!({
+                    "id": format!("workflow-{}-click", i),
+                    "type": "CLICK",
+                    "config": { "target": { "type": "image", "imageId": image } },
+                }));
+            }
+            actions.push(json!({
+                "id": format!("workflow-{}-wait", i),
+                "type": "WAIT",
+                "config": { "duration": 100 },
+            }));
+            json!({
+                "id": format!("workflow-{}", i),
+                "name": format!("Workflow {}", i),
+                "type": "sequence",
+                "actions": actions,
+            })
+        })
+        .collect();
+
+    let transitions: Vec<Value> = (0..spec.transitions)
+        .map(|i| {
+            let from = format!("state-{}", rng.below(state_count));
+            let to = format!("state-{}", rng.below(state_count));
+            let workflow_ids: Vec<String> = if spec.workflows > 0 {
+                vec![format!("workflow-{}", i % spec.workflows)]
+            } else {
+                Vec::new()
+            };
+            json!({
+                "id": format!("transition-{}", i),
+                "type": "FromTransition",
+                "workflows": workflow_ids,
+                "timeout": 10000,
+                "retryCount": 3,
+                "fromState": from,
+                "toState": to,
+                "staysVisible": false,
+                "activateStates": [to],
+                "deactivateStates": [from],
+            })
+        })
+        .collect();
+
+    json!({
+        "version": "1.0.0",
+        "metadata": {
+            "name": format!(
+                "Fixture {}s/{}t/{}w/{}i",
+                state_count, spec.transitions, spec.workflows, spec.images
+            ),
+            "description": format!("Generated fixture (seed {})", spec.seed),
+            "author": "qontinui-runner fixture generator",
+            "tags": ["fixture"],
+        },
+        "images": images,
+        "workflows": workflows,
+        "states": states,
+        "transitions": transitions,
+        "categories": ["Fixtures"],
+        "settings": null,
+    })
+}
+
+/// Minimal 8-bit RGB PNG with random pixels.
+fn encode_png(width: u32, height: u32, rng: &mut Rng) -> Vec<u8> {
+    let width = width.max(1);
+    let height = height.max(1);
+
+    let mut raw = Vec::with_capacity(((width * 3 + 1) * height) as usize);
+    for _ in 0..height {
+        // Each scanline starts with its filter type (0, none)
+        let row = (0..width * 3).map(|_| rng.next() as u8);
+        raw.extend(std::iter::once(0).chain(row));
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    encoder
+        .write_all(&raw)
+        .expect("writing to a Vec cannot fail");
+    let idat = encoder.finish().expect("writing to a Vec cannot fail");
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit depth, RGB
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &idat);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(kind.iter().chain(data)).to_be_bytes());
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// xorshift64* — small, seedable and stable across platforms.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
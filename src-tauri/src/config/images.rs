@@ -0,0 +1,297 @@
+use super::types::ImageAsset;
+use base64::engine::general_purpose::STANDARD;
+use base64::read::DecoderReader;
+use base64::Engine;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Size of the buffer used when streaming image payloads.
+const DIGEST_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Result of streaming an image payload: validated, sized and hashed without
+/// materializing the decoded bytes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageDigest {
+    pub id: String,
+    pub sha256: String,
+    pub decoded_size: u64,
+    pub mime_type: &'static str,
+}
+
+/// Where the pixel data for an image entry lives.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// Base64 payload taken out of the configuration, decoded only when requested.
+    Embedded(String),
+    /// Image file referenced by path, read only when requested.
+    File(PathBuf),
+}
+
+/// Holds image payloads separately from the parsed configuration so the
+/// config itself only carries image metadata.
+#[derive(Debug, Clone, Default)]
+pub struct ImageStore {
+    sources: HashMap<String, ImageSource>,
+    base_dir: Option<PathBuf>,
+    /// Digests taken when the configuration was validated, dropped when an
+    /// image's source changes.
+    digests: HashMap<String, ImageDigest>,
+}
+
+impl ImageStore {
+    /// Moves the `data` payload out of each image entry, leaving id, name and
+    /// other metadata in place. Entries gain a `hasData` flag and the payload
+    /// size so the UI can still describe them.
+    pub fn extract(images: &mut [ImageAsset]) -> Self {
+        let mut store = Self::default();
+        for image in images.iter_mut() {
+            store.absorb(&image.id, &mut image.extra);
+        }
+        store
+    }
+
+    /// Takes the payload out of the fields of image `id`, as `extract`
+    /// does, replacing any source already held for the id.
+    pub fn absorb(&mut self, id: &str, entry: &mut Map<String, Value>) {
+        self.digests.remove(id);
+        let id = id.to_string();
+        let payload = match entry.remove("data") {
+            Some(Value::String(data)) if !data.is_empty() => Some(data),
+            _ => None,
+        };
+
+        if let Some(data) = payload {
+            entry.insert("hasData".to_string(), Value::Bool(true));
+            entry.insert("dataSize".to_string(), Value::from(data.len()));
+            self.sources.insert(id, ImageSource::Embedded(data));
+        } else if let Some(path) = entry
+            .get("path")
+            .or_else(|| entry.get("filePath"))
+            .and_then(Value::as_str)
+            .map(PathBuf::from)
+        {
+            entry.insert("hasData".to_string(), Value::Bool(true));
+            entry.remove("dataSize");
+            self.sources.insert(id, ImageSource::File(path));
+        } else {
+            entry.insert("hasData".to_string(), Value::Bool(false));
+            entry.remove("dataSize");
+            self.sources.remove(&id);
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<ImageSource> {
+        self.digests.remove(id);
+        self.sources.remove(id)
+    }
+
+    /// Puts back a source taken with `source`, or drops it for `None`.
+    pub fn set_source(&mut self, id: &str, source: Option<ImageSource>) {
+        self.digests.remove(id);
+        match source {
+            Some(source) => self.sources.insert(id.to_string(), source),
+            None => self.sources.remove(id),
+        };
+    }
+
+    /// Directory that relative image paths are resolved against.
+    pub fn set_base_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.base_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    pub fn source(&self, id: &str) -> Option<&ImageSource> {
+        self.sources.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Puts embedded payloads back into image entries, for handing the full
+    /// configuration to the executor.
+    pub fn embed_into(&self, images: &mut [Value]) {
+        for image in images.iter_mut() {
+            let Some(entry) = image.as_object_mut() else {
+                continue;
+            };
+            let Some(id) = entry.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            if let Some(ImageSource::Embedded(data)) = self.sources.get(id) {
+                entry.insert("data".to_string(), Value::String(data.clone()));
+            }
+        }
+    }
+
+    /// Total size of the embedded base64 payloads held in memory.
+    pub fn embedded_bytes(&self) -> usize {
+        self.sources
+            .values()
+            .map(|source| match source {
+                ImageSource::Embedded(data) => data.len(),
+                ImageSource::File(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Resolves a file source against the base directory.
+    pub fn resolve_path(&self, path: &Path) -> PathBuf {
+        match &self.base_dir {
+            Some(base) if path.is_relative() => base.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Loads the raw encoded image bytes (PNG, JPEG, ...) for an image id.
+    pub fn load(&self, id: &str) -> Result<Vec<u8>, String> {
+        match self.sources.get(id) {
+            Some(ImageSource::Embedded(data)) => STANDARD
+                .decode(strip_data_url_prefix(data))
+                .map_err(|e| format!("Failed to decode image {}: {}", id, e)),
+            Some(ImageSource::File(path)) => {
+                let path = self.resolve_path(path);
+                fs::read(&path).map_err(|e| format!("Failed to read image {:?}: {}", path, e))
+            }
+            None => Err(format!("Image not found: {}", id)),
+        }
+    }
+
+    /// Decodes and hashes an image in fixed-size chunks. Invalid base64 or an
+    /// unreadable file is reported as an error.
+    pub fn digest(&self, id: &str) -> Result<ImageDigest, String> {
+        let result = match self.sources.get(id) {
+            Some(ImageSource::Embedded(data)) => {
+                let mut reader =
+                    DecoderReader::new(strip_data_url_prefix(data).as_bytes(), &STANDARD);
+                digest_reader(id, &mut reader)
+            }
+            Some(ImageSource::File(path)) => {
+                let path = self.resolve_path(path);
+                let mut file = File::open(&path)
+                    .map_err(|e| format!("Failed to open image {:?}: {}", path, e))?;
+                digest_reader(id, &mut file)
+            }
+            None => return Err(format!("Image not found: {}", id)),
+        };
+
+        result.map_err(|e| format!("Invalid image data for {}: {}", id, e))
+    }
+
+    /// Keeps the digests validation took, for `validated_digest`.
+    pub fn remember_digests(&mut self, digests: HashMap<String, ImageDigest>) {
+        self.digests = digests;
+    }
+
+    /// The digest taken when the configuration was validated, or a new one
+    /// for an image that wasn't.
+    pub fn validated_digest(&self, id: &str) -> Result<ImageDigest, String> {
+        match self.digests.get(id) {
+            Some(digest) => Ok(digest.clone()),
+            None => self.digest(id),
+        }
+    }
+
+    /// Digests every image, returning per-image results and groups of image
+    /// ids whose decoded content is identical. `on_progress(done, total)` is
+    /// called after each image; verification stops early when it returns
+    /// false.
+    pub fn verify_with<F>(
+        &self,
+        mut on_progress: F,
+    ) -> (Vec<Result<ImageDigest, String>>, Vec<Vec<String>>)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let mut ids: Vec<&String> = self.sources.keys().collect();
+        ids.sort();
+
+        let total = ids.len();
+        let mut results: Vec<Result<ImageDigest, String>> = Vec::with_capacity(total);
+        for id in ids {
+            results.push(self.digest(id));
+            if !on_progress(results.len(), total) {
+                break;
+            }
+        }
+
+        let mut by_hash: HashMap<&str, Vec<String>> = HashMap::new();
+        for digest in results.iter().flatten() {
+            by_hash
+                .entry(digest.sha256.as_str())
+                .or_default()
+                .push(digest.id.clone());
+        }
+        let duplicates = by_hash
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect();
+
+        (results, duplicates)
+    }
+
+    /// Returns the image as a `data:` URL suitable for previews in the UI.
+    pub fn data_url(&self, id: &str) -> Result<String, String> {
+        let bytes = self.load(id)?;
+        Ok(format!(
+            "data:{};base64,{}",
+            sniff_mime_type(&bytes),
+            STANDARD.encode(&bytes)
+        ))
+    }
+}
+
+fn digest_reader<R: Read>(id: &str, reader: &mut R) -> std::io::Result<ImageDigest> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; DIGEST_CHUNK_SIZE];
+    let mut decoded_size = 0u64;
+    let mut mime_type = None;
+
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        if mime_type.is_none() {
+            mime_type = Some(sniff_mime_type(&buf[..read]));
+        }
+        hasher.update(&buf[..read]);
+        decoded_size += read as u64;
+    }
+
+    Ok(ImageDigest {
+        id: id.to_string(),
+        sha256: hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+        decoded_size,
+        mime_type: mime_type.unwrap_or("application/octet-stream"),
+    })
+}
+
+/// Strips a `data:image/png;base64,` style prefix if present.
+pub fn strip_data_url_prefix(data: &str) -> &str {
+    if data.starts_with("data:") {
+        data.split_once(',').map(|(_, rest)| rest).unwrap_or(data)
+    } else {
+        data
+    }
+}
+
+/// Guesses the MIME type of encoded image bytes from their magic number.
+pub fn sniff_mime_type(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xFF, 0xD8, ..] => "image/jpeg",
+        [b'G', b'I', b'F', ..] => "image/gif",
+        [b'B', b'M', ..] => "image/bmp",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
@@ -1,8 +1,76 @@
+use super::bundle;
+use super::images::ImageStore;
+use super::migration;
+use super::substitution;
 use super::types::QontinuiConfig;
-use serde_json;
+use super::validation::{self, Diagnostic};
+use serde_json::{Map, Value};
 use std::fs;
 use std::path::Path;
 
+/// Formats a configuration file can be written in, told apart by its
+/// extension. YAML and TOML are converted to the JSON structure on load.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// `.yaml`/`.yml` and `.toml` files; anything else is read as JSON.
+    pub fn of(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "yaml" | "yml" => ConfigFormat::Yaml,
+            "toml" => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Yaml => "YAML",
+            ConfigFormat::Toml => "TOML",
+        }
+    }
+
+    pub fn to_value(self, content: &str) -> Result<Value, String> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => content
+                .parse::<toml::Table>()
+                .map(|table| toml_to_json(toml::Value::Table(table)))
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// TOML dates become strings, as they would be written in JSON.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(date) => Value::String(date.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect::<Map<String, Value>>(),
+        ),
+    }
+}
+
 pub struct ConfigLoader;
 
 impl ConfigLoader {
@@ -13,49 +81,179 @@ impl ConfigLoader {
             return Err(format!("Configuration file not found: {:?}", path));
         }
 
-        let content = fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read configuration file: {}", e))?;
+        if bundle::is_bundle(path) {
+            return Self::load_bundle(path, &bundle::cache_dir());
+        }
+        let content = Self::read(path)?;
+        Self::load(&content, ConfigFormat::of(path), path.parent(), false)
+    }
 
-        Self::load_from_string(&content)
+    /// Loads a `.qontinui`/`.zip` bundle, unpacking it under `cache`.
+    pub fn load_bundle<P: AsRef<Path>>(bundle: P, cache: &Path) -> Result<QontinuiConfig, String> {
+        let json_path = bundle::unpack(bundle.as_ref(), cache)?;
+        let content = Self::read(&json_path)?;
+        Self::load(&content, ConfigFormat::Json, json_path.parent(), true)
     }
 
+    #[allow(dead_code)]
     pub fn load_from_string(json_str: &str) -> Result<QontinuiConfig, String> {
-        // Debug: Print first 500 chars of JSON to see what we're parsing
-        eprintln!(
-            "DEBUG: Loading JSON (first 500 chars): {}",
-            &json_str.chars().take(500).collect::<String>()
-        );
+        Self::load(json_str, ConfigFormat::Json, None, false)
+    }
 
-        // Try to parse as generic JSON first to see structure
-        if let Ok(value) = serde_json::from_str::<serde_json::Value>(json_str) {
-            // Check if states array exists and print first state
-            if let Some(states) = value.get("states") {
-                if let Some(first_state) = states.as_array().and_then(|arr| arr.first()) {
-                    eprintln!(
-                        "DEBUG: First state in JSON: {}",
-                        serde_json::to_string_pretty(first_state).unwrap_or_default()
-                    );
-                }
-            }
+    fn read(path: &Path) -> Result<String, String> {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read configuration file: {}", e))
+    }
+
+    /// Reads the configuration at `path` without validating it or expanding
+    /// its placeholders, for comparing configurations as they are written.
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<QontinuiConfig, String> {
+        let mut path = path.as_ref().to_path_buf();
+        let bundled = bundle::is_bundle(&path);
+        if bundled {
+            path = bundle::unpack(&path, &bundle::cache_dir())?;
         }
+        let content = Self::read(&path)?;
+        let format = ConfigFormat::of(&path);
+        Self::parse(&content, format, path.parent(), bundled, false).map_err(|e| {
+            format!(
+                "Failed to parse {} configuration {:?}: {}",
+                format.as_str(),
+                path,
+                e
+            )
+        })
+    }
+
+    /// Reports everything wrong with the configuration at `path`, warnings
+    /// included, instead of stopping at the first problem that prevents
+    /// loading it.
+    pub fn diagnose_file<P: AsRef<Path>>(path: P) -> Result<Vec<Diagnostic>, String> {
+        let mut path = path.as_ref().to_path_buf();
+        let bundled = bundle::is_bundle(&path);
+        if bundled {
+            path = bundle::unpack(&path, &bundle::cache_dir())?;
+        }
+        let content = Self::read(&path)?;
+
+        let format = ConfigFormat::of(&path);
+        let config = match Self::parse(&content, format, path.parent(), bundled, true) {
+            Ok(config) => config,
+            Err(diagnostic) => return Ok(vec![diagnostic]),
+        };
+        let mut diagnostics = Vec::new();
+        if let Some(version) = &config.migrated_from {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "/version",
+                    format!(
+                        "written for version {}; checked as migrated to {}, which \
+                         locations refer to",
+                        version, config.version
+                    ),
+                )
+                .suggest("Migrate the file with migrate_configuration"),
+            );
+        }
+        diagnostics.extend(validation::diagnose(&config));
+        Ok(diagnostics)
+    }
 
-        let config: QontinuiConfig = serde_json::from_str(json_str).map_err(|e| {
-            eprintln!("DEBUG: Deserialization error details: {:?}", e);
-            format!("Failed to parse JSON configuration: {}", e)
+    /// Parses and validates a configuration. `base_dir` is where relative
+    /// image paths are resolved from; for an unpacked bundle (`bundled`)
+    /// it's the directory the bundle was unpacked to.
+    fn load(
+        content: &str,
+        format: ConfigFormat,
+        base_dir: Option<&Path>,
+        bundled: bool,
+    ) -> Result<QontinuiConfig, String> {
+        let mut config = Self::parse(content, format, base_dir, bundled, true).map_err(|e| {
+            tracing::warn!("Deserialization error details: {}", e);
+            format!("Failed to parse {} configuration: {}", format.as_str(), e)
         })?;
 
-        // Validate the configuration
-        config.validate().map_err(|errors| errors.join(", "))?;
+        // Validate the configuration, keeping the image digests for the
+        // asset check
+        let digests = validation::validate(&config).map_err(|errors| errors.join(", "))?;
+        config.image_store.remember_digests(digests);
 
-        // Log execution mode configuration
-        eprintln!(
-            "DEBUG: Execution mode: {} (mock: {}, screenshot: {})",
+        tracing::info!(
+            "Execution mode: {} (mock: {}, screenshot: {})",
             config.get_execution_mode().as_str(),
             config.is_mock_mode(),
             config.is_screenshot_mode()
         );
         if let Some(screenshot_dir) = config.get_screenshot_directory() {
-            eprintln!("DEBUG: Screenshot directory: {}", screenshot_dir);
+            tracing::info!("Screenshot directory: {}", screenshot_dir);
+        }
+
+        Ok(config)
+    }
+
+    /// Parses a configuration, migrating it from older schema versions,
+    /// expanding placeholders if `expand` is set, and moves image payloads
+    /// to the image store. Doesn't validate it.
+    fn parse(
+        content: &str,
+        format: ConfigFormat,
+        base_dir: Option<&Path>,
+        bundled: bool,
+        expand: bool,
+    ) -> Result<QontinuiConfig, Diagnostic> {
+        let mut value = format
+            .to_value(content)
+            .map_err(|e| Diagnostic::error("", e))?;
+
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            tracing::debug!(
+                "Loading {} (first 500 chars): {}",
+                format.as_str(),
+                &content.chars().take(500).collect::<String>()
+            );
+            if let Some(first_state) = value
+                .get("states")
+                .and_then(Value::as_array)
+                .and_then(|arr| arr.first())
+            {
+                tracing::debug!(
+                    "First state in JSON: {}",
+                    serde_json::to_string_pretty(first_state).unwrap_or_default()
+                );
+            }
+        }
+
+        let migration = migration::migrate(&mut value).map_err(|e| Diagnostic::error("", e))?;
+        for change in &migration.changes {
+            tracing::info!("Migrating configuration: {}", change);
+        }
+
+        let placeholders = if expand {
+            substitution::expand(&mut value)?
+        } else {
+            Default::default()
+        };
+        let mut config = QontinuiConfig::from_value(&value)?;
+        config.source_format = format;
+        config.placeholders = placeholders;
+        if migration.migrated() {
+            config.migrated_from = Some(migration.from_version);
+        }
+
+        // Keep only image metadata in the config; payloads are loaded on demand
+        config.image_store = ImageStore::extract(&mut config.images);
+        if let Some(dir) = base_dir {
+            config.image_store.set_base_dir(dir);
+        }
+        tracing::debug!(
+            "Deferred {} image payloads ({} bytes embedded)",
+            config.image_store.len(),
+            config.image_store.embedded_bytes()
+        );
+
+        if let (true, Some(dir)) = (bundled, base_dir) {
+            let rewritten = bundle::rewrite_image_paths(&mut config, dir);
+            tracing::info!("Pointed {} image paths at the unpacked bundle", rewritten);
+            config.bundle_dir = Some(dir.to_path_buf());
         }
 
         Ok(config)
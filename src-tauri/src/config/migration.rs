@@ -0,0 +1,195 @@
+//! Upgrades configurations written for older schema versions, recognized by
+//! their `version` field, to the current schema before they are parsed.
+//!
+//! Version 1 configurations (`1.0`, `1.0.0`) called workflows processes,
+//! and the earliest of them listed a process's `steps` rather than its
+//! `actions` and a state's `images` rather than its `identifyingImages`.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Schema version configurations are migrated to.
+pub const CURRENT_VERSION: &str = "2.0.0";
+
+/// What a migration did to a configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: String,
+    pub to_version: String,
+    /// One entry per transformation, in the order they were applied.
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn migrated(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Migrates `config` to the current schema in place. Configurations that
+/// are current, or newer than this runner knows, are left untouched.
+pub fn migrate(config: &mut Value) -> Result<MigrationReport, String> {
+    let Some(root) = config.as_object_mut() else {
+        return Err("Configuration must be a JSON object".to_string());
+    };
+    let from_version = root
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let mut report = MigrationReport {
+        to_version: from_version.clone(),
+        from_version,
+        changes: Vec::new(),
+    };
+
+    // A missing version is reported by validation, not guessed at
+    if major_version(&report.from_version).is_some_and(|major| major < 2) {
+        from_v1(root, &mut report.changes);
+        root.insert("version".to_string(), Value::from(CURRENT_VERSION));
+        report.changes.push(format!(
+            "Set version from {} to {}",
+            report.from_version, CURRENT_VERSION
+        ));
+        report.to_version = CURRENT_VERSION.to_string();
+    }
+
+    Ok(report)
+}
+
+fn major_version(version: &str) -> Option<u32> {
+    version.split('.').next()?.trim().parse().ok()
+}
+
+fn from_v1(root: &mut Map<String, Value>, changes: &mut Vec<String>) {
+    if !root.contains_key("workflows") {
+        if let Some(processes) = root.remove("processes") {
+            changes.push(format!(
+                "Renamed processes to workflows ({} entries)",
+                processes.as_array().map_or(0, Vec::len)
+            ));
+            root.insert("workflows".to_string(), processes);
+        }
+    }
+
+    for (i, workflow) in entries_mut(root, "workflows").enumerate() {
+        let id = workflow
+            .get("id")
+            .and_then(Value::as_str)
+            .map(String::from)
+            .unwrap_or_else(|| format!("workflow-{}", i));
+        if workflow.contains_key("actions") {
+            continue;
+        }
+        let Some(Value::Array(steps)) = workflow.remove("steps") else {
+            continue;
+        };
+        let actions: Vec<Value> = steps
+            .into_iter()
+            .enumerate()
+            .map(|(j, step)| step_to_action(&id, j, step))
+            .collect();
+        changes.push(format!(
+            "Converted {} steps of workflow '{}' to actions",
+            actions.len(),
+            id
+        ));
+        workflow.insert("actions".to_string(), Value::Array(actions));
+    }
+
+    let mut renamed = 0;
+    for transition in entries_mut(root, "transitions") {
+        renamed += rename(transition, "processes", "workflows") as usize;
+    }
+    if renamed > 0 {
+        changes.push(format!(
+            "Renamed processes to workflows in {} transitions",
+            renamed
+        ));
+    }
+
+    let mut renamed = 0;
+    for state in entries_mut(root, "states") {
+        renamed += rename(state, "images", "identifyingImages") as usize;
+    }
+    if renamed > 0 {
+        changes.push(format!(
+            "Renamed images to identifyingImages in {} states",
+            renamed
+        ));
+    }
+
+    for section in ["images", "transitions"] {
+        if !root.contains_key(section) {
+            root.insert(section.to_string(), Value::Array(Vec::new()));
+            changes.push(format!("Added empty {}", section));
+        }
+    }
+
+    if !root.contains_key("categories") {
+        let mut categories: Vec<Value> = Vec::new();
+        for workflow in entries_mut(root, "workflows") {
+            if let Some(category) = workflow.get("category").filter(|c| c.is_string()) {
+                if !categories.contains(category) {
+                    categories.push(category.clone());
+                }
+            }
+        }
+        changes.push(format!(
+            "Added categories from workflows ({} found)",
+            categories.len()
+        ));
+        root.insert("categories".to_string(), Value::Array(categories));
+    }
+}
+
+/// `{"action": "type", "text": "hi"}` becomes
+/// `{"id": ..., "type": "TYPE", "config": {"text": "hi"}}`; a description
+/// stays on the action.
+fn step_to_action(workflow_id: &str, index: usize, step: Value) -> Value {
+    let Value::Object(mut fields) = step else {
+        return step;
+    };
+    let mut action = Map::new();
+    let id = fields
+        .remove("id")
+        .unwrap_or_else(|| Value::from(format!("{}-step-{}", workflow_id, index + 1)));
+    action.insert("id".to_string(), id);
+    if let Some(kind) = fields.remove("action").or_else(|| fields.remove("type")) {
+        let kind = kind
+            .as_str()
+            .map(|kind| Value::from(kind.to_uppercase()))
+            .unwrap_or(kind);
+        action.insert("type".to_string(), kind);
+    }
+    if let Some(description) = fields.remove("description") {
+        action.insert("description".to_string(), description);
+    }
+    action.insert("config".to_string(), Value::Object(fields));
+    Value::Object(action)
+}
+
+fn entries_mut<'a>(
+    root: &'a mut Map<String, Value>,
+    section: &str,
+) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    root.get_mut(section)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+/// Renames field `from` to `to` unless `to` is already set.
+fn rename(entry: &mut Map<String, Value>, from: &str, to: &str) -> bool {
+    if entry.contains_key(to) {
+        return false;
+    }
+    match entry.remove(from) {
+        Some(value) => {
+            entry.insert(to.to_string(), value);
+            true
+        }
+        None => false,
+    }
+}
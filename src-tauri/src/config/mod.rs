@@ -1,5 +1,15 @@
+pub mod bundle;
+pub mod diff;
+pub mod draft;
+pub mod dry_run;
+pub mod editor;
+pub mod fixtures;
+pub mod images;
 pub mod loader;
+pub mod migration;
+pub mod substitution;
 pub mod types;
+pub mod validation;
 
 pub use loader::ConfigLoader;
 pub use types::QontinuiConfig;
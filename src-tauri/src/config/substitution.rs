@@ -0,0 +1,218 @@
+//! Expands `${NAME}` placeholders in a configuration's strings with
+//! environment variables, so shared files don't hard-code machine paths.
+//! `$${` writes a literal `${`.
+//!
+//! `${secret:NAME}` refers to a secret in the OS keychain. It's only checked
+//! on load and left in place: the executor asks for the value with a
+//! `secret_request` when an action using it runs, so secret values are never
+//! part of the configuration the executor is sent. `$${secret:` is left
+//! escaped for the executor too.
+//!
+//! Expansion happens on load. Saving and the sections sent to the UI put
+//! the placeholders back.
+
+use super::validation::Diagnostic;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// A placeholder found in a configuration string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Placeholder {
+    Env(String),
+    Secret(String),
+}
+
+/// The strings expansion changed, by expanded value, for putting the
+/// placeholders back.
+#[derive(Debug, Clone, Default)]
+pub struct Placeholders {
+    templates: HashMap<String, String>,
+}
+
+impl Placeholders {
+    /// Whether expansion changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Adds the placeholders expanded in an edit.
+    pub fn extend(&mut self, other: Placeholders) {
+        self.templates.extend(other.templates);
+    }
+
+    /// Writes the placeholders back into `value`. Any string equal to an
+    /// expanded one gets its placeholder, so a secret is never written out
+    /// even after being copied to another entry.
+    pub fn restore(&self, value: &mut Value) {
+        if self.templates.is_empty() {
+            return;
+        }
+        match value {
+            Value::String(s) => {
+                if let Some(template) = self.templates.get(s.as_str()) {
+                    *s = template.clone();
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.restore(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.restore(field)),
+            _ => {}
+        }
+    }
+}
+
+/// Expands placeholders from the environment, checking that the secrets
+/// referred to are stored.
+pub fn expand(value: &mut Value) -> Result<Placeholders, Diagnostic> {
+    let mut stored: Option<BTreeSet<String>> = None;
+    expand_with(value, &mut |placeholder| match placeholder {
+        Placeholder::Env(name) => std::env::var(name)
+            .map(Some)
+            .map_err(|_| format!("environment variable '{}' isn't set", name)),
+        Placeholder::Secret(name) => {
+            let stored =
+                stored.get_or_insert_with(|| crate::secrets::list_names().into_iter().collect());
+            if stored.contains(name) {
+                Ok(None)
+            } else {
+                Err(format!("secret '{}' isn't stored", name))
+            }
+        }
+    })
+}
+
+/// Expands placeholders with values from `lookup`, which returns `None` to
+/// leave a placeholder as written.
+pub fn expand_with<F>(value: &mut Value, lookup: &mut F) -> Result<Placeholders, Diagnostic>
+where
+    F: FnMut(&Placeholder) -> Result<Option<String>, String>,
+{
+    let mut placeholders = Placeholders::default();
+    walk(value, &mut String::new(), lookup, &mut placeholders)?;
+    Ok(placeholders)
+}
+
+fn walk<F>(
+    value: &mut Value,
+    pointer: &mut String,
+    lookup: &mut F,
+    placeholders: &mut Placeholders,
+) -> Result<(), Diagnostic>
+where
+    F: FnMut(&Placeholder) -> Result<Option<String>, String>,
+{
+    let len = pointer.len();
+    match value {
+        Value::String(s) if s.contains("${") => {
+            let expanded = expand_str(s, lookup).map_err(|e| e.at(pointer))?;
+            // An empty value would match every empty string on restore
+            if !expanded.is_empty() && expanded != *s {
+                placeholders.templates.insert(expanded.clone(), s.clone());
+            }
+            *s = expanded;
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                pointer.push_str(&format!("/{}", i));
+                walk(item, pointer, lookup, placeholders)?;
+                pointer.truncate(len);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                walk(field, pointer, lookup, placeholders)?;
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Why a string couldn't be expanded, before its location is known.
+struct Failure {
+    message: String,
+    suggestion: &'static str,
+}
+
+impl Failure {
+    fn at(self, pointer: &str) -> Diagnostic {
+        Diagnostic::error(pointer, self.message).suggest(self.suggestion)
+    }
+}
+
+fn expand_str<F>(text: &str, lookup: &mut F) -> Result<String, Failure>
+where
+    F: FnMut(&Placeholder) -> Result<Option<String>, String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            // The executor unescapes secret placeholders itself
+            let escaped = rest[start + 2..].starts_with(SECRET_PREFIX);
+            out.push_str(&rest[..start - usize::from(!escaped)]);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| Failure {
+            message: format!("unclosed placeholder in '{}'", text),
+            suggestion: "Close it with '}', or write $${ for a literal ${",
+        })?;
+        let placeholder = parse(&rest[start + 2..start + end])?;
+        let value = lookup(&placeholder).map_err(|message| Failure {
+            message,
+            suggestion: match placeholder {
+                Placeholder::Env(_) => "Set the variable before starting the runner",
+                Placeholder::Secret(_) => "Store the secret in the runner's secret settings",
+            },
+        })?;
+        out.push_str(value.as_deref().unwrap_or(&rest[start..=start + end]));
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+const SECRET_PREFIX: &str = "secret:";
+
+/// Names of the secrets `text` refers to with `${secret:NAME}`.
+pub fn secret_names(text: &str, names: &mut BTreeSet<String>) {
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let escaped = rest[..start].ends_with('$');
+        rest = &rest[start + 2..];
+        if escaped {
+            continue;
+        }
+        let Some(end) = rest.find('}') else {
+            return;
+        };
+        if let Some(name) = rest[..end].strip_prefix(SECRET_PREFIX) {
+            names.insert(name.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+}
+
+fn parse(name: &str) -> Result<Placeholder, Failure> {
+    if let Some(secret) = name.strip_prefix(SECRET_PREFIX) {
+        crate::secrets::validate_name(secret).map_err(|message| Failure {
+            message,
+            suggestion: "Use the name the secret was stored under",
+        })?;
+        return Ok(Placeholder::Secret(secret.to_string()));
+    }
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(Failure {
+            message: format!("'${{{}}}' isn't a valid placeholder", name),
+            suggestion: "Use ${NAME} for an environment variable or ${secret:NAME} for a secret",
+        });
+    }
+    Ok(Placeholder::Env(name.to_string()))
+}
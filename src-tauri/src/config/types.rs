@@ -1,5 +1,12 @@
+use super::images::ImageStore;
+use super::loader::ConfigFormat;
+use super::substitution::{self, Placeholders};
+use super::validation::{self, Diagnostic};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -78,42 +85,185 @@ pub struct ConfigMetadata {
     pub target_application: Option<String>,
 }
 
+/// An entry of one of the id-keyed sections.
+pub trait Entry {
+    fn id(&self) -> &str;
+}
+
+/// Finds the entry with `id` in a section.
+pub fn find<'a, T: Entry>(entries: &'a [T], id: &str) -> Option<&'a T> {
+    entries.iter().find(|entry| entry.id() == id)
+}
+
+// The entries below type the fields the runner works with. Fields it
+// doesn't know are kept in `extra`, so they still reach the executor and
+// survive saving the config.
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct State {
+    /// Missing ids are reported by validation rather than by the parser.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, rename = "identifyingImages")]
+    pub identifying_images: Vec<String>,
+    #[serde(default, rename = "isInitial")]
+    pub is_initial: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Transition {
+    #[serde(default)]
+    pub id: String,
+    /// Workflows the transition runs.
+    #[serde(default)]
+    pub workflows: Vec<String>,
+    #[serde(default, rename = "fromState", skip_serializing_if = "Option::is_none")]
+    pub from_state: Option<String>,
+    #[serde(default, rename = "toState", skip_serializing_if = "Option::is_none")]
+    pub to_state: Option<String>,
+    #[serde(default, rename = "activateStates")]
+    pub activate_states: Vec<String>,
+    #[serde(default, rename = "deactivateStates")]
+    pub deactivate_states: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Transition {
+    /// States the transition enters or activates.
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.to_state
+            .as_deref()
+            .into_iter()
+            .chain(self.activate_states.iter().map(String::as_str))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Workflow {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Action {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, rename = "type")]
+    pub action_type: String,
+    /// Differs per action type, so it stays untyped.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub config: Value,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Action {
+    /// Image the action looks for, with the JSON Pointer of the field
+    /// naming it relative to the action.
+    pub fn image(&self) -> Option<(&'static str, &str)> {
+        ["/config/imageId", "/config/target/imageId"]
+            .into_iter()
+            .find_map(|field| {
+                let pointer = field.strip_prefix("/config")?;
+                Some((field, self.config.pointer(pointer)?.as_str()?))
+            })
+    }
+
+    /// Workflow the action runs.
+    pub fn workflow_id(&self) -> Option<&str> {
+        self.config.get("workflowId").and_then(Value::as_str)
+    }
+}
+
+/// Image metadata; the payload is held by the config's `ImageStore`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ImageAsset {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `data` or `path` when loaded, replaced by `hasData` and `dataSize`.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+macro_rules! impl_entry {
+    ($($entry:ty),*) => {
+        $(impl Entry for $entry {
+            fn id(&self) -> &str {
+                &self.id
+            }
+        })*
+    };
+}
+
+impl_entry!(State, Transition, Workflow, Action, ImageAsset);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QontinuiConfig {
     pub version: String,
     pub metadata: ConfigMetadata,
-    pub images: Vec<Value>,
-    pub workflows: Vec<Value>,
-    pub states: Vec<Value>,
-    pub transitions: Vec<Value>,
+    pub images: Vec<ImageAsset>,
+    pub workflows: Vec<Workflow>,
+    pub states: Vec<State>,
+    pub transitions: Vec<Transition>,
     pub categories: Vec<String>,
     pub settings: Option<Settings>,
+    /// Image payloads split out of `images` at load time.
+    #[serde(skip)]
+    pub image_store: ImageStore,
+    /// Version the file was written in, when it was migrated on load. The
+    /// file itself then can't be handed to the executor as is.
+    #[serde(skip)]
+    pub migrated_from: Option<String>,
+    /// Where the bundle the config was loaded from was unpacked.
+    #[serde(skip)]
+    pub bundle_dir: Option<PathBuf>,
+    /// Format of the file the config was loaded from.
+    #[serde(skip)]
+    pub source_format: ConfigFormat,
+    /// `${...}` placeholders expanded on load, put back when the config
+    /// leaves the runner other than for the executor.
+    #[serde(skip)]
+    pub placeholders: Placeholders,
 }
 
 impl QontinuiConfig {
-    pub fn validate(&self) -> Result<(), Vec<String>> {
-        let mut errors = Vec::new();
-
-        // Check version
-        if self.version.is_empty() {
-            errors.push("Configuration version is required".to_string());
-        }
-
-        // Check for at least one state
-        if self.states.is_empty() {
-            errors.push("At least one state is required".to_string());
-        }
+    /// Top-level sections that can be fetched individually with `section`.
+    pub const SECTIONS: [&'static str; 7] = [
+        "metadata",
+        "workflows",
+        "states",
+        "transitions",
+        "images",
+        "categories",
+        "settings",
+    ];
 
-        // Check metadata
-        if self.metadata.name.is_empty() {
-            errors.push("Configuration name is required".to_string());
-        }
+    /// Parses a configuration. When an entry doesn't match its type, the
+    /// error points at the entry, e.g. `workflows[2].actions[0]`.
+    pub fn from_value(value: &Value) -> Result<Self, Diagnostic> {
+        Self::deserialize(value).map_err(|e| {
+            failing_entry(value).unwrap_or_else(|| Diagnostic::error("", e.to_string()))
+        })
+    }
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
-        }
+    /// Runs schema, reference and image checks concurrently.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        validation::validate(self).map(|_| ())
     }
 
     pub fn summary(&self) -> String {
@@ -129,6 +279,107 @@ impl QontinuiConfig {
         )
     }
 
+    /// Whether the executor reading the file itself gets this configuration.
+    /// It doesn't for a file that needed migrating, a bundle, YAML and TOML
+    /// files, or one whose placeholders were expanded.
+    pub fn readable_as_is(&self) -> bool {
+        self.migrated_from.is_none()
+            && self.bundle_dir.is_none()
+            && self.source_format == ConfigFormat::Json
+            && self.placeholders.is_empty()
+    }
+
+    /// Serializes a single top-level section without copying the rest of the
+    /// config. Expanded placeholders are shown as written.
+    pub fn section(&self, name: &str) -> Result<Value, String> {
+        let value = match name {
+            "metadata" => serde_json::to_value(&self.metadata),
+            "workflows" => serde_json::to_value(&self.workflows),
+            "states" => serde_json::to_value(&self.states),
+            "transitions" => serde_json::to_value(&self.transitions),
+            "images" => serde_json::to_value(&self.images),
+            "categories" => serde_json::to_value(&self.categories),
+            "settings" => serde_json::to_value(&self.settings),
+            _ => return Err(format!("Unknown configuration section: {}", name)),
+        };
+
+        let mut value =
+            value.map_err(|e| format!("Failed to serialize section {}: {}", name, e))?;
+        self.placeholders.restore(&mut value);
+        Ok(value)
+    }
+
+    /// Serializes the configuration with image payloads re-embedded, exactly as
+    /// validated here, for sending to the executor.
+    pub fn to_executor_json(&self) -> Result<String, String> {
+        let mut value = serde_json::to_value(self)
+            .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+
+        if let Some(images) = value.get_mut("images").and_then(Value::as_array_mut) {
+            self.image_store.embed_into(images);
+        }
+
+        serde_json::to_string(&value)
+            .map_err(|e| format!("Failed to serialize configuration: {}", e))
+    }
+
+    /// Serializes the configuration as a file the loader can read back:
+    /// payloads re-embedded, the `hasData`/`dataSize` markers dropped and
+    /// placeholders written as they were, never their values.
+    pub fn to_file_json(&self) -> Result<String, String> {
+        let mut value = serde_json::to_value(self)
+            .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+        self.placeholders.restore(&mut value);
+
+        if let Some(images) = value.get_mut("images").and_then(Value::as_array_mut) {
+            self.image_store.embed_into(images);
+            for entry in images.iter_mut().filter_map(Value::as_object_mut) {
+                entry.remove("hasData");
+                entry.remove("dataSize");
+            }
+        }
+
+        serde_json::to_string_pretty(&value)
+            .map_err(|e| format!("Failed to serialize configuration: {}", e))
+    }
+
+    /// Secrets the actions use, by the name in their `secretName` or in a
+    /// `${secret:NAME}` placeholder.
+    pub fn secret_names(&self) -> BTreeSet<String> {
+        fn collect(value: &Value, names: &mut BTreeSet<String>) {
+            match value {
+                Value::String(s) => substitution::secret_names(s, names),
+                Value::Object(fields) => {
+                    if let Some(name) = fields.get("secretName").and_then(Value::as_str) {
+                        names.insert(name.to_string());
+                    }
+                    fields.values().for_each(|field| collect(field, names));
+                }
+                Value::Array(items) => items.iter().for_each(|item| collect(item, names)),
+                _ => {}
+            }
+        }
+        let mut names = BTreeSet::new();
+        for action in self.workflows.iter().flat_map(|w| &w.actions) {
+            collect(&action.config, &mut names);
+        }
+        names
+    }
+
+    /// Lightweight id/name/category entries for each workflow.
+    pub fn workflow_summaries(&self) -> Vec<Value> {
+        self.workflows
+            .iter()
+            .map(|w| {
+                serde_json::json!({
+                    "id": w.id,
+                    "name": w.name,
+                    "category": w.category,
+                })
+            })
+            .collect()
+    }
+
     pub fn get_execution_mode(&self) -> ExecutionMode {
         self.settings
             .as_ref()
@@ -157,3 +408,41 @@ impl QontinuiConfig {
         self.get_execution_mode().is_real()
     }
 }
+
+/// First entry in the sections of `config` that doesn't deserialize.
+fn failing_entry(config: &Value) -> Option<Diagnostic> {
+    fn check<T: DeserializeOwned>(pointer: String, entry: &Value) -> Option<Diagnostic> {
+        serde_json::from_value::<T>(entry.clone())
+            .err()
+            .map(|e| Diagnostic::error(pointer, e.to_string()))
+    }
+    fn entries<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = (usize, &'a Value)> {
+        value
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+    }
+
+    for (i, workflow) in entries(config, "workflows") {
+        for (j, action) in entries(workflow, "actions") {
+            let failure = check::<Action>(format!("/workflows/{}/actions/{}", i, j), action);
+            if failure.is_some() {
+                return failure;
+            }
+        }
+    }
+    let sections = [
+        (
+            "states",
+            check::<State> as fn(String, &Value) -> Option<Diagnostic>,
+        ),
+        ("transitions", check::<Transition>),
+        ("workflows", check::<Workflow>),
+        ("images", check::<ImageAsset>),
+    ];
+    sections.into_iter().find_map(|(section, check)| {
+        entries(config, section).find_map(|(i, entry)| check(format!("/{}/{}", section, i), entry))
+    })
+}
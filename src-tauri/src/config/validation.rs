@@ -0,0 +1,338 @@
+use super::images::ImageDigest;
+use super::migration::CURRENT_VERSION;
+use super::types::{Entry, QontinuiConfig};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The configuration won't load.
+    Error,
+    /// Loads, but probably isn't what was meant.
+    Warning,
+}
+
+/// One finding about a configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// JSON Pointer to the offending element; empty for the whole file.
+    pub pointer: String,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            pointer: pointer.into(),
+            suggestion: None,
+        }
+    }
+
+    pub fn warning(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(pointer, message)
+        }
+    }
+
+    pub fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    /// The pointer written as a path, e.g. `/states/2/id` as `states[2].id`.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in self.pointer.split('/').skip(1) {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            if segment.parse::<usize>().is_ok() {
+                path.push_str(&format!("[{}]", segment));
+            } else {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&segment);
+            }
+        }
+        path
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path() {
+            path if path.is_empty() => write!(f, "{}", self.message),
+            path => write!(f, "{}: {}", path, self.message),
+        }
+    }
+}
+
+/// Runs schema, referential and image checks on separate threads and merges
+/// their findings into a single report.
+pub fn diagnose(config: &QontinuiConfig) -> Vec<Diagnostic> {
+    check(config).0
+}
+
+/// Like `diagnose`, also returning the digests of the images that could be
+/// read, so they needn't be hashed again.
+pub fn check(config: &QontinuiConfig) -> (Vec<Diagnostic>, HashMap<String, ImageDigest>) {
+    let (schema, references, (images, digests)) = thread::scope(|s| {
+        let schema = s.spawn(|| schema_errors(config));
+        let references = s.spawn(|| reference_errors(config));
+        let images = s.spawn(|| image_errors(config));
+        (
+            join(schema.join(), "schema"),
+            join(references.join(), "reference"),
+            images
+                .join()
+                .unwrap_or_else(|_| (internal_error("image"), HashMap::new())),
+        )
+    });
+
+    let diagnostics = schema.into_iter().chain(references).chain(images).collect();
+    (diagnostics, digests)
+}
+
+/// The errors `diagnose` finds; warnings don't fail validation. A valid
+/// configuration's image digests are returned.
+pub fn validate(config: &QontinuiConfig) -> Result<HashMap<String, ImageDigest>, Vec<String>> {
+    let (diagnostics, digests) = check(config);
+    let errors: Vec<String> = diagnostics
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.to_string())
+        .collect();
+    if errors.is_empty() {
+        Ok(digests)
+    } else {
+        Err(errors)
+    }
+}
+
+fn join(result: thread::Result<Vec<Diagnostic>>, stage: &str) -> Vec<Diagnostic> {
+    result.unwrap_or_else(|_| internal_error(stage))
+}
+
+fn internal_error(stage: &str) -> Vec<Diagnostic> {
+    vec![Diagnostic::error(
+        "",
+        format!("Internal error during {} validation", stage),
+    )]
+}
+
+/// Required fields and unique ids.
+pub fn schema_errors(config: &QontinuiConfig) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+
+    // Check version
+    if config.version.is_empty() {
+        errors.push(
+            Diagnostic::error("/version", "is required")
+                .suggest(format!("Set it to \"{}\"", CURRENT_VERSION)),
+        );
+    }
+
+    // Check for at least one state
+    if config.states.is_empty() {
+        errors.push(
+            Diagnostic::error("/states", "at least one state is required")
+                .suggest("Add the state the automation starts in"),
+        );
+    } else if !config.states.iter().any(|s| s.is_initial) {
+        errors.push(
+            Diagnostic::warning("/states", "no state is marked as initial")
+                .suggest("Set isInitial on the state the automation starts in"),
+        );
+    }
+
+    // Check metadata
+    if config.metadata.name.is_empty() {
+        errors.push(
+            Diagnostic::error("/metadata/name", "is required").suggest("Name the configuration"),
+        );
+    }
+
+    id_errors("states", &config.states, &mut errors);
+    id_errors("workflows", &config.workflows, &mut errors);
+    id_errors("transitions", &config.transitions, &mut errors);
+    id_errors("images", &config.images, &mut errors);
+
+    errors
+}
+
+fn id_errors<T: Entry>(section: &str, entries: &[T], errors: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let id = entry.id();
+        let pointer = format!("/{}/{}/id", section, index);
+        if id.is_empty() {
+            errors.push(Diagnostic::error(pointer, "is required").suggest("Give the entry an id"));
+        } else if !seen.insert(id) {
+            errors.push(
+                Diagnostic::error(pointer, format!("duplicate id '{}'", id))
+                    .suggest("Rename one of the entries; ids must be unique within a section"),
+            );
+        }
+    }
+}
+
+/// Transitions, states and actions must point at entries that exist:
+/// states, images and the workflows that transitions and actions run.
+/// Images nothing points at are reported as warnings.
+pub fn reference_errors(config: &QontinuiConfig) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    let state_ids = ids(&config.states);
+    let image_ids = ids(&config.images);
+    let workflow_ids = ids(&config.workflows);
+    let mut used_images = HashSet::new();
+    let mut check = |pointer: String, kind: &str, ids: &HashSet<&str>, target: &str| {
+        if !ids.contains(target) {
+            let suggestion = match closest(target, ids) {
+                Some(id) => format!("Did you mean '{}'?", id),
+                None => format!("Add {} '{}' or remove the reference", kind, target),
+            };
+            errors.push(
+                Diagnostic::error(pointer, format!("references unknown {} '{}'", kind, target))
+                    .suggest(suggestion),
+            );
+        }
+    };
+
+    for (i, transition) in config.transitions.iter().enumerate() {
+        let pointer = format!("/transitions/{}", i);
+        for (field, target) in [
+            ("fromState", &transition.from_state),
+            ("toState", &transition.to_state),
+        ] {
+            if let Some(target) = target {
+                check(
+                    format!("{}/{}", pointer, field),
+                    "state",
+                    &state_ids,
+                    target,
+                );
+            }
+        }
+        for (field, targets, kind, ids) in [
+            (
+                "activateStates",
+                &transition.activate_states,
+                "state",
+                &state_ids,
+            ),
+            (
+                "deactivateStates",
+                &transition.deactivate_states,
+                "state",
+                &state_ids,
+            ),
+            (
+                "workflows",
+                &transition.workflows,
+                "workflow",
+                &workflow_ids,
+            ),
+        ] {
+            for (j, target) in targets.iter().enumerate() {
+                check(format!("{}/{}/{}", pointer, field, j), kind, ids, target);
+            }
+        }
+    }
+
+    for (i, state) in config.states.iter().enumerate() {
+        for (j, image) in state.identifying_images.iter().enumerate() {
+            let pointer = format!("/states/{}/identifyingImages/{}", i, j);
+            check(pointer, "image", &image_ids, image);
+            used_images.insert(image.as_str());
+        }
+    }
+
+    for (i, workflow) in config.workflows.iter().enumerate() {
+        for (j, action) in workflow.actions.iter().enumerate() {
+            let pointer = format!("/workflows/{}/actions/{}", i, j);
+            if let Some((field, image)) = action.image() {
+                check(format!("{}{}", pointer, field), "image", &image_ids, image);
+                used_images.insert(image);
+            }
+            if let Some(nested) = action.workflow_id() {
+                let pointer = format!("{}/config/workflowId", pointer);
+                check(pointer, "workflow", &workflow_ids, nested);
+            }
+        }
+    }
+
+    for (i, image) in config.images.iter().enumerate() {
+        if !image.id.is_empty() && !used_images.contains(image.id.as_str()) {
+            errors.push(
+                Diagnostic::warning(
+                    format!("/images/{}", i),
+                    format!("image '{}' isn't used by any state or action", image.id),
+                )
+                .suggest("Remove it, or reference it from a state or action"),
+            );
+        }
+    }
+
+    errors
+}
+
+/// Embedded images must decode and referenced files must be readable. The
+/// digests of those that are come back with the findings.
+pub fn image_errors(config: &QontinuiConfig) -> (Vec<Diagnostic>, HashMap<String, ImageDigest>) {
+    let mut errors = Vec::new();
+    let mut digests = HashMap::new();
+    for (i, image) in config.images.iter().enumerate() {
+        let pointer = format!("/images/{}", i);
+        if config.image_store.source(&image.id).is_none() {
+            errors.push(
+                Diagnostic::warning(pointer, format!("image '{}' has no data", image.id))
+                    .suggest("Embed the image as data, or set path to an image file"),
+            );
+            continue;
+        }
+        match config.image_store.digest(&image.id) {
+            Ok(digest) => {
+                digests.insert(image.id.clone(), digest);
+            }
+            Err(e) => errors.push(
+                Diagnostic::error(pointer, e)
+                    .suggest("Re-capture the image, or fix its path if the file was moved"),
+            ),
+        }
+    }
+    (errors, digests)
+}
+
+fn ids<T: Entry>(entries: &[T]) -> HashSet<&str> {
+    entries.iter().map(Entry::id).collect()
+}
+
+/// The id most like `target`, if one is close enough to be a typo.
+fn closest<'a>(target: &str, ids: &HashSet<&'a str>) -> Option<&'a str> {
+    ids.iter()
+        .map(|id| (edit_distance(target, id), *id))
+        .filter(|(distance, id)| *distance <= 2.max(id.len() / 4))
+        .min()
+        .map(|(_, id)| id)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -172,6 +172,144 @@ impl From<AppError> for String {
     }
 }
 
+/// A failure the Python executor reports as a plain error string, recognised
+/// by a fragment of its message.
+struct ExecutorErrorPattern {
+    code: &'static str,
+    needles: &'static [&'static str],
+    /// A message the bridge scripts actually send, used for previews.
+    sample: &'static str,
+    title: &'static str,
+    message: &'static str,
+    severity: ErrorSeverity,
+    recoverable: bool,
+    suggested_action: &'static str,
+}
+
+const EXECUTOR_ERROR_PATTERNS: &[ExecutorErrorPattern] = &[
+    ExecutorErrorPattern {
+        code: "EXEC_002",
+        needles: &["Unknown command"],
+        sample: "Unknown command: record_macro",
+        title: "Unsupported Command",
+        message: "The automation executor does not support this command.",
+        severity: ErrorSeverity::Error,
+        recoverable: true,
+        suggested_action: "Update the Python executor to match this version of the runner.",
+    },
+    ExecutorErrorPattern {
+        code: "EXEC_003",
+        needles: &["No configuration loaded"],
+        sample: "No configuration loaded",
+        title: "No Configuration Loaded",
+        message: "The executor has no configuration to run.",
+        severity: ErrorSeverity::Warning,
+        recoverable: true,
+        suggested_action: "Load a configuration file before starting an automation.",
+    },
+    ExecutorErrorPattern {
+        code: "EXEC_004",
+        needles: &["No configuration provided"],
+        sample: "No configuration provided",
+        title: "Empty Configuration",
+        message: "The executor received an empty configuration.",
+        severity: ErrorSeverity::Error,
+        recoverable: true,
+        suggested_action: "Reload the configuration file and try again.",
+    },
+    ExecutorErrorPattern {
+        code: "EXEC_005",
+        needles: &["Already running"],
+        sample: "Already running",
+        title: "Automation Already Running",
+        message: "An automation is already in progress.",
+        severity: ErrorSeverity::Info,
+        recoverable: true,
+        suggested_action: "Stop the current automation before starting another.",
+    },
+    ExecutorErrorPattern {
+        code: "EXEC_006",
+        needles: &["Invalid load_chunk parameters", "out of range for"],
+        sample: "Chunk 12 out of range for transfer-3f2a",
+        title: "Configuration Transfer Failed",
+        message: "The configuration could not be sent to the executor.",
+        severity: ErrorSeverity::Error,
+        recoverable: true,
+        suggested_action: "Reload the configuration. If it keeps failing, restart the executor.",
+    },
+    ExecutorErrorPattern {
+        code: "EXEC_007",
+        needles: &["No module named"],
+        sample: "No module named 'qontinui'",
+        title: "Missing Python Package",
+        message: "A Python package required by the executor is not installed.",
+        severity: ErrorSeverity::Critical,
+        recoverable: false,
+        suggested_action: "Install the qontinui Python package in the environment the runner uses.",
+    },
+];
+
+impl ExecutorErrorPattern {
+    fn to_user_facing(&self, details: &str) -> UserFacingError {
+        UserFacingError {
+            title: self.title.to_string(),
+            message: self.message.to_string(),
+            details: Some(details.to_string()),
+            error_code: self.code.to_string(),
+            severity: self.severity.clone(),
+            recoverable: self.recoverable,
+            suggested_action: Some(self.suggested_action.to_string()),
+        }
+    }
+}
+
+/// Maps an error string from the executor to the dialog shown for it,
+/// falling back to the generic executor error.
+pub fn executor_error_to_user_facing(error: &str) -> UserFacingError {
+    EXECUTOR_ERROR_PATTERNS
+        .iter()
+        .find(|p| p.needles.iter().any(|needle| error.contains(needle)))
+        .map(|p| p.to_user_facing(error))
+        .unwrap_or_else(|| AppError::ExecutorError(error.to_string()).to_user_facing())
+}
+
+/// One representative of every `AppError` variant.
+fn sample_app_errors() -> Vec<AppError> {
+    vec![
+        AppError::ConfigError(
+            "Failed to load configuration: Configuration file not found: \"automation.json\""
+                .to_string(),
+        ),
+        AppError::ExecutorError("Python process exited unexpectedly".to_string()),
+        AppError::IoError(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "permission denied",
+        )),
+        AppError::JsonError(serde_json::from_str::<serde_json::Value>("{").unwrap_err()),
+        AppError::ProcessError("Failed to start Python executor".to_string()),
+        AppError::CommunicationError("Failed to send command to executor".to_string()),
+        AppError::StateError("Executor not running".to_string()),
+        AppError::ValidationError("Monitor 3 not found".to_string()),
+        AppError::UnexpectedError("Background task panicked".to_string()),
+    ]
+}
+
+/// Every error dialog the runner can show, with sample details, in error
+/// code order.
+pub fn preview_errors() -> Vec<UserFacingError> {
+    let mut previews: Vec<UserFacingError> = sample_app_errors()
+        .iter()
+        .map(AppError::to_user_facing)
+        .chain(
+            EXECUTOR_ERROR_PATTERNS
+                .iter()
+                .map(|p| executor_error_to_user_facing(p.sample)),
+        )
+        .collect();
+    previews.sort_by(|a, b| a.error_code.cmp(&b.error_code));
+    previews
+}
+
 #[allow(dead_code)]
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -0,0 +1,61 @@
+//! Artifacts collected during a run for diagnosing it afterwards. When an
+//! action of the default session's run fails, the monitor it runs on is
+//! captured right away into the run's artifacts folder, and the event
+//! reporting the failure carries the screenshot's path as `screenshot`.
+
+use super::python_bridge::ExecutorEvent;
+use super::sessions::DEFAULT_SESSION;
+use crate::capture;
+use crate::commands::AppState;
+use crate::monitors;
+use serde_json::Value;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Whether `event` reports an action that failed.
+pub fn is_failed_action(event: &ExecutorEvent) -> bool {
+    event.event == "action_completed"
+        && event.data.get("success").and_then(Value::as_bool) == Some(false)
+}
+
+/// Captures a screenshot for a failed action and adds its path to the
+/// event. Nothing happens outside a run.
+pub fn attach_failure_screenshot(app_handle: &AppHandle, event: &mut ExecutorEvent) {
+    if !is_failed_action(event) {
+        return;
+    }
+    match capture_failure(app_handle, event.sequence) {
+        Ok(Some(path)) => {
+            info!("Saved failure screenshot {:?}", path);
+            if let Value::Object(data) = &mut event.data {
+                data.insert(
+                    "screenshot".to_string(),
+                    Value::String(path.to_string_lossy().into_owned()),
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to capture failure screenshot: {}", e),
+    }
+}
+
+fn capture_failure(app_handle: &AppHandle, sequence: u32) -> Result<Option<PathBuf>, String> {
+    let state = app_handle.state::<AppState>();
+    let Some(run_id) = state.run_streams.current_run(DEFAULT_SESSION) else {
+        return Ok(None);
+    };
+    let monitor_index = state
+        .session
+        .current()
+        .monitor_index
+        .map_or(0, |index| index.max(0) as usize);
+    let monitor = monitors::find(app_handle, monitor_index)?;
+    let png = capture::capture_monitor(&monitor)?;
+
+    let dir = state.run_streams.artifacts_dir(&run_id)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let path = dir.join(format!("failure-{:05}.png", sequence));
+    std::fs::write(&path, png).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(Some(path))
+}
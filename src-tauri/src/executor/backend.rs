@@ -0,0 +1,325 @@
+//! What the runner drives an executor through. `PythonBridge` runs the
+//! Python bridge as a process and hosts the executors that run inside the
+//! runner (the mock and screenshot mode); anything else that speaks the
+//! bridge protocol can be slotted in beside it. Commands go in with `send`,
+//! and responses and events come out as frames to whoever subscribed — the
+//! pipeline to the frontend, or a test's channel, so an in-process executor
+//! needs neither Python nor a running app to be driven.
+//!
+//! Only `start`, `stop`, `send` and `subscribe` make a backend; the
+//! commands the runner sends are built on `send`, and the rest has defaults
+//! for backends with no process, transport or configuration sync of their
+//! own.
+
+use super::backpressure::{BackpressureConfig, PipelineMetrics};
+use super::handshake::Handshake;
+use super::metrics::BridgeStats;
+use super::protocol::{BridgeMessage, Frame, ProtocolError};
+use super::python_bridge::PendingCommand;
+use super::sessions::DEFAULT_SESSION;
+use super::shutdown::ShutdownOutcome;
+use super::throttle::ThrottleConfig;
+use super::transport::TransportKind;
+use super::watchdog::CrashReport;
+use crate::config::QontinuiConfig;
+use serde_json::{json, Map, Value};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Receives an executor's responses and events, in order.
+pub type Subscriber = Box<dyn FnMut(Frame) + Send>;
+
+pub trait ExecutorBackend: Send {
+    /// What the handshake reports as the bridge.
+    fn name(&self) -> &'static str;
+
+    /// Commands it handles.
+    fn commands(&self) -> Vec<String>;
+
+    /// Sends its responses and events to `subscriber` from now on.
+    fn subscribe(&mut self, subscriber: Subscriber);
+
+    /// Stops sending them to the subscriber, for backends that keep it
+    /// alongside the frontend.
+    fn unsubscribe(&mut self) {}
+
+    /// Starts the executor; `executor_type` picks the kind where a backend
+    /// has several.
+    fn start(&mut self, executor_type: &str) -> Result<(), String>;
+
+    fn send(&mut self, id: &str, command: &str, params: Option<Value>) -> Result<(), String>;
+
+    /// Ends the executor and any run still going; nothing is sent
+    /// afterwards.
+    fn stop(&mut self) -> Result<ShutdownOutcome, String>;
+
+    fn is_running(&self) -> bool;
+
+    /// Session the executor runs in.
+    fn session_id(&self) -> &str {
+        DEFAULT_SESSION
+    }
+
+    /// What the executor said about its protocol version when it started.
+    fn handshake(&self) -> Handshake {
+        Handshake::in_process(self.name(), self.commands())
+    }
+
+    fn send_command(&mut self, command: &str, params: Option<Value>) -> Result<(), String> {
+        self.send_command_with_id(command, params).map(|_| ())
+    }
+
+    /// Sends a command and returns its id, which the matching response
+    /// carries.
+    fn send_command_with_id(
+        &mut self,
+        command: &str,
+        params: Option<Value>,
+    ) -> Result<String, String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.send(&id, command, params)?;
+        Ok(id)
+    }
+
+    /// Sends a command whose response can be waited for.
+    fn send_command_awaiting(
+        &mut self,
+        _command: &str,
+        _params: Option<Value>,
+    ) -> Result<PendingCommand, String> {
+        Err(format!(
+            "The {} executor's responses can't be waited for",
+            self.name()
+        ))
+    }
+
+    /// Has the executor load the configuration file at `config_path`.
+    fn load_configuration(&mut self, config_path: &str) -> Result<(), String> {
+        self.send_command("load", Some(json!({ "config_path": config_path })))
+    }
+
+    /// Sends a configuration by content. Returns true when only its
+    /// changes were sent.
+    fn sync_configuration(
+        &mut self,
+        config: &Arc<QontinuiConfig>,
+        _compress: bool,
+    ) -> Result<bool, String> {
+        let content = config.to_executor_json()?;
+        self.send_command("load", Some(json!({ "config_data": content })))?;
+        Ok(false)
+    }
+
+    fn start_execution_with_params(&mut self, params: Option<Value>) -> Result<(), String> {
+        self.send_command("start", params)
+    }
+
+    fn stop_execution(&mut self) -> Result<(), String> {
+        self.send_command("stop", None)
+    }
+
+    /// Holds the execution before its next action until `resume_execution`.
+    fn pause_execution(&mut self, reason: &str) -> Result<(), String> {
+        self.send_command("pause", Some(json!({ "reason": reason })))
+    }
+
+    fn resume_execution(&mut self) -> Result<(), String> {
+        self.send_command("resume", None)
+    }
+
+    fn get_status(&mut self) -> Result<(), String> {
+        self.send_command("status", None)
+    }
+
+    fn start_recording(&mut self, base_dir: &str) -> Result<(), String> {
+        self.send_command("start_recording", Some(json!({ "base_dir": base_dir })))
+    }
+
+    fn stop_recording(&mut self) -> Result<(), String> {
+        self.send_command("stop_recording", None)
+    }
+
+    fn get_recording_status(&mut self) -> Result<(), String> {
+        self.send_command("recording_status", None)
+    }
+
+    /// Ends the executor without asking it first, for one that holds the
+    /// mouse and keyboard.
+    fn force_kill(&mut self) -> Result<(), String> {
+        self.stop().map(|_| ())
+    }
+
+    /// Checks the executor is alive by pinging it, for the watchdog.
+    fn probe(&mut self) -> Result<PendingCommand, String> {
+        self.send_command_awaiting("ping", None)
+            .map_err(|e| format!("Executor unreachable: {}", e))
+    }
+
+    /// Gives up on a dead or hung executor.
+    fn mark_crashed(&mut self, reason: String) -> CrashReport {
+        let _ = self.stop();
+        CrashReport {
+            session_id: self.session_id().to_string(),
+            reason,
+            exit_code: None,
+            stderr_tail: Vec::new(),
+            detected_at: chrono::Local::now().to_rfc3339(),
+        }
+    }
+
+    /// Transport the running executor is connected over; none for one
+    /// inside the runner.
+    fn transport(&self) -> Option<TransportKind> {
+        None
+    }
+
+    /// Whether the executor runs on another machine, which can't read the
+    /// runner's files.
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// Whether commands cross the network unencrypted, to an executor on
+    /// another machine without TLS.
+    fn unencrypted_remote(&self) -> bool {
+        false
+    }
+
+    /// Updates the per-class limits applied to events forwarded to the
+    /// frontend, for backends whose events go through the event pipeline.
+    fn set_throttle_config(&self, _config: ThrottleConfig) {}
+
+    fn throttle_config(&self) -> ThrottleConfig {
+        ThrottleConfig::default()
+    }
+
+    /// Updates how events queued for the frontend are bounded.
+    fn set_backpressure_config(&self, _config: BackpressureConfig) {}
+
+    fn backpressure_config(&self) -> BackpressureConfig {
+        BackpressureConfig::default()
+    }
+
+    /// Enables strict protocol checking of what the executor writes.
+    fn set_strict_protocol(&self, _enabled: bool) {}
+
+    /// Counters of the events queued for the frontend, for backends whose
+    /// events go through the event pipeline.
+    fn pipeline_metrics(&self) -> Option<PipelineMetrics> {
+        None
+    }
+
+    /// Counts of the traffic with the running executor.
+    fn stats(&self) -> Arc<BridgeStats> {
+        Arc::default()
+    }
+
+    /// Strict-mode violations seen since the backend was created.
+    fn protocol_violations(&self) -> u64 {
+        0
+    }
+
+    /// Id of the executor's process, when it runs as one.
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+
+    /// Internal state for debug dumps.
+    fn snapshot(&self) -> Value {
+        json!({
+            "running": self.is_running(),
+            "backend": self.name(),
+            "handshake": self.handshake(),
+        })
+    }
+
+    /// How the executor performs, and how its messages flowed to the
+    /// frontend.
+    fn metrics(&self) -> Value {
+        json!({})
+    }
+}
+
+#[derive(Default)]
+struct Subscription {
+    subscriber: Option<Subscriber>,
+    sequence: u32,
+}
+
+/// Numbers events and hands messages to the subscriber, from any thread.
+#[derive(Clone, Default)]
+pub struct Outbox {
+    subscription: Arc<Mutex<Subscription>>,
+}
+
+impl Outbox {
+    pub fn subscribe(&self, subscriber: Subscriber) {
+        self.subscription.lock().unwrap().subscriber = Some(subscriber);
+    }
+
+    /// Drops the subscriber, so later messages go nowhere.
+    pub fn close(&self) {
+        self.subscription.lock().unwrap().subscriber = None;
+    }
+
+    pub fn event(&self, event: &str, data: Value) {
+        self.deliver(
+            message(json!({ "type": "event", "event": event, "data": data })),
+            "",
+        );
+    }
+
+    pub fn respond(&self, id: &str, reply: Result<Option<Value>, String>) {
+        let reply = match reply {
+            Ok(data) => json!({ "type": "response", "success": true, "data": data }),
+            Err(e) => json!({ "type": "response", "success": false, "error": e }),
+        };
+        self.deliver(message(reply), id);
+    }
+
+    /// Sends a message as an executor would write it, filling in what it
+    /// leaves out; responses get `id`.
+    pub fn deliver(&self, message: Map<String, Value>, id: &str) {
+        let mut subscription = self.subscription.lock().unwrap();
+        let frame = materialize(message, id, &mut subscription.sequence);
+        if let Some(subscriber) = subscription.subscriber.as_mut() {
+            subscriber(frame);
+        }
+    }
+}
+
+fn message(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(message) => message,
+        _ => Map::new(),
+    }
+}
+
+fn materialize(mut message: Map<String, Value>, id: &str, sequence: &mut u32) -> Frame {
+    match message.get("type").and_then(Value::as_str) {
+        Some("response") => {
+            message.insert("id".to_string(), json!(id));
+            message.entry("success").or_insert(json!(true));
+        }
+        Some("event") => {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0);
+            message.insert("timestamp".to_string(), json!(timestamp));
+            message.insert("sequence".to_string(), json!(*sequence));
+            message.entry("data").or_insert(json!({}));
+            *sequence += 1;
+        }
+        _ => {}
+    }
+
+    let message = Value::Object(message);
+    match serde_json::from_value::<BridgeMessage>(message.clone()) {
+        Ok(message) => Frame::Message(message),
+        Err(e) => Frame::Invalid(ProtocolError {
+            error: e.to_string(),
+            excerpt: message.to_string(),
+        }),
+    }
+}
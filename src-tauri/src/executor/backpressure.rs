@@ -0,0 +1,285 @@
+//! Bounded buffering between the thread reading the executor's messages and
+//! the one emitting them to the frontend. A chatty executor can send faster
+//! than the webview takes events, so the queue between them holds at most
+//! `capacity` messages. What gives when it's full depends on the event's
+//! type: by default the reader waits for room, which in turn stalls the
+//! executor's writes, while high-frequency progress events are coalesced (a
+//! newer one replaces the queued one of its type) or dropped. Critical
+//! events and responses always wait.
+//!
+//! Events reach the runner's own handlers before they're queued, so one
+//! that's dropped is only missing from the frontend.
+
+use super::protocol::{BridgeMessage, Frame};
+use super::throttle::EventClass;
+use crate::resources;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// What happens to an event that arrives while the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// The reader waits until the emitter catches up.
+    Block,
+    /// Replaces the queued event of the same type, full or not, and is
+    /// dropped when there's none.
+    Coalesce,
+    DropNewest,
+    /// Makes room by dropping the oldest queued event of the same type, or
+    /// the new one when there's none.
+    DropOldest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackpressureConfig {
+    pub capacity: usize,
+    /// Policy per event type; other types block.
+    #[serde(default)]
+    pub policies: HashMap<String, OverflowPolicy>,
+}
+
+impl Default for BackpressureConfig {
+    fn default() -> Self {
+        let policies = [
+            ("image_recognition", OverflowPolicy::Coalesce),
+            ("state_check_performed", OverflowPolicy::Coalesce),
+            ("screenshot_taken", OverflowPolicy::Coalesce),
+            ("dsl_loop_iteration", OverflowPolicy::Coalesce),
+            ("match_found", OverflowPolicy::DropOldest),
+            ("log", OverflowPolicy::DropNewest),
+        ];
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            policies: policies
+                .into_iter()
+                .map(|(event, policy)| (event.to_string(), policy))
+                .collect(),
+        }
+    }
+}
+
+impl BackpressureConfig {
+    pub fn policy(&self, frame: &Frame) -> OverflowPolicy {
+        match frame {
+            Frame::Message(BridgeMessage::Event(event))
+                if EventClass::of(event) != EventClass::Critical =>
+            {
+                self.policies
+                    .get(&event.event)
+                    .copied()
+                    .unwrap_or(OverflowPolicy::Block)
+            }
+            _ => OverflowPolicy::Block,
+        }
+    }
+}
+
+/// Counters of a bridge's event queues, shared by its readers.
+#[derive(Debug, Default)]
+pub struct Counters {
+    received: AtomicU64,
+    forwarded: AtomicU64,
+    coalesced: AtomicU64,
+    dropped: AtomicU64,
+    throttled: AtomicU64,
+    blocked: AtomicU64,
+    blocked_ms: AtomicU64,
+    queued: AtomicU64,
+    queued_bytes: AtomicU64,
+    peak_queued: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PipelineMetrics {
+    pub received: u64,
+    pub forwarded: u64,
+    pub coalesced: u64,
+    pub dropped: u64,
+    /// Emitted too fast for the event throttle.
+    pub throttled: u64,
+    /// Times the reader waited for room, and for how long in total.
+    pub blocked: u64,
+    pub blocked_ms: u64,
+    pub queued: u64,
+    /// Roughly how much memory the queued events hold.
+    pub queued_bytes: u64,
+    pub peak_queued: u64,
+}
+
+impl PipelineMetrics {
+    /// Events that never reached the frontend, one way or another.
+    pub fn lost(&self) -> u64 {
+        self.coalesced + self.dropped + self.throttled
+    }
+}
+
+impl Counters {
+    pub fn snapshot(&self) -> PipelineMetrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        PipelineMetrics {
+            received: load(&self.received),
+            forwarded: load(&self.forwarded),
+            coalesced: load(&self.coalesced),
+            dropped: load(&self.dropped),
+            throttled: load(&self.throttled),
+            blocked: load(&self.blocked),
+            blocked_ms: load(&self.blocked_ms),
+            queued: load(&self.queued),
+            queued_bytes: load(&self.queued_bytes),
+            peak_queued: load(&self.peak_queued),
+        }
+    }
+
+    pub fn count_throttled(&self) {
+        Self::count(&self.throttled);
+    }
+
+    fn count(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct Queued {
+    frames: VecDeque<Frame>,
+    closed: bool,
+}
+
+/// Frames one reader hands to its emitter.
+#[derive(Clone)]
+pub struct EventQueue {
+    queued: Arc<(Mutex<Queued>, Condvar)>,
+    config: Arc<Mutex<BackpressureConfig>>,
+    counters: Arc<Counters>,
+}
+
+fn event_type(frame: &Frame) -> Option<&str> {
+    match frame {
+        Frame::Message(BridgeMessage::Event(event)) => Some(&event.event),
+        _ => None,
+    }
+}
+
+fn frame_bytes(frame: &Frame) -> u64 {
+    let bytes = match frame {
+        Frame::Message(BridgeMessage::Event(event)) => resources::event_bytes(event),
+        Frame::Message(BridgeMessage::Response(response)) => {
+            response.data.as_ref().map_or(0, resources::json_bytes)
+        }
+        Frame::Invalid(error) => error.excerpt.len(),
+    };
+    (std::mem::size_of::<Frame>() + bytes) as u64
+}
+
+impl EventQueue {
+    pub fn new(config: Arc<Mutex<BackpressureConfig>>, counters: Arc<Counters>) -> Self {
+        Self {
+            queued: Default::default(),
+            config,
+            counters,
+        }
+    }
+
+    /// Queues `frame`, waiting for room or dropping it as its policy says.
+    pub fn push(&self, frame: Frame) {
+        let (capacity, policy) = {
+            let config = self.config.lock().unwrap();
+            (config.capacity.max(1), config.policy(&frame))
+        };
+        Counters::count(&self.counters.received);
+        let (lock, changed) = &*self.queued;
+        let mut queued = lock.lock().unwrap();
+        let kind = event_type(&frame).map(str::to_string);
+        let same_type = |other: &Frame| event_type(other) == kind.as_deref();
+
+        if policy == OverflowPolicy::Coalesce {
+            if let Some(i) = queued.frames.iter().rposition(same_type) {
+                self.counters
+                    .queued_bytes
+                    .fetch_add(frame_bytes(&frame), Ordering::Relaxed);
+                let replaced = std::mem::replace(&mut queued.frames[i], frame);
+                self.counters
+                    .queued_bytes
+                    .fetch_sub(frame_bytes(&replaced), Ordering::Relaxed);
+                Counters::count(&self.counters.coalesced);
+                return;
+            }
+        }
+        if queued.frames.len() >= capacity {
+            match policy {
+                OverflowPolicy::Block => {
+                    Counters::count(&self.counters.blocked);
+                    let waiting = Instant::now();
+                    while queued.frames.len() >= capacity && !queued.closed {
+                        queued = changed.wait(queued).unwrap();
+                    }
+                    self.counters
+                        .blocked_ms
+                        .fetch_add(waiting.elapsed().as_millis() as u64, Ordering::Relaxed);
+                }
+                OverflowPolicy::Coalesce | OverflowPolicy::DropNewest => {
+                    Counters::count(&self.counters.dropped);
+                    return;
+                }
+                OverflowPolicy::DropOldest => {
+                    Counters::count(&self.counters.dropped);
+                    let Some(i) = queued.frames.iter().position(same_type) else {
+                        return;
+                    };
+                    if let Some(removed) = queued.frames.remove(i) {
+                        self.counters
+                            .queued_bytes
+                            .fetch_sub(frame_bytes(&removed), Ordering::Relaxed);
+                    }
+                    self.counters.queued.fetch_sub(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        self.counters
+            .queued_bytes
+            .fetch_add(frame_bytes(&frame), Ordering::Relaxed);
+        queued.frames.push_back(frame);
+        let now_queued = self.counters.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        self.counters
+            .peak_queued
+            .fetch_max(now_queued, Ordering::Relaxed);
+        changed.notify_all();
+    }
+
+    /// The next frame to emit, waiting for one. `None` once the queue is
+    /// closed and drained.
+    pub fn pop(&self) -> Option<Frame> {
+        let (lock, changed) = &*self.queued;
+        let mut queued = lock.lock().unwrap();
+        loop {
+            if let Some(frame) = queued.frames.pop_front() {
+                self.counters.queued.fetch_sub(1, Ordering::Relaxed);
+                self.counters
+                    .queued_bytes
+                    .fetch_sub(frame_bytes(&frame), Ordering::Relaxed);
+                Counters::count(&self.counters.forwarded);
+                changed.notify_all();
+                return Some(frame);
+            }
+            if queued.closed {
+                return None;
+            }
+            queued = changed.wait(queued).unwrap();
+        }
+    }
+
+    /// No more frames will be pushed; the emitter ends once it has the
+    /// queued ones.
+    pub fn close(&self) {
+        let (lock, changed) = &*self.queued;
+        lock.lock().unwrap().closed = true;
+        changed.notify_all();
+    }
+}
@@ -0,0 +1,73 @@
+use serde_json::{Map, Value};
+
+const EVENT_FIELDS: [&str; 5] = ["type", "event", "timestamp", "sequence", "data"];
+const RESPONSE_FIELDS: [&str; 5] = ["type", "id", "success", "data", "error"];
+
+/// Checks one raw protocol line against the strict message schema. Lenient
+/// decoding accepts extra fields and loose types; strict mode reports them
+/// so bridge scripts can be kept to the protocol.
+pub fn check(line: &[u8]) -> Vec<String> {
+    let value: Value = match serde_json::from_slice(line) {
+        Ok(value) => value,
+        Err(e) => return vec![format!("not valid JSON: {}", e)],
+    };
+    let Some(message) = value.as_object() else {
+        return vec!["message is not a JSON object".to_string()];
+    };
+
+    let mut violations = Vec::new();
+    match message.get("type").and_then(Value::as_str) {
+        Some("event") => check_event(message, &mut violations),
+        Some("response") => check_response(message, &mut violations),
+        Some(other) => violations.push(format!("unknown message type '{}'", other)),
+        None => violations.push("missing string field 'type'".to_string()),
+    }
+    violations
+}
+
+fn check_event(message: &Map<String, Value>, violations: &mut Vec<String>) {
+    match message.get("event").and_then(Value::as_str) {
+        Some(name) if !name.is_empty() => {}
+        _ => violations.push("event: 'event' must be a non-empty string".to_string()),
+    }
+    match message.get("timestamp").and_then(Value::as_f64) {
+        Some(timestamp) if timestamp >= 0.0 => {}
+        _ => violations.push("event: 'timestamp' must be a non-negative number".to_string()),
+    }
+    if message.get("sequence").and_then(Value::as_u64).is_none() {
+        violations.push("event: 'sequence' must be a non-negative integer".to_string());
+    }
+    if !message.get("data").is_some_and(Value::is_object) {
+        violations.push("event: 'data' must be an object".to_string());
+    }
+    unknown_fields(message, &EVENT_FIELDS, "event", violations);
+}
+
+fn check_response(message: &Map<String, Value>, violations: &mut Vec<String>) {
+    match message.get("id").and_then(Value::as_str) {
+        Some(id) if !id.is_empty() => {}
+        _ => violations.push("response: 'id' must be a non-empty string".to_string()),
+    }
+    if !message.get("success").is_some_and(Value::is_boolean) {
+        violations.push("response: 'success' must be a boolean".to_string());
+    }
+    if let Some(error) = message.get("error") {
+        if !(error.is_string() || error.is_null()) {
+            violations.push("response: 'error' must be a string or null".to_string());
+        }
+    }
+    unknown_fields(message, &RESPONSE_FIELDS, "response", violations);
+}
+
+fn unknown_fields(
+    message: &Map<String, Value>,
+    allowed: &[&str],
+    kind: &str,
+    violations: &mut Vec<String>,
+) {
+    for key in message.keys() {
+        if !allowed.contains(&key.as_str()) {
+            violations.push(format!("{}: unknown field '{}'", kind, key));
+        }
+    }
+}
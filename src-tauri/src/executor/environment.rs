@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use tracing::{debug, info};
+
+/// Bridge scripts the runner knows how to launch.
+pub const BRIDGE_SCRIPTS: [&str; 2] = ["qontinui_bridge.py", "qontinui_executor.py"];
+
+/// Directory to load the bridge scripts from instead of the bundled ones,
+/// e.g. to try local changes against a packaged build.
+pub const BRIDGE_DIR_ENV: &str = "QONTINUI_BRIDGE_DIR";
+
+/// How the Python interpreter for a bridge script is invoked.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PythonLauncher {
+    /// `poetry run python` inside the qontinui project directory.
+    Poetry { project_dir: PathBuf },
+    /// Interpreter from the virtualenv next to the bridge scripts.
+    Venv(PathBuf),
+    /// Interpreter from the environment the runner provisioned itself.
+    Managed(PathBuf),
+    /// Interpreter chosen with `set_python_interpreter`.
+    Selected(PathBuf),
+    /// `python` / `python3` from PATH.
+    System(String),
+}
+
+/// How the executor process is launched beyond the interpreter and script,
+/// saved per profile.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutorLaunchOptions {
+    /// Set in the executor's environment, over inherited values.
+    pub env: BTreeMap<String, String>,
+    /// Directory the executor runs in; the qontinui project for Poetry and
+    /// the runner's own otherwise when unset.
+    pub working_dir: Option<PathBuf>,
+    /// Passed to the interpreter before the script, e.g. `-X faulthandler`.
+    pub interpreter_args: Vec<String>,
+    /// Put in front of `PYTHONPATH`.
+    pub python_path: Vec<PathBuf>,
+}
+
+impl ExecutorLaunchOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        for name in self.env.keys() {
+            if name.is_empty() || name.contains('=') || name.contains('\0') {
+                return Err(format!("Invalid environment variable name '{}'", name));
+            }
+        }
+        if let Some(dir) = &self.working_dir {
+            if !dir.is_dir() {
+                return Err(format!("Working directory {:?} doesn't exist", dir));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `entries` in front of an `existing` `PYTHONPATH`.
+pub fn python_path(entries: &[PathBuf], existing: Option<OsString>) -> Result<OsString, String> {
+    let existing = existing.filter(|existing| !existing.is_empty());
+    let paths = entries
+        .iter()
+        .cloned()
+        .chain(existing.iter().flat_map(std::env::split_paths));
+    std::env::join_paths(paths).map_err(|e| format!("Invalid PYTHONPATH entry: {}", e))
+}
+
+/// A resolved bridge script together with the interpreter used to run it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PythonEnvironment {
+    pub script: PathBuf,
+    pub launcher: PythonLauncher,
+}
+
+impl PythonEnvironment {
+    /// Builds the command that runs the bridge script, launched as
+    /// `options` say. Callers add flags and stdio.
+    pub fn command(&self, options: &ExecutorLaunchOptions) -> Result<Command, String> {
+        let mut cmd = match &self.launcher {
+            PythonLauncher::Poetry { project_dir } => {
+                let mut cmd = Command::new("poetry");
+                if options.working_dir.is_some() {
+                    // Poetry otherwise finds the project from where it runs
+                    cmd.arg("--directory").arg(project_dir);
+                }
+                cmd.current_dir(project_dir);
+                cmd.arg("run").arg("python");
+                cmd
+            }
+            PythonLauncher::Venv(python)
+            | PythonLauncher::Managed(python)
+            | PythonLauncher::Selected(python) => Command::new(python),
+            PythonLauncher::System(python) => Command::new(python),
+        };
+        cmd.args(&options.interpreter_args).arg(&self.script);
+
+        cmd.envs(&options.env);
+        if !options.python_path.is_empty() {
+            let existing = options
+                .env
+                .get("PYTHONPATH")
+                .map(OsString::from)
+                .or_else(|| std::env::var_os("PYTHONPATH"));
+            cmd.env("PYTHONPATH", python_path(&options.python_path, existing)?);
+        }
+        if let Some(dir) = &options.working_dir {
+            cmd.current_dir(dir);
+        }
+        Ok(cmd)
+    }
+}
+
+fn bundled_dir() -> &'static Mutex<Option<PathBuf>> {
+    static BUNDLED_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    &BUNDLED_DIR
+}
+
+/// Sets where the bridge scripts were installed as Tauri resources. Called
+/// during setup, once the resource directory is known.
+pub fn set_bundled_dir(dir: PathBuf) {
+    *bundled_dir().lock().unwrap() = Some(dir);
+    invalidate_cache();
+}
+
+fn selected() -> &'static Mutex<Option<PathBuf>> {
+    static SELECTED: Mutex<Option<PathBuf>> = Mutex::new(None);
+    &SELECTED
+}
+
+/// Runs bridge scripts with `python` instead of detecting an interpreter,
+/// or goes back to detection with `None`.
+pub fn set_interpreter(python: Option<PathBuf>) {
+    *selected().lock().unwrap() = python;
+    invalidate_cache();
+}
+
+pub fn interpreter() -> Option<PathBuf> {
+    selected().lock().unwrap().clone()
+}
+
+fn cache() -> &'static Mutex<HashMap<String, PythonEnvironment>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, PythonEnvironment>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Returns the environment for a bridge script, probing the filesystem only
+/// the first time a script is requested.
+pub fn resolve(script_name: &str) -> Result<PythonEnvironment, String> {
+    if let Some(env) = cache().lock().unwrap().get(script_name) {
+        return Ok(env.clone());
+    }
+
+    let env = discover(script_name)?;
+    cache()
+        .lock()
+        .unwrap()
+        .insert(script_name.to_string(), env.clone());
+    Ok(env)
+}
+
+/// Environments resolved so far, keyed by script name.
+pub fn cached() -> HashMap<String, PythonEnvironment> {
+    cache().lock().unwrap().clone()
+}
+
+/// Drops cached environments so the next start probes again. Called when
+/// interpreter-related settings change.
+pub fn invalidate_cache() {
+    cache().lock().unwrap().clear();
+    debug!("Python environment cache cleared");
+}
+
+/// Resolves all known bridge scripts in the background so the first executor
+/// start doesn't pay for probing.
+pub fn prewarm() {
+    thread::spawn(discover_all);
+}
+
+/// Resolves and caches the environment for every bridge script.
+pub fn discover_all() {
+    for script_name in BRIDGE_SCRIPTS {
+        match resolve(script_name) {
+            Ok(env) => debug!("Prewarmed environment for {}: {:?}", script_name, env),
+            Err(e) => debug!("Could not prewarm {}: {}", script_name, e),
+        }
+    }
+}
+
+fn discover(script_name: &str) -> Result<PythonEnvironment, String> {
+    let script = find_script(script_name)?;
+
+    if let Some(python) = interpreter() {
+        let launcher = PythonLauncher::Selected(python);
+        info!("Resolved {} with {:?}", script.display(), launcher);
+        return Ok(PythonEnvironment { script, launcher });
+    }
+
+    // Bridge scripts that need the qontinui library run through Poetry when the
+    // library checkout is next to the runner; the others fall back to a venv
+    // or the system interpreter.
+    let use_poetry = script_name == "qontinui_executor.py" || script_name == "qontinui_bridge.py";
+
+    let qontinui_dir = script
+        .parent()
+        .and_then(|p| p.parent()) // Go up from python-bridge to qontinui-runner
+        .and_then(|p| p.parent()) // Go up to qontinui_parent
+        .map(|p| p.join("qontinui"));
+    let venv_path = script.parent().map(|p| p.join("venv/Scripts/python.exe"));
+
+    // Probe the Poetry project and the venvs concurrently
+    let (poetry_dir, venv_python, managed_python) = thread::scope(|s| {
+        let poetry = s.spawn(|| {
+            qontinui_dir
+                .as_ref()
+                .filter(|dir| use_poetry && dir.join("pyproject.toml").exists())
+                .cloned()
+        });
+        let venv = s.spawn(|| venv_path.as_ref().filter(|p| p.exists()).cloned());
+        let managed = s.spawn(super::provision::managed_python);
+        (
+            poetry.join().unwrap_or_default(),
+            venv.join().unwrap_or_default(),
+            managed.join().unwrap_or_default(),
+        )
+    });
+
+    let launcher = if let Some(project_dir) = poetry_dir {
+        PythonLauncher::Poetry { project_dir }
+    } else if let Some(python) = venv_python {
+        PythonLauncher::Venv(python)
+    } else if let Some(python) = managed_python {
+        PythonLauncher::Managed(python)
+    } else if cfg!(target_os = "windows") {
+        PythonLauncher::System("python".to_string())
+    } else {
+        PythonLauncher::System("python3".to_string())
+    };
+
+    info!("Resolved {} with {:?}", script.display(), launcher);
+    Ok(PythonEnvironment { script, launcher })
+}
+
+fn find_script(script_name: &str) -> Result<PathBuf, String> {
+    let override_dir = std::env::var_os(BRIDGE_DIR_ENV).map(PathBuf::from);
+    let dirs = bridge_dirs(override_dir, bundled_dir().lock().unwrap().clone());
+    debug!("Resolving {} from {:?}", script_name, dirs);
+
+    dirs.iter()
+        .map(|dir| dir.join(script_name))
+        .find(|path| path.exists())
+        .ok_or(format!(
+            "Python bridge script {} not found in {:?}",
+            script_name, dirs
+        ))
+}
+
+/// Directories searched for bridge scripts, in order. The override replaces
+/// the others so a broken override isn't masked by the bundled scripts.
+/// Debug builds run from a checkout, where the scripts aren't copied
+/// anywhere, so they also look in the source tree.
+pub fn bridge_dirs(override_dir: Option<PathBuf>, bundled: Option<PathBuf>) -> Vec<PathBuf> {
+    if let Some(dir) = override_dir {
+        return vec![dir];
+    }
+    let mut dirs: Vec<PathBuf> = bundled.into_iter().collect();
+    if cfg!(debug_assertions) {
+        if let Some(root) = Path::new(env!("CARGO_MANIFEST_DIR")).parent() {
+            dirs.push(root.join("python-bridge"));
+        }
+    }
+    dirs
+}
@@ -0,0 +1,198 @@
+//! Where everything the Python process writes ends up. Events from stdout
+//! go through the runner's hooks (input limits, safe mode, idle tracking,
+//! run plan, progress, execution queue, session, variables, secrets and
+//! webhook notifications) before the pipeline forwards them (only input limits,
+//! safe mode, secrets and notifications for sessions other than the
+//! default one); stderr lines and reader failures become `bridge-log` entries,
+//! tagged with the current run and a severity, that are written to the
+//! file log and sent to the frontend. Gaps in the executor's event numbering
+//! are reported as `events-dropped` too.
+
+use super::input_limits::{self, InputRateLimiter};
+use super::metrics::SequenceGap;
+use super::python_bridge::ExecutorEvent;
+use super::safe_mode;
+use super::sessions::{self, DEFAULT_SESSION};
+use crate::capture::Region;
+use crate::commands::AppState;
+use crate::{idle, notifications, progress, queue, run_plan, secrets, session, variables};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Severity of a line Python wrote to stderr. Tracebacks and records
+    /// from the `logging` module are recognized; anything else on stderr is
+    /// unexpected and counts as a warning.
+    pub fn of_stderr(line: &str) -> Self {
+        let line = line.trim_start();
+        let level = line.split([':', ' ']).next().unwrap_or_default();
+        match level {
+            "DEBUG" => Self::Debug,
+            "INFO" => Self::Info,
+            "ERROR" | "CRITICAL" | "Traceback" => Self::Error,
+            _ if line.ends_with("Error") || line.contains("Error: ") => Self::Error,
+            _ => Self::Warning,
+        }
+    }
+}
+
+/// Like `Severity::of_stderr`, but keeps a traceback together: its
+/// indented frames and the exception line that ends it are errors too.
+#[derive(Debug, Default)]
+pub struct StderrSeverity {
+    in_traceback: bool,
+}
+
+impl StderrSeverity {
+    pub fn classify(&mut self, line: &str) -> Severity {
+        if line.starts_with("Traceback") {
+            self.in_traceback = true;
+            return Severity::Error;
+        }
+        if self.in_traceback {
+            if !line.starts_with(char::is_whitespace) {
+                self.in_traceback = false;
+            }
+            return Severity::Error;
+        }
+        Severity::of_stderr(line)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineSource {
+    Stderr,
+    /// Raised by the runner while reading the process, e.g. a read error.
+    Runner,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeLine {
+    pub session_id: String,
+    pub source: LineSource,
+    pub severity: Severity,
+    pub run_id: Option<String>,
+    pub timestamp: String,
+    pub message: String,
+}
+
+/// One per executor process; the hooks keep per-run state here.
+pub struct EventBus {
+    app_handle: AppHandle,
+    session_id: String,
+    input_limiter: Option<InputRateLimiter>,
+    safe_bounds: Option<Region>,
+    stderr_severity: StderrSeverity,
+}
+
+impl EventBus {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self {
+            app_handle,
+            session_id: DEFAULT_SESSION.to_string(),
+            input_limiter: None,
+            safe_bounds: None,
+            stderr_severity: StderrSeverity::default(),
+        }
+    }
+
+    pub fn for_session(mut self, session_id: &str) -> Self {
+        self.session_id = session_id.to_string();
+        self
+    }
+
+    /// Runs the runner's hooks for an event from the executor.
+    pub fn dispatch(&mut self, event: &ExecutorEvent) {
+        let app = &self.app_handle;
+        let session_id = self.session_id.as_str();
+        input_limits::handle_event(app, session_id, &mut self.input_limiter, event);
+        safe_mode::handle_event(app, session_id, &mut self.safe_bounds, event);
+        if event.event == "secret_request" {
+            secrets::answer_request(app, session_id, &event.data);
+        }
+        notifications::record_event(app, session_id, event);
+        if session_id != DEFAULT_SESSION {
+            return;
+        }
+        idle::record_event(app, event);
+        run_plan::record_event(app, event);
+        progress::record_event(app, event);
+        queue::record_event(app, event);
+        session::record_event(app, event);
+        if event.event == "variable_set" {
+            variables::apply_event(app, &event.data);
+        }
+    }
+
+    pub fn stderr(&mut self, line: String) {
+        let severity = self.stderr_severity.classify(&line);
+        self.publish(LineSource::Stderr, severity, line);
+    }
+
+    pub fn runner(&self, severity: Severity, message: String) {
+        self.publish(LineSource::Runner, severity, message);
+    }
+
+    /// Warns that events went missing between two the executor numbered.
+    pub fn events_lost(&self, gap: SequenceGap) {
+        let _ = sessions::emit(
+            &self.app_handle,
+            "events-dropped",
+            &self.session_id,
+            &json!({
+                "dropped": gap.dropped,
+                "expected_sequence": gap.expected,
+                "received_sequence": gap.received,
+                "run_id": self.run_id(),
+            }),
+        );
+        self.runner(
+            Severity::Warning,
+            format!(
+                "Lost {} executor events: expected #{}, received #{}",
+                gap.dropped, gap.expected, gap.received
+            ),
+        );
+    }
+
+    fn run_id(&self) -> Option<String> {
+        self.app_handle
+            .state::<AppState>()
+            .run_streams
+            .current_run(&self.session_id)
+    }
+
+    fn publish(&self, source: LineSource, severity: Severity, message: String) {
+        let run_id = self.run_id();
+        let run = run_id.as_deref().unwrap_or("-");
+        let session = self.session_id.as_str();
+        match severity {
+            Severity::Debug => tracing::debug!(session, run_id = run, ?source, "{}", message),
+            Severity::Info => tracing::info!(session, run_id = run, ?source, "{}", message),
+            Severity::Warning => tracing::warn!(session, run_id = run, ?source, "{}", message),
+            Severity::Error => tracing::error!(session, run_id = run, ?source, "{}", message),
+        }
+        let _ = self.app_handle.emit(
+            "bridge-log",
+            BridgeLine {
+                session_id: self.session_id.clone(),
+                source,
+                severity,
+                run_id,
+                timestamp: chrono::Local::now().to_rfc3339(),
+                message,
+            },
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Per-run parameters. A configuration's execution settings apply to every
+//! run of it; an `ExecutionRequest` overrides some of them for one run —
+//! how fast to go, how often to retry a failing action, whether to stop at
+//! the first failure — and binds variables the workflow reads, so one
+//! configuration can be parameterized per run without being edited. The
+//! request is checked here and sent to the executor with `start`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const MIN_SPEED: f64 = 0.1;
+pub const MAX_SPEED: f64 = 10.0;
+pub const MAX_RETRIES: u32 = 10;
+const MAX_TAGS: usize = 32;
+const MAX_TAG_LEN: usize = 64;
+
+/// What a run does when an action still fails after its retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureStrategy {
+    Stop,
+    /// Moves on to the next action; the run still ends unsuccessful.
+    Continue,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionRequest {
+    /// Multiplies the pace between actions; 2.0 runs twice as fast.
+    pub speed: Option<f64>,
+    /// Further attempts at a failing action.
+    pub max_retries: Option<u32>,
+    /// Overrides the configuration's failure strategy.
+    pub failure_strategy: Option<FailureStrategy>,
+    /// Variables for this run only, read before the stored ones and never
+    /// stored.
+    pub variables: Map<String, Value>,
+    /// Labels echoed in the run's `execution_started` event, for telling
+    /// runs apart.
+    pub tags: Vec<String>,
+}
+
+impl ExecutionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(speed) = self.speed {
+            if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+                return Err(format!(
+                    "Speed {} is outside {} to {}",
+                    speed, MIN_SPEED, MAX_SPEED
+                ));
+            }
+        }
+        if let Some(retries) = self.max_retries {
+            if retries > MAX_RETRIES {
+                return Err(format!(
+                    "{} retries is more than the maximum of {}",
+                    retries, MAX_RETRIES
+                ));
+            }
+        }
+        validate_variables(&self.variables)?;
+        if self.tags.len() > MAX_TAGS {
+            return Err(format!("More than {} tags", MAX_TAGS));
+        }
+        for tag in &self.tags {
+            if tag.trim().is_empty() || tag.len() > MAX_TAG_LEN {
+                return Err(format!(
+                    "Tag {:?} must be 1 to {} characters",
+                    tag, MAX_TAG_LEN
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the request to `start` params. Unset overrides are left out, so
+    /// the configuration's settings apply.
+    pub fn insert_into(&self, params: &mut Map<String, Value>) {
+        if let Some(speed) = self.speed {
+            params.insert("speed".to_string(), Value::from(speed));
+        }
+        if let Some(retries) = self.max_retries {
+            params.insert("max_retries".to_string(), Value::from(retries));
+        }
+        if let Some(strategy) = self.failure_strategy {
+            params.insert("failure_strategy".to_string(), serde_json::json!(strategy));
+        }
+        if !self.variables.is_empty() {
+            params.insert(
+                "variable_bindings".to_string(),
+                Value::Object(self.variables.clone()),
+            );
+        }
+        if !self.tags.is_empty() {
+            params.insert("tags".to_string(), serde_json::json!(self.tags));
+        }
+    }
+}
+
+/// Checks variables bound for a run, at its start or while it runs.
+pub fn validate_variables(variables: &Map<String, Value>) -> Result<(), String> {
+    match variables.keys().find(|key| key.trim().is_empty()) {
+        Some(key) => Err(format!("Invalid variable name {:?}", key)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,232 @@
+//! Sidecar channel for image data. Base64 screenshots inside JSON events
+//! double their size and cost CPU on both ends, so the executor sends
+//! images over a socket of their own instead (offered in
+//! `QONTINUI_FRAMES_ADDRESS` and `QONTINUI_FRAMES_TOKEN`, like the main
+//! transport) as binary frames: a JSON header with the frame's `id`,
+//! `format` (`png`, `jpeg`, or `rgb` for raw pixels with `width` and
+//! `height`) and the bytes. Events refer to a frame by its `frame_id`, to
+//! which the runner adds a `frame_url` the frontend loads from the `frame`
+//! URI scheme. The latest frames are kept in memory up to
+//! `MAX_STORED_BYTES`.
+
+use super::transport::{self, FRAME_BINARY};
+use crate::capture::RgbImage;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use tauri::http::{header, Response, StatusCode};
+
+pub const ADDRESS_ENV: &str = "QONTINUI_FRAMES_ADDRESS";
+pub const TOKEN_ENV: &str = "QONTINUI_FRAMES_TOKEN";
+
+/// URI scheme frames are served from.
+pub const SCHEME: &str = "frame";
+
+/// Older frames are dropped once the kept ones take more than this.
+pub const MAX_STORED_BYTES: usize = 256 * 1024 * 1024;
+
+/// Largest frame accepted from the executor.
+const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    Png,
+    Jpeg,
+    /// Raw 8-bit RGB pixels, row by row.
+    Rgb,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FrameHeader {
+    pub id: String,
+    pub format: FrameFormat,
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
+}
+
+pub struct FrameImage {
+    pub content_type: &'static str,
+    pub bytes: Arc<Vec<u8>>,
+}
+
+struct StoredFrame {
+    header: FrameHeader,
+    bytes: Arc<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct Stored {
+    frames: HashMap<String, StoredFrame>,
+    /// Ids, oldest first.
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+/// The frames received from executors, shared through `AppState`.
+#[derive(Clone)]
+pub struct FrameStore {
+    stored: Arc<Mutex<Stored>>,
+    max_bytes: usize,
+}
+
+impl Default for FrameStore {
+    fn default() -> Self {
+        Self::with_limit(MAX_STORED_BYTES)
+    }
+}
+
+impl FrameStore {
+    pub fn with_limit(max_bytes: usize) -> Self {
+        Self {
+            stored: Default::default(),
+            max_bytes,
+        }
+    }
+
+    /// Keeps a frame, dropping the oldest ones past the limit.
+    pub fn insert(&self, header: FrameHeader, bytes: Vec<u8>) -> Result<(), String> {
+        if header.format == FrameFormat::Rgb
+            && bytes.len() != header.width as usize * header.height as usize * 3
+        {
+            return Err(format!(
+                "Frame {} holds {} bytes, not {}x{} RGB pixels",
+                header.id,
+                bytes.len(),
+                header.width,
+                header.height
+            ));
+        }
+        let mut stored = self.stored.lock().unwrap();
+        let id = header.id.clone();
+        if let Some(replaced) = stored.frames.remove(&id) {
+            stored.bytes -= replaced.bytes.len();
+            stored.order.retain(|other| *other != id);
+        }
+        stored.bytes += bytes.len();
+        stored.order.push_back(id.clone());
+        stored.frames.insert(
+            id,
+            StoredFrame {
+                header,
+                bytes: Arc::new(bytes),
+            },
+        );
+        while stored.bytes > self.max_bytes && stored.order.len() > 1 {
+            let Some(oldest) = stored.order.pop_front() else {
+                break;
+            };
+            if let Some(frame) = stored.frames.remove(&oldest) {
+                stored.bytes -= frame.bytes.len();
+            }
+        }
+        Ok(())
+    }
+
+    /// Frame `id` as an image the webview shows; raw pixels are encoded as
+    /// PNG.
+    pub fn image(&self, id: &str) -> Result<Option<FrameImage>, String> {
+        let Some((header, bytes)) = self
+            .stored
+            .lock()
+            .unwrap()
+            .frames
+            .get(id)
+            .map(|frame| (frame.header.clone(), frame.bytes.clone()))
+        else {
+            return Ok(None);
+        };
+        match header.format {
+            FrameFormat::Png => Ok(Some(FrameImage {
+                content_type: "image/png",
+                bytes,
+            })),
+            FrameFormat::Jpeg => Ok(Some(FrameImage {
+                content_type: "image/jpeg",
+                bytes,
+            })),
+            FrameFormat::Rgb => {
+                let image = RgbImage {
+                    width: header.width,
+                    height: header.height,
+                    pixels: bytes
+                        .chunks_exact(3)
+                        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                        .collect(),
+                };
+                Ok(Some(FrameImage {
+                    content_type: "image/png",
+                    bytes: Arc::new(image.encode_png()?),
+                }))
+            }
+        }
+    }
+
+    /// Stores the frames read from an executor's frame channel until it
+    /// closes.
+    pub fn receive(&self, mut reader: impl Read) {
+        loop {
+            match transport::read_frame(&mut reader, MAX_FRAME_BYTES) {
+                Ok(Some((FRAME_BINARY, payload))) => {
+                    let stored = transport::split_binary(&payload).and_then(|(header, bytes)| {
+                        let header: FrameHeader = serde_json::from_slice(header)
+                            .map_err(|e| format!("Invalid frame header: {}", e))?;
+                        self.insert(header, bytes.to_vec())
+                    });
+                    if let Err(e) = stored {
+                        tracing::warn!("Dropped executor frame: {}", e);
+                    }
+                }
+                Ok(Some((kind, _))) => tracing::warn!("Ignored frame of kind {}", kind),
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!("Frame channel failed: {}", e);
+                    break;
+                }
+            }
+        }
+        tracing::debug!("Frame channel closed");
+    }
+}
+
+/// Where the frontend loads frame `id` from.
+pub fn url(id: &str) -> String {
+    // Custom schemes are served over http on Windows and Android
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", SCHEME, id)
+    } else {
+        format!("{}://localhost/{}", SCHEME, id)
+    }
+}
+
+/// Adds the `frame_url` of an event's `frame_id`.
+pub fn link(data: &mut Value) {
+    let Some(id) = data.get("frame_id").and_then(Value::as_str) else {
+        return;
+    };
+    let frame_url = url(id);
+    data["frame_url"] = Value::from(frame_url);
+}
+
+/// Answers a request for `frame://localhost/<id>`.
+pub fn serve(store: &FrameStore, path: &str) -> Response<Vec<u8>> {
+    let id = path.trim_start_matches('/');
+    let response = Response::builder().header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+    let built = match store.image(id) {
+        Ok(Some(image)) => response
+            .header(header::CONTENT_TYPE, image.content_type)
+            .header(header::CACHE_CONTROL, "max-age=31536000, immutable")
+            .body(image.bytes.to_vec()),
+        Err(e) => response
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(e.into_bytes()),
+        Ok(None) => response
+            .status(StatusCode::NOT_FOUND)
+            .body(format!("Frame {} not found", id).into_bytes()),
+    };
+    built.unwrap_or_else(|_| Response::new(Vec::new()))
+}
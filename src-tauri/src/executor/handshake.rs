@@ -0,0 +1,207 @@
+//! Protocol version handshake with the bridge. Each bridge script starts by
+//! sending a `hello` event with the protocol version it speaks and the
+//! qontinui library it loaded. An older protocol the runner still supports
+//! is worked with after a warning; anything else, including a bridge that
+//! sends `ready` without saying hello first, is refused: every command but
+//! `stop` and `ping` fails until the bridge scripts are updated.
+//!
+//! The `hello` also lists the commands the bridge handles, since each
+//! script supports different ones (only `qontinui_executor.py` records).
+//! Commands it doesn't list fail at once instead of being sent.
+
+use super::python_bridge::ExecutorEvent;
+use serde::{Deserialize, Serialize};
+
+/// Protocol version this runner speaks.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest protocol version the runner still works with.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Feature of a bridge that refuses pointer input outside the safe mode
+/// bounds sent with `start`.
+pub const SAFE_MODE: &str = "safe_mode";
+
+/// Commands sent even to a bridge that was refused, to shut it down.
+const ALWAYS_ALLOWED: [&str; 2] = ["stop", "ping"];
+
+/// Data of the bridge's `hello` event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hello {
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub qontinui_version: Option<String>,
+    /// Script that sent it, e.g. `qontinui_executor`.
+    #[serde(default)]
+    pub bridge: Option<String>,
+    #[serde(default)]
+    pub python_version: Option<String>,
+    /// Commands the bridge handles; unknown when it doesn't say.
+    #[serde(default)]
+    pub commands: Option<Vec<String>>,
+    /// What the bridge enforces beyond its commands, e.g. `safe_mode`.
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl Hello {
+    pub fn supports(&self, command: &str) -> bool {
+        self.commands
+            .as_ref()
+            .is_none_or(|commands| commands.iter().any(|c| c == command))
+    }
+}
+
+/// What a command is for, to name in errors about bridges lacking it.
+fn feature(command: &str) -> &str {
+    match command {
+        "start_recording" | "stop_recording" | "recording_status" => "recording",
+        "pause" | "resume" => "pausing",
+        "secret_value" => "secrets",
+        "load_delta" => "configuration deltas",
+        "load_chunk" => "chunked configurations",
+        "set_variables" | "get_variables" => "runtime variables",
+        "active_states" | "state_graph" | "transition_history" => "state machine introspection",
+        "activate_state" | "deactivate_state" | "rebuild_states" => "state correction",
+        "match_image" => "image match testing",
+        "execute_dsl" => "DSL execution",
+        command if command.starts_with("scheduler_") => "the scheduler",
+        command => command,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Handshake {
+    /// Nothing heard from the bridge yet.
+    #[default]
+    Pending,
+    Compatible {
+        hello: Hello,
+    },
+    /// Supported, but older than the runner's protocol.
+    Outdated {
+        hello: Hello,
+        warning: String,
+    },
+    Incompatible {
+        hello: Option<Hello>,
+        reason: String,
+    },
+}
+
+impl Handshake {
+    /// How far the runner works with a bridge that said `hello`.
+    pub fn check(hello: Hello) -> Self {
+        let version = hello.protocol_version;
+        if version == PROTOCOL_VERSION {
+            Handshake::Compatible { hello }
+        } else if (MIN_PROTOCOL_VERSION..PROTOCOL_VERSION).contains(&version) {
+            Handshake::Outdated {
+                warning: format!(
+                    "The executor speaks protocol v{}, older than the runner's v{}; update the bridge scripts",
+                    version, PROTOCOL_VERSION
+                ),
+                hello,
+            }
+        } else if version > PROTOCOL_VERSION {
+            Handshake::Incompatible {
+                reason: format!(
+                    "The executor speaks protocol v{}, newer than the runner's v{}; update the runner",
+                    version, PROTOCOL_VERSION
+                ),
+                hello: Some(hello),
+            }
+        } else {
+            Handshake::Incompatible {
+                reason: format!(
+                    "The executor speaks protocol v{}, the runner needs v{} or later; update the bridge scripts",
+                    version, MIN_PROTOCOL_VERSION
+                ),
+                hello: Some(hello),
+            }
+        }
+    }
+
+    /// The handshake of an executor running inside the runner, which
+    /// always speaks the runner's protocol.
+    pub fn in_process(bridge: &str, commands: Vec<String>) -> Self {
+        Handshake::Compatible {
+            hello: Hello {
+                protocol_version: PROTOCOL_VERSION,
+                qontinui_version: None,
+                bridge: Some(bridge.to_string()),
+                python_version: None,
+                commands: Some(commands),
+                // In-process executors don't move the pointer
+                features: vec![SAFE_MODE.to_string()],
+            },
+        }
+    }
+
+    /// Settles the handshake from the bridge's first `hello` or `ready`
+    /// event. Returns true when it just did.
+    pub fn observe(&mut self, event: &ExecutorEvent) -> bool {
+        if *self != Handshake::Pending {
+            return false;
+        }
+        *self = match event.event.as_str() {
+            "hello" => match serde_json::from_value::<Hello>(event.data.clone()) {
+                Ok(hello) => Handshake::check(hello),
+                Err(e) => Handshake::Incompatible {
+                    hello: None,
+                    reason: format!("The executor sent an invalid hello: {}", e),
+                },
+            },
+            "ready" => Handshake::Incompatible {
+                hello: None,
+                reason: format!(
+                    "The executor didn't say which protocol it speaks; its bridge scripts predate v{}",
+                    PROTOCOL_VERSION
+                ),
+            },
+            _ => return false,
+        };
+        true
+    }
+
+    pub fn hello(&self) -> Option<&Hello> {
+        match self {
+            Handshake::Pending => None,
+            Handshake::Compatible { hello } | Handshake::Outdated { hello, .. } => Some(hello),
+            Handshake::Incompatible { hello, .. } => hello.as_ref(),
+        }
+    }
+
+    /// Fails unless the bridge said it enforces safe mode, since a bounded
+    /// run on one that doesn't would move the pointer anywhere.
+    pub fn allows_safe_mode(&self) -> Result<(), String> {
+        match self.hello() {
+            Some(hello) if hello.features.iter().any(|f| f == SAFE_MODE) => Ok(()),
+            hello => Err(format!(
+                "Safe mode is on, but the executor ({}) doesn't enforce it; update the bridge scripts or turn safe mode off",
+                hello
+                    .and_then(|hello| hello.bridge.as_deref())
+                    .unwrap_or("unknown bridge")
+            )),
+        }
+    }
+
+    /// Fails for commands a refused bridge isn't sent, and for those the
+    /// bridge said it doesn't handle.
+    pub fn allows(&self, command: &str) -> Result<(), String> {
+        if let Handshake::Incompatible { reason, .. } = self {
+            if !ALWAYS_ALLOWED.contains(&command) {
+                return Err(format!("Refusing to send '{}': {}", command, reason));
+            }
+        }
+        match self.hello() {
+            Some(hello) if !hello.supports(command) => Err(format!(
+                "The executor ({}) doesn't support {}",
+                hello.bridge.as_deref().unwrap_or("unknown bridge"),
+                feature(command)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
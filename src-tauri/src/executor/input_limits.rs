@@ -0,0 +1,200 @@
+//! Safety cap on synthetic input. The limits are sent to the executor with
+//! `start`, which paces its own clicks and keystrokes; the runner checks
+//! the `action_started` events independently and stops the run if the
+//! executor ignores them, so a misconfigured loop can't hammer the desktop.
+
+use super::protocol::BridgeMessage;
+use super::python_bridge::ExecutorEvent;
+use super::sessions;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::VecDeque;
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+/// Allowance for float rounding in executor timestamps, in seconds.
+const TIMESTAMP_SLACK: f64 = 0.005;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputLimits {
+    pub enabled: bool,
+    /// Click and drag actions per second. 0 disables the cap.
+    pub max_clicks_per_second: u32,
+    /// Key and type actions per second; the executor also counts each
+    /// typed character against this. 0 disables the cap.
+    pub max_keystrokes_per_second: u32,
+    /// Minimum gap between actions marked `"destructive": true`.
+    pub min_destructive_delay_ms: u64,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_clicks_per_second: 10,
+            max_keystrokes_per_second: 30,
+            min_destructive_delay_ms: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputKind {
+    Click,
+    Keystroke,
+}
+
+impl InputKind {
+    pub fn of(action_type: &str) -> Option<Self> {
+        match action_type {
+            "CLICK" | "DRAG" => Some(Self::Click),
+            "TYPE" | "KEY" => Some(Self::Keystroke),
+            _ => None,
+        }
+    }
+}
+
+/// Checks action start times against `InputLimits` over a sliding
+/// one-second window.
+#[derive(Debug)]
+pub struct InputRateLimiter {
+    limits: InputLimits,
+    clicks: VecDeque<f64>,
+    keystrokes: VecDeque<f64>,
+    last_destructive: Option<f64>,
+}
+
+impl InputRateLimiter {
+    pub fn new(limits: InputLimits) -> Self {
+        Self {
+            limits,
+            clicks: VecDeque::new(),
+            keystrokes: VecDeque::new(),
+            last_destructive: None,
+        }
+    }
+
+    /// Records an action started at `timestamp` (seconds) and describes the
+    /// limit it breaks, if any.
+    pub fn observe(
+        &mut self,
+        action_type: &str,
+        destructive: bool,
+        timestamp: f64,
+    ) -> Result<(), String> {
+        if !self.limits.enabled {
+            return Ok(());
+        }
+
+        if destructive {
+            let min_gap = self.limits.min_destructive_delay_ms as f64 / 1000.0;
+            if let Some(last) = self.last_destructive {
+                let gap = timestamp - last;
+                if gap + TIMESTAMP_SLACK < min_gap {
+                    return Err(format!(
+                        "Destructive {} action {:.0} ms after the previous one (minimum {} ms)",
+                        action_type,
+                        gap * 1000.0,
+                        self.limits.min_destructive_delay_ms
+                    ));
+                }
+            }
+            self.last_destructive = Some(timestamp);
+        }
+
+        let (window, limit, label) = match InputKind::of(action_type) {
+            Some(InputKind::Click) => (
+                &mut self.clicks,
+                self.limits.max_clicks_per_second,
+                "clicks",
+            ),
+            Some(InputKind::Keystroke) => (
+                &mut self.keystrokes,
+                self.limits.max_keystrokes_per_second,
+                "keystrokes",
+            ),
+            None => return Ok(()),
+        };
+        if limit == 0 {
+            return Ok(());
+        }
+
+        while window
+            .front()
+            .is_some_and(|&start| start <= timestamp - 1.0 + TIMESTAMP_SLACK)
+        {
+            window.pop_front();
+        }
+        window.push_back(timestamp);
+        if window.len() > limit as usize {
+            return Err(format!(
+                "{} {} in one second exceeds the limit of {}",
+                window.len(),
+                label,
+                limit
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Feeds executor events to the run's limiter, resetting it when a run
+/// starts. A broken limit stops the execution and is reported as an
+/// `input_rate_exceeded` event.
+pub fn handle_event(
+    app_handle: &AppHandle,
+    session_id: &str,
+    limiter: &mut Option<InputRateLimiter>,
+    event: &ExecutorEvent,
+) {
+    match event.event.as_str() {
+        "execution_started" => {
+            let state = app_handle.state::<crate::commands::AppState>();
+            let limits = state.input_limits.lock().unwrap().clone();
+            *limiter = Some(InputRateLimiter::new(limits));
+        }
+        "execution_completed" => *limiter = None,
+        "action_started" => {
+            let Some(active) = limiter.as_mut() else {
+                return;
+            };
+            let action_type = event
+                .data
+                .get("action_type")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default();
+            let destructive = event
+                .data
+                .get("destructive")
+                .and_then(|d| d.as_bool())
+                .unwrap_or(false);
+            if let Err(reason) = active.observe(action_type, destructive, event.timestamp) {
+                // Only the first violation stops the run
+                *limiter = None;
+                stop_execution(app_handle, session_id, event, reason);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn stop_execution(app_handle: &AppHandle, session_id: &str, event: &ExecutorEvent, reason: String) {
+    warn!("Stopping execution: {}", reason);
+    let notice = BridgeMessage::Event(ExecutorEvent::from_runner(
+        "input_rate_exceeded",
+        json!({ "reason": reason, "action_id": event.data.get("action_id") }),
+    ));
+    let _ = sessions::emit(app_handle, "executor-event", session_id, &notice);
+
+    // Keep the reader thread off the bridge lock
+    let app_handle = app_handle.clone();
+    let session_id = session_id.to_string();
+    std::thread::spawn(move || {
+        let state = app_handle.state::<crate::commands::AppState>();
+        if let Err(e) = sessions::with_bridge(&state, &session_id, |bridge| bridge.stop_execution())
+        {
+            warn!("Failed to stop execution after input limit: {}", e);
+        }
+    });
+}
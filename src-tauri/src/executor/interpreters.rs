@@ -0,0 +1,239 @@
+//! Discovery of the Python interpreters installed on this machine, so the
+//! user can pick the one bridge scripts run with. Each candidate is run
+//! once to learn its version and whether it can import qontinui.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+/// Prints the version, then whether qontinui is importable, without
+/// actually importing it.
+const PROBE_SCRIPT: &str = "import sys, importlib.util\n\
+print('%d.%d.%d' % sys.version_info[:3])\n\
+print(importlib.util.find_spec('qontinui') is not None)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterpreterSource {
+    Path,
+    Pyenv,
+    Poetry,
+    Conda,
+    /// Set up by `provision_python_environment`.
+    Managed,
+    /// A standard install location not on PATH.
+    Common,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Interpreter {
+    pub path: PathBuf,
+    pub source: InterpreterSource,
+    /// `None` when the interpreter failed to run.
+    pub version: Option<String>,
+    pub qontinui_importable: bool,
+}
+
+/// Finds interpreters from every known source and probes them all
+/// concurrently. An interpreter found in several places is listed once,
+/// under the first source.
+pub fn discover() -> Vec<Interpreter> {
+    let candidates = dedupe(candidates());
+    thread::scope(|s| {
+        let handles: Vec<_> = candidates
+            .into_iter()
+            .map(|(path, source)| s.spawn(move || probe(path, source)))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+/// Runs `path` to fill in its version and qontinui availability.
+pub fn probe(path: PathBuf, source: InterpreterSource) -> Interpreter {
+    let parsed = Command::new(&path)
+        .arg("-c")
+        .arg(PROBE_SCRIPT)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_probe(&String::from_utf8_lossy(&output.stdout)));
+    let (version, qontinui_importable) = match parsed {
+        Some((version, importable)) => (Some(version), importable),
+        None => (None, false),
+    };
+    Interpreter {
+        path,
+        source,
+        version,
+        qontinui_importable,
+    }
+}
+
+/// Reads the output of `PROBE_SCRIPT`.
+pub fn parse_probe(output: &str) -> Option<(String, bool)> {
+    let mut lines = output.lines().map(str::trim);
+    let version = lines.next().filter(|v| !v.is_empty())?.to_string();
+    let importable = match lines.next()? {
+        "True" => true,
+        "False" => false,
+        _ => return None,
+    };
+    Some((version, importable))
+}
+
+/// Drops candidates that are the same file, e.g. `/usr/bin/python3` found
+/// through PATH and again as a common location.
+pub fn dedupe(candidates: Vec<(PathBuf, InterpreterSource)>) -> Vec<(PathBuf, InterpreterSource)> {
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|(path, _)| seen.insert(std::fs::canonicalize(path).unwrap_or(path.clone())))
+        .collect()
+}
+
+/// The interpreter inside a venv or conda environment.
+pub fn env_python(env_dir: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        let scripts = env_dir.join("Scripts").join("python.exe");
+        if scripts.exists() {
+            scripts
+        } else {
+            // Conda keeps it at the top of the environment
+            env_dir.join("python.exe")
+        }
+    } else {
+        env_dir.join("bin").join("python")
+    }
+}
+
+fn candidates() -> Vec<(PathBuf, InterpreterSource)> {
+    let mut found = Vec::new();
+    let mut add = |paths: Vec<PathBuf>, source| {
+        found.extend(
+            paths
+                .into_iter()
+                .filter(|p| p.is_file())
+                .map(|p| (p, source)),
+        )
+    };
+
+    add(on_path(), InterpreterSource::Path);
+    add(pyenv(), InterpreterSource::Pyenv);
+    add(poetry(), InterpreterSource::Poetry);
+    add(conda(), InterpreterSource::Conda);
+    add(
+        super::provision::managed_python().into_iter().collect(),
+        InterpreterSource::Managed,
+    );
+    add(common(), InterpreterSource::Common);
+    found
+}
+
+fn on_path() -> Vec<PathBuf> {
+    let names: &[&str] = if cfg!(target_os = "windows") {
+        &["python.exe", "python3.exe"]
+    } else {
+        &["python3", "python"]
+    };
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .collect()
+}
+
+fn pyenv() -> Vec<PathBuf> {
+    let root = std::env::var_os("PYENV_ROOT")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".pyenv")));
+    let Some(root) = root else {
+        return Vec::new();
+    };
+    let versions = if cfg!(target_os = "windows") {
+        root.join("pyenv-win").join("versions")
+    } else {
+        root.join("versions")
+    };
+    subdirs(&versions)
+        .into_iter()
+        .map(|dir| {
+            if cfg!(target_os = "windows") {
+                dir.join("python.exe")
+            } else {
+                dir.join("bin").join("python")
+            }
+        })
+        .collect()
+}
+
+fn poetry() -> Vec<PathBuf> {
+    let Some(cache) = dirs::cache_dir() else {
+        return Vec::new();
+    };
+    let envs = if cfg!(target_os = "windows") {
+        cache.join("pypoetry").join("Cache").join("virtualenvs")
+    } else {
+        cache.join("pypoetry").join("virtualenvs")
+    };
+    subdirs(&envs).iter().map(|dir| env_python(dir)).collect()
+}
+
+fn conda() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = std::env::var_os("CONDA_PREFIX")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    if let Some(home) = dirs::home_dir() {
+        for name in ["miniconda3", "anaconda3", "miniforge3"] {
+            roots.push(home.join(name));
+        }
+    }
+
+    let mut pythons = Vec::new();
+    for root in roots {
+        pythons.push(env_python(&root));
+        pythons.extend(
+            subdirs(&root.join("envs"))
+                .iter()
+                .map(|dir| env_python(dir)),
+        );
+    }
+    pythons
+}
+
+fn common() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        let Some(local) = dirs::data_local_dir() else {
+            return Vec::new();
+        };
+        subdirs(&local.join("Programs").join("Python"))
+            .into_iter()
+            .map(|dir| dir.join("python.exe"))
+            .collect()
+    } else {
+        [
+            "/usr/bin/python3",
+            "/usr/local/bin/python3",
+            "/opt/homebrew/bin/python3",
+            "/Library/Frameworks/Python.framework/Versions/Current/bin/python3",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+}
+
+/// Subdirectories of `dir`, sorted so the listing is stable.
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
@@ -0,0 +1,266 @@
+//! Performance figures of a running executor, for telling whether slowness
+//! comes from the runner or from the automation: commands sent and how long
+//! their responses took, the rate of events coming back, and the CPU and
+//! memory the Python process uses.
+//!
+//! Events are also checked against the executor's numbering, whichever
+//! backend runs it: a sequence number that skips ahead means events were
+//! lost on the way, e.g. to a truncated stdout line, and is counted as a
+//! gap, and one already seen or lower is counted as out of order. Each run
+//! is numbered afresh from its `execution_started`. Sequence 0 is left out,
+//! as the runner's own events and whatever an executor prints before it
+//! starts numbering carry it.
+
+use super::protocol::{BridgeMessage, Frame};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Events per second are averaged over this many recent seconds.
+const RATE_WINDOW_SECS: u64 = 10;
+
+/// Commands unanswered for longer than this stop counting as in flight.
+const IN_FLIGHT_EXPIRY: Duration = Duration::from_secs(300);
+
+#[derive(Default)]
+struct Stats {
+    started: Option<Instant>,
+    commands_sent: u64,
+    responses_received: u64,
+    /// When each unanswered command was sent, by id.
+    in_flight: HashMap<String, Instant>,
+    round_trips: u64,
+    round_trip_total: Duration,
+    round_trip_max: Duration,
+    events_received: u64,
+    /// Events per second since start, for the recent seconds only.
+    recent_events: VecDeque<(u64, u64)>,
+    /// Sequence number the next event should carry.
+    expected_sequence: Option<u32>,
+    sequence_gaps: u64,
+    events_lost: u64,
+    events_lost_in_run: u64,
+    events_out_of_order: u64,
+}
+
+/// Events missing between two the executor numbered.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SequenceGap {
+    pub expected: u32,
+    pub received: u32,
+    pub dropped: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    /// Percent of one core; above 100 when using several.
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeMetrics {
+    pub uptime_ms: Option<u64>,
+    pub commands_sent: u64,
+    pub responses_received: u64,
+    pub in_flight: usize,
+    pub average_round_trip_ms: Option<f64>,
+    pub max_round_trip_ms: Option<f64>,
+    pub events_received: u64,
+    /// Over the last `RATE_WINDOW_SECS` seconds.
+    pub events_per_second: f64,
+    /// Times the executor's numbering skipped ahead.
+    pub sequence_gaps: u64,
+    /// Events skipped over, since start and since the current run started.
+    pub events_lost: u64,
+    pub events_lost_in_run: u64,
+    /// Events numbered like one already seen, or lower.
+    pub events_out_of_order: u64,
+    pub process: Option<ProcessUsage>,
+}
+
+/// Counts a bridge's traffic; shared with its reader threads.
+#[derive(Default)]
+pub struct BridgeStats {
+    stats: Mutex<Stats>,
+    /// Kept between samples, since CPU usage is measured from the previous
+    /// one.
+    system: Mutex<Option<System>>,
+}
+
+impl BridgeStats {
+    /// Starts counting afresh for a newly started executor.
+    pub fn start(&self) {
+        *self.stats.lock().unwrap() = Stats {
+            started: Some(Instant::now()),
+            ..Default::default()
+        };
+        *self.system.lock().unwrap() = None;
+    }
+
+    pub fn command_sent(&self, id: &str) {
+        let mut stats = self.stats.lock().unwrap();
+        let now = Instant::now();
+        stats.commands_sent += 1;
+        stats
+            .in_flight
+            .retain(|_, sent| now.duration_since(*sent) < IN_FLIGHT_EXPIRY);
+        stats.in_flight.insert(id.to_string(), now);
+    }
+
+    pub fn response_received(&self, id: &str) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.responses_received += 1;
+        if let Some(sent) = stats.in_flight.remove(id) {
+            let round_trip = sent.elapsed();
+            stats.round_trips += 1;
+            stats.round_trip_total += round_trip;
+            stats.round_trip_max = stats.round_trip_max.max(round_trip);
+        }
+    }
+
+    pub fn event_received(&self) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.events_received += 1;
+        let Some(second) = stats.started.map(|started| started.elapsed().as_secs()) else {
+            return;
+        };
+        match stats.recent_events.back_mut() {
+            Some((last, count)) if *last == second => *count += 1,
+            _ => stats.recent_events.push_back((second, 1)),
+        }
+        while stats
+            .recent_events
+            .front()
+            .is_some_and(|(first, _)| first + RATE_WINDOW_SECS <= second)
+        {
+            stats.recent_events.pop_front();
+        }
+    }
+
+    /// Counts a message from the executor, returning the events missing
+    /// before it if it's an event that skipped ahead.
+    pub fn frame_received(&self, frame: &Frame) -> Option<SequenceGap> {
+        match frame {
+            Frame::Message(BridgeMessage::Event(event)) => {
+                self.event_received();
+                self.sequence_received(&event.event, event.sequence)
+            }
+            Frame::Message(BridgeMessage::Response(reply)) => {
+                self.response_received(&reply.id);
+                None
+            }
+            Frame::Invalid(_) => None,
+        }
+    }
+
+    /// Checks an event's sequence number against the ones before, and
+    /// reports the events missing if it skipped ahead. `execution_started`
+    /// starts the numbering of a run, whatever it carries.
+    pub fn sequence_received(&self, event: &str, sequence: u32) -> Option<SequenceGap> {
+        let mut stats = self.stats.lock().unwrap();
+        let run_started = event == "execution_started";
+        if run_started {
+            stats.events_lost_in_run = 0;
+        }
+        if sequence == 0 {
+            return None;
+        }
+        let expected = match stats.expected_sequence {
+            Some(expected) if !run_started => expected,
+            _ => {
+                stats.expected_sequence = Some(sequence.wrapping_add(1));
+                return None;
+            }
+        };
+        if sequence < expected {
+            stats.events_out_of_order += 1;
+            return None;
+        }
+        stats.expected_sequence = Some(sequence.wrapping_add(1));
+        if sequence == expected {
+            return None;
+        }
+        let dropped = sequence - expected;
+        stats.sequence_gaps += 1;
+        stats.events_lost += dropped as u64;
+        stats.events_lost_in_run += dropped as u64;
+        Some(SequenceGap {
+            expected,
+            received: sequence,
+            dropped,
+        })
+    }
+
+    /// The figures so far, with the usage of the executor process `pid`.
+    pub fn snapshot(&self, pid: Option<u32>) -> BridgeMetrics {
+        let process = pid.and_then(|pid| self.process_usage(pid));
+        let stats = self.stats.lock().unwrap();
+        let uptime = stats.started.map(|started| started.elapsed());
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        BridgeMetrics {
+            uptime_ms: uptime.map(|uptime| uptime.as_millis() as u64),
+            commands_sent: stats.commands_sent,
+            responses_received: stats.responses_received,
+            in_flight: stats.in_flight.len(),
+            average_round_trip_ms: (stats.round_trips > 0)
+                .then(|| millis(stats.round_trip_total) / stats.round_trips as f64),
+            max_round_trip_ms: (stats.round_trips > 0).then(|| millis(stats.round_trip_max)),
+            events_received: stats.events_received,
+            events_per_second: uptime.map_or(0.0, |uptime| {
+                events_per_second(&stats.recent_events, uptime)
+            }),
+            sequence_gaps: stats.sequence_gaps,
+            events_lost: stats.events_lost,
+            events_lost_in_run: stats.events_lost_in_run,
+            events_out_of_order: stats.events_out_of_order,
+            process,
+        }
+    }
+
+    /// CPU and memory of process `pid`. The first call takes two samples,
+    /// a moment apart.
+    pub fn process_usage(&self, pid: u32) -> Option<ProcessUsage> {
+        let pid = Pid::from_u32(pid);
+        let refresh = |system: &mut System| {
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[pid]),
+                true,
+                ProcessRefreshKind::nothing().with_memory().with_cpu(),
+            );
+        };
+        let mut system = self.system.lock().unwrap();
+        let system = match system.as_mut() {
+            Some(system) => system,
+            None => {
+                // CPU usage needs two samples
+                let system = system.insert(System::new());
+                refresh(system);
+                std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+                system
+            }
+        };
+        refresh(system);
+        system.process(pid).map(|process| ProcessUsage {
+            pid: pid.as_u32(),
+            cpu_percent: process.cpu_usage(),
+            memory_bytes: process.memory(),
+        })
+    }
+}
+
+/// Rate over the recent `(second, count)` buckets, counting the current
+/// second as a whole one.
+pub fn events_per_second(buckets: &VecDeque<(u64, u64)>, uptime: Duration) -> f64 {
+    let now = uptime.as_secs();
+    let events: u64 = buckets
+        .iter()
+        .filter(|(second, _)| second + RATE_WINDOW_SECS > now)
+        .map(|(_, count)| count)
+        .sum();
+    let window = (now + 1).min(RATE_WINDOW_SECS);
+    events as f64 / window as f64
+}
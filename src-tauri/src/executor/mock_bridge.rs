@@ -0,0 +1,475 @@
+//! The mock executor, in-process. It answers the same commands with the same
+//! events as the Python bridge in mock mode — configurations sent inline,
+//! compressed or in chunks are reassembled, checked and counted, and a
+//! started workflow's actions are reported one by one — so commands can be
+//! tested, and the UI tried, without Python installed.
+
+use super::backend::{ExecutorBackend, Outbox, Subscriber};
+use super::shutdown::ShutdownOutcome;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable pointing at a script whose replies replace the mock
+/// executor's own for the commands it lists.
+pub const MOCK_SCRIPT_ENV: &str = "QONTINUI_MOCK_SCRIPT";
+
+/// Commands the mock executor handles itself.
+pub const COMMANDS: &[&str] = &[
+    "load",
+    "load_chunk",
+    "load_delta",
+    "ping",
+    "start",
+    "status",
+    "stop",
+];
+
+/// Time between the events of a simulated execution.
+const DEFAULT_PACE: Duration = Duration::from_millis(200);
+
+/// One scripted message, sent `delay_ms` after the previous step. Responses
+/// get the command id filled in; events get a timestamp and sequence number.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptStep {
+    #[serde(default)]
+    pub delay_ms: u64,
+    #[serde(flatten)]
+    pub message: Map<String, Value>,
+}
+
+/// Replies and events played back instead of the mock executor's own, keyed
+/// by command name.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MockScript {
+    #[serde(default)]
+    pub on_start: Vec<ScriptStep>,
+    #[serde(default)]
+    pub commands: HashMap<String, Vec<ScriptStep>>,
+}
+
+impl MockScript {
+    /// Loads the script named by `QONTINUI_MOCK_SCRIPT`, if it's set.
+    pub fn load() -> Result<Option<Self>, String> {
+        let Ok(path) = std::env::var(MOCK_SCRIPT_ENV) else {
+            return Ok(None);
+        };
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read mock script {}: {}", path, e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Invalid mock script {}: {}", path, e))
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    config: Option<Value>,
+    /// Chunks of configurations still being sent, by transfer id.
+    transfers: HashMap<String, Vec<Option<String>>>,
+}
+
+/// In-process mock executor, selected with executor type `"test"` or
+/// `"minimal"`.
+pub struct MockBridge {
+    outbox: Outbox,
+    state: Arc<Mutex<MockState>>,
+    running: Arc<AtomicBool>,
+    script: MockScript,
+    pace: Duration,
+    started: bool,
+}
+
+impl Default for MockBridge {
+    fn default() -> Self {
+        Self::new(MockScript::default())
+    }
+}
+
+impl MockBridge {
+    pub fn new(script: MockScript) -> Self {
+        Self {
+            outbox: Outbox::default(),
+            state: Arc::default(),
+            running: Arc::default(),
+            script,
+            pace: DEFAULT_PACE,
+            started: false,
+        }
+    }
+
+    /// Changes the time between the events of a simulated execution.
+    #[allow(dead_code)]
+    pub fn with_pace(mut self, pace: Duration) -> Self {
+        self.pace = pace;
+        self
+    }
+
+    fn handle(&self, command: &str, params: &Value) -> Result<Option<Value>, String> {
+        match command {
+            "load" => {
+                let content = self.resolve(params)?;
+                let config: Value = serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid configuration: {}", e))?;
+                self.loaded(config);
+                Ok(None)
+            }
+            "load_chunk" => self.add_chunk(params).map(Some),
+            "load_delta" => {
+                let mut config = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .config
+                    .clone()
+                    .ok_or("No configuration loaded to apply changes to")?;
+                apply_delta(&mut config, params);
+                self.loaded(config);
+                Ok(None)
+            }
+            "start" => self.start_execution(params).map(|()| None),
+            "stop" => {
+                self.running.store(false, Ordering::Relaxed);
+                self.outbox.event(
+                    "execution_completed",
+                    json!({ "success": false, "reason": "User stopped" }),
+                );
+                Ok(None)
+            }
+            "ping" => Ok(Some(json!({ "time": now() }))),
+            "status" => Ok(Some(json!({
+                "is_running": self.running.load(Ordering::Relaxed),
+                "config_loaded": self.state.lock().unwrap().config.is_some(),
+                "bridge_type": "mock",
+            }))),
+            other => Err(format!("Unknown command: {}", other)),
+        }
+    }
+
+    /// The configuration text a `load` describes: inline, or reassembled
+    /// from its chunks, then decoded and checked against its checksum.
+    fn resolve(&self, params: &Value) -> Result<String, String> {
+        let text = |key: &str| params.get(key).and_then(Value::as_str);
+        let payload = match text("transfer_id") {
+            Some(transfer_id) => {
+                let chunks = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .transfers
+                    .remove(transfer_id)
+                    .ok_or_else(|| format!("Unknown transfer: {}", transfer_id))?;
+                let missing: Vec<usize> = (0..chunks.len())
+                    .filter(|&index| chunks[index].is_none())
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(format!(
+                        "Transfer {} is missing chunks {:?}",
+                        transfer_id, missing
+                    ));
+                }
+                chunks.into_iter().flatten().collect()
+            }
+            None => match (text("config_data"), text("config_path")) {
+                (Some(data), _) => data.to_string(),
+                (None, Some(path)) => {
+                    return std::fs::read_to_string(path)
+                        .map_err(|e| format!("Failed to read {}: {}", path, e));
+                }
+                (None, None) => return Err("No configuration provided".to_string()),
+            },
+        };
+
+        let content = match text("encoding").unwrap_or("json") {
+            "json" => payload,
+            "gzip+base64" => {
+                let compressed = STANDARD
+                    .decode(payload)
+                    .map_err(|e| format!("Invalid configuration encoding: {}", e))?;
+                let mut content = String::new();
+                GzDecoder::new(compressed.as_slice())
+                    .read_to_string(&mut content)
+                    .map_err(|e| format!("Invalid configuration encoding: {}", e))?;
+                content
+            }
+            other => return Err(format!("Unsupported config encoding: {}", other)),
+        };
+
+        if let Some(expected) = text("sha256") {
+            let actual: String = Sha256::digest(content.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err("Configuration checksum mismatch".to_string());
+            }
+        }
+        Ok(content)
+    }
+
+    fn add_chunk(&self, params: &Value) -> Result<Value, String> {
+        let transfer_id = params.get("transfer_id").and_then(Value::as_str);
+        let index = params.get("index").and_then(Value::as_u64);
+        let total = params.get("total").and_then(Value::as_u64);
+        let data = params.get("data").and_then(Value::as_str);
+        let (Some(transfer_id), Some(index), Some(total), Some(data)) =
+            (transfer_id, index, total, data)
+        else {
+            return Err("Invalid load_chunk parameters".to_string());
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let chunks = state
+            .transfers
+            .entry(transfer_id.to_string())
+            .or_insert_with(|| vec![None; total as usize]);
+        if chunks.len() as u64 != total || index >= total {
+            return Err(format!("Chunk {} out of range for {}", index, transfer_id));
+        }
+        chunks[index as usize] = Some(data.to_string());
+        let received = chunks.iter().filter(|chunk| chunk.is_some()).count();
+        Ok(json!({ "received": received }))
+    }
+
+    fn loaded(&self, config: Value) {
+        let count = |section: &str| {
+            config
+                .get(section)
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len)
+        };
+        let info = json!({
+            "version": config.get("version").and_then(Value::as_str).unwrap_or("unknown"),
+            "name": config.pointer("/metadata/name").and_then(Value::as_str).unwrap_or("Unnamed"),
+            "states": count("states"),
+            "workflows": count("workflows"),
+            "transitions": count("transitions"),
+            "images": count("images"),
+        });
+        self.outbox.event(
+            "log",
+            json!({ "level": "info", "message": "Configuration loaded (mock executor)" }),
+        );
+        self.outbox.event("config_loaded", info);
+        self.state.lock().unwrap().config = Some(config);
+    }
+
+    /// Reports the actions of the workflow to run as done, one per pace;
+    /// without a workflow, a few made-up steps.
+    fn start_execution(&self, params: &Value) -> Result<(), String> {
+        let config = self
+            .state
+            .lock()
+            .unwrap()
+            .config
+            .clone()
+            .ok_or("No configuration loaded")?;
+        if self.running.swap(true, Ordering::Relaxed) {
+            return Err("Execution already in progress".to_string());
+        }
+        let workflow_id = params
+            .get("workflow_id")
+            .or_else(|| params.get("process_id"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let actions: Vec<(String, String)> = match workflow_actions(&config, workflow_id.as_deref())
+        {
+            Some(actions) => actions,
+            None => (0..3)
+                .map(|i| (format!("action_{}", i), "CLICK".to_string()))
+                .collect(),
+        };
+        self.outbox.event(
+            "execution_started",
+            json!({
+                "workflow_id": workflow_id,
+                "mode": params.get("mode").and_then(Value::as_str).unwrap_or("state_machine"),
+                "tags": params.get("tags").cloned().unwrap_or(json!([])),
+            }),
+        );
+
+        let (outbox, running, pace) = (self.outbox.clone(), self.running.clone(), self.pace);
+        thread::spawn(move || {
+            for (action_id, action_type) in actions {
+                thread::sleep(pace);
+                if !running.load(Ordering::Relaxed) {
+                    return;
+                }
+                outbox.event(
+                    "action_started",
+                    json!({ "action_id": action_id, "action_type": action_type }),
+                );
+                thread::sleep(pace);
+                outbox.event(
+                    "action_completed",
+                    json!({ "action_id": action_id, "success": true }),
+                );
+            }
+            if running.swap(false, Ordering::Relaxed) {
+                outbox.event(
+                    "execution_completed",
+                    json!({ "success": true, "workflow_id": workflow_id }),
+                );
+            }
+        });
+        Ok(())
+    }
+
+    /// Plays back scripted steps in order, each after its delay.
+    fn replay(&self, steps: &[ScriptStep], id: &str) {
+        let (outbox, steps, id) = (self.outbox.clone(), steps.to_vec(), id.to_string());
+        thread::spawn(move || {
+            for step in steps {
+                thread::sleep(Duration::from_millis(step.delay_ms));
+                outbox.deliver(step.message, &id);
+            }
+        });
+    }
+}
+
+impl ExecutorBackend for MockBridge {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn commands(&self) -> Vec<String> {
+        let mut commands: Vec<String> = COMMANDS
+            .iter()
+            .map(|command| command.to_string())
+            .chain(self.script.commands.keys().cloned())
+            .collect();
+        commands.sort();
+        commands.dedup();
+        commands
+    }
+
+    fn subscribe(&mut self, subscriber: Subscriber) {
+        self.outbox.subscribe(subscriber);
+    }
+
+    fn start(&mut self, _executor_type: &str) -> Result<(), String> {
+        self.started = true;
+        self.outbox.event(
+            "hello",
+            json!({
+                "protocol_version": super::handshake::PROTOCOL_VERSION,
+                "bridge": "mock",
+                "commands": self.commands(),
+                // Nothing is clicked for real
+                "features": [super::handshake::SAFE_MODE],
+            }),
+        );
+        self.outbox.event(
+            "ready",
+            json!({ "message": "Mock executor initialized (no Python)" }),
+        );
+        self.replay(&self.script.on_start, "");
+        Ok(())
+    }
+
+    fn send(&mut self, id: &str, command: &str, params: Option<Value>) -> Result<(), String> {
+        if let Some(steps) = self.script.commands.get(command) {
+            if command == "stop" {
+                self.running.store(false, Ordering::Relaxed);
+            }
+            self.replay(steps, id);
+            return Ok(());
+        }
+        let reply = self.handle(command, &params.unwrap_or(Value::Null));
+        self.outbox.respond(id, reply);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<ShutdownOutcome, String> {
+        self.running.store(false, Ordering::Relaxed);
+        self.outbox.close();
+        let outcome = if self.started {
+            ShutdownOutcome::Graceful
+        } else {
+            ShutdownOutcome::NotRunning
+        };
+        self.started = false;
+        Ok(outcome)
+    }
+
+    fn is_running(&self) -> bool {
+        self.started
+    }
+}
+
+/// Ids and types of the actions of `workflow_id`, if the configuration has
+/// that workflow.
+fn workflow_actions(config: &Value, workflow_id: Option<&str>) -> Option<Vec<(String, String)>> {
+    let workflow = config
+        .get("workflows")?
+        .as_array()?
+        .iter()
+        .find(|workflow| workflow.get("id").and_then(Value::as_str) == workflow_id)?;
+    let text = |action: &Value, key: &str| {
+        action
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    Some(
+        workflow
+            .get("actions")
+            .and_then(Value::as_array)
+            .map(|actions| {
+                actions
+                    .iter()
+                    .map(|action| (text(action, "id"), text(action, "type")))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+/// Applies a `load_delta` change set: per section, entries matched by id
+/// are replaced or removed, and new ones appended.
+pub fn apply_delta(config: &mut Value, delta: &Value) {
+    let (Some(config), Some(delta)) = (config.as_object_mut(), delta.as_object()) else {
+        return;
+    };
+    for (section, changes) in delta {
+        let list = |key: &str| {
+            changes
+                .get(key)
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let removed = list("removed");
+        let modified = list("modified");
+        let id = |entry: &Value| entry.get("id").cloned();
+
+        let entries = config.entry(section.clone()).or_insert_with(|| json!([]));
+        let Some(entries) = entries.as_array_mut() else {
+            continue;
+        };
+        entries.retain(|entry| !id(entry).is_some_and(|id| removed.contains(&id)));
+        for entry in entries.iter_mut() {
+            if let Some(replacement) = modified.iter().find(|m| id(m) == id(entry)) {
+                *entry = replacement.clone();
+            }
+        }
+        entries.extend(list("added"));
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
@@ -1,4 +1,37 @@
+pub mod artifacts;
+pub mod backend;
+pub mod backpressure;
+pub mod conformance;
+pub mod environment;
+pub mod event_bus;
 pub mod event_handler;
+pub mod execution_request;
+pub mod frames;
+pub mod handshake;
+pub mod input_limits;
+pub mod interpreters;
+pub mod metrics;
+pub mod mock_bridge;
+pub mod pipeline;
+pub mod process_tree;
+pub mod protocol;
+pub mod provision;
 pub mod python_bridge;
+pub mod remote_bridge;
+pub mod replay;
+pub mod restart;
+pub mod run_report;
+pub mod run_stream;
+pub mod safe_mode;
+pub mod screenshot_executor;
+pub mod selftest;
+pub mod sessions;
+pub mod shutdown;
+pub mod state_machine;
+pub mod supervisor;
+pub mod throttle;
+pub mod timeline;
+pub mod transport;
+pub mod watchdog;
 
-pub use python_bridge::PythonBridge;
+pub use python_bridge::{ConfigTransferMode, PythonBridge};
@@ -0,0 +1,155 @@
+use super::backend::Subscriber;
+use super::backpressure::Counters;
+use super::protocol::{BridgeMessage, Frame};
+use super::python_bridge::PendingResponses;
+use super::sessions::{self, DEFAULT_SESSION};
+use super::throttle::{EventClass, EventThrottle, ThrottleConfig};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+/// Forwards decoded bridge messages to the frontend, applying event
+/// throttling. Shared by every source of executor messages.
+pub struct EventPipeline {
+    app_handle: AppHandle,
+    session_id: String,
+    throttle: EventThrottle,
+    throttle_config: Arc<Mutex<ThrottleConfig>>,
+    responses: Option<PendingResponses>,
+    counters: Option<Arc<Counters>>,
+    subscriber: Option<Arc<Mutex<Option<Subscriber>>>>,
+}
+
+impl EventPipeline {
+    pub fn new(app_handle: AppHandle, throttle_config: Arc<Mutex<ThrottleConfig>>) -> Self {
+        let throttle = EventThrottle::new(throttle_config.lock().unwrap().clone());
+        Self {
+            app_handle,
+            session_id: DEFAULT_SESSION.to_string(),
+            throttle,
+            throttle_config,
+            responses: None,
+            counters: None,
+            subscriber: None,
+        }
+    }
+
+    /// Tags everything forwarded with `session_id` instead of the default
+    /// session's.
+    pub fn for_session(mut self, session_id: &str) -> Self {
+        self.session_id = session_id.to_string();
+        self
+    }
+
+    /// Also hands responses to commands sent with
+    /// `PythonBridge::send_command_awaiting` to their waiters.
+    pub fn with_responses(mut self, responses: PendingResponses) -> Self {
+        self.responses = Some(responses);
+        self
+    }
+
+    /// Counts the events the throttle holds back.
+    pub fn with_counters(mut self, counters: Arc<Counters>) -> Self {
+        self.counters = Some(counters);
+        self
+    }
+
+    /// Also hands every frame to whoever subscribed to the bridge.
+    pub fn with_subscriber(mut self, subscriber: Arc<Mutex<Option<Subscriber>>>) -> Self {
+        self.subscriber = Some(subscriber);
+        self
+    }
+
+    pub fn handle(&mut self, frame: Frame) {
+        if let Some(subscriber) = &self.subscriber {
+            if let Some(subscriber) = subscriber.lock().unwrap().as_mut() {
+                subscriber(frame.clone());
+            }
+        }
+        if let Frame::Message(ref message) = frame {
+            let state = self.app_handle.state::<crate::commands::AppState>();
+            let run_id = state.run_streams.current_run(&self.session_id);
+            state.run_streams.record_message(&self.session_id, message);
+            if let (BridgeMessage::Event(event), Some(run_id)) = (message, run_id) {
+                state.event_timeline.record(&run_id, event);
+            }
+        }
+        match frame {
+            Frame::Message(BridgeMessage::Event(event)) => {
+                // The full stream always goes to the log file; only what
+                // reaches the frontend is throttled
+                tracing::info!(
+                    "Executor event #{} {}: {}",
+                    event.sequence,
+                    event.event,
+                    event.data
+                );
+
+                self.throttle
+                    .set_config(self.throttle_config.lock().unwrap().clone());
+                let class = EventClass::of(&event);
+                let decision = self.throttle.admit(class, Instant::now());
+
+                if decision.suppressed_in_last_window > 0 {
+                    let _ = sessions::emit(
+                        &self.app_handle,
+                        "events-throttled",
+                        &self.session_id,
+                        &json!({
+                            "class": class,
+                            "suppressed": decision.suppressed_in_last_window,
+                        }),
+                    );
+                }
+
+                if let (false, Some(counters)) = (decision.emit, &self.counters) {
+                    counters.count_throttled();
+                }
+                if decision.emit {
+                    // Emit event to frontend
+                    let message = BridgeMessage::Event(event);
+                    if let Err(e) = sessions::emit(
+                        &self.app_handle,
+                        "executor-event",
+                        &self.session_id,
+                        &message,
+                    ) {
+                        tracing::warn!("Failed to emit event: {}", e);
+                    }
+                }
+            }
+            Frame::Message(response @ BridgeMessage::Response(_)) => {
+                if let (Some(responses), BridgeMessage::Response(reply)) =
+                    (&self.responses, &response)
+                {
+                    responses.resolve(reply);
+                }
+                // Emit response to frontend
+                if let Err(e) = sessions::emit(
+                    &self.app_handle,
+                    "executor-response",
+                    &self.session_id,
+                    &response,
+                ) {
+                    tracing::warn!("Failed to emit response: {}", e);
+                }
+            }
+            Frame::Invalid(error) => {
+                // Anything on stdout that isn't protocol is a bridge bug
+                // (e.g. a stray print), so make it visible
+                tracing::warn!(
+                    "Unparseable line from executor ({}): {}",
+                    error.error,
+                    error.excerpt
+                );
+                let _ = sessions::emit(
+                    &self.app_handle,
+                    "executor-protocol-error",
+                    &self.session_id,
+                    &error,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! The processes the executor started. qontinui can spawn helpers of its
+//! own, and `poetry run` puts one more process in between, so killing the
+//! executor's direct child alone would leave them running.
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tracing::warn;
+
+/// Descendants of `root` among `(pid, parent)` pairs, children before
+/// their own children.
+pub fn descendants_of(processes: &[(u32, Option<u32>)], root: u32) -> Vec<u32> {
+    let mut found = vec![root];
+    let mut next = 0;
+    while next < found.len() {
+        let parent = found[next];
+        found.extend(
+            processes
+                .iter()
+                .filter(|(pid, ppid)| *ppid == Some(parent) && !found.contains(pid))
+                .map(|(pid, _)| *pid)
+                .collect::<Vec<_>>(),
+        );
+        next += 1;
+    }
+    found.remove(0);
+    found
+}
+
+/// A process found below the executor. Its start time tells it apart from
+/// a process that's given the same id once it has exited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeProcess {
+    pub pid: u32,
+    pub start_time: u64,
+}
+
+/// The processes below `root` right now, children before their own
+/// children.
+pub fn descendants(root: u32) -> Vec<TreeProcess> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+    let processes: Vec<(u32, Option<u32>)> = system
+        .processes()
+        .iter()
+        .map(|(pid, process)| (pid.as_u32(), process.parent().map(Pid::as_u32)))
+        .collect();
+    descendants_of(&processes, root)
+        .into_iter()
+        .filter_map(|pid| {
+            let process = system.process(Pid::from_u32(pid))?;
+            Some(TreeProcess {
+                pid,
+                start_time: process.start_time(),
+            })
+        })
+        .collect()
+}
+
+/// Kills every process below `root`, but not `root` itself. They're all
+/// looked up before the first is killed, so none gets reparented and
+/// missed. Returns how many were killed.
+pub fn kill_descendants(root: u32) -> usize {
+    kill_all(&descendants(root))
+}
+
+/// Kills those of `processes` that are still running, as `descendants`
+/// lists them. An id now held by a process that started at another time
+/// was reused, and that process is left alone. Returns how many were
+/// killed.
+pub fn kill_all(processes: &[TreeProcess]) -> usize {
+    let pids: Vec<Pid> = processes
+        .iter()
+        .map(|process| Pid::from_u32(process.pid))
+        .collect();
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&pids),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+
+    let mut killed = 0;
+    // Deepest first, so nothing can spawn a replacement
+    for tracked in processes.iter().rev() {
+        let pid = Pid::from_u32(tracked.pid);
+        match system.process(pid) {
+            Some(process) if process.start_time() != tracked.start_time => {}
+            Some(process) if process.kill() => killed += 1,
+            Some(_) => warn!("Failed to kill executor process {}", pid),
+            None => {}
+        }
+    }
+    killed
+}
@@ -0,0 +1,219 @@
+use super::python_bridge::{ExecutorEvent, ExecutorResponse};
+use super::transport::{self, FRAME_BINARY, FRAME_JSON, HEADER_LEN};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufRead, Read};
+
+/// Longest excerpt of an unparseable line kept in a protocol error.
+const MAX_EXCERPT_LEN: usize = 200;
+
+/// Lines longer than this are discarded rather than buffered, so a runaway
+/// writer can't exhaust memory.
+pub const MAX_LINE_BYTES: usize = 64 * 1024 * 1024;
+
+/// One line of the bridge's stdout protocol, discriminated by its `type`
+/// field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BridgeMessage {
+    Event(ExecutorEvent),
+    Response(ExecutorResponse),
+}
+
+/// A line that could not be decoded as a `BridgeMessage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolError {
+    pub error: String,
+    pub excerpt: String,
+}
+
+#[derive(Clone)]
+pub enum Frame {
+    Message(BridgeMessage),
+    Invalid(ProtocolError),
+}
+
+/// How messages are delimited on the stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framing {
+    /// One message per line, on stdout.
+    Lines,
+    /// Length-prefixed frames, on a socket (see `transport`).
+    LengthPrefixed,
+}
+
+/// Reads newline-delimited or length-prefixed messages, reusing one buffer
+/// for the lifetime of the reader.
+pub struct MessageReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    max_line: usize,
+    framing: Framing,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_max_line(reader, MAX_LINE_BYTES)
+    }
+
+    pub fn with_max_line(reader: R, max_line: usize) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(8 * 1024),
+            max_line,
+            framing: Framing::Lines,
+        }
+    }
+
+    /// Reads length-prefixed frames instead of lines, with the same limit.
+    pub fn length_prefixed(self) -> Self {
+        Self {
+            framing: Framing::LengthPrefixed,
+            ..self
+        }
+    }
+
+    /// The raw bytes of the message behind the last returned frame.
+    pub fn last_line(&self) -> &[u8] {
+        self.buf.trim_ascii()
+    }
+
+    /// Returns the next message decoded, or `None` at end of stream. Blank
+    /// lines are skipped.
+    pub fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        if self.framing == Framing::LengthPrefixed {
+            return self.read_frame();
+        }
+        loop {
+            let Some(truncated) = self.read_line()? else {
+                return Ok(None);
+            };
+
+            let line = self.buf.trim_ascii();
+            if truncated {
+                return Ok(Some(Frame::Invalid(ProtocolError {
+                    error: format!("line exceeds {} bytes", self.max_line),
+                    excerpt: excerpt(line),
+                })));
+            }
+            if line.is_empty() {
+                continue;
+            }
+            return Ok(Some(decode(line)));
+        }
+    }
+
+    /// Reads the next length-prefixed frame into `buf`. Frames over
+    /// `max_line` bytes are skipped, reported as invalid.
+    fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        self.buf.clear();
+        // The stream may only end between frames
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let mut header = [0u8; HEADER_LEN];
+        self.reader.read_exact(&mut header)?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        if len > self.max_line as u64 {
+            io::copy(&mut (&mut self.reader).take(len), &mut io::sink())?;
+            return Ok(Some(Frame::Invalid(ProtocolError {
+                error: format!("frame exceeds {} bytes", self.max_line),
+                excerpt: String::new(),
+            })));
+        }
+        self.buf.resize(len as usize, 0);
+        self.reader.read_exact(&mut self.buf)?;
+
+        let frame = match header[4] {
+            FRAME_JSON => decode(&self.buf),
+            FRAME_BINARY => match transport::split_binary(&self.buf) {
+                Ok((message, bytes)) => {
+                    let mut frame = decode(message);
+                    attach_binary(&mut frame, bytes);
+                    // Keeps the message alone for `last_line`
+                    let message_len = message.len();
+                    self.buf.truncate(4 + message_len);
+                    self.buf.drain(..4);
+                    frame
+                }
+                Err(error) => Frame::Invalid(ProtocolError {
+                    error,
+                    excerpt: String::new(),
+                }),
+            },
+            kind => Frame::Invalid(ProtocolError {
+                error: format!("unknown frame kind {}", kind),
+                excerpt: excerpt(&self.buf),
+            }),
+        };
+        Ok(Some(frame))
+    }
+
+    /// Reads up to the next newline into `buf`, keeping at most `max_line`
+    /// bytes. Returns whether the line was cut short, or `None` at end of
+    /// stream.
+    fn read_line(&mut self) -> io::Result<Option<bool>> {
+        self.buf.clear();
+        let mut truncated = false;
+        let mut read_any = false;
+
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                return Ok(read_any.then_some(truncated));
+            }
+            read_any = true;
+
+            let newline = available.iter().position(|&b| b == b'\n');
+            let used = newline.map_or(available.len(), |i| i + 1);
+            let room = self.max_line.saturating_sub(self.buf.len());
+            if used > room {
+                truncated = true;
+            }
+            self.buf.extend_from_slice(&available[..used.min(room)]);
+            self.reader.consume(used);
+
+            if newline.is_some() {
+                return Ok(Some(truncated));
+            }
+        }
+    }
+}
+
+fn decode(line: &[u8]) -> Frame {
+    match serde_json::from_slice::<BridgeMessage>(line) {
+        Ok(message) => Frame::Message(message),
+        Err(e) => Frame::Invalid(ProtocolError {
+            error: e.to_string(),
+            excerpt: excerpt(line),
+        }),
+    }
+}
+
+/// Adds a binary frame's bytes to its message's data as `binary`.
+fn attach_binary(frame: &mut Frame, bytes: &[u8]) {
+    let data = match frame {
+        Frame::Message(BridgeMessage::Event(event)) => &mut event.data,
+        Frame::Message(BridgeMessage::Response(response)) => response
+            .data
+            .get_or_insert_with(|| Value::Object(Default::default())),
+        Frame::Invalid(_) => return,
+    };
+    if let Value::Object(fields) = data {
+        fields.insert("binary".to_string(), Value::from(STANDARD.encode(bytes)));
+    }
+}
+
+fn excerpt(line: &[u8]) -> String {
+    let head = &line[..line.len().min(MAX_EXCERPT_LEN * 4)];
+    String::from_utf8_lossy(head)
+        .chars()
+        .take(MAX_EXCERPT_LEN)
+        .collect()
+}
@@ -0,0 +1,266 @@
+//! A Python environment the runner sets up for itself, so it works on
+//! machines without Poetry or a venv. A suitable Python from PATH is used
+//! as the base, or a standalone build is downloaded when there is none; a
+//! venv with the pinned packages is then created under the data directory.
+//! `environment` prefers it over the system interpreter once it exists.
+
+use crate::tasks::TaskContext;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// Oldest Python the bridge scripts run on.
+pub const MIN_PYTHON: (u32, u32) = (3, 12);
+
+/// Python downloaded when PATH has none, from python-build-standalone.
+pub const STANDALONE_PYTHON: &str = "3.12.7";
+const STANDALONE_RELEASE: &str = "20241016";
+
+/// Installed into the venv. Changing these makes the environment out of
+/// date, so the next provisioning rebuilds it.
+pub const PACKAGES: &[&str] = &["qontinui==0.1.0", "pillow>=10.2,<11"];
+
+const MARKER_FILE: &str = "provisioned.json";
+
+/// Written once the environment is complete.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provisioned {
+    pub python: PathBuf,
+    pub base_python: String,
+    pub downloaded: bool,
+    pub packages: Vec<String>,
+    pub provisioned_at: String,
+}
+
+/// Directory holding the managed environment.
+pub fn root() -> PathBuf {
+    crate::profiles::data_dir().join("python")
+}
+
+pub fn venv_python(root: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        root.join("venv").join("Scripts").join("python.exe")
+    } else {
+        root.join("venv").join("bin").join("python")
+    }
+}
+
+fn standalone_python(root: &Path) -> PathBuf {
+    let install = root.join("runtime").join("python");
+    if cfg!(target_os = "windows") {
+        install.join("python.exe")
+    } else {
+        install.join("bin").join("python3")
+    }
+}
+
+/// The managed environment's interpreter, if it was provisioned with the
+/// current package list.
+pub fn managed_python() -> Option<PathBuf> {
+    let root = root();
+    let marker = read_marker(&root)?;
+    (marker.packages == PACKAGES && marker.python.exists()).then_some(marker.python)
+}
+
+fn read_marker(root: &Path) -> Option<Provisioned> {
+    let content = std::fs::read_to_string(root.join(MARKER_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Major and minor version from `python --version` output such as
+/// `Python 3.12.1`.
+pub fn parse_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.trim().strip_prefix("Python ")?;
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Download for a platform as named by `std::env::consts`, or `None` where
+/// no standalone build is published.
+pub fn standalone_url(os: &str, arch: &str) -> Option<String> {
+    let target = match (os, arch) {
+        ("windows", "x86_64") => "x86_64-pc-windows-msvc",
+        ("macos", "x86_64") => "x86_64-apple-darwin",
+        ("macos", "aarch64") => "aarch64-apple-darwin",
+        ("linux", "x86_64") => "x86_64-unknown-linux-gnu",
+        ("linux", "aarch64") => "aarch64-unknown-linux-gnu",
+        _ => return None,
+    };
+    Some(format!(
+        "https://github.com/astral-sh/python-build-standalone/releases/download/{release}/cpython-{python}+{release}-{target}-install_only.tar.gz",
+        release = STANDALONE_RELEASE,
+        python = STANDALONE_PYTHON,
+    ))
+}
+
+/// Sets up the environment, reporting progress on `task`. An environment
+/// that is already up to date is kept unless `force` is set.
+pub fn provision(task: &TaskContext, force: bool) -> Result<Provisioned, String> {
+    let root = root();
+    if !force {
+        if let Some(marker) = read_marker(&root).filter(|m| m.packages == PACKAGES) {
+            if marker.python.exists() {
+                task.progress(1.0, "Python environment is up to date");
+                return Ok(marker);
+            }
+        }
+    }
+    std::fs::create_dir_all(&root).map_err(|e| format!("Failed to create {:?}: {}", root, e))?;
+    // An interrupted run must not leave a marker for a half-built venv
+    let _ = std::fs::remove_file(root.join(MARKER_FILE));
+
+    task.progress(0.05, "Looking for Python");
+    let (base_python, downloaded) = match find_python() {
+        Some(python) => (python, false),
+        None => {
+            check_cancelled(task)?;
+            task.progress(0.1, format!("Downloading Python {}", STANDALONE_PYTHON));
+            (download_python(&root)?, true)
+        }
+    };
+    info!("Provisioning Python environment from {}", base_python);
+
+    check_cancelled(task)?;
+    task.progress(0.4, "Creating virtual environment");
+    let venv = root.join("venv");
+    if venv.exists() {
+        std::fs::remove_dir_all(&venv)
+            .map_err(|e| format!("Failed to remove old environment: {}", e))?;
+    }
+    run(
+        Command::new(&base_python).arg("-m").arg("venv").arg(&venv),
+        "create the virtual environment",
+    )?;
+
+    check_cancelled(task)?;
+    task.progress(0.55, format!("Installing {}", PACKAGES.join(", ")));
+    let python = venv_python(&root);
+    run(
+        Command::new(&python)
+            .args(["-m", "pip", "install", "--disable-pip-version-check"])
+            .args(PACKAGES),
+        "install packages",
+    )?;
+
+    let marker = Provisioned {
+        python,
+        base_python,
+        downloaded,
+        packages: PACKAGES.iter().map(|p| p.to_string()).collect(),
+        provisioned_at: chrono::Local::now().to_rfc3339(),
+    };
+    let content = serde_json::to_string_pretty(&marker).map_err(|e| e.to_string())?;
+    std::fs::write(root.join(MARKER_FILE), content)
+        .map_err(|e| format!("Failed to record the environment: {}", e))?;
+
+    super::environment::invalidate_cache();
+    task.progress(1.0, "Python environment ready");
+    info!("Python environment provisioned at {:?}", marker.python);
+    Ok(marker)
+}
+
+fn check_cancelled(task: &TaskContext) -> Result<(), String> {
+    if task.is_cancelled() {
+        Err("Provisioning cancelled".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// A Python from PATH that is new enough, or the standalone build from an
+/// earlier download.
+fn find_python() -> Option<String> {
+    let standalone = standalone_python(&root());
+    let candidates = if cfg!(target_os = "windows") {
+        ["python", "python3"]
+    } else {
+        ["python3", "python"]
+    };
+    candidates
+        .into_iter()
+        .map(String::from)
+        .chain(
+            standalone
+                .exists()
+                .then(|| standalone.display().to_string()),
+        )
+        .find(|python| {
+            let Ok(output) = Command::new(python).arg("--version").output() else {
+                return false;
+            };
+            // Python before 3.4 printed its version to stderr
+            let text = [output.stdout, output.stderr].concat();
+            parse_version(&String::from_utf8_lossy(&text)).is_some_and(|v| v >= MIN_PYTHON)
+        })
+}
+
+fn download_python(root: &Path) -> Result<String, String> {
+    let url = standalone_url(std::env::consts::OS, std::env::consts::ARCH).ok_or(format!(
+        "No Python download for {} {}; install Python {}.{} or newer",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        MIN_PYTHON.0,
+        MIN_PYTHON.1
+    ))?;
+    let runtime = root.join("runtime");
+    if runtime.exists() {
+        std::fs::remove_dir_all(&runtime)
+            .map_err(|e| format!("Failed to remove old download: {}", e))?;
+    }
+    std::fs::create_dir_all(&runtime)
+        .map_err(|e| format!("Failed to create {:?}: {}", runtime, e))?;
+
+    // curl and tar ship with every supported OS, Windows 10 included
+    let archive = root.join("python.tar.gz");
+    run(
+        Command::new("curl")
+            .args([
+                "--location",
+                "--fail",
+                "--silent",
+                "--show-error",
+                "--output",
+            ])
+            .arg(&archive)
+            .arg(&url),
+        "download Python",
+    )?;
+    let extracted = run(
+        Command::new("tar")
+            .arg("-xzf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&runtime),
+        "unpack Python",
+    );
+    if let Err(e) = std::fs::remove_file(&archive) {
+        warn!("Failed to remove {:?}: {}", archive, e);
+    }
+    extracted?;
+
+    let python = standalone_python(root);
+    if !python.exists() {
+        return Err(format!(
+            "Downloaded Python has no interpreter at {:?}",
+            python
+        ));
+    }
+    Ok(python.display().to_string())
+}
+
+/// Runs a step to completion, failing with the end of its output.
+fn run(cmd: &mut Command, what: &str) -> Result<(), String> {
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to {}: {}", what, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    let tail = lines[lines.len().saturating_sub(5)..].join("\n");
+    Err(format!("Failed to {} ({}): {}", what, output.status, tail))
+}
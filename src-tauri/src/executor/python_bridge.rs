@@ -1,10 +1,42 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use tauri::Emitter;
+use std::time::Duration;
+use tauri::Manager;
+
+use super::artifacts;
+use super::backend::{ExecutorBackend, Subscriber};
+use super::backpressure::{BackpressureConfig, Counters, EventQueue, PipelineMetrics};
+use super::conformance;
+use super::environment::{self, ExecutorLaunchOptions};
+use super::event_bus::{EventBus, Severity};
+use super::frames;
+use super::handshake::Handshake;
+use super::metrics::BridgeStats;
+use super::mock_bridge::{MockBridge, MockScript};
+use super::pipeline::EventPipeline;
+use super::process_tree;
+use super::protocol::{BridgeMessage, Frame, MessageReader};
+use super::screenshot_executor::{self, ScreenshotExecutor};
+use super::sessions::{self, DEFAULT_SESSION};
+use super::shutdown::{self, ShutdownOutcome};
+use super::throttle::ThrottleConfig;
+use super::transport::{self, Endpoint, TransportKind};
+use super::watchdog::{self, CrashReport};
+use crate::config::diff;
+use crate::config::QontinuiConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutorCommand {
@@ -15,29 +47,295 @@ pub struct ExecutorCommand {
     pub params: Option<Value>,
 }
 
+/// Reply to a command. The `type` tag lives on `BridgeMessage`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutorResponse {
-    #[serde(rename = "type")]
-    pub resp_type: String,
     pub id: String,
     pub success: bool,
     pub data: Option<Value>,
     pub error: Option<String>,
 }
 
+/// Unsolicited notification from the executor. The `type` tag lives on
+/// `BridgeMessage`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutorEvent {
-    #[serde(rename = "type")]
-    pub event_type: String,
     pub event: String,
     pub timestamp: f64,
     pub sequence: u32,
     pub data: Value,
 }
 
+impl ExecutorEvent {
+    /// An event raised by the runner itself, stamped now. Sequence 0 keeps
+    /// it outside the executor's numbering.
+    pub fn from_runner(event: &str, data: Value) -> Self {
+        Self {
+            event: event.to_string(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default(),
+            sequence: 0,
+            data,
+        }
+    }
+}
+
+/// How long `PendingCommand::wait` callers wait by default.
+pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Commands whose sender is waiting for the response, by command id.
+#[derive(Clone, Default)]
+pub struct PendingResponses {
+    waiting: Arc<Mutex<HashMap<String, Sender<ExecutorResponse>>>>,
+}
+
+impl PendingResponses {
+    /// Registers interest in the response to `command`, sent with `id`.
+    /// Register before sending, or a fast reply can arrive first.
+    pub fn expect(&self, id: &str, command: &str) -> PendingCommand {
+        let (sender, receiver) = mpsc::channel();
+        self.waiting.lock().unwrap().insert(id.to_string(), sender);
+        PendingCommand {
+            id: id.to_string(),
+            command: command.to_string(),
+            receiver,
+            responses: self.clone(),
+        }
+    }
+
+    /// Hands a response to its waiter. Returns false if nobody waits for it.
+    pub fn resolve(&self, response: &ExecutorResponse) -> bool {
+        match self.waiting.lock().unwrap().remove(&response.id) {
+            Some(sender) => sender.send(response.clone()).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn forget(&self, id: &str) {
+        self.waiting.lock().unwrap().remove(id);
+    }
+
+    /// Wakes every waiter with an error, for when the executor goes away.
+    pub fn abandon_all(&self) {
+        self.waiting.lock().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.waiting.lock().unwrap().len()
+    }
+}
+
+/// A sent command whose response can be waited for.
+pub struct PendingCommand {
+    id: String,
+    command: String,
+    receiver: Receiver<ExecutorResponse>,
+    responses: PendingResponses,
+}
+
+impl PendingCommand {
+    /// Blocks until the executor responds, it stops, or `timeout` passes.
+    pub fn wait(self, timeout: Duration) -> Result<ExecutorResponse, String> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(response) => Ok(response),
+            Err(RecvTimeoutError::Timeout) => {
+                self.responses.forget(&self.id);
+                Err(format!(
+                    "No response to '{}' within {} ms",
+                    self.command,
+                    timeout.as_millis()
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(format!(
+                "Executor stopped before responding to '{}'",
+                self.command
+            )),
+        }
+    }
+}
+
+/// Lines of Python stderr kept for crash reports.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Payloads larger than this are split into `load_chunk` commands.
+const CONFIG_CHUNK_SIZE: usize = 512 * 1024;
+
+/// How a validated configuration is handed to the executor.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConfigTransferMode {
+    /// Send the file path and let Python read the file itself.
+    #[default]
+    Path,
+    /// Send the validated JSON over the bridge.
+    Content,
+    /// Send the validated JSON gzip-compressed and base64-encoded.
+    Compressed,
+}
+
+impl ConfigTransferMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "path" => Ok(ConfigTransferMode::Path),
+            "content" => Ok(ConfigTransferMode::Content),
+            "compressed" => Ok(ConfigTransferMode::Compressed),
+            _ => Err(format!("Unknown config transfer mode: {}", mode)),
+        }
+    }
+}
+
+/// What a thread reading the executor's messages shares with the bridge.
+#[derive(Clone)]
+struct Inbound {
+    app_handle: tauri::AppHandle,
+    throttle_config: Arc<Mutex<ThrottleConfig>>,
+    backpressure: Arc<Mutex<BackpressureConfig>>,
+    counters: Arc<Counters>,
+    stats: Arc<BridgeStats>,
+    strict_protocol: Arc<AtomicBool>,
+    protocol_violations: Arc<AtomicU64>,
+    handshake: Arc<Mutex<Handshake>>,
+    responses: PendingResponses,
+    subscriber: Arc<Mutex<Option<Subscriber>>>,
+    session_id: String,
+}
+
+impl Inbound {
+    /// Handles messages from `source` until it ends, then gives up on the
+    /// responses still awaited. Messages are emitted from a thread of their
+    /// own, behind a bounded queue, so a slow frontend holds the reader back
+    /// instead of piling up.
+    fn read<R: BufRead>(self, mut reader: MessageReader<R>, source: &str) {
+        let Inbound {
+            app_handle,
+            throttle_config,
+            backpressure,
+            counters,
+            stats,
+            strict_protocol,
+            protocol_violations,
+            handshake,
+            responses,
+            subscriber,
+            session_id,
+        } = self;
+        let mut bus = EventBus::new(app_handle.clone()).for_session(&session_id);
+        let artifacts_handle = app_handle.clone();
+        let mut pipeline = EventPipeline::new(app_handle, throttle_config)
+            .for_session(&session_id)
+            .with_responses(responses.clone())
+            .with_counters(counters.clone())
+            .with_subscriber(subscriber);
+        let queue = EventQueue::new(backpressure, counters);
+        let emitter = {
+            let queue = queue.clone();
+            let span = tracing::Span::current();
+            thread::spawn(move || {
+                let _span = span.entered();
+                while let Some(frame) = queue.pop() {
+                    pipeline.handle(frame);
+                }
+            })
+        };
+        loop {
+            match reader.next_frame() {
+                Ok(Some(mut frame)) => {
+                    if strict_protocol.load(Ordering::Relaxed) {
+                        let line = reader.last_line();
+                        for violation in conformance::check(line) {
+                            protocol_violations.fetch_add(1, Ordering::Relaxed);
+                            tracing::warn!(
+                                "Protocol violation: {} in line: {}",
+                                violation,
+                                String::from_utf8_lossy(line)
+                            );
+                        }
+                    }
+                    if let Frame::Message(BridgeMessage::Event(ref mut event)) = frame {
+                        // Before anything else, while the screen still shows the failure
+                        if session_id == DEFAULT_SESSION {
+                            artifacts::attach_failure_screenshot(&artifacts_handle, event);
+                        }
+                        frames::link(&mut event.data);
+                        let settled = {
+                            let mut handshake = handshake.lock().unwrap();
+                            handshake.observe(event).then(|| handshake.clone())
+                        };
+                        match settled {
+                            Some(Handshake::Outdated { warning, .. }) => {
+                                bus.runner(Severity::Warning, warning)
+                            }
+                            Some(Handshake::Incompatible { reason, .. }) => {
+                                bus.runner(Severity::Error, reason)
+                            }
+                            Some(settled) => {
+                                tracing::info!("Executor handshake: {:?}", settled.hello())
+                            }
+                            None => {}
+                        }
+                        bus.dispatch(event);
+                    }
+                    if let Some(gap) = stats.frame_received(&frame) {
+                        bus.events_lost(gap);
+                    }
+                    queue.push(frame)
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    bus.runner(Severity::Error, format!("Error reading {}: {}", source, e));
+                    break;
+                }
+            }
+        }
+        tracing::debug!("Executor {} reader ending", source);
+        queue.close();
+        let _ = emitter.join();
+        // Nothing more will be answered
+        responses.abandon_all();
+    }
+}
+
 pub struct PythonBridge {
     process: Option<Child>,
+    /// Set instead of `process` when the executor runs in-process: the
+    /// mock for the `"test"` and `"minimal"` executor types, screenshot
+    /// mode for `"screenshot"` or when Python can't be found for a
+    /// screenshot-mode configuration. Also holds the connection to a
+    /// remote executor, for `"remote"`.
+    in_process: Option<Box<dyn ExecutorBackend>>,
     is_running: Arc<Mutex<bool>>,
+    throttle_config: Arc<Mutex<ThrottleConfig>>,
+    /// Bounds the queue between reading the executor's messages and
+    /// emitting them.
+    backpressure: Arc<Mutex<BackpressureConfig>>,
+    pipeline_counters: Arc<Counters>,
+    stats: Arc<BridgeStats>,
+    /// Check every inbound line against the strict protocol schema.
+    strict_protocol: Arc<AtomicBool>,
+    protocol_violations: Arc<AtomicU64>,
+    /// What the running executor said about its protocol version.
+    handshake: Arc<Mutex<Handshake>>,
+    /// Transport tried first when starting the executor.
+    transport_kind: TransportKind,
+    /// Environment, working directory and arguments of the process.
+    launch_options: ExecutorLaunchOptions,
+    /// Transport the running executor is connected over.
+    transport: Arc<Mutex<TransportKind>>,
+    /// Where commands go once the executor connected to the socket.
+    socket: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+    responses: PendingResponses,
+    /// Also receives what the executor writes, once something subscribed.
+    subscriber: Arc<Mutex<Option<Subscriber>>>,
+    /// Last lines the Python process wrote to stderr.
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    /// Tells the current process's watchdog to quit.
+    watchdog_stop: Arc<AtomicBool>,
+    /// Configuration the executor currently holds, used to send only changes
+    /// on reload. Keeps the previous version alive until the next sync.
+    last_synced: Option<Arc<QontinuiConfig>>,
+    /// Executor session this bridge belongs to, tagged onto its events.
+    session_id: String,
     app_handle: tauri::AppHandle,
 }
 
@@ -45,254 +343,416 @@ impl PythonBridge {
     pub fn new(app_handle: tauri::AppHandle) -> Self {
         Self {
             process: None,
+            in_process: None,
             is_running: Arc::new(Mutex::new(false)),
+            throttle_config: Arc::new(Mutex::new(ThrottleConfig::default())),
+            backpressure: Arc::new(Mutex::new(BackpressureConfig::default())),
+            pipeline_counters: Arc::new(Counters::default()),
+            stats: Arc::new(BridgeStats::default()),
+            strict_protocol: Arc::new(AtomicBool::new(
+                std::env::var_os("QONTINUI_PROTOCOL_STRICT").is_some(),
+            )),
+            protocol_violations: Arc::new(AtomicU64::new(0)),
+            handshake: Arc::new(Mutex::new(Handshake::Pending)),
+            transport_kind: app_handle
+                .try_state::<crate::commands::AppState>()
+                .map(|state| *state.executor_transport.lock().unwrap())
+                .unwrap_or_else(TransportKind::from_env),
+            launch_options: app_handle
+                .try_state::<crate::commands::AppState>()
+                .map(|state| state.executor_launch.lock().unwrap().clone())
+                .unwrap_or_default(),
+            transport: Arc::new(Mutex::new(TransportKind::Stdio)),
+            socket: Arc::new(Mutex::new(None)),
+            responses: PendingResponses::default(),
+            subscriber: Arc::default(),
+            stderr_tail: Arc::new(Mutex::new(VecDeque::new())),
+            watchdog_stop: Arc::new(AtomicBool::new(true)),
+            last_synced: None,
+            session_id: DEFAULT_SESSION.to_string(),
             app_handle,
         }
     }
 
-    #[allow(dead_code)]
-    pub fn start(&mut self) -> Result<(), String> {
-        self.start_with_executor("simple")
+    /// Makes this the bridge of a created session rather than the default
+    /// one. Set before starting it.
+    pub fn for_session(mut self, session_id: &str) -> Self {
+        self.session_id = session_id.to_string();
+        self
+    }
+
+    /// Launches the executor as `options` say instead of as the profile
+    /// does. Set before starting it.
+    pub fn with_launch_options(mut self, options: ExecutorLaunchOptions) -> Self {
+        self.launch_options = options;
+        self
     }
 
-    pub fn start_with_executor(&mut self, executor_type: &str) -> Result<(), String> {
+    /// Runs an executor inside the runner, its output going through the
+    /// same pipeline as a Python executor's.
+    fn start_in_process(
+        &mut self,
+        mut backend: Box<dyn ExecutorBackend>,
+        executor_type: &str,
+    ) -> Result<(), String> {
+        let mut pipeline =
+            EventPipeline::new(self.app_handle.clone(), self.throttle_config.clone())
+                .for_session(&self.session_id)
+                .with_responses(self.responses.clone())
+                .with_subscriber(self.subscriber.clone());
+        // Checked like a Python executor's messages
+        let stats = self.stats.clone();
+        let bus = EventBus::new(self.app_handle.clone()).for_session(&self.session_id);
+        backend.subscribe(Box::new(move |frame| {
+            if let Some(gap) = stats.frame_received(&frame) {
+                bus.events_lost(gap);
+            }
+            pipeline.handle(frame)
+        }));
+        self.stats.start();
+        backend.start(executor_type)?;
+        self.in_process = Some(backend);
+        *self.is_running.lock().unwrap() = true;
+        self.emit_lifecycle("started", Some(executor_type));
+        Ok(())
+    }
+
+    /// Whether the runner holds a configuration in screenshot mode with a
+    /// screenshot directory.
+    fn screenshot_mode_loaded(&self) -> bool {
+        self.app_handle
+            .try_state::<crate::commands::AppState>()
+            .and_then(|state| state.current_config.lock().unwrap().clone())
+            .is_some_and(|config| {
+                config.is_screenshot_mode()
+                    && screenshot_executor::screenshot_directory(&config).is_ok()
+            })
+    }
+
+    /// Tells the frontend the executor started or was stopped.
+    fn emit_lifecycle(&self, status: &str, executor_type: Option<&str>) {
+        let _ = sessions::emit(
+            &self.app_handle,
+            "executor-lifecycle",
+            &self.session_id,
+            &json!({
+                "status": status,
+                "executor_type": executor_type,
+            }),
+        );
+    }
+
+    /// Sends the configuration content itself so Python uses exactly the data
+    /// validated on the Rust side. Large payloads are chunked over stdio. Returns the id
+    /// of the final `load` command.
+    pub fn load_configuration_content(
+        &mut self,
+        content: &str,
+        compress: bool,
+    ) -> Result<String, String> {
+        let checksum: String = Sha256::digest(content.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        let (payload, encoding): (Cow<str>, &str) = if compress {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(content.as_bytes())
+                .and_then(|_| encoder.finish())
+                .map(|compressed| (Cow::Owned(STANDARD.encode(compressed)), "gzip+base64"))
+                .map_err(|e| format!("Failed to compress configuration: {}", e))?
+        } else {
+            (Cow::Borrowed(content), "json")
+        };
+
+        // Sockets take a payload of any size in one frame
+        if payload.len() <= CONFIG_CHUNK_SIZE || self.transport() != Some(TransportKind::Stdio) {
+            return self.send_command_with_id(
+                "load",
+                Some(json!({
+                    "config_data": payload,
+                    "encoding": encoding,
+                    "sha256": checksum,
+                })),
+            );
+        }
+
+        let transfer_id = uuid::Uuid::new_v4().to_string();
+        let chunks = split_chunks(&payload, CONFIG_CHUNK_SIZE);
+        for (index, chunk) in chunks.iter().enumerate() {
+            self.send_command(
+                "load_chunk",
+                Some(json!({
+                    "transfer_id": transfer_id,
+                    "index": index,
+                    "total": chunks.len(),
+                    "data": chunk,
+                })),
+            )?;
+        }
+
+        self.send_command_with_id(
+            "load",
+            Some(json!({
+                "transfer_id": transfer_id,
+                "encoding": encoding,
+                "sha256": checksum,
+            })),
+        )
+    }
+
+    /// Closes the socket, if the executor connected to one.
+    fn disconnect(&self) {
+        *self.socket.lock().unwrap() = None;
+        *self.transport.lock().unwrap() = TransportKind::Stdio;
+    }
+}
+
+impl ExecutorBackend for PythonBridge {
+    fn name(&self) -> &'static str {
+        self.in_process
+            .as_ref()
+            .map_or("python", |backend| backend.name())
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.handshake()
+            .hello()
+            .and_then(|hello| hello.commands.clone())
+            .unwrap_or_default()
+    }
+
+    /// Also sends what the executor writes to `subscriber`; it still goes
+    /// to the frontend.
+    fn subscribe(&mut self, subscriber: Subscriber) {
+        *self.subscriber.lock().unwrap() = Some(subscriber);
+    }
+
+    fn unsubscribe(&mut self) {
+        *self.subscriber.lock().unwrap() = None;
+    }
+
+    fn start(&mut self, executor_type: &str) -> Result<(), String> {
         if *self.is_running.lock().unwrap() {
             return Err("Python process already running".to_string());
         }
 
-        // Use minimal_bridge.py for testing when executor_type is "minimal"
+        // "test" and "minimal" run the mock executor in-process without Python
+        if executor_type == "test" || executor_type == "minimal" {
+            let mock = MockBridge::new(MockScript::load()?.unwrap_or_default());
+            return self.start_in_process(Box::new(mock), executor_type);
+        }
+        if executor_type == "screenshot" {
+            let executor = ScreenshotExecutor::new(self.app_handle.clone());
+            return self.start_in_process(Box::new(executor), executor_type);
+        }
+        if executor_type == "remote" {
+            let remote = self
+                .app_handle
+                .try_state::<crate::commands::AppState>()
+                .ok_or("No remote executor set up")?
+                .remote_executor
+                .bridge()?;
+            return self.start_in_process(Box::new(remote), executor_type);
+        }
+
         // Use qontinui_executor.py for "real" mode (has recording support)
         // Otherwise use qontinui_bridge.py which handles both real and mock modes
-        let script_name = if executor_type == "minimal" {
-            "minimal_bridge.py"
-        } else if executor_type == "real" {
+        let script_name = if executor_type == "real" {
             "qontinui_executor.py"
         } else {
             "qontinui_bridge.py"
         };
 
-        // Get the path to the Python bridge script
-        // Try multiple possible locations
-        let possible_paths = vec![
-            // When running from src-tauri (most common in development)
-            std::env::current_dir().ok().and_then(|p| {
-                // Go up from src-tauri/target/debug to qontinui-runner
-                if p.ends_with("debug") || p.ends_with("release") {
-                    p.parent()
-                        .and_then(|p| p.parent())
-                        .and_then(|p| p.parent())
-                        .map(|p| p.join("python-bridge").join(script_name))
-                } else if p.ends_with("src-tauri") {
-                    p.parent()
-                        .map(|p| p.join("python-bridge").join(script_name))
-                } else {
-                    None
-                }
-            }),
-            // When running from qontinui-runner directory
-            std::env::current_dir()
-                .ok()
-                .map(|p| p.join("python-bridge").join(script_name)),
-            // When in src-tauri directory
-            std::env::current_dir()
-                .ok()
-                .map(|p| p.join("..").join("python-bridge").join(script_name)),
-        ];
-
-        // Debug: Print current directory
-        eprintln!("Current directory: {:?}", std::env::current_dir());
-
-        let bridge_script = possible_paths
-            .into_iter()
-            .flatten()
-            .inspect(|p| eprintln!("Checking path: {:?}, exists: {}", p, p.exists()))
-            .find(|p| p.exists())
-            .ok_or(format!(
-                "Python bridge script {} not found in any expected location",
-                script_name
-            ))?;
-
-        eprintln!("Using Python bridge script: {:?}", bridge_script);
-
-        if !bridge_script.exists() {
-            return Err(format!(
-                "Python bridge script not found at: {:?}",
-                bridge_script
-            ));
-        }
-
-        // Start the Python process with appropriate mode
-        // Strategy:
-        // 1. For qontinui_executor.py and qontinui_bridge.py: use Poetry (needs qontinui library)
-        // 2. For minimal_bridge.py: use system Python (no dependencies)
-        // 3. Fall back to venv if it exists
-
-        let use_poetry = script_name == "qontinui_executor.py" || script_name == "qontinui_bridge.py";
-
-        // Check for Poetry and qontinui library location
-        let poetry_available = if use_poetry {
-            // Check if we can find the qontinui library directory
-            let qontinui_path = bridge_script.parent()
-                .and_then(|p| p.parent()) // Go up from python-bridge to qontinui-runner
-                .and_then(|p| p.parent()) // Go up to qontinui_parent
-                .map(|p| p.join("qontinui").join("pyproject.toml"));
-
-            if let Some(ref path) = qontinui_path {
-                eprintln!("Checking for qontinui at: {:?}, exists: {}", path, path.exists());
-                path.exists()
-            } else {
-                false
+        // Script location and interpreter are probed once and cached
+        let env = match environment::resolve(script_name) {
+            Ok(env) => env,
+            Err(e) if executor_type != "real" && self.screenshot_mode_loaded() => {
+                tracing::warn!(
+                    "{}; running the screenshot-mode configuration without Python",
+                    e
+                );
+                let executor = ScreenshotExecutor::new(self.app_handle.clone());
+                return self.start_in_process(Box::new(executor), "screenshot");
             }
-        } else {
-            false
+            Err(e) => return Err(e),
         };
+        tracing::info!("Using Python bridge script: {:?}", env.script);
 
-        let venv_python = bridge_script.parent().and_then(|p| {
-            let venv_path = p.join("venv/Scripts/python.exe");
-            eprintln!(
-                "Checking venv path: {:?}, exists: {}",
-                venv_path,
-                venv_path.exists()
-            );
-            if venv_path.exists() {
-                Some(venv_path)
-            } else {
-                None
-            }
-        });
-
-        let mut cmd = if poetry_available && use_poetry {
-            eprintln!("Using Poetry to run Python with qontinui library");
-            let qontinui_dir = bridge_script.parent()
-                .and_then(|p| p.parent())
-                .and_then(|p| p.parent())
-                .map(|p| p.join("qontinui"))
-                .expect("Could not determine qontinui directory");
-
-            let mut poetry_cmd = Command::new("poetry");
-            poetry_cmd.current_dir(&qontinui_dir);
-            poetry_cmd.arg("run");
-            poetry_cmd.arg("python");
-            poetry_cmd.arg(bridge_script);
-            poetry_cmd
-        } else if let Some(venv_path) = venv_python {
-            eprintln!("Using venv Python: {:?}", venv_path);
-            let mut python_cmd = Command::new(venv_path);
-            python_cmd.arg(bridge_script);
-            python_cmd
-        } else if cfg!(target_os = "windows") {
-            eprintln!("Using system python");
-            let mut python_cmd = Command::new("python");
-            python_cmd.arg(bridge_script);
-            python_cmd
-        } else {
-            eprintln!("Using system python3");
-            let mut python_cmd = Command::new("python3");
-            python_cmd.arg(bridge_script);
-            python_cmd
-        };
+        let mut cmd = env.command(&self.launch_options)?;
 
         // Pass --mock flag for simulation/mock mode
-        // executor_type values: "real", "mock", "simulation", "qontinui", "simple", "minimal"
+        // executor_type values: "real", "mock", "simulation", "qontinui", "simple"
         // Only "real" mode should NOT have --mock flag
-        // "minimal" uses minimal_bridge.py for testing without qontinui dependency
         if executor_type != "real" {
             cmd.arg("--mock");
         }
 
+        // Its own process group, so stopping it reaches what it starts
+        shutdown::isolate(&mut cmd);
+        *self.handshake.lock().unwrap() = Handshake::Pending;
+
+        // A socket the bridge may move to, with stdio as the fallback
+        self.disconnect();
+        let endpoint = match Endpoint::bind(self.transport_kind) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                tracing::warn!("{}; using stdio", e);
+                None
+            }
+        };
+        if let Some(endpoint) = &endpoint {
+            endpoint.configure(&mut cmd, transport::ADDRESS_ENV, transport::TOKEN_ENV);
+        }
+        // Images get a socket of their own even when messages use stdio
+        let frame_kind = match self.transport_kind {
+            TransportKind::Stdio => TransportKind::preferred(),
+            kind => kind,
+        };
+        let frame_endpoint = match Endpoint::bind(frame_kind) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                tracing::warn!("No frame channel: {}", e);
+                None
+            }
+        };
+        if let Some(endpoint) = &frame_endpoint {
+            endpoint.configure(&mut cmd, frames::ADDRESS_ENV, frames::TOKEN_ENV);
+        }
+
         let mut child = cmd
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| format!("Failed to start Python process: {}", e))?;
+        shutdown::contain(&child);
+        self.stats.start();
 
         // Set up stdout reader
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
-        let app_handle = self.app_handle.clone();
-        let _is_running = self.is_running.clone();
+        let inbound = Inbound {
+            app_handle: self.app_handle.clone(),
+            throttle_config: self.throttle_config.clone(),
+            backpressure: self.backpressure.clone(),
+            counters: self.pipeline_counters.clone(),
+            stats: self.stats.clone(),
+            strict_protocol: self.strict_protocol.clone(),
+            protocol_violations: self.protocol_violations.clone(),
+            handshake: self.handshake.clone(),
+            responses: self.responses.clone(),
+            subscriber: self.subscriber.clone(),
+            session_id: self.session_id.clone(),
+        };
+        let session_id = self.session_id.clone();
+
+        let stderr_bus = EventBus::new(self.app_handle.clone()).for_session(&session_id);
+        let reader_span = tracing::info_span!("executor", executor_type, session_id);
+        let stderr_span = reader_span.clone();
+        let socket_span = reader_span.clone();
+        let frame_span = reader_span.clone();
 
+        let stdout_inbound = inbound.clone();
         thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                match line {
-                    Ok(line) => {
-                        // Debug: Print raw line received from Python
-                        eprintln!("Python stdout: {}", line);
-
-                        if let Ok(event) = serde_json::from_str::<ExecutorEvent>(&line) {
-                            eprintln!("Parsed as event: {:?}", event);
-                            // Emit event to frontend
-                            match app_handle.emit("executor-event", &event) {
-                                Ok(_) => eprintln!("Event emitted successfully"),
-                                Err(e) => eprintln!("Failed to emit event: {}", e),
-                            }
-                        } else if let Ok(response) = serde_json::from_str::<ExecutorResponse>(&line)
-                        {
-                            eprintln!("Parsed as response: {:?}", response);
-                            // Emit response to frontend
-                            match app_handle.emit("executor-response", &response) {
-                                Ok(_) => eprintln!("Response emitted successfully"),
-                                Err(e) => eprintln!("Failed to emit response: {}", e),
-                            }
-                        } else {
-                            eprintln!("Could not parse line as event or response");
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Error reading stdout: {}", e);
-                        break;
-                    }
-                }
-            }
-            eprintln!("Stdout reader thread ending");
+            let _span = reader_span.entered();
+            stdout_inbound.read(MessageReader::new(BufReader::new(stdout)), "stdout");
             // Don't mark as not running here - let the process itself determine that
         });
 
+        // Once the bridge connects, messages move to the socket
+        if let Some(endpoint) = endpoint {
+            let socket = self.socket.clone();
+            let transport = self.transport.clone();
+            thread::spawn(move || {
+                let _span = socket_span.entered();
+                match endpoint.accept(transport::CONNECT_TIMEOUT) {
+                    Ok(connection) => {
+                        tracing::info!("Executor connected over {:?}", connection.kind);
+                        *transport.lock().unwrap() = connection.kind;
+                        *socket.lock().unwrap() = Some(connection.writer);
+                        let reader = BufReader::new(connection.reader);
+                        inbound.read(MessageReader::new(reader).length_prefixed(), "socket");
+                    }
+                    Err(e) => tracing::info!("{}; using stdio", e),
+                }
+            });
+        }
+
+        if let Some(endpoint) = frame_endpoint {
+            let store = self
+                .app_handle
+                .try_state::<crate::commands::AppState>()
+                .map(|state| state.frames.clone());
+            thread::spawn(move || {
+                let _span = frame_span.entered();
+                match (endpoint.accept(transport::CONNECT_TIMEOUT), store) {
+                    (Ok(connection), Some(store)) => store.receive(connection.reader),
+                    (Ok(_), None) => {}
+                    (Err(e), _) => tracing::debug!("{}; images stay inline", e),
+                }
+            });
+        }
+
         // Set up stderr reader
         let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+        let stderr_tail = self.stderr_tail.clone();
+        stderr_tail.lock().unwrap().clear();
         thread::spawn(move || {
+            let _span = stderr_span.entered();
+            let mut bus = stderr_bus;
             let reader = BufReader::new(stderr);
             for line in reader.lines().map_while(Result::ok) {
-                eprintln!("Python stderr: {}", line);
+                {
+                    let mut tail = stderr_tail.lock().unwrap();
+                    if tail.len() >= STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line.clone());
+                }
+                bus.stderr(line);
             }
         });
 
         self.process = Some(child);
         *self.is_running.lock().unwrap() = true;
 
-        Ok(())
-    }
-
-    pub fn stop(&mut self) -> Result<(), String> {
-        if let Some(mut process) = self.process.take() {
-            // Send stop command
-            self.send_command("stop", None)?;
-
-            // Wait a bit for graceful shutdown
-            std::thread::sleep(std::time::Duration::from_millis(500));
-
-            // Kill the process if still running
-            process.kill().map_err(|e| e.to_string())?;
-            process.wait().map_err(|e| e.to_string())?;
+        self.watchdog_stop = Arc::new(AtomicBool::new(false));
+        watchdog::spawn(
+            self.app_handle.clone(),
+            &self.session_id,
+            self.watchdog_stop.clone(),
+        );
 
-            *self.is_running.lock().unwrap() = false;
-        }
+        self.emit_lifecycle("started", Some(executor_type));
         Ok(())
     }
 
-    pub fn send_command(&mut self, command: &str, params: Option<Value>) -> Result<(), String> {
+    fn send(&mut self, id: &str, command: &str, params: Option<Value>) -> Result<(), String> {
+        self.handshake().allows(command)?;
+        self.app_handle
+            .state::<crate::commands::AppState>()
+            .run_streams
+            .record_command(&self.session_id, id, command, params.as_ref());
+        if let Some(backend) = self.in_process.as_mut() {
+            self.stats.command_sent(id);
+            return backend.send(id, command, params);
+        }
         if let Some(ref mut process) = self.process {
-            if let Some(ref mut stdin) = process.stdin {
-                let cmd = ExecutorCommand {
-                    cmd_type: "command".to_string(),
-                    id: uuid::Uuid::new_v4().to_string(),
-                    command: command.to_string(),
-                    params,
-                };
-
-                let json = serde_json::to_string(&cmd).map_err(|e| e.to_string())?;
+            self.stats.command_sent(id);
+            let cmd = ExecutorCommand {
+                cmd_type: "command".to_string(),
+                id: id.to_string(),
+                command: command.to_string(),
+                params,
+            };
+            let json = serde_json::to_string(&cmd).map_err(|e| e.to_string())?;
 
+            if let Some(socket) = self.socket.lock().unwrap().as_mut() {
+                return transport::write_frame(socket, json.as_bytes())
+                    .map_err(|e| format!("Failed to send command: {}", e));
+            }
+            if let Some(ref mut stdin) = process.stdin {
                 writeln!(stdin, "{}", json)
                     .map_err(|e| format!("Failed to send command: {}", e))?;
 
@@ -309,7 +769,86 @@ impl PythonBridge {
         }
     }
 
-    pub fn load_configuration(&mut self, config_path: &str) -> Result<(), String> {
+    /// Asks the executor to stop and gives it the graceful timeout to exit,
+    /// then terminates and finally kills it along with every process it
+    /// started. Returns how it went away.
+    fn stop(&mut self) -> Result<ShutdownOutcome, String> {
+        self.watchdog_stop.store(true, Ordering::Relaxed);
+        let was_running = self.is_running();
+        let mut outcome = ShutdownOutcome::NotRunning;
+        if self.in_process.is_some() {
+            let _ = self.send_command("stop", None);
+            if let Some(mut backend) = self.in_process.take() {
+                backend.stop()?;
+            }
+            *self.is_running.lock().unwrap() = false;
+            outcome = ShutdownOutcome::Graceful;
+        }
+        if self.process.is_some() {
+            // A closed pipe just means it has to be stopped the hard way
+            if let Err(e) = self.send_command("stop", None) {
+                tracing::warn!("Failed to send stop command: {}", e);
+            }
+            let mut process = self.process.take().unwrap();
+            let settings = self
+                .app_handle
+                .try_state::<crate::commands::AppState>()
+                .map(|state| state.shutdown.lock().unwrap().clone())
+                .unwrap_or_default();
+            let result = shutdown::shut_down(&mut process, &settings);
+            self.disconnect();
+            *self.is_running.lock().unwrap() = false;
+            outcome = result?;
+        }
+        self.responses.abandon_all();
+        if was_running {
+            self.emit_lifecycle("stopped", None);
+        }
+        Ok(outcome)
+    }
+
+    fn is_running(&self) -> bool {
+        if let Some(backend) = self.in_process.as_ref() {
+            // A remote executor may have been lost meanwhile
+            return *self.is_running.lock().unwrap() && backend.is_running();
+        }
+        if self.process.is_some() {
+            // Check if the process is actually still running
+            // The child process handle doesn't have a direct is_running method,
+            // so we rely on our tracking flag
+            *self.is_running.lock().unwrap()
+        } else {
+            false
+        }
+    }
+
+    /// What the executor said about its protocol version when it started.
+    fn handshake(&self) -> Handshake {
+        match self.in_process.as_ref() {
+            Some(backend) => backend.handshake(),
+            None => self.handshake.lock().unwrap().clone(),
+        }
+    }
+
+    /// Sends a command whose response can be waited for. Wait after
+    /// releasing the bridge lock; the response is delivered by the stdout
+    /// reader, which doesn't need it.
+    fn send_command_awaiting(
+        &mut self,
+        command: &str,
+        params: Option<Value>,
+    ) -> Result<PendingCommand, String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let pending = self.responses.expect(&id, command);
+        if let Err(e) = self.send(&id, command, params) {
+            self.responses.forget(&id);
+            return Err(e);
+        }
+        Ok(pending)
+    }
+
+    fn load_configuration(&mut self, config_path: &str) -> Result<(), String> {
+        self.last_synced = None;
         self.send_command(
             "load",
             Some(json!({
@@ -318,58 +857,223 @@ impl PythonBridge {
         )
     }
 
-    #[allow(dead_code)]
-    pub fn start_execution(&mut self, mode: &str) -> Result<(), String> {
-        self.send_command(
-            "start",
-            Some(json!({
-                "mode": mode
+    /// Sends a configuration by content. If the executor already holds an
+    /// earlier version and only some entries changed, just the changed
+    /// states, transitions, workflows and images are sent as `load_delta`.
+    /// Returns true when a delta was sent.
+    fn sync_configuration(
+        &mut self,
+        config: &Arc<QontinuiConfig>,
+        compress: bool,
+    ) -> Result<bool, String> {
+        let delta = self
+            .last_synced
+            .as_ref()
+            .and_then(|previous| diff::diff_configs(previous, config));
+
+        let total_entries = config.states.len()
+            + config.transitions.len()
+            + config.workflows.len()
+            + config.images.len();
+
+        let sent_delta = match delta {
+            // Past half the entries a full resend is as cheap and simpler
+            Some(delta) if delta.len() * 2 <= total_entries => {
+                let params = serde_json::to_value(&delta).map_err(|e| e.to_string())?;
+                self.send_command("load_delta", Some(params))?;
+                true
+            }
+            _ => {
+                let content = config.to_executor_json()?;
+                self.load_configuration_content(&content, compress)?;
+                false
+            }
+        };
+
+        self.last_synced = Some(config.clone());
+        Ok(sent_delta)
+    }
+
+    /// Kills the executor and every process it started without asking it
+    /// to stop first, for a runaway executor that holds the mouse and
+    /// keyboard. Leaves the bridge as `stop` does.
+    fn force_kill(&mut self) -> Result<(), String> {
+        self.watchdog_stop.store(true, Ordering::Relaxed);
+        let was_running = self.is_running();
+        if let Some(mut backend) = self.in_process.take() {
+            backend.stop()?;
+        }
+        if let Some(mut process) = self.process.take() {
+            let killed = process_tree::kill_descendants(process.id());
+            shutdown::kill_tree(&mut process);
+            process.wait().map_err(|e| e.to_string())?;
+            self.disconnect();
+            tracing::warn!(
+                "Executor force-killed along with {} child process(es)",
+                killed
+            );
+        }
+        *self.is_running.lock().unwrap() = false;
+        self.responses.abandon_all();
+        if was_running {
+            self.emit_lifecycle("stopped", None);
+        }
+        Ok(())
+    }
+
+    /// Id of the Python process, while there is one.
+    fn pid(&self) -> Option<u32> {
+        self.process.as_ref().map(|p| p.id())
+    }
+
+    /// Internal state for debug dumps.
+    fn snapshot(&self) -> Value {
+        json!({
+            "session_id": self.session_id,
+            "running": self.is_running(),
+            "backend": self.in_process.as_ref().map_or("python", |backend| backend.name()),
+            "pid": self.process.as_ref().map(|p| p.id()),
+            "throttle": self.throttle_config(),
+            "backpressure": self.backpressure_config(),
+            "strict_protocol": self.strict_protocol.load(Ordering::Relaxed),
+            "protocol_violations": self.protocol_violations(),
+            "handshake": self.handshake(),
+            "transport": self.transport(),
+            "launch_options": self.launch_options,
+            "awaited_responses": self.responses.len(),
+            "last_synced_config": self.last_synced.as_ref().map(|c| json!({
+                "name": c.metadata.name,
+                "version": c.version,
             })),
-        )
+        })
     }
 
-    pub fn start_execution_with_params(
-        &mut self,
-        params: Option<serde_json::Value>,
-    ) -> Result<(), String> {
-        self.send_command("start", params)
+    /// How the executor performs since it started, and how its messages
+    /// flowed to the frontend since the bridge was created.
+    fn metrics(&self) -> Value {
+        let pipeline = self.pipeline_counters.snapshot();
+        json!({
+            "performance": self.stats.snapshot(self.process.as_ref().map(|p| p.id())),
+            "dropped_events": pipeline.lost(),
+            "pipeline": pipeline,
+            "capacity": self.backpressure.lock().unwrap().capacity,
+            "protocol_violations": self.protocol_violations(),
+            "awaited_responses": self.responses.len(),
+        })
     }
 
-    pub fn stop_execution(&mut self) -> Result<(), String> {
-        self.send_command("stop", None)
+    fn session_id(&self) -> &str {
+        &self.session_id
     }
 
-    pub fn get_status(&mut self) -> Result<(), String> {
-        self.send_command("status", None)
+    /// Updates the per-class limits applied to events forwarded to the
+    /// frontend. Takes effect immediately for a running executor.
+    fn set_throttle_config(&self, config: ThrottleConfig) {
+        *self.throttle_config.lock().unwrap() = config;
     }
 
-    pub fn start_recording(&mut self, base_dir: &str) -> Result<(), String> {
-        self.send_command(
-            "start_recording",
-            Some(json!({
-                "base_dir": base_dir
-            })),
-        )
+    fn throttle_config(&self) -> ThrottleConfig {
+        self.throttle_config.lock().unwrap().clone()
     }
 
-    pub fn stop_recording(&mut self) -> Result<(), String> {
-        self.send_command("stop_recording", None)
+    /// Updates how events queued for the frontend are bounded. Takes effect
+    /// immediately for a running executor.
+    fn set_backpressure_config(&self, config: BackpressureConfig) {
+        *self.backpressure.lock().unwrap() = config;
     }
 
-    pub fn get_recording_status(&mut self) -> Result<(), String> {
-        self.send_command("recording_status", None)
+    fn backpressure_config(&self) -> BackpressureConfig {
+        self.backpressure.lock().unwrap().clone()
     }
 
-    pub fn is_running(&self) -> bool {
-        if let Some(ref _process) = self.process {
-            // Check if the process is actually still running
-            // The child process handle doesn't have a direct is_running method,
-            // so we rely on our tracking flag
-            *self.is_running.lock().unwrap()
-        } else {
-            false
+    fn pipeline_metrics(&self) -> Option<PipelineMetrics> {
+        Some(self.pipeline_counters.snapshot())
+    }
+
+    /// Enables strict protocol checking. Violations are logged with the raw
+    /// line; messages are still processed.
+    fn set_strict_protocol(&self, enabled: bool) {
+        self.strict_protocol.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Checks that the process is alive and pings it, for the watchdog.
+    /// Fails with the reason if the process has exited or can't be written
+    /// to.
+    fn probe(&mut self) -> Result<PendingCommand, String> {
+        if let Some(process) = self.process.as_mut() {
+            if let Ok(Some(status)) = process.try_wait() {
+                return Err(format!("Executor process exited ({})", status));
+            }
+        }
+        self.send_command_awaiting("ping", None)
+            .map_err(|e| format!("Executor unreachable: {}", e))
+    }
+
+    /// Gives up on a dead or hung process: kills it if needed, stops
+    /// reporting it as running and wakes anything waiting on a response.
+    fn mark_crashed(&mut self, reason: String) -> CrashReport {
+        self.watchdog_stop.store(true, Ordering::Relaxed);
+        let exit_code = self.process.take().and_then(|mut process| {
+            // Also takes down what a dead executor left running
+            shutdown::kill_tree(&mut process);
+            process.wait().ok().and_then(|status| status.code())
+        });
+        self.disconnect();
+        *self.is_running.lock().unwrap() = false;
+        self.responses.abandon_all();
+
+        CrashReport {
+            session_id: self.session_id.clone(),
+            reason,
+            exit_code,
+            stderr_tail: self.stderr_tail.lock().unwrap().iter().cloned().collect(),
+            detected_at: chrono::Local::now().to_rfc3339(),
+        }
+    }
+
+    /// Transport the running executor is connected over.
+    fn transport(&self) -> Option<TransportKind> {
+        match self.in_process.as_ref() {
+            Some(backend) => backend.transport(),
+            None => Some(*self.transport.lock().unwrap()),
+        }
+    }
+
+    fn unencrypted_remote(&self) -> bool {
+        self.in_process
+            .as_ref()
+            .is_some_and(|backend| backend.unencrypted_remote())
+    }
+
+    /// Counts of the traffic with the running executor.
+    fn stats(&self) -> Arc<BridgeStats> {
+        self.stats.clone()
+    }
+
+    /// Strict-mode violations seen since the bridge was created.
+    fn protocol_violations(&self) -> u64 {
+        self.protocol_violations.load(Ordering::Relaxed)
+    }
+}
+
+/// Splits text into pieces of at most `size` bytes on char boundaries. A
+/// char wider than `size` gets a piece of its own.
+pub fn split_chunks(text: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
         }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
     }
+    chunks
 }
 
 impl Drop for PythonBridge {
@@ -0,0 +1,563 @@
+//! Drives an executor on another machine, e.g. a dedicated test machine
+//! controlled from the runner on a laptop. `python-bridge/remote_executor.py`
+//! serves the Python executor there on a TCP port, behind TLS when it has a
+//! certificate, to runners that know its token. The runner connects, sends
+//! `{"type": "connect", "token": ...}` as its first frame and then speaks
+//! the same length-prefixed frames as over the local socket transport. The
+//! executor answers an accepted connection with its `hello`; one that's
+//! closed before that had the token refused. Without TLS only an executor
+//! on this machine is connected to, as everything would cross the network
+//! readable.
+//!
+//! A connection lost while the executor is started is retried with
+//! exponential backoff, reported as `remote_connection` events. The remote
+//! executor keeps its configuration and any run while the runner is away,
+//! so a reconnected runner carries on where it left off; commands sent in
+//! between fail.
+
+use super::backend::{ExecutorBackend, Subscriber};
+use super::handshake::Handshake;
+use super::protocol::{BridgeMessage, Frame, MessageReader};
+use super::python_bridge::{ExecutorCommand, ExecutorEvent};
+use super::shutdown::ShutdownOutcome;
+use super::transport::{self, TransportKind};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Port `remote_executor.py` listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 9876;
+
+/// How long connecting, and the executor's `hello`, may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first reconnection attempt, doubled after each failure.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Longest sleep between checks of the stop flag.
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+/// How long a TLS read holds the connection before a write can get in.
+const TLS_READ_POLL: Duration = Duration::from_millis(50);
+
+/// Largest first frame accepted from the executor.
+const MAX_HELLO_FRAME: usize = 64 * 1024;
+
+/// Where the remote executor is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteTarget {
+    /// `host:port`, or just the host for `DEFAULT_PORT`.
+    pub address: String,
+    /// Never serialized, so it doesn't leak into status replies.
+    #[serde(skip_serializing)]
+    pub token: String,
+    #[serde(default)]
+    pub tls: bool,
+    /// PEM certificates trusted besides the public roots, for an executor
+    /// with a self-signed certificate.
+    #[serde(default)]
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl RemoteTarget {
+    pub fn validate(&self) -> Result<(), String> {
+        let (host, _) = self.host_port()?;
+        if self.token.is_empty() {
+            return Err("A token is needed to connect to a remote executor".to_string());
+        }
+        // The token, configurations and secrets would cross the network
+        // readable
+        if !self.tls && !is_loopback(host) {
+            return Err(format!(
+                "Connecting to {} needs TLS; without it only an executor on this machine can be used",
+                host
+            ));
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            if !self.tls {
+                return Err("A CA certificate only applies with TLS".to_string());
+            }
+            if !ca_cert.is_file() {
+                return Err(format!("CA certificate not found: {}", ca_cert.display()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The host, without brackets around an IPv6 address, and the port.
+    fn host_port(&self) -> Result<(&str, u16), String> {
+        let address = self.address.trim();
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+                let port = port
+                    .parse()
+                    .map_err(|_| format!("Invalid port in {}", address))?;
+                (host, port)
+            }
+            _ => (address, DEFAULT_PORT),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(format!("No host in the remote address '{}'", address));
+        }
+        Ok((host, port))
+    }
+}
+
+/// Whether `host` is this machine.
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    Connected,
+    /// Lost, and being retried.
+    Reconnecting,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RemoteStatus {
+    pub state: ConnectionState,
+    pub address: Option<String>,
+    pub tls: bool,
+    pub connected_at: Option<String>,
+    /// Reconnection attempts since the connection was lost.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// The remote executor set up to connect to, and how its connection is
+/// doing.
+#[derive(Default)]
+pub struct RemoteExecutor {
+    target: Mutex<Option<RemoteTarget>>,
+    status: Arc<Mutex<RemoteStatus>>,
+}
+
+impl RemoteExecutor {
+    pub fn set_target(&self, target: RemoteTarget) {
+        *self.target.lock().unwrap() = Some(target);
+    }
+
+    pub fn clear_target(&self) {
+        *self.target.lock().unwrap() = None;
+    }
+
+    pub fn target(&self) -> Option<RemoteTarget> {
+        self.target.lock().unwrap().clone()
+    }
+
+    pub fn status(&self) -> RemoteStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// A bridge to the target, reporting to this status.
+    pub fn bridge(&self) -> Result<RemoteBridge, String> {
+        let target = self
+            .target()
+            .ok_or("No remote executor set up; connect to one first")?;
+        Ok(RemoteBridge::new(target, self.status.clone()))
+    }
+}
+
+/// The runner's end of a connection to the remote executor.
+struct Link {
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    /// Shut down to end the connection from another thread.
+    socket: TcpStream,
+    hello: ExecutorEvent,
+}
+
+/// Connects to the executor, authenticates and waits for its `hello`.
+fn connect(target: &RemoteTarget) -> Result<Link, String> {
+    let (host, port) = target.host_port()?;
+    let addresses: Vec<_> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Can't resolve {}: {}", host, e))?
+        .collect();
+    let mut last_error = format!("{} has no address", host);
+    let socket = addresses
+        .iter()
+        .find_map(
+            |address| match TcpStream::connect_timeout(address, CONNECT_TIMEOUT) {
+                Ok(socket) => Some(socket),
+                Err(e) => {
+                    last_error = e.to_string();
+                    None
+                }
+            },
+        )
+        .ok_or_else(|| format!("Can't connect to {}: {}", target.address, last_error))?;
+    socket.set_nodelay(true).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    let clone = |socket: &TcpStream| socket.try_clone().map_err(|e| e.to_string());
+    let (mut reader, mut writer): (Box<dyn Read + Send>, Box<dyn Write + Send>) = if target.tls {
+        let server_name = ServerName::try_from(host.to_string())
+            .map_err(|e| format!("Invalid host name {}: {}", host, e))?;
+        let connection = ClientConnection::new(tls_config(target)?, server_name)
+            .map_err(|e| format!("TLS setup failed: {}", e))?;
+        let stream = Arc::new(Mutex::new(StreamOwned::new(connection, clone(&socket)?)));
+        (Box::new(TlsHalf(stream.clone())), Box::new(TlsHalf(stream)))
+    } else {
+        (Box::new(clone(&socket)?), Box::new(clone(&socket)?))
+    };
+
+    let connect = json!({ "type": "connect", "token": target.token });
+    transport::write_frame(&mut writer, connect.to_string().as_bytes())
+        .map_err(|e| format!("Failed to authenticate with {}: {}", target.address, e))?;
+    // Read unbuffered, so nothing after the hello is lost to the relay
+    let hello = match transport::read_frame(&mut reader, MAX_HELLO_FRAME) {
+        Ok(Some((_, payload))) => serde_json::from_slice::<BridgeMessage>(&payload).ok(),
+        Ok(None) => {
+            return Err(format!(
+                "{} closed the connection; check the token",
+                target.address
+            ))
+        }
+        Err(e) => return Err(format!("No hello from {}: {}", target.address, e)),
+    };
+    let hello = match hello {
+        Some(BridgeMessage::Event(event)) if event.event == "hello" => event,
+        _ => return Err(format!("{} isn't a qontinui executor", target.address)),
+    };
+
+    // TLS reads give way to writes now and then; plain reads just block
+    let read_timeout = target.tls.then_some(TLS_READ_POLL);
+    socket
+        .set_read_timeout(read_timeout)
+        .map_err(|e| e.to_string())?;
+    Ok(Link {
+        reader,
+        writer,
+        socket,
+        hello,
+    })
+}
+
+fn tls_config(target: &RemoteTarget) -> Result<Arc<ClientConfig>, String> {
+    let mut roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    if let Some(path) = &target.ca_cert {
+        let certificates = CertificateDer::pem_file_iter(path)
+            .map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+        for certificate in certificates {
+            let certificate =
+                certificate.map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+            roots
+                .add(certificate)
+                .map_err(|e| format!("Invalid certificate in {}: {}", path.display(), e))?;
+        }
+    }
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+/// One side of a TLS connection shared by the reading and writing threads.
+/// The socket's read timeout makes a read let go of the connection
+/// regularly; it's retried until data or an error arrives.
+struct TlsHalf(Arc<Mutex<StreamOwned<ClientConnection, TcpStream>>>);
+
+impl Read for TlsHalf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.0.lock().unwrap().read(buf) {
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Write for TlsHalf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+/// What the relay thread shares with the bridge.
+#[derive(Clone)]
+struct Shared {
+    target: RemoteTarget,
+    status: Arc<Mutex<RemoteStatus>>,
+    subscriber: Arc<Mutex<Option<Subscriber>>>,
+    writer: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+    socket: Arc<Mutex<Option<TcpStream>>>,
+    handshake: Arc<Mutex<Handshake>>,
+    stopping: Arc<AtomicBool>,
+}
+
+impl Shared {
+    fn deliver(&self, frame: Frame) {
+        if let Frame::Message(BridgeMessage::Event(event)) = &frame {
+            self.handshake.lock().unwrap().observe(event);
+        }
+        if let Some(subscriber) = self.subscriber.lock().unwrap().as_mut() {
+            subscriber(frame);
+        }
+    }
+
+    /// Takes a new connection into use, returning where its messages
+    /// arrive.
+    fn attach(&self, link: Link) -> Box<dyn Read + Send> {
+        *self.handshake.lock().unwrap() = Handshake::Pending;
+        *self.writer.lock().unwrap() = Some(link.writer);
+        *self.socket.lock().unwrap() = Some(link.socket);
+        self.update(|status| {
+            status.state = ConnectionState::Connected;
+            status.connected_at = Some(chrono::Local::now().to_rfc3339());
+            status.attempts = 0;
+            status.last_error = None;
+        });
+        self.deliver(Frame::Message(BridgeMessage::Event(link.hello)));
+        link.reader
+    }
+
+    fn detach(&self) {
+        *self.writer.lock().unwrap() = None;
+        if let Some(socket) = self.socket.lock().unwrap().take() {
+            let _ = socket.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Changes the status and tells the subscriber.
+    fn update(&self, change: impl FnOnce(&mut RemoteStatus)) {
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            change(&mut status);
+            status.clone()
+        };
+        let event = ExecutorEvent::from_runner("remote_connection", json!(status));
+        self.deliver(Frame::Message(BridgeMessage::Event(event)));
+    }
+
+    /// Relays the executor's messages until the bridge stops, reconnecting
+    /// whenever the connection is lost.
+    fn relay(self, mut reader: Box<dyn Read + Send>) {
+        loop {
+            let mut messages = MessageReader::new(BufReader::new(reader)).length_prefixed();
+            let reason = loop {
+                match messages.next_frame() {
+                    Ok(Some(frame)) => self.deliver(frame),
+                    Ok(None) => break "the executor closed the connection".to_string(),
+                    Err(e) => break e.to_string(),
+                }
+            };
+            self.detach();
+            if self.stopping.load(Ordering::Relaxed) {
+                return;
+            }
+            tracing::warn!(
+                "Lost the remote executor at {}: {}",
+                self.target.address,
+                reason
+            );
+            match self.reconnect(reason) {
+                Some(link) => reader = self.attach(link),
+                None => return,
+            }
+        }
+    }
+
+    /// Retries the connection with exponential backoff. `None` once it
+    /// gives up or the bridge is stopped.
+    fn reconnect(&self, reason: String) -> Option<Link> {
+        self.update(|status| {
+            status.state = ConnectionState::Reconnecting;
+            status.last_error = Some(reason);
+        });
+        let mut delay = RECONNECT_DELAY;
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            let mut waited = Duration::ZERO;
+            while waited < delay {
+                if self.stopping.load(Ordering::Relaxed) {
+                    return None;
+                }
+                thread::sleep(STOP_POLL.min(delay - waited));
+                waited += STOP_POLL;
+            }
+            if self.stopping.load(Ordering::Relaxed) {
+                return None;
+            }
+            match connect(&self.target) {
+                Ok(link) => {
+                    tracing::info!(
+                        "Reconnected to the remote executor at {}",
+                        self.target.address
+                    );
+                    return Some(link);
+                }
+                Err(e) => {
+                    tracing::warn!("Reconnection attempt {} failed: {}", attempt, e);
+                    self.update(|status| {
+                        status.attempts = attempt;
+                        status.last_error = Some(e);
+                    });
+                }
+            }
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+        self.update(|status| status.state = ConnectionState::Disconnected);
+        None
+    }
+}
+
+pub struct RemoteBridge {
+    shared: Shared,
+    started: bool,
+}
+
+impl RemoteBridge {
+    pub fn new(target: RemoteTarget, status: Arc<Mutex<RemoteStatus>>) -> Self {
+        Self {
+            shared: Shared {
+                target,
+                status,
+                subscriber: Arc::default(),
+                writer: Arc::default(),
+                socket: Arc::default(),
+                handshake: Arc::default(),
+                stopping: Arc::new(AtomicBool::new(false)),
+            },
+            started: false,
+        }
+    }
+
+    pub fn status(&self) -> RemoteStatus {
+        self.shared.status.lock().unwrap().clone()
+    }
+}
+
+impl ExecutorBackend for RemoteBridge {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.handshake()
+            .hello()
+            .and_then(|hello| hello.commands.clone())
+            .unwrap_or_default()
+    }
+
+    fn subscribe(&mut self, subscriber: Subscriber) {
+        *self.shared.subscriber.lock().unwrap() = Some(subscriber);
+    }
+
+    fn start(&mut self, _executor_type: &str) -> Result<(), String> {
+        let target = &self.shared.target;
+        target.validate()?;
+        *self.shared.status.lock().unwrap() = RemoteStatus {
+            address: Some(target.address.clone()),
+            tls: target.tls,
+            ..Default::default()
+        };
+        let link = connect(target).inspect_err(|e| {
+            self.shared.status.lock().unwrap().last_error = Some(e.clone());
+        })?;
+        tracing::info!("Connected to the remote executor at {}", target.address);
+        self.shared.stopping.store(false, Ordering::Relaxed);
+        let reader = self.shared.attach(link);
+        let shared = self.shared.clone();
+        let span = tracing::info_span!("remote_executor", address = %target.address);
+        thread::spawn(move || {
+            let _span = span.entered();
+            shared.relay(reader);
+        });
+        self.started = true;
+        Ok(())
+    }
+
+    fn send(&mut self, id: &str, command: &str, params: Option<Value>) -> Result<(), String> {
+        let command = ExecutorCommand {
+            cmd_type: "command".to_string(),
+            id: id.to_string(),
+            command: command.to_string(),
+            params,
+        };
+        let json = serde_json::to_string(&command).map_err(|e| e.to_string())?;
+        let mut writer = self.shared.writer.lock().unwrap();
+        let Some(socket) = writer.as_mut() else {
+            return Err(format!(
+                "Not connected to the remote executor at {}",
+                self.shared.target.address
+            ));
+        };
+        transport::write_frame(socket, json.as_bytes())
+            .map_err(|e| format!("Failed to send command: {}", e))
+    }
+
+    fn stop(&mut self) -> Result<ShutdownOutcome, String> {
+        if !self.started {
+            return Ok(ShutdownOutcome::NotRunning);
+        }
+        self.started = false;
+        self.shared.stopping.store(true, Ordering::Relaxed);
+        self.shared.detach();
+        self.shared
+            .update(|status| status.state = ConnectionState::Disconnected);
+        Ok(ShutdownOutcome::Graceful)
+    }
+
+    /// Also while reconnecting, which commands fail during.
+    fn is_running(&self) -> bool {
+        self.started && self.status().state != ConnectionState::Disconnected
+    }
+
+    fn handshake(&self) -> Handshake {
+        self.shared.handshake.lock().unwrap().clone()
+    }
+
+    fn transport(&self) -> Option<TransportKind> {
+        Some(TransportKind::Tcp)
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn unencrypted_remote(&self) -> bool {
+        !self.shared.target.tls
+    }
+}
+
+impl Drop for RemoteBridge {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
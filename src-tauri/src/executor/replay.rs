@@ -0,0 +1,102 @@
+use super::pipeline::EventPipeline;
+use super::protocol::{BridgeMessage, Frame, MessageReader};
+use crate::tasks::TaskContext;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// Longest single sleep between cancellation checks.
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+/// Replay gaps longer than this are shortened, so an idle period in the
+/// original run doesn't stall the reproduction.
+const MAX_GAP: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplaySummary {
+    pub events: usize,
+    pub responses: usize,
+    pub invalid_lines: usize,
+    pub cancelled: bool,
+}
+
+/// Reads a captured event log: one bridge protocol message per line, as
+/// written to the executor's stdout.
+pub fn read_log(path: &Path) -> Result<Vec<Frame>, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open event log {:?}: {}", path, e))?;
+    let mut reader = MessageReader::new(BufReader::new(file));
+    let mut frames = Vec::new();
+    while let Some(frame) = reader
+        .next_frame()
+        .map_err(|e| format!("Failed to read event log {:?}: {}", path, e))?
+    {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Feeds captured frames through the event pipeline, keeping the original
+/// spacing between events divided by `speed`. A speed of zero replays
+/// without delays.
+pub fn replay(
+    frames: Vec<Frame>,
+    speed: f64,
+    pipeline: &mut EventPipeline,
+    task: &TaskContext,
+) -> ReplaySummary {
+    let mut summary = ReplaySummary::default();
+    let mut previous_timestamp: Option<f64> = None;
+    let total = frames.len();
+
+    for (index, frame) in frames.into_iter().enumerate() {
+        match &frame {
+            Frame::Message(BridgeMessage::Event(event)) => {
+                if let Some(previous) = previous_timestamp {
+                    if speed > 0.0 && event.timestamp > previous {
+                        let gap = Duration::from_secs_f64((event.timestamp - previous) / speed);
+                        if !sleep_unless_cancelled(gap.min(MAX_GAP), task) {
+                            summary.cancelled = true;
+                            return summary;
+                        }
+                    }
+                }
+                previous_timestamp = Some(event.timestamp);
+                summary.events += 1;
+            }
+            Frame::Message(BridgeMessage::Response(_)) => summary.responses += 1,
+            Frame::Invalid(_) => summary.invalid_lines += 1,
+        }
+
+        if task.is_cancelled() {
+            summary.cancelled = true;
+            return summary;
+        }
+        pipeline.handle(frame);
+
+        if index % 50 == 0 || index + 1 == total {
+            task.progress(
+                (index + 1) as f32 / total as f32,
+                format!("Replayed {} of {} messages", index + 1, total),
+            );
+        }
+    }
+
+    summary
+}
+
+fn sleep_unless_cancelled(duration: Duration, task: &TaskContext) -> bool {
+    let mut remaining = duration;
+    while !remaining.is_zero() {
+        if task.is_cancelled() {
+            return false;
+        }
+        let step = remaining.min(CANCEL_POLL);
+        thread::sleep(step);
+        remaining -= step;
+    }
+    !task.is_cancelled()
+}
@@ -0,0 +1,247 @@
+//! Automatic restart of the Python executor after the watchdog reports it
+//! dead. Restarts back off exponentially and give up after a number of
+//! attempts; an executor that stays up for a while earns a fresh budget.
+//! The restarted bridge gets the loaded configuration again, and progress
+//! is reported as `executor-restart` events.
+
+use super::backend::ExecutorBackend;
+use super::sessions::{self, DEFAULT_SESSION};
+use super::watchdog::CrashReport;
+use super::PythonBridge;
+use crate::commands::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Uptime after which an executor counts as stable again and the attempt
+/// count starts over.
+pub const STABLE_UPTIME: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    /// Restart when the process dies with an error, is killed, or hangs.
+    OnCrash,
+    /// Also restart when the process exits cleanly.
+    Always,
+}
+
+impl RestartPolicy {
+    /// Whether an executor that went away as `report` describes should be
+    /// brought back.
+    pub fn applies_to(self, report: &CrashReport) -> bool {
+        match self {
+            Self::Never => false,
+            Self::OnCrash => report.exit_code != Some(0),
+            Self::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestartSettings {
+    pub policy: RestartPolicy,
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RestartSettings {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::Never,
+            max_attempts: 5,
+            initial_delay_ms: 1000,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+impl RestartSettings {
+    /// Wait before restart `attempt` (from 1): the initial delay, doubled
+    /// for each earlier attempt, up to the maximum.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(
+            self.initial_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_delay_ms),
+        )
+    }
+}
+
+/// Restarts used up since the executor was last stable.
+#[derive(Debug, Default)]
+pub struct RestartBudget {
+    attempts: u32,
+    last_restart: Option<Instant>,
+}
+
+impl RestartBudget {
+    /// Claims the next attempt number, or `None` once `max_attempts` have
+    /// been used without the executor staying up.
+    pub fn next_attempt(&mut self, settings: &RestartSettings, now: Instant) -> Option<u32> {
+        if self
+            .last_restart
+            .is_some_and(|last| now.duration_since(last) >= STABLE_UPTIME)
+        {
+            self.attempts = 0;
+        }
+        if self.attempts >= settings.max_attempts {
+            return None;
+        }
+        self.attempts += 1;
+        self.last_restart = Some(now);
+        Some(self.attempts)
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+/// The restart settings and budget, kept in `AppState`.
+#[derive(Default)]
+pub struct Restarts {
+    pub settings: Mutex<RestartSettings>,
+    budget: Mutex<RestartBudget>,
+}
+
+impl Restarts {
+    pub fn new(settings: RestartSettings) -> Self {
+        Self {
+            settings: Mutex::new(settings),
+            budget: Default::default(),
+        }
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.budget.lock().unwrap().attempts()
+    }
+}
+
+/// Schedules a restart after the watchdog has given up on the executor,
+/// if the policy asks for one.
+pub fn handle_crash(app_handle: &AppHandle, report: &CrashReport) {
+    let state = app_handle.state::<AppState>();
+    let settings = state.restarts.settings.lock().unwrap().clone();
+    if !settings.policy.applies_to(report) {
+        return;
+    }
+    let Some(executor_type) = state.session.current().executor_type else {
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    thread::spawn(move || restart(&app_handle, &settings, &executor_type));
+}
+
+fn restart(app_handle: &AppHandle, settings: &RestartSettings, executor_type: &str) {
+    let state = app_handle.state::<AppState>();
+    loop {
+        let attempt = state
+            .restarts
+            .budget
+            .lock()
+            .unwrap()
+            .next_attempt(settings, Instant::now());
+        let Some(attempt) = attempt else {
+            warn!(
+                "Giving up restarting the executor after {} attempts",
+                settings.max_attempts
+            );
+            progress(app_handle, "gave_up", settings.max_attempts, settings, None);
+            return;
+        };
+
+        let delay = settings.delay(attempt);
+        info!(
+            "Restarting the executor in {} ms (attempt {}/{})",
+            delay.as_millis(),
+            attempt,
+            settings.max_attempts
+        );
+        progress(app_handle, "scheduled", attempt, settings, Some(delay));
+        thread::sleep(delay);
+
+        match respawn(app_handle, executor_type) {
+            Ok(true) => {
+                info!("Executor restarted (attempt {})", attempt);
+                progress(app_handle, "restarted", attempt, settings, None);
+                return;
+            }
+            // Stopped or started by the user in the meantime
+            Ok(false) => {
+                progress(app_handle, "cancelled", attempt, settings, None);
+                return;
+            }
+            Err(e) => {
+                warn!("Executor restart {} failed: {}", attempt, e);
+                let _ = sessions::emit(
+                    app_handle,
+                    "executor-restart",
+                    DEFAULT_SESSION,
+                    &json!({
+                        "status": "failed",
+                        "attempt": attempt,
+                        "max_attempts": settings.max_attempts,
+                        "error": e,
+                    }),
+                );
+            }
+        }
+    }
+}
+
+/// Starts a new bridge and sends it the loaded configuration. Returns
+/// false without doing anything if the executor was stopped or started by
+/// hand since the crash.
+fn respawn(app_handle: &AppHandle, executor_type: &str) -> Result<bool, String> {
+    let state = app_handle.state::<AppState>();
+    let mut bridge_lock = state.executor.lock().unwrap();
+    let crashed = bridge_lock
+        .as_ref()
+        .is_some_and(|bridge| !bridge.is_running());
+    if !crashed || state.session.current().executor_type.as_deref() != Some(executor_type) {
+        return Ok(false);
+    }
+
+    let mut bridge = PythonBridge::new(app_handle.clone());
+    bridge.start(executor_type)?;
+    // Bridge before config, like everywhere else
+    let config = state.current_config.lock().unwrap().clone();
+    if let Some(config) = config {
+        bridge
+            .sync_configuration(&config, false)
+            .map_err(|e| format!("Failed to reload configuration: {}", e))?;
+    }
+    *bridge_lock = Some(Box::new(bridge));
+    Ok(true)
+}
+
+fn progress(
+    app_handle: &AppHandle,
+    status: &str,
+    attempt: u32,
+    settings: &RestartSettings,
+    delay: Option<Duration>,
+) {
+    let _ = sessions::emit(
+        app_handle,
+        "executor-restart",
+        DEFAULT_SESSION,
+        &json!({
+            "status": status,
+            "attempt": attempt,
+            "max_attempts": settings.max_attempts,
+            "delay_ms": delay.map(|d| d.as_millis() as u64),
+        }),
+    );
+}
@@ -0,0 +1,503 @@
+//! Reports of finished runs, built from their recorded stream: a summary,
+//! how long each action took, the failures with their screenshots, and the
+//! timeline of state changes. A report can be written as JSON, as a single
+//! HTML page with the screenshots embedded, or as JUnit XML, where each
+//! action is a test case, for CI dashboards to pick up.
+//!
+//! Dates, durations and labels are formatted for the report's locale;
+//! JUnit XML stays in the machine format its readers expect.
+
+use super::run_stream::{Direction, StreamEntry};
+use crate::i18n::Formatter;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Html,
+    Junit,
+}
+
+impl ReportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "json" => Ok(Self::Json),
+            "html" => Ok(Self::Html),
+            "junit" => Ok(Self::Junit),
+            _ => Err(format!("Unknown report format: {}", format)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Html => "html",
+            Self::Junit => "xml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSummary {
+    pub workflow_id: Option<String>,
+    pub tags: Vec<String>,
+    /// `None` when the run never completed.
+    pub success: Option<bool>,
+    pub result: String,
+    pub started_at: String,
+    pub duration_ms: f64,
+    pub duration: String,
+    pub actions_total: usize,
+    pub actions_passed: usize,
+    pub actions_failed: usize,
+    pub text: String,
+    /// Why the run ended unsuccessfully, when the executor said.
+    pub error: Option<String>,
+}
+
+/// One run of an action.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionTiming {
+    pub action_id: String,
+    pub action_type: Option<String>,
+    /// Since the run started.
+    pub started_ms: f64,
+    /// `None` when the action never completed.
+    pub duration_ms: Option<f64>,
+    pub duration: Option<String>,
+    pub success: Option<bool>,
+    pub error: Option<String>,
+    /// Path of the screenshot taken when the action failed.
+    pub screenshot: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StateChange {
+    pub elapsed_ms: f64,
+    pub timestamp: String,
+    pub transition_id: Option<String>,
+    pub active_states: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub run_id: String,
+    pub locale: String,
+    pub summary: ReportSummary,
+    /// In the order they started.
+    pub actions: Vec<ActionTiming>,
+    pub failures: Vec<ActionTiming>,
+    pub timeline: Vec<StateChange>,
+}
+
+impl RunReport {
+    pub fn build(
+        run_id: &str,
+        entries: &[StreamEntry],
+        formatter: &Formatter,
+        locale: &str,
+    ) -> Self {
+        let text = |entry: &StreamEntry, key: &str| {
+            entry
+                .data
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let mut workflow_id = None;
+        let mut tags = Vec::new();
+        let mut success = None;
+        let mut error = None;
+        let mut actions: Vec<ActionTiming> = Vec::new();
+        // Started but not yet completed actions, by id
+        let mut running: HashMap<String, usize> = HashMap::new();
+        let mut timeline = Vec::new();
+
+        for entry in entries.iter().filter(|e| e.direction == Direction::Event) {
+            match entry.name.as_str() {
+                "execution_started" => {
+                    workflow_id = text(entry, "workflow_id");
+                    tags = entry
+                        .data
+                        .get("tags")
+                        .and_then(|tags| serde_json::from_value(tags.clone()).ok())
+                        .unwrap_or_default();
+                }
+                "action_started" => {
+                    let action_id = text(entry, "action_id").unwrap_or_default();
+                    running.insert(action_id.clone(), actions.len());
+                    actions.push(ActionTiming {
+                        action_id,
+                        action_type: text(entry, "action_type"),
+                        started_ms: entry.elapsed_ms,
+                        duration_ms: None,
+                        duration: None,
+                        success: None,
+                        error: None,
+                        screenshot: None,
+                    });
+                }
+                "action_completed" => {
+                    let action_id = text(entry, "action_id").unwrap_or_default();
+                    // Completions without a start still count, without a time
+                    let index = running.remove(&action_id).unwrap_or_else(|| {
+                        actions.push(ActionTiming {
+                            action_id: action_id.clone(),
+                            action_type: None,
+                            started_ms: entry.elapsed_ms,
+                            duration_ms: None,
+                            duration: None,
+                            success: None,
+                            error: None,
+                            screenshot: None,
+                        });
+                        actions.len() - 1
+                    });
+                    let action = &mut actions[index];
+                    if action.duration_ms.is_none() && action.started_ms < entry.elapsed_ms {
+                        let elapsed = entry.elapsed_ms - action.started_ms;
+                        action.duration_ms = Some(elapsed);
+                        action.duration = Some(formatter.duration(millis(elapsed)));
+                    }
+                    action.success =
+                        Some(entry.data.get("success").and_then(Value::as_bool) == Some(true));
+                    action.error = text(entry, "error");
+                    action.screenshot = text(entry, "screenshot");
+                }
+                "state_changed" => timeline.push(StateChange {
+                    elapsed_ms: entry.elapsed_ms,
+                    timestamp: local_timestamp(formatter, &entry.timestamp),
+                    transition_id: text(entry, "transition_id"),
+                    active_states: entry
+                        .data
+                        .get("active_states")
+                        .and_then(|states| serde_json::from_value(states.clone()).ok())
+                        .unwrap_or_default(),
+                }),
+                "execution_completed" => {
+                    success =
+                        Some(entry.data.get("success").and_then(Value::as_bool) == Some(true));
+                    error = text(entry, "error").or_else(|| text(entry, "reason"));
+                }
+                _ => {}
+            }
+        }
+
+        let failures: Vec<ActionTiming> = actions
+            .iter()
+            .filter(|action| action.success == Some(false))
+            .cloned()
+            .collect();
+        let passed = actions
+            .iter()
+            .filter(|action| action.success == Some(true))
+            .count();
+        let duration_ms = entries.last().map_or(0.0, |entry| entry.elapsed_ms);
+        let summary = ReportSummary {
+            workflow_id,
+            tags,
+            success,
+            result: formatter.text(
+                match success {
+                    Some(true) => "report.passed",
+                    Some(false) => "report.failed",
+                    None => "report.incomplete",
+                },
+                &[],
+            ),
+            started_at: entries
+                .first()
+                .map(|entry| local_timestamp(formatter, &entry.timestamp))
+                .unwrap_or_default(),
+            duration_ms,
+            duration: formatter.duration(millis(duration_ms)),
+            actions_total: actions.len(),
+            actions_passed: passed,
+            actions_failed: failures.len(),
+            text: formatter.text(
+                "report.actions_passed",
+                &[
+                    ("passed", passed.to_string()),
+                    ("total", actions.len().to_string()),
+                ],
+            ),
+            error,
+        };
+        RunReport {
+            run_id: run_id.to_string(),
+            locale: locale.to_string(),
+            summary,
+            actions,
+            failures,
+            timeline,
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat, formatter: &Formatter) -> Result<String, String> {
+        match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            ReportFormat::Html => Ok(self.to_html(formatter)),
+            ReportFormat::Junit => Ok(self.to_junit()),
+        }
+    }
+
+    /// One page with nothing to fetch: styles inline, screenshots embedded.
+    pub fn to_html(&self, formatter: &Formatter) -> String {
+        let label = |key: &str| escape(&formatter.text(key, &[]));
+        let summary = &self.summary;
+        let mut html = String::new();
+        let _ = writeln!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{} {}</title>\n<style>{}</style>\n</head>\n<body>",
+            escape(&self.locale),
+            label("report.title"),
+            escape(&self.run_id),
+            STYLE
+        );
+        let _ = writeln!(
+            html,
+            "<h1>{} {}</h1>\n<p class=\"{}\"><strong>{}</strong> — {}</p>\n<dl>",
+            label("report.title"),
+            escape(&self.run_id),
+            result_class(summary.success),
+            escape(&summary.result),
+            escape(&summary.text)
+        );
+        if let Some(workflow_id) = &summary.workflow_id {
+            let _ = writeln!(html, "<dt>Workflow</dt><dd>{}</dd>", escape(workflow_id));
+        }
+        let _ = writeln!(
+            html,
+            "<dt>{}</dt><dd>{}</dd>\n<dt>{}</dt><dd>{}</dd>",
+            label("report.started_at"),
+            escape(&summary.started_at),
+            label("report.duration"),
+            escape(&summary.duration)
+        );
+        if !summary.tags.is_empty() {
+            let _ = writeln!(
+                html,
+                "<dt>Tags</dt><dd>{}</dd>",
+                escape(&summary.tags.join(", "))
+            );
+        }
+        if let Some(error) = &summary.error {
+            let _ = writeln!(
+                html,
+                "<dt>{}</dt><dd>{}</dd>",
+                label("report.error"),
+                escape(error)
+            );
+        }
+        html.push_str("</dl>\n");
+
+        let _ = writeln!(
+            html,
+            "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th></th><th>{}</th><th>{}</th><th>{}</th></tr>",
+            label("report.actions"),
+            label("report.action"),
+            label("report.duration"),
+            label("report.result"),
+            label("report.error")
+        );
+        for action in &self.actions {
+            let result = match action.success {
+                Some(true) => "report.passed",
+                Some(false) => "report.failed",
+                None => "report.incomplete",
+            };
+            let _ = writeln!(
+                html,
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                result_class(action.success),
+                escape(&action.action_id),
+                escape(action.action_type.as_deref().unwrap_or_default()),
+                escape(action.duration.as_deref().unwrap_or_default()),
+                label(result),
+                escape(action.error.as_deref().unwrap_or_default())
+            );
+        }
+        html.push_str("</table>\n");
+
+        let _ = writeln!(html, "<h2>{}</h2>", label("report.failures"));
+        if self.failures.is_empty() {
+            let _ = writeln!(html, "<p>{}</p>", label("report.no_failures"));
+        }
+        for failure in &self.failures {
+            let _ = writeln!(
+                html,
+                "<section class=\"failure\">\n<h3>{}</h3>\n<p>{}</p>",
+                escape(&failure.action_id),
+                escape(failure.error.as_deref().unwrap_or_default())
+            );
+            if let Some(path) = &failure.screenshot {
+                match std::fs::read(path) {
+                    Ok(png) => {
+                        let _ = writeln!(
+                            html,
+                            "<img alt=\"{}\" src=\"data:image/png;base64,{}\">",
+                            escape(path),
+                            STANDARD.encode(png)
+                        );
+                    }
+                    // Artifacts may have been cleaned up since
+                    Err(_) => {
+                        let _ = writeln!(html, "<p class=\"missing\">{}</p>", escape(path));
+                    }
+                }
+            }
+            html.push_str("</section>\n");
+        }
+
+        let _ = writeln!(
+            html,
+            "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th></tr>",
+            label("report.timeline"),
+            label("report.started_at"),
+            label("report.transition"),
+            label("report.active_states")
+        );
+        for change in &self.timeline {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&change.timestamp),
+                escape(change.transition_id.as_deref().unwrap_or_default()),
+                escape(&change.active_states.join(", "))
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// One test suite for the run, with a test case per action.
+    pub fn to_junit(&self) -> String {
+        let summary = &self.summary;
+        let name = summary.workflow_id.as_deref().unwrap_or(&self.run_id);
+        let unfinished = self
+            .actions
+            .iter()
+            .filter(|action| action.success.is_none())
+            .count();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites>\n  <testsuite name=\"{}\" id=\"{}\" tests=\"{}\" failures=\"{}\" \
+             errors=\"{}\" time=\"{:.3}\">",
+            escape(name),
+            escape(&self.run_id),
+            self.actions.len(),
+            summary.actions_failed,
+            unfinished,
+            summary.duration_ms / 1000.0
+        );
+        for action in &self.actions {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape(name),
+                escape(&action_name(action)),
+                action.duration_ms.unwrap_or(0.0) / 1000.0
+            );
+            match action.success {
+                Some(true) => xml.push_str("/>\n"),
+                Some(false) => {
+                    let message = action.error.as_deref().unwrap_or("Action failed");
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>",
+                        escape(message),
+                        escape(message)
+                    );
+                    if let Some(path) = &action.screenshot {
+                        // The attachment convention CI servers look for
+                        let _ = writeln!(
+                            xml,
+                            "      <system-out>[[ATTACHMENT|{}]]</system-out>",
+                            escape(path)
+                        );
+                    }
+                    xml.push_str("    </testcase>\n");
+                }
+                None => xml.push_str(
+                    ">\n      <error message=\"The action never completed\"/>\n    </testcase>\n",
+                ),
+            }
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Writes the report to `dest`.
+    pub fn write(
+        &self,
+        format: ReportFormat,
+        formatter: &Formatter,
+        dest: &Path,
+    ) -> Result<(), String> {
+        let content = self.render(format, formatter)?;
+        if let Some(dir) = dest.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::write(dest, content).map_err(|e| format!("Failed to write {:?}: {}", dest, e))
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+dt{font-weight:bold}.passed{color:#1a7f37}.failed{color:#cf222e}\
+.incomplete{color:#9a6700}.failure img{max-width:100%;border:1px solid #ccc}\
+.missing{font-style:italic}";
+
+fn action_name(action: &ActionTiming) -> String {
+    match &action.action_type {
+        Some(action_type) => format!("{} ({})", action.action_id, action_type),
+        None => action.action_id.clone(),
+    }
+}
+
+fn result_class(success: Option<bool>) -> &'static str {
+    match success {
+        Some(true) => "passed",
+        Some(false) => "failed",
+        None => "incomplete",
+    }
+}
+
+fn millis(millis: f64) -> Duration {
+    Duration::from_secs_f64(millis.max(0.0) / 1000.0)
+}
+
+/// An entry's RFC 3339 timestamp in local time, formatted for the locale.
+fn local_timestamp(formatter: &Formatter, timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| formatter.timestamp(&time.with_timezone(&Local)))
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Escapes text for HTML and XML, in content and attributes alike.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,485 @@
+//! The full message stream of each run — commands sent, responses and
+//! events received — kept in one JSONL file per run so it can be exported
+//! for support or analysis. A run starts with a `start` command and ends
+//! with `execution_completed`; responses to commands sent during the run
+//! are still added after that, until the next run starts. Each executor
+//! session records its own runs, side by side.
+//!
+//! Entries are normalized so they line up in external tools: every entry
+//! carries an RFC 3339 UTC timestamp and milliseconds since the run
+//! started, and responses carry the name of the command they answer.
+//!
+//! The run's events are also written, exactly as the frontend receives
+//! them, to a log file of the same name under the log directory, for
+//! attaching to bug reports. Artifacts of the run go to a folder next to
+//! it.
+
+use super::protocol::BridgeMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Heartbeats would drown out everything else.
+const UNRECORDED_COMMANDS: &[&str] = &["ping"];
+
+/// Command params never written to a stream.
+const REDACTED_PARAMS: &[(&str, &str)] = &[("secret_value", "value")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Command,
+    Response,
+    Event,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamEntry {
+    /// Position in the run, from 0.
+    pub index: u64,
+    pub timestamp: String,
+    pub elapsed_ms: f64,
+    pub direction: Direction,
+    /// Command or event name; for responses, the command answered.
+    pub name: String,
+    /// Command id, shared by a command and its response.
+    pub correlation_id: Option<String>,
+    /// Executor sequence number; `None` for commands, responses and
+    /// events raised by the runner.
+    pub sequence: Option<u32>,
+    pub success: Option<bool>,
+    pub data: Value,
+}
+
+impl StreamEntry {
+    fn new(direction: Direction, name: &str, data: Value) -> Self {
+        Self {
+            index: 0,
+            timestamp: String::new(),
+            elapsed_ms: 0.0,
+            direction,
+            name: name.to_string(),
+            correlation_id: None,
+            sequence: None,
+            success: None,
+            data,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "jsonl" => Ok(Self::Jsonl),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("Unknown export format: {}", format)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// A recorded run, for listing.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamInfo {
+    pub run_id: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+struct ActiveStream {
+    run_id: String,
+    file: File,
+    event_log: Option<File>,
+    started: f64,
+    next_index: u64,
+    completed: bool,
+    /// Command names by id, until their response arrives.
+    pending: HashMap<String, String>,
+}
+
+/// Records run streams into a directory, normally the active profile's
+/// `history/streams`, and event logs into `log_dir`.
+pub struct RunStreams {
+    dir: Mutex<PathBuf>,
+    log_dir: PathBuf,
+    /// The latest run of each session, by session id.
+    active: Mutex<HashMap<String, ActiveStream>>,
+}
+
+impl RunStreams {
+    pub fn new(dir: PathBuf, log_dir: PathBuf) -> Self {
+        Self {
+            dir: Mutex::new(dir),
+            log_dir,
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Where the following runs are recorded, e.g. after a profile switch.
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.lock().unwrap() = dir;
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.dir.lock().unwrap().clone()
+    }
+
+    /// The session's run being recorded, if any.
+    pub fn current_run(&self, session_id: &str) -> Option<String> {
+        self.active
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .filter(|stream| !stream.completed)
+            .map(|stream| stream.run_id.clone())
+    }
+
+    /// The session's run being recorded or, once it has completed, its
+    /// last one until the next starts.
+    pub fn latest_run(&self, session_id: &str) -> Option<String> {
+        self.active
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map(|stream| stream.run_id.clone())
+    }
+
+    pub fn record_command(
+        &self,
+        session_id: &str,
+        id: &str,
+        command: &str,
+        params: Option<&Value>,
+    ) {
+        self.record_command_at(session_id, id, command, params, now());
+    }
+
+    /// Records a command sent to the session at `time` (Unix seconds).
+    /// `start` begins a new run.
+    pub fn record_command_at(
+        &self,
+        session_id: &str,
+        id: &str,
+        command: &str,
+        params: Option<&Value>,
+        time: f64,
+    ) {
+        let mut active = self.active.lock().unwrap();
+        if command == "start" {
+            match self.begin(time) {
+                Some(stream) => active.insert(session_id.to_string(), stream),
+                None => active.remove(session_id),
+            };
+        }
+        let Some(stream) = active
+            .get_mut(session_id)
+            .filter(|stream| !stream.completed)
+        else {
+            return;
+        };
+        if UNRECORDED_COMMANDS.contains(&command) {
+            return;
+        }
+
+        stream.pending.insert(id.to_string(), command.to_string());
+        let mut data = params.cloned().unwrap_or(Value::Null);
+        for (redacted_command, key) in REDACTED_PARAMS {
+            if command == *redacted_command {
+                if let Some(value) = data.get_mut(*key) {
+                    *value = Value::String("[redacted]".to_string());
+                }
+            }
+        }
+        stream.write(
+            time,
+            StreamEntry {
+                correlation_id: Some(id.to_string()),
+                ..StreamEntry::new(Direction::Command, command, data)
+            },
+        );
+    }
+
+    pub fn record_message(&self, session_id: &str, message: &BridgeMessage) {
+        self.record_message_at(session_id, message, now());
+    }
+
+    /// Records a message received from the session at `time` (Unix
+    /// seconds). Events use their own timestamp.
+    pub fn record_message_at(&self, session_id: &str, message: &BridgeMessage, time: f64) {
+        let mut active = self.active.lock().unwrap();
+        let Some(stream) = active.get_mut(session_id) else {
+            return;
+        };
+        match message {
+            BridgeMessage::Response(response) => {
+                let Some(command) = stream.pending.remove(&response.id) else {
+                    return;
+                };
+                let data = serde_json::json!({
+                    "data": response.data,
+                    "error": response.error,
+                });
+                stream.write(
+                    time,
+                    StreamEntry {
+                        correlation_id: Some(response.id.clone()),
+                        success: Some(response.success),
+                        ..StreamEntry::new(Direction::Response, &command, data)
+                    },
+                );
+            }
+            BridgeMessage::Event(event) if !stream.completed => {
+                stream.log_event(message);
+                stream.write(
+                    event.timestamp,
+                    StreamEntry {
+                        sequence: (event.sequence > 0).then_some(event.sequence),
+                        success: event.data.get("success").and_then(Value::as_bool),
+                        ..StreamEntry::new(Direction::Event, &event.event, event.data.clone())
+                    },
+                );
+                if event.event == "execution_completed" {
+                    stream.completed = true;
+                }
+            }
+            BridgeMessage::Event(_) => {}
+        }
+    }
+
+    /// Recorded runs, newest first.
+    pub fn list(&self) -> Vec<StreamInfo> {
+        let dir = self.dir();
+        let mut streams: Vec<StreamInfo> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let path = entry.path();
+                if path.extension()? != "jsonl" {
+                    return None;
+                }
+                Some(StreamInfo {
+                    run_id: path.file_stem()?.to_string_lossy().into_owned(),
+                    size_bytes: entry.metadata().ok()?.len(),
+                    path,
+                })
+            })
+            .collect();
+        // Run ids start with their start time
+        streams.sort_by(|a, b| b.run_id.cmp(&a.run_id));
+        streams
+    }
+
+    /// The entries of a recorded run, in order.
+    pub fn read(&self, run_id: &str) -> Result<Vec<StreamEntry>, String> {
+        let path = self.path(run_id)?;
+        let file = File::open(&path).map_err(|_| format!("Run '{}' not found", run_id))?;
+        let mut entries = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                // A crash can leave half a line at the end
+                Err(e) => warn!("Skipping line {} of {:?}: {}", number + 1, path, e),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Writes a run's stream to `dest` and returns the number of entries.
+    pub fn export(&self, run_id: &str, format: ExportFormat, dest: &Path) -> Result<usize, String> {
+        let entries = self.read(run_id)?;
+        let content = match format {
+            ExportFormat::Jsonl => entries
+                .iter()
+                .map(|entry| serde_json::to_string(entry).map(|line| line + "\n"))
+                .collect::<Result<String, _>>()
+                .map_err(|e| e.to_string())?,
+            ExportFormat::Csv => to_csv(&entries),
+        };
+        if let Some(dir) = dest.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::write(dest, content).map_err(|e| format!("Failed to write {:?}: {}", dest, e))?;
+        Ok(entries.len())
+    }
+
+    /// Copies a run's event log to `dest` and returns its size in bytes.
+    pub fn export_log(&self, run_id: &str, dest: &Path) -> Result<u64, String> {
+        check_run_id(run_id)?;
+        let path = self.log_dir.join(format!("{}.jsonl", run_id));
+        if !path.exists() {
+            return Err(format!("No event log for run '{}'", run_id));
+        }
+        if let Some(dir) = dest.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::copy(&path, dest).map_err(|e| format!("Failed to write {:?}: {}", dest, e))
+    }
+
+    /// Where files collected during a run, like failure screenshots, are
+    /// kept: a folder next to its event log.
+    pub fn artifacts_dir(&self, run_id: &str) -> Result<PathBuf, String> {
+        check_run_id(run_id)?;
+        Ok(self.log_dir.join(format!("{}-artifacts", run_id)))
+    }
+
+    fn path(&self, run_id: &str) -> Result<PathBuf, String> {
+        check_run_id(run_id)?;
+        Ok(self.dir().join(format!("{}.jsonl", run_id)))
+    }
+
+    fn begin(&self, time: f64) -> Option<ActiveStream> {
+        let started_at = timestamp(time);
+        let run_id = format!(
+            "{}-{}",
+            started_at.format("%Y%m%d-%H%M%S"),
+            &uuid::Uuid::new_v4().to_simple().to_string()[..8]
+        );
+        let dir = self.dir();
+        let opened = std::fs::create_dir_all(&dir)
+            .and_then(|_| File::create(dir.join(format!("{}.jsonl", run_id))));
+        match opened {
+            Ok(file) => Some(ActiveStream {
+                event_log: self.open_event_log(&run_id),
+                run_id,
+                file,
+                started: time,
+                next_index: 0,
+                completed: false,
+                pending: HashMap::new(),
+            }),
+            Err(e) => {
+                warn!("Not recording run {}: {}", run_id, e);
+                None
+            }
+        }
+    }
+
+    fn open_event_log(&self, run_id: &str) -> Option<File> {
+        let opened = std::fs::create_dir_all(&self.log_dir)
+            .and_then(|_| File::create(self.log_dir.join(format!("{}.jsonl", run_id))));
+        match opened {
+            Ok(file) => Some(file),
+            Err(e) => {
+                warn!("Not logging events of run {}: {}", run_id, e);
+                None
+            }
+        }
+    }
+}
+
+impl ActiveStream {
+    /// Appends `entry`, numbering and stamping it with `time`.
+    fn write(&mut self, time: f64, mut entry: StreamEntry) {
+        entry.index = self.next_index;
+        entry.timestamp = timestamp(time).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        entry.elapsed_ms = ((time - self.started) * 1000.0).max(0.0).round();
+        self.next_index += 1;
+        let written = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(self.file, "{}", line).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!(
+                "Failed to record {} in run {}: {}",
+                entry.name, self.run_id, e
+            );
+        }
+    }
+
+    fn log_event(&mut self, message: &BridgeMessage) {
+        let Some(file) = self.event_log.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_string(message)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(file, "{}", line).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to log event in run {}: {}", self.run_id, e);
+        }
+    }
+}
+
+/// Run ids become file names, so only ids this module generates are
+/// accepted.
+fn check_run_id(run_id: &str) -> Result<(), String> {
+    if run_id.is_empty()
+        || !run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Invalid run id '{}'", run_id));
+    }
+    Ok(())
+}
+
+fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
+
+fn timestamp(time: f64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis((time * 1000.0).round() as i64).unwrap_or_default()
+}
+
+/// One row per entry; `data` is kept as a JSON string.
+fn to_csv(entries: &[StreamEntry]) -> String {
+    let mut csv = String::from(
+        "index,timestamp,elapsed_ms,direction,name,correlation_id,sequence,success,data\r\n",
+    );
+    for entry in entries {
+        let direction = match entry.direction {
+            Direction::Command => "command",
+            Direction::Response => "response",
+            Direction::Event => "event",
+        };
+        let fields = [
+            entry.index.to_string(),
+            entry.timestamp.clone(),
+            entry.elapsed_ms.to_string(),
+            direction.to_string(),
+            entry.name.clone(),
+            entry.correlation_id.clone().unwrap_or_default(),
+            entry.sequence.map(|s| s.to_string()).unwrap_or_default(),
+            entry.success.map(|s| s.to_string()).unwrap_or_default(),
+            entry.data.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,181 @@
+//! Safe mode: pointer input confined to one window or screen region, so a
+//! real-mode run can't click into the user's other work. The bounds are
+//! resolved when a run starts and sent to the executor with `start`, which
+//! refuses targets outside them. The executor reports every pointer target
+//! in an `input_target` event, and the runner checks those too and stops
+//! the run at the first one outside the bounds. Each session's run keeps
+//! its own bounds, and a bounded run only starts on an executor whose
+//! `hello` says it enforces them.
+
+use super::protocol::BridgeMessage;
+use super::python_bridge::ExecutorEvent;
+use super::sessions;
+use crate::accessibility::{self, ElementQuery};
+use crate::capture::Region;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafeMode {
+    pub enabled: bool,
+    /// Title (or part of it) of the window to stay inside. Its current
+    /// bounds are looked up each time a run starts.
+    pub window: Option<String>,
+    /// Screen region in physical pixels, used when no window is set.
+    pub region: Option<Region>,
+}
+
+impl SafeMode {
+    /// The rectangle input has to stay inside for the next run, or `None`
+    /// when safe mode is off. Fails if the window can't be found, since
+    /// running unbounded is what safe mode is there to prevent.
+    pub fn resolve(&self) -> Result<Option<Region>, String> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let bounds = match (&self.window, self.region) {
+            (Some(title), _) => window_bounds(title)?,
+            (None, Some(region)) => region,
+            (None, None) => {
+                return Err("Safe mode needs a window or a region".to_string());
+            }
+        };
+        if bounds.width == 0 || bounds.height == 0 {
+            return Err("Safe mode bounds are empty".to_string());
+        }
+        Ok(Some(bounds))
+    }
+}
+
+/// Bounds each session's latest run was started with, for its event hooks
+/// to pick up when the run starts.
+#[derive(Debug, Default)]
+pub struct RunBounds(Mutex<HashMap<String, Region>>);
+
+impl RunBounds {
+    pub fn set(&self, session_id: &str, bounds: Option<Region>) {
+        let mut sessions = self.0.lock().unwrap();
+        match bounds {
+            Some(bounds) => sessions.insert(session_id.to_string(), bounds),
+            None => sessions.remove(session_id),
+        };
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<Region> {
+        self.0.lock().unwrap().get(session_id).copied()
+    }
+}
+
+fn window_bounds(title: &str) -> Result<Region, String> {
+    // Windows are visited before their children, so the first match
+    // without role or name filters is the window itself
+    let query = ElementQuery {
+        window: Some(title.to_string()),
+        max_results: Some(1),
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let window = accessibility::query_elements(&query)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Safe mode window '{}' not found", title))?;
+    let bounds = window
+        .bounds
+        .ok_or_else(|| format!("Safe mode window '{}' has no bounds", title))?;
+    Ok(Region {
+        x: bounds.x,
+        y: bounds.y,
+        width: bounds.width.max(0) as u32,
+        height: bounds.height.max(0) as u32,
+    })
+}
+
+pub fn contains(bounds: &Region, x: f64, y: f64) -> bool {
+    x >= bounds.x as f64
+        && y >= bounds.y as f64
+        && x < bounds.x as f64 + bounds.width as f64
+        && y < bounds.y as f64 + bounds.height as f64
+}
+
+/// Checks the points of an `input_target` event (`{"points": [[x, y],
+/// ...]}`) and describes the first one outside `bounds`. A target without
+/// points counts as outside, since it can't be checked.
+pub fn check_target(bounds: &Region, data: &Value) -> Result<(), String> {
+    let points = data
+        .get("points")
+        .and_then(Value::as_array)
+        .filter(|points| !points.is_empty())
+        .ok_or("Input target without coordinates")?;
+    for point in points {
+        let x = point.get(0).and_then(Value::as_f64);
+        let y = point.get(1).and_then(Value::as_f64);
+        let (Some(x), Some(y)) = (x, y) else {
+            return Err(format!("Invalid input target {}", point));
+        };
+        if !contains(bounds, x, y) {
+            return Err(format!(
+                "Input at ({}, {}) is outside the safe area {}x{} at ({}, {})",
+                x, y, bounds.width, bounds.height, bounds.x, bounds.y
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Picks up the run's bounds when it starts and checks each reported
+/// input target against them. A target outside stops the execution and is
+/// reported as a `safe_mode_violation` event.
+pub fn handle_event(
+    app_handle: &AppHandle,
+    session_id: &str,
+    bounds: &mut Option<Region>,
+    event: &ExecutorEvent,
+) {
+    match event.event.as_str() {
+        "execution_started" => {
+            let state = app_handle.state::<crate::commands::AppState>();
+            *bounds = state.safe_bounds.get(session_id);
+        }
+        "execution_completed" => *bounds = None,
+        "input_target" => {
+            let Some(active) = bounds.as_ref() else {
+                return;
+            };
+            if let Err(reason) = check_target(active, &event.data) {
+                // Only the first violation stops the run
+                *bounds = None;
+                stop_execution(app_handle, session_id, event, reason);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn stop_execution(app_handle: &AppHandle, session_id: &str, event: &ExecutorEvent, reason: String) {
+    warn!("Stopping execution: {}", reason);
+    let notice = BridgeMessage::Event(ExecutorEvent::from_runner(
+        "safe_mode_violation",
+        json!({
+            "reason": reason,
+            "action_id": event.data.get("action_id"),
+            "points": event.data.get("points"),
+        }),
+    ));
+    let _ = sessions::emit(app_handle, "executor-event", session_id, &notice);
+
+    // Keep the reader thread off the bridge lock
+    let app_handle = app_handle.clone();
+    let session_id = session_id.to_string();
+    std::thread::spawn(move || {
+        let state = app_handle.state::<crate::commands::AppState>();
+        if let Err(e) = sessions::with_bridge(&state, &session_id, |bridge| bridge.stop_execution())
+        {
+            warn!("Failed to stop execution after safe mode violation: {}", e);
+        }
+    });
+}
@@ -0,0 +1,461 @@
+//! Screenshot-mode execution without Python. A configuration whose execution
+//! mode is `screenshot` runs against the PNGs of its screenshot directory
+//! rather than the screen, so it can be smoke-tested on a machine with no
+//! Python at all: executor type `"screenshot"` runs it in-process, and a
+//! screenshot-mode configuration falls back to it when no Python bridge can
+//! be found. Workflows are walked as the Python executor walks them; image
+//! actions search every screenshot, starting from the one the last image was
+//! found in, and `VANISH` succeeds when its image is nowhere. There's nothing
+//! to click or type on, so other actions succeed without doing anything.
+//!
+//! The run uses the runner's loaded configuration rather than the copy sent
+//! with `load`.
+
+use super::backend::{ExecutorBackend, Outbox, Subscriber};
+use super::shutdown::ShutdownOutcome;
+use crate::commands::AppState;
+use crate::config::types::Action;
+use crate::config::QontinuiConfig;
+use crate::matching::{test_template, GrayImage, Match};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tauri::{AppHandle, Manager};
+
+/// Commands the native executor answers.
+pub const COMMANDS: &[&str] = &[
+    "load",
+    "load_chunk",
+    "load_delta",
+    "ping",
+    "start",
+    "status",
+    "stop",
+];
+
+/// Threshold of image actions that set none, when the configuration's
+/// recognition settings have no `defaultThreshold` either.
+const DEFAULT_THRESHOLD: f32 = 0.9;
+
+/// Nested workflows deeper than this fail rather than run.
+const MAX_DEPTH: usize = 16;
+
+/// The screenshots of a directory, decoded when first searched.
+pub struct Screenshots {
+    files: Vec<PathBuf>,
+    decoded: Vec<Option<GrayImage>>,
+    /// Screenshot the last image was found in, searched first.
+    current: usize,
+}
+
+/// Where an image was found, or how close it came.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sighting {
+    /// File name of the screenshot it was found in.
+    pub screenshot: Option<String>,
+    pub found: Option<Match>,
+    /// The best score in any screenshot, even below the threshold.
+    pub best_score: Option<f32>,
+}
+
+impl Screenshots {
+    /// The PNGs in `dir`, in file name order.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read screenshot directory {:?}: {}", dir, e))?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+            })
+            .collect();
+        if files.is_empty() {
+            return Err(format!("No PNG screenshots in {:?}", dir));
+        }
+        files.sort();
+        Ok(Self {
+            decoded: vec![None; files.len()],
+            files,
+            current: 0,
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Searches the screenshots for `needle`, the current one first.
+    /// Screenshots smaller than the needle are passed over.
+    pub fn find(&mut self, needle: &GrayImage, threshold: f32) -> Result<Sighting, String> {
+        let mut best_score: Option<f32> = None;
+        for offset in 0..self.files.len() {
+            let index = (self.current + offset) % self.files.len();
+            let Ok(test) = test_template(self.screenshot(index)?, needle, threshold, 1) else {
+                continue;
+            };
+            if let Some(score) = test.best_score {
+                best_score = Some(best_score.map_or(score, |best| best.max(score)));
+            }
+            if let Some(found) = test.matches.into_iter().next() {
+                self.current = index;
+                return Ok(Sighting {
+                    screenshot: Some(self.name(index)),
+                    found: Some(found),
+                    best_score,
+                });
+            }
+        }
+        Ok(Sighting {
+            screenshot: None,
+            found: None,
+            best_score,
+        })
+    }
+
+    fn screenshot(&mut self, index: usize) -> Result<&GrayImage, String> {
+        if self.decoded[index].is_none() {
+            let path = &self.files[index];
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("Failed to read screenshot {:?}: {}", path, e))?;
+            let image = GrayImage::decode_png(&bytes)
+                .map_err(|e| format!("Screenshot {:?}: {}", path, e))?;
+            self.decoded[index] = Some(image);
+        }
+        Ok(self.decoded[index].as_ref().unwrap())
+    }
+
+    fn name(&self, index: usize) -> String {
+        self.files[index]
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// The screenshot directory of `config`, relative paths taken from the
+/// configuration's directory.
+pub fn screenshot_directory(config: &QontinuiConfig) -> Result<PathBuf, String> {
+    let dir = config
+        .get_screenshot_directory()
+        .filter(|dir| !dir.trim().is_empty())
+        .ok_or("The configuration has no screenshot directory")?;
+    Ok(config.image_store.resolve_path(Path::new(&dir)))
+}
+
+/// One execution of a workflow over screenshots, reporting what it does
+/// with the events the Python executor sends.
+pub struct Run<'a> {
+    config: &'a QontinuiConfig,
+    screenshots: &'a mut Screenshots,
+    stop: &'a AtomicBool,
+    continue_on_failure: bool,
+    templates: HashMap<String, GrayImage>,
+    emit: &'a mut dyn FnMut(&str, Value),
+}
+
+impl<'a> Run<'a> {
+    pub fn new(
+        config: &'a QontinuiConfig,
+        screenshots: &'a mut Screenshots,
+        stop: &'a AtomicBool,
+        emit: &'a mut dyn FnMut(&str, Value),
+    ) -> Self {
+        let continue_on_failure = config
+            .settings
+            .as_ref()
+            .and_then(|s| s.execution.as_ref())
+            .and_then(|e| e.failure_strategy.as_deref())
+            == Some("continue");
+        Self {
+            config,
+            screenshots,
+            stop,
+            continue_on_failure,
+            templates: HashMap::new(),
+            emit,
+        }
+    }
+
+    /// Overrides the configuration's failure strategy.
+    pub fn continue_on_failure(mut self, continue_on_failure: bool) -> Self {
+        self.continue_on_failure = continue_on_failure;
+        self
+    }
+
+    /// Runs `workflow_id` from `execution_started` to `execution_completed`.
+    pub fn execute(&mut self, workflow_id: &str) -> bool {
+        (self.emit)(
+            "execution_started",
+            json!({ "workflow_id": workflow_id, "tags": [] }),
+        );
+        self.log(
+            "info",
+            format!(
+                "Running in screenshot mode over {} screenshots",
+                self.screenshots.count()
+            ),
+        );
+        let success = self.workflow(workflow_id, &mut Vec::new());
+        if self.stop.load(Ordering::Relaxed) {
+            (self.emit)(
+                "execution_completed",
+                json!({ "success": false, "reason": "User stopped" }),
+            );
+            return false;
+        }
+        (self.emit)(
+            "execution_completed",
+            json!({ "success": success, "workflow_id": workflow_id }),
+        );
+        success
+    }
+
+    fn workflow(&mut self, workflow_id: &str, stack: &mut Vec<String>) -> bool {
+        let config = self.config;
+        let Some(workflow) = config.workflows.iter().find(|w| w.id == workflow_id) else {
+            self.log("error", format!("Workflow {} not found", workflow_id));
+            return false;
+        };
+        (self.emit)(
+            "workflow_started",
+            json!({ "workflow_id": workflow_id, "workflow_name": workflow.name }),
+        );
+        stack.push(workflow_id.to_string());
+        let mut success = true;
+        for action in &workflow.actions {
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if !self.action(action, stack) {
+                success = false;
+                if !self.continue_on_failure {
+                    break;
+                }
+            }
+        }
+        stack.pop();
+        (self.emit)(
+            "workflow_completed",
+            json!({ "workflow_id": workflow_id, "success": success }),
+        );
+        success
+    }
+
+    fn action(&mut self, action: &Action, stack: &mut Vec<String>) -> bool {
+        (self.emit)(
+            "action_started",
+            json!({
+                "action_id": action.id,
+                "action_type": action.action_type,
+                "destructive": false,
+            }),
+        );
+        let result = if let Some(nested) = action.workflow_id() {
+            if stack.iter().any(|id| id == nested) {
+                Err(format!("Workflow {} runs itself", nested))
+            } else if stack.len() >= MAX_DEPTH {
+                Err(format!("Workflows nested deeper than {}", MAX_DEPTH))
+            } else if self.workflow(nested, stack) {
+                Ok(())
+            } else {
+                Err(format!("Workflow {} failed", nested))
+            }
+        } else if let Some((_, image_id)) = action.image() {
+            let vanish = action.action_type == "VANISH";
+            match self.look_for(action, image_id) {
+                Ok(found) if found != vanish => Ok(()),
+                Ok(true) => Err(format!("Image {} is still there", image_id)),
+                Ok(false) => Err(format!("Image {} not found in any screenshot", image_id)),
+                Err(e) => Err(e),
+            }
+        } else {
+            Ok(())
+        };
+        let completed = match &result {
+            Ok(()) => json!({ "action_id": action.id, "success": true }),
+            Err(e) => json!({ "action_id": action.id, "success": false, "error": e }),
+        };
+        (self.emit)("action_completed", completed);
+        result.is_ok()
+    }
+
+    /// Searches the screenshots for an action's image, reporting it with an
+    /// `image_recognition` event.
+    fn look_for(&mut self, action: &Action, image_id: &str) -> Result<bool, String> {
+        let threshold = self.threshold(action);
+        if !self.templates.contains_key(image_id) {
+            let bytes = self.config.image_store.load(image_id)?;
+            let template =
+                GrayImage::decode_png(&bytes).map_err(|e| format!("Image {}: {}", image_id, e))?;
+            self.templates.insert(image_id.to_string(), template);
+        }
+        let template = &self.templates[image_id];
+        let template_size = format!("{}x{}", template.width, template.height);
+        let sighting = self.screenshots.find(template, threshold)?;
+        let confidence = sighting
+            .found
+            .as_ref()
+            .map(|found| found.score)
+            .or(sighting.best_score)
+            .unwrap_or(0.0);
+        let mut data = json!({
+            "image_path": image_id,
+            "template_size": template_size,
+            "threshold": threshold,
+            "confidence": confidence,
+            "found": sighting.found.is_some(),
+            "gap": (threshold - confidence).max(0.0),
+        });
+        if let (Some(found), Some(screenshot)) = (&sighting.found, &sighting.screenshot) {
+            data["location"] = json!(format!("({}, {})", found.x, found.y));
+            data["screenshot"] = json!(screenshot);
+        }
+        (self.emit)("image_recognition", data);
+        Ok(sighting.found.is_some())
+    }
+
+    /// The action's own threshold, as the Python executor reads it, else the
+    /// configuration's default.
+    fn threshold(&self, action: &Action) -> f32 {
+        action
+            .config
+            .pointer("/target/threshold")
+            .or_else(|| action.config.get("similarity"))
+            .and_then(Value::as_f64)
+            .or_else(|| {
+                self.config
+                    .settings
+                    .as_ref()
+                    .and_then(|s| s.recognition.as_ref())
+                    .and_then(|r| r.get("defaultThreshold"))
+                    .and_then(Value::as_f64)
+            })
+            .map_or(DEFAULT_THRESHOLD, |threshold| threshold as f32)
+    }
+
+    fn log(&mut self, level: &str, message: String) {
+        (self.emit)("log", json!({ "level": level, "message": message }));
+    }
+}
+
+/// In-process executor for screenshot mode, selected with executor type
+/// `"screenshot"`.
+pub struct ScreenshotExecutor {
+    app_handle: AppHandle,
+    outbox: Outbox,
+    stop: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+    started: bool,
+}
+
+impl ScreenshotExecutor {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self {
+            app_handle,
+            outbox: Outbox::default(),
+            stop: Arc::default(),
+            running: Arc::default(),
+            started: false,
+        }
+    }
+
+    fn handle(&self, command: &str, params: &Value) -> Result<Option<Value>, String> {
+        match command {
+            "ping" | "load" | "load_chunk" | "load_delta" => Ok(None),
+            "status" => Ok(Some(json!({
+                "is_running": self.running.load(Ordering::Relaxed),
+                "bridge_type": "screenshot",
+            }))),
+            "start" => self.start_execution(params).map(|()| None),
+            "stop" => {
+                self.stop.store(true, Ordering::Relaxed);
+                Ok(None)
+            }
+            other => Err(format!("Unknown command: {}", other)),
+        }
+    }
+
+    fn start_execution(&self, params: &Value) -> Result<(), String> {
+        if self.running.load(Ordering::Relaxed) {
+            return Err("Execution already in progress".to_string());
+        }
+        let config = self
+            .app_handle
+            .state::<AppState>()
+            .current_config
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or("No configuration loaded")?;
+        let workflow_id = params
+            .get("workflow_id")
+            .and_then(Value::as_str)
+            .ok_or("Workflow ID is required")?
+            .to_string();
+        let mut screenshots = Screenshots::open(&screenshot_directory(&config)?)?;
+        let strategy = params
+            .get("failure_strategy")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        self.stop.store(false, Ordering::Relaxed);
+        self.running.store(true, Ordering::Relaxed);
+        let (outbox, stop, running) =
+            (self.outbox.clone(), self.stop.clone(), self.running.clone());
+        thread::spawn(move || {
+            let mut emit = |event: &str, data: Value| outbox.event(event, data);
+            let mut run = Run::new(&config, &mut screenshots, &stop, &mut emit);
+            if let Some(strategy) = strategy {
+                run = run.continue_on_failure(strategy == "continue");
+            }
+            run.execute(&workflow_id);
+            running.store(false, Ordering::Relaxed);
+        });
+        Ok(())
+    }
+}
+
+impl ExecutorBackend for ScreenshotExecutor {
+    fn name(&self) -> &'static str {
+        "screenshot"
+    }
+
+    fn commands(&self) -> Vec<String> {
+        COMMANDS.iter().map(|command| command.to_string()).collect()
+    }
+
+    fn subscribe(&mut self, subscriber: Subscriber) {
+        self.outbox.subscribe(subscriber);
+    }
+
+    fn start(&mut self, _executor_type: &str) -> Result<(), String> {
+        self.started = true;
+        Ok(())
+    }
+
+    fn send(&mut self, id: &str, command: &str, params: Option<Value>) -> Result<(), String> {
+        let reply = self.handle(command, &params.unwrap_or(Value::Null));
+        self.outbox.respond(id, reply);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<ShutdownOutcome, String> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.outbox.close();
+        let outcome = if self.started {
+            ShutdownOutcome::Graceful
+        } else {
+            ShutdownOutcome::NotRunning
+        };
+        self.started = false;
+        Ok(outcome)
+    }
+
+    fn is_running(&self) -> bool {
+        self.started
+    }
+}
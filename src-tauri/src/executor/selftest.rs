@@ -0,0 +1,249 @@
+use super::backend::ExecutorBackend;
+use super::python_bridge::PythonBridge;
+use crate::config::fixtures::{self, FixtureSpec};
+use crate::error;
+use crate::tasks::TaskContext;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener};
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(15);
+const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// The script answered `Unknown command`. Only acceptable for optional
+    /// features such as recording.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelftestReport {
+    pub executor_type: String,
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+    pub protocol_violations: u64,
+}
+
+/// Events and responses from the bridge under test, in arrival order.
+#[derive(Default)]
+struct Inbox {
+    messages: Mutex<Vec<Value>>,
+    arrived: Condvar,
+}
+
+impl Inbox {
+    fn push(&self, payload: &str) {
+        if let Ok(message) = serde_json::from_str::<Value>(payload) {
+            self.messages.lock().unwrap().push(message);
+            self.arrived.notify_all();
+        }
+    }
+
+    fn wait_for(&self, timeout: Duration, matches: impl Fn(&Value) -> bool) -> Option<Value> {
+        let deadline = Instant::now() + timeout;
+        let mut messages = self.messages.lock().unwrap();
+        loop {
+            if let Some(found) = messages.iter().find(|m| matches(m)) {
+                return Some(found.clone());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            messages = self
+                .arrived
+                .wait_timeout(messages, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    fn response(&self, id: &str) -> Result<Value, String> {
+        self.wait_for(RESPONSE_TIMEOUT, |m| {
+            m["type"] == "response" && m["id"].as_str() == Some(id)
+        })
+        .ok_or_else(|| format!("No response within {} s", RESPONSE_TIMEOUT.as_secs()))
+    }
+
+    fn event(&self, name: &str, timeout: Duration) -> Result<(), String> {
+        self.wait_for(timeout, |m| m["type"] == "event" && m["event"] == name)
+            .map(|_| ())
+            .ok_or_else(|| format!("No '{}' event within {} s", name, timeout.as_secs()))
+    }
+}
+
+/// A configuration with one state and one wait-only workflow, forced into
+/// mock execution so the run never touches the real screen.
+fn tiny_config() -> Value {
+    let mut config = fixtures::generate(&FixtureSpec {
+        states: 1,
+        transitions: 0,
+        workflows: 1,
+        images: 0,
+        ..Default::default()
+    });
+    config["settings"] = json!({ "execution": { "executionMode": "mock" } });
+    config
+}
+
+/// Starts a separate bridge of `executor_type` with strict protocol checks
+/// and drives it through ping, load, a mock run and recording start/stop,
+/// checking each response. The bridge is stopped afterwards.
+pub fn run(executor_type: &str, app_handle: AppHandle, task: &TaskContext) -> SelftestReport {
+    let inbox = Arc::new(Inbox::default());
+    let listeners: Vec<_> = ["executor-event", "executor-response"]
+        .into_iter()
+        .map(|name| {
+            let inbox = inbox.clone();
+            app_handle.listen(name, move |event| inbox.push(event.payload()))
+        })
+        .collect();
+
+    let mut bridge = PythonBridge::new(app_handle.clone());
+    bridge.set_strict_protocol(true);
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    let start = bridge.start(executor_type);
+    checks.push(finish("start", started, start.map(|_| CheckStatus::Passed)));
+
+    if checks[0].status == CheckStatus::Passed {
+        let base_dir =
+            std::env::temp_dir().join(format!("qontinui-selftest-{}", uuid::Uuid::new_v4()));
+        let config = tiny_config().to_string();
+        let steps: [(&str, bool); 5] = [
+            ("ping", true),
+            ("load_config", true),
+            ("mock_run", true),
+            ("recording_start", false),
+            ("recording_stop", false),
+        ];
+
+        for (index, (name, required)) in steps.into_iter().enumerate() {
+            if task.is_cancelled() {
+                break;
+            }
+            task.progress(
+                index as f32 / steps.len() as f32,
+                format!("Checking {}", name),
+            );
+
+            let started = Instant::now();
+            let outcome = match name {
+                "ping" => exchange(&mut bridge, &inbox, "status", None),
+                "load_config" => bridge
+                    .load_configuration_content(&config, false)
+                    .and_then(|id| check_response(&inbox, &id)),
+                "mock_run" => exchange(
+                    &mut bridge,
+                    &inbox,
+                    "start",
+                    Some(json!({
+                        "mode": "mock",
+                        "process_id": "workflow-0",
+                        "workflow_id": "workflow-0",
+                        "monitor_index": 0,
+                    })),
+                )
+                .and_then(|status| {
+                    inbox.event("execution_completed", RUN_TIMEOUT)?;
+                    Ok(status)
+                }),
+                "recording_start" => exchange(
+                    &mut bridge,
+                    &inbox,
+                    "start_recording",
+                    Some(json!({ "base_dir": base_dir.to_string_lossy() })),
+                ),
+                "recording_stop" => exchange(&mut bridge, &inbox, "stop_recording", None),
+                _ => unreachable!("unknown self-test step {}", name),
+            };
+
+            let outcome = match outcome {
+                Ok(CheckStatus::Unsupported) if required => {
+                    Err("Command not supported by the bridge script".to_string())
+                }
+                other => other,
+            };
+            checks.push(finish(name, started, outcome));
+        }
+
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    let _ = bridge.stop();
+    for listener in listeners {
+        app_handle.unlisten(listener);
+    }
+
+    let protocol_violations = bridge.protocol_violations();
+    let protocol = if protocol_violations == 0 {
+        Ok(CheckStatus::Passed)
+    } else {
+        Err(format!(
+            "{} protocol violations (details in the log)",
+            protocol_violations
+        ))
+    };
+    checks.push(finish("protocol", Instant::now(), protocol));
+
+    SelftestReport {
+        executor_type: executor_type.to_string(),
+        passed: checks.iter().all(|c| c.status != CheckStatus::Failed),
+        checks,
+        protocol_violations,
+    }
+}
+
+fn exchange(
+    bridge: &mut PythonBridge,
+    inbox: &Inbox,
+    command: &str,
+    params: Option<Value>,
+) -> Result<CheckStatus, String> {
+    let id = bridge.send_command_with_id(command, params)?;
+    check_response(inbox, &id)
+}
+
+fn check_response(inbox: &Inbox, id: &str) -> Result<CheckStatus, String> {
+    let response = inbox.response(id)?;
+    if response["success"] == true {
+        return Ok(CheckStatus::Passed);
+    }
+
+    let message = response["error"]
+        .as_str()
+        .unwrap_or("Command reported failure");
+    if error::executor_error_to_user_facing(message).error_code == "EXEC_002" {
+        Ok(CheckStatus::Unsupported)
+    } else {
+        Err(message.to_string())
+    }
+}
+
+fn finish(name: &str, started: Instant, outcome: Result<CheckStatus, String>) -> CheckResult {
+    let (status, message) = match outcome {
+        Ok(status) => (status, None),
+        Err(e) => (CheckStatus::Failed, Some(e)),
+    };
+    CheckResult {
+        name: name.to_string(),
+        status,
+        message,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
@@ -0,0 +1,151 @@
+//! Executor sessions: independent Python processes that each hold their own
+//! configuration, so several automations can run side by side. The session
+//! the rest of the runner drives (`AppState::executor`) is
+//! `DEFAULT_SESSION`; more are created with `create_session`. Everything a
+//! session emits to the frontend carries its `session_id`.
+//!
+//! Idle gating, the run plan, crash restarts and the persisted session
+//! follow the default session only. Input limits, safe mode, secret
+//! requests and run streams apply to every session.
+
+use super::backend::ExecutorBackend;
+use crate::commands::AppState;
+use crate::config::QontinuiConfig;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+pub const DEFAULT_SESSION: &str = "default";
+
+pub struct ExecutorSession {
+    pub bridge: Box<dyn ExecutorBackend>,
+    pub executor_type: String,
+    pub config: Option<Arc<QontinuiConfig>>,
+    pub config_path: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub executor_type: Option<String>,
+    pub running: bool,
+    pub config_path: Option<String>,
+    pub config_name: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// The sessions created besides the default one, by id.
+#[derive(Default)]
+pub struct ExecutorSessions {
+    sessions: Mutex<HashMap<String, Arc<Mutex<ExecutorSession>>>>,
+}
+
+impl ExecutorSessions {
+    pub fn insert(&self, session_id: &str, session: ExecutorSession) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), Arc::new(Mutex::new(session)));
+    }
+
+    /// Looks a session up. The map lock is released before the session is
+    /// used, so a slow command on one session doesn't hold up the others.
+    pub fn get(&self, session_id: &str) -> Result<Arc<Mutex<ExecutorSession>>, String> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| format!("No executor session '{}'", session_id))
+    }
+
+    pub fn remove(&self, session_id: &str) -> Option<Arc<Mutex<ExecutorSession>>> {
+        self.sessions.lock().unwrap().remove(session_id)
+    }
+
+    /// Created sessions, oldest first.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let sessions: Vec<_> = self.sessions.lock().unwrap().values().cloned().collect();
+        let mut infos: Vec<SessionInfo> = sessions
+            .iter()
+            .map(|session| {
+                let session = session.lock().unwrap();
+                SessionInfo {
+                    session_id: session.bridge.session_id().to_string(),
+                    executor_type: Some(session.executor_type.clone()),
+                    running: session.bridge.is_running(),
+                    config_path: session.config_path.clone(),
+                    config_name: session.config.as_ref().map(|c| c.metadata.name.clone()),
+                    created_at: Some(session.created_at.clone()),
+                }
+            })
+            .collect();
+        infos.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        infos
+    }
+
+    /// Stops every created session, for shutdown.
+    pub fn stop_all(&self) {
+        let sessions: Vec<_> = self.sessions.lock().unwrap().drain().collect();
+        for (session_id, session) in sessions {
+            if let Err(e) = session.lock().unwrap().bridge.stop() {
+                warn!("Failed to stop executor session {}: {}", session_id, e);
+            }
+        }
+    }
+}
+
+/// Runs `action` on the bridge of `session_id`, the default session's when
+/// that's `DEFAULT_SESSION`.
+pub fn with_bridge<T>(
+    state: &AppState,
+    session_id: &str,
+    action: impl FnOnce(&mut dyn ExecutorBackend) -> Result<T, String>,
+) -> Result<T, String> {
+    if session_id == DEFAULT_SESSION {
+        let mut bridge = state.executor.lock().unwrap();
+        return match bridge.as_mut() {
+            Some(bridge) => action(bridge.as_mut()),
+            None => Err("Python executor not initialized".to_string()),
+        };
+    }
+    let session = state.executor_sessions.get(session_id)?;
+    let mut session = session.lock().unwrap();
+    action(session.bridge.as_mut())
+}
+
+/// A payload with the id of the session it came from.
+#[derive(Clone, Serialize)]
+pub struct SessionTagged<'a, T> {
+    pub session_id: &'a str,
+    #[serde(flatten)]
+    pub payload: &'a T,
+}
+
+/// Emits `payload` tagged with `session_id`.
+pub fn emit<T: Serialize + Clone>(
+    app_handle: &AppHandle,
+    event: &str,
+    session_id: &str,
+    payload: &T,
+) -> tauri::Result<()> {
+    app_handle.emit(
+        event,
+        SessionTagged {
+            session_id,
+            payload,
+        },
+    )
+}
+
+/// Whether an event payload from the frontend channel came from the
+/// default session. Untagged payloads count as the default session's.
+pub fn is_default(payload: &serde_json::Value) -> bool {
+    payload
+        .get("session_id")
+        .and_then(|id| id.as_str())
+        .is_none_or(|id| id == DEFAULT_SESSION)
+}
@@ -0,0 +1,228 @@
+//! Stopping the executor along with every process it started. On Unix the
+//! executor runs in a process group of its own, so signals reach the
+//! interpreter behind `poetry run` and qontinui's helpers too; on Windows
+//! it runs in a job object that kills what's left in it once closed, so
+//! even a runner that crashes doesn't leave them behind. Whatever was below
+//! the executor when the stop began is killed if it outlives it. A stop
+//! asks the executor to exit and waits the graceful timeout, then
+//! escalates: SIGTERM to the group, and SIGKILL when that is ignored as
+//! well.
+
+use super::process_tree;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::collections::HashMap;
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+#[cfg(windows)]
+use std::process::Stdio;
+use std::process::{Child, Command};
+#[cfg(windows)]
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The job object of each running executor, by process id. Dropping one
+/// closes it and kills what's still inside.
+#[cfg(windows)]
+static JOBS: Mutex<Option<HashMap<u32, OwnedHandle>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownSettings {
+    /// How long the executor gets to exit after the `stop` command.
+    pub graceful_timeout_ms: u64,
+    /// How long it gets after SIGTERM before it's killed. Not used on
+    /// Windows, where a console process can't be asked to terminate.
+    pub terminate_timeout_ms: u64,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        Self {
+            graceful_timeout_ms: 3000,
+            terminate_timeout_ms: 2000,
+        }
+    }
+}
+
+/// How a stopped executor went away.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownOutcome {
+    /// There was no executor to stop.
+    NotRunning,
+    /// It exited on its own after the `stop` command.
+    Graceful,
+    /// It exited on SIGTERM.
+    Terminated,
+    /// It had to be killed.
+    Killed,
+}
+
+impl ShutdownOutcome {
+    pub fn is_graceful(self) -> bool {
+        matches!(self, Self::NotRunning | Self::Graceful)
+    }
+}
+
+/// Puts the process `command` spawns in a process group of its own, which
+/// its children inherit. On Windows `contain` does this once it's spawned.
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// Puts `child` in a job object of its own, which the processes it starts
+/// join too, killed along with it when the job is closed: by `kill_tree`,
+/// or by Windows if the runner dies first. Does nothing elsewhere.
+pub fn contain(child: &Child) {
+    #[cfg(windows)]
+    match job_for(child) {
+        Ok(job) => {
+            JOBS.lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(child.id(), job);
+        }
+        Err(e) => warn!("The executor runs without a job object: {}", e),
+    }
+    #[cfg(not(windows))]
+    let _ = child;
+}
+
+#[cfg(windows)]
+fn job_for(child: &Child) -> Result<OwnedHandle, String> {
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    // SAFETY: the job handle is checked and owned before it's used, the
+    // limits are passed with their own size, and the process handle is
+    // borrowed from `child`, which outlives the call.
+    unsafe {
+        let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        let job = OwnedHandle::from_raw_handle(handle);
+        let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        if SetInformationJobObject(
+            job.as_raw_handle(),
+            JobObjectExtendedLimitInformation,
+            &limits as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) == 0
+            || AssignProcessToJobObject(job.as_raw_handle(), child.as_raw_handle()) == 0
+        {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        Ok(job)
+    }
+}
+
+/// Waits up to `timeout` for `child` to exit. Returns whether it did.
+pub fn wait_for_exit(child: &mut Child, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return true,
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => return false,
+            // Can't be waited on, so it isn't ours to wait for any more
+            Err(_) => return true,
+        }
+    }
+}
+
+/// Ends `child`, which has been asked to stop, and what it started, then
+/// reaps it.
+pub fn shut_down(
+    child: &mut Child,
+    settings: &ShutdownSettings,
+) -> Result<ShutdownOutcome, String> {
+    // Taken now: once the executor exits, its children are reparented
+    let tree = process_tree::descendants(child.id());
+    let outcome = if wait_for_exit(child, Duration::from_millis(settings.graceful_timeout_ms)) {
+        ShutdownOutcome::Graceful
+    } else if terminate(child.id())
+        && wait_for_exit(child, Duration::from_millis(settings.terminate_timeout_ms))
+    {
+        ShutdownOutcome::Terminated
+    } else {
+        ShutdownOutcome::Killed
+    };
+    if outcome == ShutdownOutcome::Killed {
+        warn!("Executor didn't stop in time, killing it");
+    } else {
+        info!("Executor exited ({:?})", outcome);
+    }
+    // Also when it exited: helpers it left behind are still in the group.
+    // It isn't reaped yet, so the group id can't have been reused.
+    kill_tree(child);
+    let left = process_tree::kill_all(&tree);
+    if left > 0 {
+        warn!("Killed {} process(es) the executor left behind", left);
+    }
+    child.wait().map_err(|e| e.to_string())?;
+    Ok(outcome)
+}
+
+/// Kills `child` and every process of its tree.
+pub fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    signal_group(libc::SIGKILL, child.id());
+    #[cfg(windows)]
+    {
+        let job = JOBS
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|jobs| jobs.remove(&child.id()));
+        // Closing the job kills what's in it; without one, taskkill ends the
+        // tree as far as it can still be found
+        if job.is_none() {
+            let _ = Command::new("taskkill")
+                .args(["/PID", &child.id().to_string(), "/T", "/F"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+    // Fails only if it already exited, which is fine
+    let _ = child.kill();
+}
+
+/// Sends SIGTERM to the process group led by `pid`. Returns whether it was
+/// sent; never on Windows.
+fn terminate(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        signal_group(libc::SIGTERM, pid)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        false
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(signal: libc::c_int, pgid: u32) -> bool {
+    let Ok(pgid) = libc::pid_t::try_from(pgid) else {
+        return false;
+    };
+    // SAFETY: killpg only sends a signal; a group that's gone is an error
+    unsafe { libc::killpg(pgid, signal) == 0 }
+}
@@ -0,0 +1,95 @@
+//! Where the automation is in its configuration's state machine, as the
+//! executor sees it: the states it's in, the graph of states and
+//! transitions, and the transitions it has run. The executor starts in the
+//! configuration's initial states and follows the transitions its
+//! navigation runs; a `state_changed` event reports each change, so a live
+//! graph only needs to query once.
+//!
+//! When the automation gets lost, the user can correct it rather than
+//! restart the run: activate or deactivate a state by hand, or have the
+//! executor look for every state's images on screen and start over from
+//! what it finds.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateRef {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveStates {
+    pub states: Vec<StateRef>,
+}
+
+/// The active states after `rebuild_states`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebuiltStates {
+    pub states: Vec<StateRef>,
+    /// States without images to look for, left as they were.
+    #[serde(default)]
+    pub unverified: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateNode {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub initial: bool,
+    #[serde(default)]
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionEdge {
+    pub id: String,
+    #[serde(default)]
+    pub from_state: Option<String>,
+    #[serde(default)]
+    pub to_state: Option<String>,
+    #[serde(default)]
+    pub activate_states: Vec<String>,
+    #[serde(default)]
+    pub deactivate_states: Vec<String>,
+    #[serde(default)]
+    pub workflows: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateGraph {
+    pub states: Vec<StateNode>,
+    pub transitions: Vec<TransitionEdge>,
+}
+
+/// A transition the executor ran, successfully or not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionRecord {
+    pub transition_id: String,
+    pub workflow_id: String,
+    #[serde(default)]
+    pub from_state: Option<String>,
+    #[serde(default)]
+    pub to_states: Vec<String>,
+    pub success: bool,
+    /// Seconds since the epoch, as the executor's event timestamps.
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionHistory {
+    /// Oldest first.
+    pub transitions: Vec<TransitionRecord>,
+}
+
+/// Reads the `data` of the executor's answer to `command`.
+pub fn parse<T: DeserializeOwned>(command: &str, data: Option<Value>) -> Result<T, String> {
+    let data = data.ok_or_else(|| format!("The executor sent no {}", command))?;
+    serde_json::from_value(data)
+        .map_err(|e| format!("Invalid {} from the executor: {}", command, e))
+}
@@ -0,0 +1,223 @@
+//! Keeps a runaway executor from taking the machine down with it. A
+//! supervisor thread samples the default session's Python process against
+//! the executor limits in `ResourceLimits`: memory, CPU and how long the
+//! current execution has run. Going over one raises an
+//! `executor-limit-exceeded` warning; still being over it once the grace
+//! period has passed pauses the execution or kills the executor, as
+//! configured, and records why in the execution history.
+
+use super::sessions::DEFAULT_SESSION;
+use super::watchdog;
+use crate::commands::AppState;
+use crate::resources::{ExecutorLimits, LimitAction};
+use crate::scheduler::ExecutionRecord;
+use serde::Serialize;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, warn};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// How often the executor is sampled.
+const SUPERVISE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One look at the executor.
+#[derive(Debug, Clone, Default)]
+pub struct Sample {
+    pub memory_bytes: Option<u64>,
+    pub cpu_percent: Option<f32>,
+    /// How long the current execution has run.
+    pub run_time: Option<Duration>,
+}
+
+/// The limits `sample` is over, described.
+pub fn exceeded(limits: &ExecutorLimits, sample: &Sample) -> Vec<String> {
+    let mut exceeded = Vec::new();
+    if let (Some(max), Some(memory)) = (limits.max_memory_mb, sample.memory_bytes) {
+        if memory > max.saturating_mul(BYTES_PER_MB) {
+            exceeded.push(format!(
+                "memory {} MB over the {} MB limit",
+                memory / BYTES_PER_MB,
+                max
+            ));
+        }
+    }
+    if let (Some(max), Some(cpu)) = (limits.max_cpu_percent, sample.cpu_percent) {
+        if cpu > max {
+            exceeded.push(format!("CPU {:.0}% over the {:.0}% limit", cpu, max));
+        }
+    }
+    if let (Some(max), Some(run_time)) = (limits.max_run_minutes, sample.run_time) {
+        if run_time > Duration::from_secs(max * 60) {
+            exceeded.push(format!(
+                "execution running {} min, over the {} min limit",
+                run_time.as_secs() / 60,
+                max
+            ));
+        }
+    }
+    exceeded
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Warn(String),
+    Act(LimitAction, String),
+}
+
+/// Tracks how long the executor has been over its limits.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    over_since: Option<Instant>,
+    acted: bool,
+}
+
+impl Supervisor {
+    /// What to do about `sample`: warn when a limit is first exceeded, act
+    /// once it still is after the grace period, then nothing until the
+    /// executor is back within its limits.
+    pub fn check(
+        &mut self,
+        limits: &ExecutorLimits,
+        sample: &Sample,
+        now: Instant,
+    ) -> Option<Verdict> {
+        let exceeded = exceeded(limits, sample);
+        if exceeded.is_empty() {
+            *self = Supervisor::default();
+            return None;
+        }
+        let reason = format!("Executor {}", exceeded.join(", "));
+        match self.over_since {
+            None => {
+                self.over_since = Some(now);
+                Some(Verdict::Warn(reason))
+            }
+            Some(since)
+                if !self.acted
+                    && now.duration_since(since) >= Duration::from_secs(limits.grace_seconds) =>
+            {
+                self.acted = true;
+                Some(Verdict::Act(limits.action, reason))
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LimitNotice<'a> {
+    stage: &'a str,
+    reason: &'a str,
+    action: Option<LimitAction>,
+}
+
+/// Supervises the default session's executor for the rest of the app's
+/// life.
+pub fn start(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let mut supervisor = Supervisor::default();
+        loop {
+            thread::sleep(SUPERVISE_INTERVAL);
+            let state = app_handle.state::<AppState>();
+            let limits = state.resource_limits.lock().unwrap().executor.clone();
+            let Some(sample) = sample(&state) else {
+                supervisor = Supervisor::default();
+                continue;
+            };
+            match supervisor.check(&limits, &sample, Instant::now()) {
+                Some(Verdict::Warn(reason)) => {
+                    warn!("{}", reason);
+                    let _ = app_handle.emit(
+                        "executor-limit-exceeded",
+                        LimitNotice {
+                            stage: "warning",
+                            reason: &reason,
+                            action: None,
+                        },
+                    );
+                }
+                Some(Verdict::Act(action, reason)) => enforce(&app_handle, action, &reason),
+                None => {}
+            }
+        }
+    });
+}
+
+fn sample(state: &AppState) -> Option<Sample> {
+    // Sampled without holding the bridge, which the first CPU sample waits on
+    let (stats, pid) = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|bridge| bridge.is_running())
+        .and_then(|bridge| Some((bridge.stats(), bridge.pid()?)))?;
+    let usage = stats.process_usage(pid);
+    let run_time = state
+        .run_tracker
+        .active()
+        .and_then(|(_, started_at)| (chrono::Local::now() - started_at).to_std().ok());
+    Some(Sample {
+        memory_bytes: usage.as_ref().map(|usage| usage.memory_bytes),
+        cpu_percent: usage.as_ref().map(|usage| usage.cpu_percent),
+        run_time,
+    })
+}
+
+/// Pauses or kills the executor and notes it in the execution history.
+fn enforce(app_handle: &AppHandle, action: LimitAction, reason: &str) {
+    let state = app_handle.state::<AppState>();
+    let active = state.run_tracker.active();
+    let mut taken = action;
+    {
+        let mut guard = state.executor.lock().unwrap();
+        let Some(bridge) = guard.as_mut() else {
+            return;
+        };
+        if action == LimitAction::Pause {
+            if let Err(e) = bridge.pause_execution(reason) {
+                warn!("Can't pause the executor ({}); killing it", e);
+                taken = LimitAction::Kill;
+            }
+        }
+        if taken == LimitAction::Kill {
+            if let Err(e) = bridge.force_kill() {
+                error!("Failed to kill the executor: {}", e);
+            }
+        }
+    }
+    match taken {
+        LimitAction::Pause => error!("{}; paused the execution", reason),
+        LimitAction::Kill => error!("{}; killed the executor", reason),
+    }
+    if taken == LimitAction::Kill {
+        watchdog::end_run(app_handle, DEFAULT_SESSION, reason);
+    }
+    let _ = app_handle.emit(
+        "executor-limit-exceeded",
+        LimitNotice {
+            stage: "enforced",
+            reason,
+            action: Some(taken),
+        },
+    );
+
+    let now = chrono::Local::now();
+    let (workflow_id, started_at) = active.unwrap_or((None, now));
+    let record = ExecutionRecord {
+        schedule_id: None,
+        process_id: workflow_id.unwrap_or_default(),
+        config_path: None,
+        started_at: started_at.to_rfc3339(),
+        finished_at: now.to_rfc3339(),
+        success: false,
+        error: Some(reason.to_string()),
+        videos: Vec::new(),
+        limit_action: Some(taken),
+    };
+    if let Err(e) = state.scheduler.finish(&record) {
+        warn!("Failed to record the limit in the execution history: {}", e);
+    }
+}
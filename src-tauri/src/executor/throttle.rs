@@ -0,0 +1,144 @@
+use super::python_bridge::ExecutorEvent;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Priority class of an executor event, used to decide how aggressively it
+/// may be throttled before reaching the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventClass {
+    /// Errors, lifecycle and state changes. Never throttled.
+    Critical,
+    /// Regular workflow events.
+    Normal,
+    /// High-frequency per-action progress and log chatter.
+    Bulk,
+}
+
+impl EventClass {
+    pub fn of(event: &ExecutorEvent) -> Self {
+        match event.event.as_str() {
+            "error"
+            | "dsl_execution_error"
+            | "hello"
+            | "ready"
+            | "config_loaded"
+            | "execution_started"
+            | "execution_completed"
+            | "process_started"
+            | "process_completed"
+            | "workflow_started"
+            | "workflow_completed"
+            | "state_changed"
+            | "state_detected"
+            | "recording_started"
+            | "recording_stopped"
+            | "input_rate_exceeded"
+            | "safe_mode_violation"
+            | "execution_paused"
+            | "execution_resumed" => EventClass::Critical,
+            "log" => match event.data.get("level").and_then(|l| l.as_str()) {
+                Some("error") | Some("critical") | Some("warning") => EventClass::Critical,
+                _ => EventClass::Bulk,
+            },
+            "action_started"
+            | "action_completed"
+            | "action_execution"
+            | "image_recognition"
+            | "match_found"
+            | "state_check_performed"
+            | "screenshot_taken"
+            | "dsl_loop_iteration" => EventClass::Bulk,
+            _ => EventClass::Normal,
+        }
+    }
+}
+
+/// Per-class emission limits, in events per second.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleConfig {
+    pub normal_per_second: u32,
+    pub bulk_per_second: u32,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            normal_per_second: 100,
+            bulk_per_second: 20,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    started: Option<Instant>,
+    emitted: u32,
+    suppressed: u64,
+}
+
+/// Outcome of offering an event to the throttle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottleDecision {
+    pub emit: bool,
+    /// Number of events of this class suppressed in the window that just closed.
+    pub suppressed_in_last_window: u64,
+}
+
+/// Fixed one-second window rate limiter applied per event class.
+#[derive(Debug, Default)]
+pub struct EventThrottle {
+    config: ThrottleConfig,
+    normal: Window,
+    bulk: Window,
+}
+
+impl EventThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_config(&mut self, config: ThrottleConfig) {
+        self.config = config;
+    }
+
+    pub fn admit(&mut self, class: EventClass, now: Instant) -> ThrottleDecision {
+        let (window, limit) = match class {
+            EventClass::Critical => {
+                return ThrottleDecision {
+                    emit: true,
+                    suppressed_in_last_window: 0,
+                }
+            }
+            EventClass::Normal => (&mut self.normal, self.config.normal_per_second),
+            EventClass::Bulk => (&mut self.bulk, self.config.bulk_per_second),
+        };
+
+        let mut suppressed_in_last_window = 0;
+        let expired = window
+            .started
+            .is_none_or(|started| now.duration_since(started) >= Duration::from_secs(1));
+        if expired {
+            suppressed_in_last_window = window.suppressed;
+            *window = Window {
+                started: Some(now),
+                ..Default::default()
+            };
+        }
+
+        let emit = window.emitted < limit;
+        if emit {
+            window.emitted += 1;
+        } else {
+            window.suppressed += 1;
+        }
+
+        ThrottleDecision {
+            emit,
+            suppressed_in_last_window,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! The latest events of recent runs, kept in memory so a frontend that
+//! reconnects, or is opened in the middle of a run, can catch up with
+//! `get_event_timeline` rather than starting blind. Each run keeps its last
+//! `EVENTS_PER_RUN` events as received from the executor, before
+//! throttling. Runs that are no longer in memory, e.g. from before the
+//! runner restarted, are read back from their recorded stream.
+
+use super::python_bridge::ExecutorEvent;
+use super::run_stream::{Direction, StreamEntry};
+use crate::resources;
+use chrono::DateTime;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Events kept per run; older ones drop off.
+pub const EVENTS_PER_RUN: usize = 5000;
+
+/// Runs kept in memory.
+pub const RUNS_KEPT: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timeline {
+    pub run_id: String,
+    /// Events after the requested sequence, oldest first.
+    pub events: Vec<ExecutorEvent>,
+    /// Earlier events of the run that didn't fit in memory.
+    pub dropped: u64,
+    /// Whether the run has ended.
+    pub completed: bool,
+}
+
+struct RunTimeline {
+    run_id: String,
+    events: VecDeque<ExecutorEvent>,
+    /// Roughly how much memory `events` hold.
+    bytes: usize,
+    dropped: u64,
+    completed: bool,
+}
+
+pub struct EventTimeline {
+    capacity: usize,
+    runs: Mutex<VecDeque<RunTimeline>>,
+}
+
+impl Default for EventTimeline {
+    fn default() -> Self {
+        Self::new(EVENTS_PER_RUN)
+    }
+}
+
+impl EventTimeline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            runs: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Adds an event of `run_id`, which is kept as the latest run if it
+    /// isn't in memory yet. Runs of different sessions may interleave.
+    pub fn record(&self, run_id: &str, event: &ExecutorEvent) {
+        let mut runs = self.runs.lock().unwrap();
+        if !runs.iter().any(|run| run.run_id == run_id) {
+            if runs.len() == RUNS_KEPT {
+                runs.pop_front();
+            }
+            runs.push_back(RunTimeline {
+                run_id: run_id.to_string(),
+                events: VecDeque::new(),
+                bytes: 0,
+                dropped: 0,
+                completed: false,
+            });
+        }
+        let run = runs.iter_mut().find(|run| run.run_id == run_id).unwrap();
+        if run.events.len() == self.capacity {
+            if let Some(oldest) = run.events.pop_front() {
+                run.bytes -= resources::event_bytes(&oldest);
+            }
+            run.dropped += 1;
+        }
+        run.bytes += resources::event_bytes(event);
+        run.events.push_back(event.clone());
+        if event.event == "execution_completed" {
+            run.completed = true;
+        }
+    }
+
+    /// Events kept across runs, and roughly how much memory they hold.
+    pub fn usage(&self) -> (usize, usize) {
+        let runs = self.runs.lock().unwrap();
+        runs.iter().fold((0, 0), |(events, bytes), run| {
+            (events + run.events.len(), bytes + run.bytes)
+        })
+    }
+
+    /// The run's events after `since_sequence`, or `None` if the run isn't
+    /// in memory.
+    pub fn since(&self, run_id: &str, since_sequence: u32) -> Option<Timeline> {
+        let runs = self.runs.lock().unwrap();
+        let run = runs.iter().find(|run| run.run_id == run_id)?;
+        Some(Timeline {
+            run_id: run.run_id.clone(),
+            events: after(run.events.iter(), since_sequence),
+            dropped: run.dropped,
+            completed: run.completed,
+        })
+    }
+}
+
+/// A run's timeline rebuilt from its recorded stream.
+pub fn from_stream(run_id: &str, entries: &[StreamEntry], since_sequence: u32) -> Timeline {
+    let events: Vec<ExecutorEvent> = entries
+        .iter()
+        .filter(|entry| entry.direction == Direction::Event)
+        .map(|entry| ExecutorEvent {
+            event: entry.name.clone(),
+            timestamp: DateTime::parse_from_rfc3339(&entry.timestamp)
+                .map(|time| time.timestamp_millis() as f64 / 1000.0)
+                .unwrap_or_default(),
+            sequence: entry.sequence.unwrap_or(0),
+            data: entry.data.clone(),
+        })
+        .collect();
+    Timeline {
+        run_id: run_id.to_string(),
+        completed: events
+            .iter()
+            .any(|event| event.event == "execution_completed"),
+        events: after(events.iter(), since_sequence),
+        dropped: 0,
+    }
+}
+
+/// The events after the last one numbered up to `since_sequence`. The
+/// runner's own events are numbered 0, so they're placed by the executor
+/// events around them.
+fn after<'a, I>(events: I, since_sequence: u32) -> Vec<ExecutorEvent>
+where
+    I: Iterator<Item = &'a ExecutorEvent>,
+{
+    let events: Vec<&ExecutorEvent> = events.collect();
+    let start = events
+        .iter()
+        .rposition(|event| event.sequence != 0 && event.sequence <= since_sequence)
+        .map_or(0, |last_seen| last_seen + 1);
+    events[start..]
+        .iter()
+        .map(|event| (*event).clone())
+        .collect()
+}
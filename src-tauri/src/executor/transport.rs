@@ -0,0 +1,329 @@
+//! Transports between the runner and the bridge. JSON lines over the
+//! process's stdin and stdout always work, but large payloads (screenshots,
+//! big configurations) are slow and fragile there. So the runner also
+//! listens on a local socket, a Unix-domain socket or a TCP port on
+//! `127.0.0.1`, and passes its address and a token to the bridge in
+//! `QONTINUI_TRANSPORT_ADDRESS` and `QONTINUI_TRANSPORT_TOKEN`. A bridge
+//! that connects and sends the token gets its commands there and writes its
+//! messages there; one that doesn't within `CONNECT_TIMEOUT` (e.g. an older
+//! script) stays on stdio.
+//!
+//! Socket messages are length-prefixed frames: the payload's length as a
+//! 4-byte big-endian integer, a kind byte and the payload. A JSON frame
+//! holds one message. A binary frame holds the message's length as 4
+//! bytes, the message and raw bytes, which the runner attaches to an
+//! event's data base64-encoded as `binary`.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Environment variable choosing the transport: `stdio`, `tcp` or `unix`.
+pub const TRANSPORT_ENV: &str = "QONTINUI_TRANSPORT";
+
+/// Environment variables telling the bridge where to connect.
+pub const ADDRESS_ENV: &str = "QONTINUI_TRANSPORT_ADDRESS";
+pub const TOKEN_ENV: &str = "QONTINUI_TRANSPORT_TOKEN";
+
+/// How long a bridge has to connect before the runner sticks to stdio.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a pending connection is checked for.
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// How long a connection gets to send the token.
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest first frame read while waiting for the token.
+const MAX_CONNECT_FRAME: usize = 1024;
+
+pub const FRAME_JSON: u8 = 0;
+pub const FRAME_BINARY: u8 = 1;
+
+/// Length and kind of a frame.
+pub const HEADER_LEN: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    Stdio,
+    Tcp,
+    Unix,
+}
+
+impl TransportKind {
+    /// The socket this platform offers: Unix-domain sockets where there
+    /// are, TCP elsewhere.
+    pub fn preferred() -> Self {
+        if cfg!(unix) {
+            TransportKind::Unix
+        } else {
+            TransportKind::Tcp
+        }
+    }
+
+    /// The transport set in `QONTINUI_TRANSPORT`, or the preferred one.
+    pub fn from_env() -> Self {
+        match std::env::var(TRANSPORT_ENV) {
+            Ok(kind) => Self::parse(&kind).unwrap_or_else(|e| {
+                tracing::warn!("{}", e);
+                Self::preferred()
+            }),
+            Err(_) => Self::preferred(),
+        }
+    }
+
+    pub fn parse(kind: &str) -> Result<Self, String> {
+        match kind.to_lowercase().as_str() {
+            "stdio" => Ok(TransportKind::Stdio),
+            "tcp" => Ok(TransportKind::Tcp),
+            "unix" if cfg!(unix) => Ok(TransportKind::Unix),
+            "unix" => Err("Unix-domain sockets aren't available on this platform".to_string()),
+            _ => Err(format!("Unknown transport: {}", kind)),
+        }
+    }
+}
+
+/// Writes one JSON frame.
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+    let mut header = [0u8; HEADER_LEN];
+    header[..4].copy_from_slice(&len.to_be_bytes());
+    header[4] = FRAME_JSON;
+    writer.write_all(&header)?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Reads one frame's kind and payload, or `None` when the stream ends
+/// between frames. Payloads over `max_len` bytes are an error.
+pub fn read_frame(reader: &mut impl Read, max_len: usize) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0u8; HEADER_LEN];
+    match reader.read(&mut header[..1])? {
+        0 => return Ok(None),
+        _ => reader.read_exact(&mut header[1..])?,
+    }
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds {}", len, max_len),
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some((header[4], payload)))
+}
+
+/// Splits a binary frame's payload into its message and bytes.
+pub fn split_binary(payload: &[u8]) -> Result<(&[u8], &[u8]), String> {
+    let Some((len, rest)) = payload.split_first_chunk::<4>() else {
+        return Err("binary frame shorter than its header".to_string());
+    };
+    let len = u32::from_be_bytes(*len) as usize;
+    if len > rest.len() {
+        return Err(format!(
+            "binary frame message of {} bytes exceeds the frame",
+            len
+        ));
+    }
+    Ok(rest.split_at(len))
+}
+
+/// Both ends of a connected bridge.
+pub struct Connection {
+    pub kind: TransportKind,
+    pub reader: Box<dyn Read + Send>,
+    pub writer: Box<dyn Write + Send>,
+}
+
+enum Socket {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+/// A socket the bridge is invited to connect to.
+pub struct Endpoint {
+    socket: Socket,
+    address: String,
+    token: String,
+    /// A Unix-domain socket's file, removed when done.
+    path: Option<PathBuf>,
+}
+
+impl Endpoint {
+    /// Starts listening for the bridge. `None` for stdio.
+    pub fn bind(kind: TransportKind) -> Result<Option<Self>, String> {
+        let token = uuid::Uuid::new_v4().to_string();
+        let (socket, address, path) = match kind {
+            TransportKind::Stdio => return Ok(None),
+            TransportKind::Tcp => {
+                let listener = TcpListener::bind("127.0.0.1:0")
+                    .map_err(|e| format!("Failed to listen for the executor: {}", e))?;
+                let address = listener.local_addr().map_err(|e| e.to_string())?;
+                (Socket::Tcp(listener), format!("tcp://{}", address), None)
+            }
+            #[cfg(unix)]
+            TransportKind::Unix => {
+                let path = std::env::temp_dir().join(format!("qontinui-{}.sock", &token[..8]));
+                let listener = std::os::unix::net::UnixListener::bind(&path)
+                    .map_err(|e| format!("Failed to listen for the executor: {}", e))?;
+                let address = format!("unix://{}", path.display());
+                (Socket::Unix(listener), address, Some(path))
+            }
+            #[cfg(not(unix))]
+            TransportKind::Unix => {
+                return Err("Unix-domain sockets aren't available on this platform".to_string())
+            }
+        };
+        Ok(Some(Self {
+            socket,
+            address,
+            token,
+            path,
+        }))
+    }
+
+    /// Tells the bridge started by `cmd` where to connect, in the
+    /// environment variables named.
+    pub fn configure(&self, cmd: &mut Command, address_env: &str, token_env: &str) {
+        cmd.env(address_env, &self.address)
+            .env(token_env, &self.token);
+    }
+
+    /// Waits up to `timeout` for the bridge to connect and send the token.
+    /// Connections without it are dropped.
+    pub fn accept(self, timeout: Duration) -> Result<Connection, String> {
+        let deadline = Instant::now() + timeout;
+        self.set_nonblocking(true)?;
+        loop {
+            match self.try_accept() {
+                Ok(Some(mut stream)) => match self.check_token(&mut stream) {
+                    Ok(()) => return stream.into_connection().map_err(|e| e.to_string()),
+                    Err(e) => tracing::warn!("Rejected executor connection: {}", e),
+                },
+                Ok(None) => {}
+                Err(e) => return Err(format!("Failed to accept the executor: {}", e)),
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "The executor didn't connect to {} within {:?}",
+                    self.address, timeout
+                ));
+            }
+            std::thread::sleep(ACCEPT_POLL);
+        }
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), String> {
+        match &self.socket {
+            Socket::Tcp(listener) => listener.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            Socket::Unix(listener) => listener.set_nonblocking(nonblocking),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn try_accept(&self) -> io::Result<Option<Stream>> {
+        let accepted = match &self.socket {
+            Socket::Tcp(listener) => listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+            #[cfg(unix)]
+            Socket::Unix(listener) => listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+        };
+        match accepted {
+            Ok(stream) => {
+                stream.prepare()?;
+                Ok(Some(stream))
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The first frame must be `{"type": "connect", "token": ...}`.
+    fn check_token(&self, stream: &mut Stream) -> Result<(), String> {
+        let mut header = [0u8; HEADER_LEN];
+        stream.read_exact(&mut header).map_err(|e| e.to_string())?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if header[4] != FRAME_JSON || len > MAX_CONNECT_FRAME {
+            return Err("unexpected first frame".to_string());
+        }
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).map_err(|e| e.to_string())?;
+        let connect: serde_json::Value =
+            serde_json::from_slice(&payload).map_err(|e| e.to_string())?;
+        match connect["token"].as_str() {
+            Some(token) if token == self.token => Ok(()),
+            _ => Err("wrong token".to_string()),
+        }
+    }
+}
+
+impl Drop for Endpoint {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl Stream {
+    /// Blocking, with the wait for the token bounded.
+    fn prepare(&self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => {
+                stream.set_nonblocking(false)?;
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(TOKEN_TIMEOUT))
+            }
+            #[cfg(unix)]
+            Stream::Unix(stream) => {
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(TOKEN_TIMEOUT))
+            }
+        }
+    }
+
+    fn into_connection(self) -> io::Result<Connection> {
+        match self {
+            Stream::Tcp(stream) => {
+                stream.set_read_timeout(None)?;
+                Ok(Connection {
+                    kind: TransportKind::Tcp,
+                    writer: Box::new(stream.try_clone()?),
+                    reader: Box::new(stream),
+                })
+            }
+            #[cfg(unix)]
+            Stream::Unix(stream) => {
+                stream.set_read_timeout(None)?;
+                Ok(Connection {
+                    kind: TransportKind::Unix,
+                    writer: Box::new(stream.try_clone()?),
+                    reader: Box::new(stream),
+                })
+            }
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Crash detection for the Python executor. A watchdog thread checks that
+//! the process is still alive and sends it a `ping` every few seconds; any
+//! response counts as a heartbeat. A process that exited, or that misses
+//! several heartbeats in a row, is marked as crashed: the bridge stops
+//! reporting it as running and the frontend gets an `executor-crashed`
+//! event with the exit code and the last lines of stderr. Each executor
+//! session has its own watchdog.
+
+use super::protocol::BridgeMessage;
+use super::python_bridge::ExecutorEvent;
+use super::sessions::{self, DEFAULT_SESSION};
+use crate::commands::AppState;
+use crate::{notifications, progress};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, warn};
+
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a ping may go unanswered before it counts as missed.
+pub const HEARTBEAT_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Consecutive missed heartbeats after which the process counts as hung.
+pub const MAX_MISSED_HEARTBEATS: u32 = 3;
+
+/// Longest sleep between checks of the stop flag.
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+/// What the frontend is told when the executor dies.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    pub session_id: String,
+    pub reason: String,
+    /// `None` when the process was killed by a signal or had to be killed
+    /// because it hung.
+    pub exit_code: Option<i32>,
+    pub stderr_tail: Vec<String>,
+    pub detected_at: String,
+}
+
+/// Counts heartbeats missed in a row.
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    missed: u32,
+}
+
+impl Heartbeat {
+    pub fn answered(&mut self) {
+        self.missed = 0;
+    }
+
+    /// Records a missed heartbeat and returns whether the process should
+    /// now be considered hung.
+    pub fn missed(&mut self) -> bool {
+        self.missed += 1;
+        self.missed >= MAX_MISSED_HEARTBEATS
+    }
+
+    pub fn missed_count(&self) -> u32 {
+        self.missed
+    }
+}
+
+/// Watches the executor until `stop` is set, which the bridge does when it
+/// stops the process itself.
+pub fn spawn(app_handle: AppHandle, session_id: &str, stop: Arc<AtomicBool>) {
+    let session_id = session_id.to_string();
+    thread::spawn(move || {
+        let mut heartbeat = Heartbeat::default();
+        while sleep_unless_stopped(HEARTBEAT_INTERVAL, &stop) {
+            let state = app_handle.state::<AppState>();
+            let probe = sessions::with_bridge(&state, &session_id, |bridge| {
+                Ok((!stop.load(Ordering::Relaxed)).then(|| bridge.probe()))
+            });
+            // Stopped, or the session is gone
+            let Ok(Some(probe)) = probe else {
+                return;
+            };
+
+            let reason = match probe {
+                Ok(ping) => match ping.wait(HEARTBEAT_DEADLINE) {
+                    Ok(_) => {
+                        heartbeat.answered();
+                        continue;
+                    }
+                    Err(_) if stop.load(Ordering::Relaxed) => return,
+                    Err(e) => {
+                        warn!("Missed executor heartbeat: {}", e);
+                        if !heartbeat.missed() {
+                            continue;
+                        }
+                        format!(
+                            "Executor stopped responding ({} heartbeats missed)",
+                            heartbeat.missed_count()
+                        )
+                    }
+                },
+                Err(reason) => reason,
+            };
+            report_crash(&app_handle, &session_id, &stop, reason);
+            return;
+        }
+    });
+}
+
+/// Sleeps for `duration`, returning false early if `stop` is set.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) -> bool {
+    let deadline = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(STOP_POLL));
+    }
+    false
+}
+
+fn report_crash(app_handle: &AppHandle, session_id: &str, stop: &AtomicBool, reason: String) {
+    let state = app_handle.state::<AppState>();
+    let report = sessions::with_bridge(&state, session_id, |bridge| {
+        // Stopped on purpose while the last check was in flight
+        Ok((!stop.load(Ordering::Relaxed)).then(|| bridge.mark_crashed(reason)))
+    });
+    let Ok(Some(report)) = report else {
+        return;
+    };
+    error!(
+        "Executor session {} crashed: {} (exit code {:?})",
+        session_id, report.reason, report.exit_code
+    );
+    let _ = app_handle.emit("executor-crashed", &report);
+    end_run(app_handle, session_id, &report.reason);
+    // Restarts are for the default session only
+    if session_id == DEFAULT_SESSION {
+        super::restart::handle_crash(app_handle, &report);
+    }
+}
+
+/// Ends the run of a session whose executor died or was killed. No
+/// `execution_completed` will come from it, so one is raised here for
+/// everything waiting on it.
+pub fn end_run(app_handle: &AppHandle, session_id: &str, error: &str) {
+    let state = app_handle.state::<AppState>();
+    let completed = ExecutorEvent::from_runner(
+        "execution_completed",
+        json!({ "success": false, "error": error }),
+    );
+    notifications::record_event(app_handle, session_id, &completed);
+    if let Some(run_id) = state.run_streams.current_run(session_id) {
+        state.event_timeline.record(&run_id, &completed);
+        state
+            .run_streams
+            .record_message(session_id, &BridgeMessage::Event(completed.clone()));
+    }
+    // Idle gating and progress follow the default session only
+    if session_id != DEFAULT_SESSION || !state.idle_monitor.is_executing() {
+        return;
+    }
+    state.idle_monitor.record_event(&completed);
+    state.run_tracker.record_event(&completed);
+    progress::record_event(app_handle, &completed);
+    state.execution_queue.record_event(&completed);
+    let _ = sessions::emit(
+        app_handle,
+        "executor-event",
+        DEFAULT_SESSION,
+        &BridgeMessage::Event(completed),
+    );
+}
@@ -0,0 +1,319 @@
+use crate::commands::{self, AppState, CommandResponse};
+use crate::executor::backend::Subscriber;
+use crate::executor::protocol::{BridgeMessage, Frame};
+use crate::executor::sessions;
+use crate::tasks::TaskContext;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener, Manager};
+
+/// A scripted sequence of runner operations with expectations, used for
+/// end-to-end tests of the runner itself.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestScript {
+    #[serde(default)]
+    pub name: String,
+    pub steps: Vec<TestStep>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TestStep {
+    StartExecutor {
+        #[serde(default = "default_executor_type")]
+        executor_type: String,
+    },
+    StopExecutor,
+    /// Relative paths are resolved against the script's directory.
+    LoadConfig {
+        path: String,
+        #[serde(default)]
+        transfer_mode: Option<String>,
+    },
+    StartExecution {
+        process_id: String,
+        #[serde(default)]
+        monitor_index: Option<i32>,
+    },
+    StopExecution,
+    /// Waits for an `executor-event` named `event` whose `data` contains
+    /// every field of `data`. Only events after the previous match count.
+    ExpectEvent {
+        event: String,
+        #[serde(default)]
+        data: Option<Value>,
+        #[serde(default = "default_within_secs")]
+        within_secs: f64,
+    },
+    Sleep {
+        millis: u64,
+    },
+}
+
+fn default_executor_type() -> String {
+    "test".to_string()
+}
+
+fn default_within_secs() -> f64 {
+    5.0
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub index: usize,
+    pub step: String,
+    pub passed: bool,
+    pub message: Option<String>,
+    pub duration_ms: u64,
+    /// `duration_ms` formatted for the report's locale.
+    pub duration: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestReport {
+    pub name: String,
+    pub passed: bool,
+    pub steps: Vec<StepResult>,
+    pub skipped: usize,
+    /// Human-readable fields, in the runner's locale.
+    pub locale: String,
+    pub started_at: String,
+    pub duration: String,
+    pub summary: String,
+}
+
+/// Executor events seen while a script runs. The executor's own are taken
+/// from the bridge before they're throttled, so a script sees every one;
+/// the runner's, numbered 0, from what's emitted to the frontend.
+#[derive(Default)]
+struct EventLog {
+    events: Mutex<Vec<Value>>,
+    arrived: Condvar,
+}
+
+impl EventLog {
+    fn push(&self, event: Value) {
+        self.events.lock().unwrap().push(event);
+        self.arrived.notify_all();
+    }
+
+    fn subscriber(log: &Arc<Self>) -> Subscriber {
+        let log = log.clone();
+        Box::new(move |frame| {
+            if let Frame::Message(BridgeMessage::Event(event)) = frame {
+                if event.sequence != 0 {
+                    log.push(serde_json::to_value(&event).unwrap_or_default());
+                }
+            }
+        })
+    }
+
+    /// Finds the first matching event at or after `cursor`, waiting until
+    /// `deadline`. Returns the index of the match.
+    fn wait_for(
+        &self,
+        cursor: usize,
+        event: &str,
+        data: Option<&Value>,
+        deadline: Instant,
+    ) -> Option<usize> {
+        let mut events = self.events.lock().unwrap();
+        let mut checked = cursor;
+        loop {
+            for (index, candidate) in events.iter().enumerate().skip(checked) {
+                if matches_event(candidate, event, data) {
+                    return Some(index);
+                }
+            }
+            checked = checked.max(events.len());
+
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            events = self.arrived.wait_timeout(events, deadline - now).unwrap().0;
+        }
+    }
+}
+
+pub fn load_script(path: &Path) -> Result<TestScript, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read test script {:?}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid test script {:?}: {}", path, e))
+}
+
+/// Runs every step in order, stopping at the first failure. An executor
+/// started by the script is always stopped afterwards.
+pub fn run(
+    script: TestScript,
+    base_dir: PathBuf,
+    app_handle: AppHandle,
+    task: &TaskContext,
+) -> TestReport {
+    let log = Arc::new(EventLog::default());
+    let listener_log = log.clone();
+    let listener = app_handle.listen("executor-event", move |event| {
+        if let Ok(payload) = serde_json::from_str::<Value>(event.payload()) {
+            let from_runner = payload.get("sequence").and_then(Value::as_u64) == Some(0);
+            if sessions::is_default(&payload) && from_runner {
+                listener_log.push(payload);
+            }
+        }
+    });
+
+    let state = app_handle.state::<AppState>();
+    if let Some(bridge) = state.executor.lock().unwrap().as_mut() {
+        bridge.subscribe(EventLog::subscriber(&log));
+    }
+    let locale = state.translations.locale();
+    let formatter = state.translations.formatter(&locale);
+    let started_at = chrono::Local::now();
+    let run_started = Instant::now();
+    let total = script.steps.len();
+    let mut results = Vec::new();
+    let mut cursor = 0;
+    let mut started_executor = false;
+
+    for (index, step) in script.steps.iter().enumerate() {
+        if task.is_cancelled() {
+            break;
+        }
+        task.progress(
+            index as f32 / total as f32,
+            format!("Step {}: {:?}", index + 1, step),
+        );
+
+        let started = Instant::now();
+        let outcome = match step {
+            TestStep::StartExecutor { executor_type } => {
+                started_executor = true;
+                check(commands::start_python_executor_subscribed(
+                    app_handle.clone(),
+                    state.clone(),
+                    executor_type.clone(),
+                    None,
+                    Some(EventLog::subscriber(&log)),
+                ))
+            }
+            TestStep::StopExecutor => {
+                started_executor = false;
+                check(commands::stop_python_executor(state.clone()))
+            }
+            TestStep::LoadConfig {
+                path,
+                transfer_mode,
+            } => check(commands::load_configuration(
+                base_dir.join(path).to_string_lossy().into_owned(),
+                transfer_mode.clone(),
+                None,
+                app_handle.clone(),
+                state.clone(),
+            )),
+            TestStep::StartExecution {
+                process_id,
+                monitor_index,
+            } => check(commands::start_execution(
+                Some(process_id.clone()),
+                *monitor_index,
+                None,
+                state.clone(),
+            )),
+            TestStep::StopExecution => check(commands::stop_execution(state.clone())),
+            TestStep::ExpectEvent {
+                event,
+                data,
+                within_secs,
+            } => {
+                let deadline = Instant::now() + Duration::from_secs_f64(within_secs.max(0.0));
+                match log.wait_for(cursor, event, data.as_ref(), deadline) {
+                    Some(matched) => {
+                        cursor = matched + 1;
+                        Ok(())
+                    }
+                    None => Err(format!(
+                        "Event '{}' not received within {} s",
+                        event, within_secs
+                    )),
+                }
+            }
+            TestStep::Sleep { millis } => {
+                std::thread::sleep(Duration::from_millis(*millis));
+                Ok(())
+            }
+        };
+
+        let passed = outcome.is_ok();
+        let elapsed = started.elapsed();
+        results.push(StepResult {
+            index,
+            step: format!("{:?}", step),
+            passed,
+            message: outcome.err(),
+            duration_ms: elapsed.as_millis() as u64,
+            duration: formatter.duration(elapsed),
+        });
+        if !passed {
+            break;
+        }
+    }
+
+    app_handle.unlisten(listener);
+    if started_executor {
+        let _ = commands::stop_python_executor(state.clone());
+    }
+    if let Some(bridge) = state.executor.lock().unwrap().as_mut() {
+        bridge.unsubscribe();
+    }
+
+    let passed_steps = results.iter().filter(|r| r.passed).count();
+    TestReport {
+        name: script.name,
+        passed: passed_steps == total,
+        skipped: total - results.len(),
+        steps: results,
+        started_at: formatter.timestamp(&started_at),
+        duration: formatter.duration(run_started.elapsed()),
+        summary: formatter.text(
+            "report.steps_passed",
+            &[
+                ("passed", passed_steps.to_string()),
+                ("total", total.to_string()),
+            ],
+        ),
+        locale,
+    }
+}
+
+fn check(result: Result<CommandResponse, String>) -> Result<(), String> {
+    match result {
+        Ok(response) if response.success => Ok(()),
+        Ok(response) => Err(response
+            .message
+            .unwrap_or_else(|| "Command reported failure".to_string())),
+        Err(e) => Err(e),
+    }
+}
+
+fn matches_event(candidate: &Value, event: &str, data: Option<&Value>) -> bool {
+    if candidate.get("event").and_then(Value::as_str) != Some(event) {
+        return false;
+    }
+    match data {
+        Some(expected) => contains(candidate.get("data").unwrap_or(&Value::Null), expected),
+        None => true,
+    }
+}
+
+/// True when every field in `expected` is present in `actual` with an equal
+/// (or, for objects, recursively contained) value.
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| contains(a, value))),
+        _ => actual == expected,
+    }
+}
@@ -0,0 +1,106 @@
+//! Global hotkeys, which work while an automation has the mouse and the
+//! Stop button can't be reached. The shortcuts are kept in the app
+//! settings: `start` runs the last workflow again, `stop` stops the
+//! execution and `abort` is the panic key that takes the executor down
+//! at once. Both also stop an input replay.
+
+use crate::commands::{self, AppState};
+use crate::settings::HotkeySettings;
+use crate::tray;
+use serde::Serialize;
+use std::str::FromStr;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyAction {
+    Start,
+    Stop,
+    Abort,
+}
+
+/// The shortcut of each action that has one. Fails on a shortcut that
+/// can't be parsed or is given to two actions.
+pub fn bindings(hotkeys: &HotkeySettings) -> Result<Vec<(HotkeyAction, Shortcut)>, String> {
+    let mut bindings: Vec<(HotkeyAction, Shortcut)> = Vec::new();
+    for (action, key) in [
+        (HotkeyAction::Start, &hotkeys.start),
+        (HotkeyAction::Stop, &hotkeys.stop),
+        (HotkeyAction::Abort, &hotkeys.abort),
+    ] {
+        let Some(key) = key.as_deref().filter(|key| !key.trim().is_empty()) else {
+            continue;
+        };
+        let shortcut = Shortcut::from_str(key)
+            .map_err(|e| format!("Invalid hotkey '{}' for {:?}: {}", key, action, e))?;
+        if let Some((other, _)) = bindings.iter().find(|(_, bound)| *bound == shortcut) {
+            return Err(format!(
+                "Hotkey '{}' is used for both {:?} and {:?}",
+                key, other, action
+            ));
+        }
+        bindings.push((action, shortcut));
+    }
+    Ok(bindings)
+}
+
+/// Replaces the registered shortcuts with `hotkeys`.
+pub fn apply(app_handle: &AppHandle, hotkeys: &HotkeySettings) -> Result<(), String> {
+    let bindings = bindings(hotkeys)?;
+    let shortcuts = app_handle.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister hotkeys: {}", e))?;
+    for (action, shortcut) in bindings {
+        shortcuts
+            .on_shortcut(shortcut, move |app_handle, _, event| {
+                if event.state() == ShortcutState::Pressed {
+                    trigger(app_handle, action);
+                }
+            })
+            .map_err(|e| format!("Failed to register the {:?} hotkey: {}", action, e))?;
+    }
+    Ok(())
+}
+
+/// Registers the hotkeys from the settings.
+pub fn start(app_handle: &AppHandle) {
+    let hotkeys = app_handle.state::<AppState>().settings.hotkeys();
+    if let Err(e) = apply(app_handle, &hotkeys) {
+        warn!("{}", e);
+    }
+}
+
+fn trigger(app_handle: &AppHandle, action: HotkeyAction) {
+    info!("{:?} hotkey pressed", action);
+    let handle = app_handle.clone();
+    // Off the thread delivering the shortcut; stopping waits on the executor
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = handle.state::<AppState>();
+        if action != HotkeyAction::Start {
+            state.replay.stop();
+        }
+        let result = match action {
+            HotkeyAction::Start => {
+                let session = state.session.current();
+                commands::start_execution(
+                    session.workflow_id,
+                    session.monitor_index,
+                    session.execution_request,
+                    state,
+                )
+            }
+            HotkeyAction::Stop => commands::stop_execution(state),
+            HotkeyAction::Abort => commands::abort_execution(handle.clone(), state),
+        };
+        let message = match result {
+            Ok(response) if response.success => return,
+            Ok(response) => response.message.unwrap_or_default(),
+            Err(e) => e,
+        };
+        warn!("{:?} hotkey failed: {}", action, message);
+        tray::notify(&handle, "Hotkey failed", &message);
+    });
+}
@@ -0,0 +1,552 @@
+//! Optional local HTTP API, so external tools and test harnesses can drive
+//! the runner. It's off by default; enabled in the app settings it listens
+//! on 127.0.0.1 only, or on every interface for an agent (see `agent`)
+//! with a TLS certificate, and every request needs the settings' token,
+//! either as `Authorization: Bearer <token>` or, for `EventSource` clients
+//! that can't set headers, as `?token=<token>`. With a certificate and key
+//! in the settings it's served over HTTPS.
+//!
+//! The endpoints mirror the Tauri commands and answer with the same
+//! `CommandResponse`:
+//!
+//! - `GET /status`
+//! - `POST /config/load` with `{"path"}`
+//! - `POST /executor/start` with `{"executor_type"}`, `POST /executor/stop`
+//! - `POST /execution/start` with `{"process_id", "monitor_index"}`,
+//!   `POST /execution/stop`
+//! - `GET /events`: the frontend events in `FORWARDED_EVENTS`, as
+//!   server-sent events named after them
+//! - `GET /ws`: the same events over a WebSocket, one JSON text message
+//!   each with the executor session and run they belong to:
+//!   `{"event", "session_id", "run_id", "payload"}`. The run is only known
+//!   for the default session.
+//!
+//!   A client too slow to keep up on either is sent `stream-lagged` with
+//!   `{"dropped"}`, how many events it missed; `events-dropped` reports
+//!   events lost between the executor and the runner.
+//! - `POST /jobs` with `{"workflows", "config" or "config_path",
+//!   "executor_type", "monitor_index", "label"}` queues a job, `GET /jobs`
+//!   and `GET /jobs/{id}` report on them
+//! - `GET /logs?lines=<n>`: the end of the runner's log
+
+use crate::agent::{self, JobRequest};
+use crate::commands::{self, AppState, CommandResponse};
+use crate::executor::execution_request::ExecutionRequest;
+use crate::executor::sessions::DEFAULT_SESSION;
+use crate::settings::HttpApiSettings;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use futures_util::stream::{self, Stream, StreamExt};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex, OnceLock};
+use subtle::ConstantTimeEq;
+use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Frontend events passed on to API clients.
+pub const FORWARDED_EVENTS: &[&str] = &[
+    "executor-event",
+    "executor-response",
+    "executor-lifecycle",
+    "executor-crashed",
+    "executor-restart",
+    "bridge-log",
+    "events-dropped",
+    "config-reloaded",
+    "queue-progress",
+    "schedule-run",
+    "preview-frame",
+    "preview-stopped",
+];
+
+/// Events buffered per client; a client that falls further behind is told
+/// how many it missed.
+const EVENT_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardedEvent {
+    #[serde(rename = "event")]
+    pub name: &'static str,
+    pub session_id: String,
+    /// Run of the event's session it was emitted during, or after until
+    /// the next run starts.
+    pub run_id: Option<String>,
+    pub payload: Value,
+}
+
+impl ForwardedEvent {
+    /// Reads the session from the payload's `session_id`; untagged events
+    /// belong to the default session. `latest_run` looks up a session's
+    /// run.
+    pub fn new(
+        name: &'static str,
+        payload: &str,
+        latest_run: impl FnOnce(&str) -> Option<String>,
+    ) -> Self {
+        let payload: Value = serde_json::from_str(payload).unwrap_or(Value::Null);
+        let session_id = payload
+            .get("session_id")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_SESSION)
+            .to_string();
+        let run_id = latest_run(&session_id);
+        Self {
+            name,
+            session_id,
+            run_id,
+            payload,
+        }
+    }
+}
+
+struct Server {
+    settings: HttpApiSettings,
+    shutdown: watch::Sender<bool>,
+}
+
+#[derive(Default)]
+pub struct HttpApi {
+    server: Mutex<Option<Server>>,
+    events: OnceLock<broadcast::Sender<ForwardedEvent>>,
+}
+
+impl HttpApi {
+    /// Starts, restarts or stops the server to match `settings`.
+    pub fn apply(&self, app_handle: &AppHandle, settings: &HttpApiSettings) -> Result<(), String> {
+        let mut server = self.server.lock().unwrap();
+        if let Some(running) = server.as_ref() {
+            if settings.enabled && running.settings == *settings {
+                return Ok(());
+            }
+        }
+        if let Some(running) = server.take() {
+            let _ = running.shutdown.send(true);
+            info!("HTTP API stopped");
+        }
+        if !settings.enabled {
+            return Ok(());
+        }
+        if settings.token.is_empty() {
+            return Err("The HTTP API has no token; turn it on again to generate one".to_string());
+        }
+
+        // The token mustn't cross the network in clear text
+        let address = if agent::active() && settings.tls() {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        if agent::active() && !settings.tls() {
+            warn!(
+                "The agent's HTTP API only listens on 127.0.0.1: set a TLS certificate \
+                 (tls_cert and tls_key in the settings, or --tls-cert and --tls-key with \
+                 --install-agent) to accept clients from other machines"
+            );
+        }
+        let tls = settings.tls().then(|| tls_config(settings)).transpose()?;
+        // Bound here so a port in use is reported to the caller
+        let listener = std::net::TcpListener::bind((address, settings.port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .map_err(|e| format!("Failed to listen on port {}: {}", settings.port, e))?;
+        let (shutdown, stopped) = watch::channel(false);
+        let router = router(ApiState {
+            app_handle: app_handle.clone(),
+            token: settings.token.as_str().into(),
+            stopped: stopped.clone(),
+        });
+        let mut signal = stopped;
+        tauri::async_runtime::spawn(async move {
+            let served = match tls {
+                Some(tls) => {
+                    let handle = axum_server::Handle::new();
+                    let stop = handle.clone();
+                    tokio::spawn(async move {
+                        let _ = signal.changed().await;
+                        stop.graceful_shutdown(None);
+                    });
+                    axum_server::from_tcp_rustls(listener, RustlsConfig::from_config(tls))
+                        .handle(handle)
+                        .serve(router.into_make_service())
+                        .await
+                }
+                None => match tokio::net::TcpListener::from_std(listener) {
+                    Ok(listener) => {
+                        axum::serve(listener, router)
+                            .with_graceful_shutdown(async move {
+                                let _ = signal.changed().await;
+                            })
+                            .await
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            if let Err(e) = served {
+                warn!("HTTP API stopped with an error: {}", e);
+            }
+        });
+
+        info!(
+            "HTTP API listening on {}:{}{}",
+            address,
+            settings.port,
+            if settings.tls() { " over HTTPS" } else { "" }
+        );
+        *server = Some(Server {
+            settings: settings.clone(),
+            shutdown,
+        });
+        Ok(())
+    }
+
+    /// Port the API is listening on, if it's running.
+    pub fn port(&self) -> Option<u16> {
+        self.server
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|server| server.settings.port)
+    }
+
+    /// Receives the forwarded events from now on. The listeners are hooked
+    /// up on first use.
+    pub fn subscribe(&self, app_handle: &AppHandle) -> broadcast::Receiver<ForwardedEvent> {
+        self.events
+            .get_or_init(|| {
+                let (sender, _) = broadcast::channel(EVENT_BUFFER);
+                for &name in FORWARDED_EVENTS {
+                    let sender = sender.clone();
+                    let handle = app_handle.clone();
+                    app_handle.listen(name, move |event| {
+                        let streams = &handle.state::<AppState>().run_streams;
+                        let _ =
+                            sender.send(ForwardedEvent::new(name, event.payload(), |session| {
+                                streams.latest_run(session)
+                            }));
+                    });
+                }
+                sender
+            })
+            .subscribe()
+    }
+}
+
+/// The server side of HTTPS, from the settings' PEM files.
+pub fn tls_config(settings: &HttpApiSettings) -> Result<Arc<ServerConfig>, String> {
+    let (Some(cert), Some(key)) = (&settings.tls_cert, &settings.tls_key) else {
+        return Err("HTTPS needs both a certificate and its private key".to_string());
+    };
+    let certificates = CertificateDer::pem_file_iter(cert)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Can't read {}: {}", cert.display(), e))?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| format!("Can't read {}: {}", key.display(), e))?;
+    let config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_no_client_auth()
+            .with_single_cert(certificates, key)
+            .map_err(|e| format!("Invalid certificate or key: {}", e))?;
+    Ok(Arc::new(config))
+}
+
+/// Starts the API if the settings have it enabled.
+pub fn start(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let settings = if agent::active() {
+        match agent::api_settings(&state.settings) {
+            Ok(settings) => settings,
+            Err(e) => return warn!("{}", e),
+        }
+    } else {
+        state.settings.http_api()
+    };
+    if let Err(e) = state.http_api.apply(app_handle, &settings) {
+        warn!("{}", e);
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app_handle: AppHandle,
+    token: Arc<str>,
+    /// Changes when the server is stopped, to end event streams.
+    stopped: watch::Receiver<bool>,
+}
+
+fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/config/load", post(load_config))
+        .route("/executor/start", post(start_executor))
+        .route("/executor/stop", post(stop_executor))
+        .route("/execution/start", post(start_execution))
+        .route("/execution/stop", post(stop_execution))
+        .route("/events", get(events))
+        .route("/ws", get(websocket))
+        .route("/jobs", get(list_jobs).post(submit_job))
+        .route("/jobs/{id}", get(get_job))
+        .route("/logs", get(logs))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+async fn authorize(State(api): State<ApiState>, request: Request, next: Next) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request.uri().query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "token")
+            .map(|(_, value)| value.into_owned())
+    });
+    let presented = bearer.map(str::to_string).or(query);
+    if !presented.is_some_and(|token| token_matches(&token, &api.token)) {
+        return failure(
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong API token".to_string(),
+        );
+    }
+    next.run(request).await
+}
+
+/// Compares in constant time so response timings don't reveal how much of
+/// a guess was right. An empty token never matches.
+pub fn token_matches(presented: &str, token: &str) -> bool {
+    !token.is_empty() && bool::from(presented.as_bytes().ct_eq(token.as_bytes()))
+}
+
+/// Runs a command off the async runtime; they block on the bridge.
+async fn call<F>(api: ApiState, command: F) -> Response
+where
+    F: FnOnce(&AppHandle) -> Result<CommandResponse, String> + Send + 'static,
+{
+    let app_handle = api.app_handle.clone();
+    match tokio::task::spawn_blocking(move || command(&app_handle)).await {
+        Ok(Ok(response)) => Json(response).into_response(),
+        Ok(Err(e)) => failure(StatusCode::BAD_REQUEST, e),
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+fn failure(status: StatusCode, message: String) -> Response {
+    let body = CommandResponse {
+        success: false,
+        message: Some(message),
+        data: None,
+    };
+    (status, Json(body)).into_response()
+}
+
+async fn status(State(api): State<ApiState>) -> Response {
+    call(api, |app| commands::get_executor_status(app.state())).await
+}
+
+#[derive(Deserialize)]
+struct LoadConfig {
+    path: String,
+    transfer_mode: Option<String>,
+    confirm_large: Option<bool>,
+}
+
+async fn load_config(State(api): State<ApiState>, Json(body): Json<LoadConfig>) -> Response {
+    call(api, move |app| {
+        commands::load_configuration(
+            body.path,
+            body.transfer_mode,
+            body.confirm_large,
+            app.clone(),
+            app.state(),
+        )
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct StartExecutor {
+    #[serde(default = "default_executor_type")]
+    executor_type: String,
+}
+
+fn default_executor_type() -> String {
+    "simple".to_string()
+}
+
+async fn start_executor(State(api): State<ApiState>, Json(body): Json<StartExecutor>) -> Response {
+    call(api, move |app| {
+        commands::start_python_executor_with_type(
+            app.clone(),
+            app.state(),
+            body.executor_type,
+            None,
+        )
+    })
+    .await
+}
+
+async fn stop_executor(State(api): State<ApiState>) -> Response {
+    call(api, |app| commands::stop_python_executor(app.state())).await
+}
+
+#[derive(Deserialize)]
+struct StartExecution {
+    process_id: String,
+    monitor_index: Option<i32>,
+    request: Option<ExecutionRequest>,
+}
+
+async fn start_execution(
+    State(api): State<ApiState>,
+    Json(body): Json<StartExecution>,
+) -> Response {
+    call(api, move |app| {
+        commands::start_execution(
+            Some(body.process_id),
+            body.monitor_index,
+            body.request,
+            app.state(),
+        )
+    })
+    .await
+}
+
+async fn stop_execution(State(api): State<ApiState>) -> Response {
+    call(api, |app| commands::stop_execution(app.state())).await
+}
+
+async fn submit_job(State(api): State<ApiState>, Json(body): Json<JobRequest>) -> Response {
+    call(api, move |app| {
+        let job = agent::submit(app, body)?;
+        Ok(CommandResponse {
+            success: true,
+            message: Some(format!("Queued {} runs", job.runs.len())),
+            data: Some(json!(job)),
+        })
+    })
+    .await
+}
+
+async fn list_jobs(State(api): State<ApiState>) -> Response {
+    let jobs = api.app_handle.state::<AppState>().agent_jobs.list();
+    Json(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(json!(jobs)),
+    })
+    .into_response()
+}
+
+async fn get_job(State(api): State<ApiState>, Path(id): Path<String>) -> Response {
+    let Some(job) = api.app_handle.state::<AppState>().agent_jobs.get(&id) else {
+        return failure(StatusCode::NOT_FOUND, format!("No job {}", id));
+    };
+    Json(CommandResponse {
+        success: true,
+        message: None,
+        data: Some(json!(job)),
+    })
+    .into_response()
+}
+
+#[derive(Deserialize)]
+struct Logs {
+    lines: Option<usize>,
+}
+
+async fn logs(State(api): State<ApiState>, Query(query): Query<Logs>) -> Response {
+    call(api, move |_| {
+        let lines = agent::tail_log(query.lines.unwrap_or(agent::DEFAULT_LOG_LINES))?;
+        Ok(CommandResponse {
+            success: true,
+            message: None,
+            data: Some(json!({ "lines": lines })),
+        })
+    })
+    .await
+}
+
+async fn events(State(api): State<ApiState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = api
+        .app_handle
+        .state::<AppState>()
+        .http_api
+        .subscribe(&api.app_handle);
+    let mut stopped = api.stopped.clone();
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(event) => Event::default()
+                .event(event.name)
+                .data(event.payload.to_string()),
+            Err(RecvError::Lagged(missed)) => Event::default()
+                .event("stream-lagged")
+                .data(format!("{{\"dropped\":{}}}", missed)),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    })
+    .take_until(async move {
+        let _ = stopped.changed().await;
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn websocket(State(api): State<ApiState>, upgrade: WebSocketUpgrade) -> Response {
+    let receiver = api
+        .app_handle
+        .state::<AppState>()
+        .http_api
+        .subscribe(&api.app_handle);
+    upgrade.on_upgrade(move |socket| forward(socket, receiver, api.stopped))
+}
+
+/// Sends events to a WebSocket client until it disconnects or the server
+/// stops.
+async fn forward(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<ForwardedEvent>,
+    mut stopped: watch::Receiver<bool>,
+) {
+    loop {
+        let message = tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => json!(event),
+                Err(RecvError::Lagged(missed)) => json!({
+                    "event": "stream-lagged",
+                    "payload": { "dropped": missed },
+                }),
+                Err(RecvError::Closed) => break,
+            },
+            // Clients only listen; anything but a close is ignored
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            _ = stopped.changed() => break,
+        };
+        if socket
+            .send(Message::Text(message.to_string().into()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
@@ -0,0 +1,276 @@
+//! Localization. Translation catalogs are flat JSON files bundled as app
+//! resources under `locales/`, one per locale (`de.json`, `en-US.json`).
+//! Lookups fall back from the region to the language to English, and the
+//! catalogs also carry the date patterns and duration units used when
+//! formatting reports.
+
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Used when the bundled English catalog can't be read.
+const EMBEDDED_FALLBACK: &str = include_str!("../locales/en.json");
+
+pub type Catalog = BTreeMap<String, String>;
+
+/// Turns an OS locale such as `de_DE.UTF-8` or `en-us` into a tag like
+/// `de-DE`. `C` and `POSIX` mean no preference.
+pub fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default().trim();
+    if tag.is_empty() || tag == "C" || tag == "POSIX" {
+        return None;
+    }
+
+    let mut parts = tag.split(['_', '-']);
+    let language = parts.next()?.to_ascii_lowercase();
+    if language.len() < 2 || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    match parts.next() {
+        Some(region) if !region.is_empty() => {
+            Some(format!("{}-{}", language, region.to_ascii_uppercase()))
+        }
+        _ => Some(language),
+    }
+}
+
+/// Locales whose catalogs make up `locale`, most specific first:
+/// `de-AT` reads `de-AT`, then `de`, then `en`.
+pub fn fallback_chain(locale: &str) -> Vec<String> {
+    let mut chain = vec![locale.to_string()];
+    if let Some((language, _)) = locale.split_once('-') {
+        chain.push(language.to_string());
+    }
+    if !chain.iter().any(|l| l == FALLBACK_LOCALE) {
+        chain.push(FALLBACK_LOCALE.to_string());
+    }
+    chain
+}
+
+/// The user's locale from the environment or the OS settings.
+pub fn detect_locale() -> String {
+    let from_env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find_map(|value| normalize(&value));
+    from_env
+        .or_else(|| platform_locale().as_deref().and_then(normalize))
+        .unwrap_or_else(|| FALLBACK_LOCALE.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn platform_locale() -> Option<String> {
+    command_output(Command::new("powershell").args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "(Get-Culture).Name",
+    ]))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_locale() -> Option<String> {
+    command_output(Command::new("defaults").args(["read", "-g", "AppleLocale"]))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn platform_locale() -> Option<String> {
+    None
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn command_output(cmd: &mut Command) -> Option<String> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The active locale and the catalogs read so far.
+#[derive(Default)]
+pub struct Translations {
+    dir: Mutex<Option<PathBuf>>,
+    chosen: Mutex<Option<String>>,
+    detected: OnceLock<String>,
+    catalogs: Mutex<HashMap<String, Option<Arc<Catalog>>>>,
+}
+
+impl Translations {
+    /// Directory holding the catalogs, normally `locales/` in the app's
+    /// resource directory.
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.lock().unwrap() = Some(dir);
+        self.catalogs.lock().unwrap().clear();
+    }
+
+    /// The chosen locale, or the detected one if none was chosen.
+    /// Detection is deferred to first use since it can shell out.
+    pub fn locale(&self) -> String {
+        self.chosen().unwrap_or_else(|| {
+            self.detected
+                .get_or_init(|| {
+                    let detected = detect_locale();
+                    info!("Detected locale {}", detected);
+                    detected
+                })
+                .clone()
+        })
+    }
+
+    /// The locale picked by the user, if any.
+    pub fn chosen(&self) -> Option<String> {
+        self.chosen.lock().unwrap().clone()
+    }
+
+    /// Picks a locale, or goes back to the detected one with `None`.
+    pub fn choose(&self, locale: Option<&str>) -> Result<Option<String>, String> {
+        let normalized = locale
+            .map(|locale| normalize(locale).ok_or_else(|| format!("Invalid locale '{}'", locale)))
+            .transpose()?;
+        *self.chosen.lock().unwrap() = normalized.clone();
+        Ok(normalized)
+    }
+
+    /// Locales with a bundled catalog, sorted.
+    pub fn available(&self) -> Vec<String> {
+        let dir = self.dir.lock().unwrap().clone();
+        let mut locales: Vec<String> = dir
+            .and_then(|dir| std::fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .collect();
+        if !locales.iter().any(|l| l == FALLBACK_LOCALE) {
+            locales.push(FALLBACK_LOCALE.to_string());
+        }
+        locales.sort();
+        locales
+    }
+
+    /// All strings for `locale`, with missing ones filled in from its
+    /// language and then English.
+    pub fn catalog(&self, locale: &str) -> Catalog {
+        let mut merged = Catalog::new();
+        for name in fallback_chain(locale).iter().rev() {
+            if let Some(catalog) = self.load(name) {
+                merged.extend(catalog.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+        }
+        merged
+    }
+
+    pub fn formatter(&self, locale: &str) -> Formatter {
+        Formatter::new(self.catalog(locale))
+    }
+
+    fn load(&self, name: &str) -> Option<Arc<Catalog>> {
+        let mut catalogs = self.catalogs.lock().unwrap();
+        if let Some(cached) = catalogs.get(name) {
+            return cached.clone();
+        }
+
+        let dir = self.dir.lock().unwrap().clone();
+        let content =
+            dir.and_then(|dir| std::fs::read_to_string(dir.join(format!("{}.json", name))).ok());
+        let content = match content {
+            Some(content) => Some(content),
+            None if name == FALLBACK_LOCALE => Some(EMBEDDED_FALLBACK.to_string()),
+            None => None,
+        };
+        let catalog = content.and_then(|content| {
+            serde_json::from_str::<Catalog>(&content)
+                .map_err(|e| warn!("Ignoring invalid catalog for {}: {}", name, e))
+                .ok()
+                .map(Arc::new)
+        });
+        catalogs.insert(name.to_string(), catalog.clone());
+        catalog
+    }
+}
+
+/// Formats text, timestamps and durations for one locale.
+#[derive(Debug, Clone)]
+pub struct Formatter {
+    catalog: Catalog,
+}
+
+impl Formatter {
+    pub fn new(catalog: Catalog) -> Self {
+        Self { catalog }
+    }
+
+    /// The string for `key` with `{name}` placeholders filled from `args`;
+    /// the key itself if there's no translation.
+    pub fn text(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self
+            .catalog
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string());
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+
+    pub fn timestamp(&self, time: &DateTime<Local>) -> String {
+        let pattern = self
+            .catalog
+            .get("format.datetime")
+            .map(String::as_str)
+            .unwrap_or("%Y-%m-%d %H:%M:%S");
+        time.format(pattern).to_string()
+    }
+
+    /// Milliseconds under a second, tenths of a second under a minute,
+    /// then minutes and seconds, then hours and minutes.
+    pub fn duration(&self, duration: Duration) -> String {
+        let millis = duration.as_millis();
+        if millis < 1000 {
+            return self.text("duration.milliseconds", &[("value", millis.to_string())]);
+        }
+        let seconds = duration.as_secs();
+        if seconds < 60 {
+            let separator = self
+                .catalog
+                .get("format.decimal_separator")
+                .map(String::as_str)
+                .unwrap_or(".");
+            let value = format!("{:.1}", duration.as_secs_f64()).replace('.', separator);
+            return self.text("duration.seconds", &[("value", value)]);
+        }
+        if seconds < 3600 {
+            return self.text(
+                "duration.minutes",
+                &[
+                    ("minutes", (seconds / 60).to_string()),
+                    ("seconds", (seconds % 60).to_string()),
+                ],
+            );
+        }
+        self.text(
+            "duration.hours",
+            &[
+                ("hours", (seconds / 3600).to_string()),
+                ("minutes", (seconds % 3600 / 60).to_string()),
+            ],
+        )
+    }
+}
@@ -0,0 +1,401 @@
+//! User-idle detection, so background runs wait until nobody is using the
+//! machine and pause when someone comes back.
+//!
+//! The OS idle timer is reset by the executor's own synthetic input too, so
+//! `IdleMonitor` follows the executor's input actions and activity that
+//! coincides with one isn't counted as the user's.
+
+use crate::commands::AppState;
+use crate::executor::backend::ExecutorBackend;
+use crate::executor::input_limits::InputKind;
+use crate::executor::protocol::BridgeMessage;
+use crate::executor::python_bridge::ExecutorEvent;
+use crate::executor::sessions::{self, DEFAULT_SESSION};
+use crate::tasks::TaskContext;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// How often idle time is sampled while a gated run waits or runs.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long to wait for the executor to report a gated run as started.
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Input reported this soon after an executor action ends is still
+/// attributed to the executor.
+const SYNTHETIC_GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdlePolicy {
+    /// Idle time required before a gated run starts or resumes.
+    pub min_idle_minutes: u32,
+    /// Pause a gated run when the user touches the mouse or keyboard.
+    pub pause_on_activity: bool,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            min_idle_minutes: 5,
+            pause_on_activity: true,
+        }
+    }
+}
+
+impl IdlePolicy {
+    pub fn min_idle(&self) -> Duration {
+        Duration::from_secs(self.min_idle_minutes as u64 * 60)
+    }
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    executing: bool,
+    /// Input actions started and not yet completed.
+    inputs_in_flight: usize,
+    last_input_end: Option<Instant>,
+}
+
+/// Execution and synthetic input state, fed from the executor's events.
+#[derive(Clone, Default)]
+pub struct IdleMonitor {
+    state: Arc<Mutex<MonitorState>>,
+}
+
+impl IdleMonitor {
+    pub fn record_event(&self, event: &ExecutorEvent) {
+        let mut state = self.state.lock().unwrap();
+        match event.event.as_str() {
+            "execution_started" => state.executing = true,
+            "execution_completed" => {
+                state.executing = false;
+                state.inputs_in_flight = 0;
+            }
+            "action_started" if is_input(event) => state.inputs_in_flight += 1,
+            "action_completed" if state.inputs_in_flight > 0 => {
+                state.inputs_in_flight -= 1;
+                state.last_input_end = Some(Instant::now());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_executing(&self) -> bool {
+        self.state.lock().unwrap().executing
+    }
+
+    /// Whether the input that last reset the idle timer, `idle` ago, was
+    /// the user's rather than the executor's.
+    pub fn is_user_activity(&self, idle: Duration, now: Instant) -> bool {
+        let state = self.state.lock().unwrap();
+        is_user_activity(idle, state.inputs_in_flight, state.last_input_end, now)
+    }
+}
+
+/// Feeds an executor event to the app's `IdleMonitor`.
+pub fn record_event(app_handle: &AppHandle, event: &ExecutorEvent) {
+    app_handle
+        .state::<AppState>()
+        .idle_monitor
+        .record_event(event);
+}
+
+fn is_input(event: &ExecutorEvent) -> bool {
+    event
+        .data
+        .get("action_type")
+        .and_then(|t| t.as_str())
+        .and_then(InputKind::of)
+        .is_some()
+}
+
+/// Input within the last poll counts as the user's unless an executor
+/// input action was running or had just finished when it happened.
+pub fn is_user_activity(
+    idle: Duration,
+    inputs_in_flight: usize,
+    last_input_end: Option<Instant>,
+    now: Instant,
+) -> bool {
+    if idle >= POLL_INTERVAL || inputs_in_flight > 0 {
+        return false;
+    }
+    let Some(input_at) = now.checked_sub(idle) else {
+        return true;
+    };
+    match last_input_end {
+        Some(end) => input_at > end + SYNTHETIC_GRACE,
+        None => true,
+    }
+}
+
+/// Calls `start` once the machine has been idle long enough, then watches
+/// the run until it completes, pausing the executor while the user is
+/// active. Each step is reported as an `idle_gate` executor event, and
+/// `on_wait` hears why the run is waiting and for how much longer at least.
+pub fn run_when_idle(
+    app_handle: &AppHandle,
+    task: &TaskContext,
+    policy: &IdlePolicy,
+    on_wait: impl Fn(&str, Duration),
+    start: impl FnOnce() -> Result<(), String>,
+) -> Result<Value, String> {
+    let monitor = app_handle.state::<AppState>().idle_monitor.clone();
+    let mut waited = wait_for_idle(app_handle, task, policy, "start", &on_wait)?;
+    start()?;
+    notify(
+        app_handle,
+        "started",
+        "Machine is idle, run started".to_string(),
+        None,
+        policy,
+    );
+
+    let started = Instant::now();
+    while !monitor.is_executing() {
+        if started.elapsed() > START_TIMEOUT {
+            warn!("Gated run was not reported as started; no longer watching for activity");
+            return Ok(json!({ "waited_seconds": waited.as_secs(), "pauses": 0 }));
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    let mut pauses = 0;
+    while monitor.is_executing() {
+        if !sleep_unless_cancelled(task, POLL_INTERVAL) {
+            with_bridge(app_handle, |bridge| bridge.stop_execution());
+            return Err("Cancelled".to_string());
+        }
+        if !policy.pause_on_activity || !monitor.is_executing() {
+            continue;
+        }
+        let idle = match idle_time() {
+            Ok(idle) => idle,
+            Err(e) => {
+                warn!("Can't check for user activity: {}", e);
+                continue;
+            }
+        };
+        if !monitor.is_user_activity(idle, Instant::now()) {
+            continue;
+        }
+
+        let reason = format!(
+            "User activity detected, paused until the machine has been idle for {} min",
+            policy.min_idle_minutes
+        );
+        info!("{}", reason);
+        with_bridge(app_handle, |bridge| bridge.pause_execution(&reason));
+        notify(app_handle, "paused", reason, Some(idle), policy);
+        pauses += 1;
+
+        let resumed = wait_for_idle(app_handle, task, policy, "resume", &on_wait);
+        if resumed.is_err() {
+            with_bridge(app_handle, |bridge| bridge.stop_execution());
+        }
+        waited += resumed?;
+        with_bridge(app_handle, |bridge| bridge.resume_execution());
+        notify(
+            app_handle,
+            "resumed",
+            "Machine is idle, run resumed".to_string(),
+            None,
+            policy,
+        );
+    }
+
+    Ok(json!({ "waited_seconds": waited.as_secs(), "pauses": pauses }))
+}
+
+/// Blocks until the idle time reaches the policy minimum, emitting a
+/// `waiting` event with each sample. Returns how long it waited.
+fn wait_for_idle(
+    app_handle: &AppHandle,
+    task: &TaskContext,
+    policy: &IdlePolicy,
+    purpose: &str,
+    on_wait: &impl Fn(&str, Duration),
+) -> Result<Duration, String> {
+    let required = policy.min_idle();
+    let began = Instant::now();
+    loop {
+        let idle = idle_time()?;
+        if idle >= required {
+            return Ok(began.elapsed());
+        }
+
+        let remaining = required - idle;
+        let reason = format!(
+            "Waiting to {}: machine idle for {}s, needs {} min",
+            purpose,
+            idle.as_secs(),
+            policy.min_idle_minutes
+        );
+        task.progress(idle.as_secs_f32() / required.as_secs_f32(), reason.clone());
+        on_wait(&reason, remaining);
+        notify(app_handle, "waiting", reason, Some(idle), policy);
+
+        if !sleep_unless_cancelled(task, remaining.min(POLL_INTERVAL)) {
+            return Err("Cancelled".to_string());
+        }
+    }
+}
+
+/// Sleeps in short steps; false if the task was cancelled meanwhile.
+fn sleep_unless_cancelled(task: &TaskContext, duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while Instant::now() < until {
+        if task.is_cancelled() {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(250).min(until - Instant::now()));
+    }
+    !task.is_cancelled()
+}
+
+fn with_bridge(
+    app_handle: &AppHandle,
+    action: impl FnOnce(&mut dyn ExecutorBackend) -> Result<(), String>,
+) {
+    let state = app_handle.state::<AppState>();
+    let mut bridge = state.executor.lock().unwrap();
+    match bridge.as_mut() {
+        Some(bridge) => {
+            if let Err(e) = action(bridge.as_mut()) {
+                warn!("Idle gate failed to control the executor: {}", e);
+            }
+        }
+        None => warn!("Idle gate: Python executor not initialized"),
+    }
+}
+
+fn notify(
+    app_handle: &AppHandle,
+    state: &str,
+    reason: String,
+    idle: Option<Duration>,
+    policy: &IdlePolicy,
+) {
+    let event = BridgeMessage::Event(ExecutorEvent::from_runner(
+        "idle_gate",
+        json!({
+            "state": state,
+            "reason": reason,
+            "idle_seconds": idle.map(|d| d.as_secs()),
+            "required_seconds": policy.min_idle().as_secs(),
+        }),
+    ));
+    let _ = sessions::emit(app_handle, "executor-event", DEFAULT_SESSION, &event);
+}
+
+/// Time since the last keyboard or mouse input on this machine.
+pub fn idle_time() -> Result<Duration, String> {
+    platform_idle_time()
+}
+
+fn output_of(cmd: &mut Command) -> Result<String, String> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to query idle time: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to query idle time: {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "windows")]
+fn platform_idle_time() -> Result<Duration, String> {
+    const SCRIPT: &str = r#"
+Add-Type @'
+using System;
+using System.Runtime.InteropServices;
+public static class QontinuiIdle {
+    [StructLayout(LayoutKind.Sequential)]
+    struct LASTINPUTINFO { public uint cbSize; public uint dwTime; }
+    [DllImport("user32.dll")]
+    static extern bool GetLastInputInfo(ref LASTINPUTINFO info);
+    public static uint Milliseconds() {
+        var info = new LASTINPUTINFO();
+        info.cbSize = (uint)Marshal.SizeOf(info);
+        GetLastInputInfo(ref info);
+        return (uint)Environment.TickCount - info.dwTime;
+    }
+}
+'@
+[QontinuiIdle]::Milliseconds()
+"#;
+    let output = output_of(Command::new("powershell").args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        SCRIPT,
+    ]))?;
+    output
+        .trim()
+        .parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("Unexpected idle time output: {}", output.trim()))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_idle_time() -> Result<Duration, String> {
+    let output = output_of(Command::new("ioreg").args(["-c", "IOHIDSystem", "-d", "4"]))?;
+    parse_ioreg(&output).ok_or_else(|| "HIDIdleTime not reported by ioreg".to_string())
+}
+
+/// `xprintidle` on X11, Mutter's idle monitor on GNOME Wayland.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn platform_idle_time() -> Result<Duration, String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        if let Ok(output) = output_of(&mut Command::new("xprintidle")) {
+            if let Ok(ms) = output.trim().parse::<u64>() {
+                return Ok(Duration::from_millis(ms));
+            }
+        }
+    }
+    let output = output_of(Command::new("gdbus").args([
+        "call",
+        "--session",
+        "--dest",
+        "org.gnome.Mutter.IdleMonitor",
+        "--object-path",
+        "/org/gnome/Mutter/IdleMonitor/Core",
+        "--method",
+        "org.gnome.Mutter.IdleMonitor.GetIdletime",
+    ]))
+    .map_err(|e| format!("{} (install xprintidle, or use GNOME on Wayland)", e))?;
+    parse_gdbus_idletime(&output)
+        .ok_or_else(|| format!("Unexpected idle time output: {}", output.trim()))
+}
+
+/// Reads `"HIDIdleTime" = <nanoseconds>` from `ioreg` output.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn parse_ioreg(output: &str) -> Option<Duration> {
+    output
+        .lines()
+        .find_map(|line| line.split_once("\"HIDIdleTime\" = "))
+        .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+        .map(Duration::from_nanos)
+}
+
+/// Reads Mutter's `(uint64 <milliseconds>,)` reply.
+#[cfg_attr(any(target_os = "windows", target_os = "macos"), allow(dead_code))]
+pub fn parse_gdbus_idletime(output: &str) -> Option<Duration> {
+    output
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim_end_matches(',')
+        .trim()
+        .strip_prefix("uint64 ")
+        .and_then(|ms| ms.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+}
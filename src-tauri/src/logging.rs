@@ -1,19 +1,117 @@
 use chrono::Local;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::{non_blocking, rolling};
 use tracing_subscriber::{
+    filter::filter_fn,
     fmt::{self, format::FmtSpan},
     layer::SubscriberExt,
+    reload,
     util::SubscriberInitExt,
-    EnvFilter, Registry,
+    EnvFilter, Layer, Registry,
 };
 
-pub struct LoggingConfig {
-    pub level: Level,
+/// Name of the rolling log files; the appender adds the date.
+pub const LOG_FILE_PREFIX: &str = "qontinui-runner.log";
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// The file writer flushes on a background thread that stops when its guard
+/// is dropped, so the guard lives as long as the app.
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Swaps the level filter while the app runs.
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// The file layer is always installed and skips events while this is off,
+/// so file logging can be switched without rebuilding the subscriber.
+static FILE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Where the log files are written.
+pub fn log_dir() -> PathBuf {
+    LOG_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| LoggingConfig::default().log_dir)
+}
+
+/// Logging options the user can change without restarting, saved in the
+/// data directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// `error`, `warn`, `info`, `debug` or `trace`.
+    pub level: String,
     pub log_to_file: bool,
+    /// Log files older than this are deleted.
+    pub retention_days: u32,
+    /// Oldest log files are deleted while the directory is larger than this.
+    pub max_total_mb: u64,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            log_to_file: true,
+            retention_days: 14,
+            max_total_mb: 500,
+        }
+    }
+}
+
+impl LogSettings {
+    pub fn path() -> PathBuf {
+        crate::profiles::data_dir().join("logging.json")
+    }
+
+    /// The saved settings, or the defaults when there are none.
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, content).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+
+    pub fn validate(&self) -> Result<Level, String> {
+        if self.retention_days == 0 {
+            return Err("Log retention must be at least one day".to_string());
+        }
+        if self.max_total_mb == 0 {
+            return Err("The log size limit must be at least 1 MB".to_string());
+        }
+        parse_level(&self.level)
+    }
+}
+
+pub fn parse_level(level: &str) -> Result<Level, String> {
+    Level::from_str(level.trim()).map_err(|_| format!("Unknown log level '{}'", level))
+}
+
+fn filter_for(level: Level) -> EnvFilter {
+    EnvFilter::new(format!("qontinui_runner={},tauri=info", level))
+}
+
+pub struct LoggingConfig {
     pub log_to_console: bool,
     pub log_dir: PathBuf,
+    pub settings: LogSettings,
 }
 
 impl Default for LoggingConfig {
@@ -24,10 +122,9 @@ impl Default for LoggingConfig {
             .join("logs");
 
         Self {
-            level: Level::INFO,
-            log_to_file: true,
             log_to_console: cfg!(debug_assertions),
             log_dir,
+            settings: LogSettings::default(),
         }
     }
 }
@@ -35,54 +132,125 @@ impl Default for LoggingConfig {
 pub fn init_logging(config: LoggingConfig) -> anyhow::Result<()> {
     std::fs::create_dir_all(&config.log_dir)?;
 
-    let env_filter = EnvFilter::new(
-        std::env::var("RUST_LOG")
-            .unwrap_or_else(|_| format!("qontinui_runner={},tauri=info", config.level)),
-    );
+    let level = config.settings.validate().unwrap_or(Level::INFO);
+    // RUST_LOG wins at startup; set_log_level replaces it
+    let env_filter = match std::env::var("RUST_LOG") {
+        Ok(directives) => EnvFilter::new(directives),
+        Err(_) => filter_for(level),
+    };
+    let (filter_layer, filter_handle) = reload::Layer::new(env_filter);
+    let _ = FILTER_HANDLE.set(filter_handle);
 
-    let registry = Registry::default().with(env_filter);
+    let registry = Registry::default().with(filter_layer);
 
     // Store log_dir for logging before it's moved
     let log_dir_path = config.log_dir.clone();
+    let _ = LOG_DIR.set(config.log_dir.clone());
+    FILE_ENABLED.store(config.settings.log_to_file, Ordering::Relaxed);
 
-    if config.log_to_file {
-        let file_appender = rolling::daily(config.log_dir, "qontinui-runner.log");
-        let (non_blocking_file, _guard) = non_blocking(file_appender);
+    let file_appender = rolling::daily(config.log_dir, LOG_FILE_PREFIX);
+    let (non_blocking_file, guard) = non_blocking(file_appender);
+    let _ = FILE_GUARD.set(guard);
 
-        let file_layer = fmt::layer()
-            .with_writer(non_blocking_file)
-            .with_ansi(false)
-            .with_span_events(FmtSpan::CLOSE)
-            .with_timer(fmt::time::ChronoLocal::new(
-                "%Y-%m-%d %H:%M:%S%.3f".to_string(),
-            ));
-
-        let subscriber = registry.with(file_layer);
+    let file_layer = fmt::layer()
+        .with_writer(non_blocking_file)
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_timer(fmt::time::ChronoLocal::new(
+            "%Y-%m-%d %H:%M:%S%.3f".to_string(),
+        ))
+        .with_filter(filter_fn(|_| FILE_ENABLED.load(Ordering::Relaxed)));
 
-        if config.log_to_console {
-            let console_layer = fmt::layer()
-                .with_writer(std::io::stdout)
-                .with_span_events(FmtSpan::CLOSE);
+    let subscriber = registry.with(file_layer);
 
-            subscriber.with(console_layer).init();
-        } else {
-            subscriber.init();
-        }
-    } else if config.log_to_console {
+    if config.log_to_console {
         let console_layer = fmt::layer()
             .with_writer(std::io::stdout)
             .with_span_events(FmtSpan::CLOSE);
 
-        registry.with(console_layer).init();
+        subscriber.with(console_layer).init();
+    } else {
+        subscriber.init();
     }
 
-    tracing::info!("Logging initialized at level: {:?}", config.level);
+    tracing::info!("Logging initialized at level: {:?}", level);
     tracing::info!("Log directory: {:?}", log_dir_path);
     tracing::info!("Application started at {}", Local::now());
 
+    let removed = prune_logs(&log_dir_path, &config.settings, SystemTime::now());
+    if removed > 0 {
+        tracing::info!("Removed {} old log files", removed);
+    }
+
     Ok(())
 }
 
+/// Applies `settings` to the running subscriber and prunes the log
+/// directory to the new limits.
+pub fn apply_settings(settings: &LogSettings) -> Result<(), String> {
+    let level = settings.validate()?;
+    if let Some(handle) = FILTER_HANDLE.get() {
+        handle
+            .reload(filter_for(level))
+            .map_err(|e| format!("Failed to change the log level: {}", e))?;
+    }
+    FILE_ENABLED.store(settings.log_to_file, Ordering::Relaxed);
+    tracing::info!(
+        "Log level set to {} (file logging {})",
+        level,
+        if settings.log_to_file { "on" } else { "off" }
+    );
+
+    let removed = prune_logs(&log_dir(), settings, SystemTime::now());
+    if removed > 0 {
+        tracing::info!("Removed {} old log files", removed);
+    }
+    Ok(())
+}
+
+/// Deletes log files older than the retention period, then the oldest ones
+/// while the rest exceed the size limit. The newest file, which is being
+/// written, is always kept. Returns how many were deleted.
+pub fn prune_logs(dir: &Path, settings: &LogSettings, now: SystemTime) -> usize {
+    let mut files: Vec<(PathBuf, String, u64, SystemTime)> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(LOG_FILE_PREFIX) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some((
+                entry.path(),
+                name,
+                metadata.len(),
+                metadata.modified().ok()?,
+            ))
+        })
+        .collect();
+    // Newest first; the appender suffixes file names with the date
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let max_age = Duration::from_secs(u64::from(settings.retention_days) * 24 * 60 * 60);
+    let max_bytes = settings.max_total_mb.saturating_mul(1024 * 1024);
+    let mut kept_bytes = 0u64;
+    let mut removed = 0;
+    for (index, (path, _, size, modified)) in files.iter().enumerate() {
+        let expired = now.duration_since(*modified).is_ok_and(|age| age > max_age);
+        if index > 0 && (expired || kept_bytes + size > max_bytes) {
+            match std::fs::remove_file(path) {
+                Ok(()) => removed += 1,
+                Err(e) => tracing::warn!("Failed to remove {:?}: {}", path, e),
+            }
+        } else {
+            kept_bytes += size;
+        }
+    }
+    removed
+}
+
 #[macro_export]
 macro_rules! log_error {
     ($result:expr, $context:expr) => {
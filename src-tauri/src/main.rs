@@ -1,17 +1,52 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod accessibility;
+mod agent;
+mod assets;
+mod capture;
+mod clipboard;
+mod color;
 mod commands;
 mod config;
 mod error;
 mod executor;
+mod harness;
+mod hotkeys;
+mod http_api;
+mod i18n;
+mod idle;
 mod logging;
+mod matching;
+mod monitors;
+mod notifications;
+mod ocr;
+mod preview;
+mod profiles;
+mod progress;
+mod queue;
+mod recordings;
+mod region_select;
+mod replay;
+mod resources;
+mod run_plan;
+mod scheduler;
+mod secrets;
+mod session;
+mod settings;
+mod startup;
+mod tasks;
+mod tray;
+mod variables;
+mod video;
+mod visual_diff;
+mod watcher;
 
 #[cfg(test)]
 mod test;
 
 use commands::AppState;
-use logging::{init_logging, setup_panic_handler, LoggingConfig};
+use logging::{init_logging, setup_panic_handler, LogSettings, LoggingConfig};
 use std::sync::Mutex;
 use tauri::Manager;
 use tracing::{error, info};
@@ -35,109 +70,374 @@ fn main() {
 }
 
 fn run_app() -> Result<(), Box<dyn std::error::Error>> {
-    init_logging(LoggingConfig::default())?;
+    startup::begin();
+    startup::phase("logging", || {
+        init_logging(LoggingConfig {
+            settings: LogSettings::load(),
+            ..Default::default()
+        })
+    })?;
     setup_panic_handler();
 
     info!("Starting Qontinui Runner v{}", env!("CARGO_PKG_VERSION"));
 
-    #[cfg(not(debug_assertions))]
-    {
-        if let Ok(dsn) = std::env::var("SENTRY_DSN") {
-            let _guard = sentry::init((
-                dsn,
-                sentry::ClientOptions {
-                    release: sentry::release_name!(),
-                    environment: Some("beta".into()),
-                    before_send: Some(std::sync::Arc::new(|event| {
-                        info!("Sending error to Sentry: {:?}", event);
-                        Some(event)
-                    })),
-                    ..Default::default()
-                },
-            ));
-            info!("Sentry crash reporting initialized");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match agent::LaunchMode::from_args(args.iter().cloned()) {
+        agent::LaunchMode::InstallAgent => {
+            println!("{}", agent::install(&args)?);
+            return Ok(());
+        }
+        agent::LaunchMode::UninstallAgent => {
+            println!("{}", agent::uninstall()?);
+            return Ok(());
         }
+        agent::LaunchMode::Agent => agent::activate(),
+        agent::LaunchMode::Window => {}
+    }
+
+    let profiles = profiles::Profiles::open(profiles::data_dir());
+    let profile = profiles.active();
+    let settings = profile.load_settings();
+    info!("Using profile '{}'", profile.name);
+
+    executor::environment::set_interpreter(settings.python_interpreter.clone());
+
+    let translations = i18n::Translations::default();
+    if let Err(e) = translations.choose(settings.locale.as_deref()) {
+        error!("Ignoring profile locale: {}", e);
     }
 
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .manage(AppState {
-            python_bridge: Mutex::new(None),
+            executor: Mutex::new(None),
+            executor_sessions: Default::default(),
             current_config: Mutex::new(None),
+            config_source_bytes: Mutex::new(0),
+            config_edits: Default::default(),
+            resource_limits: Mutex::new(settings.resource_limits),
+            input_limits: Mutex::new(settings.input_limits),
+            safe_mode: Mutex::new(settings.safe_mode),
+            safe_bounds: Default::default(),
+            restarts: executor::restart::Restarts::new(settings.restart),
+            shutdown: Mutex::new(settings.shutdown),
+            executor_transport: Mutex::new(executor::transport::TransportKind::from_env()),
+            executor_launch: Mutex::new(settings.executor_launch),
+            remote_executor: Default::default(),
+            frames: Default::default(),
+            idle_policy: Mutex::new(settings.idle_policy),
+            idle_monitor: Default::default(),
+            run_tracker: Default::default(),
+            progress: Default::default(),
+            execution_queue: Default::default(),
+            agent_jobs: Default::default(),
+            scheduler: scheduler::Scheduler::open(&profiles::data_dir()),
+            http_api: Default::default(),
+            run_notifier: Default::default(),
+            tasks: Default::default(),
+            clipboard: Default::default(),
+            preview: Default::default(),
+            replay: Default::default(),
+            video_recorder: video::VideoRecorder::new(profile.recordings_dir.join("videos")),
+            recordings: recordings::Recordings::open(profile.recordings_dir.clone()),
+            variables: variables::VariableStore::open(profile.variables_path()),
+            run_streams: executor::run_stream::RunStreams::new(
+                profile.streams_dir(),
+                logging::log_dir().join("runs"),
+            ),
+            event_timeline: Default::default(),
+            profiles,
+            translations,
+            session: session::SessionStore::open(profiles::data_dir().join("session.json")),
+            config_watcher: Default::default(),
+            assets: Default::default(),
+            settings: settings::SettingsStore::open(profiles::data_dir().join("app-settings.json")),
         })
         .invoke_handler(tauri::generate_handler![
             commands::load_configuration,
+            commands::migrate_configuration,
+            commands::watch_configuration,
+            commands::diff_configurations,
             commands::start_python_executor,
             commands::start_python_executor_with_type,
             commands::stop_python_executor,
+            commands::abort_execution,
+            commands::refresh_python_environment,
+            commands::provision_python_environment,
+            commands::list_python_interpreters,
+            commands::set_python_interpreter,
+            commands::get_executor_launch_options,
+            commands::set_executor_launch_options,
             commands::start_execution,
+            commands::start_execution_when_idle,
+            commands::get_idle_status,
+            commands::get_run_plan,
+            commands::get_execution_progress,
+            commands::enqueue_execution,
+            commands::get_queue,
+            commands::reorder_queue,
+            commands::cancel_queued,
+            commands::add_schedule,
+            commands::list_schedules,
+            commands::remove_schedule,
+            commands::get_execution_history,
+            commands::get_http_api_settings,
+            commands::set_http_api_settings,
+            commands::set_http_api_tls,
+            commands::list_webhooks,
+            commands::add_webhook,
+            commands::remove_webhook,
+            commands::get_hotkeys,
+            commands::set_hotkeys,
+            commands::get_previous_session,
+            commands::restore_previous_session,
+            commands::discard_previous_session,
+            commands::set_idle_policy,
             commands::stop_execution,
             commands::get_executor_status,
+            commands::get_executor_info,
+            commands::create_session,
+            commands::destroy_session,
+            commands::list_sessions,
+            commands::start_session_execution,
+            commands::stop_session_execution,
+            commands::get_session_status,
+            commands::send_executor_command,
+            commands::query_executor_status,
+            commands::set_execution_variables,
+            commands::get_execution_variables,
+            commands::get_active_states,
+            commands::get_state_graph,
+            commands::get_transition_history,
+            commands::activate_state,
+            commands::deactivate_state,
+            commands::rebuild_state_from_screen,
+            commands::set_event_throttle,
+            commands::set_event_backpressure,
+            commands::get_bridge_metrics,
+            commands::set_protocol_strict_mode,
+            commands::set_executor_transport,
+            commands::connect_remote_executor,
+            commands::disconnect_remote_executor,
+            commands::get_remote_executor_status,
+            commands::replay_event_log,
+            commands::list_event_streams,
+            commands::get_event_timeline,
+            commands::export_event_stream,
+            commands::generate_run_report,
+            commands::export_run_log,
+            commands::logs::get_log_files,
+            commands::logs::tail_logs,
+            commands::logs::get_log_settings,
+            commands::logs::set_log_settings,
+            commands::logs::set_log_level,
+            commands::run_test_script,
+            commands::run_bridge_selftest,
             commands::get_current_configuration,
+            commands::get_config_section,
+            commands::validate_configuration,
+            commands::validate_execution,
+            commands::add_state,
+            commands::update_state,
+            commands::remove_state,
+            commands::add_transition,
+            commands::update_transition,
+            commands::remove_transition,
+            commands::add_workflow,
+            commands::update_workflow,
+            commands::remove_workflow,
+            commands::add_image,
+            commands::update_image,
+            commands::remove_image,
+            commands::undo_config_edit,
+            commands::redo_config_edit,
+            commands::get_config_edit_state,
+            commands::save_configuration,
+            commands::get_image_data,
+            commands::get_image_thumbnail,
+            commands::get_missing_images,
+            commands::verify_images,
+            commands::get_resource_usage,
+            commands::set_resource_limits,
+            commands::get_input_limits,
+            commands::set_input_limits,
+            commands::get_safe_mode,
+            commands::set_safe_mode,
+            commands::get_restart_policy,
+            commands::set_restart_policy,
+            commands::get_shutdown_settings,
+            commands::set_shutdown_settings,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::switch_profile,
+            commands::list_background_tasks,
+            commands::cancel_background_task,
+            commands::get_health_report,
+            commands::dump_app_state,
+            commands::generate_fixture_config,
             commands::get_monitors,
+            commands::set_simulated_monitor_layout,
+            commands::translate_coordinates,
+            commands::query_elements,
+            commands::click_element,
+            commands::type_into_element,
+            commands::ocr_region,
+            commands::select_screen_region,
+            commands::capture_region,
+            commands::capture_screenshot,
+            commands::start_preview,
+            commands::stop_preview,
+            commands::get_preview_status,
+            commands::start_replay,
+            commands::pause_replay,
+            commands::resume_replay,
+            commands::stop_replay,
+            commands::get_replay_status,
+            commands::find_image_on_screen,
+            commands::test_image_match,
+            commands::get_pixel_color,
+            commands::wait_for_color,
+            commands::compare_screenshots,
+            commands::compare_run_screenshots,
+            commands::get_clipboard,
+            commands::set_clipboard,
+            commands::get_clipboard_history,
+            commands::set_clipboard_capture,
+            commands::get_variable,
+            commands::set_variable,
+            commands::list_variables,
+            commands::store_secret,
+            commands::delete_secret,
+            commands::list_secret_names,
+            commands::get_recent_configurations,
+            commands::add_recent_configuration,
+            commands::clear_recent_configurations,
+            commands::get_translations,
+            commands::set_locale,
             commands::handle_error,
+            commands::preview_error,
             commands::check_for_updates,
             commands::start_recording,
             commands::stop_recording,
             commands::get_recording_status,
+            commands::start_video_recording,
+            commands::stop_video_recording,
+            commands::list_video_recordings,
+            commands::list_recordings,
+            commands::get_recording_metadata,
+            commands::delete_recording,
+            commands::rename_recording,
+            commands::recording_to_config,
             commands::open_folder,
         ])
+        .register_uri_scheme_protocol(executor::frames::SCHEME, |ctx, request| {
+            let state = ctx.app_handle().state::<AppState>();
+            executor::frames::serve(&state.frames, request.uri().path())
+        })
         .setup(|app| {
             info!("Tauri application setup starting");
 
-            // Position window at top-center of screen
-            if let Some(window) = app.get_webview_window("main") {
-                if let Ok(monitor) = window.current_monitor() {
-                    if let Some(monitor) = monitor {
-                        let monitor_size = monitor.size();
-                        let monitor_pos = monitor.position();
-
-                        if let Ok(window_size) = window.outer_size() {
-                            // Calculate center X position
-                            let x = monitor_pos.x
-                                + ((monitor_size.width as i32 - window_size.width as i32) / 2);
-                            // Position at top (with small margin)
-                            let y = monitor_pos.y + 20;
-
-                            if let Err(e) = window.set_position(tauri::Position::Physical(
-                                tauri::PhysicalPosition { x, y },
-                            )) {
-                                error!("Failed to set window position: {}", e);
-                            } else {
-                                info!("Window positioned at top-center: x={}, y={}", x, y);
-                            }
-                        }
-                    }
-                } else {
-                    error!("Failed to get current monitor");
+            match app.path().resource_dir() {
+                Ok(dir) => {
+                    app.state::<AppState>()
+                        .translations
+                        .set_dir(dir.join("locales"));
+                    executor::environment::set_bundled_dir(dir.join("python-bridge"));
+                }
+                Err(e) => error!("Failed to find the resource directory: {}", e),
+            }
+
+            if agent::active() {
+                // Headless: clients drive the agent through the HTTP API
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
                 }
             } else {
-                error!("Failed to get main window");
+                startup::phase("window_position", || position_main_window(app));
+                if let Err(e) = startup::phase("tray", || tray::create(app)) {
+                    error!("{}", e);
+                }
             }
 
             info!("Tauri application setup complete");
             Ok(())
         })
         .on_window_event(|window, event| {
+            // Not for the region selector overlay
+            if window.label() != "main" {
+                return;
+            }
             if let tauri::WindowEvent::CloseRequested { .. } = event {
                 info!("Window close requested");
                 let app_state = window.state::<AppState>();
-                if let Ok(mut bridge) = app_state.python_bridge.lock() {
+                if let Ok(mut bridge) = app_state.executor.lock() {
                     if let Some(ref mut pb) = *bridge {
                         let _ = pb.stop();
                     }
                 }; // Add semicolon to drop the temporary earlier
+                app_state.executor_sessions.stop_all();
             }
         })
         .build(tauri::generate_context!())?;
 
     info!("Tauri application built successfully");
-    app.run(|_, event| {
-        if let tauri::RunEvent::ExitRequested { .. } = event {
+    app.run(|app_handle, event| match event {
+        tauri::RunEvent::Ready => {
+            startup::mark_interactive();
+            // Sentry, Python environment discovery and the resource monitor
+            // aren't needed to show the UI
+            startup::run_deferred(app_handle.clone());
+        }
+        tauri::RunEvent::ExitRequested { .. } => {
             info!("Application exit requested");
         }
+        tauri::RunEvent::Exit => {
+            let state = app_handle.state::<AppState>();
+            // Finishes the file, which ffmpeg would otherwise leave broken
+            if state.video_recorder.current().is_some() {
+                let _ = state.video_recorder.stop();
+                let _ =
+                    commands::finish_recording(&state.recordings, recordings::RecordingKind::Video);
+            }
+            state.session.close();
+        }
+        _ => {}
     });
 
     Ok(())
 }
+
+/// Positions the main window at the top-center of its monitor.
+fn position_main_window(app: &tauri::App) {
+    if let Some(window) = app.get_webview_window("main") {
+        if let Ok(monitor) = window.current_monitor() {
+            if let Some(monitor) = monitor {
+                let monitor_size = monitor.size();
+                let monitor_pos = monitor.position();
+
+                if let Ok(window_size) = window.outer_size() {
+                    // Calculate center X position
+                    let x = monitor_pos.x
+                        + ((monitor_size.width as i32 - window_size.width as i32) / 2);
+                    // Position at top (with small margin)
+                    let y = monitor_pos.y + 20;
+
+                    if let Err(e) = window
+                        .set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+                    {
+                        error!("Failed to set window position: {}", e);
+                    } else {
+                        info!("Window positioned at top-center: x={}, y={}", x, y);
+                    }
+                }
+            }
+        } else {
+            error!("Failed to get current monitor");
+        }
+    } else {
+        error!("Failed to get main window");
+    }
+}
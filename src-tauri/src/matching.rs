@@ -0,0 +1,338 @@
+//! Template matching with zero-mean normalised cross-correlation. Searches a
+//! downscaled copy first and refines candidates at full resolution, so a
+//! full-screen search stays interactive.
+
+use crate::capture::RgbImage;
+use serde::{Deserialize, Serialize};
+
+/// Templates are shrunk until their shorter side is about this long for the
+/// coarse pass.
+const COARSE_TEMPLATE_SIDE: u32 = 16;
+const MAX_COARSE_SCALE: u32 = 8;
+/// Coarse scores are blurrier than full-resolution ones, so candidates are
+/// kept a little below the threshold before refinement.
+const COARSE_SLACK: f32 = 0.15;
+/// Fine detail can drop a true match well below the slack when it sits
+/// between coarse pixels, so this many of the best peaks per requested
+/// result are refined regardless of score.
+const COARSE_PEAKS_PER_RESULT: usize = 4;
+/// Upper bound on refinements, for repetitive content with many near-equal
+/// peaks.
+const MAX_CANDIDATES: usize = 64;
+
+/// 8-bit luminance image stored as floats for the correlation maths.
+#[derive(Debug, Clone)]
+pub struct GrayImage {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Normalised cross-correlation, -1 to 1.
+    pub score: f32,
+}
+
+impl GrayImage {
+    #[allow(dead_code)]
+    pub fn new(width: u32, height: u32, pixels: Vec<f32>) -> Result<Self, String> {
+        if pixels.len() != (width as usize) * (height as usize) {
+            return Err(format!(
+                "Expected {} pixels for {}x{}, got {}",
+                width as usize * height as usize,
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decodes a PNG of any colour type into luminance.
+    pub fn decode_png(bytes: &[u8]) -> Result<Self, String> {
+        Ok(Self::from_rgb(&RgbImage::decode_png(bytes)?))
+    }
+
+    pub fn from_rgb(image: &RgbImage) -> Self {
+        let pixels = image
+            .pixels
+            .iter()
+            .map(|[r, g, b]| 0.299 * *r as f32 + 0.587 * *g as f32 + 0.114 * *b as f32)
+            .collect();
+        Self {
+            width: image.width,
+            height: image.height,
+            pixels,
+        }
+    }
+
+    fn at(&self, x: u32, y: u32) -> f32 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Box-filter downscale by an integer factor.
+    fn downscale(&self, factor: u32) -> Self {
+        if factor <= 1 {
+            return self.clone();
+        }
+        let width = (self.width / factor).max(1);
+        let height = (self.height / factor).max(1);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = 0.0;
+                let mut count = 0.0;
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        let (sx, sy) = (x * factor + dx, y * factor + dy);
+                        if sx < self.width && sy < self.height {
+                            sum += self.at(sx, sy);
+                            count += 1.0;
+                        }
+                    }
+                }
+                pixels.push(sum / count);
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// Summed-area tables of values and squared values, for O(1) window
+/// statistics.
+struct Integral {
+    width: usize,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl Integral {
+    fn new(image: &GrayImage) -> Self {
+        let width = image.width as usize + 1;
+        let height = image.height as usize + 1;
+        let mut sum = vec![0.0; width * height];
+        let mut sum_sq = vec![0.0; width * height];
+        for y in 1..height {
+            for x in 1..width {
+                let v = image.at(x as u32 - 1, y as u32 - 1) as f64;
+                let i = y * width + x;
+                sum[i] = v + sum[i - 1] + sum[i - width] - sum[i - width - 1];
+                sum_sq[i] = v * v + sum_sq[i - 1] + sum_sq[i - width] - sum_sq[i - width - 1];
+            }
+        }
+        Self { width, sum, sum_sq }
+    }
+
+    fn window(&self, table: &[f64], x: u32, y: u32, w: u32, h: u32) -> f64 {
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = (x0 + w as usize, y0 + h as usize);
+        table[y1 * self.width + x1] - table[y0 * self.width + x1] - table[y1 * self.width + x0]
+            + table[y0 * self.width + x0]
+    }
+}
+
+/// A template with its mean removed, ready for correlation.
+struct Template<'a> {
+    image: &'a GrayImage,
+    centred: Vec<f32>,
+    norm: f64,
+}
+
+impl<'a> Template<'a> {
+    fn new(image: &'a GrayImage) -> Option<Self> {
+        let mean = image.pixels.iter().sum::<f32>() / image.pixels.len() as f32;
+        let centred: Vec<f32> = image.pixels.iter().map(|p| p - mean).collect();
+        let norm = centred
+            .iter()
+            .map(|v| (*v as f64).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        (norm > 1e-6).then_some(Self {
+            image,
+            centred,
+            norm,
+        })
+    }
+
+    fn score(&self, haystack: &GrayImage, integral: &Integral, x: u32, y: u32) -> f32 {
+        let (w, h) = (self.image.width, self.image.height);
+        let n = (w * h) as f64;
+        let sum = integral.window(&integral.sum, x, y, w, h);
+        let sum_sq = integral.window(&integral.sum_sq, x, y, w, h);
+        let variance = (sum_sq - sum * sum / n).max(0.0);
+        if variance < 1e-6 {
+            return 0.0;
+        }
+
+        // The template is zero-mean, so the window mean drops out
+        let mut cross = 0.0f64;
+        for ty in 0..h {
+            let row = ((y + ty) * haystack.width + x) as usize;
+            let haystack_row = &haystack.pixels[row..row + w as usize];
+            let template_row = &self.centred[(ty * w) as usize..((ty + 1) * w) as usize];
+            cross += haystack_row
+                .iter()
+                .zip(template_row)
+                .map(|(a, b)| (a * b) as f64)
+                .sum::<f64>();
+        }
+        (cross / (variance.sqrt() * self.norm)) as f32
+    }
+}
+
+/// Finds non-overlapping occurrences of `needle` in `haystack` scoring at
+/// least `threshold`, best first.
+pub fn find_template(
+    haystack: &GrayImage,
+    needle: &GrayImage,
+    threshold: f32,
+    max_results: usize,
+) -> Result<Vec<Match>, String> {
+    if needle.width > haystack.width || needle.height > haystack.height {
+        return Err(format!(
+            "Template ({}x{}) is larger than the search area ({}x{})",
+            needle.width, needle.height, haystack.width, haystack.height
+        ));
+    }
+
+    let scale = (needle.width.min(needle.height) / COARSE_TEMPLATE_SIDE).clamp(1, MAX_COARSE_SCALE);
+    let coarse_haystack = haystack.downscale(scale);
+    let coarse_needle = needle.downscale(scale);
+    let coarse_template =
+        Template::new(&coarse_needle).ok_or("Template has no contrast to match on")?;
+    let coarse_integral = Integral::new(&coarse_haystack);
+
+    let map_width = coarse_haystack.width - coarse_needle.width + 1;
+    let map_height = coarse_haystack.height - coarse_needle.height + 1;
+    let mut scores = Vec::with_capacity((map_width * map_height) as usize);
+    for y in 0..map_height {
+        for x in 0..map_width {
+            scores.push(coarse_template.score(&coarse_haystack, &coarse_integral, x, y));
+        }
+    }
+
+    // Only local maxima become candidates, so a smeared peak is refined once
+    let coarse_floor = if scale > 1 {
+        threshold - COARSE_SLACK
+    } else {
+        threshold
+    };
+    let score_at = |x: u32, y: u32| scores[(y * map_width + x) as usize];
+    let mut candidates = Vec::new();
+    for y in 0..map_height {
+        for x in 0..map_width {
+            let score = score_at(x, y);
+            if score <= 0.0 {
+                continue;
+            }
+            let is_peak = (y.saturating_sub(1)..=(y + 1).min(map_height - 1)).all(|ny| {
+                (x.saturating_sub(1)..=(x + 1).min(map_width - 1))
+                    .all(|nx| score_at(nx, ny) <= score)
+            });
+            if is_peak {
+                candidates.push((x * scale, y * scale, score));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+    let always_refined = if scale > 1 {
+        max_results * COARSE_PEAKS_PER_RESULT
+    } else {
+        0
+    };
+    let mut rank = 0;
+    candidates.retain(|&(_, _, score)| {
+        rank += 1;
+        rank <= always_refined || score >= coarse_floor
+    });
+
+    let template = Template::new(needle).ok_or("Template has no contrast to match on")?;
+    let integral = Integral::new(haystack);
+    let mut refined: Vec<Match> = Vec::new();
+
+    for (cx, cy, _) in candidates.into_iter().take(MAX_CANDIDATES) {
+        // Refine around the coarse hit at full resolution
+        let max_x = haystack.width - needle.width;
+        let max_y = haystack.height - needle.height;
+        let mut best: Option<Match> = None;
+        for y in cy.saturating_sub(scale)..=(cy + scale).min(max_y) {
+            for x in cx.saturating_sub(scale)..=(cx + scale).min(max_x) {
+                let score = template.score(haystack, &integral, x, y);
+                if best.as_ref().is_none_or(|b| score > b.score) {
+                    best = Some(Match {
+                        x,
+                        y,
+                        width: needle.width,
+                        height: needle.height,
+                        score,
+                    });
+                }
+            }
+        }
+
+        refined.extend(best.filter(|b| b.score >= threshold));
+    }
+
+    // Keep the best of each cluster of overlapping hits
+    refined.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut matches: Vec<Match> = Vec::new();
+    for candidate in refined {
+        if matches.len() >= max_results {
+            break;
+        }
+        if !matches
+            .iter()
+            .any(|m| overlaps(m, candidate.x, candidate.y, needle))
+        {
+            matches.push(candidate);
+        }
+    }
+    Ok(matches)
+}
+
+/// What a search at a threshold finds, for tuning the threshold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchTest {
+    /// At or above the threshold, best first.
+    pub matches: Vec<Match>,
+    /// The best score anywhere, even below the threshold.
+    pub best_score: Option<f32>,
+    /// The best places below the threshold, best first.
+    #[serde(default)]
+    pub near_misses: Vec<Match>,
+}
+
+/// Like `find_template`, but also reports what fell short of `threshold`.
+pub fn test_template(
+    haystack: &GrayImage,
+    needle: &GrayImage,
+    threshold: f32,
+    max_results: usize,
+) -> Result<MatchTest, String> {
+    let found = find_template(haystack, needle, 0.0, max_results)?;
+    let best_score = found.first().map(|m| m.score);
+    let (matches, near_misses) = found.into_iter().partition(|m| m.score >= threshold);
+    Ok(MatchTest {
+        matches,
+        best_score,
+        near_misses,
+    })
+}
+
+/// Whether a match at (x, y) would cover more than half of `existing`.
+fn overlaps(existing: &Match, x: u32, y: u32, needle: &GrayImage) -> bool {
+    x.abs_diff(existing.x) < needle.width / 2 && y.abs_diff(existing.y) < needle.height / 2
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+/// Environment variable naming a monitor layout fixture to use instead of
+/// the real displays.
+pub const LAYOUT_ENV: &str = "QONTINUI_MONITOR_LAYOUT";
+
+/// One display, in physical pixels on the virtual desktop.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default = "default_scale_factor")]
+    pub scale_factor: f64,
+    #[serde(default)]
+    pub is_primary: bool,
+}
+
+fn default_scale_factor() -> f64 {
+    1.0
+}
+
+impl MonitorInfo {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && (x as i64) < self.x as i64 + self.width as i64
+            && (y as i64) < self.y as i64 + self.height as i64
+    }
+}
+
+/// A point on a specific monitor, in the monitor's physical and logical
+/// pixels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonitorPoint {
+    pub monitor_index: usize,
+    pub x: i32,
+    pub y: i32,
+    pub logical_x: f64,
+    pub logical_y: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LayoutFixture {
+    monitors: Vec<MonitorInfo>,
+}
+
+fn simulated() -> &'static Mutex<Option<Vec<MonitorInfo>>> {
+    static SIMULATED: OnceLock<Mutex<Option<Vec<MonitorInfo>>>> = OnceLock::new();
+    SIMULATED.get_or_init(|| {
+        let layout = std::env::var(LAYOUT_ENV)
+            .ok()
+            .and_then(|path| load_layout(Path::new(&path)).ok());
+        Mutex::new(layout)
+    })
+}
+
+/// Reads a layout fixture: `{"monitors": [{"index", "x", "y", "width",
+/// "height", "scale_factor", "is_primary"}]}`.
+pub fn load_layout(path: &Path) -> Result<Vec<MonitorInfo>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read monitor layout {:?}: {}", path, e))?;
+    let fixture: LayoutFixture = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid monitor layout {:?}: {}", path, e))?;
+    if fixture.monitors.is_empty() {
+        return Err(format!("Monitor layout {:?} has no monitors", path));
+    }
+    Ok(fixture.monitors)
+}
+
+/// Replaces the real displays with `layout`, or restores them with `None`.
+pub fn set_simulated_layout(layout: Option<Vec<MonitorInfo>>) {
+    match &layout {
+        Some(monitors) => info!("Using simulated layout with {} monitors", monitors.len()),
+        None => info!("Using real monitor layout"),
+    }
+    *simulated().lock().unwrap() = layout;
+}
+
+pub fn is_simulated() -> bool {
+    simulated().lock().unwrap().is_some()
+}
+
+/// The simulated layout if one is set, otherwise the displays reported by
+/// the main window.
+pub fn detect(app_handle: &AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    if let Some(layout) = simulated().lock().unwrap().clone() {
+        return Ok(layout);
+    }
+
+    let window = app_handle
+        .get_webview_window("main")
+        .ok_or("Failed to get main window")?;
+    let primary = window.primary_monitor().ok().flatten();
+    let monitors = window
+        .available_monitors()
+        .map_err(|e| format!("Failed to get monitors: {}", e))?;
+
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+            is_primary: primary
+                .as_ref()
+                .is_some_and(|p| p.position() == monitor.position()),
+        })
+        .collect())
+}
+
+pub fn find(app_handle: &AppHandle, index: usize) -> Result<MonitorInfo, String> {
+    detect(app_handle)?
+        .into_iter()
+        .find(|m| m.index == index)
+        .ok_or_else(|| format!("Monitor {} not found", index))
+}
+
+/// Converts a point relative to a monitor into virtual desktop coordinates.
+/// With `logical`, the input is in the monitor's logical (DPI-scaled) pixels.
+pub fn monitor_to_global(
+    monitors: &[MonitorInfo],
+    monitor_index: usize,
+    x: f64,
+    y: f64,
+    logical: bool,
+) -> Result<(i32, i32), String> {
+    let monitor = monitors
+        .iter()
+        .find(|m| m.index == monitor_index)
+        .ok_or_else(|| format!("Monitor {} not found", monitor_index))?;
+    let scale = if logical { monitor.scale_factor } else { 1.0 };
+
+    Ok((
+        monitor.x + (x * scale).round() as i32,
+        monitor.y + (y * scale).round() as i32,
+    ))
+}
+
+/// Finds the monitor containing a virtual desktop point and returns the
+/// point relative to it.
+pub fn global_to_monitor(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<MonitorPoint> {
+    let monitor = monitors.iter().find(|m| m.contains(x, y))?;
+    let local_x = x - monitor.x;
+    let local_y = y - monitor.y;
+
+    Some(MonitorPoint {
+        monitor_index: monitor.index,
+        x: local_x,
+        y: local_y,
+        logical_x: local_x as f64 / monitor.scale_factor,
+        logical_y: local_y as f64 / monitor.scale_factor,
+    })
+}
@@ -0,0 +1,222 @@
+//! Webhook notifications about runs. The webhooks registered in the app
+//! settings are POSTed a JSON `RunNotification` when a run they're
+//! interested in starts, completes or fails, in any executor session, so an
+//! overnight automation that fails can raise an alert. The payload's `text`
+//! summarizes it in one line, which is what Slack and Teams incoming
+//! webhooks display.
+
+use crate::commands::AppState;
+use crate::executor::python_bridge::ExecutorEvent;
+use crate::executor::sessions::DEFAULT_SESSION;
+use crate::settings::{Webhook, WebhookEvent};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunNotification {
+    pub event: WebhookEvent,
+    pub text: String,
+    pub session_id: String,
+    /// The recorded run, if the event came during one.
+    pub run_id: Option<String>,
+    pub workflow_id: Option<String>,
+    pub config_name: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_ms: Option<i64>,
+    /// Last error the executor reported during the run.
+    pub last_error: Option<String>,
+}
+
+struct ActiveRun {
+    run_id: Option<String>,
+    workflow_id: Option<String>,
+    config_name: Option<String>,
+    started_at: chrono::DateTime<chrono::Local>,
+    last_error: Option<String>,
+}
+
+/// Follows the run of each executor session.
+#[derive(Default)]
+pub struct RunNotifier {
+    runs: Mutex<HashMap<String, ActiveRun>>,
+}
+
+impl RunNotifier {
+    /// Follows `event` of `session_id`'s run and returns the notification
+    /// it calls for, if any. `run_id` and `config_name` are only used when
+    /// the run starts.
+    pub fn record_event(
+        &self,
+        session_id: &str,
+        event: &ExecutorEvent,
+        run_id: Option<String>,
+        config_name: Option<String>,
+    ) -> Option<RunNotification> {
+        let mut runs = self.runs.lock().unwrap();
+        match event.event.as_str() {
+            "execution_started" => {
+                let workflow_id = ["workflow_id", "process_id"]
+                    .iter()
+                    .find_map(|key| event.data.get(*key).and_then(Value::as_str))
+                    .map(str::to_string);
+                let run = ActiveRun {
+                    run_id,
+                    workflow_id,
+                    config_name,
+                    started_at: chrono::Local::now(),
+                    last_error: None,
+                };
+                let notification = notification(WebhookEvent::Started, session_id, &run, None);
+                runs.insert(session_id.to_string(), run);
+                Some(notification)
+            }
+            "error" => {
+                if let Some(run) = runs.get_mut(session_id) {
+                    run.last_error = error_message(&event.data);
+                }
+                None
+            }
+            "execution_completed" => {
+                let mut run = runs.remove(session_id)?;
+                let success = event
+                    .data
+                    .get("success")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                if !success {
+                    run.last_error = error_message(&event.data).or(run.last_error);
+                }
+                let kind = if success {
+                    WebhookEvent::Completed
+                } else {
+                    WebhookEvent::Failed
+                };
+                Some(notification(
+                    kind,
+                    session_id,
+                    &run,
+                    Some(chrono::Local::now()),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn error_message(data: &Value) -> Option<String> {
+    ["error", "message"]
+        .iter()
+        .find_map(|key| data.get(*key).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+fn notification(
+    event: WebhookEvent,
+    session_id: &str,
+    run: &ActiveRun,
+    finished_at: Option<chrono::DateTime<chrono::Local>>,
+) -> RunNotification {
+    let name = run
+        .workflow_id
+        .as_deref()
+        .or(run.config_name.as_deref())
+        .unwrap_or("Automation");
+    let text = match event {
+        WebhookEvent::Started => format!("Qontinui run of {} started", name),
+        WebhookEvent::Completed => format!("Qontinui run of {} completed", name),
+        WebhookEvent::Failed => format!(
+            "Qontinui run of {} failed: {}",
+            name,
+            run.last_error.as_deref().unwrap_or("no error reported")
+        ),
+    };
+    RunNotification {
+        event,
+        text,
+        session_id: session_id.to_string(),
+        run_id: run.run_id.clone(),
+        workflow_id: run.workflow_id.clone(),
+        config_name: run.config_name.clone(),
+        started_at: run.started_at.to_rfc3339(),
+        finished_at: finished_at.map(|time| time.to_rfc3339()),
+        duration_ms: finished_at.map(|time| (time - run.started_at).num_milliseconds()),
+        last_error: run.last_error.clone(),
+    }
+}
+
+/// Follows an executor event of any session and notifies the webhooks
+/// interested in what it means for the run.
+pub fn record_event(app_handle: &AppHandle, session_id: &str, event: &ExecutorEvent) {
+    let state = app_handle.state::<AppState>();
+    let (run_id, config_name) = if event.event == "execution_started" {
+        run_context(&state, session_id)
+    } else {
+        (None, None)
+    };
+    if let Some(notification) =
+        state
+            .run_notifier
+            .record_event(session_id, event, run_id, config_name)
+    {
+        send(state.settings.webhooks(), notification);
+    }
+}
+
+/// The recorded run and the configuration of a session.
+fn run_context(state: &AppState, session_id: &str) -> (Option<String>, Option<String>) {
+    let run_id = state.run_streams.current_run(session_id);
+    if session_id == DEFAULT_SESSION {
+        let config = state.current_config.lock().unwrap().clone();
+        return (run_id, config.map(|config| config.metadata.name.clone()));
+    }
+    let config_name = state
+        .executor_sessions
+        .get(session_id)
+        .ok()
+        .and_then(|session| {
+            let session = session.lock().unwrap();
+            session
+                .config
+                .as_ref()
+                .map(|config| config.metadata.name.clone())
+        });
+    (run_id, config_name)
+}
+
+/// POSTs `notification` to the webhooks that want it, in the background.
+pub fn send(webhooks: Vec<Webhook>, notification: RunNotification) {
+    let webhooks: Vec<Webhook> = webhooks
+        .into_iter()
+        .filter(|webhook| webhook.events.contains(&notification.event))
+        .collect();
+    if webhooks.is_empty() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        for webhook in webhooks {
+            let sent = client
+                .post(&webhook.url)
+                .timeout(REQUEST_TIMEOUT)
+                .json(&notification)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match sent {
+                Ok(_) => info!(
+                    "Notified webhook {} of {:?}",
+                    webhook.id, notification.event
+                ),
+                Err(e) => warn!("Failed to notify webhook {}: {}", webhook.id, e),
+            }
+        }
+    });
+}
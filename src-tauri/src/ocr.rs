@@ -0,0 +1,184 @@
+//! Text recognition on captured regions with `ocrs`, which runs its
+//! detection and recognition models in-process. It reads Latin script; the
+//! models are loaded once from the models directory and kept.
+
+use crate::capture::{Region, RgbImage};
+use ocrs::{ImageSource, OcrEngine, OcrEngineParams, TextItem};
+use rten::Model;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Directory holding the models when it isn't `ocr-models` in the data
+/// directory.
+pub const MODELS_ENV: &str = "QONTINUI_OCR_MODELS";
+
+const DETECTION_MODEL: &str = "text-detection.rten";
+const RECOGNITION_MODEL: &str = "text-recognition.rten";
+const MODELS_URL: &str = "https://ocrs-models.s3-accelerate.amazonaws.com/";
+
+static ENGINE: Mutex<Option<Arc<OcrEngine>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrWord {
+    pub text: String,
+    pub bounds: Region,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrLine {
+    pub text: String,
+    pub bounds: Region,
+    pub words: Vec<OcrWord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OcrResult {
+    /// Recognised lines joined with newlines.
+    pub text: String,
+    pub lines: Vec<OcrLine>,
+}
+
+/// Where the detection and recognition models are read from.
+pub fn models_dir() -> PathBuf {
+    std::env::var_os(MODELS_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::profiles::data_dir().join("ocr-models"))
+}
+
+/// The engine, loading the models on first use. A failure isn't kept, so
+/// models added later are picked up without a restart.
+fn engine() -> Result<Arc<OcrEngine>, String> {
+    let mut engine = ENGINE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(engine) = engine.as_ref() {
+        return Ok(engine.clone());
+    }
+
+    let dir = models_dir();
+    let load = |name: &str| {
+        let path = dir.join(name);
+        if !path.is_file() {
+            return Err(format!(
+                "OCR model {:?} is missing: download {} and {} from {} into {:?} or set {}",
+                path, DETECTION_MODEL, RECOGNITION_MODEL, MODELS_URL, dir, MODELS_ENV
+            ));
+        }
+        Model::load_file(&path).map_err(|e| format!("Failed to load {:?}: {}", path, e))
+    };
+    let loaded = OcrEngine::new(OcrEngineParams {
+        detection_model: Some(load(DETECTION_MODEL)?),
+        recognition_model: Some(load(RECOGNITION_MODEL)?),
+        ..Default::default()
+    })
+    .map_err(|e| format!("Failed to start OCR: {}", e))?;
+    Ok(engine.insert(Arc::new(loaded)).clone())
+}
+
+/// Recognises the text in `image`, a capture of `region`, and returns
+/// boxes in the same coordinate space as `region`.
+pub fn recognize(image: &RgbImage, region: Region) -> Result<OcrResult, String> {
+    let engine = engine()?;
+    let bytes: Vec<u8> = image.pixels.iter().flatten().copied().collect();
+    let source = ImageSource::from_bytes(&bytes, (image.width, image.height))
+        .map_err(|e| format!("OCR failed: {}", e))?;
+    let input = engine
+        .prepare_input(source)
+        .map_err(|e| format!("OCR failed: {}", e))?;
+    let words = engine
+        .detect_words(&input)
+        .map_err(|e| format!("OCR failed: {}", e))?;
+    let lines = engine.find_text_lines(&input, &words);
+    let lines = engine
+        .recognize_text(&input, &lines)
+        .map_err(|e| format!("OCR failed: {}", e))?;
+
+    let lines = lines
+        .iter()
+        .flatten()
+        .map(|line| {
+            line.words()
+                .map(|word| {
+                    let rect = word.bounding_rect();
+                    let bounds = Region {
+                        x: rect.left(),
+                        y: rect.top(),
+                        width: rect.width().max(0) as u32,
+                        height: rect.height().max(0) as u32,
+                    };
+                    (word.to_string(), bounds)
+                })
+                .collect()
+        })
+        .collect();
+    Ok(assemble(lines, region))
+}
+
+/// Builds the result from recognised words, grouped into lines in reading
+/// order, offsetting their boxes from the capture by `region`'s origin.
+pub fn assemble(lines: Vec<Vec<(String, Region)>>, region: Region) -> OcrResult {
+    let lines: Vec<OcrLine> = lines
+        .into_iter()
+        .filter_map(|words| {
+            let words: Vec<OcrWord> = words
+                .into_iter()
+                .map(|(text, bounds)| (text.trim().to_string(), bounds))
+                .filter(|(text, _)| !text.is_empty())
+                .map(|(text, bounds)| OcrWord {
+                    text,
+                    bounds: Region {
+                        x: region.x + bounds.x,
+                        y: region.y + bounds.y,
+                        ..bounds
+                    },
+                })
+                .collect();
+            if words.is_empty() {
+                return None;
+            }
+            Some(OcrLine {
+                text: words
+                    .iter()
+                    .map(|w| w.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                bounds: union(words.iter().map(|w| w.bounds)),
+                words,
+            })
+        })
+        .collect();
+
+    OcrResult {
+        text: lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        lines,
+    }
+}
+
+fn union(mut boxes: impl Iterator<Item = Region>) -> Region {
+    let Some(first) = boxes.next() else {
+        return Region {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        };
+    };
+    let (mut left, mut top) = (first.x, first.y);
+    let mut right = first.x + first.width as i32;
+    let mut bottom = first.y + first.height as i32;
+    for b in boxes {
+        left = left.min(b.x);
+        top = top.min(b.y);
+        right = right.max(b.x + b.width as i32);
+        bottom = bottom.max(b.y + b.height as i32);
+    }
+    Region {
+        x: left,
+        y: top,
+        width: (right - left) as u32,
+        height: (bottom - top) as u32,
+    }
+}
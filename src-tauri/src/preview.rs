@@ -0,0 +1,162 @@
+//! Live preview of the monitor an automation runs on, for watching it from
+//! another machine without screen sharing. While enabled, the monitor is
+//! captured at the configured rate, scaled down and emitted as a
+//! `preview-frame` event (also forwarded by the HTTP API). Capturing stops
+//! on the first failure, reported as `preview-stopped`.
+
+use crate::capture::{self, RgbImage};
+use crate::monitors::MonitorInfo;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+pub const MAX_FPS: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewSettings {
+    /// Frames captured per second.
+    pub fps: f64,
+    /// Frames wider than this are scaled down by a whole factor until they
+    /// fit.
+    pub max_width: u32,
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        Self {
+            fps: 2.0,
+            max_width: 960,
+        }
+    }
+}
+
+impl PreviewSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.fps > 0.0 && self.fps <= MAX_FPS) {
+            return Err(format!(
+                "Preview rate must be above 0 and at most {} frames per second",
+                MAX_FPS
+            ));
+        }
+        if self.max_width == 0 {
+            return Err("Preview width must be above 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// The smallest factor that brings `width` within `max_width`.
+    pub fn downscale_factor(&self, width: u32) -> u32 {
+        width.div_ceil(self.max_width.max(1)).max(1)
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewStatus {
+    pub monitor_index: usize,
+    pub settings: PreviewSettings,
+}
+
+/// The running preview, shared through `AppState`.
+#[derive(Clone, Default)]
+pub struct Preview {
+    status: Arc<Mutex<Option<PreviewStatus>>>,
+    /// Replaced for each preview; cleared to stop its capture thread.
+    running: Arc<Mutex<Arc<AtomicBool>>>,
+}
+
+impl Preview {
+    pub fn status(&self) -> Option<PreviewStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Starts previewing `monitor`, replacing any running preview.
+    pub fn start(
+        &self,
+        app_handle: &AppHandle,
+        monitor: MonitorInfo,
+        settings: PreviewSettings,
+    ) -> Result<(), String> {
+        settings.validate()?;
+        self.stop();
+        info!(
+            "Previewing monitor {} at {} fps",
+            monitor.index, settings.fps
+        );
+
+        let running = Arc::new(AtomicBool::new(true));
+        *self.running.lock().unwrap() = running.clone();
+        *self.status.lock().unwrap() = Some(PreviewStatus {
+            monitor_index: monitor.index,
+            settings: settings.clone(),
+        });
+
+        let preview = self.clone();
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || {
+            let mut sequence = 0u64;
+            while running.load(Ordering::SeqCst) {
+                let started = Instant::now();
+                let frame =
+                    capture::capture_monitor(&monitor).and_then(|png| frame_png(&png, &settings));
+                let (png, width, height) = match frame {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        warn!("Preview stopped: {}", e);
+                        if running.swap(false, Ordering::SeqCst) {
+                            preview.status.lock().unwrap().take();
+                            let _ = app_handle.emit("preview-stopped", json!({ "error": e }));
+                        }
+                        return;
+                    }
+                };
+                // Stopped while capturing
+                if !running.load(Ordering::SeqCst) {
+                    return;
+                }
+                sequence += 1;
+                let _ = app_handle.emit(
+                    "preview-frame",
+                    json!({
+                        "sequence": sequence,
+                        "monitor_index": monitor.index,
+                        "width": width,
+                        "height": height,
+                        "png": STANDARD.encode(png),
+                        "captured_at": chrono::Local::now().to_rfc3339(),
+                    }),
+                );
+                if let Some(wait) = settings.interval().checked_sub(started.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Stops the running preview, if any.
+    pub fn stop(&self) {
+        if self.running.lock().unwrap().swap(false, Ordering::SeqCst) {
+            info!("Preview stopped");
+        }
+        self.status.lock().unwrap().take();
+    }
+}
+
+/// Scales a captured frame down for the preview. Returns the PNG and its
+/// size.
+fn frame_png(png: &[u8], settings: &PreviewSettings) -> Result<(Vec<u8>, u32, u32), String> {
+    let image = RgbImage::decode_png(png)?;
+    let frame = image.downscale(settings.downscale_factor(image.width));
+    Ok((frame.encode_png()?, frame.width, frame.height))
+}
@@ -0,0 +1,233 @@
+//! Named profiles for machines shared by several testers. Each profile has
+//! its own settings, stored variables, run history and recordings
+//! directory. The default profile lives in the runner's data directory
+//! itself, so data from before profiles existed carries over.
+
+use crate::executor::environment::ExecutorLaunchOptions;
+use crate::executor::input_limits::InputLimits;
+use crate::executor::restart::RestartSettings;
+use crate::executor::safe_mode::SafeMode;
+use crate::executor::shutdown::ShutdownSettings;
+use crate::idle::IdlePolicy;
+use crate::resources::ResourceLimits;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+const MAX_NAME_LEN: usize = 64;
+
+/// The runner's data directory, holding the default profile and the
+/// others under `profiles/`.
+pub fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("qontinui-runner")
+}
+
+/// Settings saved per profile and applied when it is switched to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    pub resource_limits: ResourceLimits,
+    pub input_limits: InputLimits,
+    pub safe_mode: SafeMode,
+    /// What happens when the executor crashes.
+    pub restart: RestartSettings,
+    /// How long a stopped executor gets to exit.
+    pub shutdown: ShutdownSettings,
+    pub idle_policy: IdlePolicy,
+    /// UI and report locale; detected from the OS when unset.
+    pub locale: Option<String>,
+    /// Interpreter for the bridge scripts; detected when unset.
+    pub python_interpreter: Option<PathBuf>,
+    pub executor_launch: ExecutorLaunchOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfileEntry {
+    name: String,
+    created_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileIndex {
+    #[serde(default)]
+    active: Option<String>,
+    #[serde(default)]
+    profiles: Vec<ProfileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub name: String,
+    pub created_at: Option<String>,
+    pub dir: PathBuf,
+    pub history_dir: PathBuf,
+    pub recordings_dir: PathBuf,
+}
+
+impl Profile {
+    fn new(root: &Path, name: &str, created_at: Option<String>) -> Self {
+        let dir = if name == DEFAULT_PROFILE {
+            root.to_path_buf()
+        } else {
+            root.join("profiles").join(name)
+        };
+        Self {
+            name: name.to_string(),
+            created_at,
+            history_dir: dir.join("history"),
+            recordings_dir: dir.join("recordings"),
+            dir,
+        }
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.dir.join("settings.json")
+    }
+
+    pub fn variables_path(&self) -> PathBuf {
+        self.dir.join("variables.json")
+    }
+
+    /// Recorded message streams of this profile's runs.
+    pub fn streams_dir(&self) -> PathBuf {
+        self.history_dir.join("streams")
+    }
+
+    pub fn load_settings(&self) -> ProfileSettings {
+        let path = self.settings_path();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid profile settings {:?}: {}", path, e);
+                ProfileSettings::default()
+            }),
+            Err(_) => ProfileSettings::default(),
+        }
+    }
+
+    pub fn save_settings(&self, settings: &ProfileSettings) -> Result<(), String> {
+        write_json(&self.settings_path(), settings)
+    }
+}
+
+/// Profile names become directory names, so they are kept simple.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!(
+            "Profile names must be 1 to {} characters long",
+            MAX_NAME_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+        return Err(format!(
+            "Invalid profile name '{}'; use letters, digits, '_' and '-'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// The known profiles and which one is active, persisted in
+/// `profiles.json`.
+#[derive(Clone)]
+pub struct Profiles {
+    root: PathBuf,
+    index: Arc<Mutex<ProfileIndex>>,
+}
+
+impl Profiles {
+    pub fn open(root: PathBuf) -> Self {
+        let path = root.join("profiles.json");
+        let index = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| {
+                serde_json::from_str(&content)
+                    .map_err(|e| warn!("Ignoring invalid profile index {:?}: {}", path, e))
+                    .ok()
+            })
+            .unwrap_or_default();
+        Self {
+            root,
+            index: Arc::new(Mutex::new(index)),
+        }
+    }
+
+    /// The default profile first, then the others in creation order.
+    pub fn list(&self) -> Vec<Profile> {
+        let index = self.index.lock().unwrap();
+        std::iter::once(Profile::new(&self.root, DEFAULT_PROFILE, None))
+            .chain(
+                index.profiles.iter().map(|entry| {
+                    Profile::new(&self.root, &entry.name, Some(entry.created_at.clone()))
+                }),
+            )
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Profile, String> {
+        self.list()
+            .into_iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| format!("Profile '{}' not found", name))
+    }
+
+    /// Falls back to the default profile if the active one has gone.
+    pub fn active(&self) -> Profile {
+        let active = self.index.lock().unwrap().active.clone();
+        active
+            .and_then(|name| self.get(&name).ok())
+            .unwrap_or_else(|| Profile::new(&self.root, DEFAULT_PROFILE, None))
+    }
+
+    /// Creates a profile, starting from `settings`.
+    pub fn create(&self, name: &str, settings: &ProfileSettings) -> Result<Profile, String> {
+        validate_name(name)?;
+        if self.get(name).is_ok() {
+            return Err(format!("Profile '{}' already exists", name));
+        }
+
+        let profile = Profile::new(&self.root, name, Some(chrono::Local::now().to_rfc3339()));
+        for dir in [&profile.dir, &profile.history_dir, &profile.recordings_dir] {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        profile.save_settings(settings)?;
+
+        let mut index = self.index.lock().unwrap();
+        index.profiles.push(ProfileEntry {
+            name: name.to_string(),
+            created_at: profile.created_at.clone().unwrap_or_default(),
+        });
+        self.save(&index)?;
+        info!("Created profile '{}'", name);
+        Ok(profile)
+    }
+
+    pub fn set_active(&self, name: &str) -> Result<Profile, String> {
+        let profile = self.get(name)?;
+        let mut index = self.index.lock().unwrap();
+        index.active = Some(profile.name.clone());
+        self.save(&index)?;
+        info!("Switched to profile '{}'", name);
+        Ok(profile)
+    }
+
+    fn save(&self, index: &ProfileIndex) -> Result<(), String> {
+        write_json(&self.root.join("profiles.json"), index)
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to save {:?}: {}", path, e))
+}
@@ -0,0 +1,270 @@
+//! How far the current execution is, for the frontend to show without
+//! rebuilding it from the raw event stream. When an execution starts, its
+//! workflow is expanded from the loaded configuration (nested workflows
+//! counted each time they're run) into the actions and transitions
+//! (`GO_TO_STATE` actions) it should take; the executor's events then tick
+//! them off. The estimate assumes the remaining actions take as long as the
+//! completed ones did, so it's rough, and loops or retries can make an
+//! execution run past 100%, which is shown as 99% until it completes.
+
+use crate::commands::AppState;
+use crate::config::QontinuiConfig;
+use crate::executor::python_bridge::ExecutorEvent;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often `execution-progress` is emitted while an execution runs.
+const EMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Nested workflows deeper than this aren't expanded.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowProgress {
+    pub workflow_id: String,
+    pub actions_total: usize,
+    pub actions_completed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionProgress {
+    pub workflow_id: Option<String>,
+    pub running: bool,
+    /// Set once the execution completed.
+    pub success: Option<bool>,
+    pub started_at: String,
+    pub elapsed_ms: u64,
+    pub actions_total: usize,
+    pub actions_completed: usize,
+    pub actions_failed: usize,
+    pub transitions_total: usize,
+    pub transitions_completed: usize,
+    pub current_action: Option<String>,
+    pub percent: f64,
+    /// Estimated time left; `None` before the first action completes.
+    pub eta_ms: Option<u64>,
+    /// Per workflow, in the order they're first run.
+    pub workflows: Vec<WorkflowProgress>,
+}
+
+/// The actions an execution of a workflow is expected to take.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub actions_total: usize,
+    pub transitions_total: usize,
+    workflows: Vec<WorkflowProgress>,
+    /// Workflow each action belongs to.
+    action_workflows: HashMap<String, String>,
+    transition_actions: HashSet<String>,
+}
+
+impl Plan {
+    pub fn of(config: &QontinuiConfig, workflow_id: &str) -> Self {
+        let mut plan = Plan::default();
+        plan.expand(config, workflow_id, &mut Vec::new());
+        plan
+    }
+
+    fn expand<'a>(
+        &mut self,
+        config: &'a QontinuiConfig,
+        workflow_id: &'a str,
+        stack: &mut Vec<&'a str>,
+    ) {
+        if stack.contains(&workflow_id) || stack.len() >= MAX_DEPTH {
+            return;
+        }
+        let Some(workflow) = config.workflows.iter().find(|w| w.id == workflow_id) else {
+            return;
+        };
+        stack.push(workflow_id);
+        let index = match self
+            .workflows
+            .iter()
+            .position(|w| w.workflow_id == workflow_id)
+        {
+            Some(index) => index,
+            None => {
+                self.workflows.push(WorkflowProgress {
+                    workflow_id: workflow_id.to_string(),
+                    actions_total: 0,
+                    actions_completed: 0,
+                });
+                self.workflows.len() - 1
+            }
+        };
+        for action in &workflow.actions {
+            self.actions_total += 1;
+            self.workflows[index].actions_total += 1;
+            self.action_workflows
+                .insert(action.id.clone(), workflow_id.to_string());
+            if action.action_type == "GO_TO_STATE" {
+                self.transitions_total += 1;
+                self.transition_actions.insert(action.id.clone());
+            }
+            if let Some(nested) = action.workflow_id() {
+                self.expand(config, nested, stack);
+            }
+        }
+        stack.pop();
+    }
+}
+
+#[derive(Debug)]
+struct Run {
+    workflow_id: Option<String>,
+    plan: Plan,
+    started_at: chrono::DateTime<chrono::Local>,
+    started: Instant,
+    finished: Option<(Instant, bool)>,
+    completed: usize,
+    failed: usize,
+    transitions_completed: usize,
+    current_action: Option<String>,
+}
+
+impl Run {
+    fn progress(&self, now: Instant) -> ExecutionProgress {
+        let elapsed = self.finished.map_or(now, |(at, _)| at) - self.started;
+        let total = self.plan.actions_total;
+        let done = self.completed + self.failed;
+        let success = self.finished.map(|(_, success)| success);
+        let percent = match success {
+            Some(true) => 100.0,
+            _ if total == 0 => 0.0,
+            _ => (done as f64 / total as f64 * 100.0).min(99.0),
+        };
+        let eta = (self.finished.is_none() && done > 0 && done < total)
+            .then(|| elapsed.mul_f64((total - done) as f64 / done as f64));
+        ExecutionProgress {
+            workflow_id: self.workflow_id.clone(),
+            running: self.finished.is_none(),
+            success,
+            started_at: self.started_at.to_rfc3339(),
+            elapsed_ms: elapsed.as_millis() as u64,
+            actions_total: total,
+            actions_completed: self.completed,
+            actions_failed: self.failed,
+            transitions_total: self.plan.transitions_total,
+            transitions_completed: self.transitions_completed,
+            current_action: self.current_action.clone(),
+            percent,
+            eta_ms: eta.map(|eta| eta.as_millis() as u64),
+            workflows: self.plan.workflows.clone(),
+        }
+    }
+}
+
+/// Progress of the default session's latest execution.
+#[derive(Clone, Default)]
+pub struct ProgressTracker {
+    run: Arc<Mutex<Option<Run>>>,
+}
+
+impl ProgressTracker {
+    pub fn record_event(
+        &self,
+        event: &ExecutorEvent,
+        config: Option<&QontinuiConfig>,
+        now: Instant,
+    ) {
+        let text = |key: &str| event.data.get(key).and_then(|v| v.as_str());
+        let mut run = self.run.lock().unwrap();
+        if event.event == "execution_started" {
+            let workflow_id = text("workflow_id").map(str::to_string);
+            let plan = match (config, &workflow_id) {
+                (Some(config), Some(workflow_id)) => Plan::of(config, workflow_id),
+                _ => Plan::default(),
+            };
+            *run = Some(Run {
+                workflow_id,
+                plan,
+                started_at: chrono::Local::now(),
+                started: now,
+                finished: None,
+                completed: 0,
+                failed: 0,
+                transitions_completed: 0,
+                current_action: None,
+            });
+            return;
+        }
+        let Some(run) = run.as_mut().filter(|run| run.finished.is_none()) else {
+            return;
+        };
+        match event.event.as_str() {
+            "action_started" => run.current_action = text("action_id").map(str::to_string),
+            "action_completed" => {
+                let action_id = text("action_id").unwrap_or_default();
+                if event.data.get("success").and_then(|s| s.as_bool()) == Some(true) {
+                    run.completed += 1;
+                    if run.plan.transition_actions.contains(action_id) {
+                        run.transitions_completed += 1;
+                    }
+                    if let Some(workflow_id) = run.plan.action_workflows.get(action_id) {
+                        if let Some(workflow) = run
+                            .plan
+                            .workflows
+                            .iter_mut()
+                            .find(|w| &w.workflow_id == workflow_id)
+                        {
+                            workflow.actions_completed += 1;
+                        }
+                    }
+                } else {
+                    run.failed += 1;
+                }
+                run.current_action = None;
+            }
+            "execution_completed" => {
+                let success = event.data.get("success").and_then(|s| s.as_bool());
+                run.finished = Some((now, success.unwrap_or(false)));
+                run.current_action = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn progress(&self, now: Instant) -> Option<ExecutionProgress> {
+        self.run
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|run| run.progress(now))
+    }
+}
+
+pub fn record_event(app_handle: &AppHandle, event: &ExecutorEvent) {
+    let state = app_handle.state::<AppState>();
+    let config = state.current_config.lock().unwrap().clone();
+    state
+        .progress
+        .record_event(event, config.as_deref(), Instant::now());
+}
+
+/// Emits `execution-progress` every second while an execution runs, and
+/// once when it has ended.
+pub fn start(app_handle: AppHandle) {
+    thread::spawn(move || {
+        // Start of the execution whose end was last emitted
+        let mut ended: Option<String> = None;
+        loop {
+            thread::sleep(EMIT_INTERVAL);
+            let state = app_handle.state::<AppState>();
+            let Some(progress) = state.progress.progress(Instant::now()) else {
+                continue;
+            };
+            if !progress.running {
+                if ended.as_ref() == Some(&progress.started_at) {
+                    continue;
+                }
+                ended = Some(progress.started_at.clone());
+            }
+            let _ = app_handle.emit("execution-progress", &progress);
+        }
+    });
+}
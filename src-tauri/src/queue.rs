@@ -0,0 +1,382 @@
+//! Workflows queued to run back to back on the default executor session.
+//! A background task starts each one once the previous execution has
+//! completed and reports every step as a `queue-progress` event. A failed
+//! run doesn't stop the queue; cancelling the task does, leaving what's
+//! still waiting in place.
+
+use crate::commands::{self, AppState};
+use crate::executor::execution_request::ExecutionRequest;
+use crate::executor::python_bridge::ExecutorEvent;
+use crate::executor::sessions::{self, DEFAULT_SESSION};
+use crate::run_plan::QueuedRun;
+use crate::tasks::TaskContext;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// How often a waiting worker checks for cancellation and a stopped
+/// executor.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    Waiting,
+    Running,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QueueParams {
+    pub monitor_index: Option<i32>,
+    /// Shown in the queue and run plan instead of the workflow id.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub id: String,
+    pub process_id: String,
+    pub monitor_index: Option<i32>,
+    pub label: String,
+    pub status: EntryStatus,
+    pub enqueued_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueProgress {
+    /// `started`, `completed`, `failed`, `cancelled` or `idle` once the
+    /// queue has drained.
+    pub status: &'static str,
+    pub entry: Option<QueueEntry>,
+    pub error: Option<String>,
+    /// Entries still waiting.
+    pub remaining: usize,
+    pub completed: u32,
+    pub failed: u32,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    /// The running entry, if any, is first.
+    entries: VecDeque<QueueEntry>,
+    worker_running: bool,
+    /// The running entry's execution has started.
+    started: bool,
+    /// Success of the running entry's execution, once it has completed.
+    outcome: Option<bool>,
+    /// The running entry was cancelled.
+    cancelled: bool,
+    completed: u32,
+    failed: u32,
+}
+
+/// Follows the queue and the execution of its running entry.
+#[derive(Clone, Default)]
+pub struct ExecutionQueue {
+    state: Arc<(Mutex<QueueState>, Condvar)>,
+}
+
+impl ExecutionQueue {
+    /// Adds a run at the end. Returns the entry and whether a worker needs
+    /// to be started for it.
+    pub fn enqueue(&self, process_id: &str, params: QueueParams) -> (QueueEntry, bool) {
+        let entry = QueueEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            process_id: process_id.to_string(),
+            monitor_index: params.monitor_index,
+            label: params
+                .label
+                .unwrap_or_else(|| format!("Run {}", process_id)),
+            status: EntryStatus::Waiting,
+            enqueued_at: chrono::Local::now().to_rfc3339(),
+        };
+        let mut state = self.state.0.lock().unwrap();
+        state.entries.push_back(entry.clone());
+        let start_worker = !state.worker_running;
+        state.worker_running = true;
+        (entry, start_worker)
+    }
+
+    pub fn entries(&self) -> Vec<QueueEntry> {
+        self.state
+            .0
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Puts the waiting entries in the order of `ids`. Every waiting entry
+    /// must be listed; the running one stays first whether listed or not.
+    pub fn reorder(&self, ids: &[String]) -> Result<Vec<QueueEntry>, String> {
+        let mut state = self.state.0.lock().unwrap();
+        let (running, mut waiting): (Vec<_>, Vec<_>) = state
+            .entries
+            .drain(..)
+            .partition(|entry| entry.status == EntryStatus::Running);
+        let ids: Vec<&String> = ids
+            .iter()
+            .filter(|id| !running.iter().any(|entry| &&entry.id == id))
+            .collect();
+        let complete =
+            ids.len() == waiting.len() && waiting.iter().all(|entry| ids.contains(&&entry.id));
+        if complete {
+            waiting.sort_by_key(|entry| ids.iter().position(|id| **id == entry.id));
+        }
+        state.entries = running.into_iter().chain(waiting).collect();
+        if !complete {
+            return Err("The new order must list every waiting entry once".to_string());
+        }
+        Ok(state.entries.iter().cloned().collect())
+    }
+
+    /// Removes a waiting entry, or flags the running one as cancelled so
+    /// its completion is reported that way. Returns the entry.
+    pub fn cancel(&self, id: &str) -> Result<QueueEntry, String> {
+        let mut state = self.state.0.lock().unwrap();
+        let index = state
+            .entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or_else(|| format!("No queued run '{}'", id))?;
+        if state.entries[index].status == EntryStatus::Running {
+            state.cancelled = true;
+            return Ok(state.entries[index].clone());
+        }
+        Ok(state.entries.remove(index).unwrap())
+    }
+
+    /// Marks the first waiting entry as running and returns it. With
+    /// nothing left the worker is done.
+    pub fn next(&self) -> Option<QueueEntry> {
+        let mut state = self.state.0.lock().unwrap();
+        state.started = false;
+        state.outcome = None;
+        state.cancelled = false;
+        match state.entries.front_mut() {
+            Some(entry) => {
+                entry.status = EntryStatus::Running;
+                Some(entry.clone())
+            }
+            None => {
+                state.worker_running = false;
+                None
+            }
+        }
+    }
+
+    /// Lets a new worker start after this one quits early.
+    pub fn release_worker(&self) {
+        self.state.0.lock().unwrap().worker_running = false;
+    }
+
+    /// Follows the running entry's execution.
+    pub fn record_event(&self, event: &ExecutorEvent) {
+        let (lock, arrived) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        let running = state
+            .entries
+            .front()
+            .is_some_and(|entry| entry.status == EntryStatus::Running);
+        if !running {
+            return;
+        }
+        match event.event.as_str() {
+            "execution_started" => state.started = true,
+            // Only once ours started, so the end of an earlier run isn't taken for it
+            "execution_completed" if state.started => {
+                state.outcome = Some(
+                    event
+                        .data
+                        .get("success")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                );
+                arrived.notify_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// Waits up to `timeout` for the running entry's execution to complete.
+    pub fn wait_for_outcome(&self, timeout: Duration) -> Option<bool> {
+        let (lock, arrived) = &*self.state;
+        let state = lock.lock().unwrap();
+        let (state, _) = arrived
+            .wait_timeout_while(state, timeout, |state| state.outcome.is_none())
+            .unwrap();
+        state.outcome
+    }
+
+    /// Takes the running entry off the queue and reports how it ended.
+    pub fn finish(&self, result: Result<bool, String>) -> QueueProgress {
+        let mut state = self.state.0.lock().unwrap();
+        let entry = state.entries.pop_front();
+        let (status, error) = match result {
+            _ if state.cancelled => ("cancelled", None),
+            Ok(true) => ("completed", None),
+            Ok(false) => ("failed", Some("Execution failed".to_string())),
+            Err(e) => ("failed", Some(e)),
+        };
+        match status {
+            "completed" => state.completed += 1,
+            "failed" => state.failed += 1,
+            _ => {}
+        }
+        QueueProgress {
+            status,
+            entry,
+            error,
+            remaining: state.entries.len(),
+            completed: state.completed,
+            failed: state.failed,
+        }
+    }
+
+    /// A progress report not tied to one entry.
+    pub fn progress(&self, status: &'static str, entry: Option<QueueEntry>) -> QueueProgress {
+        let state = self.state.0.lock().unwrap();
+        QueueProgress {
+            status,
+            entry,
+            error: None,
+            remaining: state
+                .entries
+                .iter()
+                .filter(|entry| entry.status == EntryStatus::Waiting)
+                .count(),
+            completed: state.completed,
+            failed: state.failed,
+        }
+    }
+}
+
+/// Queues a run and starts the worker if it isn't running.
+pub fn enqueue(
+    app_handle: &AppHandle,
+    process_id: &str,
+    params: QueueParams,
+) -> Result<QueueEntry, String> {
+    if process_id.is_empty() {
+        return Err("Workflow ID is required".to_string());
+    }
+    let state = app_handle.state::<AppState>();
+    let (entry, start_worker) = state.execution_queue.enqueue(process_id, params);
+    state.run_tracker.enqueue(QueuedRun {
+        task_id: entry.id.clone(),
+        workflow_id: entry.process_id.clone(),
+        label: entry.label.clone(),
+        status: Some("queued".to_string()),
+        ready_at: None,
+    });
+    if start_worker {
+        let handle = app_handle.clone();
+        state.tasks.spawn(
+            app_handle.clone(),
+            "execution_queue",
+            "Execution queue",
+            move |task| run(&handle, task),
+        );
+    }
+    Ok(entry)
+}
+
+/// Takes an entry off the queue, stopping its execution if it's running.
+pub fn cancel(app_handle: &AppHandle, id: &str) -> Result<QueueEntry, String> {
+    let state = app_handle.state::<AppState>();
+    let entry = state.execution_queue.cancel(id)?;
+    state.run_tracker.dequeue(id);
+    if entry.status == EntryStatus::Running {
+        sessions::with_bridge(&state, DEFAULT_SESSION, |bridge| bridge.stop_execution())?;
+    } else {
+        emit(
+            app_handle,
+            &state
+                .execution_queue
+                .progress("cancelled", Some(entry.clone())),
+        );
+    }
+    Ok(entry)
+}
+
+/// The worker: runs entries until the queue is empty or the task is
+/// cancelled.
+fn run(app_handle: &AppHandle, task: &TaskContext) -> Result<Value, String> {
+    let state = app_handle.state::<AppState>();
+    let queue = &state.execution_queue;
+    loop {
+        if task.is_cancelled() {
+            queue.release_worker();
+            info!("Execution queue stopped");
+            break;
+        }
+        let Some(entry) = queue.next() else {
+            break;
+        };
+        state.run_tracker.dequeue(&entry.id);
+        info!("Starting queued run of {}", entry.process_id);
+        emit(app_handle, &queue.progress("started", Some(entry.clone())));
+        task.progress(0.0, format!("Running {}", entry.label));
+
+        let result = commands::begin_execution(
+            &state,
+            Some(entry.process_id.clone()),
+            entry.monitor_index,
+            &ExecutionRequest::default(),
+            None,
+        )
+        .and_then(|_| wait_for_completion(&state, task));
+        if let Err(e) = &result {
+            warn!("Queued run of {} failed: {}", entry.process_id, e);
+        }
+        emit(app_handle, &queue.finish(result));
+    }
+
+    let idle = queue.progress("idle", None);
+    emit(app_handle, &idle);
+    Ok(json!({ "completed": idle.completed, "failed": idle.failed }))
+}
+
+/// Waits for the running entry's execution to complete. Fails if the
+/// executor goes away first; cancelling the task stops the execution.
+fn wait_for_completion(state: &AppState, task: &TaskContext) -> Result<bool, String> {
+    let queue = &state.execution_queue;
+    loop {
+        if let Some(success) = queue.wait_for_outcome(POLL_INTERVAL) {
+            return Ok(success);
+        }
+        if task.is_cancelled() {
+            sessions::with_bridge(state, DEFAULT_SESSION, |bridge| bridge.stop_execution())?;
+            return Err("Queue cancelled".to_string());
+        }
+        let running = state
+            .executor
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|bridge| bridge.is_running());
+        if !running {
+            return Err("Python executor stopped".to_string());
+        }
+    }
+}
+
+fn emit(app_handle: &AppHandle, progress: &QueueProgress) {
+    app_handle.state::<AppState>().agent_jobs.record(progress);
+    let _ = app_handle.emit("queue-progress", progress);
+}
+
+/// Feeds a default-session executor event to the app's queue.
+pub fn record_event(app_handle: &AppHandle, event: &ExecutorEvent) {
+    app_handle
+        .state::<AppState>()
+        .execution_queue
+        .record_event(event);
+}
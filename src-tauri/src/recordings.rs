@@ -0,0 +1,312 @@
+//! The recordings library: the executor's recordings and the runner's
+//! videos, listed in `recordings.json` in the profile's recordings
+//! directory with when they were made, how long they ran, how many frames
+//! they hold and the configuration that was loaded. Renaming changes the
+//! name shown, not the files. Deleting removes the files too, but only
+//! those inside the recordings directory; an executor recording made
+//! elsewhere is just forgotten.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingKind {
+    /// Frames and actions recorded by the Python executor into a folder.
+    Executor,
+    /// A screen video recorded by the runner.
+    Video,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingEntry {
+    pub id: String,
+    pub name: String,
+    pub kind: RecordingKind,
+    /// The video file, or the executor's recording folder.
+    pub path: PathBuf,
+    #[serde(default)]
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub config_name: Option<String>,
+    #[serde(default)]
+    pub config_path: Option<String>,
+    #[serde(default)]
+    pub monitor_index: Option<usize>,
+    /// Frame rate of a video.
+    #[serde(default)]
+    pub fps: Option<u32>,
+    pub started_at: String,
+    /// `None` while recording.
+    #[serde(default)]
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub duration_ms: Option<i64>,
+    /// Images in an executor recording; estimated from the duration for a
+    /// video.
+    #[serde(default)]
+    pub frame_count: Option<u64>,
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+}
+
+impl RecordingEntry {
+    /// A recording starting now, named after its file.
+    pub fn new(kind: RecordingKind, path: PathBuf) -> Self {
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Recording".to_string());
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            kind,
+            path,
+            run_id: None,
+            config_name: None,
+            config_path: None,
+            monitor_index: None,
+            fps: None,
+            started_at: chrono::Local::now().to_rfc3339(),
+            finished_at: None,
+            duration_ms: None,
+            frame_count: None,
+            size_bytes: None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.finished_at.is_none()
+    }
+
+    /// Whether the recording covers some of the time from `start` to `end`
+    /// (RFC 3339). One still going runs up to now.
+    pub fn overlaps(&self, start: &str, end: &str) -> bool {
+        let parse = |time: &str| chrono::DateTime::parse_from_rfc3339(time).ok();
+        let (Some(start), Some(end), Some(started)) =
+            (parse(start), parse(end), parse(&self.started_at))
+        else {
+            return false;
+        };
+        let finished = match &self.finished_at {
+            Some(finished) => parse(finished),
+            None => Some(chrono::Local::now().fixed_offset()),
+        };
+        started <= end && finished.is_some_and(|finished| finished >= start)
+    }
+
+    /// Marks the recording finished at `finished_at` and fills in what can
+    /// be measured from its files.
+    fn finish(&mut self, finished_at: chrono::DateTime<chrono::FixedOffset>) {
+        self.finished_at = Some(finished_at.to_rfc3339());
+        self.duration_ms = chrono::DateTime::parse_from_rfc3339(&self.started_at)
+            .ok()
+            .map(|started| (finished_at - started).num_milliseconds().max(0));
+        self.refresh();
+    }
+
+    /// Updates the size and frame count from the files.
+    fn refresh(&mut self) {
+        let (size, images) = measure(&self.path);
+        self.size_bytes = Some(size);
+        self.frame_count = match self.kind {
+            RecordingKind::Executor => Some(images),
+            RecordingKind::Video => self
+                .fps
+                .zip(self.duration_ms)
+                .map(|(fps, duration_ms)| fps as u64 * duration_ms as u64 / 1000),
+        };
+    }
+}
+
+/// Total size of the files at `path` and how many of them are images.
+fn measure(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        let image = path.extension().is_some_and(|ext| {
+            IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+        });
+        return (metadata.len(), image as u64);
+    }
+    std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| measure(&entry.path()))
+        .fold((0, 0), |(size, images), (more_size, more_images)| {
+            (size + more_size, images + more_images)
+        })
+}
+
+/// The recordings of the active profile, shared through `AppState`.
+#[derive(Clone)]
+pub struct Recordings {
+    dir: Arc<Mutex<PathBuf>>,
+    entries: Arc<Mutex<Vec<RecordingEntry>>>,
+}
+
+impl Recordings {
+    /// Reads the library in `dir`. Recordings left unfinished by a runner
+    /// that exited during them end when their files were last written.
+    pub fn open(dir: PathBuf) -> Self {
+        let recordings = Self {
+            dir: Arc::new(Mutex::new(dir)),
+            entries: Default::default(),
+        };
+        recordings.reload();
+        recordings
+    }
+
+    /// Switches to the library in `dir`, e.g. after a profile switch.
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.lock().unwrap() = dir;
+        self.reload();
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.dir.lock().unwrap().clone()
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir().join("recordings.json")
+    }
+
+    fn reload(&self) {
+        let path = self.index_path();
+        let mut entries: Vec<RecordingEntry> = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid recordings index {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        let mut interrupted = false;
+        for entry in entries.iter_mut().filter(|entry| entry.is_recording()) {
+            let modified = std::fs::metadata(&entry.path)
+                .and_then(|metadata| metadata.modified())
+                .map(chrono::DateTime::<chrono::Local>::from)
+                .unwrap_or_else(|_| chrono::Local::now());
+            entry.finish(modified.fixed_offset());
+            interrupted = true;
+        }
+        *self.entries.lock().unwrap() = entries;
+        if interrupted {
+            if let Err(e) = self.update(|_| ()) {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    /// Newest first.
+    pub fn list(&self) -> Vec<RecordingEntry> {
+        let mut entries = self.entries.lock().unwrap().clone();
+        entries.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        entries
+    }
+
+    /// The recording of `kind` in progress, if any.
+    pub fn active(&self, kind: RecordingKind) -> Option<RecordingEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|entry| entry.kind == kind && entry.is_recording())
+            .cloned()
+    }
+
+    /// Adds a recording that has just started.
+    pub fn begin(&self, entry: RecordingEntry) -> Result<RecordingEntry, String> {
+        info!("Recording {} started: {:?}", entry.id, entry.path);
+        self.update(|entries| entries.push(entry.clone()))?;
+        Ok(entry)
+    }
+
+    /// Marks a recording finished now.
+    pub fn finish(&self, id: &str) -> Result<RecordingEntry, String> {
+        self.update(|entries| {
+            let entry = find(entries, id)?;
+            entry.finish(chrono::Local::now().fixed_offset());
+            Ok(entry.clone())
+        })?
+    }
+
+    /// A recording with its size and frame count measured again.
+    pub fn metadata(&self, id: &str) -> Result<RecordingEntry, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = find(&mut entries, id)?;
+        entry.refresh();
+        Ok(entry.clone())
+    }
+
+    pub fn rename(&self, id: &str, name: &str) -> Result<RecordingEntry, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Recording names can't be empty".to_string());
+        }
+        self.update(|entries| {
+            let entry = find(entries, id)?;
+            entry.name = name.to_string();
+            Ok(entry.clone())
+        })?
+    }
+
+    /// Removes a finished recording, with its files if they're in the
+    /// recordings directory.
+    pub fn delete(&self, id: &str) -> Result<RecordingEntry, String> {
+        let entry = self.metadata(id)?;
+        if entry.is_recording() {
+            return Err(format!("Recording '{}' is still going", entry.name));
+        }
+        if entry.path.starts_with(self.dir()) && entry.path != self.dir() {
+            let removed = if entry.path.is_dir() {
+                std::fs::remove_dir_all(&entry.path)
+            } else {
+                std::fs::remove_file(&entry.path)
+            };
+            match removed {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("Failed to delete {:?}: {}", entry.path, e)),
+            }
+        }
+        self.update(|entries| entries.retain(|other| other.id != id))?;
+        info!("Recording {} deleted", id);
+        Ok(entry)
+    }
+
+    /// Files of the recordings of `kind` covering some of the time from
+    /// `start` to `end`, including one still going.
+    pub fn overlapping(&self, kind: RecordingKind, start: &str, end: &str) -> Vec<PathBuf> {
+        self.list()
+            .into_iter()
+            .filter(|entry| entry.kind == kind && entry.overlaps(start, end))
+            .map(|entry| entry.path)
+            .collect()
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut Vec<RecordingEntry>) -> T) -> Result<T, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let result = change(&mut entries);
+        let path = self.index_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        let json = serde_json::to_string_pretty(&*entries).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to save {:?}: {}", path, e))?;
+        Ok(result)
+    }
+}
+
+fn find<'a>(entries: &'a mut [RecordingEntry], id: &str) -> Result<&'a mut RecordingEntry, String> {
+    entries
+        .iter_mut()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Recording '{}' not found", id))
+}
@@ -0,0 +1,135 @@
+//! Picking a region of the screen with the mouse, for building
+//! configurations from the runner and for confining execution to part of a
+//! screen. A transparent window covering the monitor lets the user drag a
+//! rectangle; it reports the rectangle in CSS pixels, or null when Escape
+//! cancels, as a `region-selection` event.
+
+use crate::capture::Region;
+use crate::monitors::MonitorInfo;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{
+    AppHandle, Listener, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder,
+    WindowEvent,
+};
+use tracing::info;
+
+pub const WINDOW_LABEL: &str = "region-selector";
+const SELECTION_EVENT: &str = "region-selection";
+
+/// The rectangle dragged on the overlay, in its CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct OverlayRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SelectedRegion {
+    pub monitor_index: usize,
+    /// Physical pixels relative to the monitor, as captures take them.
+    pub region: Region,
+    /// Physical pixels on the virtual desktop.
+    pub absolute: Region,
+}
+
+/// Converts a rectangle dragged on the overlay covering `monitor` into
+/// physical pixels, clipped to the monitor. `None` when nothing is left.
+pub fn to_region(monitor: &MonitorInfo, rect: OverlayRect) -> Option<SelectedRegion> {
+    let scale = monitor.scale_factor;
+    let physical = |value: f64, max: u32| (value * scale).round().clamp(0.0, max as f64) as i32;
+    let left = physical(rect.x.min(rect.x + rect.width), monitor.width);
+    let right = physical(rect.x.max(rect.x + rect.width), monitor.width);
+    let top = physical(rect.y.min(rect.y + rect.height), monitor.height);
+    let bottom = physical(rect.y.max(rect.y + rect.height), monitor.height);
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    let region = Region {
+        x: left,
+        y: top,
+        width: (right - left) as u32,
+        height: (bottom - top) as u32,
+    };
+    Some(SelectedRegion {
+        monitor_index: monitor.index,
+        region,
+        absolute: Region {
+            x: monitor.x + region.x,
+            y: monitor.y + region.y,
+            ..region
+        },
+    })
+}
+
+/// Covers `monitor` with the overlay and waits for the user to select a
+/// region. `None` when cancelled.
+pub async fn select(
+    app_handle: &AppHandle,
+    monitor: MonitorInfo,
+) -> Result<Option<SelectedRegion>, String> {
+    if app_handle.get_webview_window(WINDOW_LABEL).is_some() {
+        return Err("A region selection is already in progress".to_string());
+    }
+    info!("Selecting a region on monitor {}", monitor.index);
+
+    let (sender, receiver) = tokio::sync::oneshot::channel::<Option<OverlayRect>>();
+    let sender = Arc::new(Mutex::new(Some(sender)));
+    let reply = move |rect: Option<OverlayRect>| {
+        if let Some(sender) = sender.lock().unwrap().take() {
+            let _ = sender.send(rect);
+        }
+    };
+
+    let on_selection = reply.clone();
+    let listener = app_handle.listen(SELECTION_EVENT, move |event| {
+        on_selection(serde_json::from_str(event.payload()).ok().flatten());
+    });
+    let window = WebviewWindowBuilder::new(
+        app_handle,
+        WINDOW_LABEL,
+        WebviewUrl::App("region-selector.html".into()),
+    )
+    .title("Select a region")
+    .decorations(false)
+    .transparent(true)
+    .shadow(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .resizable(false)
+    .visible(false)
+    .build();
+    let window = match window {
+        Ok(window) => window,
+        Err(e) => {
+            app_handle.unlisten(listener);
+            return Err(format!("Failed to open the region selector: {}", e));
+        }
+    };
+    // Closed some other way than with Escape
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            reply(None);
+        }
+    });
+
+    // Placed in physical pixels, so scaled monitors are covered exactly
+    let shown = window
+        .set_position(PhysicalPosition::new(monitor.x, monitor.y))
+        .and_then(|_| window.set_size(PhysicalSize::new(monitor.width, monitor.height)))
+        .and_then(|_| window.show())
+        .and_then(|_| window.set_focus());
+    let rect = if shown.is_ok() {
+        receiver.await.ok().flatten()
+    } else {
+        None
+    };
+    app_handle.unlisten(listener);
+    let _ = window.destroy();
+    shown.map_err(|e| format!("Failed to show the region selector: {}", e))?;
+
+    Ok(rect.and_then(|rect| to_region(&monitor, rect)))
+}
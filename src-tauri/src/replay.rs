@@ -0,0 +1,337 @@
+//! Replays a recorded trace of mouse and keyboard input natively, without
+//! the executor or the configuration's states. The trace is JSON Lines,
+//! one event per line with `timestamp` (or `t`) in milliseconds and its
+//! `type`: `mouse_move`, `mouse_down`, `mouse_up`, `click`, `scroll`,
+//! `key_down`, `key_up`, `key_press` or `text`. Events are played at their
+//! recorded pace, scaled by the replay speed; pausing holds the schedule.
+//! The stop and abort hotkeys stop a replay too.
+
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+pub const MIN_SPEED: f64 = 0.1;
+pub const MAX_SPEED: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Middle,
+    Right,
+}
+
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => Button::Left,
+            MouseButton::Middle => Button::Middle,
+            MouseButton::Right => Button::Right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputEvent {
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    /// Presses at the pointer, after moving it when a position is given.
+    MouseDown {
+        #[serde(default)]
+        button: MouseButton,
+        x: Option<i32>,
+        y: Option<i32>,
+    },
+    MouseUp {
+        #[serde(default)]
+        button: MouseButton,
+        x: Option<i32>,
+        y: Option<i32>,
+    },
+    Click {
+        #[serde(default)]
+        button: MouseButton,
+        x: Option<i32>,
+        y: Option<i32>,
+    },
+    /// Positive `dy` scrolls down, positive `dx` right.
+    Scroll {
+        #[serde(default)]
+        dx: i32,
+        #[serde(default)]
+        dy: i32,
+    },
+    KeyDown {
+        key: String,
+    },
+    KeyUp {
+        key: String,
+    },
+    KeyPress {
+        key: String,
+    },
+    Text {
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TimedEvent {
+    #[serde(alias = "t")]
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: InputEvent,
+}
+
+/// Reads a trace, checking every event can be played before any is.
+pub fn parse_trace(content: &str) -> Result<Vec<TimedEvent>, String> {
+    let mut events = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: TimedEvent = serde_json::from_str(line)
+            .map_err(|e| format!("Invalid event on line {}: {}", i + 1, e))?;
+        if let InputEvent::KeyDown { key }
+        | InputEvent::KeyUp { key }
+        | InputEvent::KeyPress { key } = &event.event
+        {
+            parse_key(key).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        }
+        events.push(event);
+    }
+    // Stable, so events recorded in the same millisecond keep their order
+    events.sort_by_key(|event| event.timestamp);
+    Ok(events)
+}
+
+/// A key by name (`enter`, `ctrl`, `f5`, ...) or as the character it types.
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Unicode(c));
+    }
+    let key = match name.to_lowercase().as_str() {
+        "enter" | "return" => Key::Return,
+        "esc" | "escape" => Key::Escape,
+        "tab" => Key::Tab,
+        "backspace" => Key::Backspace,
+        "space" => Key::Space,
+        "delete" | "del" => Key::Delete,
+        "shift" => Key::Shift,
+        "ctrl" | "control" => Key::Control,
+        "alt" | "option" => Key::Alt,
+        "meta" | "cmd" | "command" | "super" | "win" => Key::Meta,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => return Err(format!("Unknown key '{}'", name)),
+    };
+    Ok(key)
+}
+
+/// When `event` is due after the replay started, at `speed`.
+pub fn offset(event: &TimedEvent, first: &TimedEvent, speed: f64) -> Duration {
+    let recorded = event.timestamp.saturating_sub(first.timestamp);
+    Duration::from_secs_f64(recorded as f64 / 1000.0 / speed)
+}
+
+fn perform(enigo: &mut Enigo, event: &InputEvent) -> Result<(), String> {
+    let move_to = |enigo: &mut Enigo, x: Option<i32>, y: Option<i32>| match x.zip(y) {
+        Some((x, y)) => enigo.move_mouse(x, y, Coordinate::Abs),
+        None => Ok(()),
+    };
+    let result =
+        match event {
+            InputEvent::MouseMove { x, y } => enigo.move_mouse(*x, *y, Coordinate::Abs),
+            InputEvent::MouseDown { button, x, y } => move_to(enigo, *x, *y)
+                .and_then(|_| enigo.button((*button).into(), Direction::Press)),
+            InputEvent::MouseUp { button, x, y } => move_to(enigo, *x, *y)
+                .and_then(|_| enigo.button((*button).into(), Direction::Release)),
+            InputEvent::Click { button, x, y } => move_to(enigo, *x, *y)
+                .and_then(|_| enigo.button((*button).into(), Direction::Click)),
+            InputEvent::Scroll { dx, dy } => {
+                let horizontal = match dx {
+                    0 => Ok(()),
+                    dx => enigo.scroll(*dx, Axis::Horizontal),
+                };
+                horizontal.and_then(|_| match dy {
+                    0 => Ok(()),
+                    dy => enigo.scroll(*dy, Axis::Vertical),
+                })
+            }
+            InputEvent::KeyDown { key } => enigo.key(parse_key(key)?, Direction::Press),
+            InputEvent::KeyUp { key } => enigo.key(parse_key(key)?, Direction::Release),
+            InputEvent::KeyPress { key } => enigo.key(parse_key(key)?, Direction::Click),
+            InputEvent::Text { text } => enigo.text(text),
+        };
+    result.map_err(|e| format!("Failed to replay {:?}: {}", event, e))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStatus {
+    pub path: String,
+    pub speed: f64,
+    pub state: ReplayState,
+    pub played: usize,
+    pub total: usize,
+}
+
+/// The running replay, shared through `AppState`.
+#[derive(Clone, Default)]
+pub struct Replay {
+    status: Arc<(Mutex<Option<ReplayStatus>>, Condvar)>,
+}
+
+impl Replay {
+    pub fn status(&self) -> Option<ReplayStatus> {
+        self.status.0.lock().unwrap().clone()
+    }
+
+    /// Replays the trace at `path`. Fails while another replay runs.
+    pub fn start(&self, app_handle: &AppHandle, path: &str, speed: f64) -> Result<usize, String> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(format!(
+                "Replay speed must be between {} and {}",
+                MIN_SPEED, MAX_SPEED
+            ));
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read trace {}: {}", path, e))?;
+        let events = parse_trace(&content)?;
+        if events.is_empty() {
+            return Err(format!("Trace {} holds no events", path));
+        }
+        let total = events.len();
+
+        {
+            let mut status = self.status.0.lock().unwrap();
+            if status.is_some() {
+                return Err("A replay is already running".to_string());
+            }
+            *status = Some(ReplayStatus {
+                path: path.to_string(),
+                speed,
+                state: ReplayState::Playing,
+                played: 0,
+                total,
+            });
+        }
+        info!("Replaying {} events from {} at {}x", total, path, speed);
+
+        let replay = self.clone();
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || {
+            let result = replay.play(&events, speed);
+            let status = replay.status.0.lock().unwrap().take();
+            let played = status.map_or(0, |status| status.played);
+            match &result {
+                Ok(()) => info!("Replay ended after {} of {} events", played, total),
+                Err(e) => warn!("Replay failed: {}", e),
+            }
+            let _ = app_handle.emit(
+                "replay-finished",
+                json!({
+                    "played": played,
+                    "total": total,
+                    "error": result.err(),
+                }),
+            );
+        });
+        Ok(total)
+    }
+
+    fn play(&self, events: &[TimedEvent], speed: f64) -> Result<(), String> {
+        let mut enigo = Enigo::new(&enigo::Settings::default())
+            .map_err(|e| format!("Failed to start input replay: {}", e))?;
+        let (lock, changed) = &*self.status;
+        let mut started = Instant::now();
+        for event in events {
+            let due = offset(event, &events[0], speed);
+            let mut status = lock.lock().unwrap();
+            loop {
+                match status.as_ref().map(|status| status.state) {
+                    None | Some(ReplayState::Stopped) => return Ok(()),
+                    Some(ReplayState::Paused) => {
+                        // The time spent paused moves the rest of the schedule
+                        let paused = Instant::now();
+                        status = changed.wait(status).unwrap();
+                        started += paused.elapsed();
+                    }
+                    Some(ReplayState::Playing) => {
+                        let Some(wait) = (started + due).checked_duration_since(Instant::now())
+                        else {
+                            break;
+                        };
+                        status = changed.wait_timeout(status, wait).unwrap().0;
+                    }
+                }
+            }
+            drop(status);
+
+            perform(&mut enigo, &event.event)?;
+            if let Some(status) = lock.lock().unwrap().as_mut() {
+                status.played += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_state(&self, state: ReplayState) -> Result<ReplayStatus, String> {
+        let mut status = self.status.0.lock().unwrap();
+        let Some(status) = status.as_mut() else {
+            return Err("No replay is running".to_string());
+        };
+        status.state = state;
+        self.status.1.notify_all();
+        Ok(status.clone())
+    }
+
+    pub fn pause(&self) -> Result<ReplayStatus, String> {
+        self.set_state(ReplayState::Paused)
+    }
+
+    pub fn resume(&self) -> Result<ReplayStatus, String> {
+        self.set_state(ReplayState::Playing)
+    }
+
+    /// Stops the replay after the event being played, if any.
+    pub fn stop(&self) -> Option<ReplayStatus> {
+        let status = self.set_state(ReplayState::Stopped).ok()?;
+        info!("Replay stopped");
+        Some(status)
+    }
+}
@@ -0,0 +1,201 @@
+use crate::commands::AppState;
+use crate::executor::python_bridge::ExecutorEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::mem::size_of;
+use std::thread;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// How often the guardrail thread samples memory usage.
+const MONITOR_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Thresholds for the runner's own resource usage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Emit a `resource-warning` event when the runner's RSS exceeds this.
+    pub memory_warning_mb: u64,
+    /// Configurations larger than this need explicit confirmation to load.
+    pub max_config_size_mb: u64,
+    /// Limits on the Python executor, which the runner enforces.
+    #[serde(default)]
+    pub executor: ExecutorLimits,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            memory_warning_mb: 1024,
+            max_config_size_mb: 100,
+            executor: ExecutorLimits::default(),
+        }
+    }
+}
+
+/// What the runner does to an executor still over a limit after the
+/// warning.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitAction {
+    /// Pauses the execution, falling back to killing an executor that
+    /// can't pause.
+    Pause,
+    #[default]
+    Kill,
+}
+
+/// Caps on the executor's resource usage; unset ones aren't enforced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutorLimits {
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+    /// Percent of one core, so above 100 allows several.
+    #[serde(default)]
+    pub max_cpu_percent: Option<f32>,
+    /// Longest an execution may run.
+    #[serde(default)]
+    pub max_run_minutes: Option<u64>,
+    #[serde(default)]
+    pub action: LimitAction,
+    /// How long the executor may stay over a limit after the warning.
+    #[serde(default = "default_grace_seconds")]
+    pub grace_seconds: u64,
+}
+
+fn default_grace_seconds() -> u64 {
+    30
+}
+
+impl Default for ExecutorLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_mb: None,
+            max_cpu_percent: None,
+            max_run_minutes: None,
+            action: LimitAction::default(),
+            grace_seconds: default_grace_seconds(),
+        }
+    }
+}
+
+impl ResourceLimits {
+    pub fn max_config_bytes(&self) -> u64 {
+        self.max_config_size_mb.saturating_mul(BYTES_PER_MB)
+    }
+}
+
+/// Memory figures reported by `get_resource_usage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceUsage {
+    pub process_memory_bytes: Option<u64>,
+    pub process_virtual_memory_bytes: Option<u64>,
+    pub config_source_bytes: u64,
+    pub image_payload_bytes: usize,
+    /// Events waiting to be emitted to the frontend, and roughly how much
+    /// memory they hold.
+    pub queued_events: u64,
+    pub queued_event_bytes: u64,
+    /// Events the timeline keeps for backfilling frontends, likewise.
+    pub timeline_events: usize,
+    pub timeline_bytes: usize,
+    pub limits: ResourceLimits,
+}
+
+/// Roughly how much memory `value` holds: its nodes, strings and keys.
+pub fn json_bytes(value: &Value) -> usize {
+    size_of::<Value>()
+        + match value {
+            Value::String(s) => s.len(),
+            Value::Array(items) => items.iter().map(json_bytes).sum(),
+            Value::Object(fields) => fields
+                .iter()
+                .map(|(key, value)| size_of::<String>() + key.len() + json_bytes(value))
+                .sum(),
+            _ => 0,
+        }
+}
+
+/// Roughly how much memory `event` holds.
+pub fn event_bytes(event: &ExecutorEvent) -> usize {
+    size_of::<ExecutorEvent>() + event.event.len() + json_bytes(&event.data)
+}
+
+/// Resident and virtual memory of a process, if it can be read.
+pub fn process_memory(pid: u32) -> Option<(u64, u64)> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    system
+        .process(pid)
+        .map(|process| (process.memory(), process.virtual_memory()))
+}
+
+pub fn collect_usage(state: &AppState) -> ResourceUsage {
+    let memory = process_memory(std::process::id());
+    let image_payload_bytes = state
+        .current_config
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|config| config.image_store.embedded_bytes())
+        .unwrap_or(0);
+    let pipeline = state
+        .executor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|bridge| bridge.pipeline_metrics());
+    let (timeline_events, timeline_bytes) = state.event_timeline.usage();
+
+    ResourceUsage {
+        process_memory_bytes: memory.map(|(rss, _)| rss),
+        process_virtual_memory_bytes: memory.map(|(_, virt)| virt),
+        config_source_bytes: *state.config_source_bytes.lock().unwrap(),
+        image_payload_bytes,
+        queued_events: pipeline.as_ref().map_or(0, |pipeline| pipeline.queued),
+        queued_event_bytes: pipeline
+            .as_ref()
+            .map_or(0, |pipeline| pipeline.queued_bytes),
+        timeline_events,
+        timeline_bytes,
+        limits: state.resource_limits.lock().unwrap().clone(),
+    }
+}
+
+/// Periodically checks the runner's memory against the configured warning
+/// threshold and notifies the frontend when it is crossed.
+pub fn start_monitor(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let mut warned = false;
+        loop {
+            thread::sleep(MONITOR_INTERVAL);
+
+            let state = app_handle.state::<AppState>();
+            let usage = collect_usage(&state);
+            let Some(rss) = usage.process_memory_bytes else {
+                continue;
+            };
+
+            let threshold = usage.limits.memory_warning_mb.saturating_mul(BYTES_PER_MB);
+            if rss >= threshold && !warned {
+                warn!(
+                    "Runner memory usage {} MB exceeds warning threshold {} MB",
+                    rss / BYTES_PER_MB,
+                    usage.limits.memory_warning_mb
+                );
+                let _ = app_handle.emit("resource-warning", &usage);
+                warned = true;
+            } else if rss < threshold {
+                warned = false;
+            }
+        }
+    });
+}
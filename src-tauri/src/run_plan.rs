@@ -0,0 +1,253 @@
+//! What the machine will do next: the active execution, runs waiting in
+//! the queue, and scheduled runs, each with an estimated finish time from
+//! the durations of earlier runs of the same workflow.
+
+use crate::commands::AppState;
+use crate::executor::python_bridge::ExecutorEvent;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Durations remembered per workflow for estimates.
+const DURATION_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanKind {
+    Active,
+    Queued,
+    Scheduled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedRun {
+    pub kind: PlanKind,
+    pub workflow_id: Option<String>,
+    pub label: String,
+    /// Background task or execution queue entry driving the run, which can
+    /// be cancelled.
+    pub task_id: Option<String>,
+    pub status: Option<String>,
+    pub started_at: Option<String>,
+    /// Expected start, for runs that haven't started.
+    pub expected_start: Option<String>,
+    /// Expected finish; `None` without earlier runs to estimate from.
+    pub eta: Option<String>,
+    pub estimated_duration_seconds: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunPlan {
+    pub generated_at: String,
+    pub active: Vec<PlannedRun>,
+    pub queued: Vec<PlannedRun>,
+    pub scheduled: Vec<PlannedRun>,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveRun {
+    workflow_id: Option<String>,
+    started_at: DateTime<Local>,
+}
+
+/// A run waiting in the queue, as registered by whoever queued it.
+#[derive(Debug, Clone)]
+pub struct QueuedRun {
+    pub task_id: String,
+    pub workflow_id: String,
+    pub label: String,
+    pub status: Option<String>,
+    /// Earliest time the run could start regardless of what's ahead of it.
+    pub ready_at: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    active: Option<ActiveRun>,
+    queued: Vec<QueuedRun>,
+    durations: HashMap<String, VecDeque<Duration>>,
+}
+
+/// Follows executions and queued runs for `get_run_plan`.
+#[derive(Clone, Default)]
+pub struct RunTracker {
+    state: Arc<Mutex<TrackerState>>,
+}
+
+impl RunTracker {
+    pub fn record_event(&self, event: &ExecutorEvent) {
+        self.record_event_at(event, Local::now());
+    }
+
+    pub fn record_event_at(&self, event: &ExecutorEvent, now: DateTime<Local>) {
+        let mut state = self.state.lock().unwrap();
+        match event.event.as_str() {
+            "execution_started" => {
+                state.active = Some(ActiveRun {
+                    workflow_id: event
+                        .data
+                        .get("workflow_id")
+                        .and_then(|w| w.as_str())
+                        .map(str::to_string),
+                    started_at: now,
+                });
+            }
+            "execution_completed" => {
+                let Some(run) = state.active.take() else {
+                    return;
+                };
+                let succeeded = event.data.get("success").and_then(|s| s.as_bool());
+                // Stopped runs say little about how long a full run takes
+                if let (Some(workflow_id), Some(true)) = (run.workflow_id, succeeded) {
+                    let elapsed = (now - run.started_at).to_std().unwrap_or_default();
+                    let samples = state.durations.entry(workflow_id).or_default();
+                    if samples.len() >= DURATION_SAMPLES {
+                        samples.pop_front();
+                    }
+                    samples.push_back(elapsed);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Workflow and start of the execution in progress.
+    pub fn active(&self) -> Option<(Option<String>, DateTime<Local>)> {
+        let state = self.state.lock().unwrap();
+        let run = state.active.as_ref()?;
+        Some((run.workflow_id.clone(), run.started_at))
+    }
+
+    pub fn enqueue(&self, run: QueuedRun) {
+        self.state.lock().unwrap().queued.push(run);
+    }
+
+    /// Expected duration of a run of `workflow_id`, from earlier runs.
+    pub fn estimate(&self, workflow_id: &str) -> Option<Duration> {
+        self.state
+            .lock()
+            .unwrap()
+            .durations
+            .get(workflow_id)
+            .and_then(average)
+    }
+
+    /// Puts the listed queued runs in the given order, within the places
+    /// they already take up. Other queued runs stay where they are.
+    pub fn reorder_queued(&self, task_ids: &[String]) {
+        let mut state = self.state.lock().unwrap();
+        let slots: Vec<usize> = (0..state.queued.len())
+            .filter(|&i| task_ids.contains(&state.queued[i].task_id))
+            .collect();
+        let mut listed: Vec<QueuedRun> = slots.iter().map(|&i| state.queued[i].clone()).collect();
+        listed.sort_by_key(|run| task_ids.iter().position(|id| *id == run.task_id));
+        for (slot, run) in slots.into_iter().zip(listed) {
+            state.queued[slot] = run;
+        }
+    }
+
+    /// Removes a queued run once it starts or is abandoned.
+    pub fn dequeue(&self, task_id: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .queued
+            .retain(|run| run.task_id != task_id);
+    }
+
+    /// Updates what a queued run is waiting for.
+    pub fn update_queued(&self, task_id: &str, status: String, ready_at: Option<DateTime<Local>>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(run) = state.queued.iter_mut().find(|run| run.task_id == task_id) {
+            run.status = Some(status);
+            run.ready_at = ready_at;
+        }
+    }
+
+    /// Lays the active run and the queue out back to back. Queued runs
+    /// start in order, each once the previous one finishes and it is ready.
+    pub fn plan(&self, now: DateTime<Local>) -> RunPlan {
+        let state = self.state.lock().unwrap();
+        let estimate = |workflow_id: Option<&str>| {
+            workflow_id.and_then(|id| state.durations.get(id).and_then(average))
+        };
+
+        let mut next_free = Some(now);
+        let active: Vec<PlannedRun> = state
+            .active
+            .iter()
+            .map(|run| {
+                let duration = estimate(run.workflow_id.as_deref());
+                let eta = duration.map(|d| (run.started_at + to_chrono(d)).max(now));
+                next_free = eta;
+                PlannedRun {
+                    kind: PlanKind::Active,
+                    workflow_id: run.workflow_id.clone(),
+                    label: run
+                        .workflow_id
+                        .clone()
+                        .unwrap_or_else(|| "Execution".to_string()),
+                    task_id: None,
+                    status: Some("running".to_string()),
+                    started_at: Some(run.started_at.to_rfc3339()),
+                    expected_start: None,
+                    eta: eta.map(|t| t.to_rfc3339()),
+                    estimated_duration_seconds: duration.map(|d| d.as_secs_f64()),
+                }
+            })
+            .collect();
+
+        let queued = state
+            .queued
+            .iter()
+            .map(|run| {
+                let duration = estimate(Some(&run.workflow_id));
+                let start =
+                    next_free.map(|free| run.ready_at.map_or(free, |ready| ready.max(free)));
+                let eta = start.zip(duration).map(|(start, d)| start + to_chrono(d));
+                next_free = eta;
+                PlannedRun {
+                    kind: PlanKind::Queued,
+                    workflow_id: Some(run.workflow_id.clone()),
+                    label: run.label.clone(),
+                    task_id: Some(run.task_id.clone()),
+                    status: run.status.clone(),
+                    started_at: None,
+                    expected_start: start.map(|t| t.to_rfc3339()),
+                    eta: eta.map(|t| t.to_rfc3339()),
+                    estimated_duration_seconds: duration.map(|d| d.as_secs_f64()),
+                }
+            })
+            .collect();
+
+        RunPlan {
+            generated_at: now.to_rfc3339(),
+            active,
+            queued,
+            // Filled in from the scheduler
+            scheduled: Vec::new(),
+        }
+    }
+}
+
+/// Feeds an executor event to the app's `RunTracker`.
+pub fn record_event(app_handle: &AppHandle, event: &ExecutorEvent) {
+    app_handle
+        .state::<AppState>()
+        .run_tracker
+        .record_event(event);
+}
+
+fn average(samples: &VecDeque<Duration>) -> Option<Duration> {
+    if samples.is_empty() {
+        return None;
+    }
+    Some(samples.iter().sum::<Duration>() / samples.len() as u32)
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero())
+}
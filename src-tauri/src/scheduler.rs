@@ -0,0 +1,421 @@
+//! Recurring runs on a cron schedule (`minute hour day-of-month month
+//! day-of-week`, in local time). Schedules are kept in `schedules.json` in
+//! the data directory. When one is due the runner starts an executor
+//! session of its own, sends it the schedule's configuration, runs the
+//! workflow and closes the session again, so a scheduled run doesn't
+//! disturb whatever the default session holds. Each outcome is appended to
+//! `execution-history.jsonl`, with the videos recorded during the run.
+//!
+//! Occurrences missed while the runner wasn't running are skipped, and a
+//! schedule whose previous run is still going skips its next occurrence.
+
+use crate::commands::{self, AppState};
+use crate::executor::execution_request::ExecutionRequest;
+use crate::executor::sessions;
+use crate::recordings::RecordingKind;
+use crate::resources::LimitAction;
+use crate::run_plan::{PlanKind, PlannedRun, RunTracker};
+use chrono::{DateTime, Local};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tracing::{info, warn};
+
+/// How often schedules are checked for due runs.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often a scheduled run checks that its executor is still alive.
+const RUN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub cron_expr: String,
+    pub process_id: String,
+    pub config_path: String,
+    #[serde(default = "default_executor_type")]
+    pub executor_type: String,
+    #[serde(default)]
+    pub monitor_index: Option<i32>,
+    pub created_at: String,
+    #[serde(default)]
+    pub last_run_at: Option<String>,
+    #[serde(default)]
+    pub last_success: Option<bool>,
+}
+
+fn default_executor_type() -> String {
+    "real".to_string()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleInfo {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    pub next_run_at: Option<String>,
+    pub running: bool,
+}
+
+/// One finished run in the execution history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    /// The schedule that started the run.
+    pub schedule_id: Option<String>,
+    pub process_id: String,
+    pub config_path: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Videos recorded while the run was going.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub videos: Vec<PathBuf>,
+    /// Set when the runner stepped in because the executor exceeded a
+    /// resource limit; the record of a paused run ends when it was paused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_action: Option<LimitAction>,
+}
+
+#[derive(Clone)]
+pub struct Scheduler {
+    path: PathBuf,
+    history_path: PathBuf,
+    schedules: Arc<Mutex<Vec<Schedule>>>,
+    /// Schedules with a run in progress.
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Parses a five-field cron expression.
+pub fn parse_cron(expr: &str) -> Result<Cron, String> {
+    Cron::new(expr)
+        .parse()
+        .map_err(|e| format!("Invalid cron expression '{}': {}", expr, e))
+}
+
+/// The first occurrence of `expr` after `after`.
+pub fn next_occurrence(expr: &str, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    parse_cron(expr)
+        .ok()?
+        .find_next_occurrence(&after, false)
+        .ok()
+}
+
+impl Scheduler {
+    /// Reads the schedules in `dir`; a missing or unreadable file gives
+    /// none.
+    pub fn open(dir: &Path) -> Self {
+        let path = dir.join("schedules.json");
+        let schedules = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid schedules {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            path,
+            history_path: dir.join("execution-history.jsonl"),
+            schedules: Arc::new(Mutex::new(schedules)),
+            running: Default::default(),
+        }
+    }
+
+    pub fn add(
+        &self,
+        cron_expr: &str,
+        process_id: &str,
+        config_path: &str,
+        executor_type: Option<String>,
+        monitor_index: Option<i32>,
+    ) -> Result<Schedule, String> {
+        parse_cron(cron_expr)?;
+        if process_id.is_empty() {
+            return Err("Workflow ID is required".to_string());
+        }
+        if !Path::new(config_path).is_file() {
+            return Err(format!("Configuration file not found: {}", config_path));
+        }
+        let schedule = Schedule {
+            id: uuid::Uuid::new_v4().to_string(),
+            cron_expr: cron_expr.trim().to_string(),
+            process_id: process_id.to_string(),
+            config_path: config_path.to_string(),
+            executor_type: executor_type.unwrap_or_else(default_executor_type),
+            monitor_index,
+            created_at: Local::now().to_rfc3339(),
+            last_run_at: None,
+            last_success: None,
+        };
+        self.update(|schedules| schedules.push(schedule.clone()))?;
+        Ok(schedule)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<Schedule, String> {
+        self.update(|schedules| {
+            let index = schedules
+                .iter()
+                .position(|schedule| schedule.id == id)
+                .ok_or_else(|| format!("No schedule '{}'", id))?;
+            Ok(schedules.remove(index))
+        })?
+    }
+
+    pub fn list(&self, now: DateTime<Local>) -> Vec<ScheduleInfo> {
+        let running = self.running.lock().unwrap();
+        self.schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|schedule| ScheduleInfo {
+                next_run_at: next_occurrence(&schedule.cron_expr, now).map(|t| t.to_rfc3339()),
+                running: running.contains(&schedule.id),
+                schedule: schedule.clone(),
+            })
+            .collect()
+    }
+
+    /// Schedules with an occurrence after `after` and no later than `until`.
+    pub fn due(&self, after: DateTime<Local>, until: DateTime<Local>) -> Vec<Schedule> {
+        self.schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|schedule| {
+                next_occurrence(&schedule.cron_expr, after).is_some_and(|next| next <= until)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The next run of every schedule, soonest first, with estimates from
+    /// `tracker`.
+    pub fn planned(&self, tracker: &RunTracker, now: DateTime<Local>) -> Vec<PlannedRun> {
+        let mut upcoming: Vec<(DateTime<Local>, PlannedRun)> = self
+            .schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|schedule| {
+                let start = next_occurrence(&schedule.cron_expr, now)?;
+                let duration = tracker.estimate(&schedule.process_id);
+                let eta = duration
+                    .and_then(|d| chrono::Duration::from_std(d).ok())
+                    .map(|d| start + d);
+                let run = PlannedRun {
+                    kind: PlanKind::Scheduled,
+                    workflow_id: Some(schedule.process_id.clone()),
+                    label: format!("{} ({})", schedule.process_id, schedule.cron_expr),
+                    task_id: Some(schedule.id.clone()),
+                    status: None,
+                    started_at: None,
+                    expected_start: Some(start.to_rfc3339()),
+                    eta: eta.map(|t| t.to_rfc3339()),
+                    estimated_duration_seconds: duration.map(|d| d.as_secs_f64()),
+                };
+                Some((start, run))
+            })
+            .collect();
+        upcoming.sort_by_key(|(start, _)| *start);
+        upcoming.into_iter().map(|(_, run)| run).collect()
+    }
+
+    /// Claims a schedule for a run; false if it's already running.
+    pub fn begin(&self, id: &str) -> bool {
+        self.running.lock().unwrap().insert(id.to_string())
+    }
+
+    /// Releases the schedule, notes the outcome on it and appends it to the
+    /// execution history.
+    pub fn finish(&self, record: &ExecutionRecord) -> Result<(), String> {
+        if let Some(id) = &record.schedule_id {
+            self.running.lock().unwrap().remove(id);
+            self.update(|schedules| {
+                if let Some(schedule) = schedules.iter_mut().find(|s| &s.id == id) {
+                    schedule.last_run_at = Some(record.started_at.clone());
+                    schedule.last_success = Some(record.success);
+                }
+            })?;
+        }
+        let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.history_path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| format!("Failed to write {:?}: {}", self.history_path, e))
+    }
+
+    /// The last `limit` runs in the execution history, newest first.
+    pub fn history(&self, limit: usize) -> Vec<ExecutionRecord> {
+        let Ok(content) = std::fs::read_to_string(&self.history_path) else {
+            return Vec::new();
+        };
+        content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .take(limit)
+            .collect()
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut Vec<Schedule>) -> T) -> Result<T, String> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let result = change(&mut schedules);
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        let json = serde_json::to_string_pretty(&*schedules).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, json)
+            .map_err(|e| format!("Failed to save {:?}: {}", self.path, e))?;
+        Ok(result)
+    }
+}
+
+/// Checks the schedules every few seconds and starts the runs that are due.
+pub fn start(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let mut last_check = Local::now();
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            let now = Local::now();
+            let due = app_handle
+                .state::<AppState>()
+                .scheduler
+                .due(last_check, now);
+            for schedule in due {
+                let app_handle = app_handle.clone();
+                thread::spawn(move || run(&app_handle, schedule));
+            }
+            last_check = now;
+        }
+    });
+}
+
+fn run(app_handle: &AppHandle, schedule: Schedule) {
+    let scheduler = &app_handle.state::<AppState>().scheduler;
+    if !scheduler.begin(&schedule.id) {
+        warn!(
+            "Skipping scheduled run of {}: the previous one is still going",
+            schedule.process_id
+        );
+        return;
+    }
+    info!(
+        "Starting scheduled run of {} ({})",
+        schedule.process_id, schedule.cron_expr
+    );
+    let _ = app_handle.emit(
+        "schedule-run",
+        json!({ "status": "started", "schedule_id": schedule.id }),
+    );
+
+    let started_at = Local::now().to_rfc3339();
+    let result = execute(app_handle, &schedule);
+    let finished_at = Local::now().to_rfc3339();
+    let videos = app_handle.state::<AppState>().recordings.overlapping(
+        RecordingKind::Video,
+        &started_at,
+        &finished_at,
+    );
+    let record = ExecutionRecord {
+        schedule_id: Some(schedule.id.clone()),
+        process_id: schedule.process_id.clone(),
+        config_path: Some(schedule.config_path.clone()),
+        started_at,
+        finished_at,
+        videos,
+        limit_action: None,
+        success: matches!(result, Ok(true)),
+        error: match result {
+            Ok(true) => None,
+            Ok(false) => Some("Execution failed".to_string()),
+            Err(e) => Some(e),
+        },
+    };
+    if let Some(e) = &record.error {
+        warn!("Scheduled run of {} failed: {}", schedule.process_id, e);
+    }
+    if let Err(e) = scheduler.finish(&record) {
+        warn!("Failed to record scheduled run: {}", e);
+    }
+    let _ = app_handle.emit(
+        "schedule-run",
+        json!({
+            "status": if record.success { "completed" } else { "failed" },
+            "schedule_id": schedule.id,
+            "record": record,
+        }),
+    );
+}
+
+/// Runs the schedule's workflow in a session of its own and returns
+/// whether it succeeded.
+fn execute(app_handle: &AppHandle, schedule: &Schedule) -> Result<bool, String> {
+    let state = app_handle.state::<AppState>();
+    let session_id = commands::open_session(
+        app_handle.clone(),
+        &state,
+        schedule.executor_type.clone(),
+        Some(schedule.config_path.clone()),
+    )?;
+
+    let (sender, receiver) = mpsc::channel();
+    let listen_id = session_id.clone();
+    let listener = app_handle.listen("executor-event", move |event| {
+        let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else {
+            return;
+        };
+        let ours = payload.get("session_id").and_then(Value::as_str) == Some(&listen_id);
+        if ours && payload.get("event").and_then(Value::as_str) == Some("execution_completed") {
+            let success = payload["data"]["success"].as_bool().unwrap_or(false);
+            let _ = sender.send(success);
+        }
+    });
+
+    let result = commands::begin_session_execution(
+        &state,
+        &session_id,
+        &schedule.process_id,
+        schedule.monitor_index,
+        &ExecutionRequest::default(),
+    )
+    .and_then(|_| wait_for_completion(&state, &session_id, &receiver));
+
+    app_handle.unlisten(listener);
+    if let Err(e) = commands::close_session(&state, &session_id) {
+        warn!("Failed to close scheduled session {}: {}", session_id, e);
+    }
+    result
+}
+
+/// Fails if the session's executor goes away before the run completes.
+fn wait_for_completion(
+    state: &AppState,
+    session_id: &str,
+    receiver: &Receiver<bool>,
+) -> Result<bool, String> {
+    loop {
+        match receiver.recv_timeout(RUN_POLL_INTERVAL) {
+            Ok(success) => return Ok(success),
+            Err(RecvTimeoutError::Timeout) => {
+                let running =
+                    sessions::with_bridge(state, session_id, |bridge| Ok(bridge.is_running()))?;
+                if !running {
+                    return Err("Python executor stopped".to_string());
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("Stopped listening for the execution".to_string())
+            }
+        }
+    }
+}
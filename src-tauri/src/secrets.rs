@@ -0,0 +1,200 @@
+//! Named credentials kept in the OS keychain: the Windows Credential
+//! Manager, the macOS login keychain, or the Secret Service on Linux,
+//! through the `keyring` crate.
+//!
+//! Configurations refer to secrets by name only. The executor asks for a
+//! value with a `secret_request` event when an action needs it and the
+//! runner answers with a `secret_value` command, so values never appear in
+//! configs, logs or the event stream. Config strings can also embed a
+//! secret as `${secret:NAME}`, which the executor resolves the same way
+//! when the action runs.
+//! Only secrets the loaded configuration's actions name are handed out, and
+//! never to an executor on another machine over an unencrypted link.
+//! Names are kept in a small index file because the keychains can't list
+//! entries by service portably.
+
+use crate::commands::AppState;
+use crate::config::QontinuiConfig;
+use crate::executor::sessions::{self, DEFAULT_SESSION};
+use keyring::Entry;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Service (or resource) name the secrets are filed under.
+const SERVICE: &str = "qontinui-runner";
+
+const MAX_NAME_LEN: usize = 128;
+
+/// Names are restricted so they read the same in configurations, in
+/// `${secret:NAME}` placeholders and in the keychain.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!(
+            "Secret names must be 1 to {} characters long",
+            MAX_NAME_LEN
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+    {
+        return Err(format!(
+            "Invalid character {:?} in secret name '{}'; use letters, digits, '_', '-' and '.'",
+            c, name
+        ));
+    }
+    Ok(())
+}
+
+/// Shared by all profiles, like the keychain itself.
+fn index_path() -> PathBuf {
+    crate::profiles::data_dir().join("secret-names.json")
+}
+
+fn read_index() -> BTreeSet<String> {
+    std::fs::read_to_string(index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_index(names: &BTreeSet<String>) -> Result<(), String> {
+    let path = index_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let json = serde_json::to_string_pretty(names).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to save {:?}: {}", path, e))
+}
+
+/// Names of the stored secrets, sorted.
+pub fn list_names() -> Vec<String> {
+    read_index().into_iter().collect()
+}
+
+/// The keychain entry holding `name`.
+fn entry(name: &str) -> Result<Entry, String> {
+    validate_name(name)?;
+    Entry::new(SERVICE, name).map_err(|e| e.to_string())
+}
+
+/// Stores `value` under `name`, replacing any previous value.
+pub fn store(name: &str, value: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret '{}': {}", name, e))?;
+
+    let mut names = read_index();
+    names.insert(name.to_string());
+    write_index(&names)?;
+    info!("Stored secret '{}'", name);
+    Ok(())
+}
+
+pub fn load(name: &str) -> Result<String, String> {
+    entry(name)?
+        .get_password()
+        .map_err(|e| format!("Secret '{}' is not available: {}", name, e))
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    let result = entry(name)?.delete_credential();
+
+    // An entry that's already gone from the keychain is still dropped from
+    // the index
+    let mut names = read_index();
+    let indexed = names.remove(name);
+    write_index(&names)?;
+    match result {
+        Ok(_) => {
+            info!("Deleted secret '{}'", name);
+            Ok(())
+        }
+        Err(keyring::Error::NoEntry) if indexed => {
+            warn!("Secret '{}' was missing from the keychain", name);
+            Ok(())
+        }
+        Err(e) => Err(format!("Failed to delete secret '{}': {}", name, e)),
+    }
+}
+
+/// Answers a `secret_request` event (`{"request_id", "name"}`) with a
+/// `secret_value` command to the executor session that asked. Runs off the stdout reader thread so a slow
+/// keychain prompt can't stall event delivery.
+pub fn answer_request(app_handle: &AppHandle, session_id: &str, data: &Value) {
+    let Some(request_id) = data.get("request_id").and_then(Value::as_str) else {
+        warn!("Ignoring secret_request event without a request_id");
+        return;
+    };
+    let request_id = request_id.to_string();
+    let name = data
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let app_handle = app_handle.clone();
+    let session_id = session_id.to_string();
+
+    std::thread::spawn(move || {
+        let state = app_handle.state::<AppState>();
+        let unencrypted =
+            sessions::with_bridge(
+                &state,
+                &session_id,
+                |bridge| Ok(bridge.unencrypted_remote()),
+            )
+            .unwrap_or(true);
+        let config = loaded_config(&state, &session_id);
+        let value = may_answer(&name, config.as_deref(), unencrypted).and_then(|_| load(&name));
+        let params = match value {
+            Ok(value) => json!({ "request_id": request_id, "name": name, "value": value }),
+            Err(e) => {
+                warn!("{}", e);
+                json!({ "request_id": request_id, "name": name, "error": e })
+            }
+        };
+
+        let sent = sessions::with_bridge(&state, &session_id, |bridge| {
+            bridge.send_command("secret_value", Some(params))
+        });
+        if let Err(e) = sent {
+            warn!("Failed to answer secret request for '{}': {}", name, e);
+        }
+    });
+}
+
+/// Whether an executor running `config` over a link that may be
+/// `unencrypted` can have the secret `name`.
+pub fn may_answer(
+    name: &str,
+    config: Option<&QontinuiConfig>,
+    unencrypted: bool,
+) -> Result<(), String> {
+    if !config.is_some_and(|config| config.secret_names().contains(name)) {
+        return Err(format!(
+            "Secret '{}' isn't used by the loaded configuration",
+            name
+        ));
+    }
+    if unencrypted {
+        return Err(format!(
+            "Secret '{}' isn't sent to a remote executor without TLS",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// The configuration the session's executor runs.
+fn loaded_config(state: &AppState, session_id: &str) -> Option<Arc<QontinuiConfig>> {
+    if session_id == DEFAULT_SESSION {
+        return state.current_config.lock().unwrap().clone();
+    }
+    let session = state.executor_sessions.get(session_id).ok()?;
+    let config = session.lock().unwrap().config.clone();
+    config
+}
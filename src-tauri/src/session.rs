@@ -0,0 +1,167 @@
+//! The runner's working session — the loaded configuration, the executor,
+//! the selected workflow and monitor, queued runs and how far the current
+//! execution got — saved as it changes. If the runner dies without a clean
+//! shutdown (a crash, a forced reboot), the next start keeps the last
+//! session so it can be restored.
+
+use crate::commands::AppState;
+use crate::executor::execution_request::ExecutionRequest;
+use crate::executor::python_bridge::ExecutorEvent;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub started_at: String,
+    pub updated_at: String,
+    /// Set when the runner exits normally.
+    pub clean_shutdown: bool,
+    pub config_path: Option<String>,
+    /// Executor type the bridge was started with; `None` when stopped.
+    pub executor_type: Option<String>,
+    pub workflow_id: Option<String>,
+    pub monitor_index: Option<i32>,
+    /// Parameters of the last run started, reused when it's resumed.
+    pub execution_request: Option<ExecutionRequest>,
+    /// Runs waiting to start, in order.
+    pub queued: Vec<SessionRun>,
+    /// The execution in progress.
+    pub active: Option<Checkpoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRun {
+    pub task_id: String,
+    pub workflow_id: String,
+    pub monitor_index: Option<i32>,
+}
+
+/// How far a running execution got.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub workflow_id: String,
+    pub monitor_index: Option<i32>,
+    pub started_at: String,
+    /// Last action that completed successfully; a resumed run continues
+    /// with the action after it.
+    pub last_completed_action: Option<String>,
+}
+
+/// The current session, written to disk on every change, and the previous
+/// one if it ended uncleanly.
+#[derive(Clone)]
+pub struct SessionStore {
+    path: PathBuf,
+    current: Arc<Mutex<Session>>,
+    previous: Arc<Mutex<Option<Session>>>,
+}
+
+impl SessionStore {
+    /// Reads the last session from `path` and starts a new one there.
+    pub fn open(path: PathBuf) -> Self {
+        let previous = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Session>(&content).ok())
+            .filter(|session| !session.clean_shutdown);
+        if let Some(session) = &previous {
+            warn!(
+                "Previous session from {} did not shut down cleanly",
+                session.started_at
+            );
+        }
+
+        let now = chrono::Local::now().to_rfc3339();
+        let store = Self {
+            path,
+            current: Arc::new(Mutex::new(Session {
+                started_at: now.clone(),
+                updated_at: now,
+                ..Default::default()
+            })),
+            previous: Arc::new(Mutex::new(previous)),
+        };
+        store.update(|_| {});
+        store
+    }
+
+    pub fn current(&self) -> Session {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// The session that ended uncleanly, until restored or discarded.
+    pub fn previous(&self) -> Option<Session> {
+        self.previous.lock().unwrap().clone()
+    }
+
+    pub fn take_previous(&self) -> Option<Session> {
+        self.previous.lock().unwrap().take()
+    }
+
+    pub fn update(&self, change: impl FnOnce(&mut Session)) {
+        let mut session = self.current.lock().unwrap();
+        change(&mut session);
+        session.updated_at = chrono::Local::now().to_rfc3339();
+        if let Err(e) = self.write(&session) {
+            warn!("Failed to save session: {}", e);
+        }
+    }
+
+    /// Tracks the active execution and its progress.
+    pub fn record_event(&self, event: &ExecutorEvent) {
+        match event.event.as_str() {
+            "execution_started" => {
+                let workflow_id = event
+                    .data
+                    .get("workflow_id")
+                    .and_then(|w| w.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                self.update(|session| {
+                    session.active = Some(Checkpoint {
+                        workflow_id,
+                        monitor_index: session.monitor_index,
+                        started_at: chrono::Local::now().to_rfc3339(),
+                        last_completed_action: None,
+                    })
+                });
+            }
+            "action_completed" => {
+                let succeeded = event.data.get("success").and_then(|s| s.as_bool());
+                let action_id = event.data.get("action_id").and_then(|a| a.as_str());
+                if let (Some(true), Some(action_id)) = (succeeded, action_id) {
+                    self.update(|session| {
+                        if let Some(active) = session.active.as_mut() {
+                            active.last_completed_action = Some(action_id.to_string());
+                        }
+                    });
+                }
+            }
+            "execution_completed" => self.update(|session| session.active = None),
+            _ => {}
+        }
+    }
+
+    /// Marks the session as ended normally.
+    pub fn close(&self) {
+        self.update(|session| session.clean_shutdown = true);
+    }
+
+    fn write(&self, session: &Session) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        let json = serde_json::to_string_pretty(session).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, json)
+            .map_err(|e| format!("Failed to save {:?}: {}", self.path, e))
+    }
+}
+
+/// Feeds an executor event to the app's session.
+pub fn record_event(app_handle: &AppHandle, event: &ExecutorEvent) {
+    app_handle.state::<AppState>().session.record_event(event);
+}
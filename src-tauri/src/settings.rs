@@ -0,0 +1,281 @@
+//! Settings that belong to the runner rather than to a profile, kept in
+//! `app-settings.json` in the data directory: the recently used
+//! configurations, each with the workflow and monitor it was last run with
+//! so the UI can offer to run it again in one click, the local HTTP API,
+//! the webhooks notified about runs and the global hotkeys.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Recent configurations kept; older ones drop off.
+pub const MAX_RECENT: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentConfiguration {
+    pub path: String,
+    /// Configuration name from its metadata.
+    pub name: Option<String>,
+    pub last_used_at: String,
+    pub last_workflow_id: Option<String>,
+    pub last_monitor_index: Option<i32>,
+}
+
+/// Default port of the local HTTP API.
+pub const DEFAULT_HTTP_PORT: u16 = 47819;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Clients send this as a bearer token. Generated the first time the
+    /// API is enabled.
+    pub token: String,
+    /// PEM certificate chain and private key to serve HTTPS with. An agent
+    /// only accepts clients from other machines with them.
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+}
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_HTTP_PORT,
+            token: String::new(),
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+impl HttpApiSettings {
+    /// Whether the API is served over HTTPS.
+    pub fn tls(&self) -> bool {
+        self.tls_cert.is_some() && self.tls_key.is_some()
+    }
+}
+
+/// Global shortcuts, in the `Ctrl+Alt+S` form; `None` leaves an action
+/// without one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    /// Runs the last workflow again.
+    pub start: Option<String>,
+    pub stop: Option<String>,
+    /// Kills the executor at once.
+    pub abort: Option<String>,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            start: None,
+            stop: Some("Ctrl+Alt+S".to_string()),
+            abort: Some("Ctrl+Alt+Q".to_string()),
+        }
+    }
+}
+
+/// Run events a webhook can be notified about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "run_started")]
+    Started,
+    #[serde(rename = "run_completed")]
+    Completed,
+    #[serde(rename = "run_failed")]
+    Failed,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 3] = [Self::Started, Self::Completed, Self::Failed];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Events this webhook is sent.
+    pub events: Vec<WebhookEvent>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Most recently used first.
+    pub recent_configurations: Vec<RecentConfiguration>,
+    pub http_api: HttpApiSettings,
+    pub webhooks: Vec<Webhook>,
+    pub hotkeys: HotkeySettings,
+}
+
+#[derive(Clone)]
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Arc<Mutex<AppSettings>>,
+}
+
+impl SettingsStore {
+    /// Reads the settings at `path`; a missing or unreadable file gives the
+    /// defaults.
+    pub fn open(path: PathBuf) -> Self {
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid app settings {:?}: {}", path, e);
+                AppSettings::default()
+            }),
+            Err(_) => AppSettings::default(),
+        };
+        Self {
+            path,
+            settings: Arc::new(Mutex::new(settings)),
+        }
+    }
+
+    pub fn recent_configurations(&self) -> Vec<RecentConfiguration> {
+        self.settings.lock().unwrap().recent_configurations.clone()
+    }
+
+    /// Moves `path` to the top of the recent configurations. The workflow
+    /// and monitor it was last run with are kept unless given.
+    pub fn add_recent_configuration(
+        &self,
+        path: &str,
+        name: Option<String>,
+        workflow_id: Option<String>,
+        monitor_index: Option<i32>,
+    ) -> Result<RecentConfiguration, String> {
+        if path.is_empty() {
+            return Err("A recent configuration needs a path".to_string());
+        }
+        self.update(|settings| {
+            let recent = &mut settings.recent_configurations;
+            let previous = recent
+                .iter()
+                .position(|entry| entry.path == path)
+                .map(|index| recent.remove(index));
+            let entry = RecentConfiguration {
+                path: path.to_string(),
+                name: name.or_else(|| previous.as_ref().and_then(|p| p.name.clone())),
+                last_used_at: chrono::Local::now().to_rfc3339(),
+                last_workflow_id: workflow_id
+                    .or_else(|| previous.as_ref().and_then(|p| p.last_workflow_id.clone())),
+                last_monitor_index: monitor_index
+                    .or_else(|| previous.as_ref().and_then(|p| p.last_monitor_index)),
+            };
+            recent.insert(0, entry.clone());
+            recent.truncate(MAX_RECENT);
+            entry
+        })
+    }
+
+    pub fn clear_recent_configurations(&self) -> Result<(), String> {
+        self.update(|settings| settings.recent_configurations.clear())
+    }
+
+    pub fn http_api(&self) -> HttpApiSettings {
+        self.settings.lock().unwrap().http_api.clone()
+    }
+
+    /// Turns the HTTP API on or off, generating its token if there isn't
+    /// one yet. The port is kept unless given.
+    pub fn set_http_api(
+        &self,
+        enabled: bool,
+        port: Option<u16>,
+    ) -> Result<HttpApiSettings, String> {
+        if port == Some(0) {
+            return Err("The HTTP API needs a port".to_string());
+        }
+        self.update(|settings| {
+            let api = &mut settings.http_api;
+            api.enabled = enabled;
+            api.port = port.unwrap_or(api.port);
+            if api.token.is_empty() {
+                api.token = uuid::Uuid::new_v4().to_simple().to_string();
+            }
+            api.clone()
+        })
+    }
+
+    /// Serves the HTTP API over HTTPS with the PEM files given, or plain
+    /// HTTP without them.
+    pub fn set_http_api_tls(
+        &self,
+        cert: Option<PathBuf>,
+        key: Option<PathBuf>,
+    ) -> Result<HttpApiSettings, String> {
+        if cert.is_some() != key.is_some() {
+            return Err("HTTPS needs both a certificate and its private key".to_string());
+        }
+        self.update(|settings| {
+            let api = &mut settings.http_api;
+            api.tls_cert = cert;
+            api.tls_key = key;
+            api.clone()
+        })
+    }
+
+    pub fn webhooks(&self) -> Vec<Webhook> {
+        self.settings.lock().unwrap().webhooks.clone()
+    }
+
+    pub fn add_webhook(&self, url: &str, events: Vec<WebhookEvent>) -> Result<Webhook, String> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("Not an HTTP URL: {}", url));
+        }
+        if events.is_empty() {
+            return Err("A webhook needs at least one event".to_string());
+        }
+        let webhook = Webhook {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            events: WebhookEvent::ALL
+                .into_iter()
+                .filter(|event| events.contains(event))
+                .collect(),
+            created_at: chrono::Local::now().to_rfc3339(),
+        };
+        self.update(|settings| settings.webhooks.push(webhook.clone()))?;
+        Ok(webhook)
+    }
+
+    pub fn remove_webhook(&self, id: &str) -> Result<Webhook, String> {
+        self.update(|settings| {
+            let index = settings
+                .webhooks
+                .iter()
+                .position(|webhook| webhook.id == id)?;
+            Some(settings.webhooks.remove(index))
+        })?
+        .ok_or_else(|| format!("No webhook '{}'", id))
+    }
+
+    pub fn hotkeys(&self) -> HotkeySettings {
+        self.settings.lock().unwrap().hotkeys.clone()
+    }
+
+    pub fn set_hotkeys(&self, hotkeys: HotkeySettings) -> Result<(), String> {
+        self.update(|settings| settings.hotkeys = hotkeys)
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut AppSettings) -> T) -> Result<T, String> {
+        let mut settings = self.settings.lock().unwrap();
+        let result = change(&mut settings);
+        write(&self.path, &settings)?;
+        Ok(result)
+    }
+}
+
+fn write(path: &Path, settings: &AppSettings) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to save {:?}: {}", path, e))
+}
@@ -0,0 +1,160 @@
+use crate::executor::{environment, supervisor};
+use crate::{hotkeys, http_api, progress, resources, scheduler, tray};
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+use tauri::AppHandle;
+use tracing::info;
+
+/// Soft budget for reaching an interactive window; exceeding it is logged.
+const INTERACTIVE_BUDGET_MS: u64 = 1500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupPhase {
+    pub name: String,
+    pub started_ms: u64,
+    pub duration_ms: u64,
+    pub deferred: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupReport {
+    pub uptime_ms: u64,
+    pub interactive_ms: Option<u64>,
+    pub deferred_complete_ms: Option<u64>,
+    pub budget_ms: u64,
+    pub phases: Vec<StartupPhase>,
+}
+
+struct Tracker {
+    origin: Instant,
+    phases: Vec<StartupPhase>,
+    interactive_ms: Option<u64>,
+    deferred_complete_ms: Option<u64>,
+}
+
+fn tracker() -> &'static Mutex<Tracker> {
+    static TRACKER: OnceLock<Mutex<Tracker>> = OnceLock::new();
+    TRACKER.get_or_init(|| {
+        Mutex::new(Tracker {
+            origin: Instant::now(),
+            phases: Vec::new(),
+            interactive_ms: None,
+            deferred_complete_ms: None,
+        })
+    })
+}
+
+fn elapsed_ms() -> u64 {
+    tracker().lock().unwrap().origin.elapsed().as_millis() as u64
+}
+
+/// Starts the startup clock. Call as early as possible in `main`.
+pub fn begin() {
+    tracker();
+}
+
+/// Runs and times one startup phase.
+pub fn phase<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    record(name, false, f)
+}
+
+fn record<T>(name: &str, deferred: bool, f: impl FnOnce() -> T) -> T {
+    let started_ms = elapsed_ms();
+    let result = f();
+    let duration_ms = elapsed_ms() - started_ms;
+
+    info!(
+        "Startup phase '{}' took {} ms{}",
+        name,
+        duration_ms,
+        if deferred { " (deferred)" } else { "" }
+    );
+    tracker().lock().unwrap().phases.push(StartupPhase {
+        name: name.to_string(),
+        started_ms,
+        duration_ms,
+        deferred,
+    });
+    result
+}
+
+/// Records the point at which the window is up and commands are served.
+pub fn mark_interactive() {
+    let interactive_ms = elapsed_ms();
+    tracker().lock().unwrap().interactive_ms = Some(interactive_ms);
+
+    if interactive_ms > INTERACTIVE_BUDGET_MS {
+        tracing::warn!(
+            "Startup took {} ms, over the {} ms budget",
+            interactive_ms,
+            INTERACTIVE_BUDGET_MS
+        );
+    } else {
+        info!("Runner interactive after {} ms", interactive_ms);
+    }
+}
+
+/// Runs non-essential initialization on a background thread once the
+/// window is up.
+pub fn run_deferred(app_handle: AppHandle) {
+    thread::spawn(move || {
+        record("sentry", true, init_sentry);
+        record("python_environment", true, environment::discover_all);
+        record("scheduler", true, || scheduler::start(app_handle.clone()));
+        record("http_api", true, || http_api::start(&app_handle));
+        record("hotkeys", true, || hotkeys::start(&app_handle));
+        record("update_check", true, || tray::check_for_update(&app_handle));
+        record("executor_supervisor", true, || {
+            supervisor::start(app_handle.clone())
+        });
+        record("execution_progress", true, || {
+            progress::start(app_handle.clone())
+        });
+        record("resource_monitor", true, || {
+            resources::start_monitor(app_handle)
+        });
+
+        let complete_ms = elapsed_ms();
+        tracker().lock().unwrap().deferred_complete_ms = Some(complete_ms);
+        info!("Deferred initialization complete after {} ms", complete_ms);
+    });
+}
+
+pub fn report() -> StartupReport {
+    let tracker = tracker().lock().unwrap();
+    StartupReport {
+        uptime_ms: tracker.origin.elapsed().as_millis() as u64,
+        interactive_ms: tracker.interactive_ms,
+        deferred_complete_ms: tracker.deferred_complete_ms,
+        budget_ms: INTERACTIVE_BUDGET_MS,
+        phases: tracker.phases.clone(),
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn init_sentry() {
+    static GUARD: OnceLock<sentry::ClientInitGuard> = OnceLock::new();
+
+    if let Ok(dsn) = std::env::var("SENTRY_DSN") {
+        let guard = sentry::init((
+            dsn,
+            sentry::ClientOptions {
+                release: sentry::release_name!(),
+                environment: Some("beta".into()),
+                before_send: Some(std::sync::Arc::new(|event| {
+                    info!("Sending error to Sentry: {:?}", event);
+                    Some(event)
+                })),
+                ..Default::default()
+            },
+        ));
+        // Keep the client alive for the rest of the process
+        let _ = GUARD.set(guard);
+        info!("Sentry crash reporting initialized");
+    }
+}
+
+#[cfg(debug_assertions)]
+fn init_sentry() {}
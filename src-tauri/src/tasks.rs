@@ -0,0 +1,260 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+/// Finished tasks kept around for `list_background_tasks`.
+const MAX_FINISHED_TASKS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskInfo {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    pub status: TaskStatus,
+    pub progress: f32,
+    pub message: Option<String>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+}
+
+struct TaskEntry {
+    info: TaskInfo,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Registry {
+    tasks: HashMap<String, TaskEntry>,
+    finished_order: Vec<String>,
+}
+
+/// Handle given to a running task for reporting progress and checking for
+/// cancellation.
+pub struct TaskContext {
+    id: String,
+    cancelled: Arc<AtomicBool>,
+    runner: TaskRunner,
+    app_handle: AppHandle,
+}
+
+impl TaskContext {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Records progress (0.0 to 1.0) and notifies the frontend.
+    pub fn progress(&self, fraction: f32, message: impl Into<String>) {
+        let message = message.into();
+        let info = self.runner.update(&self.id, |info| {
+            info.progress = fraction.clamp(0.0, 1.0);
+            info.message = Some(message);
+        });
+        if let Some(info) = info {
+            let _ = self.app_handle.emit("background-task-progress", &info);
+        }
+    }
+}
+
+/// Runs command-initiated background work on a bounded number of blocking
+/// threads, tracking each task so it can be listed and cancelled.
+#[derive(Clone)]
+pub struct TaskRunner {
+    registry: Arc<Mutex<Registry>>,
+    permits: Arc<Semaphore>,
+}
+
+impl Default for TaskRunner {
+    fn default() -> Self {
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get().min(4))
+            .unwrap_or(2);
+        Self::new(workers)
+    }
+}
+
+impl TaskRunner {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            registry: Arc::new(Mutex::new(Registry::default())),
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Queues `work` and returns the task id. The closure's return value is
+    /// stored as the task result.
+    pub fn spawn<F>(&self, app_handle: AppHandle, kind: &str, label: &str, work: F) -> String
+    where
+        F: FnOnce(&TaskContext) -> Result<Value, String> + Send + 'static,
+    {
+        let id = uuid::Uuid::new_v4().to_string();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let info = TaskInfo {
+            id: id.clone(),
+            kind: kind.to_string(),
+            label: label.to_string(),
+            status: TaskStatus::Queued,
+            progress: 0.0,
+            message: None,
+            result: None,
+            error: None,
+            created_at: chrono::Local::now().to_rfc3339(),
+            finished_at: None,
+        };
+
+        self.registry.lock().unwrap().tasks.insert(
+            id.clone(),
+            TaskEntry {
+                info: info.clone(),
+                cancelled: cancelled.clone(),
+            },
+        );
+        let _ = app_handle.emit("background-task-updated", &info);
+        info!("Queued background task {} ({})", id, label);
+
+        let runner = self.clone();
+        let task_id = id.clone();
+        tauri::async_runtime::spawn(async move {
+            let Ok(_permit) = runner.permits.clone().acquire_owned().await else {
+                return;
+            };
+
+            if cancelled.load(Ordering::Relaxed) {
+                runner.finish(&app_handle, &task_id, TaskStatus::Cancelled, None, None);
+                return;
+            }
+
+            if let Some(info) = runner.update(&task_id, |info| info.status = TaskStatus::Running) {
+                let _ = app_handle.emit("background-task-updated", &info);
+            }
+
+            let context = TaskContext {
+                id: task_id.clone(),
+                cancelled: cancelled.clone(),
+                runner: runner.clone(),
+                app_handle: app_handle.clone(),
+            };
+            let outcome = tauri::async_runtime::spawn_blocking(move || work(&context)).await;
+
+            match outcome {
+                _ if cancelled.load(Ordering::Relaxed) => {
+                    runner.finish(&app_handle, &task_id, TaskStatus::Cancelled, None, None)
+                }
+                Ok(Ok(result)) => runner.finish(
+                    &app_handle,
+                    &task_id,
+                    TaskStatus::Completed,
+                    Some(result),
+                    None,
+                ),
+                Ok(Err(e)) => {
+                    warn!("Background task {} failed: {}", task_id, e);
+                    runner.finish(&app_handle, &task_id, TaskStatus::Failed, None, Some(e))
+                }
+                Err(e) => {
+                    warn!("Background task {} panicked: {}", task_id, e);
+                    runner.finish(
+                        &app_handle,
+                        &task_id,
+                        TaskStatus::Failed,
+                        None,
+                        Some(format!("Task panicked: {}", e)),
+                    )
+                }
+            }
+        });
+
+        id
+    }
+
+    pub fn list(&self) -> Vec<TaskInfo> {
+        let registry = self.registry.lock().unwrap();
+        let mut tasks: Vec<TaskInfo> = registry.tasks.values().map(|e| e.info.clone()).collect();
+        tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        tasks
+    }
+
+    /// Requests cancellation. Queued tasks never start; running tasks stop at
+    /// their next cancellation check.
+    pub fn cancel(&self, id: &str) -> Result<(), String> {
+        let registry = self.registry.lock().unwrap();
+        let entry = registry
+            .tasks
+            .get(id)
+            .ok_or_else(|| format!("Background task not found: {}", id))?;
+
+        if entry.info.status.is_finished() {
+            return Err(format!("Background task {} already finished", id));
+        }
+        entry.cancelled.store(true, Ordering::Relaxed);
+        info!("Cancellation requested for background task {}", id);
+        Ok(())
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut TaskInfo)) -> Option<TaskInfo> {
+        let mut registry = self.registry.lock().unwrap();
+        let entry = registry.tasks.get_mut(id)?;
+        f(&mut entry.info);
+        Some(entry.info.clone())
+    }
+
+    fn finish(
+        &self,
+        app_handle: &AppHandle,
+        id: &str,
+        status: TaskStatus,
+        result: Option<Value>,
+        error: Option<String>,
+    ) {
+        let info = self.update(id, |info| {
+            info.status = status;
+            if status == TaskStatus::Completed {
+                info.progress = 1.0;
+            }
+            info.result = result;
+            info.error = error;
+            info.finished_at = Some(chrono::Local::now().to_rfc3339());
+        });
+
+        let mut registry = self.registry.lock().unwrap();
+        registry.finished_order.push(id.to_string());
+        while registry.finished_order.len() > MAX_FINISHED_TASKS {
+            let oldest = registry.finished_order.remove(0);
+            registry.tasks.remove(&oldest);
+        }
+        drop(registry);
+
+        if let Some(info) = info {
+            let _ = app_handle.emit("background-task-updated", &info);
+        }
+    }
+}
@@ -0,0 +1,6160 @@
+use crate::config::fixtures::{self, FixtureSpec};
+use crate::config::ConfigLoader;
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, removed when dropped.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("qontinui-test-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl std::ops::Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for ScratchDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn load_fixture(spec: &FixtureSpec) -> crate::config::QontinuiConfig {
+    let json = fixtures::generate(spec).to_string();
+    ConfigLoader::load_from_string(&json).expect("generated fixture should load")
+}
+
+#[test]
+fn generated_fixture_loads_and_validates() {
+    let spec = FixtureSpec {
+        states: 25,
+        transitions: 60,
+        workflows: 8,
+        images: 12,
+        ..Default::default()
+    };
+    let config = load_fixture(&spec);
+
+    assert_eq!(config.states.len(), 25);
+    assert_eq!(config.transitions.len(), 60);
+    assert_eq!(config.workflows.len(), 8);
+    assert_eq!(config.images.len(), 12);
+    assert_eq!(config.image_store.len(), 12);
+}
+
+#[test]
+fn generator_is_deterministic_per_seed() {
+    let spec = FixtureSpec::default();
+    assert_eq!(fixtures::generate(&spec), fixtures::generate(&spec));
+
+    let other = FixtureSpec {
+        seed: 2,
+        ..Default::default()
+    };
+    assert_ne!(fixtures::generate(&spec), fixtures::generate(&other));
+}
+
+#[test]
+fn generated_images_are_distinct_pngs() {
+    let spec = FixtureSpec {
+        images: 5,
+        image_width: 32,
+        image_height: 16,
+        ..Default::default()
+    };
+    let config = load_fixture(&spec);
+    let (results, duplicates) = config.image_store.verify_with(|_, _| true);
+
+    assert!(duplicates.is_empty());
+    for digest in results {
+        let digest = digest.expect("generated image should decode");
+        assert_eq!(digest.mime_type, "image/png");
+    }
+
+    let bytes = config.image_store.load("image-0").unwrap();
+    assert_eq!(&bytes[16..20], &32u32.to_be_bytes());
+    assert_eq!(&bytes[20..24], &16u32.to_be_bytes());
+}
+
+#[test]
+fn empty_spec_still_produces_a_valid_config() {
+    let spec = FixtureSpec {
+        states: 0,
+        transitions: 0,
+        workflows: 0,
+        images: 0,
+        ..Default::default()
+    };
+    let config = load_fixture(&spec);
+    assert_eq!(config.states.len(), 1);
+}
+
+#[test]
+fn oversized_specs_are_refused() {
+    assert!(FixtureSpec::default().validate().is_ok());
+    let too_many = FixtureSpec {
+        states: fixtures::MAX_ENTRIES + 1,
+        ..Default::default()
+    };
+    assert!(too_many.validate().unwrap_err().contains("states"));
+    let too_large = FixtureSpec {
+        images: 1000,
+        image_width: 4096,
+        image_height: 4096,
+        ..Default::default()
+    };
+    assert!(too_large.validate().is_err());
+}
+
+mod config_transfer {
+    use crate::executor::python_bridge::split_chunks;
+
+    #[test]
+    fn chunks_keep_chars_whole() {
+        let text = "aé€😀b";
+        assert_eq!(split_chunks(text, 4), vec!["aé", "€", "😀", "b"]);
+        assert_eq!(split_chunks(text, 1), vec!["a", "é", "€", "😀", "b"]);
+        assert_eq!(split_chunks(text, 0).concat(), text);
+        assert!(split_chunks("", 4).is_empty());
+    }
+}
+
+mod protocol_fuzz {
+    use crate::config::fixtures::Rng;
+    use crate::executor::conformance;
+    use crate::executor::protocol::{BridgeMessage, Frame, MessageReader};
+    use std::io::BufReader;
+
+    const EVENT: &str = r#"{"type":"event","event":"log","timestamp":1.5,"sequence":3,"data":{"level":"info","message":"hi"}}"#;
+    const RESPONSE: &str =
+        r#"{"type":"response","id":"abc","success":true,"data":null,"error":null}"#;
+
+    /// Reads every frame with a small buffer so lines straddle refills.
+    fn read_all(input: &[u8], max_line: usize) -> Vec<Frame> {
+        let mut reader = MessageReader::with_max_line(BufReader::with_capacity(7, input), max_line);
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.next_frame().unwrap() {
+            conformance::check(reader.last_line());
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[test]
+    fn random_bytes_never_panic_or_hang() {
+        let mut rng = Rng::new(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..500 {
+            let len = rng.below(512);
+            let input: Vec<u8> = (0..len)
+                .map(|_| match rng.below(10) {
+                    0 => b'\n',
+                    1 => b'{',
+                    2 => b'"',
+                    _ => rng.next() as u8,
+                })
+                .collect();
+
+            let frames = read_all(&input, 64);
+            let lines = input.iter().filter(|&&b| b == b'\n').count() + 1;
+            assert!(frames.len() <= lines);
+        }
+    }
+
+    #[test]
+    fn mutated_messages_are_reported_not_dropped() {
+        let mut rng = Rng::new(42);
+        for _ in 0..500 {
+            let mut line = if rng.below(2) == 0 { EVENT } else { RESPONSE }
+                .as_bytes()
+                .to_vec();
+            for _ in 0..=rng.below(4) {
+                let index = rng.below(line.len());
+                match rng.below(3) {
+                    0 => line[index] = rng.next() as u8,
+                    1 => line.truncate(index.max(1)),
+                    _ => line.insert(index, rng.next() as u8),
+                }
+            }
+            line.retain(|&b| b != b'\n');
+            line.push(b'\n');
+            line.extend_from_slice(EVENT.as_bytes());
+
+            let frames = read_all(&line, 1024);
+            assert!(matches!(
+                frames.last(),
+                Some(Frame::Message(BridgeMessage::Event(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn oversized_line_is_rejected_and_reader_recovers() {
+        let input = format!("{}\n{}\n", "x".repeat(100), RESPONSE);
+        let frames = read_all(input.as_bytes(), 80);
+
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(&frames[0], Frame::Invalid(e) if e.error.contains("exceeds")));
+        assert!(matches!(
+            &frames[1],
+            Frame::Message(BridgeMessage::Response(_))
+        ));
+    }
+
+    #[test]
+    fn blank_lines_and_missing_trailing_newline() {
+        let input = format!("\n  \r\n{}\n\n{}", EVENT, RESPONSE);
+        let frames = read_all(input.as_bytes(), 1024);
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[test]
+    fn conformance_accepts_protocol_messages() {
+        assert!(conformance::check(EVENT.as_bytes()).is_empty());
+        assert!(conformance::check(RESPONSE.as_bytes()).is_empty());
+    }
+
+    #[test]
+    fn conformance_flags_loose_messages() {
+        let extra_field = br#"{"type":"response","id":"abc","success":true,"is_running":false}"#;
+        assert_eq!(
+            conformance::check(extra_field),
+            vec!["response: unknown field 'is_running'".to_string()]
+        );
+
+        let bad_event = br#"{"type":"event","event":"","timestamp":-1,"data":[]}"#;
+        assert_eq!(conformance::check(bad_event).len(), 4);
+
+        assert!(!conformance::check(b"[1,2,3]").is_empty());
+        assert!(!conformance::check(br#"{"type":"ping"}"#).is_empty());
+    }
+}
+
+mod monitor_layouts {
+    use crate::monitors::{self, MonitorInfo};
+    use std::path::Path;
+
+    fn layout() -> Vec<MonitorInfo> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/monitor_layouts/mixed_dpi_negative_origin.json");
+        monitors::load_layout(&path).unwrap()
+    }
+
+    #[test]
+    fn fixture_layout_loads() {
+        let monitors = layout();
+        assert_eq!(monitors.len(), 3);
+        assert!(monitors[0].is_primary);
+        assert_eq!(monitors[1].x, -1920);
+    }
+
+    #[test]
+    fn negative_origin_round_trips() {
+        let monitors = layout();
+        let (x, y) = monitors::monitor_to_global(&monitors, 1, 100.0, 50.0, false).unwrap();
+        assert_eq!((x, y), (-1820, 590));
+
+        let point = monitors::global_to_monitor(&monitors, x, y).unwrap();
+        assert_eq!(point.monitor_index, 1);
+        assert_eq!((point.x, point.y), (100, 50));
+    }
+
+    #[test]
+    fn logical_coordinates_use_monitor_scale() {
+        let monitors = layout();
+        let (x, y) = monitors::monitor_to_global(&monitors, 2, 100.0, 100.0, true).unwrap();
+        assert_eq!((x, y), (765, -1315));
+
+        let point = monitors::global_to_monitor(&monitors, x, y).unwrap();
+        assert_eq!(point.monitor_index, 2);
+        assert_eq!((point.logical_x, point.logical_y), (100.0, 100.0));
+    }
+
+    #[test]
+    fn points_off_every_monitor_are_not_mapped() {
+        let monitors = layout();
+        assert!(monitors::global_to_monitor(&monitors, -1, -1).is_none());
+        assert!(monitors::global_to_monitor(&monitors, 3840, 0).is_none());
+        assert!(monitors::monitor_to_global(&monitors, 7, 0.0, 0.0, false).is_err());
+    }
+
+    #[test]
+    fn overlay_selections_become_physical_regions() {
+        use crate::capture::Region;
+        use crate::region_select::{to_region, OverlayRect};
+
+        let monitors = layout();
+        let rect = |x, y, width, height| OverlayRect {
+            x,
+            y,
+            width,
+            height,
+        };
+        let selection = to_region(&monitors[2], rect(100.0, 40.0, 200.0, 80.0)).unwrap();
+        assert_eq!(selection.monitor_index, 2);
+        let region = Region {
+            x: 125,
+            y: 50,
+            width: 250,
+            height: 100,
+        };
+        assert_eq!(selection.region, region);
+        assert_eq!(
+            selection.absolute,
+            Region {
+                x: 765,
+                y: -1390,
+                ..region
+            }
+        );
+
+        // Clipped to the monitor, and nothing left is no selection
+        let clipped = to_region(&monitors[1], rect(1800.0, -10.0, 500.0, 20.0)).unwrap();
+        assert_eq!(
+            clipped.region,
+            Region {
+                x: 1800,
+                y: 0,
+                width: 120,
+                height: 10
+            }
+        );
+        assert!(to_region(&monitors[1], rect(2000.0, 0.0, 50.0, 50.0)).is_none());
+    }
+}
+
+mod error_golden {
+    use crate::error::{self, UserFacingError};
+    use std::path::{Path, PathBuf};
+
+    /// Set to rewrite the golden files after an intentional copy change.
+    const UPDATE_ENV: &str = "QONTINUI_UPDATE_GOLDEN";
+
+    fn golden_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/error_golden")
+    }
+
+    #[test]
+    fn user_facing_errors_match_golden_files() {
+        let dir = golden_dir();
+        let update = std::env::var_os(UPDATE_ENV).is_some();
+
+        for preview in error::preview_errors() {
+            let path = dir.join(format!("{}.json", preview.error_code));
+            let actual = serde_json::to_string_pretty(&preview).unwrap() + "\n";
+            if update {
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(&path, &actual).unwrap();
+                continue;
+            }
+
+            let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+                panic!(
+                    "missing golden file {:?}; rerun with {}=1",
+                    path, UPDATE_ENV
+                )
+            });
+            assert_eq!(
+                expected.replace("\r\n", "\n"),
+                actual,
+                "{} changed; rerun with {}=1 if intended",
+                preview.error_code,
+                UPDATE_ENV
+            );
+        }
+    }
+
+    #[test]
+    fn every_golden_file_has_an_error() {
+        let codes: Vec<String> = error::preview_errors()
+            .into_iter()
+            .map(|p| p.error_code)
+            .collect();
+        for entry in std::fs::read_dir(golden_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            let code = path.file_stem().unwrap().to_string_lossy().into_owned();
+            assert!(codes.contains(&code), "stale golden file {:?}", path);
+        }
+    }
+
+    #[test]
+    fn error_codes_are_unique() {
+        let mut codes: Vec<String> = error::preview_errors()
+            .into_iter()
+            .map(|p| p.error_code)
+            .collect();
+        let total = codes.len();
+        codes.dedup();
+        assert_eq!(codes.len(), total);
+    }
+
+    #[test]
+    fn executor_errors_map_by_message() {
+        let mapped: UserFacingError =
+            error::executor_error_to_user_facing("No configuration loaded to apply changes to");
+        assert_eq!(mapped.error_code, "EXEC_003");
+        assert_eq!(
+            mapped.details.as_deref(),
+            Some("No configuration loaded to apply changes to")
+        );
+
+        let unknown = error::executor_error_to_user_facing("Screen capture failed");
+        assert_eq!(unknown.error_code, "EXEC_001");
+    }
+}
+
+mod ocr_results {
+    use crate::capture::Region;
+    use crate::ocr;
+
+    fn region(x: i32, y: i32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn lines() -> Vec<Vec<(String, Region)>> {
+        vec![
+            vec![
+                ("Save".to_string(), region(10, 10, 100, 30)),
+                ("changes".to_string(), region(120, 12, 90, 28)),
+            ],
+            vec![("Cancel".to_string(), region(10, 60, 200, 30))],
+        ]
+    }
+
+    #[test]
+    fn words_are_grouped_into_lines() {
+        let result = ocr::assemble(lines(), region(0, 0, 400, 200));
+
+        assert_eq!(result.text, "Save changes\nCancel");
+        assert_eq!(result.lines.len(), 2);
+        assert_eq!(result.lines[0].words.len(), 2);
+        assert_eq!(result.lines[0].bounds, region(10, 10, 200, 30));
+    }
+
+    #[test]
+    fn boxes_are_offset_into_region_space() {
+        let result = ocr::assemble(lines(), region(50, 20, 400, 200));
+
+        assert_eq!(result.lines[0].words[0].bounds, region(60, 30, 100, 30));
+        assert_eq!(result.lines[1].bounds, region(60, 80, 200, 30));
+    }
+
+    #[test]
+    fn blank_words_and_lines_are_dropped() {
+        assert!(ocr::assemble(Vec::new(), region(0, 0, 10, 10))
+            .lines
+            .is_empty());
+        let result = ocr::assemble(
+            vec![vec![(" ".to_string(), region(0, 0, 5, 5))], Vec::new()],
+            region(0, 0, 10, 10),
+        );
+        assert!(result.lines.is_empty());
+        assert_eq!(result.text, "");
+    }
+}
+
+mod accessibility_search {
+    use crate::accessibility::{self, ElementBounds, ElementQuery, Node};
+
+    struct Fake {
+        name: &'static str,
+        role: &'static str,
+        children: Vec<Fake>,
+    }
+
+    fn node(role: &'static str, name: &'static str, children: Vec<Fake>) -> Fake {
+        Fake {
+            name,
+            role,
+            children,
+        }
+    }
+
+    impl Node for Fake {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+        fn role(&self) -> String {
+            self.role.to_string()
+        }
+        fn children(&self) -> Vec<Self> {
+            self.children
+                .iter()
+                .map(|child| node(child.role, child.name, Vec::new()))
+                .collect()
+        }
+        fn value(&self) -> Option<String> {
+            None
+        }
+        fn enabled(&self) -> bool {
+            true
+        }
+        fn bounds(&self) -> Option<ElementBounds> {
+            None
+        }
+        fn activate(&self) -> Result<(), String> {
+            Ok(())
+        }
+        fn set_value(&self, _text: &str) -> Result<bool, String> {
+            Ok(false)
+        }
+        fn focus(&self) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    fn desktop() -> Vec<Fake> {
+        vec![
+            node(
+                "window",
+                "Invoice - ERP",
+                vec![
+                    node("button", "Save", Vec::new()),
+                    node("button", "Save as", Vec::new()),
+                ],
+            ),
+            node("window", "Notes", vec![node("button", "Save", Vec::new())]),
+        ]
+    }
+
+    fn names(query: &ElementQuery) -> Vec<(String, String)> {
+        accessibility::search(desktop(), query)
+            .unwrap()
+            .iter()
+            .map(|(node, window)| (node.name(), window.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn filters_are_case_insensitive_and_windows_come_first() {
+        let query = ElementQuery {
+            role: Some("BUTTON".to_string()),
+            name: Some("save".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&query),
+            [
+                ("Save".to_string(), "Invoice - ERP".to_string()),
+                ("Save".to_string(), "Notes".to_string()),
+            ]
+        );
+
+        let query = ElementQuery {
+            window: Some("erp".to_string()),
+            max_results: Some(1),
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&query),
+            [("Invoice - ERP".to_string(), "Invoice - ERP".to_string())]
+        );
+    }
+
+    #[test]
+    fn depth_limits_the_walk() {
+        let query = ElementQuery {
+            name_contains: Some("save".to_string()),
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert!(names(&query).is_empty());
+    }
+
+    #[test]
+    fn actions_need_a_unique_match_or_an_index() {
+        let query = ElementQuery {
+            name_contains: Some("save".to_string()),
+            ..Default::default()
+        };
+        let matches = || accessibility::search(desktop(), &query).unwrap();
+
+        let error = accessibility::pick(matches(), None).err().unwrap();
+        assert_eq!(error, "3 elements match the query; pass an index");
+        let error = accessibility::pick(matches(), Some(3)).err().unwrap();
+        assert_eq!(error, "No element at index 3 (3 matched)");
+        let (node, window) = accessibility::pick(matches(), Some(1)).unwrap();
+        assert_eq!(
+            (node.name(), window.as_str()),
+            ("Save as".to_string(), "Invoice - ERP")
+        );
+    }
+}
+
+mod template_matching {
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::ConfigLoader;
+    use crate::matching::{self, GrayImage};
+
+    fn noise(width: u32, height: u32, seed: u64) -> Vec<f32> {
+        let mut state = seed;
+        (0..width * height)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 256) as f32
+            })
+            .collect()
+    }
+
+    fn crop(image: &[f32], image_width: u32, x: u32, y: u32, w: u32, h: u32) -> GrayImage {
+        let pixels = (y..y + h)
+            .flat_map(|row| {
+                let start = (row * image_width + x) as usize;
+                image[start..start + w as usize].to_vec()
+            })
+            .collect();
+        GrayImage::new(w, h, pixels).unwrap()
+    }
+
+    #[test]
+    fn finds_template_at_exact_position() {
+        let pixels = noise(320, 200, 7);
+        let needle = crop(&pixels, 320, 141, 77, 48, 40);
+        let haystack = GrayImage::new(320, 200, pixels).unwrap();
+
+        let matches = matching::find_template(&haystack, &needle, 0.9, 5).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].x, matches[0].y), (141, 77));
+        assert!(matches[0].score > 0.99);
+    }
+
+    #[test]
+    fn finds_every_copy_best_first() {
+        let mut pixels = noise(300, 120, 11);
+        let needle = crop(&pixels, 300, 10, 10, 32, 32);
+        // Paste a fainter copy elsewhere
+        for row in 0..32 {
+            for col in 0..32 {
+                let value = pixels[((10 + row) * 300 + 10 + col) as usize];
+                pixels[((70 + row) * 300 + 200 + col) as usize] = value * 0.5 + 40.0;
+            }
+        }
+        let haystack = GrayImage::new(300, 120, pixels).unwrap();
+
+        let matches = matching::find_template(&haystack, &needle, 0.9, 10).unwrap();
+        let positions: Vec<(u32, u32)> = matches.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(positions, vec![(10, 10), (200, 70)]);
+    }
+
+    #[test]
+    fn unrelated_content_is_not_matched() {
+        let haystack = GrayImage::new(200, 200, noise(200, 200, 3)).unwrap();
+        let needle = GrayImage::new(40, 40, noise(40, 40, 99)).unwrap();
+        assert!(matching::find_template(&haystack, &needle, 0.8, 5)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn match_tests_report_scores_below_the_threshold() {
+        let mut pixels = noise(300, 120, 11);
+        let needle = crop(&pixels, 300, 10, 10, 32, 32);
+        for row in 0..32 {
+            for col in 0..32 {
+                let value = pixels[((10 + row) * 300 + 10 + col) as usize];
+                pixels[((70 + row) * 300 + 200 + col) as usize] = value * 0.5 + 40.0;
+                // Spoil the original so it no longer passes
+                pixels[((10 + row) * 300 + 10 + col) as usize] += ((row * col) % 97) as f32;
+            }
+        }
+        let haystack = GrayImage::new(300, 120, pixels).unwrap();
+
+        let test = matching::test_template(&haystack, &needle, 0.99, 5).unwrap();
+        assert_eq!(test.matches.len(), 1);
+        assert_eq!((test.matches[0].x, test.matches[0].y), (200, 70));
+        assert_eq!(test.best_score, Some(test.matches[0].score));
+        let near = &test.near_misses[0];
+        assert_eq!((near.x, near.y), (10, 10));
+        assert!(near.score < 0.99 && near.score > 0.5);
+    }
+
+    #[test]
+    fn flat_or_oversized_templates_are_rejected() {
+        let haystack = GrayImage::new(50, 50, noise(50, 50, 5)).unwrap();
+        let flat = GrayImage::new(10, 10, vec![128.0; 100]).unwrap();
+        assert!(matching::find_template(&haystack, &flat, 0.8, 5).is_err());
+
+        let large = GrayImage::new(60, 10, noise(60, 10, 5)).unwrap();
+        assert!(matching::find_template(&haystack, &large, 0.8, 5).is_err());
+    }
+
+    #[test]
+    fn decodes_config_images() {
+        let spec = FixtureSpec {
+            images: 1,
+            image_width: 24,
+            image_height: 12,
+            ..Default::default()
+        };
+        let json = fixtures::generate(&spec).to_string();
+        let config = ConfigLoader::load_from_string(&json).unwrap();
+        let image = GrayImage::decode_png(&config.image_store.load("image-0").unwrap()).unwrap();
+        assert_eq!((image.width, image.height), (24, 12));
+    }
+}
+
+mod color_checks {
+    use crate::capture::RgbImage;
+    use crate::color::{self, Color};
+
+    #[test]
+    fn parses_and_formats_hex_colours() {
+        let color = Color::parse("#1E90ff").unwrap();
+        assert_eq!(
+            color,
+            Color {
+                r: 30,
+                g: 144,
+                b: 255
+            }
+        );
+        assert_eq!(color.to_string(), "#1e90ff");
+        assert_eq!(Color::parse("00ff00").unwrap().g, 255);
+
+        for bad in ["", "#fff", "#12345g", "#1234567", "#ééé"] {
+            assert!(Color::parse(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn tolerance_is_per_channel() {
+        let green = Color::parse("#20c020").unwrap();
+        assert!(green.matches(&Color::parse("#28b828").unwrap(), 8));
+        assert!(!green.matches(&Color::parse("#29c020").unwrap(), 8));
+    }
+
+    #[test]
+    fn sample_reports_fraction_and_average() {
+        // A green light with a dark anti-aliased border
+        let mut pixels = vec![[0x20, 0xc0, 0x20]; 16];
+        for i in [0, 3, 12, 15] {
+            pixels[i] = [0x10, 0x10, 0x10];
+        }
+        let image = RgbImage {
+            width: 4,
+            height: 4,
+            pixels,
+        };
+
+        let sample = color::sample(&image, &Color::parse("#22be22").unwrap(), 5);
+        assert_eq!(sample.fraction, 0.75);
+        assert_eq!(sample.average, "#1c941c");
+    }
+}
+
+mod live_preview {
+    use crate::capture::RgbImage;
+    use crate::preview::PreviewSettings;
+
+    #[test]
+    fn frames_are_scaled_down_to_fit() {
+        let settings = PreviewSettings::default();
+        assert_eq!(settings.downscale_factor(800), 1);
+        assert_eq!(settings.downscale_factor(1920), 2);
+        assert_eq!(settings.downscale_factor(3840), 4);
+        assert_eq!(settings.downscale_factor(2560), 3);
+
+        let image = RgbImage {
+            width: 2,
+            height: 2,
+            pixels: vec![[0, 0, 0], [100, 0, 0], [0, 200, 0], [100, 200, 40]],
+        };
+        let scaled = image.downscale(2);
+        assert_eq!((scaled.width, scaled.height), (1, 1));
+        assert_eq!(scaled.pixels, vec![[50, 100, 10]]);
+    }
+
+    #[test]
+    fn rates_outside_the_limits_are_rejected() {
+        assert!(PreviewSettings::default().validate().is_ok());
+        for fps in [0.0, -1.0, 30.0, f64::NAN] {
+            let settings = PreviewSettings {
+                fps,
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err(), "{}", fps);
+        }
+    }
+}
+
+mod screenshot_diff {
+    use super::ScratchDir;
+    use crate::capture::{Region, RgbImage};
+    use crate::visual_diff;
+
+    fn solid(width: u32, height: u32, color: [u8; 3]) -> RgbImage {
+        RgbImage {
+            width,
+            height,
+            pixels: vec![color; (width * height) as usize],
+        }
+    }
+
+    #[test]
+    fn identical_images_score_zero() {
+        let image = solid(8, 8, [40, 80, 120]);
+        let (summary, heatmap) = visual_diff::compare(&image, &image, 0).unwrap();
+        assert_eq!(summary.changed_pixels, 0);
+        assert_eq!(summary.score, 0.0);
+        assert!(summary.changed_bounds.is_none());
+        assert_eq!(heatmap.pixels.len(), 64);
+    }
+
+    #[test]
+    fn changes_are_scored_bounded_and_highlighted() {
+        let a = solid(10, 10, [100, 100, 100]);
+        let mut b = a.clone();
+        for (x, y) in [(2, 3), (6, 7), (4, 5)] {
+            b.pixels[y * 10 + x] = [100, 200, 100];
+        }
+        b.pixels[0] = [105, 100, 100]; // below the threshold
+
+        let (summary, heatmap) = visual_diff::compare(&a, &b, 10).unwrap();
+        assert_eq!(summary.changed_pixels, 3);
+        assert_eq!(summary.score, 0.03);
+        assert_eq!(
+            summary.changed_bounds,
+            Some(Region {
+                x: 2,
+                y: 3,
+                width: 5,
+                height: 5
+            })
+        );
+        assert_eq!(heatmap.pixels[3 * 10 + 2], [255, 155, 0]);
+        assert_ne!(heatmap.pixels[0][0], 255);
+    }
+
+    #[test]
+    fn size_mismatch_is_an_error() {
+        assert!(visual_diff::compare(&solid(4, 4, [0; 3]), &solid(4, 5, [0; 3]), 0).is_err());
+    }
+
+    #[test]
+    fn heatmap_png_round_trips() {
+        let image = solid(3, 2, [1, 2, 3]);
+        let decoded = RgbImage::decode_png(&image.encode_png().unwrap()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.pixels, image.pixels);
+    }
+
+    #[test]
+    fn runs_are_paired_by_relative_path() {
+        let (run_a, run_b, output) = (
+            ScratchDir::new("a"),
+            ScratchDir::new("b"),
+            ScratchDir::new("out"),
+        );
+        for run in [&run_a, &run_b] {
+            std::fs::create_dir_all(run.join("steps")).unwrap();
+        }
+        let write = |path: std::path::PathBuf, image: &RgbImage| {
+            std::fs::write(path, image.encode_png().unwrap()).unwrap()
+        };
+        write(run_a.join("steps/login.png"), &solid(4, 4, [0; 3]));
+        write(run_b.join("steps/login.png"), &solid(4, 4, [255; 3]));
+        write(run_a.join("start.png"), &solid(4, 4, [9; 3]));
+        write(run_b.join("start.png"), &solid(4, 4, [9; 3]));
+        write(run_a.join("only-a.png"), &solid(1, 1, [0; 3]));
+        write(run_b.join("only-b.png"), &solid(1, 1, [0; 3]));
+
+        let comparison =
+            visual_diff::compare_runs(&run_a, &run_b, 16, &output, |_, _| true).unwrap();
+        let paths: Vec<&str> = comparison
+            .screenshots
+            .iter()
+            .map(|s| s.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["steps/login.png", "start.png"]);
+        assert_eq!(
+            comparison.screenshots[0].summary.as_ref().unwrap().score,
+            1.0
+        );
+        assert!(comparison.screenshots[0].heatmap.as_ref().unwrap().exists());
+        assert_eq!(comparison.only_in_a, vec!["only-a.png"]);
+        assert_eq!(comparison.only_in_b, vec!["only-b.png"]);
+    }
+}
+
+mod variable_store {
+    use super::ScratchDir;
+    use crate::variables::{VariableScope, VariableStore};
+    use serde_json::json;
+
+    #[test]
+    fn values_survive_reopening() {
+        let dir = ScratchDir::new("variables");
+        let path = dir.join("variables.json");
+        let store = VariableStore::open(path.clone());
+        store
+            .set(
+                VariableScope::Global,
+                None,
+                "last_invoice",
+                Some(json!(1042)),
+            )
+            .unwrap();
+        store
+            .set(
+                VariableScope::Config,
+                Some("Billing"),
+                "cursor",
+                Some(json!("abc")),
+            )
+            .unwrap();
+
+        let reopened = VariableStore::open(path.clone());
+        assert_eq!(
+            reopened
+                .get(VariableScope::Global, None, "last_invoice")
+                .unwrap(),
+            Some(json!(1042))
+        );
+        assert_eq!(
+            reopened.for_execution(Some("Billing")),
+            json!({ "global": { "last_invoice": 1042 }, "config": { "cursor": "abc" } })
+        );
+    }
+
+    #[test]
+    fn config_scope_is_per_configuration() {
+        let dir = ScratchDir::new("variables");
+        let path = dir.join("variables.json");
+        let store = VariableStore::open(path.clone());
+        store
+            .set(VariableScope::Config, Some("A"), "count", Some(json!(1)))
+            .unwrap();
+
+        assert_eq!(
+            store
+                .get(VariableScope::Config, Some("B"), "count")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            store.get(VariableScope::Global, None, "count").unwrap(),
+            None
+        );
+        assert!(store.get(VariableScope::Config, None, "count").is_err());
+    }
+
+    #[test]
+    fn none_deletes_a_value() {
+        let dir = ScratchDir::new("variables");
+        let path = dir.join("variables.json");
+        let store = VariableStore::open(path.clone());
+        store
+            .set(VariableScope::Global, None, "token", Some(json!("x")))
+            .unwrap();
+        store
+            .set(VariableScope::Global, None, "token", None)
+            .unwrap();
+
+        assert!(VariableStore::open(path.clone())
+            .list(VariableScope::Global, None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn corrupt_store_is_set_aside() {
+        let dir = ScratchDir::new("variables");
+        let path = dir.join("variables.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let store = VariableStore::open(path.clone());
+        assert!(store.list(VariableScope::Global, None).unwrap().is_empty());
+        assert!(path.with_extension("json.corrupt").exists());
+    }
+
+    #[test]
+    fn scope_names_are_validated() {
+        assert_eq!(VariableScope::parse(None).unwrap(), VariableScope::Global);
+        assert_eq!(
+            VariableScope::parse(Some("config")).unwrap(),
+            VariableScope::Config
+        );
+        assert!(VariableScope::parse(Some("session")).is_err());
+    }
+}
+
+mod secret_names {
+    use crate::config::QontinuiConfig;
+    use crate::secrets::{may_answer, validate_name};
+    use serde_json::json;
+
+    #[test]
+    fn accepts_plain_names() {
+        for name in ["erp-password", "API_TOKEN", "smtp.user.2"] {
+            assert!(validate_name(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_names_that_need_quoting() {
+        for name in ["", "with space", "quote'd", "semi;colon", &"x".repeat(129)] {
+            assert!(validate_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn only_secrets_the_configuration_types_are_answered() {
+        let config = QontinuiConfig::from_value(&json!({
+            "version": "1.0.0",
+            "metadata": { "name": "Login" },
+            "images": [],
+            "states": [],
+            "transitions": [],
+            "categories": [],
+            "workflows": [{
+                "id": "main",
+                "actions": [
+                    { "id": "a1", "type": "TYPE", "config": { "textSource": "secret", "secretName": "erp-password" } },
+                    { "id": "a2", "type": "IF", "config": { "thenActions": [
+                        { "type": "TYPE", "config": { "secretName": "erp-user" } },
+                    ] } },
+                    { "id": "a3", "type": "TYPE", "config": { "text": "${secret:erp-token} $${secret:literal}" } },
+                ],
+            }],
+        }))
+        .unwrap();
+        assert_eq!(
+            config.secret_names().into_iter().collect::<Vec<_>>(),
+            ["erp-password", "erp-token", "erp-user"]
+        );
+
+        assert!(may_answer("erp-user", Some(&config), false).is_ok());
+        assert!(may_answer("smtp-password", Some(&config), false).is_err());
+        assert!(may_answer("erp-user", None, false).is_err());
+        let refused = may_answer("erp-user", Some(&config), true).unwrap_err();
+        assert!(refused.contains("without TLS"), "{}", refused);
+    }
+}
+
+mod input_limits {
+    use crate::executor::input_limits::{InputLimits, InputRateLimiter};
+
+    fn limiter() -> InputRateLimiter {
+        InputRateLimiter::new(InputLimits {
+            enabled: true,
+            max_clicks_per_second: 4,
+            max_keystrokes_per_second: 10,
+            min_destructive_delay_ms: 500,
+        })
+    }
+
+    #[test]
+    fn evenly_paced_clicks_are_allowed() {
+        let mut limiter = limiter();
+        for i in 0..20 {
+            let timestamp = 100.0 + i as f64 * 0.25;
+            assert!(
+                limiter.observe("CLICK", false, timestamp).is_ok(),
+                "click {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn burst_of_clicks_is_rejected() {
+        let mut limiter = limiter();
+        for i in 0..4 {
+            limiter
+                .observe("CLICK", false, 100.0 + i as f64 * 0.01)
+                .unwrap();
+        }
+        let err = limiter.observe("DRAG", false, 100.05).unwrap_err();
+        assert!(err.contains("5 clicks"), "{}", err);
+    }
+
+    #[test]
+    fn clicks_and_keystrokes_are_counted_separately() {
+        let mut limiter = limiter();
+        for i in 0..4 {
+            limiter
+                .observe("CLICK", false, 100.0 + i as f64 * 0.01)
+                .unwrap();
+        }
+        for i in 0..10 {
+            limiter
+                .observe("KEY", false, 100.0 + i as f64 * 0.01)
+                .unwrap();
+        }
+        assert!(limiter.observe("FIND", false, 100.2).is_ok());
+        assert!(limiter.observe("TYPE", false, 100.2).is_err());
+    }
+
+    #[test]
+    fn destructive_actions_need_a_gap() {
+        let mut limiter = limiter();
+        limiter.observe("CLICK", true, 100.0).unwrap();
+        assert!(limiter.observe("CLICK", false, 100.1).is_ok());
+        assert!(limiter.observe("CLICK", true, 100.6).is_ok());
+        assert!(limiter.observe("KEY", true, 100.8).is_err());
+    }
+
+    #[test]
+    fn disabled_limits_allow_anything() {
+        let mut limiter = InputRateLimiter::new(InputLimits {
+            enabled: false,
+            ..InputLimits::default()
+        });
+        for _ in 0..100 {
+            assert!(limiter.observe("CLICK", true, 100.0).is_ok());
+        }
+    }
+}
+
+mod safe_mode {
+    use crate::capture::Region;
+    use crate::executor::safe_mode::{check_target, contains, RunBounds, SafeMode};
+    use serde_json::json;
+
+    const BOUNDS: Region = Region {
+        x: 100,
+        y: 50,
+        width: 200,
+        height: 100,
+    };
+
+    #[test]
+    fn each_session_keeps_its_own_bounds() {
+        let bounds = RunBounds::default();
+        let other = Region { x: 0, ..BOUNDS };
+        bounds.set("default", Some(BOUNDS));
+        bounds.set("second", Some(other));
+        assert_eq!(bounds.get("default"), Some(BOUNDS));
+        assert_eq!(bounds.get("second"), Some(other));
+
+        // An unbounded run in one session leaves the others alone
+        bounds.set("second", None);
+        assert_eq!(bounds.get("second"), None);
+        assert_eq!(bounds.get("default"), Some(BOUNDS));
+    }
+
+    #[test]
+    fn bounds_include_the_top_left_edge_only() {
+        assert!(contains(&BOUNDS, 100.0, 50.0));
+        assert!(contains(&BOUNDS, 299.5, 149.5));
+        assert!(!contains(&BOUNDS, 300.0, 100.0));
+        assert!(!contains(&BOUNDS, 150.0, 150.0));
+        assert!(!contains(&BOUNDS, 99.9, 100.0));
+    }
+
+    #[test]
+    fn targets_inside_the_bounds_pass() {
+        let data = json!({ "action_id": "a1", "points": [[120, 60], [290.5, 140]] });
+        assert!(check_target(&BOUNDS, &data).is_ok());
+    }
+
+    #[test]
+    fn any_point_outside_fails() {
+        let data = json!({ "points": [[120, 60], [400, 60]] });
+        let err = check_target(&BOUNDS, &data).unwrap_err();
+        assert!(err.contains("(400, 60)"), "{}", err);
+    }
+
+    #[test]
+    fn targets_without_coordinates_fail() {
+        assert!(check_target(&BOUNDS, &json!({})).is_err());
+        assert!(check_target(&BOUNDS, &json!({ "points": [] })).is_err());
+        assert!(check_target(&BOUNDS, &json!({ "points": [[null, 60]] })).is_err());
+    }
+
+    #[test]
+    fn disabled_safe_mode_has_no_bounds() {
+        let safe_mode = SafeMode {
+            enabled: false,
+            region: Some(BOUNDS),
+            window: None,
+        };
+        assert_eq!(safe_mode.resolve().unwrap(), None);
+    }
+
+    #[test]
+    fn region_is_used_as_is() {
+        let safe_mode = SafeMode {
+            enabled: true,
+            region: Some(BOUNDS),
+            window: None,
+        };
+        assert_eq!(safe_mode.resolve().unwrap(), Some(BOUNDS));
+    }
+
+    #[test]
+    fn enabled_safe_mode_needs_a_non_empty_area() {
+        let unbounded = SafeMode {
+            enabled: true,
+            ..SafeMode::default()
+        };
+        assert!(unbounded.resolve().is_err());
+
+        let empty = SafeMode {
+            enabled: true,
+            region: Some(Region { width: 0, ..BOUNDS }),
+            window: None,
+        };
+        assert!(empty.resolve().is_err());
+    }
+
+    #[test]
+    fn settings_default_to_off() {
+        let safe_mode: SafeMode = serde_json::from_str("{}").unwrap();
+        assert_eq!(safe_mode, SafeMode::default());
+        assert!(!safe_mode.enabled);
+    }
+}
+
+mod idle_detection {
+    use crate::idle::{is_user_activity, parse_gdbus_idletime, parse_ioreg, POLL_INTERVAL};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn parses_macos_hid_idle_time() {
+        let output = r#"    | |   "HIDIdleTime" = 2503912083
+    | |   "HIDKeyboardModifierMappingPairs" = ()"#;
+        assert_eq!(parse_ioreg(output), Some(Duration::from_nanos(2503912083)));
+        assert_eq!(parse_ioreg("no idle time here"), None);
+    }
+
+    #[test]
+    fn parses_mutter_idle_time() {
+        assert_eq!(
+            parse_gdbus_idletime("(uint64 48213,)\n"),
+            Some(Duration::from_millis(48213))
+        );
+        assert_eq!(parse_gdbus_idletime("Error: not available"), None);
+    }
+
+    #[test]
+    fn long_idle_is_not_activity() {
+        let now = Instant::now();
+        assert!(!is_user_activity(POLL_INTERVAL, 0, None, now));
+    }
+
+    #[test]
+    fn recent_input_without_executor_input_is_the_user() {
+        let now = Instant::now();
+        assert!(is_user_activity(Duration::from_secs(1), 0, None, now));
+        let long_ago = now - Duration::from_secs(30);
+        assert!(is_user_activity(
+            Duration::from_secs(1),
+            0,
+            Some(long_ago),
+            now
+        ));
+    }
+
+    #[test]
+    fn executor_input_is_not_the_user() {
+        let now = Instant::now();
+        assert!(!is_user_activity(Duration::from_millis(100), 1, None, now));
+        // The idle timer was reset half a second after an action finished
+        let action_end = now - Duration::from_secs(2);
+        assert!(!is_user_activity(
+            Duration::from_millis(1500),
+            0,
+            Some(action_end),
+            now
+        ));
+    }
+}
+
+mod run_plan {
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::run_plan::{QueuedRun, RunTracker};
+    use chrono::{Duration, Local};
+    use serde_json::json;
+
+    fn event(name: &str, data: serde_json::Value) -> ExecutorEvent {
+        ExecutorEvent {
+            event: name.to_string(),
+            timestamp: 0.0,
+            sequence: 1,
+            data,
+        }
+    }
+
+    fn queued(task_id: &str, workflow_id: &str) -> QueuedRun {
+        QueuedRun {
+            task_id: task_id.to_string(),
+            workflow_id: workflow_id.to_string(),
+            label: format!("Run {}", workflow_id),
+            status: None,
+            ready_at: None,
+        }
+    }
+
+    /// Records one successful run of `workflow_id` lasting `seconds`.
+    fn complete_run(tracker: &RunTracker, workflow_id: &str, seconds: i64) {
+        let start = Local::now() - Duration::hours(1);
+        tracker.record_event_at(
+            &event("execution_started", json!({ "workflow_id": workflow_id })),
+            start,
+        );
+        tracker.record_event_at(
+            &event("execution_completed", json!({ "success": true })),
+            start + Duration::seconds(seconds),
+        );
+    }
+
+    #[test]
+    fn empty_plan_without_runs() {
+        let plan = RunTracker::default().plan(Local::now());
+        assert!(plan.active.is_empty());
+        assert!(plan.queued.is_empty());
+        assert!(plan.scheduled.is_empty());
+    }
+
+    #[test]
+    fn active_run_eta_uses_average_duration() {
+        let tracker = RunTracker::default();
+        complete_run(&tracker, "login", 60);
+        complete_run(&tracker, "login", 120);
+
+        let now = Local::now();
+        tracker.record_event_at(
+            &event("execution_started", json!({ "workflow_id": "login" })),
+            now,
+        );
+        let plan = tracker.plan(now);
+        assert_eq!(plan.active.len(), 1);
+        assert_eq!(plan.active[0].estimated_duration_seconds, Some(90.0));
+        assert_eq!(
+            plan.active[0].eta,
+            Some((now + Duration::seconds(90)).to_rfc3339())
+        );
+    }
+
+    #[test]
+    fn stopped_runs_do_not_count_toward_estimates() {
+        let tracker = RunTracker::default();
+        let start = Local::now();
+        tracker.record_event_at(
+            &event("execution_started", json!({ "workflow_id": "login" })),
+            start,
+        );
+        tracker.record_event_at(
+            &event("execution_completed", json!({ "success": false })),
+            start + Duration::seconds(5),
+        );
+
+        tracker.enqueue(queued("t1", "login"));
+        let plan = tracker.plan(Local::now());
+        assert!(plan.active.is_empty());
+        assert_eq!(plan.queued[0].estimated_duration_seconds, None);
+        assert_eq!(plan.queued[0].eta, None);
+    }
+
+    #[test]
+    fn queued_runs_follow_the_active_run_in_order() {
+        let tracker = RunTracker::default();
+        complete_run(&tracker, "a", 60);
+        complete_run(&tracker, "b", 30);
+
+        let now = Local::now();
+        tracker.record_event_at(
+            &event("execution_started", json!({ "workflow_id": "a" })),
+            now,
+        );
+        tracker.enqueue(queued("t1", "b"));
+        let mut later = queued("t2", "a");
+        later.ready_at = Some(now + Duration::seconds(600));
+        tracker.enqueue(later);
+
+        let plan = tracker.plan(now);
+        let first = &plan.queued[0];
+        assert_eq!(
+            first.expected_start,
+            Some((now + Duration::seconds(60)).to_rfc3339())
+        );
+        assert_eq!(first.eta, Some((now + Duration::seconds(90)).to_rfc3339()));
+        // Not ready until later than the previous run ends
+        let second = &plan.queued[1];
+        assert_eq!(
+            second.expected_start,
+            Some((now + Duration::seconds(600)).to_rfc3339())
+        );
+        assert_eq!(
+            second.eta,
+            Some((now + Duration::seconds(660)).to_rfc3339())
+        );
+
+        tracker.dequeue("t1");
+        assert_eq!(tracker.plan(now).queued.len(), 1);
+    }
+}
+
+mod profiles {
+    use super::ScratchDir;
+    use crate::profiles::{ProfileSettings, Profiles, DEFAULT_PROFILE};
+
+    #[test]
+    fn default_profile_uses_the_data_dir() {
+        let root = ScratchDir::new("profiles");
+        let profiles = Profiles::open(root.to_path_buf());
+
+        let active = profiles.active();
+        assert_eq!(active.name, DEFAULT_PROFILE);
+        assert_eq!(active.dir, *root);
+        assert_eq!(active.variables_path(), root.join("variables.json"));
+    }
+
+    #[test]
+    fn created_profiles_are_isolated_and_remembered() {
+        let root = ScratchDir::new("profiles");
+        let profiles = Profiles::open(root.to_path_buf());
+        let mut settings = ProfileSettings::default();
+        settings.input_limits.max_clicks_per_second = 3;
+
+        let tester = profiles.create("tester-1", &settings).unwrap();
+        assert_eq!(tester.dir, root.join("profiles").join("tester-1"));
+        assert!(tester.recordings_dir.is_dir());
+        assert!(tester.history_dir.is_dir());
+        assert_ne!(tester.variables_path(), profiles.active().variables_path());
+
+        profiles.set_active("tester-1").unwrap();
+        let reopened = Profiles::open(root.to_path_buf());
+        assert_eq!(reopened.active().name, "tester-1");
+        assert_eq!(
+            reopened
+                .active()
+                .load_settings()
+                .input_limits
+                .max_clicks_per_second,
+            3
+        );
+        let names: Vec<String> = reopened.list().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec![DEFAULT_PROFILE, "tester-1"]);
+    }
+
+    #[test]
+    fn rejects_duplicate_and_unsafe_names() {
+        let root = ScratchDir::new("profiles");
+        let profiles = Profiles::open(root.to_path_buf());
+        let settings = ProfileSettings::default();
+
+        assert!(profiles.create(DEFAULT_PROFILE, &settings).is_err());
+        assert!(profiles.create("../escape", &settings).is_err());
+        assert!(profiles.create("", &settings).is_err());
+        profiles.create("qa", &settings).unwrap();
+        assert!(profiles.create("qa", &settings).is_err());
+        assert!(profiles.set_active("missing").is_err());
+    }
+}
+
+mod config_editor {
+    use super::load_fixture;
+    use crate::config::editor::{self, EditState, EntryChange, Section};
+    use crate::config::fixtures::FixtureSpec;
+    use crate::config::QontinuiConfig;
+    use serde_json::json;
+
+    fn small_spec() -> FixtureSpec {
+        FixtureSpec {
+            states: 3,
+            transitions: 2,
+            workflows: 1,
+            images: 2,
+            image_width: 8,
+            image_height: 8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adds_entries_with_unique_ids() {
+        let config = load_fixture(&small_spec());
+
+        let edited = editor::apply(&config, |config| {
+            editor::add_entry(
+                config,
+                Section::States,
+                json!({ "id": "login", "name": "Login" }),
+            )
+            .map(|_| ())
+        })
+        .unwrap();
+        assert_eq!(edited.states.len(), 4);
+        assert_eq!(config.states.len(), 3);
+
+        let duplicate = editor::apply(&edited, |config| {
+            editor::add_entry(config, Section::States, json!({ "id": "login" })).map(|_| ())
+        });
+        assert!(duplicate.unwrap_err().contains("already exists"));
+        let missing_id = editor::apply(&edited, |config| {
+            editor::add_entry(config, Section::States, json!({ "name": "Nameless" })).map(|_| ())
+        });
+        assert!(missing_id.is_err());
+    }
+
+    #[test]
+    fn rejects_edits_that_break_references() {
+        let config = load_fixture(&small_spec());
+        let referenced = config.transitions[0].from_state.clone().unwrap();
+
+        let result = editor::apply(&config, |config| {
+            editor::remove_entry(config, Section::States, &referenced).map(|_| ())
+        });
+        let error = result.unwrap_err();
+        assert!(error.contains("references unknown state"), "{}", error);
+        assert!(config.states.iter().any(|s| s.id == referenced));
+
+        let result = editor::apply(&config, |config| {
+            editor::update_entry(
+                config,
+                Section::Transitions,
+                "transition-0",
+                json!({ "toState": "nowhere" }),
+                false,
+            )
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn updates_merge_fields_and_keep_the_id() {
+        let config = load_fixture(&small_spec());
+
+        let edited = editor::apply(&config, |config| {
+            editor::update_entry(
+                config,
+                Section::Transitions,
+                "transition-1",
+                json!({ "timeout": 2500, "retryCount": null }),
+                false,
+            )
+        })
+        .unwrap();
+        let transition = &edited.transitions[1];
+        assert_eq!(transition.extra["timeout"], 2500);
+        assert!(transition.extra.get("retryCount").is_none());
+        assert_eq!(transition.from_state, config.transitions[1].from_state);
+
+        let renamed = editor::apply(&config, |config| {
+            editor::update_entry(
+                config,
+                Section::States,
+                "state-0",
+                json!({ "id": "state-9" }),
+                false,
+            )
+        });
+        assert!(renamed.unwrap_err().contains("can't be changed"));
+        let missing = editor::apply(&config, |config| {
+            editor::update_entry(config, Section::States, "state-9", json!({}), false)
+        });
+        assert!(missing.unwrap_err().contains("not found"));
+    }
+
+    #[test]
+    fn image_payloads_follow_their_entries() {
+        let config = load_fixture(&small_spec());
+        let data = config.image_store.data_url("image-0").unwrap();
+
+        let edited = editor::apply(&config, |config| {
+            editor::add_entry(
+                config,
+                Section::Images,
+                json!({ "id": "copy", "name": "Copy", "data": data }),
+            )
+            .map(|_| ())
+        })
+        .unwrap();
+        let added = edited.images.iter().find(|i| i.id == "copy").unwrap();
+        assert!(added.extra.get("data").is_none());
+        assert_eq!(added.extra["hasData"], true);
+        assert_eq!(
+            edited.image_store.load("copy").unwrap(),
+            config.image_store.load("image-0").unwrap()
+        );
+
+        let removed = editor::apply(&edited, |config| {
+            editor::remove_entry(config, Section::Images, "copy").map(|_| ())
+        })
+        .unwrap();
+        assert!(removed.image_store.source("copy").is_none());
+        assert_eq!(removed.image_store.len(), 2);
+    }
+
+    #[test]
+    fn saved_file_loads_back() {
+        let config = load_fixture(&small_spec());
+        let json = config.to_file_json().unwrap();
+        assert!(!json.contains("hasData"));
+
+        let reloaded = crate::config::ConfigLoader::load_from_string(&json).unwrap();
+        assert_eq!(reloaded.states, config.states);
+        assert_eq!(
+            reloaded.image_store.load("image-1").unwrap(),
+            config.image_store.load("image-1").unwrap()
+        );
+    }
+
+    fn record_edit<F>(
+        config: &QontinuiConfig,
+        edits: &mut EditState,
+        section: Section,
+        id: &str,
+        edit: F,
+    ) -> QontinuiConfig
+    where
+        F: FnOnce(&mut QontinuiConfig) -> Result<(), String>,
+    {
+        let edited = editor::apply(config, edit).unwrap();
+        let description = format!("Edited {} '{}'", section.noun(), id);
+        edits.record(EntryChange::between(
+            config,
+            &edited,
+            section,
+            id,
+            description,
+        ));
+        edited
+    }
+
+    #[test]
+    fn undo_and_redo_restore_entries() {
+        let original = load_fixture(&small_spec());
+        let mut edits = EditState::loaded("config.json".into());
+
+        let added = record_edit(&original, &mut edits, Section::States, "login", |c| {
+            editor::add_entry(c, Section::States, json!({ "id": "login" })).map(|_| ())
+        });
+        let updated = record_edit(&added, &mut edits, Section::States, "state-1", |c| {
+            editor::update_entry(
+                c,
+                Section::States,
+                "state-1",
+                json!({ "name": "Home" }),
+                false,
+            )
+        });
+        let data = original.image_store.data_url("image-0").unwrap();
+        let replaced = record_edit(&updated, &mut edits, Section::Images, "image-1", |c| {
+            editor::update_entry(
+                c,
+                Section::Images,
+                "image-1",
+                json!({ "data": data }),
+                false,
+            )
+        });
+        assert_eq!(
+            replaced.image_store.load("image-1").unwrap(),
+            original.image_store.load("image-0").unwrap()
+        );
+
+        let mut config = replaced.clone();
+        edits.next_undo().unwrap().undo(&mut config);
+        edits.undone();
+        assert_eq!(config.images, updated.images);
+        assert_eq!(
+            config.image_store.load("image-1").unwrap(),
+            original.image_store.load("image-1").unwrap()
+        );
+
+        edits.next_undo().unwrap().undo(&mut config);
+        edits.undone();
+        edits.next_undo().unwrap().undo(&mut config);
+        edits.undone();
+        assert_eq!(config.states, original.states);
+        assert!(edits.next_undo().is_none());
+        assert!(!edits.dirty);
+
+        edits.next_redo().unwrap().redo(&mut config);
+        edits.redone();
+        assert_eq!(config.states, added.states);
+        assert!(edits.dirty);
+        assert_eq!(
+            edits.last_edit.as_deref(),
+            Some("Redid: Edited state 'login'")
+        );
+    }
+
+    #[test]
+    fn dirty_state_follows_the_saved_point() {
+        let config = load_fixture(&small_spec());
+        let mut edits = EditState::loaded("config.json".into());
+        assert!(!edits.dirty);
+
+        let edited = record_edit(&config, &mut edits, Section::States, "a", |c| {
+            editor::add_entry(c, Section::States, json!({ "id": "a" })).map(|_| ())
+        });
+        record_edit(&edited, &mut edits, Section::States, "b", |c| {
+            editor::add_entry(c, Section::States, json!({ "id": "b" })).map(|_| ())
+        });
+        assert!(edits.dirty);
+        assert_eq!(edits.edit_count, 2);
+
+        edits.saved("copy.json".into());
+        assert!(!edits.dirty);
+        assert_eq!(edits.source_path, Some("copy.json".into()));
+        edits.undone();
+        assert!(edits.dirty);
+        edits.redone();
+        assert!(!edits.dirty);
+
+        // A new edit after undoing past the saved point can't get back to it
+        edits.undone();
+        record_edit(&edited, &mut edits, Section::States, "c", |c| {
+            editor::add_entry(c, Section::States, json!({ "id": "c" })).map(|_| ())
+        });
+        assert!(edits.next_redo().is_none());
+        edits.undone();
+        assert!(edits.dirty);
+
+        let report = serde_json::to_value(&edits).unwrap();
+        assert_eq!(report["undo_depth"], 1);
+        assert_eq!(report["redo_depth"], 1);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let config = load_fixture(&small_spec());
+        let mut edits = EditState::loaded("config.json".into());
+        let change = |id: &str| {
+            let edited = editor::apply(&config, |c| {
+                editor::add_entry(c, Section::States, json!({ "id": id })).map(|_| ())
+            })
+            .unwrap();
+            EntryChange::between(&config, &edited, Section::States, id, id.to_string())
+        };
+        for i in 0..150 {
+            edits.record(change(&format!("s{}", i)));
+        }
+
+        let mut undone = 0;
+        while edits.next_undo().is_some() {
+            edits.undone();
+            undone += 1;
+        }
+        assert_eq!(undone, 100);
+        assert!(edits.dirty);
+    }
+}
+
+mod localization {
+    use crate::i18n::{self, Translations};
+    use chrono::{Local, TimeZone};
+    use std::time::Duration;
+
+    fn bundled() -> Translations {
+        let translations = Translations::default();
+        translations.set_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("locales"));
+        translations
+    }
+
+    #[test]
+    fn normalizes_os_locale_names() {
+        assert_eq!(i18n::normalize("de_DE.UTF-8").as_deref(), Some("de-DE"));
+        assert_eq!(i18n::normalize("en-us").as_deref(), Some("en-US"));
+        assert_eq!(i18n::normalize("fr").as_deref(), Some("fr"));
+        assert_eq!(i18n::normalize("sr_RS@latin").as_deref(), Some("sr-RS"));
+        assert_eq!(i18n::normalize("C"), None);
+        assert_eq!(i18n::normalize("POSIX"), None);
+        assert_eq!(i18n::normalize(""), None);
+        assert_eq!(i18n::fallback_chain("de-AT"), vec!["de-AT", "de", "en"]);
+        assert_eq!(i18n::fallback_chain("en-US"), vec!["en-US", "en"]);
+    }
+
+    #[test]
+    fn catalogs_fall_back_to_language_then_english() {
+        let translations = bundled();
+        assert!(translations.available().contains(&"de".to_string()));
+
+        let austrian = translations.catalog("de-AT");
+        assert_eq!(austrian["config.load"], "Konfiguration laden");
+
+        let american = translations.catalog("en-US");
+        assert_eq!(american["config.load"], "Load Configuration");
+        assert_eq!(american["format.date"], "%m/%d/%Y");
+
+        let unknown = translations.catalog("xx");
+        assert_eq!(unknown["config.load"], "Load Configuration");
+    }
+
+    #[test]
+    fn english_is_available_without_bundled_catalogs() {
+        let translations = Translations::default();
+        translations.set_dir(std::env::temp_dir().join("qontinui-no-locales"));
+        assert_eq!(translations.catalog("de")["report.passed"], "Passed");
+        assert_eq!(translations.available(), vec!["en"]);
+    }
+
+    #[test]
+    fn chosen_locale_overrides_detection() {
+        let translations = Translations::default();
+        assert_eq!(
+            translations.choose(Some("fr_FR")).unwrap().as_deref(),
+            Some("fr-FR")
+        );
+        assert_eq!(translations.locale(), "fr-FR");
+        assert!(translations.choose(Some("1")).is_err());
+        assert_eq!(translations.chosen().as_deref(), Some("fr-FR"));
+    }
+
+    #[test]
+    fn formats_durations_and_timestamps_per_locale() {
+        let translations = bundled();
+        let german = translations.formatter("de-DE");
+        let english = translations.formatter("en");
+        let american = translations.formatter("en-US");
+
+        assert_eq!(german.duration(Duration::from_millis(250)), "250 ms");
+        assert_eq!(german.duration(Duration::from_millis(2500)), "2,5 s");
+        assert_eq!(english.duration(Duration::from_millis(2500)), "2.5 s");
+        assert_eq!(english.duration(Duration::from_secs(125)), "2 min 5 s");
+        assert_eq!(english.duration(Duration::from_secs(7325)), "2 h 2 min");
+
+        let time = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+        assert_eq!(german.timestamp(&time), "09.03.2024 14:05:07");
+        assert_eq!(english.timestamp(&time), "2024-03-09 14:05:07");
+        assert_eq!(american.timestamp(&time), "03/09/2024 02:05:07 PM");
+
+        let summary = german.text(
+            "report.steps_passed",
+            &[("passed", "3".to_string()), ("total", "4".to_string())],
+        );
+        assert_eq!(summary, "3 von 4 Schritten bestanden");
+        assert_eq!(german.text("missing.key", &[]), "missing.key");
+    }
+}
+
+mod session_restore {
+    use super::ScratchDir;
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::session::{Session, SessionStore};
+    use serde_json::json;
+
+    fn event(name: &str, data: serde_json::Value) -> ExecutorEvent {
+        ExecutorEvent {
+            event: name.to_string(),
+            timestamp: 0.0,
+            sequence: 1,
+            data,
+        }
+    }
+
+    #[test]
+    fn offers_only_sessions_that_ended_uncleanly() {
+        let dir = ScratchDir::new("session");
+        let path = dir.join("session.json");
+        let first = SessionStore::open(path.clone());
+        assert!(first.previous().is_none());
+        first.update(|session| session.config_path = Some("flows.json".to_string()));
+
+        // No close(): the runner died
+        let second = SessionStore::open(path.clone());
+        let previous = second.previous().expect("unclean session is kept");
+        assert_eq!(previous.config_path.as_deref(), Some("flows.json"));
+        assert!(second.current().config_path.is_none());
+        assert!(second.take_previous().is_some());
+        assert!(second.previous().is_none());
+
+        second.close();
+        let third = SessionStore::open(path.clone());
+        assert!(third.previous().is_none());
+    }
+
+    #[test]
+    fn checkpoints_follow_the_execution() {
+        let dir = ScratchDir::new("session");
+        let path = dir.join("session.json");
+        let store = SessionStore::open(path.clone());
+        store.update(|session| session.monitor_index = Some(1));
+
+        store.record_event(&event("execution_started", json!({ "workflow_id": "wf" })));
+        store.record_event(&event(
+            "action_completed",
+            json!({ "action_id": "a1", "success": true }),
+        ));
+        store.record_event(&event(
+            "action_completed",
+            json!({ "action_id": "a2", "success": false }),
+        ));
+
+        let saved: Session =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let checkpoint = saved.active.expect("execution is in progress");
+        assert_eq!(checkpoint.workflow_id, "wf");
+        assert_eq!(checkpoint.monitor_index, Some(1));
+        assert_eq!(checkpoint.last_completed_action.as_deref(), Some("a1"));
+        assert!(!saved.clean_shutdown);
+
+        store.record_event(&event("execution_completed", json!({ "success": true })));
+        assert!(store.current().active.is_none());
+    }
+}
+
+mod event_streams {
+    use super::ScratchDir;
+    use crate::executor::protocol::BridgeMessage;
+    use crate::executor::python_bridge::{ExecutorEvent, ExecutorResponse};
+    use crate::executor::run_stream::{Direction, ExportFormat, RunStreams};
+    use crate::executor::sessions::DEFAULT_SESSION;
+    use serde_json::json;
+
+    const T0: f64 = 1_700_000_000.0;
+
+    fn streams(dir: &ScratchDir) -> RunStreams {
+        RunStreams::new(dir.join("streams"), dir.join("logs"))
+    }
+
+    fn event(name: &str, sequence: u32, timestamp: f64, data: serde_json::Value) -> BridgeMessage {
+        BridgeMessage::Event(ExecutorEvent {
+            event: name.to_string(),
+            timestamp,
+            sequence,
+            data,
+        })
+    }
+
+    fn response(id: &str, success: bool) -> BridgeMessage {
+        BridgeMessage::Response(ExecutorResponse {
+            id: id.to_string(),
+            success,
+            data: None,
+            error: None,
+        })
+    }
+
+    /// A run that is stopped halfway, with the stop's response arriving
+    /// after completion.
+    fn record_run(streams: &RunStreams) -> String {
+        streams.record_command_at(
+            DEFAULT_SESSION,
+            "c1",
+            "start",
+            Some(&json!({ "workflow_id": "w1" })),
+            T0,
+        );
+        let run_id = streams.current_run(DEFAULT_SESSION).unwrap();
+        streams.record_message_at(DEFAULT_SESSION, &response("c1", true), T0 + 0.01);
+        streams.record_message_at(
+            DEFAULT_SESSION,
+            &event("execution_started", 1, T0 + 0.02, json!({})),
+            T0,
+        );
+        streams.record_command_at(DEFAULT_SESSION, "c2", "stop", None, T0 + 0.5);
+        streams.record_message_at(
+            DEFAULT_SESSION,
+            &event(
+                "execution_completed",
+                2,
+                T0 + 0.6,
+                json!({ "success": false, "message": "stopped, by user" }),
+            ),
+            T0,
+        );
+        streams.record_message_at(DEFAULT_SESSION, &response("c2", true), T0 + 0.7);
+        run_id
+    }
+
+    #[test]
+    fn commands_responses_and_events_are_recorded_in_order() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        let run_id = record_run(&streams);
+        assert_eq!(streams.current_run(DEFAULT_SESSION), None);
+
+        let entries = streams.read(&run_id).unwrap();
+        let names: Vec<(Direction, &str)> = entries
+            .iter()
+            .map(|e| (e.direction, e.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (Direction::Command, "start"),
+                (Direction::Response, "start"),
+                (Direction::Event, "execution_started"),
+                (Direction::Command, "stop"),
+                (Direction::Event, "execution_completed"),
+                (Direction::Response, "stop"),
+            ]
+        );
+        assert!(entries.iter().enumerate().all(|(i, e)| e.index == i as u64));
+        assert_eq!(entries[1].correlation_id.as_deref(), Some("c1"));
+        assert_eq!(entries[4].sequence, Some(2));
+        assert_eq!(entries[4].success, Some(false));
+        assert_eq!(entries[4].elapsed_ms, 600.0);
+        assert_eq!(entries[0].timestamp, "2023-11-14T22:13:20.000Z");
+    }
+
+    #[test]
+    fn nothing_is_recorded_outside_a_run() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        streams.record_command_at(DEFAULT_SESSION, "c1", "status", None, T0);
+        streams.record_message_at(DEFAULT_SESSION, &event("log", 1, T0, json!({})), T0);
+        assert!(streams.list().is_empty());
+
+        let run_id = record_run(&streams);
+        streams.record_message_at(
+            DEFAULT_SESSION,
+            &event("log", 3, T0 + 1.0, json!({})),
+            T0 + 1.0,
+        );
+        streams.record_command_at(DEFAULT_SESSION, "c3", "status", None, T0 + 1.0);
+        assert_eq!(streams.read(&run_id).unwrap().len(), 6);
+        assert_eq!(streams.list()[0].run_id, run_id);
+        // Completed, but still the latest
+        assert_eq!(streams.current_run(DEFAULT_SESSION), None);
+        assert_eq!(streams.latest_run(DEFAULT_SESSION), Some(run_id));
+    }
+
+    #[test]
+    fn each_session_records_its_own_runs() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        streams.record_command_at("s1", "c1", "start", None, T0);
+        let default_run = record_run(&streams);
+        streams.record_message_at("s1", &event("log", 1, T0 + 0.1, json!({})), T0 + 0.1);
+        streams.record_message_at("s1", &response("c1", true), T0 + 0.2);
+        streams.record_message_at("s1", &response("c2", true), T0 + 0.3);
+
+        let run_id = streams.current_run("s1").unwrap();
+        assert_ne!(run_id, default_run);
+        assert_eq!(streams.current_run(DEFAULT_SESSION), None);
+        assert_eq!(
+            streams.latest_run(DEFAULT_SESSION),
+            Some(default_run.clone())
+        );
+        assert_eq!(streams.latest_run("s2"), None);
+
+        let names: Vec<(Direction, String)> = streams
+            .read(&run_id)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.direction, e.name))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (Direction::Command, "start".to_string()),
+                (Direction::Event, "log".to_string()),
+                (Direction::Response, "start".to_string()),
+            ]
+        );
+        assert_eq!(streams.read(&default_run).unwrap().len(), 6);
+        assert_eq!(streams.list().len(), 2);
+    }
+
+    #[test]
+    fn heartbeats_are_left_out() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        streams.record_command_at(DEFAULT_SESSION, "c1", "start", None, T0);
+        streams.record_command_at(DEFAULT_SESSION, "p1", "ping", None, T0 + 5.0);
+        streams.record_message_at(DEFAULT_SESSION, &response("p1", true), T0 + 5.1);
+
+        let run_id = streams.current_run(DEFAULT_SESSION).unwrap();
+        assert_eq!(streams.read(&run_id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn secret_values_are_redacted() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        streams.record_command_at(DEFAULT_SESSION, "c1", "start", None, T0);
+        let params = json!({ "request_id": "r1", "name": "pw", "value": "hunter2" });
+        streams.record_command_at(
+            DEFAULT_SESSION,
+            "c2",
+            "secret_value",
+            Some(&params),
+            T0 + 0.1,
+        );
+
+        let run_id = streams.current_run(DEFAULT_SESSION).unwrap();
+        let entries = streams.read(&run_id).unwrap();
+        assert_eq!(entries[1].data["name"], "pw");
+        assert_eq!(entries[1].data["value"], "[redacted]");
+    }
+
+    #[test]
+    fn exports_jsonl_and_csv() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        let run_id = record_run(&streams);
+        let dir = streams.dir().join("exports");
+
+        let jsonl = dir.join("run.jsonl");
+        assert_eq!(
+            streams
+                .export(&run_id, ExportFormat::Jsonl, &jsonl)
+                .unwrap(),
+            6
+        );
+        let content = std::fs::read_to_string(&jsonl).unwrap();
+        assert_eq!(content.lines().count(), 6);
+
+        let csv = dir.join("run.csv");
+        streams.export(&run_id, ExportFormat::Csv, &csv).unwrap();
+        let content = std::fs::read_to_string(&csv).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("index,timestamp,elapsed_ms,direction,name"));
+        assert!(lines[5].contains(r#""{""message"":""stopped, by user"",""success"":false}""#));
+    }
+
+    #[test]
+    fn event_logs_hold_the_events_as_emitted() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        let run_id = record_run(&streams);
+        let dest = streams.dir().join("exports").join("events.jsonl");
+        assert!(streams.export_log(&run_id, &dest).unwrap() > 0);
+
+        let content = std::fs::read_to_string(&dest).unwrap();
+        let events: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let names: Vec<&str> = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["execution_started", "execution_completed"]);
+        assert_eq!(events[1]["sequence"], 2);
+    }
+
+    #[test]
+    fn unknown_and_unsafe_run_ids_are_rejected() {
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        assert!(streams.read("20240101-000000-abcdef12").is_err());
+        assert!(streams.read("../settings").is_err());
+        assert!(streams
+            .export_log("../settings", &streams.dir().join("out"))
+            .is_err());
+        assert!(streams.artifacts_dir("../settings").is_err());
+        assert!(ExportFormat::parse("xlsx").is_err());
+    }
+
+    #[test]
+    fn failed_actions_are_recognized() {
+        use crate::executor::artifacts::is_failed_action;
+
+        let action = |data| ExecutorEvent {
+            event: "action_completed".to_string(),
+            timestamp: T0,
+            sequence: 3,
+            data,
+        };
+        assert!(is_failed_action(&action(json!({ "success": false }))));
+        assert!(!is_failed_action(&action(json!({ "success": true }))));
+        assert!(!is_failed_action(&action(json!({}))));
+
+        let dir = ScratchDir::new("streams");
+        let streams = streams(&dir);
+        let run_id = record_run(&streams);
+        let dir = streams.artifacts_dir(&run_id).unwrap();
+        assert_eq!(
+            dir.file_name().unwrap(),
+            format!("{}-artifacts", run_id).as_str()
+        );
+    }
+}
+
+mod response_correlation {
+    use crate::executor::python_bridge::{ExecutorResponse, PendingResponses};
+    use std::time::Duration;
+
+    fn response(id: &str) -> ExecutorResponse {
+        ExecutorResponse {
+            id: id.to_string(),
+            success: true,
+            data: Some(serde_json::json!({ "state": "idle" })),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn responses_reach_the_command_with_their_id() {
+        let responses = PendingResponses::default();
+        let status = responses.expect("c1", "status");
+        let other = responses.expect("c2", "pause");
+
+        assert!(responses.resolve(&response("c1")));
+        let reply = status.wait(Duration::from_secs(1)).unwrap();
+        assert_eq!(reply.data.unwrap()["state"], "idle");
+        assert_eq!(responses.len(), 1);
+        drop(other);
+    }
+
+    #[test]
+    fn responses_nobody_waits_for_are_ignored() {
+        let responses = PendingResponses::default();
+        assert!(!responses.resolve(&response("unknown")));
+    }
+
+    #[test]
+    fn responses_from_another_thread_are_awaited() {
+        let responses = PendingResponses::default();
+        let pending = responses.expect("c1", "status");
+        let resolver = responses.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            resolver.resolve(&response("c1"));
+        });
+        assert!(pending.wait(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn waiting_times_out_and_forgets_the_command() {
+        let responses = PendingResponses::default();
+        let pending = responses.expect("c1", "status");
+        let err = pending.wait(Duration::from_millis(10)).unwrap_err();
+        assert!(err.contains("'status'"), "{}", err);
+        assert_eq!(responses.len(), 0);
+        assert!(!responses.resolve(&response("c1")));
+    }
+
+    #[test]
+    fn stopping_the_executor_wakes_waiters() {
+        let responses = PendingResponses::default();
+        let pending = responses.expect("c1", "start");
+        responses.abandon_all();
+        let err = pending.wait(Duration::from_secs(5)).unwrap_err();
+        assert!(err.contains("stopped"), "{}", err);
+    }
+}
+
+mod executor_watchdog {
+    use crate::executor::watchdog::{Heartbeat, MAX_MISSED_HEARTBEATS};
+
+    #[test]
+    fn consecutive_misses_mark_the_process_hung() {
+        let mut heartbeat = Heartbeat::default();
+        for _ in 1..MAX_MISSED_HEARTBEATS {
+            assert!(!heartbeat.missed());
+        }
+        assert!(heartbeat.missed());
+        assert_eq!(heartbeat.missed_count(), MAX_MISSED_HEARTBEATS);
+    }
+
+    #[test]
+    fn an_answer_resets_the_count() {
+        let mut heartbeat = Heartbeat::default();
+        for _ in 1..MAX_MISSED_HEARTBEATS {
+            heartbeat.missed();
+        }
+        heartbeat.answered();
+        assert_eq!(heartbeat.missed_count(), 0);
+        assert!(!heartbeat.missed());
+    }
+}
+
+mod executor_restart {
+    use crate::executor::restart::{RestartBudget, RestartPolicy, RestartSettings, STABLE_UPTIME};
+    use crate::executor::watchdog::CrashReport;
+    use std::time::{Duration, Instant};
+
+    fn report(exit_code: Option<i32>) -> CrashReport {
+        CrashReport {
+            session_id: "default".to_string(),
+            reason: "Executor process exited".to_string(),
+            exit_code,
+            stderr_tail: Vec::new(),
+            detected_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn policies_decide_which_exits_restart() {
+        assert!(!RestartPolicy::Never.applies_to(&report(Some(1))));
+        assert!(RestartPolicy::OnCrash.applies_to(&report(Some(1))));
+        assert!(RestartPolicy::OnCrash.applies_to(&report(None)));
+        assert!(!RestartPolicy::OnCrash.applies_to(&report(Some(0))));
+        assert!(RestartPolicy::Always.applies_to(&report(Some(0))));
+    }
+
+    #[test]
+    fn delays_double_up_to_the_maximum() {
+        let settings = RestartSettings {
+            initial_delay_ms: 500,
+            max_delay_ms: 3000,
+            ..Default::default()
+        };
+        let delays: Vec<u128> = (1..=5).map(|a| settings.delay(a).as_millis()).collect();
+        assert_eq!(delays, [500, 1000, 2000, 3000, 3000]);
+        assert_eq!(settings.delay(100).as_millis(), 3000);
+    }
+
+    #[test]
+    fn attempts_run_out_until_the_executor_is_stable() {
+        let settings = RestartSettings {
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let mut budget = RestartBudget::default();
+        let start = Instant::now();
+        assert_eq!(budget.next_attempt(&settings, start), Some(1));
+        assert_eq!(
+            budget.next_attempt(&settings, start + Duration::from_secs(5)),
+            Some(2)
+        );
+        assert_eq!(
+            budget.next_attempt(&settings, start + Duration::from_secs(10)),
+            None
+        );
+
+        let later = start + Duration::from_secs(5) + STABLE_UPTIME;
+        assert_eq!(budget.next_attempt(&settings, later), Some(1));
+    }
+
+    #[test]
+    fn policy_names_match_the_settings_file() {
+        let settings: RestartSettings =
+            serde_json::from_str(r#"{ "policy": "on_crash" }"#).unwrap();
+        assert_eq!(settings.policy, RestartPolicy::OnCrash);
+        assert_eq!(
+            settings.max_attempts,
+            RestartSettings::default().max_attempts
+        );
+    }
+}
+
+mod bridge_scripts {
+    use crate::executor::environment::bridge_dirs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn override_replaces_the_bundled_scripts() {
+        let dirs = bridge_dirs(
+            Some(PathBuf::from("/custom/bridge")),
+            Some(PathBuf::from("/app/resources/python-bridge")),
+        );
+        assert_eq!(dirs, [PathBuf::from("/custom/bridge")]);
+    }
+
+    #[test]
+    fn bundled_scripts_come_before_the_checkout() {
+        let bundled = PathBuf::from("/app/resources/python-bridge");
+        let dirs = bridge_dirs(None, Some(bundled.clone()));
+        assert_eq!(dirs[0], bundled);
+        // Tests are debug builds, which also search the source tree
+        assert!(dirs[1].join("qontinui_bridge.py").exists());
+    }
+}
+
+mod python_provisioning {
+    use crate::executor::provision::{parse_version, standalone_url, STANDALONE_PYTHON};
+
+    #[test]
+    fn versions_are_read_from_python_output() {
+        assert_eq!(parse_version("Python 3.12.1\n"), Some((3, 12)));
+        assert_eq!(parse_version("Python 3.13.0rc2"), Some((3, 13)));
+        assert_eq!(parse_version("Python 2.7.18"), Some((2, 7)));
+        assert_eq!(parse_version("python: command not found"), None);
+        assert!(parse_version("Python 3.9.2").unwrap() < (3, 12));
+    }
+
+    #[test]
+    fn downloads_exist_for_supported_platforms_only() {
+        let url = standalone_url("linux", "x86_64").unwrap();
+        assert!(url.contains(STANDALONE_PYTHON));
+        assert!(url.ends_with("x86_64-unknown-linux-gnu-install_only.tar.gz"));
+        assert!(standalone_url("macos", "aarch64")
+            .unwrap()
+            .contains("aarch64-apple-darwin"));
+        assert!(standalone_url("windows", "x86").is_none());
+        assert!(standalone_url("freebsd", "x86_64").is_none());
+    }
+}
+
+mod python_interpreters {
+    use super::ScratchDir;
+    use crate::executor::interpreters::{dedupe, parse_probe, InterpreterSource};
+    use std::path::PathBuf;
+
+    #[test]
+    fn probe_output_gives_version_and_qontinui() {
+        assert_eq!(
+            parse_probe("3.12.4\nTrue\n"),
+            Some(("3.12.4".to_string(), true))
+        );
+        assert_eq!(
+            parse_probe("3.11.9\r\nFalse\r\n"),
+            Some(("3.11.9".to_string(), false))
+        );
+        assert_eq!(parse_probe("3.12.4\n"), None);
+        assert_eq!(
+            parse_probe("Traceback (most recent call last):\n  ..."),
+            None
+        );
+    }
+
+    #[test]
+    fn the_same_interpreter_is_listed_once() {
+        let dir = ScratchDir::new("interp");
+        let python = dir.join("python3");
+        std::fs::write(&python, "").unwrap();
+
+        let found = dedupe(vec![
+            (python.clone(), InterpreterSource::Path),
+            (dir.join(".").join("python3"), InterpreterSource::Common),
+            (PathBuf::from("/missing/python"), InterpreterSource::Pyenv),
+        ]);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], (python, InterpreterSource::Path));
+    }
+}
+
+mod log_console {
+    use super::ScratchDir;
+    use crate::commands::logs::{log_files, read_tail, take_lines};
+
+    #[test]
+    fn tail_reads_the_last_lines_across_blocks() {
+        let dir = ScratchDir::new("logs");
+        let path = dir.join("qontinui-runner.log.2026-01-02");
+        // Long enough to need more than one block
+        let content: String = (0..5000)
+            .map(|i| format!("line {} {}\n", i, "x".repeat(40)))
+            .collect();
+        std::fs::write(&path, &content).unwrap();
+        std::fs::write(dir.join("qontinui-runner.log.2026-01-01"), "old\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let (lines, len) = read_tail(&path, 3000).unwrap();
+        assert_eq!(len, content.len() as u64);
+        assert_eq!(lines.len(), 3000);
+        assert!(lines[0].starts_with("line 2000 "));
+        assert!(lines[2999].starts_with("line 4999 "));
+
+        let names: Vec<String> = log_files(&dir).into_iter().map(|f| f.name).collect();
+        assert_eq!(
+            names,
+            [
+                "qontinui-runner.log.2026-01-02",
+                "qontinui-runner.log.2026-01-01"
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_lines_wait_for_the_rest() {
+        let mut buffer = "first\nsecond\nthi".to_string();
+        assert_eq!(take_lines(&mut buffer), ["first", "second"]);
+        assert_eq!(buffer, "thi");
+        assert!(take_lines(&mut buffer).is_empty());
+
+        buffer.push_str("rd\r\n");
+        assert_eq!(take_lines(&mut buffer), ["third"]);
+        assert!(buffer.is_empty());
+    }
+}
+
+mod log_settings {
+    use super::ScratchDir;
+    use crate::logging::{parse_level, prune_logs, LogSettings};
+    use std::time::{Duration, SystemTime};
+    use tracing::Level;
+
+    #[test]
+    fn levels_and_limits_are_validated() {
+        assert_eq!(parse_level("DEBUG"), Ok(Level::DEBUG));
+        assert_eq!(parse_level(" warn "), Ok(Level::WARN));
+        assert!(parse_level("verbose").is_err());
+
+        let settings: LogSettings = serde_json::from_str(r#"{ "level": "trace" }"#).unwrap();
+        assert_eq!(settings.validate(), Ok(Level::TRACE));
+        assert!(settings.log_to_file);
+        let no_retention = LogSettings {
+            retention_days: 0,
+            ..LogSettings::default()
+        };
+        assert!(no_retention.validate().is_err());
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_files_within_limits() {
+        let dir = ScratchDir::new("prune");
+        let megabyte = vec![b'x'; 1024 * 1024];
+        for day in 1..=4 {
+            let name = format!("qontinui-runner.log.2026-01-0{}", day);
+            std::fs::write(dir.join(name), &megabyte).unwrap();
+        }
+        std::fs::write(dir.join("other.txt"), &megabyte).unwrap();
+
+        let settings = LogSettings {
+            max_total_mb: 2,
+            ..LogSettings::default()
+        };
+        assert_eq!(prune_logs(&dir, &settings, SystemTime::now()), 2);
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "other.txt",
+                "qontinui-runner.log.2026-01-03",
+                "qontinui-runner.log.2026-01-04"
+            ]
+        );
+
+        // Everything is past retention a month later, except the current file
+        let later = SystemTime::now() + Duration::from_secs(30 * 24 * 60 * 60);
+        assert_eq!(prune_logs(&dir, &settings, later), 1);
+        assert!(dir.join("qontinui-runner.log.2026-01-04").exists());
+    }
+}
+
+mod bridge_log {
+    use crate::executor::event_bus::{Severity, StderrSeverity};
+
+    #[test]
+    fn stderr_lines_get_a_severity() {
+        assert_eq!(
+            Severity::of_stderr("DEBUG:qontinui:matched"),
+            Severity::Debug
+        );
+        assert_eq!(Severity::of_stderr("INFO:root:ready"), Severity::Info);
+        assert_eq!(Severity::of_stderr("ERROR:bridge:failed"), Severity::Error);
+        assert_eq!(
+            Severity::of_stderr("CRITICAL: out of memory"),
+            Severity::Error
+        );
+        assert_eq!(
+            Severity::of_stderr("ValueError: invalid literal for int()"),
+            Severity::Error
+        );
+        assert_eq!(
+            Severity::of_stderr("DeprecationWarning: use find_all"),
+            Severity::Warning
+        );
+        assert_eq!(Severity::of_stderr("loading model..."), Severity::Warning);
+    }
+
+    #[test]
+    fn tracebacks_stay_errors_to_the_end() {
+        let mut severity = StderrSeverity::default();
+        let lines = [
+            "Traceback (most recent call last):",
+            "  File \"qontinui_executor.py\", line 12, in run",
+            "    do_thing()",
+            "KeyError: 'state'",
+            "continuing with next action",
+        ];
+        let severities: Vec<Severity> = lines.iter().map(|l| severity.classify(l)).collect();
+        assert_eq!(
+            severities,
+            [
+                Severity::Error,
+                Severity::Error,
+                Severity::Error,
+                Severity::Error,
+                Severity::Warning
+            ]
+        );
+    }
+}
+
+mod dry_run {
+    use crate::config::dry_run::walk_through;
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::images::ImageSource;
+    use crate::config::{ConfigLoader, QontinuiConfig};
+    use serde_json::json;
+
+    fn config() -> QontinuiConfig {
+        let fixture = fixtures::generate(&FixtureSpec {
+            images: 1,
+            ..Default::default()
+        });
+        let png = fixture["images"][0]["data"].clone();
+        let json = json!({
+            "version": "1.0.0",
+            "metadata": { "name": "Dry run" },
+            "images": [{ "id": "ok", "data": png }, { "id": "gone", "data": png }],
+            "workflows": [
+                {
+                    "id": "main",
+                    "name": "Main",
+                    "actions": [
+                        { "id": "find", "type": "FIND", "config": { "target": { "imageId": "ok" } } },
+                        { "id": "sub", "type": "RUN_WORKFLOW", "config": { "workflowId": "helper" } },
+                    ],
+                },
+                {
+                    "id": "helper",
+                    "actions": [
+                        { "id": "click", "type": "CLICK", "config": { "imageId": "gone" } },
+                        { "id": "loop", "type": "RUN_WORKFLOW", "config": { "workflowId": "main" } },
+                    ],
+                },
+            ],
+            "states": [
+                { "id": "start", "isInitial": true, "identifyingImages": ["ok"] },
+                { "id": "next" },
+                { "id": "island" },
+            ],
+            "transitions": [
+                { "id": "go", "workflows": ["main"], "fromState": "start", "toState": "next" },
+            ],
+            "categories": [],
+        });
+        ConfigLoader::load_from_string(&json.to_string()).unwrap()
+    }
+
+    #[test]
+    fn a_sound_workflow_passes() {
+        let mut config = config();
+        // Without the self-call, everything but the island state checks out
+        config.workflows[1].actions.truncate(1);
+        let report = walk_through(&config, "main").unwrap();
+
+        let actions: Vec<&str> = report.steps.iter().map(|s| s.action_id.as_str()).collect();
+        assert_eq!(actions, ["find", "sub", "click"]);
+        assert_eq!(report.steps[2].workflow_path, ["main", "helper"]);
+        assert_eq!(report.transitions, ["go"]);
+        assert_eq!(report.states, ["start", "next"]);
+        assert_eq!(report.images_checked, 2);
+        assert!(report.missing_assets.is_empty());
+        assert_eq!(report.unreachable_states, ["island"]);
+        assert!(report.ok);
+    }
+
+    #[test]
+    fn broken_images_and_cycles_are_reported() {
+        let mut config = config();
+        config.image_store.set_source(
+            "gone",
+            Some(ImageSource::File("/nonexistent/gone.png".into())),
+        );
+        let report = walk_through(&config, "main").unwrap();
+
+        assert_eq!(report.missing_assets.len(), 1);
+        assert_eq!(report.missing_assets[0].image_id, "gone");
+        assert_eq!(report.missing_assets[0].referenced_by, ["action 'click'"]);
+        assert!(report.steps[3].issues[0].contains("calls itself"));
+        assert!(!report.ok);
+
+        assert!(walk_through(&config, "missing").is_err());
+    }
+}
+
+mod typed_config {
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::{ConfigLoader, QontinuiConfig};
+    use serde_json::{json, Value};
+
+    fn fixture() -> Value {
+        fixtures::generate(&FixtureSpec {
+            states: 3,
+            transitions: 2,
+            workflows: 2,
+            images: 1,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn unknown_fields_round_trip() {
+        let mut json = fixture();
+        json["states"][1]["color"] = json!("#ff0000");
+        let config = ConfigLoader::load_from_string(&json.to_string()).unwrap();
+
+        assert_eq!(config.states[1].extra["color"], "#ff0000");
+        assert!(config.states[0].is_initial);
+        assert_eq!(config.workflows[0].actions[0].action_type, "FIND");
+        assert_eq!(
+            config.workflows[0].actions[0].image(),
+            Some(("/config/target/imageId", "image-0"))
+        );
+
+        let written: Value = serde_json::from_str(&config.to_file_json().unwrap()).unwrap();
+        assert_eq!(written["states"], json["states"]);
+        assert_eq!(written["transitions"], json["transitions"]);
+        assert_eq!(written["workflows"], json["workflows"]);
+    }
+
+    #[test]
+    fn mistyped_entries_are_named() {
+        let mut json = fixture();
+        json["workflows"][1]["actions"][0]["type"] = json!(7);
+        let error = QontinuiConfig::from_value(&json).unwrap_err().to_string();
+        assert!(error.starts_with("workflows[1].actions[0]: "), "{}", error);
+
+        let mut json = fixture();
+        json["states"][2]["identifyingImages"] = json!("image-0");
+        let error = ConfigLoader::load_from_string(&json.to_string()).unwrap_err();
+        assert!(error.contains("states[2]: invalid type"), "{}", error);
+    }
+
+    #[test]
+    fn broken_references_report_their_path() {
+        let mut json = fixture();
+        json["transitions"][1]["toState"] = json!("nowhere");
+        json["transitions"][0]["workflows"] = json!(["workflow-0", "gone"]);
+        json["workflows"][1]["actions"][2]["config"] = json!({ "workflowId": "gone" });
+        json["states"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "name": "No id" }));
+        let error = ConfigLoader::load_from_string(&json.to_string()).unwrap_err();
+
+        for expected in [
+            "states[3].id: is required",
+            "transitions[1].toState: references unknown state 'nowhere'",
+            "transitions[0].workflows[1]: references unknown workflow 'gone'",
+            "workflows[1].actions[2].config.workflowId: references unknown workflow 'gone'",
+        ] {
+            assert!(
+                error.contains(expected),
+                "{} missing from {}",
+                expected,
+                error
+            );
+        }
+    }
+}
+
+mod config_migration {
+    use crate::config::migration::{migrate, CURRENT_VERSION};
+    use crate::config::ConfigLoader;
+    use serde_json::{json, Value};
+    use std::path::Path;
+
+    fn example(name: &str) -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn processes_become_workflows() {
+        let mut config = example("demo-config.json");
+        let report = migrate(&mut config).unwrap();
+
+        assert_eq!(report.from_version, "1.0.0");
+        assert_eq!(report.to_version, CURRENT_VERSION);
+        assert!(config.get("processes").is_none());
+        assert_eq!(
+            config["transitions"][0]["workflows"],
+            json!(["process-demo-1"])
+        );
+        assert_eq!(config["categories"], json!([]));
+        assert!(report.changes[0].starts_with("Renamed processes to workflows"));
+
+        let loaded = ConfigLoader::load_from_string(&config.to_string()).unwrap();
+        assert_eq!(loaded.workflows[0].id, "process-demo-1");
+        assert_eq!(loaded.transitions[0].workflows, ["process-demo-1"]);
+    }
+
+    #[test]
+    fn steps_become_actions() {
+        let original = example("examples/notepad_automation.json");
+        let mut config = original.clone();
+        let report = migrate(&mut config).unwrap();
+        assert_eq!(report.from_version, "1.0");
+        assert_eq!(config["workflows"][0]["actions"][8]["type"], "KEY");
+
+        // Loading migrates too
+        let loaded = ConfigLoader::load_from_string(&original.to_string()).unwrap();
+        assert_eq!(loaded.migrated_from.as_deref(), Some("1.0"));
+        let action = &loaded.workflows[0].actions[0];
+        assert_eq!(action.id, "type_in_notepad-step-1");
+        assert_eq!(action.action_type, "KEY");
+        assert_eq!(action.config, json!({ "key": "win+r" }));
+        assert_eq!(action.extra["description"], "Open Run dialog");
+        assert!(loaded.states[0].extra.get("images").is_none());
+    }
+
+    #[test]
+    fn current_configs_are_untouched() {
+        let original = example("examples/config-real-mode.json");
+        let mut config = original.clone();
+        let report = migrate(&mut config).unwrap();
+
+        assert!(!report.migrated());
+        assert_eq!(config, original);
+        let loaded = ConfigLoader::load_from_string(&config.to_string()).unwrap();
+        assert!(loaded.migrated_from.is_none());
+    }
+}
+
+mod config_diagnostics {
+    use super::ScratchDir;
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::validation::Severity;
+    use crate::config::ConfigLoader;
+    use serde_json::{json, Value};
+    use std::path::{Path, PathBuf};
+
+    fn write_config(dir: &Path, config: &Value) -> PathBuf {
+        let path = dir.join("config.json");
+        std::fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn problems_point_at_their_element() {
+        let mut config = fixtures::generate(&FixtureSpec {
+            states: 3,
+            transitions: 2,
+            workflows: 1,
+            images: 2,
+            ..Default::default()
+        });
+        config["transitions"][1]["toState"] = json!("state-11");
+        config["images"][1]["data"] = json!("data:image/png;base64,%%%%");
+        config["images"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "id": "spare", "path": "spare.png" }));
+        let dir = ScratchDir::new("diag");
+        let path = write_config(&dir, &config);
+        let diagnostics = ConfigLoader::diagnose_file(&path).unwrap();
+
+        let at = |pointer: &str| {
+            diagnostics
+                .iter()
+                .find(|d| d.pointer == pointer)
+                .unwrap_or_else(|| panic!("nothing at {} in {:?}", pointer, diagnostics))
+        };
+        let unknown = at("/transitions/1/toState");
+        assert_eq!(unknown.severity, Severity::Error);
+        assert_eq!(unknown.message, "references unknown state 'state-11'");
+        assert_eq!(
+            unknown.suggestion.as_deref(),
+            Some("Did you mean 'state-1'?")
+        );
+        assert_eq!(
+            unknown.to_string(),
+            "transitions[1].toState: references unknown state 'state-11'"
+        );
+        assert_eq!(at("/images/1").severity, Severity::Error);
+        let spare = diagnostics
+            .iter()
+            .filter(|d| d.pointer == "/images/2")
+            .map(|d| d.severity)
+            .collect::<Vec<_>>();
+        assert_eq!(spare, [Severity::Warning, Severity::Error]);
+    }
+
+    #[test]
+    fn parse_failures_are_reported_not_returned() {
+        let dir = ScratchDir::new("diag");
+        let path = write_config(
+            &dir,
+            &json!({ "version": "2.0.0", "states": [{ "id": 4 }] }),
+        );
+        let diagnostics = ConfigLoader::diagnose_file(&path).unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+
+        assert!(ConfigLoader::diagnose_file("/nonexistent/config.json").is_err());
+    }
+}
+
+mod config_bundles {
+    use super::ScratchDir;
+    use crate::config::bundle;
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::images::ImageSource;
+    use crate::config::ConfigLoader;
+    use serde_json::json;
+    use std::io::Write;
+    use std::path::Path;
+    use zip::write::SimpleFileOptions;
+
+    fn write_bundle(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// A config whose two images live in the bundle's `images/`, one
+    /// referenced relative to the bundle and one by a path on another machine.
+    fn bundle_files() -> (String, Vec<u8>) {
+        let mut config = fixtures::generate(&FixtureSpec {
+            images: 2,
+            ..Default::default()
+        });
+        let png = crate::config::ConfigLoader::load_from_string(&config.to_string())
+            .unwrap()
+            .image_store
+            .load("image-0")
+            .unwrap();
+        config["images"][0] = json!({ "id": "image-0", "path": "images/a.png" });
+        config["images"][1] = json!({ "id": "image-1", "path": "C:\\Users\\me\\shots\\b.png" });
+        (config.to_string(), png)
+    }
+
+    #[test]
+    fn bundles_load_with_their_images() {
+        let dir = ScratchDir::new("bundle");
+        let (config, png) = bundle_files();
+        let path = dir.join("automation.qontinui");
+        write_bundle(
+            &path,
+            &[
+                ("config.json", config.as_bytes()),
+                ("notes.json", b"{}"),
+                ("images/a.png", &png),
+                ("images/b.png", &png),
+            ],
+        );
+        let cache = dir.join("cache");
+
+        let loaded = ConfigLoader::load_bundle(&path, &cache).unwrap();
+        let unpacked = loaded.bundle_dir.clone().unwrap();
+        assert!(unpacked.starts_with(&cache));
+        assert_eq!(
+            loaded.image_store.source("image-1"),
+            Some(&ImageSource::File(unpacked.join("images").join("b.png")))
+        );
+        assert_eq!(
+            loaded.images[0].extra["path"],
+            unpacked.join("images/a.png").to_string_lossy().as_ref()
+        );
+        assert_eq!(loaded.image_store.load("image-1").unwrap(), png);
+
+        // The same bundle is unpacked once
+        std::fs::remove_file(unpacked.join("notes.json")).unwrap();
+        let again = ConfigLoader::load_bundle(&path, &cache).unwrap();
+        assert_eq!(again.bundle_dir, Some(unpacked.clone()));
+        assert!(!unpacked.join("notes.json").exists());
+    }
+
+    #[test]
+    fn unusable_bundles_are_rejected() {
+        let dir = ScratchDir::new("bundle");
+        let cache = dir.join("cache");
+        assert!(bundle::is_bundle(Path::new("a/b.ZIP")));
+        assert!(!bundle::is_bundle(Path::new("a/b.json")));
+
+        let ambiguous = dir.join("ambiguous.zip");
+        write_bundle(&ambiguous, &[("a.json", b"{}"), ("b.json", b"{}")]);
+        let error = bundle::unpack(&ambiguous, &cache).unwrap_err();
+        assert!(error.contains("config.json"), "{}", error);
+
+        let escaping = dir.join("escaping.zip");
+        write_bundle(&escaping, &[("../config.json", b"{}")]);
+        assert!(bundle::unpack(&escaping, &cache).is_err());
+        assert!(!dir.join("config.json").exists());
+
+        let not_zip = dir.join("plain.zip");
+        std::fs::write(&not_zip, "{}").unwrap();
+        assert!(bundle::unpack(&not_zip, &cache).is_err());
+    }
+}
+
+mod config_formats {
+    use super::ScratchDir;
+    use crate::config::loader::ConfigFormat;
+    use crate::config::{ConfigLoader, QontinuiConfig};
+    use serde_json::json;
+    use std::path::{Path, PathBuf};
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn expected() -> QontinuiConfig {
+        let config = json!({
+            "version": "2.0.0",
+            "metadata": { "name": "Login", "tags": ["smoke", "auth"] },
+            "states": [
+                { "id": "home", "name": "Home", "isInitial": true, "identifyingImages": [] },
+                { "id": "done", "identifyingImages": [] }
+            ],
+            "transitions": [
+                { "id": "go", "fromState": "home", "toState": "done", "workflows": ["login"] }
+            ],
+            "workflows": [{
+                "id": "login",
+                "name": "Log in",
+                "actions": [
+                    { "id": "type", "type": "TYPE", "config": { "text": "user: admin # not a comment\n" } },
+                    { "id": "wait", "type": "WAIT", "config": { "duration": 1.5, "retries": 3 } }
+                ]
+            }],
+            "images": [],
+            "categories": ["Main"]
+        });
+        ConfigLoader::load_from_string(&config.to_string()).unwrap()
+    }
+
+    #[test]
+    fn yaml_and_toml_load_like_json() {
+        let dir = ScratchDir::new("formats");
+        let yaml = write(
+            &dir,
+            "login.yaml",
+            r#"# Logs in as the admin user
+version: "2.0.0"
+metadata:
+  name: Login
+  tags: [smoke, auth]
+states:
+  - id: home
+    name: Home
+    isInitial: true   # where every run starts
+    identifyingImages: []
+  - id: done
+    identifyingImages: []
+transitions:
+- {id: go, fromState: home, toState: done, workflows: [login]}
+workflows:
+  - id: login
+    name: Log in
+    actions:
+      - id: type
+        type: TYPE
+        config:
+          text: |
+            user: admin # not a comment
+      - id: wait
+        type: WAIT
+        config: { duration: 1.5, retries: 3 }
+images: []
+categories:
+  - 'Main'
+"#,
+        );
+        let toml = write(
+            &dir,
+            "login.toml",
+            r#"# Logs in as the admin user
+version = "2.0.0"
+images = []
+categories = ["Main"]
+
+[metadata]
+name = "Login"
+tags = ["smoke", "auth"]
+
+[[states]]
+id = "home"
+name = "Home"
+isInitial = true
+identifyingImages = []
+
+[[states]]
+id = "done"
+identifyingImages = []
+
+[[transitions]]
+id = "go"
+fromState = "home"
+toState = "done"
+workflows = ["login"]
+
+[[workflows]]
+id = "login"
+name = "Log in"
+
+[[workflows.actions]]
+id = "type"
+type = "TYPE"
+config = { text = "user: admin # not a comment\n" }
+
+[[workflows.actions]]
+id = "wait"
+type = "WAIT"
+config = { duration = 1.5, retries = 3 }
+"#,
+        );
+        let expected = expected().to_file_json().unwrap();
+
+        for path in [yaml, toml] {
+            let config = ConfigLoader::load_from_file(&path).unwrap();
+            assert_eq!(config.source_format, ConfigFormat::of(&path));
+            assert_eq!(config.to_file_json().unwrap(), expected, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn broken_yaml_is_reported() {
+        let dir = ScratchDir::new("formats");
+        let path = write(&dir, "broken.yml", "version: [\n");
+        let error = ConfigLoader::load_from_file(&path).unwrap_err();
+        assert!(error.contains("YAML"), "{}", error);
+    }
+}
+
+mod config_substitution {
+    use super::ScratchDir;
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::substitution::{expand_with, Placeholder};
+    use crate::config::{ConfigLoader, QontinuiConfig};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn env_placeholders_expand_for_the_executor_only() {
+        std::env::set_var("QONTINUI_TEST_SHOTS", "/home/ci/shots");
+        let mut config = fixtures::generate(&FixtureSpec {
+            workflows: 1,
+            ..Default::default()
+        });
+        config["workflows"][0]["actions"][0]["config"] = json!({
+            "directory": "${QONTINUI_TEST_SHOTS}/login",
+            "note": "costs $${PRICE}",
+        });
+
+        let loaded = ConfigLoader::load_from_string(&config.to_string()).unwrap();
+        let action = &loaded.workflows[0].actions[0];
+        assert_eq!(action.config["directory"], "/home/ci/shots/login");
+        assert_eq!(action.config["note"], "costs ${PRICE}");
+
+        let executor: Value = serde_json::from_str(&loaded.to_executor_json().unwrap()).unwrap();
+        assert_eq!(
+            executor["workflows"][0]["actions"][0]["config"]["directory"],
+            "/home/ci/shots/login"
+        );
+        let saved: Value = serde_json::from_str(&loaded.to_file_json().unwrap()).unwrap();
+        assert_eq!(
+            saved["workflows"][0]["actions"][0]["config"],
+            config["workflows"][0]["actions"][0]["config"]
+        );
+        let section = loaded.section("workflows").unwrap();
+        assert_eq!(
+            section[0]["actions"][0]["config"]["directory"],
+            "${QONTINUI_TEST_SHOTS}/login"
+        );
+    }
+
+    #[test]
+    fn path_mode_isnt_used_for_expanded_files() {
+        std::env::set_var("QONTINUI_TEST_USER", "ci");
+        let dir = ScratchDir::new("placeholders");
+        let mut config = fixtures::generate(&FixtureSpec {
+            workflows: 1,
+            ..Default::default()
+        });
+        let path = dir.join("plain.json");
+        std::fs::write(&path, config.to_string()).unwrap();
+        assert!(ConfigLoader::load_from_file(&path)
+            .unwrap()
+            .readable_as_is());
+
+        config["workflows"][0]["actions"][0]["config"] = json!({ "text": "${QONTINUI_TEST_USER}" });
+        let path = dir.join("placeholders.json");
+        std::fs::write(&path, config.to_string()).unwrap();
+        assert!(!ConfigLoader::load_from_file(&path)
+            .unwrap()
+            .readable_as_is());
+    }
+
+    #[test]
+    fn secret_placeholders_are_left_for_the_executor() {
+        let mut value = json!({
+            "login": { "user": "${secret:app.user}", "password": "${PREFIX}${secret:app.password}" },
+            "literal": "$${secret:app.user} costs $${PRICE}",
+        });
+        let written = value.clone();
+        let mut lookups = Vec::new();
+        let placeholders = expand_with(&mut value, &mut |placeholder| {
+            lookups.push(placeholder.clone());
+            match placeholder {
+                Placeholder::Secret(_) => Ok(None),
+                Placeholder::Env(name) => Ok(Some(format!("{}-", name.to_lowercase()))),
+            }
+        })
+        .unwrap();
+        assert_eq!(value["login"]["user"], "${secret:app.user}");
+        assert_eq!(value["login"]["password"], "prefix-${secret:app.password}");
+        assert_eq!(value["literal"], "$${secret:app.user} costs ${PRICE}");
+        assert_eq!(lookups.len(), 3);
+
+        placeholders.restore(&mut value);
+        assert_eq!(value, written);
+    }
+
+    #[test]
+    fn the_executor_is_sent_secret_names_not_values() {
+        let mut config = fixtures::generate(&FixtureSpec {
+            workflows: 1,
+            ..Default::default()
+        });
+        config["workflows"][0]["actions"][0]["config"] =
+            json!({ "text": "${secret:app.password}" });
+        let config = QontinuiConfig::from_value(&config).unwrap();
+
+        let executor: Value = serde_json::from_str(&config.to_executor_json().unwrap()).unwrap();
+        assert_eq!(
+            executor["workflows"][0]["actions"][0]["config"]["text"],
+            "${secret:app.password}"
+        );
+        assert!(config.secret_names().contains("app.password"));
+    }
+
+    #[test]
+    fn unresolved_placeholders_point_at_their_string() {
+        for (text, expected) in [
+            ("${QONTINUI_TEST_UNSET_VARIABLE}", "isn't set"),
+            ("${not valid}", "isn't a valid placeholder"),
+            ("${OPEN", "unclosed placeholder"),
+            ("${secret:bad name}", "Invalid character"),
+        ] {
+            let mut value = json!({ "states": [{ "name": text }] });
+            let error = expand_with(&mut value, &mut |placeholder| match placeholder {
+                Placeholder::Env(name) => Err(format!("environment variable '{}' isn't set", name)),
+                Placeholder::Secret(_) => Ok(None),
+            })
+            .unwrap_err();
+            assert_eq!(error.pointer, "/states/0/name", "{}", text);
+            assert!(
+                error.message.contains(expected),
+                "{}: {}",
+                text,
+                error.message
+            );
+            assert!(error.suggestion.is_some());
+        }
+    }
+}
+
+mod recent_configurations {
+    use super::ScratchDir;
+    use crate::settings::{SettingsStore, MAX_RECENT};
+    use std::path::PathBuf;
+
+    #[test]
+    fn reuse_moves_a_configuration_to_the_top() {
+        let dir = ScratchDir::new("settings");
+        let path = dir.join("app-settings.json");
+        let store = SettingsStore::open(path.clone());
+        store
+            .add_recent_configuration("login.json", Some("Login".to_string()), None, None)
+            .unwrap();
+        store
+            .add_recent_configuration("checkout.json", None, Some("pay".to_string()), Some(1))
+            .unwrap();
+
+        // A run records the workflow and monitor without losing the name
+        let entry = store
+            .add_recent_configuration("login.json", None, Some("sign-in".to_string()), Some(2))
+            .unwrap();
+        assert_eq!(entry.name.as_deref(), Some("Login"));
+
+        let reopened = SettingsStore::open(path);
+        let recent = reopened.recent_configurations();
+        let paths: Vec<&str> = recent.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, ["login.json", "checkout.json"]);
+        assert_eq!(recent[0].last_workflow_id.as_deref(), Some("sign-in"));
+        assert_eq!(recent[0].last_monitor_index, Some(2));
+
+        // Loading it again keeps what it was last run with
+        let entry = reopened
+            .add_recent_configuration("login.json", None, None, None)
+            .unwrap();
+        assert_eq!(entry.last_workflow_id.as_deref(), Some("sign-in"));
+    }
+
+    #[test]
+    fn the_list_is_capped_and_can_be_cleared() {
+        let dir = ScratchDir::new("settings");
+        let path = dir.join("app-settings.json");
+        let store = SettingsStore::open(path.clone());
+        for i in 0..MAX_RECENT + 3 {
+            store
+                .add_recent_configuration(&format!("config-{}.json", i), None, None, None)
+                .unwrap();
+        }
+        let recent = store.recent_configurations();
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0].path, format!("config-{}.json", MAX_RECENT + 2));
+
+        assert!(store
+            .add_recent_configuration("", None, None, None)
+            .is_err());
+        store.clear_recent_configurations().unwrap();
+        assert!(SettingsStore::open(path).recent_configurations().is_empty());
+    }
+
+    #[test]
+    fn the_http_api_token_is_generated_once() {
+        let dir = ScratchDir::new("settings");
+        let path = dir.join("app-settings.json");
+        let store = SettingsStore::open(path.clone());
+        assert!(!store.http_api().enabled);
+        assert!(store.http_api().token.is_empty());
+
+        let enabled = store.set_http_api(true, Some(48000)).unwrap();
+        assert!(!enabled.token.is_empty());
+        let disabled = store.set_http_api(false, None).unwrap();
+        assert_eq!(disabled.port, 48000);
+        assert_eq!(disabled.token, enabled.token);
+
+        assert!(store.set_http_api(true, Some(0)).is_err());
+        assert_eq!(SettingsStore::open(path).http_api().token, enabled.token);
+    }
+
+    #[test]
+    fn https_needs_a_certificate_and_its_key() {
+        let dir = ScratchDir::new("settings");
+        let store = SettingsStore::open(dir.join("app-settings.json"));
+        assert!(!store.http_api().tls());
+        assert!(store
+            .set_http_api_tls(Some(PathBuf::from("cert.pem")), None)
+            .is_err());
+        let https = store
+            .set_http_api_tls(
+                Some(PathBuf::from("cert.pem")),
+                Some(PathBuf::from("key.pem")),
+            )
+            .unwrap();
+        assert!(https.tls());
+        assert!(!store.set_http_api_tls(None, None).unwrap().tls());
+    }
+}
+
+mod config_changes {
+    use super::ScratchDir;
+    use crate::config::diff::{change_set, ChangeKind};
+    use crate::config::fixtures::{self, FixtureSpec};
+    use crate::config::ConfigLoader;
+    use serde_json::{json, Value};
+    use std::path::{Path, PathBuf};
+
+    fn write(dir: &Path, name: &str, config: &Value) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn changes_are_listed_per_entry_and_field() {
+        let old = fixtures::generate(&FixtureSpec {
+            states: 3,
+            transitions: 2,
+            workflows: 1,
+            images: 2,
+            ..Default::default()
+        });
+        let mut new = old.clone();
+        new["metadata"]["name"] = json!("Renamed");
+        new["states"].as_array_mut().unwrap().remove(2);
+        new["states"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "id": "state-new", "name": "${UNSET_FOR_DIFF}" }));
+        new["transitions"][0]["toState"] = json!("state-new");
+        let actions = new["workflows"][0]["actions"].as_array_mut().unwrap();
+        actions.insert(
+            0,
+            json!({ "id": "inserted", "type": "WAIT", "config": { "duration": 1 } }),
+        );
+        let swapped = new["images"][1]["data"].clone();
+        new["images"][0]["data"] = swapped;
+
+        let dir = ScratchDir::new("diff");
+        let changes = change_set(
+            &ConfigLoader::parse_file(write(&dir, "old.json", &old)).unwrap(),
+            &ConfigLoader::parse_file(write(&dir, "new.json", &new)).unwrap(),
+        )
+        .unwrap();
+
+        assert!(!changes.identical);
+        assert_eq!(changes.top_level.len(), 1);
+        assert_eq!(changes.top_level[0].pointer, "/metadata/name");
+        assert_eq!(changes.top_level[0].after, Some(json!("Renamed")));
+
+        let kinds: Vec<(&str, &str, ChangeKind)> = changes
+            .entries
+            .iter()
+            .map(|entry| (entry.section, entry.id.as_str(), entry.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("states", "state-2", ChangeKind::Removed),
+                ("states", "state-new", ChangeKind::Added),
+                ("transitions", "transition-0", ChangeKind::Modified),
+                ("workflows", "workflow-0", ChangeKind::Modified),
+            ]
+        );
+        assert_eq!(
+            changes.entries[1].fields[0].after.as_ref().unwrap()["name"],
+            "${UNSET_FOR_DIFF}"
+        );
+        assert_eq!(changes.entries[2].fields[0].pointer, "/toState");
+
+        // Actions are matched by id, so only the inserted one shows
+        let workflow = &changes.entries[3].fields;
+        assert_eq!(workflow.len(), 1);
+        assert_eq!(workflow[0].pointer, "/actions/0");
+        assert!(workflow[0].before.is_none());
+
+        assert_eq!(changes.images_swapped.len(), 1);
+        let swap = &changes.images_swapped[0];
+        assert_eq!(swap.id, "image-0");
+        assert_ne!(swap.before_sha256, swap.after_sha256);
+        assert_eq!(changes.counts["states"].added, 1);
+        assert_eq!(changes.counts["images"].modified, 0);
+
+        let same = ConfigLoader::parse_file(write(&dir, "same.json", &old)).unwrap();
+        assert!(change_set(&same, &same).unwrap().identical);
+    }
+}
+
+mod http_events {
+    use crate::http_api::{token_matches, ForwardedEvent};
+    use serde_json::json;
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc12", "abc123"));
+        assert!(!token_matches("abc1234", "abc123"));
+        assert!(!token_matches("", ""));
+    }
+
+    #[test]
+    fn events_carry_their_session_and_run() {
+        let payload = json!({ "session_id": "s1", "event": "log" }).to_string();
+        let latest_run = |session: &str| Some(format!("run-of-{}", session));
+        let event = ForwardedEvent::new("executor-event", &payload, latest_run);
+        assert_eq!(event.session_id, "s1");
+        assert_eq!(event.run_id.as_deref(), Some("run-of-s1"));
+
+        let event = ForwardedEvent::new("queue-progress", r#"{"status":"idle"}"#, latest_run);
+        let message = serde_json::to_value(&event).unwrap();
+        assert_eq!(message["event"], "queue-progress");
+        assert_eq!(message["session_id"], "default");
+        assert_eq!(message["run_id"], "run-of-default");
+        assert_eq!(message["payload"]["status"], "idle");
+    }
+}
+
+mod webhooks {
+    use super::ScratchDir;
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::notifications::RunNotifier;
+    use crate::settings::{SettingsStore, WebhookEvent};
+    use serde_json::json;
+
+    #[test]
+    fn webhooks_are_kept_with_their_events() {
+        let dir = ScratchDir::new("settings");
+        let path = dir.join("app-settings.json");
+        let store = SettingsStore::open(path.clone());
+        let events = vec![
+            WebhookEvent::Failed,
+            WebhookEvent::Started,
+            WebhookEvent::Failed,
+        ];
+        let webhook = store
+            .add_webhook("https://hooks.example.com/alerts", events)
+            .unwrap();
+        assert_eq!(
+            webhook.events,
+            [WebhookEvent::Started, WebhookEvent::Failed]
+        );
+        assert_eq!(SettingsStore::open(path).webhooks()[0].id, webhook.id);
+
+        assert!(store
+            .add_webhook("ftp://example.com", vec![WebhookEvent::Failed])
+            .is_err());
+        assert!(store
+            .add_webhook("https://example.com", Vec::new())
+            .is_err());
+        store.remove_webhook(&webhook.id).unwrap();
+        assert!(store.remove_webhook(&webhook.id).is_err());
+    }
+
+    #[test]
+    fn a_failed_run_reports_its_last_error() {
+        let notifier = RunNotifier::default();
+        let event = |name: &str, data| ExecutorEvent::from_runner(name, data);
+
+        // Nothing to report outside a run
+        assert!(notifier
+            .record_event("s1", &event("execution_completed", json!({})), None, None)
+            .is_none());
+
+        let started = notifier
+            .record_event(
+                "s1",
+                &event("execution_started", json!({ "process_id": "nightly" })),
+                Some("run-1".to_string()),
+                Some("Reports".to_string()),
+            )
+            .unwrap();
+        assert_eq!(started.event, WebhookEvent::Started);
+        assert_eq!(started.workflow_id.as_deref(), Some("nightly"));
+
+        let error = event("error", json!({ "message": "Login button not found" }));
+        assert!(notifier.record_event("s1", &error, None, None).is_none());
+        let failed = notifier
+            .record_event(
+                "s1",
+                &event("execution_completed", json!({ "success": false })),
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(failed.event, WebhookEvent::Failed);
+        assert_eq!(failed.run_id.as_deref(), Some("run-1"));
+        assert_eq!(failed.config_name.as_deref(), Some("Reports"));
+        assert_eq!(failed.last_error.as_deref(), Some("Login button not found"));
+        assert!(failed.text.contains("Login button not found"));
+        assert!(failed.duration_ms.is_some());
+    }
+}
+
+mod hotkeys {
+    use crate::hotkeys::{bindings, HotkeyAction};
+    use crate::settings::HotkeySettings;
+
+    #[test]
+    fn the_default_hotkeys_are_valid() {
+        let actions: Vec<HotkeyAction> = bindings(&HotkeySettings::default())
+            .unwrap()
+            .into_iter()
+            .map(|(action, _)| action)
+            .collect();
+        assert_eq!(actions, [HotkeyAction::Stop, HotkeyAction::Abort]);
+    }
+
+    #[test]
+    fn invalid_and_shared_hotkeys_are_rejected() {
+        let invalid = HotkeySettings {
+            start: Some("Ctrl+Banana".to_string()),
+            ..Default::default()
+        };
+        assert!(bindings(&invalid).is_err());
+
+        // Same keys, written differently
+        let shared = HotkeySettings {
+            start: Some("ctrl+alt+s".to_string()),
+            ..Default::default()
+        };
+        assert!(bindings(&shared).unwrap_err().contains("Start"));
+
+        let blank = HotkeySettings {
+            start: Some(" ".to_string()),
+            stop: None,
+            abort: Some("Ctrl+Alt+Q".to_string()),
+        };
+        assert_eq!(bindings(&blank).unwrap().len(), 1);
+    }
+}
+
+mod process_tree {
+    use crate::executor::process_tree::{descendants, descendants_of, kill_all, TreeProcess};
+
+    #[test]
+    fn descendants_are_found_across_generations() {
+        // poetry (10) runs python (11), which started two helpers, one with
+        // a child of its own; 20 is unrelated
+        let processes = [
+            (1, None),
+            (10, Some(1)),
+            (11, Some(10)),
+            (12, Some(11)),
+            (13, Some(11)),
+            (14, Some(13)),
+            (20, Some(1)),
+        ];
+        assert_eq!(descendants_of(&processes, 10), [11, 12, 13, 14]);
+        assert!(descendants_of(&processes, 14).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reused_process_ids_are_left_alone() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let sleeper = descendants(std::process::id())
+            .into_iter()
+            .find(|process| process.pid == child.id())
+            .unwrap();
+        let reused = TreeProcess {
+            start_time: sleeper.start_time + 1,
+            ..sleeper
+        };
+        assert_eq!(kill_all(&[reused]), 0);
+        assert!(child.try_wait().unwrap().is_none());
+        assert_eq!(kill_all(&[sleeper]), 1);
+        child.wait().unwrap();
+    }
+}
+
+mod executor_shutdown {
+    use crate::executor::shutdown::{ShutdownOutcome, ShutdownSettings};
+
+    #[test]
+    fn missing_timeouts_take_the_defaults() {
+        let settings: ShutdownSettings =
+            serde_json::from_str(r#"{ "graceful_timeout_ms": 500 }"#).unwrap();
+        assert_eq!(settings.graceful_timeout_ms, 500);
+        assert_eq!(
+            settings.terminate_timeout_ms,
+            ShutdownSettings::default().terminate_timeout_ms
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn an_executor_that_ignores_stop_is_terminated_with_its_helpers() {
+        use crate::executor::shutdown::{isolate, shut_down};
+        use std::process::Command;
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 & echo $!; wait"]);
+        command.stdout(std::process::Stdio::piped());
+        isolate(&mut command);
+        let mut child = command.spawn().unwrap();
+        let mut line = String::new();
+        std::io::BufRead::read_line(
+            &mut std::io::BufReader::new(child.stdout.take().unwrap()),
+            &mut line,
+        )
+        .unwrap();
+        let helper = line.trim().to_string();
+
+        let settings = ShutdownSettings {
+            graceful_timeout_ms: 100,
+            terminate_timeout_ms: 2000,
+        };
+        let outcome = shut_down(&mut child, &settings).unwrap();
+        assert_eq!(outcome, ShutdownOutcome::Terminated);
+        assert!(!outcome.is_graceful());
+        // Gone once it has been reaped, which can take a moment
+        let helper_alive = || {
+            Command::new("kill")
+                .args(["-0", &helper])
+                .status()
+                .unwrap()
+                .success()
+        };
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while helper_alive() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        assert!(!helper_alive());
+    }
+}
+
+mod executor_sessions {
+    use crate::executor::protocol::BridgeMessage;
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::executor::sessions::{is_default, SessionTagged, DEFAULT_SESSION};
+    use serde_json::json;
+
+    #[test]
+    fn tagged_messages_keep_their_shape() {
+        let message = BridgeMessage::Event(ExecutorEvent::from_runner(
+            "execution_started",
+            json!({ "workflow_id": "w" }),
+        ));
+        let tagged = serde_json::to_value(SessionTagged {
+            session_id: "s-1",
+            payload: &message,
+        })
+        .unwrap();
+        assert_eq!(tagged["session_id"], "s-1");
+        assert_eq!(tagged["type"], "event");
+        assert_eq!(tagged["event"], "execution_started");
+        assert_eq!(tagged["data"]["workflow_id"], "w");
+
+        // Listeners that parse the message itself still can
+        let parsed: BridgeMessage = serde_json::from_value(tagged.clone()).unwrap();
+        assert!(matches!(parsed, BridgeMessage::Event(e) if e.event == "execution_started"));
+        assert!(!is_default(&tagged));
+    }
+
+    #[test]
+    fn untagged_payloads_belong_to_the_default_session() {
+        assert!(is_default(&json!({ "event": "execution_started" })));
+        assert!(is_default(&json!({ "session_id": DEFAULT_SESSION })));
+        assert!(!is_default(&json!({ "session_id": "other" })));
+    }
+}
+
+mod execution_queue {
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::queue::{EntryStatus, ExecutionQueue, QueueParams};
+    use crate::run_plan::{QueuedRun, RunTracker};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn event(name: &str, data: serde_json::Value) -> ExecutorEvent {
+        ExecutorEvent {
+            event: name.to_string(),
+            timestamp: 0.0,
+            sequence: 1,
+            data,
+        }
+    }
+
+    fn ids(queue: &ExecutionQueue) -> Vec<String> {
+        queue.entries().into_iter().map(|e| e.process_id).collect()
+    }
+
+    #[test]
+    fn one_worker_runs_entries_in_order() {
+        let queue = ExecutionQueue::default();
+        let (first, start) = queue.enqueue("a", QueueParams::default());
+        assert!(start);
+        let (_, start) = queue.enqueue("b", QueueParams::default());
+        assert!(!start);
+        assert_eq!(first.label, "Run a");
+
+        let running = queue.next().unwrap();
+        assert_eq!(running.process_id, "a");
+        assert_eq!(queue.entries()[0].status, EntryStatus::Running);
+
+        // The end of a run that started before ours isn't taken for it
+        queue.record_event(&event("execution_completed", json!({ "success": true })));
+        assert_eq!(queue.wait_for_outcome(Duration::ZERO), None);
+        queue.record_event(&event("execution_started", json!({})));
+        queue.record_event(&event("execution_completed", json!({ "success": false })));
+        assert_eq!(queue.wait_for_outcome(Duration::ZERO), Some(false));
+
+        let progress = queue.finish(Ok(false));
+        assert_eq!(progress.status, "failed");
+        assert_eq!(progress.remaining, 1);
+        assert_eq!(queue.next().unwrap().process_id, "b");
+        assert_eq!(queue.finish(Ok(true)).completed, 1);
+        assert!(queue.next().is_none());
+        // The worker is done, so the next entry needs a new one
+        assert!(queue.enqueue("c", QueueParams::default()).1);
+    }
+
+    #[test]
+    fn reorder_and_cancel() {
+        let queue = ExecutionQueue::default();
+        let entries: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|id| queue.enqueue(id, QueueParams::default()).0.id)
+            .collect();
+        queue.next();
+
+        // The running entry stays first
+        let order = vec![entries[2].clone(), entries[1].clone()];
+        queue.reorder(&order).unwrap();
+        assert_eq!(ids(&queue), ["a", "c", "b"]);
+        assert!(queue.reorder(&order[..1]).is_err());
+        assert_eq!(ids(&queue), ["a", "c", "b"]);
+
+        queue.cancel(&entries[2]).unwrap();
+        assert_eq!(ids(&queue), ["a", "b"]);
+        // Cancelling the running entry only flags it
+        assert_eq!(
+            queue.cancel(&entries[0]).unwrap().status,
+            EntryStatus::Running
+        );
+        assert_eq!(queue.finish(Ok(false)).status, "cancelled");
+        assert!(queue.cancel("missing").is_err());
+    }
+
+    #[test]
+    fn run_plan_follows_the_new_order() {
+        let tracker = RunTracker::default();
+        for id in ["idle", "q1", "q2"] {
+            tracker.enqueue(QueuedRun {
+                task_id: id.to_string(),
+                workflow_id: id.to_string(),
+                label: id.to_string(),
+                status: None,
+                ready_at: None,
+            });
+        }
+        tracker.reorder_queued(&["q2".to_string(), "q1".to_string()]);
+        let plan = tracker.plan(chrono::Local::now());
+        let order: Vec<_> = plan.queued.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(order, ["idle", "q2", "q1"]);
+    }
+}
+
+mod schedules {
+    use super::ScratchDir;
+    use crate::run_plan::{PlanKind, RunTracker};
+    use crate::scheduler::{next_occurrence, ExecutionRecord, Scheduler};
+    use chrono::{Local, TimeZone, Timelike};
+
+    #[test]
+    fn next_occurrence_follows_the_expression() {
+        let at = Local.with_ymd_and_hms(2026, 3, 10, 1, 30, 0).unwrap();
+        let next = next_occurrence("0 2 * * *", at).unwrap();
+        assert_eq!((next.hour(), next.minute()), (2, 0));
+        assert_eq!(next.date_naive(), at.date_naive());
+        // Strictly after, so a run on the minute isn't found again
+        let again = next_occurrence("0 2 * * *", next).unwrap();
+        assert_eq!(again - next, chrono::Duration::days(1));
+        assert!(next_occurrence("not cron", at).is_none());
+    }
+
+    #[test]
+    fn schedules_persist_and_fall_due() {
+        let dir = ScratchDir::new("schedules");
+        let config = dir.join("config.json");
+        std::fs::write(&config, "{}").unwrap();
+        let config = config.to_str().unwrap();
+
+        let scheduler = Scheduler::open(&dir);
+        assert!(scheduler
+            .add("61 * * * *", "w", config, None, None)
+            .is_err());
+        assert!(scheduler
+            .add("* * * * *", "w", "/missing.json", None, None)
+            .is_err());
+        let schedule = scheduler
+            .add("*/5 * * * *", "w", config, None, Some(1))
+            .unwrap();
+        assert_eq!(schedule.executor_type, "real");
+
+        let reopened = Scheduler::open(&dir);
+        let now = Local::now();
+        let listed = reopened.list(now);
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].next_run_at.is_some());
+
+        let next = next_occurrence("*/5 * * * *", now).unwrap();
+        assert!(reopened
+            .due(now, next - chrono::Duration::seconds(1))
+            .is_empty());
+        assert_eq!(reopened.due(now, next).len(), 1);
+
+        let plan = reopened.planned(&RunTracker::default(), now);
+        assert_eq!(plan[0].kind, PlanKind::Scheduled);
+        assert_eq!(plan[0].expected_start, Some(next.to_rfc3339()));
+
+        reopened.remove(&schedule.id).unwrap();
+        assert!(Scheduler::open(&dir).list(now).is_empty());
+    }
+
+    #[test]
+    fn outcomes_go_to_the_history() {
+        let dir = ScratchDir::new("schedules");
+        let config = dir.join("config.json");
+        std::fs::write(&config, "{}").unwrap();
+        let scheduler = Scheduler::open(&dir);
+        let schedule = scheduler
+            .add("0 3 * * *", "w", config.to_str().unwrap(), None, None)
+            .unwrap();
+
+        assert!(scheduler.begin(&schedule.id));
+        assert!(!scheduler.begin(&schedule.id));
+        for success in [true, false] {
+            scheduler
+                .finish(&ExecutionRecord {
+                    schedule_id: Some(schedule.id.clone()),
+                    process_id: "w".to_string(),
+                    config_path: None,
+                    started_at: Local::now().to_rfc3339(),
+                    finished_at: Local::now().to_rfc3339(),
+                    success,
+                    error: None,
+                    videos: Vec::new(),
+                    limit_action: None,
+                })
+                .unwrap();
+        }
+        assert!(scheduler.begin(&schedule.id));
+
+        let history = scheduler.history(10);
+        assert_eq!(history.len(), 2);
+        assert!(!history[0].success);
+        assert_eq!(scheduler.history(1).len(), 1);
+        assert_eq!(
+            Scheduler::open(&dir).list(Local::now())[0]
+                .schedule
+                .last_success,
+            Some(false)
+        );
+    }
+}
+
+mod video_recordings {
+    use crate::video::{VideoFormat, VideoSettings};
+
+    #[test]
+    fn settings_default_to_mp4() {
+        let settings: VideoSettings = serde_json::from_str(r#"{ "format": "webm" }"#).unwrap();
+        assert_eq!(settings.format.extension(), "webm");
+        assert_eq!(settings.fps, VideoSettings::default().fps);
+        assert_eq!(VideoSettings::default().format, VideoFormat::Mp4);
+        assert!(VideoSettings {
+            fps: 0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
+
+mod recordings_library {
+    use super::ScratchDir;
+    use crate::recordings::{RecordingEntry, RecordingKind, Recordings};
+    use std::path::PathBuf;
+
+    fn library() -> (Recordings, ScratchDir) {
+        let dir = ScratchDir::new("recordings");
+        (Recordings::open(dir.to_path_buf()), dir)
+    }
+
+    fn recording(started_at: &str, finished_at: Option<&str>) -> RecordingEntry {
+        let mut entry = RecordingEntry::new(RecordingKind::Video, PathBuf::from("run.mp4"));
+        entry.started_at = started_at.to_string();
+        entry.finished_at = finished_at.map(str::to_string);
+        entry
+    }
+
+    #[test]
+    fn videos_are_linked_to_the_runs_they_cover() {
+        let video = recording(
+            "2024-05-01T10:00:00+02:00",
+            Some("2024-05-01T10:30:00+02:00"),
+        );
+        assert!(video.overlaps("2024-05-01T10:20:00+02:00", "2024-05-01T11:00:00+02:00"));
+        assert!(video.overlaps("2024-05-01T09:00:00+02:00", "2024-05-01T10:00:00+02:00"));
+        assert!(!video.overlaps("2024-05-01T10:31:00+02:00", "2024-05-01T11:00:00+02:00"));
+        // Same instant in another offset
+        assert!(video.overlaps("2024-05-01T08:10:00Z", "2024-05-01T08:15:00Z"));
+
+        let still_recording = recording("2024-05-01T10:00:00+02:00", None);
+        let now = chrono::Local::now().to_rfc3339();
+        assert!(still_recording.overlaps(&now, &now));
+    }
+
+    #[test]
+    fn executor_recordings_count_their_frames() {
+        let (recordings, dir) = library();
+        let folder = dir.join("session");
+        std::fs::create_dir_all(folder.join("frames")).unwrap();
+        std::fs::write(folder.join("frames/0001.png"), [0u8; 10]).unwrap();
+        std::fs::write(folder.join("frames/0002.PNG"), [0u8; 10]).unwrap();
+        std::fs::write(folder.join("actions.json"), "[]").unwrap();
+
+        let mut entry = RecordingEntry::new(RecordingKind::Executor, folder);
+        entry.config_name = Some("Login flow".to_string());
+        let id = recordings.begin(entry).unwrap().id;
+        assert!(recordings.active(RecordingKind::Executor).is_some());
+
+        let finished = recordings.finish(&id).unwrap();
+        assert_eq!(finished.frame_count, Some(2));
+        assert_eq!(finished.size_bytes, Some(22));
+        assert!(finished.duration_ms.is_some());
+        assert!(recordings.active(RecordingKind::Executor).is_none());
+
+        // Kept across restarts
+        let reopened = Recordings::open(dir.to_path_buf());
+        assert_eq!(reopened.list(), vec![finished]);
+    }
+
+    #[test]
+    fn renaming_keeps_the_files() {
+        let (recordings, dir) = library();
+        let path = dir.join("videos/run-1.mp4");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"video").unwrap();
+        let id = recordings
+            .begin(RecordingEntry::new(RecordingKind::Video, path.clone()))
+            .unwrap()
+            .id;
+        assert_eq!(recordings.list()[0].name, "run-1");
+
+        assert_eq!(
+            recordings.rename(&id, "  Checkout ").unwrap().name,
+            "Checkout"
+        );
+        assert!(recordings.rename(&id, " ").is_err());
+        assert!(recordings.rename("missing", "Name").is_err());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn deleting_removes_only_files_in_the_library() {
+        let (recordings, dir) = library();
+        let inside = dir.join("videos/run-1.mp4");
+        std::fs::create_dir_all(inside.parent().unwrap()).unwrap();
+        std::fs::write(&inside, b"video").unwrap();
+        let outside = ScratchDir::new("outside");
+
+        let video = recordings
+            .begin(RecordingEntry::new(RecordingKind::Video, inside.clone()))
+            .unwrap()
+            .id;
+        let executor = recordings
+            .begin(RecordingEntry::new(
+                RecordingKind::Executor,
+                outside.to_path_buf(),
+            ))
+            .unwrap()
+            .id;
+        // Not while recording
+        assert!(recordings.delete(&video).is_err());
+
+        recordings.finish(&video).unwrap();
+        recordings.finish(&executor).unwrap();
+        recordings.delete(&video).unwrap();
+        recordings.delete(&executor).unwrap();
+        assert!(!inside.exists());
+        assert!(outside.exists());
+        assert!(recordings.list().is_empty());
+    }
+
+    #[test]
+    fn interrupted_recordings_are_finished_on_open() {
+        let (recordings, dir) = library();
+        let path = dir.join("videos/run-1.mp4");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"video").unwrap();
+        let mut entry = RecordingEntry::new(RecordingKind::Video, path);
+        entry.fps = Some(10);
+        entry.started_at = (chrono::Local::now() - chrono::Duration::seconds(2)).to_rfc3339();
+        recordings.begin(entry).unwrap();
+
+        let reopened = Recordings::open(dir.to_path_buf());
+        let entry = &reopened.list()[0];
+        assert!(!entry.is_recording());
+        let duration_ms = entry.duration_ms.unwrap();
+        assert!(duration_ms >= 1000);
+        assert_eq!(entry.frame_count, Some(10 * duration_ms as u64 / 1000));
+    }
+}
+
+mod recording_drafts {
+    use super::ScratchDir;
+    use crate::capture::{Region, RgbImage};
+    use crate::config::draft;
+    use crate::config::ConfigLoader;
+    use serde_json::{json, Value};
+    use std::path::Path;
+
+    fn screen(shade: u8) -> RgbImage {
+        let (width, height) = (200, 100);
+        let pixels = (0..width * height)
+            .map(|i| {
+                if i % width < 100 {
+                    [shade; 3]
+                } else {
+                    [255 - shade; 3]
+                }
+            })
+            .collect();
+        RgbImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn recording(actions: Value) -> ScratchDir {
+        let dir = ScratchDir::new("draft");
+        std::fs::create_dir_all(dir.join("frames")).unwrap();
+        for (name, shade) in [("login", 0), ("home", 200)] {
+            let png = screen(shade).encode_png().unwrap();
+            std::fs::write(dir.join(format!("frames/{}.png", name)), png).unwrap();
+        }
+        std::fs::write(dir.join("actions.json"), actions.to_string()).unwrap();
+        dir
+    }
+
+    fn ids(config: &Value, section: &str) -> Vec<String> {
+        config[section]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn screens_become_states_and_actions_transitions() {
+        let dir = recording(json!([
+            { "type": "click", "x": 50, "y": 50, "timestamp": 0, "screenshot": "frames/login.png" },
+            { "type": "type", "text": "admin", "timestamp": 200 },
+            { "type": "key", "key": "enter", "timestamp": 1400 },
+            { "type": "click", "x": 150, "y": 10, "timestamp": 3000, "screenshot": "frames/home.png" },
+            // Back on the first screen
+            { "type": "click", "x": 60, "y": 40, "timestamp": 4000, "screenshot": "frames/login.png" },
+        ]));
+        let config = draft::from_recording(&dir, "Login (draft)").unwrap();
+
+        assert_eq!(ids(&config, "states"), ["state-1", "state-2"]);
+        assert_eq!(config["states"][0]["isInitial"], true);
+        assert_eq!(
+            ids(&config, "workflows"),
+            ["workflow-1", "workflow-2", "workflow-3"]
+        );
+        let transitions = config["transitions"].as_array().unwrap();
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0]["fromState"], "state-1");
+        assert_eq!(transitions[0]["toState"], "state-2");
+        assert_eq!(transitions[1]["toState"], "state-1");
+
+        // The pause before Enter became a wait
+        let types: Vec<&str> = config["workflows"][0]["actions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|action| action["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["CLICK", "TYPE", "WAIT", "KEY"]);
+        let actions = &config["workflows"][0]["actions"];
+        assert_eq!(actions[0]["id"], "workflow-1-action-1");
+        assert_eq!(actions[1]["config"], json!({ "text": "admin" }));
+        assert_eq!(actions[2]["config"]["duration"], 1200);
+
+        // Two screenshots and three click targets
+        assert_eq!(ids(&config, "images").len(), 5);
+        let target = actions[0]["config"]["target"]["imageId"].as_str().unwrap();
+        let image = config["images"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|image| image["id"] == target)
+            .unwrap();
+        assert_eq!(
+            (image["width"].clone(), image["height"].clone()),
+            (json!(64), json!(64))
+        );
+
+        let loaded = ConfigLoader::load_from_string(&config.to_string()).unwrap();
+        assert_eq!(loaded.metadata.name, "Login (draft)");
+    }
+
+    #[test]
+    fn recordings_without_actions_are_refused() {
+        let dir = recording(json!([]));
+        assert!(draft::from_recording(&dir, "Empty").is_err());
+        assert!(draft::from_recording(Path::new("/nonexistent/recording"), "Missing").is_err());
+    }
+
+    #[test]
+    fn drafts_never_replace_a_file() {
+        let dir = recording(json!([]));
+        let first = draft::draft_path(&dir);
+        assert!(first.to_string_lossy().ends_with("-draft.json"));
+        std::fs::write(&first, "{}").unwrap();
+        let second = draft::draft_path(&dir);
+        assert!(second.to_string_lossy().ends_with("-draft-2.json"));
+        let _ = std::fs::remove_file(first);
+    }
+
+    #[test]
+    fn crops_are_clipped_to_the_image() {
+        let image = screen(0);
+        let corner = image
+            .crop(Region {
+                x: -10,
+                y: -10,
+                width: 30,
+                height: 30,
+            })
+            .unwrap();
+        assert_eq!((corner.width, corner.height), (20, 20));
+        let right = image
+            .crop(Region {
+                x: 190,
+                y: 0,
+                width: 20,
+                height: 1,
+            })
+            .unwrap();
+        assert_eq!(right.pixels, vec![[255; 3]; 10]);
+        assert!(image
+            .crop(Region {
+                x: 300,
+                y: 0,
+                width: 10,
+                height: 10,
+            })
+            .is_none());
+    }
+}
+
+mod input_replay {
+    use crate::replay::{offset, parse_key, parse_trace, InputEvent, MouseButton};
+    use enigo::Key;
+    use std::time::Duration;
+
+    #[test]
+    fn traces_are_read_in_time_order() {
+        let trace = r#"
+{"timestamp": 0, "type": "mouse_move", "x": 10, "y": 20}
+{"t": 500, "type": "key_press", "key": "enter"}
+{"t": 250, "type": "click", "button": "right", "x": 10, "y": 20}
+
+{"t": 500, "type": "text", "text": "hello"}
+"#;
+        let events = parse_trace(trace).unwrap();
+        let timestamps: Vec<u64> = events.iter().map(|event| event.timestamp).collect();
+        assert_eq!(timestamps, [0, 250, 500, 500]);
+        assert_eq!(
+            events[1].event,
+            InputEvent::Click {
+                button: MouseButton::Right,
+                x: Some(10),
+                y: Some(20)
+            }
+        );
+        // Same millisecond, recorded order
+        assert!(matches!(events[2].event, InputEvent::KeyPress { .. }));
+        assert!(matches!(events[3].event, InputEvent::Text { .. }));
+    }
+
+    #[test]
+    fn bad_events_are_reported_by_line() {
+        let error = parse_trace(
+            "{\"t\": 0, \"type\": \"text\", \"text\": \"a\"}\n{\"t\": 1, \"type\": \"teleport\"}",
+        )
+        .unwrap_err();
+        assert!(error.contains("line 2"), "{}", error);
+        let error =
+            parse_trace("{\"t\": 0, \"type\": \"key_down\", \"key\": \"hyper\"}").unwrap_err();
+        assert!(error.contains("Unknown key 'hyper'"), "{}", error);
+    }
+
+    #[test]
+    fn keys_are_named_or_typed() {
+        assert_eq!(parse_key("Enter").unwrap(), Key::Return);
+        assert_eq!(parse_key("ctrl").unwrap(), Key::Control);
+        assert_eq!(parse_key("F5").unwrap(), Key::F5);
+        assert_eq!(parse_key("a").unwrap(), Key::Unicode('a'));
+        assert_eq!(parse_key("é").unwrap(), Key::Unicode('é'));
+        assert!(parse_key("").is_err());
+    }
+
+    #[test]
+    fn speed_scales_the_schedule() {
+        let events = parse_trace(
+            "{\"t\": 1000, \"type\": \"mouse_move\", \"x\": 0, \"y\": 0}\n{\"t\": 3000, \"type\": \"mouse_move\", \"x\": 1, \"y\": 1}",
+        )
+        .unwrap();
+        assert_eq!(offset(&events[0], &events[0], 1.0), Duration::ZERO);
+        assert_eq!(offset(&events[1], &events[0], 1.0), Duration::from_secs(2));
+        assert_eq!(offset(&events[1], &events[0], 2.0), Duration::from_secs(1));
+        assert_eq!(offset(&events[1], &events[0], 0.5), Duration::from_secs(4));
+    }
+}
+
+mod protocol_handshake {
+    use crate::executor::handshake::{Handshake, Hello, PROTOCOL_VERSION, SAFE_MODE};
+    use crate::executor::python_bridge::ExecutorEvent;
+    use serde_json::json;
+
+    fn event(name: &str, data: serde_json::Value) -> ExecutorEvent {
+        ExecutorEvent {
+            event: name.to_string(),
+            timestamp: 0.0,
+            sequence: 1,
+            data,
+        }
+    }
+
+    fn hello(protocol_version: u32) -> Hello {
+        Hello {
+            protocol_version,
+            qontinui_version: Some("0.1.0".to_string()),
+            bridge: Some("qontinui_executor".to_string()),
+            python_version: None,
+            commands: None,
+            features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bounded_runs_need_a_bridge_that_enforces_safe_mode() {
+        assert!(Handshake::Pending.allows_safe_mode().is_err());
+        let error = Handshake::check(hello(PROTOCOL_VERSION))
+            .allows_safe_mode()
+            .unwrap_err();
+        assert!(error.contains("qontinui_executor"), "{}", error);
+
+        let enforcing = Hello {
+            features: vec![SAFE_MODE.to_string()],
+            ..hello(PROTOCOL_VERSION)
+        };
+        assert!(Handshake::check(enforcing).allows_safe_mode().is_ok());
+        assert!(Handshake::in_process("screenshot", Vec::new())
+            .allows_safe_mode()
+            .is_ok());
+    }
+
+    #[test]
+    fn hello_settles_the_handshake_once() {
+        let mut handshake = Handshake::Pending;
+        assert!(!handshake.observe(&event("log", json!({}))));
+        assert_eq!(handshake, Handshake::Pending);
+
+        let data = json!({
+            "protocol_version": PROTOCOL_VERSION,
+            "qontinui_version": "0.1.0",
+            "bridge": "qontinui_executor",
+        });
+        assert!(handshake.observe(&event("hello", data)));
+        assert_eq!(
+            handshake
+                .hello()
+                .and_then(|hello| hello.qontinui_version.as_deref()),
+            Some("0.1.0")
+        );
+        assert!(matches!(handshake, Handshake::Compatible { .. }));
+        assert!(!handshake.observe(&event("ready", json!({}))));
+        assert!(handshake.allows("start").is_ok());
+    }
+
+    #[test]
+    fn newer_protocols_are_refused() {
+        let handshake = Handshake::check(hello(PROTOCOL_VERSION + 1));
+        assert!(matches!(handshake, Handshake::Incompatible { .. }));
+        let error = handshake.allows("start").unwrap_err();
+        assert!(error.contains("update the runner"), "{}", error);
+        assert!(handshake.allows("stop").is_ok());
+        assert!(handshake.allows("ping").is_ok());
+    }
+
+    #[test]
+    fn scripts_without_hello_are_refused() {
+        let mut handshake = Handshake::Pending;
+        assert!(handshake.observe(&event("ready", json!({"message": "ready"}))));
+        assert_eq!(handshake.hello(), None);
+        assert!(handshake.allows("load").is_err());
+    }
+
+    #[test]
+    fn invalid_hellos_are_refused() {
+        let mut handshake = Handshake::Pending;
+        assert!(handshake.observe(&event("hello", json!({"bridge": "old"}))));
+        assert!(matches!(
+            handshake,
+            Handshake::Incompatible { hello: None, .. }
+        ));
+    }
+
+    #[test]
+    fn unlisted_commands_fail_fast() {
+        let mut handshake = Handshake::Pending;
+        let data = json!({
+            "protocol_version": PROTOCOL_VERSION,
+            "bridge": "qontinui_bridge",
+            "commands": ["load", "start", "stop", "ping", "status"],
+        });
+        handshake.observe(&event("hello", data));
+        assert!(handshake.allows("start").is_ok());
+        let error = handshake.allows("start_recording").unwrap_err();
+        assert_eq!(
+            error,
+            "The executor (qontinui_bridge) doesn't support recording"
+        );
+        assert!(handshake.allows("pause").unwrap_err().contains("pausing"));
+
+        // Bridges that don't list their commands are sent everything
+        assert!(Handshake::check(hello(PROTOCOL_VERSION))
+            .allows("start_recording")
+            .is_ok());
+        assert!(Handshake::Pending.allows("start_recording").is_ok());
+    }
+
+    #[test]
+    fn the_status_is_serialized_as_a_tag() {
+        let value =
+            serde_json::to_value(Handshake::in_process("mock", vec!["load".to_string()])).unwrap();
+        assert_eq!(value["status"], "compatible");
+        assert_eq!(value["hello"]["bridge"], "mock");
+        assert_eq!(value["hello"]["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(value["hello"]["commands"], json!(["load"]));
+    }
+}
+
+mod socket_transport {
+    use crate::executor::protocol::{BridgeMessage, Frame, MessageReader};
+    use crate::executor::transport::{self, TransportKind, FRAME_BINARY};
+    use std::io::BufReader;
+
+    const EVENT: &str = r#"{"type":"event","event":"screenshot","timestamp":1.5,"sequence":3,"data":{"name":"login"}}"#;
+    const RESPONSE: &str =
+        r#"{"type":"response","id":"abc","success":true,"data":null,"error":null}"#;
+
+    fn json_frame(payload: &str) -> Vec<u8> {
+        let mut frame = Vec::new();
+        transport::write_frame(&mut frame, payload.as_bytes()).unwrap();
+        frame
+    }
+
+    fn binary_frame(message: &str, bytes: &[u8]) -> Vec<u8> {
+        let len = 4 + message.len() + bytes.len();
+        let mut frame = (len as u32).to_be_bytes().to_vec();
+        frame.push(FRAME_BINARY);
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(message.as_bytes());
+        frame.extend_from_slice(bytes);
+        frame
+    }
+
+    /// Reads every frame with a small buffer so frames straddle refills.
+    fn read_all(input: &[u8], max_frame: usize) -> Vec<Frame> {
+        let mut reader =
+            MessageReader::with_max_line(BufReader::with_capacity(7, input), max_frame)
+                .length_prefixed();
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.next_frame().unwrap() {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[test]
+    fn frames_carry_messages_with_newlines() {
+        let multiline = EVENT.replace(r#""login""#, "\"line 1\\nline 2\"");
+        let mut input = json_frame(&multiline);
+        input.extend(json_frame(RESPONSE));
+
+        let frames = read_all(&input, 1024);
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(
+            &frames[0],
+            Frame::Message(BridgeMessage::Event(event)) if event.data["name"] == "line 1\nline 2"
+        ));
+        assert!(matches!(
+            &frames[1],
+            Frame::Message(BridgeMessage::Response(_))
+        ));
+    }
+
+    #[test]
+    fn binary_frames_attach_their_bytes() {
+        let mut input = binary_frame(EVENT, &[0, 159, 146, 150]);
+        input.extend(binary_frame(RESPONSE, b"png"));
+
+        let frames = read_all(&input, 1024);
+        let Frame::Message(BridgeMessage::Event(event)) = &frames[0] else {
+            panic!("expected an event");
+        };
+        assert_eq!(event.data["name"], "login");
+        assert_eq!(event.data["binary"], "AJ+Slg==");
+        let Frame::Message(BridgeMessage::Response(response)) = &frames[1] else {
+            panic!("expected a response");
+        };
+        assert_eq!(response.data.as_ref().unwrap()["binary"], "cG5n");
+    }
+
+    #[test]
+    fn oversized_frames_are_skipped() {
+        let mut input = json_frame(&format!("{}{}", EVENT, " ".repeat(100)));
+        input.extend(json_frame(RESPONSE));
+
+        let frames = read_all(&input, 100);
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(&frames[0], Frame::Invalid(e) if e.error.contains("exceeds")));
+        assert!(matches!(
+            &frames[1],
+            Frame::Message(BridgeMessage::Response(_))
+        ));
+    }
+
+    #[test]
+    fn bad_frames_are_reported() {
+        let mut unknown_kind = json_frame(RESPONSE);
+        unknown_kind[4] = 7;
+        assert!(
+            matches!(&read_all(&unknown_kind, 1024)[0], Frame::Invalid(e) if e.error.contains("kind 7"))
+        );
+
+        let mut short = binary_frame(RESPONSE, b"");
+        short[5..9].copy_from_slice(&1000u32.to_be_bytes());
+        assert!(matches!(&read_all(&short, 1024)[0], Frame::Invalid(_)));
+
+        // A stream cut inside a frame is an error, not the end
+        let truncated = json_frame(RESPONSE);
+        let mut reader = MessageReader::new(BufReader::new(&truncated[..20])).length_prefixed();
+        assert!(reader.next_frame().is_err());
+    }
+
+    #[test]
+    fn transports_parse_by_name() {
+        assert_eq!(TransportKind::parse("stdio"), Ok(TransportKind::Stdio));
+        assert_eq!(TransportKind::parse("TCP"), Ok(TransportKind::Tcp));
+        assert!(TransportKind::parse("carrier-pigeon").is_err());
+        if cfg!(unix) {
+            assert_eq!(TransportKind::parse("unix"), Ok(TransportKind::Unix));
+        }
+    }
+}
+
+mod frame_channel {
+    use crate::executor::frames::{self, FrameFormat, FrameHeader, FrameStore};
+    use crate::executor::transport::FRAME_BINARY;
+    use serde_json::json;
+
+    fn header(id: &str, format: FrameFormat, width: u32, height: u32) -> FrameHeader {
+        FrameHeader {
+            id: id.to_string(),
+            format,
+            width,
+            height,
+        }
+    }
+
+    fn binary_frame(header: &str, bytes: &[u8]) -> Vec<u8> {
+        let len = 4 + header.len() + bytes.len();
+        let mut frame = (len as u32).to_be_bytes().to_vec();
+        frame.push(FRAME_BINARY);
+        frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        frame.extend_from_slice(header.as_bytes());
+        frame.extend_from_slice(bytes);
+        frame
+    }
+
+    #[test]
+    fn raw_pixels_are_served_as_png() {
+        let store = FrameStore::default();
+        store
+            .insert(
+                header("a", FrameFormat::Rgb, 2, 1),
+                vec![255, 0, 0, 0, 0, 255],
+            )
+            .unwrap();
+        let image = store.image("a").unwrap().unwrap();
+        assert_eq!(image.content_type, "image/png");
+        assert!(image.bytes.starts_with(b"\x89PNG"));
+
+        let error = store
+            .insert(header("b", FrameFormat::Rgb, 2, 2), vec![0; 6])
+            .unwrap_err();
+        assert!(error.contains("2x2"), "{}", error);
+        assert!(store.image("b").unwrap().is_none());
+    }
+
+    #[test]
+    fn oldest_frames_go_past_the_limit() {
+        let store = FrameStore::with_limit(10);
+        for id in ["a", "b", "c"] {
+            store
+                .insert(header(id, FrameFormat::Jpeg, 0, 0), vec![0; 4])
+                .unwrap();
+        }
+        assert!(store.image("a").unwrap().is_none());
+        assert_eq!(
+            store.image("b").unwrap().unwrap().content_type,
+            "image/jpeg"
+        );
+        assert!(store.image("c").unwrap().is_some());
+
+        // A single frame over the limit is still kept
+        store
+            .insert(header("big", FrameFormat::Png, 0, 0), vec![0; 20])
+            .unwrap();
+        assert!(store.image("big").unwrap().is_some());
+        assert!(store.image("c").unwrap().is_none());
+    }
+
+    #[test]
+    fn frames_are_received_from_the_channel() {
+        let mut input = binary_frame(
+            r#"{"id":"shot","format":"rgb","width":1,"height":1}"#,
+            &[1, 2, 3],
+        );
+        input.extend(binary_frame(r#"{"format":"png"}"#, b"no id"));
+        input.extend(binary_frame(r#"{"id":"logo","format":"png"}"#, b"\x89PNG"));
+
+        let store = FrameStore::default();
+        store.receive(&input[..]);
+        assert!(store.image("shot").unwrap().is_some());
+        assert_eq!(&**store.image("logo").unwrap().unwrap().bytes, b"\x89PNG");
+    }
+
+    #[test]
+    fn events_get_the_url_of_their_frame() {
+        let mut data = json!({ "found": true, "frame_id": "shot" });
+        frames::link(&mut data);
+        assert_eq!(data["frame_url"], frames::url("shot"));
+        assert!(data["frame_url"].as_str().unwrap().ends_with("/shot"));
+
+        let mut data = json!({ "frame_id": null });
+        frames::link(&mut data);
+        assert!(data.get("frame_url").is_none());
+    }
+
+    #[test]
+    fn unknown_frames_are_not_found() {
+        let store = FrameStore::default();
+        let response = frames::serve(&store, "/missing");
+        assert_eq!(response.status(), 404);
+        store
+            .insert(header("logo", FrameFormat::Png, 0, 0), b"png".to_vec())
+            .unwrap();
+        let response = frames::serve(&store, "/logo");
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), b"png");
+    }
+}
+
+mod event_backpressure {
+    use crate::executor::backpressure::{BackpressureConfig, Counters, EventQueue, OverflowPolicy};
+    use crate::executor::protocol::{BridgeMessage, Frame};
+    use crate::executor::python_bridge::ExecutorEvent;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn event(name: &str, sequence: u32) -> Frame {
+        Frame::Message(BridgeMessage::Event(ExecutorEvent {
+            event: name.to_string(),
+            timestamp: 0.0,
+            sequence,
+            data: json!({}),
+        }))
+    }
+
+    fn queue(capacity: usize, policies: &[(&str, OverflowPolicy)]) -> (EventQueue, Arc<Counters>) {
+        let config = BackpressureConfig {
+            capacity,
+            policies: policies
+                .iter()
+                .map(|(event, policy)| (event.to_string(), *policy))
+                .collect(),
+        };
+        let counters = Arc::new(Counters::default());
+        let queue = EventQueue::new(Arc::new(Mutex::new(config)), counters.clone());
+        (queue, counters)
+    }
+
+    fn drain(queue: &EventQueue) -> Vec<(String, u32)> {
+        queue.close();
+        std::iter::from_fn(|| queue.pop())
+            .map(|frame| match frame {
+                Frame::Message(BridgeMessage::Event(event)) => (event.event, event.sequence),
+                _ => panic!("not an event"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn progress_events_coalesce_in_place() {
+        let (queue, counters) = queue(10, &[("image_recognition", OverflowPolicy::Coalesce)]);
+        queue.push(event("image_recognition", 1));
+        queue.push(event("action_started", 2));
+        queue.push(event("image_recognition", 3));
+        queue.push(event("image_recognition", 4));
+        assert!(counters.snapshot().queued_bytes > 0);
+
+        assert_eq!(
+            drain(&queue),
+            vec![
+                ("image_recognition".to_string(), 4),
+                ("action_started".to_string(), 2)
+            ]
+        );
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.received, 4);
+        assert_eq!(metrics.coalesced, 2);
+        assert_eq!(metrics.forwarded, 2);
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.queued_bytes, 0);
+        assert_eq!(metrics.peak_queued, 2);
+    }
+
+    #[test]
+    fn full_queues_drop_by_policy() {
+        let (queue, counters) = queue(
+            2,
+            &[
+                ("log", OverflowPolicy::DropNewest),
+                ("match_found", OverflowPolicy::DropOldest),
+            ],
+        );
+        queue.push(event("match_found", 1));
+        queue.push(event("match_found", 2));
+        queue.push(event("log", 3));
+        queue.push(event("match_found", 4));
+
+        assert_eq!(
+            drain(&queue),
+            vec![
+                ("match_found".to_string(), 2),
+                ("match_found".to_string(), 4)
+            ]
+        );
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.dropped, 2);
+        assert_eq!(metrics.queued_bytes, 0);
+    }
+
+    #[test]
+    fn critical_events_wait_for_room() {
+        let (queue, counters) = queue(1, &[("error", OverflowPolicy::DropNewest)]);
+        queue.push(event("action_started", 1));
+
+        let pusher = {
+            let queue = queue.clone();
+            std::thread::spawn(move || queue.push(event("error", 2)))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!pusher.is_finished());
+        assert!(queue.pop().is_some());
+        pusher.join().unwrap();
+
+        assert_eq!(drain(&queue), vec![("error".to_string(), 2)]);
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.blocked, 1);
+        assert_eq!(metrics.dropped, 0);
+    }
+
+    #[test]
+    fn default_policies_only_cover_chatty_events() {
+        let config = BackpressureConfig::default();
+        assert_eq!(
+            config.policy(&event("image_recognition", 1)),
+            OverflowPolicy::Coalesce
+        );
+        assert_eq!(
+            config.policy(&event("workflow_started", 1)),
+            OverflowPolicy::Block
+        );
+        assert_eq!(
+            config.policy(&event("variable_set", 1)),
+            OverflowPolicy::Block
+        );
+    }
+}
+
+mod bridge_metrics {
+    use crate::executor::metrics::{self, BridgeStats, SequenceGap};
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    #[test]
+    fn round_trips_are_timed_by_command_id() {
+        let stats = BridgeStats::default();
+        stats.start();
+        stats.command_sent("a");
+        stats.command_sent("b");
+        std::thread::sleep(Duration::from_millis(20));
+        stats.response_received("a");
+        stats.response_received("unknown");
+        stats.event_received();
+
+        let metrics = stats.snapshot(None);
+        assert_eq!(metrics.commands_sent, 2);
+        assert_eq!(metrics.responses_received, 2);
+        assert_eq!(metrics.in_flight, 1);
+        assert!(metrics.average_round_trip_ms.unwrap() >= 20.0);
+        assert_eq!(metrics.average_round_trip_ms, metrics.max_round_trip_ms);
+        assert_eq!(metrics.events_received, 1);
+        assert!(metrics.uptime_ms.is_some());
+        assert!(metrics.process.is_none());
+    }
+
+    #[test]
+    fn sequence_gaps_are_counted() {
+        let stats = BridgeStats::default();
+        stats.start();
+        // The hello and the runner's own events are numbered 0
+        assert_eq!(stats.sequence_received("hello", 0), None);
+        assert_eq!(stats.sequence_received("ready", 1), None);
+        assert_eq!(stats.sequence_received("remote_connection", 0), None);
+        assert_eq!(stats.sequence_received("log", 2), None);
+        assert_eq!(
+            stats.sequence_received("log", 5),
+            Some(SequenceGap {
+                expected: 3,
+                received: 5,
+                dropped: 2
+            })
+        );
+        // Repeated and earlier numbers are out of order, not lost
+        assert_eq!(stats.sequence_received("log", 5), None);
+        assert_eq!(stats.sequence_received("log", 4), None);
+        // A run is numbered afresh
+        assert_eq!(stats.sequence_received("execution_started", 1), None);
+        assert_eq!(
+            stats
+                .sequence_received("action_started", 5)
+                .unwrap()
+                .dropped,
+            3
+        );
+
+        let metrics = stats.snapshot(None);
+        assert_eq!(metrics.sequence_gaps, 2);
+        assert_eq!(metrics.events_lost, 5);
+        assert_eq!(metrics.events_lost_in_run, 3);
+        assert_eq!(metrics.events_out_of_order, 2);
+
+        // A restarted executor numbers from the start again
+        stats.start();
+        assert_eq!(stats.sequence_received("ready", 1), None);
+        assert_eq!(stats.sequence_received("log", 2), None);
+        assert_eq!(stats.snapshot(None).events_out_of_order, 0);
+    }
+
+    #[test]
+    fn event_rate_covers_recent_seconds() {
+        let buckets: VecDeque<(u64, u64)> = [(0, 100), (25, 10), (29, 20), (30, 30)].into();
+        // The first second is long gone
+        assert_eq!(
+            metrics::events_per_second(&buckets, Duration::from_millis(30_500)),
+            6.0
+        );
+        // Barely started: the elapsed seconds only
+        let buckets: VecDeque<(u64, u64)> = [(0, 10), (1, 10)].into();
+        assert_eq!(
+            metrics::events_per_second(&buckets, Duration::from_millis(1_200)),
+            10.0
+        );
+    }
+
+    #[test]
+    fn process_usage_is_sampled() {
+        let stats = BridgeStats::default();
+        assert!(stats.snapshot(None).uptime_ms.is_none());
+        let usage = stats.snapshot(Some(std::process::id())).process.unwrap();
+        assert_eq!(usage.pid, std::process::id());
+        assert!(usage.memory_bytes > 0);
+    }
+}
+
+mod executor_limits {
+    use crate::executor::supervisor::{self, Sample, Supervisor, Verdict};
+    use crate::resources::{ExecutorLimits, LimitAction, ResourceLimits};
+    use std::time::{Duration, Instant};
+
+    fn limits() -> ExecutorLimits {
+        ExecutorLimits {
+            max_memory_mb: Some(100),
+            max_cpu_percent: Some(150.0),
+            max_run_minutes: Some(10),
+            action: LimitAction::Pause,
+            grace_seconds: 30,
+        }
+    }
+
+    fn over_memory() -> Sample {
+        Sample {
+            memory_bytes: Some(200 * 1024 * 1024),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn every_exceeded_limit_is_named() {
+        let sample = Sample {
+            memory_bytes: Some(50 * 1024 * 1024),
+            cpu_percent: Some(390.0),
+            run_time: Some(Duration::from_secs(11 * 60)),
+        };
+        let exceeded = supervisor::exceeded(&limits(), &sample);
+        assert_eq!(
+            exceeded,
+            vec![
+                "CPU 390% over the 150% limit",
+                "execution running 11 min, over the 10 min limit"
+            ]
+        );
+        assert!(supervisor::exceeded(&ExecutorLimits::default(), &sample).is_empty());
+    }
+
+    #[test]
+    fn warns_then_acts_after_the_grace_period() {
+        let mut supervisor = Supervisor::default();
+        let start = Instant::now();
+        assert!(matches!(
+            supervisor.check(&limits(), &over_memory(), start),
+            Some(Verdict::Warn(reason)) if reason.contains("memory 200 MB")
+        ));
+        let later = start + Duration::from_secs(10);
+        assert_eq!(supervisor.check(&limits(), &over_memory(), later), None);
+        let after_grace = start + Duration::from_secs(30);
+        assert!(matches!(
+            supervisor.check(&limits(), &over_memory(), after_grace),
+            Some(Verdict::Act(LimitAction::Pause, _))
+        ));
+        // Once, until back within limits
+        let much_later = start + Duration::from_secs(60);
+        assert_eq!(
+            supervisor.check(&limits(), &over_memory(), much_later),
+            None
+        );
+        assert_eq!(
+            supervisor.check(&limits(), &Sample::default(), much_later),
+            None
+        );
+        assert!(matches!(
+            supervisor.check(&limits(), &over_memory(), much_later),
+            Some(Verdict::Warn(_))
+        ));
+    }
+
+    #[test]
+    fn saved_limits_without_executor_ones_still_load() {
+        let limits: ResourceLimits =
+            serde_json::from_str(r#"{"memory_warning_mb": 512, "max_config_size_mb": 10}"#)
+                .unwrap();
+        assert_eq!(limits.executor.max_memory_mb, None);
+        assert_eq!(limits.executor.action, LimitAction::Kill);
+        assert_eq!(limits.executor.grace_seconds, 30);
+    }
+
+    #[test]
+    fn huge_limits_dont_overflow() {
+        let limits = ResourceLimits {
+            max_config_size_mb: u64::MAX,
+            ..Default::default()
+        };
+        assert_eq!(limits.max_config_bytes(), u64::MAX);
+    }
+}
+
+mod executor_launch_options {
+    use crate::executor::environment::{
+        self, ExecutorLaunchOptions, PythonEnvironment, PythonLauncher,
+    };
+    use std::ffi::OsStr;
+    use std::path::PathBuf;
+
+    fn system_python() -> PythonEnvironment {
+        PythonEnvironment {
+            script: PathBuf::from("bridge.py"),
+            launcher: PythonLauncher::System("python3".to_string()),
+        }
+    }
+
+    #[test]
+    fn interpreter_args_come_before_the_script() {
+        let options = ExecutorLaunchOptions {
+            env: [("QONTINUI_LOG".to_string(), "debug".to_string())].into(),
+            working_dir: Some(std::env::temp_dir()),
+            interpreter_args: vec!["-X".to_string(), "faulthandler".to_string()],
+            python_path: Vec::new(),
+        };
+        let cmd = system_python().command(&options).unwrap();
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(args, ["-X", "faulthandler", "bridge.py"]);
+        assert_eq!(cmd.get_current_dir(), Some(std::env::temp_dir().as_path()));
+        assert!(cmd
+            .get_envs()
+            .any(|(name, value)| name == "QONTINUI_LOG" && value == Some(OsStr::new("debug"))));
+
+        let cmd = system_python()
+            .command(&ExecutorLaunchOptions::default())
+            .unwrap();
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["bridge.py"]);
+        assert_eq!(cmd.get_current_dir(), None);
+    }
+
+    #[test]
+    fn poetry_keeps_its_project_in_another_working_dir() {
+        let env = PythonEnvironment {
+            script: PathBuf::from("bridge.py"),
+            launcher: PythonLauncher::Poetry {
+                project_dir: PathBuf::from("/qontinui"),
+            },
+        };
+        let options = ExecutorLaunchOptions {
+            working_dir: Some(std::env::temp_dir()),
+            interpreter_args: vec!["-u".to_string()],
+            ..Default::default()
+        };
+        let cmd = env.command(&options).unwrap();
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "--directory",
+                "/qontinui",
+                "run",
+                "python",
+                "-u",
+                "bridge.py"
+            ]
+        );
+        assert_eq!(cmd.get_current_dir(), Some(std::env::temp_dir().as_path()));
+    }
+
+    #[test]
+    fn python_path_entries_go_first() {
+        let joined = environment::python_path(
+            &[PathBuf::from("/extra"), PathBuf::from("/more")],
+            std::env::join_paths(["/existing"]).ok(),
+        )
+        .unwrap();
+        let paths: Vec<PathBuf> = std::env::split_paths(&joined).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/extra"),
+                PathBuf::from("/more"),
+                PathBuf::from("/existing")
+            ]
+        );
+        let joined = environment::python_path(&[PathBuf::from("/extra")], None).unwrap();
+        assert_eq!(joined, "/extra");
+    }
+
+    #[test]
+    fn invalid_options_are_refused() {
+        let options = ExecutorLaunchOptions {
+            env: [("A=B".to_string(), "x".to_string())].into(),
+            ..Default::default()
+        };
+        assert!(options.validate().unwrap_err().contains("A=B"));
+        let options = ExecutorLaunchOptions {
+            working_dir: Some(PathBuf::from("/no/such/dir")),
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+        assert!(ExecutorLaunchOptions::default().validate().is_ok());
+    }
+}
+
+mod execution_request {
+    use crate::executor::execution_request::{self, ExecutionRequest, FailureStrategy};
+    use serde_json::json;
+
+    #[test]
+    fn request_is_read_from_the_frontend() {
+        let request: ExecutionRequest = serde_json::from_value(json!({
+            "speed": 2.0,
+            "failure_strategy": "continue",
+            "variables": { "user": "alice" },
+            "tags": ["nightly"]
+        }))
+        .unwrap();
+        assert_eq!(request.speed, Some(2.0));
+        assert_eq!(request.max_retries, None);
+        assert_eq!(request.failure_strategy, Some(FailureStrategy::Continue));
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn out_of_range_values_are_refused() {
+        let too_fast = ExecutionRequest {
+            speed: Some(50.0),
+            ..Default::default()
+        };
+        assert!(too_fast.validate().unwrap_err().contains("Speed"));
+        let too_many = ExecutionRequest {
+            max_retries: Some(100),
+            ..Default::default()
+        };
+        assert!(too_many.validate().is_err());
+        let blank_tag = ExecutionRequest {
+            tags: vec![" ".to_string()],
+            ..Default::default()
+        };
+        assert!(blank_tag.validate().is_err());
+    }
+
+    #[test]
+    fn runtime_variables_need_names() {
+        let variables = json!({ "user": "alice", "counter": 3, "path": null });
+        assert!(execution_request::validate_variables(variables.as_object().unwrap()).is_ok());
+        let unnamed = json!({ " ": "x" });
+        assert!(execution_request::validate_variables(unnamed.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn only_set_overrides_are_sent() {
+        let mut params = serde_json::Map::new();
+        ExecutionRequest::default().insert_into(&mut params);
+        assert!(params.is_empty());
+
+        let request = ExecutionRequest {
+            max_retries: Some(2),
+            variables: json!({ "user": "alice" }).as_object().unwrap().clone(),
+            ..Default::default()
+        };
+        request.insert_into(&mut params);
+        assert_eq!(
+            serde_json::Value::Object(params),
+            json!({ "max_retries": 2, "variable_bindings": { "user": "alice" } })
+        );
+    }
+}
+
+mod state_machine {
+    use crate::executor::state_machine::{
+        self, ActiveStates, RebuiltStates, StateGraph, TransitionHistory,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn answers_are_read_into_typed_structures() {
+        let graph: StateGraph = state_machine::parse(
+            "state_graph",
+            Some(json!({
+                "states": [
+                    { "id": "login", "name": "Login", "initial": true, "active": false },
+                    { "id": "home", "name": null, "initial": false, "active": true }
+                ],
+                "transitions": [
+                    { "id": "t1", "from_state": "login", "to_state": "home",
+                      "activate_states": [], "deactivate_states": [], "workflows": ["w1"] }
+                ]
+            })),
+        )
+        .unwrap();
+        assert_eq!(graph.states.len(), 2);
+        assert!(graph.states[1].active);
+        assert_eq!(graph.transitions[0].to_state.as_deref(), Some("home"));
+
+        let history: TransitionHistory = state_machine::parse(
+            "transition_history",
+            Some(json!({
+                "transitions": [{
+                    "transition_id": "t1", "workflow_id": "w1", "from_state": "login",
+                    "to_states": ["home"], "success": true, "timestamp": 1700000000.5
+                }]
+            })),
+        )
+        .unwrap();
+        assert!(history.transitions[0].success);
+    }
+
+    #[test]
+    fn malformed_answers_are_errors() {
+        let error = state_machine::parse::<ActiveStates>("active_states", None).unwrap_err();
+        assert!(error.contains("active_states"));
+        let error = state_machine::parse::<ActiveStates>(
+            "active_states",
+            Some(json!({ "states": "home" })),
+        );
+        assert!(error.unwrap_err().starts_with("Invalid active_states"));
+    }
+
+    #[test]
+    fn rebuilt_states_list_the_unverified_ones() {
+        let rebuilt: RebuiltStates = state_machine::parse(
+            "rebuild_states",
+            Some(json!({ "states": [{ "id": "home", "name": "Home" }], "unverified": ["menu"] })),
+        )
+        .unwrap();
+        assert_eq!(rebuilt.states[0].id, "home");
+        assert_eq!(rebuilt.unverified, ["menu"]);
+    }
+}
+
+mod execution_progress {
+    use crate::config::QontinuiConfig;
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::progress::{Plan, ProgressTracker};
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+
+    fn config() -> QontinuiConfig {
+        let json = json!({
+            "version": "1.0.0",
+            "metadata": { "name": "Progress" },
+            "images": [],
+            "states": [],
+            "transitions": [],
+            "categories": [],
+            "workflows": [
+                {
+                    "id": "main",
+                    "actions": [
+                        { "id": "a1", "type": "CLICK" },
+                        { "id": "a2", "type": "RUN_WORKFLOW", "config": { "workflowId": "sub" } },
+                        { "id": "a3", "type": "GO_TO_STATE", "config": { "stateIds": ["home"] } },
+                    ],
+                },
+                { "id": "sub", "actions": [{ "id": "s1", "type": "TYPE" }] },
+            ],
+        });
+        QontinuiConfig::from_value(&json).unwrap()
+    }
+
+    fn event(name: &str, data: serde_json::Value) -> ExecutorEvent {
+        ExecutorEvent::from_runner(name, data)
+    }
+
+    #[test]
+    fn nested_workflows_are_counted() {
+        let plan = Plan::of(&config(), "main");
+        assert_eq!(plan.actions_total, 4);
+        assert_eq!(plan.transitions_total, 1);
+    }
+
+    #[test]
+    fn events_tick_off_actions_and_estimate_the_rest() {
+        let config = config();
+        let tracker = ProgressTracker::default();
+        let start = Instant::now();
+        assert!(tracker.progress(start).is_none());
+        let started = event("execution_started", json!({ "workflow_id": "main" }));
+        tracker.record_event(&started, Some(&config), start);
+        for id in ["a1", "s1"] {
+            let completed = event(
+                "action_completed",
+                json!({ "action_id": id, "success": true }),
+            );
+            tracker.record_event(&completed, Some(&config), start);
+        }
+
+        let progress = tracker.progress(start + Duration::from_secs(10)).unwrap();
+        assert!(progress.running);
+        assert_eq!(progress.actions_completed, 2);
+        assert_eq!(progress.percent, 50.0);
+        assert_eq!(progress.eta_ms, Some(10_000));
+        assert_eq!(progress.workflows[1].workflow_id, "sub");
+        assert_eq!(progress.workflows[1].actions_completed, 1);
+
+        let done = event("execution_completed", json!({ "success": true }));
+        tracker.record_event(&done, Some(&config), start + Duration::from_secs(12));
+        let progress = tracker.progress(start + Duration::from_secs(60)).unwrap();
+        assert!(!progress.running);
+        assert_eq!(progress.percent, 100.0);
+        assert_eq!(progress.elapsed_ms, 12_000);
+        assert_eq!(progress.eta_ms, None);
+    }
+}
+
+mod run_report {
+    use crate::executor::run_report::{ReportFormat, RunReport};
+    use crate::executor::run_stream::StreamEntry;
+    use crate::i18n::Formatter;
+    use serde_json::{json, Value};
+
+    fn event(index: u64, elapsed_ms: f64, name: &str, data: Value) -> StreamEntry {
+        serde_json::from_value(json!({
+            "index": index,
+            "timestamp": "2024-03-09T14:05:07Z",
+            "elapsed_ms": elapsed_ms,
+            "direction": "event",
+            "name": name,
+            "correlation_id": null,
+            "sequence": index,
+            "success": null,
+            "data": data,
+        }))
+        .unwrap()
+    }
+
+    fn english() -> Formatter {
+        Formatter::new(serde_json::from_str(include_str!("../locales/en.json")).unwrap())
+    }
+
+    fn entries() -> Vec<StreamEntry> {
+        vec![
+            event(
+                0,
+                0.0,
+                "execution_started",
+                json!({"workflow_id": "login", "tags": ["nightly"]}),
+            ),
+            event(
+                1,
+                10.0,
+                "action_started",
+                json!({"action_id": "a1", "action_type": "CLICK"}),
+            ),
+            event(
+                2,
+                260.0,
+                "action_completed",
+                json!({"action_id": "a1", "success": true}),
+            ),
+            event(
+                3,
+                300.0,
+                "state_changed",
+                json!({"active_states": ["home"], "transition_id": "t1"}),
+            ),
+            event(
+                4,
+                400.0,
+                "action_started",
+                json!({"action_id": "a2", "action_type": "FIND"}),
+            ),
+            event(
+                5,
+                2900.0,
+                "action_completed",
+                json!({"action_id": "a2", "success": false, "error": "Image <logo> not found", "screenshot": "/missing/failure.png"}),
+            ),
+            event(6, 3000.0, "execution_completed", json!({"success": false})),
+        ]
+    }
+
+    #[test]
+    fn summarizes_actions_failures_and_transitions() {
+        let report = RunReport::build("run-1", &entries(), &english(), "en");
+
+        assert_eq!(report.summary.workflow_id.as_deref(), Some("login"));
+        assert_eq!(report.summary.tags, ["nightly"]);
+        assert_eq!(report.summary.success, Some(false));
+        assert_eq!(report.summary.result, "Failed");
+        assert_eq!(report.summary.text, "1 of 2 actions passed");
+        assert_eq!(report.summary.duration, "3.0 s");
+        assert_eq!(report.actions[0].duration_ms, Some(250.0));
+        assert_eq!(report.actions[1].duration.as_deref(), Some("2.5 s"));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            report.failures[0].screenshot.as_deref(),
+            Some("/missing/failure.png")
+        );
+        assert_eq!(report.timeline[0].active_states, ["home"]);
+        assert_eq!(report.timeline[0].transition_id.as_deref(), Some("t1"));
+    }
+
+    #[test]
+    fn renders_junit_and_html() {
+        let formatter = english();
+        let report = RunReport::build("run-1", &entries(), &formatter, "en");
+
+        let junit = report.render(ReportFormat::Junit, &formatter).unwrap();
+        assert!(junit.contains("tests=\"2\" failures=\"1\" errors=\"0\" time=\"3.000\""));
+        assert!(
+            junit.contains("<testcase classname=\"login\" name=\"a1 (CLICK)\" time=\"0.250\"/>")
+        );
+        assert!(junit.contains("<failure message=\"Image &lt;logo&gt; not found\">"));
+        assert!(junit.contains("[[ATTACHMENT|/missing/failure.png]]"));
+
+        let html = report.render(ReportFormat::Html, &formatter).unwrap();
+        assert!(html.contains("<h2>Failures</h2>"));
+        assert!(html.contains("Image &lt;logo&gt; not found"));
+        assert!(!html.contains("<logo>"));
+
+        let json: Value =
+            serde_json::from_str(&report.render(ReportFormat::Json, &formatter).unwrap()).unwrap();
+        assert_eq!(json["summary"]["actions_failed"], 1);
+        assert!(ReportFormat::parse("pdf").is_err());
+    }
+
+    #[test]
+    fn unfinished_runs_are_incomplete() {
+        let entries = &entries()[..5];
+        let report = RunReport::build("run-1", entries, &english(), "en");
+        assert_eq!(report.summary.success, None);
+        assert_eq!(report.summary.result, "Incomplete");
+        assert_eq!(report.actions[1].success, None);
+        assert!(report.to_junit().contains("errors=\"1\""));
+    }
+}
+
+mod image_assets {
+    use super::ScratchDir;
+    use crate::assets::{self, AssetCache};
+    use crate::capture::RgbImage;
+    use crate::config::images::{ImageSource, ImageStore};
+    use crate::config::{ConfigLoader, QontinuiConfig};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn png(width: u32, height: u32, shade: u8) -> String {
+        let image = RgbImage {
+            width,
+            height,
+            pixels: vec![[shade, 0, 255 - shade]; (width * height) as usize],
+        };
+        STANDARD.encode(image.encode_png().unwrap())
+    }
+
+    /// Loading refuses unreadable images, so this one is built directly, as
+    /// if its file had gone since.
+    fn config() -> Arc<QontinuiConfig> {
+        let json = json!({
+            "version": "1.0.0",
+            "metadata": { "name": "Assets" },
+            "images": [
+                { "id": "logo", "data": png(300, 150, 10) },
+                { "id": "copy", "data": png(300, 150, 10) },
+                { "id": "button", "data": png(40, 20, 200), "sha256": "0000" },
+                { "id": "empty" },
+                { "id": "file", "path": "/nonexistent/qontinui/image.png" },
+            ],
+            "workflows": [],
+            "states": [{ "id": "home", "isInitial": true }],
+            "transitions": [],
+            "categories": [],
+        });
+        let mut config = QontinuiConfig::from_value(&json).unwrap();
+        config.image_store = ImageStore::extract(&mut config.images);
+        Arc::new(config)
+    }
+
+    #[test]
+    fn reports_missing_mismatched_and_duplicate_images() {
+        let report = assets::check(&config());
+        let missing: Vec<&str> = report
+            .missing
+            .iter()
+            .map(|problem| problem.image_id.as_str())
+            .collect();
+        assert_eq!(missing, ["button", "empty", "file"]);
+        assert!(report.missing[0].error.contains("doesn't match 0000"));
+        assert_eq!(report.duplicates, vec![vec!["logo", "copy"]]);
+        assert_eq!(report.images, 5);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn digests_from_validation_are_kept_until_the_image_changes() {
+        let dir = ScratchDir::new("assets");
+        let file = dir.join("logo.png");
+        std::fs::write(&file, STANDARD.decode(png(30, 15, 10)).unwrap()).unwrap();
+        let json = json!({
+            "version": "2.0.0",
+            "metadata": { "name": "Assets" },
+            "images": [{ "id": "logo", "path": file }],
+            "workflows": [],
+            "states": [{ "id": "home", "isInitial": true, "identifyingImages": ["logo"] }],
+            "transitions": [],
+            "categories": [],
+        });
+        let mut config = ConfigLoader::load_from_string(&json.to_string()).unwrap();
+        drop(dir);
+
+        // Hashed once, while loading
+        assert!(config.image_store.digest("logo").is_err());
+        let digest = config.image_store.validated_digest("logo").unwrap();
+        assert_eq!(digest.mime_type, "image/png");
+
+        config
+            .image_store
+            .set_source("logo", Some(ImageSource::Embedded(png(10, 10, 0))));
+        let changed = config.image_store.validated_digest("logo").unwrap();
+        assert_ne!(changed.sha256, digest.sha256);
+    }
+
+    #[test]
+    fn caches_decoded_images_and_thumbnails() {
+        let config = config();
+        let cache = AssetCache::default();
+
+        let decoded = cache.decoded(&config, "logo").unwrap();
+        assert!(Arc::ptr_eq(
+            &decoded,
+            &cache.decoded(&config, "logo").unwrap()
+        ));
+        assert_eq!((decoded.width, decoded.height), (300, 150));
+
+        let thumbnail = cache.thumbnail(&config, "logo", 100).unwrap();
+        let small = RgbImage::decode_png(&thumbnail).unwrap();
+        assert_eq!((small.width, small.height), (100, 50));
+        assert!(Arc::ptr_eq(
+            &thumbnail,
+            &cache.thumbnail(&config, "logo", 100).unwrap()
+        ));
+        // Already small enough
+        let button =
+            RgbImage::decode_png(&cache.thumbnail(&config, "button", 64).unwrap()).unwrap();
+        assert_eq!((button.width, button.height), (40, 20));
+
+        assert!(cache.thumbnail(&config, "logo", 4).is_err());
+        assert!(cache.decoded(&config, "empty").is_err());
+        assert!(cache.decoded(&config, "file").is_err());
+    }
+
+    #[test]
+    fn reports_are_kept_per_configuration() {
+        let cache = AssetCache::default();
+        let first = config();
+        assert_eq!(cache.report(&first).missing.len(), 3);
+
+        let mut edited = (*first).clone();
+        edited.images.retain(|image| image.id == "logo");
+        let edited = Arc::new(edited);
+        assert!(cache.report(&edited).is_clean());
+        assert_eq!(cache.report(&first).missing.len(), 3);
+    }
+}
+
+mod screenshot_executor {
+    use super::ScratchDir;
+    use crate::capture::RgbImage;
+    use crate::config::images::ImageStore;
+    use crate::config::QontinuiConfig;
+    use crate::executor::screenshot_executor::{screenshot_directory, Run, Screenshots};
+    use crate::matching::GrayImage;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde_json::{json, Value};
+    use std::sync::atomic::AtomicBool;
+
+    /// Texture that differs per seed, so templates only match where they
+    /// were cut from.
+    fn texture(width: u32, height: u32, seed: u32) -> RgbImage {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let v = ((x * x * 31 + y * 17 + x * y * 7 + seed * 101) % 251) as u8;
+                [v, v / 2, 255 - v]
+            })
+            .collect();
+        RgbImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn crop(image: &RgbImage, x: u32, y: u32, width: u32, height: u32) -> RgbImage {
+        let pixels = (y..y + height)
+            .flat_map(|row| (x..x + width).map(move |column| (column, row)))
+            .map(|(column, row)| image.pixels[(row * image.width + column) as usize])
+            .collect();
+        RgbImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn screenshot_dir() -> ScratchDir {
+        let dir = ScratchDir::new("screenshots");
+        for (name, seed) in [("a.png", 1), ("b.png", 2)] {
+            let png = texture(120, 80, seed).encode_png().unwrap();
+            std::fs::write(dir.join(name), png).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not a screenshot").unwrap();
+        dir
+    }
+
+    fn config(dir: &std::path::Path) -> QontinuiConfig {
+        let image = |image: RgbImage| STANDARD.encode(image.encode_png().unwrap());
+        let json = json!({
+            "version": "1.0.0",
+            "metadata": { "name": "Screenshots" },
+            "settings": {
+                "execution": {
+                    "executionMode": "screenshot",
+                    "screenshotDirectory": dir.to_string_lossy(),
+                },
+                "recognition": { "defaultThreshold": 0.95 },
+            },
+            "images": [
+                { "id": "button", "data": image(crop(&texture(120, 80, 2), 30, 20, 24, 16)) },
+                { "id": "gone", "data": image(crop(&texture(120, 80, 3), 10, 10, 24, 16)) },
+            ],
+            "workflows": [
+                {
+                    "id": "main",
+                    "actions": [
+                        { "id": "find", "type": "FIND", "config": { "target": { "imageId": "button" } } },
+                        { "id": "click", "type": "CLICK", "config": {} },
+                        { "id": "vanish", "type": "VANISH", "config": { "imageId": "gone" } },
+                        { "id": "nested", "type": "RUN_WORKFLOW", "config": { "workflowId": "inner" } },
+                        { "id": "after", "type": "TYPE", "config": {} },
+                    ],
+                },
+                {
+                    "id": "inner",
+                    "actions": [
+                        { "id": "missing", "type": "FIND", "config": { "imageId": "gone" } },
+                    ],
+                },
+            ],
+            "states": [],
+            "transitions": [],
+            "categories": [],
+        });
+        let mut config = QontinuiConfig::from_value(&json).unwrap();
+        config.image_store = ImageStore::extract(&mut config.images);
+        config
+    }
+
+    fn run(config: &QontinuiConfig, continue_on_failure: bool) -> (bool, Vec<(String, Value)>) {
+        let mut screenshots = Screenshots::open(&screenshot_directory(config).unwrap()).unwrap();
+        let stop = AtomicBool::new(false);
+        let mut events = Vec::new();
+        let mut emit = |event: &str, data: Value| events.push((event.to_string(), data));
+        let success = Run::new(config, &mut screenshots, &stop, &mut emit)
+            .continue_on_failure(continue_on_failure)
+            .execute("main");
+        (success, events)
+    }
+
+    #[test]
+    fn finds_templates_in_any_screenshot() {
+        let dir = screenshot_dir();
+        let mut screenshots = Screenshots::open(&dir).unwrap();
+        assert_eq!(screenshots.count(), 2);
+
+        let needle = GrayImage::from_rgb(&crop(&texture(120, 80, 2), 30, 20, 24, 16));
+        let sighting = screenshots.find(&needle, 0.95).unwrap();
+        assert_eq!(sighting.screenshot.as_deref(), Some("b.png"));
+        let found = sighting.found.unwrap();
+        assert_eq!((found.x, found.y), (30, 20));
+
+        let absent = GrayImage::from_rgb(&crop(&texture(120, 80, 3), 10, 10, 24, 16));
+        let sighting = screenshots.find(&absent, 0.95).unwrap();
+        assert_eq!(sighting.found, None);
+        assert!(sighting.best_score.unwrap() < 0.95);
+
+        assert!(Screenshots::open(&dir.join("missing")).is_err());
+    }
+
+    #[test]
+    fn runs_workflows_over_screenshots() {
+        let dir = screenshot_dir();
+        let config = config(&dir);
+        let (success, events) = run(&config, false);
+        assert!(!success);
+
+        let completed: Vec<(&str, bool)> = events
+            .iter()
+            .filter(|(event, _)| event == "action_completed")
+            .map(|(_, data)| {
+                (
+                    data["action_id"].as_str().unwrap(),
+                    data["success"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            completed,
+            [
+                ("find", true),
+                ("click", true),
+                ("vanish", true),
+                ("missing", false),
+                ("nested", false),
+            ]
+        );
+
+        let recognition = &events
+            .iter()
+            .find(|(event, _)| event == "image_recognition")
+            .unwrap()
+            .1;
+        assert_eq!(recognition["image_path"], "button");
+        assert_eq!(recognition["screenshot"], "b.png");
+        assert_eq!(recognition["location"], "(30, 20)");
+        assert_eq!(recognition["threshold"].as_f64().unwrap() as f32, 0.95);
+
+        let (first, _) = events.first().unwrap();
+        let (last, data) = events.last().unwrap();
+        assert_eq!(first, "execution_started");
+        assert_eq!(last, "execution_completed");
+        assert_eq!(data["success"], false);
+
+        // Carrying on after the failure still runs the last action
+        let (success, events) = run(&config, true);
+        assert!(!success);
+        assert!(events
+            .iter()
+            .any(|(event, data)| event == "action_completed" && data["action_id"] == "after"));
+    }
+}
+
+mod mock_executor {
+    use crate::executor::backend::ExecutorBackend;
+    use crate::executor::mock_bridge::{apply_delta, MockBridge};
+    use crate::executor::protocol::{BridgeMessage, Frame};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use serde_json::{json, Value};
+    use std::io::Write;
+    use std::sync::mpsc::{self, Receiver};
+    use std::time::Duration;
+
+    fn started() -> (MockBridge, Receiver<BridgeMessage>) {
+        let (sender, receiver) = mpsc::channel();
+        let mut mock = MockBridge::default().with_pace(Duration::ZERO);
+        mock.subscribe(Box::new(move |frame| {
+            if let Frame::Message(message) = frame {
+                let _ = sender.send(message);
+            }
+        }));
+        mock.start("test").unwrap();
+        (mock, receiver)
+    }
+
+    /// Messages up to and including the response to `id`, as event names
+    /// and `response` entries.
+    fn until_response(
+        receiver: &Receiver<BridgeMessage>,
+        id: &str,
+    ) -> (Vec<String>, BridgeMessage) {
+        let mut events = Vec::new();
+        loop {
+            match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+                BridgeMessage::Event(event) => events.push(event.event),
+                BridgeMessage::Response(response) if response.id == id => {
+                    return (events, BridgeMessage::Response(response));
+                }
+                BridgeMessage::Response(_) => {}
+            }
+        }
+    }
+
+    fn response(message: BridgeMessage) -> (bool, Option<Value>, Option<String>) {
+        match message {
+            BridgeMessage::Response(response) => (response.success, response.data, response.error),
+            BridgeMessage::Event(_) => unreachable!(),
+        }
+    }
+
+    fn config() -> String {
+        json!({
+            "version": "1.0.0",
+            "metadata": { "name": "Mock" },
+            "states": [{ "id": "home" }],
+            "workflows": [{
+                "id": "main",
+                "actions": [
+                    { "id": "a1", "type": "CLICK" },
+                    { "id": "a2", "type": "TYPE" },
+                ],
+            }],
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn loads_configurations_sent_inline_or_in_chunks() {
+        let (mut mock, receiver) = started();
+        let content = config();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        let payload = STANDARD.encode(encoder.finish().unwrap());
+        let (first, second) = payload.split_at(payload.len() / 2);
+        for (index, data) in [first, second].into_iter().enumerate() {
+            let params = json!({ "transfer_id": "t1", "index": index, "total": 2, "data": data });
+            mock.send(&format!("c{}", index), "load_chunk", Some(params))
+                .unwrap();
+        }
+        let params = json!({ "transfer_id": "t1", "encoding": "gzip+base64" });
+        mock.send("load", "load", Some(params)).unwrap();
+        let (events, message) = until_response(&receiver, "load");
+        assert!(events.contains(&"hello".to_string()));
+        assert!(events.contains(&"config_loaded".to_string()));
+        assert!(response(message).0);
+
+        let params = json!({ "config_data": content, "sha256": "0000" });
+        mock.send("bad", "load", Some(params)).unwrap();
+        let (success, _, error) = response(until_response(&receiver, "bad").1);
+        assert!(!success);
+        assert_eq!(error.as_deref(), Some("Configuration checksum mismatch"));
+
+        mock.send("status", "status", None).unwrap();
+        let (_, data, _) = response(until_response(&receiver, "status").1);
+        assert_eq!(data.unwrap()["config_loaded"], true);
+
+        mock.send("other", "record", None).unwrap();
+        let (success, _, error) = response(until_response(&receiver, "other").1);
+        assert!(!success);
+        assert_eq!(error.as_deref(), Some("Unknown command: record"));
+    }
+
+    #[test]
+    fn runs_the_actions_of_the_started_workflow() {
+        let (mut mock, receiver) = started();
+        let params = json!({ "config_data": config() });
+        mock.send("load", "load", Some(params)).unwrap();
+        until_response(&receiver, "load");
+
+        let params = json!({ "workflow_id": "main" });
+        mock.send("start", "start", Some(params)).unwrap();
+        let mut completed = Vec::new();
+        loop {
+            if let BridgeMessage::Event(event) =
+                receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+            {
+                match event.event.as_str() {
+                    "action_completed" => completed.push(event.data["action_id"].clone()),
+                    "execution_completed" => {
+                        assert_eq!(event.data["success"], true);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert_eq!(completed, [json!("a1"), json!("a2")]);
+    }
+
+    #[test]
+    fn applies_deltas_by_entry_id() {
+        let mut config = json!({
+            "states": [{ "id": "a", "name": "A" }, { "id": "b" }, { "id": "c" }],
+        });
+        let delta = json!({
+            "states": {
+                "added": [{ "id": "d" }],
+                "modified": [{ "id": "a", "name": "Renamed" }],
+                "removed": ["b"],
+            },
+        });
+        apply_delta(&mut config, &delta);
+        assert_eq!(
+            config["states"],
+            json!([{ "id": "a", "name": "Renamed" }, { "id": "c" }, { "id": "d" }])
+        );
+    }
+}
+
+mod remote_executor {
+    use crate::executor::backend::ExecutorBackend;
+    use crate::executor::protocol::{BridgeMessage, Frame};
+    use crate::executor::remote_bridge::{ConnectionState, RemoteBridge, RemoteTarget};
+    use crate::executor::transport;
+    use serde_json::{json, Value};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Receiver};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn target(address: &str, token: &str) -> RemoteTarget {
+        RemoteTarget {
+            address: address.to_string(),
+            token: token.to_string(),
+            tls: false,
+            ca_cert: None,
+        }
+    }
+
+    fn read(stream: &mut TcpStream) -> Option<Value> {
+        let (_, payload) = transport::read_frame(stream, 1024 * 1024).ok()??;
+        serde_json::from_slice(&payload).ok()
+    }
+
+    fn write(stream: &mut TcpStream, message: Value) {
+        transport::write_frame(stream, message.to_string().as_bytes()).unwrap();
+    }
+
+    /// Accepts a runner with the token "secret" and says hello.
+    fn accept(listener: &TcpListener) -> TcpStream {
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(read(&mut stream).unwrap()["token"], "secret");
+        let hello = json!({ "protocol_version": 1, "bridge": "qontinui_executor" });
+        write(
+            &mut stream,
+            json!({ "type": "event", "event": "hello", "timestamp": 0.0, "sequence": 0, "data": hello }),
+        );
+        stream
+    }
+
+    fn next_event(receiver: &Receiver<BridgeMessage>, name: &str) -> Value {
+        loop {
+            match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+                BridgeMessage::Event(event) if event.event == name => return event.data,
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn validates_the_target() {
+        let tls = |address: &str| RemoteTarget {
+            tls: true,
+            ..target(address, "secret")
+        };
+        assert!(tls("test-box:9876").validate().is_ok());
+        assert!(tls("test-box").validate().is_ok());
+        assert!(target("[::1]:9876", "secret").validate().is_ok());
+        assert!(target("127.0.0.1:9876", "secret").validate().is_ok());
+        assert!(target("localhost", "secret").validate().is_ok());
+        assert!(tls("test-box:port").validate().is_err());
+        assert!(target(":9876", "secret").validate().is_err());
+        assert!(target("test-box", "").validate().is_err());
+    }
+
+    #[test]
+    fn plain_tcp_is_only_for_this_machine() {
+        for address in ["test-box:9876", "192.168.1.20", "[2001:db8::1]:9876"] {
+            let refused = target(address, "secret").validate().unwrap_err();
+            assert!(refused.contains("needs TLS"), "{}", refused);
+        }
+    }
+
+    #[test]
+    fn refuses_to_start_when_the_token_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read(&mut stream);
+        });
+        let mut bridge = RemoteBridge::new(target(&address, "wrong"), Arc::default());
+        let error = bridge.start("remote").unwrap_err();
+        assert!(error.contains("check the token"), "{}", error);
+        assert!(!bridge.is_running());
+    }
+
+    #[test]
+    fn relays_messages_and_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (commands, received) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stream = accept(&listener);
+            let command = read(&mut stream).unwrap();
+            commands.send(command["command"].clone()).unwrap();
+            write(
+                &mut stream,
+                json!({ "type": "response", "id": command["id"], "success": true }),
+            );
+            // Drops the runner, then takes it back
+            drop(stream);
+            let _stream = accept(&listener);
+            thread::sleep(Duration::from_secs(10));
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        let mut bridge = RemoteBridge::new(target(&address, "secret"), Arc::default());
+        bridge.subscribe(Box::new(move |frame| {
+            if let Frame::Message(message) = frame {
+                let _ = sender.send(message);
+            }
+        }));
+        bridge.start("remote").unwrap();
+        assert_eq!(
+            next_event(&receiver, "hello")["bridge"],
+            "qontinui_executor"
+        );
+        assert_eq!(
+            bridge.handshake().hello().unwrap().bridge.as_deref(),
+            Some("qontinui_executor")
+        );
+
+        bridge.send("c1", "ping", None).unwrap();
+        assert_eq!(received.recv().unwrap(), "ping");
+        loop {
+            match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+                BridgeMessage::Response(response) => {
+                    assert_eq!(response.id, "c1");
+                    break;
+                }
+                BridgeMessage::Event(_) => {}
+            }
+        }
+
+        assert_eq!(
+            next_event(&receiver, "remote_connection")["state"],
+            "reconnecting"
+        );
+        assert_eq!(
+            next_event(&receiver, "remote_connection")["state"],
+            "connected"
+        );
+        next_event(&receiver, "hello");
+        assert_eq!(bridge.status().state, ConnectionState::Connected);
+        assert!(bridge.is_running());
+
+        bridge.stop().unwrap();
+        assert!(!bridge.is_running());
+        assert!(bridge.send("c2", "ping", None).is_err());
+    }
+}
+
+mod event_timeline {
+    use crate::executor::python_bridge::ExecutorEvent;
+    use crate::executor::run_stream::{Direction, StreamEntry};
+    use crate::executor::timeline::{self, EventTimeline, RUNS_KEPT};
+    use serde_json::json;
+
+    fn event(name: &str, sequence: u32) -> ExecutorEvent {
+        ExecutorEvent {
+            event: name.to_string(),
+            timestamp: 0.0,
+            sequence,
+            data: json!({}),
+        }
+    }
+
+    fn names(events: &[ExecutorEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.event.as_str()).collect()
+    }
+
+    #[test]
+    fn backfills_the_events_after_a_sequence() {
+        let timeline = EventTimeline::new(4);
+        timeline.record("run", &event("execution_started", 1));
+        timeline.record("run", &event("action_started", 2));
+        timeline.record("run", &event("remote_connection", 0));
+        timeline.record("run", &event("action_completed", 3));
+
+        let all = timeline.since("run", 0).unwrap();
+        assert_eq!(all.events.len(), 4);
+        let (events, bytes) = timeline.usage();
+        assert_eq!(events, 4);
+        assert!(bytes > 0);
+        assert!(!all.completed);
+        // The runner's event came after #2, so a client that saw #2 gets it
+        let missed = timeline.since("run", 2).unwrap();
+        assert_eq!(
+            names(&missed.events),
+            ["remote_connection", "action_completed"]
+        );
+        assert!(timeline.since("run", 3).unwrap().events.is_empty());
+
+        timeline.record("run", &event("execution_completed", 4));
+        let full = timeline.since("run", 0).unwrap();
+        assert_eq!(full.dropped, 1);
+        assert_eq!(timeline.usage().0, 4);
+        assert_eq!(full.events[0].event, "action_started");
+        assert!(full.completed);
+        assert!(timeline.since("other", 0).is_none());
+    }
+
+    #[test]
+    fn keeps_the_latest_runs() {
+        let timeline = EventTimeline::default();
+        for run in 0..=RUNS_KEPT {
+            timeline.record(&format!("run-{}", run), &event("execution_started", 1));
+        }
+        assert!(timeline.since("run-0", 0).is_none());
+        assert!(timeline.since(&format!("run-{}", RUNS_KEPT), 0).is_some());
+    }
+
+    #[test]
+    fn runs_of_different_sessions_interleave() {
+        let timeline = EventTimeline::default();
+        timeline.record("default-run", &event("execution_started", 1));
+        timeline.record("session-run", &event("execution_started", 1));
+        timeline.record("default-run", &event("action_started", 2));
+        timeline.record("session-run", &event("execution_completed", 2));
+
+        let default_run = timeline.since("default-run", 0).unwrap();
+        assert_eq!(
+            names(&default_run.events),
+            ["execution_started", "action_started"]
+        );
+        assert!(!default_run.completed);
+        assert!(timeline.since("session-run", 0).unwrap().completed);
+    }
+
+    #[test]
+    fn rebuilds_a_run_from_its_stream() {
+        let entry = |direction, name: &str, sequence| StreamEntry {
+            index: 0,
+            timestamp: "2026-01-02T03:04:05.500Z".to_string(),
+            elapsed_ms: 0.0,
+            direction,
+            name: name.to_string(),
+            correlation_id: None,
+            sequence,
+            success: None,
+            data: json!({}),
+        };
+        let entries = [
+            entry(Direction::Command, "start", None),
+            entry(Direction::Event, "execution_started", Some(1)),
+            entry(Direction::Event, "action_started", Some(2)),
+            entry(Direction::Event, "execution_completed", Some(3)),
+        ];
+        let rebuilt = timeline::from_stream("run", &entries, 1);
+        assert_eq!(
+            names(&rebuilt.events),
+            ["action_started", "execution_completed"]
+        );
+        assert_eq!(rebuilt.events[0].timestamp, 1767323045.5);
+        assert!(rebuilt.completed);
+    }
+}
+
+mod agent {
+    use crate::agent::{self, JobRun, Jobs, LaunchMode};
+    use crate::queue::{ExecutionQueue, QueueParams};
+    use std::path::Path;
+
+    fn args(args: &[&str]) -> LaunchMode {
+        LaunchMode::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reads_the_launch_mode_from_the_arguments() {
+        assert_eq!(args(&[]), LaunchMode::Window);
+        assert_eq!(args(&["--verbose", "--agent"]), LaunchMode::Agent);
+        assert_eq!(
+            args(&["--agent", "--install-agent"]),
+            LaunchMode::InstallAgent
+        );
+        assert_eq!(args(&["--uninstall-agent"]), LaunchMode::UninstallAgent);
+
+        let unit = agent::systemd_unit(Path::new("/opt/qontinui/runner"));
+        assert!(unit.contains("ExecStart=\"/opt/qontinui/runner\" --agent\n"));
+        assert!(unit.contains("WantedBy=graphical-session.target"));
+        let install: Vec<String> = [
+            "--install-agent",
+            "--tls-cert",
+            "cert.pem",
+            "--tls-key=key.pem",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        assert_eq!(
+            agent::option(&install, "--tls-cert").as_deref(),
+            Some("cert.pem")
+        );
+        assert_eq!(
+            agent::option(&install, "--tls-key").as_deref(),
+            Some("key.pem")
+        );
+        assert_eq!(agent::option(&install, "--port"), None);
+
+        let task = agent::scheduled_task_args(Path::new("C:\\Qontinui\\runner.exe"));
+        assert_eq!(task.last().unwrap(), "\"C:\\Qontinui\\runner.exe\" --agent");
+    }
+
+    #[test]
+    fn follows_its_runs_through_the_queue() {
+        let queue = ExecutionQueue::default();
+        let jobs = Jobs::default();
+        let runs = ["a", "b"]
+            .into_iter()
+            .map(|workflow| {
+                let (entry, _) = queue.enqueue(workflow, QueueParams::default());
+                JobRun {
+                    entry_id: entry.id,
+                    workflow_id: workflow.to_string(),
+                    status: "queued",
+                    error: None,
+                }
+            })
+            .collect();
+        let job = jobs.add("job".to_string(), None, runs);
+        assert_eq!(job.status, "queued");
+
+        let entry = queue.next().unwrap();
+        jobs.record(&queue.progress("started", Some(entry)));
+        assert_eq!(jobs.get("job").unwrap().status, "running");
+        jobs.record(&queue.finish(Ok(true)));
+        assert_eq!(jobs.get("job").unwrap().status, "running");
+
+        let entry = queue.next().unwrap();
+        jobs.record(&queue.progress("started", Some(entry)));
+        jobs.record(&queue.finish(Err("Python executor stopped".to_string())));
+        let job = jobs.get("job").unwrap();
+        assert_eq!(job.status, "failed");
+        assert_eq!(job.runs[0].status, "completed");
+        assert_eq!(
+            job.runs[1].error.as_deref(),
+            Some("Python executor stopped")
+        );
+        assert!(jobs.get("other").is_none());
+    }
+}
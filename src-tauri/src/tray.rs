@@ -0,0 +1,217 @@
+//! The system tray icon and desktop notifications, so the runner can be
+//! minimized while an automation runs. The tray menu shows the executor's
+//! status and starts and stops it; clicking the icon brings the window
+//! back. Notifications are shown for finished executions, executor crashes
+//! and available updates, unless the window is in front anyway.
+
+use crate::commands::{self, AppState};
+use crate::executor::sessions;
+use serde_json::Value;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Listener, Manager, Wry};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_updater::UpdaterExt;
+use tracing::{debug, info, warn};
+
+const TRAY_ID: &str = "main";
+
+/// Adds the tray icon and starts following the executor for it.
+pub fn create(app: &tauri::App) -> Result<(), String> {
+    build(app).map_err(|e| format!("Failed to create the tray icon: {}", e))
+}
+
+fn build(app: &tauri::App) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", "Executor stopped", false, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "start_executor", "Start executor", true, None::<&str>)?,
+            &MenuItem::with_id(app, "stop_execution", "Stop execution", true, None::<&str>)?,
+            &MenuItem::with_id(app, "stop_executor", "Stop executor", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "show", "Show window", true, None::<&str>)?,
+            &MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Qontinui Runner")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    let tray = tray.build(app)?;
+
+    follow_executor(app.handle(), status, tray);
+    Ok(())
+}
+
+fn handle_menu(app_handle: &AppHandle, event: MenuEvent) {
+    let command = event.id().as_ref().to_string();
+    if command == "show" {
+        return show_window(app_handle);
+    }
+    if command == "quit" {
+        return app_handle.exit(0);
+    }
+    // Starting and stopping wait on the executor, so not on the UI thread
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = match command.as_str() {
+            "start_executor" => {
+                commands::start_python_executor(handle.clone(), handle.state::<AppState>())
+            }
+            "stop_execution" => commands::stop_execution(handle.state::<AppState>()),
+            "stop_executor" => commands::stop_python_executor(handle.state::<AppState>()),
+            _ => return,
+        };
+        match result {
+            Ok(response) if !response.success => notify(
+                &handle,
+                "Qontinui Runner",
+                response.message.as_deref().unwrap_or(""),
+            ),
+            Ok(_) => {}
+            Err(e) => notify(&handle, "Qontinui Runner", &e),
+        }
+    });
+}
+
+fn show_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Keeps the status item and tooltip up to date with the default session,
+/// and notifies about finished executions and crashes of any session.
+fn follow_executor(app_handle: &AppHandle, status: MenuItem<Wry>, tray: TrayIcon) {
+    let set_status = move |text: &str| {
+        let _ = status.set_text(text);
+        let _ = tray.set_tooltip(Some(format!("Qontinui Runner: {}", text)));
+    };
+
+    let lifecycle_status = set_status.clone();
+    app_handle.listen("executor-lifecycle", move |event| {
+        let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else {
+            return;
+        };
+        if !sessions::is_default(&payload) {
+            return;
+        }
+        match payload.get("status").and_then(Value::as_str) {
+            Some("started") => lifecycle_status("Executor ready"),
+            Some("stopped") => lifecycle_status("Executor stopped"),
+            _ => {}
+        }
+    });
+
+    let handle = app_handle.clone();
+    let event_status = set_status.clone();
+    app_handle.listen("executor-event", move |event| {
+        let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else {
+            return;
+        };
+        let default = sessions::is_default(&payload);
+        let data = payload.get("data").cloned().unwrap_or(Value::Null);
+        match payload.get("event").and_then(Value::as_str) {
+            Some("execution_started") if default => event_status("Executing"),
+            Some("execution_completed") => {
+                if default {
+                    event_status("Executor ready");
+                }
+                let success = data.get("success").and_then(Value::as_bool) == Some(true);
+                let body = if success {
+                    "The automation completed".to_string()
+                } else {
+                    let error = ["error", "message"]
+                        .iter()
+                        .find_map(|key| data.get(*key).and_then(Value::as_str));
+                    match error {
+                        Some(error) => format!("The automation failed: {}", error),
+                        None => "The automation failed".to_string(),
+                    }
+                };
+                notify(&handle, "Execution finished", &body);
+            }
+            _ => {}
+        }
+    });
+
+    let handle = app_handle.clone();
+    app_handle.listen("executor-crashed", move |event| {
+        let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else {
+            return;
+        };
+        if sessions::is_default(&payload) {
+            set_status("Executor crashed");
+        }
+        let reason = payload
+            .get("reason")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown reason");
+        notify(&handle, "Executor crashed", reason);
+    });
+}
+
+/// Shows a desktop notification unless the window is in front.
+pub fn notify(app_handle: &AppHandle, title: &str, body: &str) {
+    let in_front = app_handle.get_webview_window("main").is_some_and(|window| {
+        window.is_visible().unwrap_or(false)
+            && window.is_focused().unwrap_or(false)
+            && !window.is_minimized().unwrap_or(true)
+    });
+    if in_front {
+        return;
+    }
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        warn!("Failed to show notification: {}", e);
+    }
+}
+
+/// Looks for an update in the background and tells the user if there's one.
+pub fn check_for_update(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let update = match handle.updater() {
+            Ok(updater) => updater.check().await,
+            Err(e) => Err(e),
+        };
+        match update {
+            Ok(Some(update)) => {
+                info!("Update {} available", update.version);
+                notify(
+                    &handle,
+                    "Update available",
+                    &format!("Qontinui Runner {} is available", update.version),
+                );
+            }
+            Ok(None) => debug!("No update available"),
+            // No update endpoint configured in development builds
+            Err(e) => debug!("Update check failed: {}", e),
+        }
+    });
+}
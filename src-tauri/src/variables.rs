@@ -0,0 +1,180 @@
+//! Key-value store that survives restarts, so runs can hand data to later
+//! runs. Variables are either global or scoped to a configuration (by its
+//! metadata name).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VariableScope {
+    Global,
+    Config,
+}
+
+impl VariableScope {
+    /// `None` means global.
+    pub fn parse(scope: Option<&str>) -> Result<Self, String> {
+        match scope.unwrap_or("global") {
+            "global" => Ok(Self::Global),
+            "config" => Ok(Self::Config),
+            other => Err(format!(
+                "Unknown variable scope '{}', expected 'global' or 'config'",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VariableFile {
+    #[serde(default)]
+    global: Map<String, Value>,
+    #[serde(default)]
+    configs: BTreeMap<String, Map<String, Value>>,
+}
+
+#[derive(Clone)]
+pub struct VariableStore {
+    path: Arc<Mutex<PathBuf>>,
+    data: Arc<Mutex<VariableFile>>,
+}
+
+impl VariableStore {
+    /// Opens the store at `path`. A missing file is an empty store; an
+    /// unreadable one is set aside so it isn't overwritten.
+    pub fn open(path: PathBuf) -> Self {
+        let data = read(&path);
+        Self {
+            path: Arc::new(Mutex::new(path)),
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+
+    /// Switches to the store at `path`, as when changing profiles.
+    pub fn reopen(&self, path: PathBuf) {
+        let mut data = self.data.lock().unwrap();
+        *data = read(&path);
+        *self.path.lock().unwrap() = path;
+    }
+
+    /// `config` is the configuration name, required for config scope.
+    pub fn get(
+        &self,
+        scope: VariableScope,
+        config: Option<&str>,
+        key: &str,
+    ) -> Result<Option<Value>, String> {
+        Ok(self.list(scope, config)?.remove(key))
+    }
+
+    pub fn list(
+        &self,
+        scope: VariableScope,
+        config: Option<&str>,
+    ) -> Result<Map<String, Value>, String> {
+        let data = self.data.lock().unwrap();
+        Ok(match scope {
+            VariableScope::Global => data.global.clone(),
+            VariableScope::Config => data
+                .configs
+                .get(config_name(config)?)
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Sets `key`, or removes it when `value` is `None`, and saves.
+    pub fn set(
+        &self,
+        scope: VariableScope,
+        config: Option<&str>,
+        key: &str,
+        value: Option<Value>,
+    ) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        let variables = match scope {
+            VariableScope::Global => &mut data.global,
+            VariableScope::Config => data
+                .configs
+                .entry(config_name(config)?.to_string())
+                .or_default(),
+        };
+        match value {
+            Some(value) => {
+                variables.insert(key.to_string(), value);
+            }
+            None => {
+                variables.remove(key);
+            }
+        }
+        data.configs.retain(|_, variables| !variables.is_empty());
+        save(&self.path.lock().unwrap(), &data)
+    }
+
+    /// Both scopes as sent to the executor with `start`.
+    pub fn for_execution(&self, config: Option<&str>) -> Value {
+        let data = self.data.lock().unwrap();
+        serde_json::json!({
+            "global": data.global,
+            "config": config
+                .and_then(|name| data.configs.get(name))
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+fn read(path: &Path) -> VariableFile {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring corrupt variable store {:?}: {}", path, e);
+            let _ = std::fs::rename(path, path.with_extension("json.corrupt"));
+            VariableFile::default()
+        }),
+        Err(_) => VariableFile::default(),
+    }
+}
+
+fn config_name(config: Option<&str>) -> Result<&str, String> {
+    config.ok_or_else(|| "No configuration loaded for config-scoped variables".to_string())
+}
+
+/// Writes through a temporary file so a crash can't leave half a store.
+fn save(path: &Path, data: &VariableFile) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let json = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json)
+        .and_then(|_| std::fs::rename(&temp, path))
+        .map_err(|e| format!("Failed to save variables to {:?}: {}", path, e))
+}
+
+/// Persists a `variable_set` event from the executor: `{"key", "value",
+/// "scope"}`, where a null value deletes the key.
+pub fn apply_event(app_handle: &tauri::AppHandle, data: &Value) {
+    use tauri::Manager;
+
+    let Some(key) = data.get("key").and_then(Value::as_str) else {
+        tracing::warn!("Ignoring variable_set event without a key: {}", data);
+        return;
+    };
+    let state = app_handle.state::<crate::commands::AppState>();
+    let config = state
+        .current_config
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|c| c.metadata.name.clone());
+    let value = data.get("value").filter(|v| !v.is_null()).cloned();
+
+    let result = VariableScope::parse(data.get("scope").and_then(Value::as_str))
+        .and_then(|scope| state.variables.set(scope, config.as_deref(), key, value));
+    if let Err(e) = result {
+        tracing::warn!("Failed to store variable '{}' from executor: {}", key, e);
+    }
+}
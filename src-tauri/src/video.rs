@@ -0,0 +1,273 @@
+//! Screen video recording by the runner itself, next to the Python-side
+//! recorder, using `ffmpeg` with the grabber each OS provides: `gdigrab` on
+//! Windows, `avfoundation` on macOS and `x11grab` on Linux (Wayland isn't
+//! supported). A video is named after the run being recorded when one is
+//! going and kept in the profile's `recordings/videos`; the recordings
+//! library lists them. Scheduled runs link the videos recorded while they
+//! ran in the execution history.
+
+use crate::monitors::MonitorInfo;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Time ffmpeg gets to finish the file after it's told to quit.
+const FINISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long after starting ffmpeg counts as having failed to start.
+const STARTUP_CHECK: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoFormat {
+    #[default]
+    Mp4,
+    Webm,
+}
+
+impl VideoFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Mp4 => &[
+                "-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p",
+            ],
+            Self::Webm => &[
+                "-c:v",
+                "libvpx",
+                "-deadline",
+                "realtime",
+                "-cpu-used",
+                "8",
+                "-b:v",
+                "2M",
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSettings {
+    /// Monitor to record; the one executions run on when unset.
+    pub monitor_index: Option<usize>,
+    pub fps: u32,
+    pub format: VideoFormat,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            monitor_index: None,
+            fps: 15,
+            format: VideoFormat::Mp4,
+        }
+    }
+}
+
+impl VideoSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=60).contains(&self.fps) {
+            return Err("Video frame rate must be 1 to 60 frames per second".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The video being recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoRecording {
+    pub path: PathBuf,
+    /// The run being recorded when the video started.
+    pub run_id: Option<String>,
+    pub monitor_index: usize,
+    pub format: VideoFormat,
+    pub fps: u32,
+    pub started_at: String,
+}
+
+struct ActiveVideo {
+    recording: VideoRecording,
+    process: Child,
+}
+
+/// The video being recorded, shared through `AppState`.
+pub struct VideoRecorder {
+    dir: Mutex<PathBuf>,
+    active: Mutex<Option<ActiveVideo>>,
+}
+
+impl VideoRecorder {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir: Mutex::new(dir),
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Where the following videos are kept, e.g. after a profile switch.
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.lock().unwrap() = dir;
+    }
+
+    /// The video being recorded, if any.
+    pub fn current(&self) -> Option<VideoRecording> {
+        self.active
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|active| active.recording.clone())
+    }
+
+    /// Starts recording `monitor`. The file is named after `run_id` when
+    /// given.
+    pub fn start(
+        &self,
+        monitor: &MonitorInfo,
+        settings: &VideoSettings,
+        run_id: Option<String>,
+    ) -> Result<VideoRecording, String> {
+        settings.validate()?;
+        let mut active = self.active.lock().unwrap();
+        if active.is_some() {
+            return Err("A video is already being recorded".to_string());
+        }
+
+        let dir = self.dir.lock().unwrap().clone();
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let now = chrono::Local::now();
+        let name = run_id
+            .clone()
+            .unwrap_or_else(|| format!("video-{}", now.format("%Y%m%d-%H%M%S")));
+        let path = dir.join(format!("{}.{}", name, settings.format.extension()));
+
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-loglevel", "error", "-nostats"])
+            .args(grab_args(monitor, settings.fps)?)
+            .args(settings.format.codec_args())
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        let mut process = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to start ffmpeg (is it installed?): {}", e))?;
+
+        // A wrong grabber or codec makes ffmpeg give up at once
+        std::thread::sleep(STARTUP_CHECK);
+        if let Ok(Some(status)) = process.try_wait() {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = process.stderr.take() {
+                let _ = std::io::Read::read_to_string(&mut pipe, &mut stderr);
+            }
+            return Err(format!(
+                "ffmpeg failed to record ({}): {}",
+                status,
+                stderr.trim()
+            ));
+        }
+
+        let recording = VideoRecording {
+            path,
+            run_id,
+            monitor_index: monitor.index,
+            format: settings.format,
+            fps: settings.fps,
+            started_at: now.to_rfc3339(),
+        };
+        info!(
+            "Recording video of monitor {} to {:?}",
+            monitor.index, recording.path
+        );
+        *active = Some(ActiveVideo {
+            recording: recording.clone(),
+            process,
+        });
+        Ok(recording)
+    }
+
+    /// Stops the recording and lets ffmpeg finish the file.
+    pub fn stop(&self) -> Result<VideoRecording, String> {
+        let Some(ActiveVideo {
+            recording,
+            mut process,
+        }) = self.active.lock().unwrap().take()
+        else {
+            return Err("No video is being recorded".to_string());
+        };
+
+        // `q` makes ffmpeg write the trailer; a killed ffmpeg leaves an
+        // unplayable MP4
+        if let Some(mut stdin) = process.stdin.take() {
+            let _ = stdin.write_all(b"q\n");
+        }
+        if !crate::executor::shutdown::wait_for_exit(&mut process, FINISH_TIMEOUT) {
+            warn!("ffmpeg didn't finish the video in time, killing it");
+            let _ = process.kill();
+        }
+        let _ = process.wait();
+
+        info!("Video saved to {:?}", recording.path);
+        Ok(recording)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn grab_args(monitor: &MonitorInfo, fps: u32) -> Result<Vec<String>, String> {
+    Ok(vec![
+        "-f".to_string(),
+        "gdigrab".to_string(),
+        "-framerate".to_string(),
+        fps.to_string(),
+        "-offset_x".to_string(),
+        monitor.x.to_string(),
+        "-offset_y".to_string(),
+        monitor.y.to_string(),
+        "-video_size".to_string(),
+        format!("{}x{}", monitor.width, monitor.height),
+        "-i".to_string(),
+        "desktop".to_string(),
+    ])
+}
+
+#[cfg(target_os = "macos")]
+fn grab_args(monitor: &MonitorInfo, fps: u32) -> Result<Vec<String>, String> {
+    Ok(vec![
+        "-f".to_string(),
+        "avfoundation".to_string(),
+        "-framerate".to_string(),
+        fps.to_string(),
+        "-capture_cursor".to_string(),
+        "1".to_string(),
+        "-i".to_string(),
+        format!("Capture screen {}:none", monitor.index),
+    ])
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn grab_args(monitor: &MonitorInfo, fps: u32) -> Result<Vec<String>, String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return Err("Video recording isn't supported on Wayland".to_string());
+    }
+    let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+    Ok(vec![
+        "-f".to_string(),
+        "x11grab".to_string(),
+        "-framerate".to_string(),
+        fps.to_string(),
+        "-video_size".to_string(),
+        format!("{}x{}", monitor.width, monitor.height),
+        "-i".to_string(),
+        format!("{}+{},{}", display, monitor.x, monitor.y),
+    ])
+}
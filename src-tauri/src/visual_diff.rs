@@ -0,0 +1,197 @@
+//! Pixel comparison of two screenshots, producing a difference score and a
+//! heatmap of what changed.
+
+use crate::capture::{Region, RgbImage};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffSummary {
+    pub width: u32,
+    pub height: u32,
+    pub changed_pixels: u64,
+    /// Share of pixels that changed by more than the threshold, 0 to 1.
+    pub score: f64,
+    /// Mean per-pixel difference over the whole image, 0 to 1.
+    pub mean_difference: f64,
+    /// Smallest rectangle containing every changed pixel.
+    pub changed_bounds: Option<Region>,
+}
+
+/// Compares two screenshots of the same size. A pixel counts as changed when
+/// any channel differs by more than `threshold`. The heatmap shows the second
+/// image dimmed, with changes from yellow (slight) to red (strong).
+pub fn compare(
+    a: &RgbImage,
+    b: &RgbImage,
+    threshold: u8,
+) -> Result<(DiffSummary, RgbImage), String> {
+    if (a.width, a.height) != (b.width, b.height) {
+        return Err(format!(
+            "Screenshots differ in size: {}x{} vs {}x{}",
+            a.width, a.height, b.width, b.height
+        ));
+    }
+
+    let mut changed_pixels = 0u64;
+    let mut total_difference = 0u64;
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    let mut heatmap = Vec::with_capacity(b.pixels.len());
+
+    for (i, (pa, pb)) in a.pixels.iter().zip(&b.pixels).enumerate() {
+        let difference = pa
+            .iter()
+            .zip(pb)
+            .map(|(ca, cb)| ca.abs_diff(*cb))
+            .max()
+            .unwrap_or(0);
+        total_difference += difference as u64;
+
+        if difference > threshold {
+            changed_pixels += 1;
+            let (x, y) = (i as u32 % a.width, i as u32 / a.width);
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+            heatmap.push([255, 255 - difference, 0]);
+        } else {
+            let luma = (pb[0] as u32 * 299 + pb[1] as u32 * 587 + pb[2] as u32 * 114) / 1000;
+            heatmap.push([(luma / 3) as u8; 3]);
+        }
+    }
+
+    let total = a.pixels.len().max(1) as f64;
+    let summary = DiffSummary {
+        width: a.width,
+        height: a.height,
+        changed_pixels,
+        score: changed_pixels as f64 / total,
+        mean_difference: total_difference as f64 / (total * 255.0),
+        changed_bounds: (changed_pixels > 0).then(|| Region {
+            x: left as i32,
+            y: top as i32,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        }),
+    };
+    let heatmap = RgbImage {
+        width: a.width,
+        height: a.height,
+        pixels: heatmap,
+    };
+    Ok((summary, heatmap))
+}
+
+/// Compares two PNG files and writes the heatmap to `heatmap_path`.
+pub fn compare_files(
+    path_a: &Path,
+    path_b: &Path,
+    threshold: u8,
+    heatmap_path: &Path,
+) -> Result<DiffSummary, String> {
+    let (summary, heatmap) = compare(
+        &RgbImage::load(path_a)?,
+        &RgbImage::load(path_b)?,
+        threshold,
+    )?;
+    std::fs::write(heatmap_path, heatmap.encode_png()?)
+        .map_err(|e| format!("Failed to write heatmap {:?}: {}", heatmap_path, e))?;
+    Ok(summary)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenshotComparison {
+    /// Path relative to both run directories.
+    pub path: String,
+    pub heatmap: Option<PathBuf>,
+    pub summary: Option<DiffSummary>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunComparison {
+    /// Most changed first.
+    pub screenshots: Vec<ScreenshotComparison>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+}
+
+/// PNG files under `dir`, as sorted paths relative to it.
+pub fn list_screenshots(dir: &Path) -> Result<Vec<String>, String> {
+    fn walk(root: &Path, dir: &Path, found: &mut Vec<String>) -> Result<(), String> {
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| format!("Failed to read {:?}: {}", dir, e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, found)?;
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+            {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    found.push(relative.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let mut found = Vec::new();
+    walk(dir, dir, &mut found)?;
+    found.sort();
+    Ok(found)
+}
+
+/// Pairs screenshots with the same relative path in two run directories and
+/// compares each pair, writing heatmaps into `output_dir`. `on_progress`
+/// returns false to stop early.
+pub fn compare_runs<F>(
+    run_a: &Path,
+    run_b: &Path,
+    threshold: u8,
+    output_dir: &Path,
+    mut on_progress: F,
+) -> Result<RunComparison, String>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let in_a = list_screenshots(run_a)?;
+    let in_b = list_screenshots(run_b)?;
+    let shared: Vec<&String> = in_a.iter().filter(|p| in_b.contains(p)).collect();
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create {:?}: {}", output_dir, e))?;
+
+    let mut screenshots = Vec::new();
+    for (done, path) in shared.iter().enumerate() {
+        if !on_progress(done, shared.len()) {
+            break;
+        }
+        let heatmap = output_dir.join(format!("{}.diff.png", path.replace('/', "__")));
+        let result = compare_files(&run_a.join(path), &run_b.join(path), threshold, &heatmap);
+        screenshots.push(match result {
+            Ok(summary) => ScreenshotComparison {
+                path: path.to_string(),
+                heatmap: Some(heatmap),
+                summary: Some(summary),
+                error: None,
+            },
+            Err(e) => ScreenshotComparison {
+                path: path.to_string(),
+                heatmap: None,
+                summary: None,
+                error: Some(e),
+            },
+        });
+    }
+
+    let score = |c: &ScreenshotComparison| c.summary.as_ref().map_or(f64::MAX, |s| s.score);
+    screenshots.sort_by(|x, y| score(y).total_cmp(&score(x)));
+
+    Ok(RunComparison {
+        screenshots,
+        only_in_a: in_a.iter().filter(|p| !in_b.contains(p)).cloned().collect(),
+        only_in_b: in_b.iter().filter(|p| !in_a.contains(p)).cloned().collect(),
+    })
+}
@@ -0,0 +1,197 @@
+//! Reloads the configuration when its file changes on disk, so edits made
+//! in qontinui-web take effect without loading the file again by hand. A
+//! changed file is validated, replaces the loaded config, is sent to a
+//! running executor and is announced with a `config-reloaded` event.
+
+use crate::assets;
+use crate::commands::{self, AppState};
+use crate::config::ConfigLoader;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Editors save in several writes; a change is picked up once the file has
+/// been quiet this long.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Watches one configuration file at a time.
+#[derive(Default)]
+pub struct ConfigWatcher {
+    active: Mutex<Option<Watch>>,
+}
+
+struct Watch {
+    path: PathBuf,
+    /// Dropping the watcher ends its reload thread.
+    _watcher: RecommendedWatcher,
+}
+
+/// Payload of the `config-reloaded` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadEvent {
+    pub path: String,
+    pub success: bool,
+    /// Summary of the reloaded configuration.
+    pub summary: Option<String>,
+    /// Why the change wasn't picked up, or couldn't be sent to the executor.
+    pub error: Option<String>,
+    pub sent_to_executor: bool,
+}
+
+impl ConfigWatcher {
+    pub fn path(&self) -> Option<PathBuf> {
+        self.active
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|watch| watch.path.clone())
+    }
+
+    /// Starts watching `path`, replacing any earlier watch.
+    pub fn watch(&self, app_handle: AppHandle, path: PathBuf) -> Result<(), String> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| format!("Not a configuration file: {:?}", path))?
+            .to_os_string();
+        if !path.is_file() {
+            return Err(format!("Configuration file not found: {:?}", path));
+        }
+
+        // Editors often save by replacing the file, which ends a watch on
+        // the file itself, so its directory is watched instead
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let (changes, changed) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if event.kind.is_access() => {}
+                Ok(event) => {
+                    if event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == Some(&file_name))
+                    {
+                        let _ = changes.send(());
+                    }
+                }
+                Err(e) => warn!("Configuration watcher error: {}", e),
+            })
+            .map_err(|e| format!("Failed to start watching {:?}: {}", path, e))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {:?}: {}", dir, e))?;
+
+        let digest = file_digest(&path);
+        let watched = path.clone();
+        thread::spawn(move || reload_on_change(app_handle, watched, changed, digest));
+
+        *self.active.lock().unwrap() = Some(Watch {
+            path: path.clone(),
+            _watcher: watcher,
+        });
+        info!("Watching configuration {:?} for changes", path);
+        Ok(())
+    }
+
+    /// Stops watching `path`. Returns false if it wasn't being watched.
+    pub fn unwatch(&self, path: &Path) -> bool {
+        let mut active = self.active.lock().unwrap();
+        if active.as_ref().is_some_and(|watch| watch.path == path) {
+            *active = None;
+            info!("Stopped watching configuration {:?}", path);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn reload_on_change(
+    app_handle: AppHandle,
+    path: PathBuf,
+    changed: mpsc::Receiver<()>,
+    mut digest: Option<Vec<u8>>,
+) {
+    while changed.recv().is_ok() {
+        while changed.recv_timeout(SETTLE_TIME).is_ok() {}
+
+        // Saves that leave the content as it was, and a file that's gone
+        // between the delete and rename of a save, aren't reloads
+        let current = file_digest(&path);
+        if current.is_none() || current == digest {
+            continue;
+        }
+        digest = current;
+
+        let event = reload(&app_handle, &path);
+        let _ = app_handle.emit("config-reloaded", &event);
+    }
+}
+
+fn reload(app_handle: &AppHandle, path: &Path) -> ReloadEvent {
+    let state = app_handle.state::<AppState>();
+    let mut event = ReloadEvent {
+        path: path.display().to_string(),
+        success: false,
+        summary: None,
+        error: None,
+        sent_to_executor: false,
+    };
+
+    if state.config_edits.lock().unwrap().dirty {
+        warn!(
+            "Not reloading {:?}: the loaded configuration has unsaved edits",
+            path
+        );
+        event.error = Some(
+            "The configuration has unsaved edits in the runner; save or undo them to pick up changes to the file"
+                .to_string(),
+        );
+        return event;
+    }
+    let config = match ConfigLoader::load_from_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Not reloading {:?}: {}", path, e);
+            event.error = Some(e);
+            return event;
+        }
+    };
+
+    let source_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let config = commands::install_configuration(&state, &event.path, config, source_bytes);
+    assets::check_loaded(app_handle, config.clone());
+    event.summary = Some(config.summary());
+    info!("Reloaded configuration: {}", config.summary());
+
+    if let Some(ref mut bridge) = *state.executor.lock().unwrap() {
+        if bridge.is_running() {
+            match bridge.sync_configuration(&config, false) {
+                Ok(_) => event.sent_to_executor = true,
+                Err(e) => {
+                    warn!("Failed to send the reloaded configuration to Python: {}", e);
+                    event.error = Some(format!(
+                        "Reloaded, but failed to send the configuration to Python: {}",
+                        e
+                    ));
+                }
+            }
+        }
+    }
+    event.success = event.error.is_none();
+    event
+}
+
+fn file_digest(path: &Path) -> Option<Vec<u8>> {
+    std::fs::read(path)
+        .ok()
+        .map(|content| Sha256::digest(&content).to_vec())
+}
@@ -389,8 +389,8 @@ function App() {
         multiple: false,
         filters: [
           {
-            name: "JSON",
-            extensions: ["json"],
+            name: "Configuration",
+            extensions: ["json", "yaml", "yml", "toml", "qontinui", "zip"],
           },
         ],
       });
@@ -409,14 +409,23 @@ function App() {
 
         const result: any = await invoke("load_configuration", { path: selected });
         if (result.success) {
+          const fetchSection = async (name: string) => {
+            const section: any = await invoke("get_config_section", { name });
+            return section.data || [];
+          };
+          const [workflowsSection, statesSection, imagesSection] = await Promise.all([
+            fetchSection("workflows"),
+            fetchSection("states"),
+            fetchSection("images"),
+          ]);
           const loadedConfig = {
             name: selected.split("/").pop() || "config.json",
-            version: "1.0.0",
-            statesCount: result.data?.states?.length || 0,
-            workflowsCount: result.data?.workflows?.length || 0,
-            workflows: result.data?.workflows || [],
-            images: result.data?.images || [],
-            states: result.data?.states || [],
+            version: result.data?.version || "1.0.0",
+            statesCount: result.data?.counts?.states || 0,
+            workflowsCount: result.data?.counts?.workflows || 0,
+            workflows: workflowsSection,
+            images: imagesSection,
+            states: statesSection,
             path: selected,
           };
           console.log("Config loaded with images:", loadedConfig.images?.length || 0, "images");
@@ -435,7 +444,7 @@ function App() {
           }
           setConfig(loadedConfig);
           // Filter workflows to only show those in the "main" category
-          const allWorkflows = result.data?.workflows || [];
+          const allWorkflows = workflowsSection;
 
           // Debug: Log all workflows with their categories
           console.log("All workflows loaded:", allWorkflows.length);
@@ -739,7 +748,7 @@ Attempts: ${entry.attempts}`;
 
   const filteredLogs = logLevel === "all" ? logs : logs.filter((log) => log.level === logLevel);
 
-  // Check for updates on mount
+  // Check for updates shortly after mount so it doesn't compete with startup
   useEffect(() => {
     const checkUpdates = async () => {
       try {
@@ -752,7 +761,8 @@ Attempts: ${entry.attempts}`;
         console.error("Failed to check for updates:", error);
       }
     };
-    checkUpdates();
+    const timer = setTimeout(checkUpdates, 5000);
+    return () => clearTimeout(timer);
   }, []);
 
   return (